@@ -0,0 +1,392 @@
+//! Conditions som sitter på en varelse, motsvarar datat i `Condition` och
+//! dess underklasser i TFS. Hur de startar, tickar och slutar finns i
+//! `rules::condition`; här finns typ, id, hur länge den varar och det
+//! varje sorts condition behöver minnas.
+
+use std::collections::VecDeque;
+
+use crate::creature::{LightInfo, Outfit};
+use crate::player::{Skill, Stat};
+
+/// Motsvarar `ConditionType_t`, en bit per typ så att flera kan testas på en gång
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct ConditionType(pub u32);
+
+impl ConditionType {
+    pub const NONE: Self = Self(0);
+    pub const POISON: Self = Self(1 << 0);
+    pub const FIRE: Self = Self(1 << 1);
+    pub const ENERGY: Self = Self(1 << 2);
+    pub const BLEEDING: Self = Self(1 << 3);
+    pub const HASTE: Self = Self(1 << 4);
+    pub const PARALYZE: Self = Self(1 << 5);
+    pub const OUTFIT: Self = Self(1 << 6);
+    pub const INVISIBLE: Self = Self(1 << 7);
+    pub const LIGHT: Self = Self(1 << 8);
+    pub const MANASHIELD: Self = Self(1 << 9);
+    pub const INFIGHT: Self = Self(1 << 10);
+    pub const DRUNK: Self = Self(1 << 11);
+    pub const EXHAUST_WEAPON: Self = Self(1 << 12);
+    pub const REGENERATION: Self = Self(1 << 13);
+    pub const SOUL: Self = Self(1 << 14);
+    pub const DROWN: Self = Self(1 << 15);
+    pub const MUTED: Self = Self(1 << 16);
+    pub const CHANNELMUTEDTICKS: Self = Self(1 << 17);
+    pub const YELLTICKS: Self = Self(1 << 18);
+    pub const ATTRIBUTES: Self = Self(1 << 19);
+    pub const FREEZING: Self = Self(1 << 20);
+    pub const DAZZLED: Self = Self(1 << 21);
+    pub const CURSED: Self = Self(1 << 22);
+    pub const EXHAUST_COMBAT: Self = Self(1 << 23);
+    pub const EXHAUST_HEAL: Self = Self(1 << 24);
+    pub const PACIFIED: Self = Self(1 << 25);
+    pub const SPELLCOOLDOWN: Self = Self(1 << 26);
+    pub const SPELLGROUPCOOLDOWN: Self = Self(1 << 27);
+
+    /// Conditions som skadar eller hindrar, se `Condition::is_aggressive`
+    pub const AGGRESSIVE: Self = Self(
+        Self::POISON.0
+            | Self::FIRE.0
+            | Self::ENERGY.0
+            | Self::BLEEDING.0
+            | Self::PARALYZE.0
+            | Self::DRUNK.0
+            | Self::DROWN.0
+            | Self::FREEZING.0
+            | Self::DAZZLED.0
+            | Self::CURSED.0,
+    );
+
+    pub fn intersects(self, other: Self) -> bool {
+        self.0 & other.0 != 0
+    }
+}
+
+impl std::ops::BitOr for ConditionType {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+/// Ikonerna klienten visar, motsvarar `icons_t`
+pub mod icons {
+    pub const POISON: u16 = 1 << 0;
+    pub const BURN: u16 = 1 << 1;
+    pub const ENERGY: u16 = 1 << 2;
+    pub const DRUNK: u16 = 1 << 3;
+    pub const MANASHIELD: u16 = 1 << 4;
+    pub const PARALYZE: u16 = 1 << 5;
+    pub const HASTE: u16 = 1 << 6;
+    pub const SWORDS: u16 = 1 << 7;
+    pub const DROWNING: u16 = 1 << 8;
+    pub const FREEZING: u16 = 1 << 9;
+    pub const DAZZLED: u16 = 1 << 10;
+    pub const CURSED: u16 = 1 << 11;
+    pub const PARTY_BUFF: u16 = 1 << 12;
+    pub const REDSWORDS: u16 = 1 << 13;
+    pub const PIGEON: u16 = 1 << 14;
+    pub const BLEEDING: u16 = 1 << 15;
+}
+
+/// Vad conditionen kommer ifrån, motsvarar `ConditionId_t`. Utrustning
+/// använder sin plats så att den kan tas bort när itemet tas av.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ConditionId {
+    #[default]
+    Default,
+    Combat,
+    Head,
+    Necklace,
+    Backpack,
+    Armor,
+    Right,
+    Left,
+    Legs,
+    Feet,
+    Ring,
+    Ammo,
+}
+
+impl ConditionId {
+    /// Värdet i TFS, där `Default` är -1
+    pub fn as_i8(self) -> i8 {
+        match self {
+            ConditionId::Default => -1,
+            other => other as i8 - 1,
+        }
+    }
+
+    pub fn from_i8(value: i8) -> Option<Self> {
+        use ConditionId::*;
+        Some(match value {
+            -1 => Default,
+            0 => Combat,
+            1 => Head,
+            2 => Necklace,
+            3 => Backpack,
+            4 => Armor,
+            5 => Right,
+            6 => Left,
+            7 => Legs,
+            8 => Feet,
+            9 => Ring,
+            10 => Ammo,
+            _ => return None,
+        })
+    }
+}
+
+/// Ett slag i en skadesekvens, motsvarar `IntervalInfo`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IntervalInfo {
+    pub time_left: i32,
+    pub value: i32,
+    pub interval: i32,
+}
+
+/// Gift, eld, energi och de andra som skadar, motsvarar `ConditionDamage`
+#[derive(Debug, Clone, PartialEq)]
+pub struct DamageCondition {
+    /// Varelsen som gav conditionen, 0 om ingen
+    pub owner: u32,
+    /// Första slaget kommer efter ett intervall i stället för direkt
+    pub delayed: bool,
+    /// Ersätter en pågående även om den gör mindre skada
+    pub force_update: bool,
+    /// Från ett fält; spelare tar halva skadan av andra spelares fält
+    pub field: bool,
+    pub min_damage: i32,
+    pub max_damage: i32,
+    pub start_damage: i32,
+    pub tick_interval: i32,
+    /// Skada varje `tick_interval` så länge conditionen finns, i stället för en sekvens
+    pub period_damage: i32,
+    pub period_damage_tick: i32,
+    pub damage_list: VecDeque<IntervalInfo>,
+}
+
+impl Default for DamageCondition {
+    fn default() -> Self {
+        Self {
+            owner: 0,
+            delayed: false,
+            force_update: false,
+            field: false,
+            min_damage: 0,
+            max_damage: 0,
+            start_damage: 0,
+            tick_interval: 2000,
+            period_damage: 0,
+            period_damage_tick: 0,
+            damage_list: VecDeque::new(),
+        }
+    }
+}
+
+/// Haste och paralyze, motsvarar `ConditionSpeed`. Med `speed_delta` 0
+/// slumpas ändringen fram ur formeln och grundhastigheten när den startar.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct SpeedCondition {
+    pub speed_delta: i32,
+    pub mina: f32,
+    pub minb: f32,
+    pub maxa: f32,
+    pub maxb: f32,
+}
+
+/// Motsvarar `ConditionLight`; ljuset blir en nivå svagare varje intervall
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LightCondition {
+    pub light: LightInfo,
+    pub internal_light_ticks: u32,
+    pub light_change_interval: u32,
+}
+
+/// Motsvarar `ConditionRegeneration`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegenerationCondition {
+    pub health_ticks: u32,
+    pub health_gain: u32,
+    pub mana_ticks: u32,
+    pub mana_gain: u32,
+    pub internal_health_ticks: u32,
+    pub internal_mana_ticks: u32,
+}
+
+impl Default for RegenerationCondition {
+    fn default() -> Self {
+        Self {
+            health_ticks: 1000,
+            health_gain: 0,
+            mana_ticks: 1000,
+            mana_gain: 0,
+            internal_health_ticks: 0,
+            internal_mana_ticks: 0,
+        }
+    }
+}
+
+/// Motsvarar `ConditionSoul`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SoulCondition {
+    pub soul_ticks: u32,
+    pub soul_gain: u32,
+    pub internal_soul_ticks: u32,
+}
+
+/// Skill- och statbonusar, motsvarar `ConditionAttributes`. Procenten
+/// räknas om till fasta värden när conditionen startar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AttributesCondition {
+    pub skills: [i32; Skill::ALL.len()],
+    pub skills_percent: [i32; Skill::ALL.len()],
+    pub stats: [i32; Stat::ALL.len()],
+    pub stats_percent: [i32; Stat::ALL.len()],
+    pub disable_defense: bool,
+}
+
+/// Det som skiljer sorterna åt, en variant per underklass till `Condition`
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum ConditionData {
+    /// `ConditionGeneric`, `ConditionInvisible` och spell-cooldowns
+    #[default]
+    Generic,
+    Damage(DamageCondition),
+    Speed(SpeedCondition),
+    Outfit(Outfit),
+    Light(LightCondition),
+    Regeneration(RegenerationCondition),
+    Soul(SoulCondition),
+    Attributes(AttributesCondition),
+}
+
+impl ConditionData {
+    /// Datat för en ny condition av typen, `None` för typer som inte finns.
+    /// Motsvarar `Condition::createCondition`.
+    pub fn for_type(condition_type: ConditionType) -> Option<Self> {
+        Some(match condition_type {
+            ConditionType::POISON
+            | ConditionType::FIRE
+            | ConditionType::ENERGY
+            | ConditionType::DROWN
+            | ConditionType::FREEZING
+            | ConditionType::DAZZLED
+            | ConditionType::CURSED
+            | ConditionType::BLEEDING => ConditionData::Damage(DamageCondition::default()),
+            ConditionType::HASTE | ConditionType::PARALYZE => ConditionData::Speed(SpeedCondition::default()),
+            ConditionType::OUTFIT => ConditionData::Outfit(Outfit::default()),
+            ConditionType::LIGHT => ConditionData::Light(LightCondition::default()),
+            ConditionType::REGENERATION => ConditionData::Regeneration(RegenerationCondition::default()),
+            ConditionType::SOUL => ConditionData::Soul(SoulCondition::default()),
+            ConditionType::ATTRIBUTES => ConditionData::Attributes(AttributesCondition::default()),
+            ConditionType::INVISIBLE
+            | ConditionType::INFIGHT
+            | ConditionType::DRUNK
+            | ConditionType::EXHAUST_WEAPON
+            | ConditionType::EXHAUST_COMBAT
+            | ConditionType::EXHAUST_HEAL
+            | ConditionType::MUTED
+            | ConditionType::CHANNELMUTEDTICKS
+            | ConditionType::YELLTICKS
+            | ConditionType::PACIFIED
+            | ConditionType::MANASHIELD
+            | ConditionType::SPELLCOOLDOWN
+            | ConditionType::SPELLGROUPCOOLDOWN => ConditionData::Generic,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Condition {
+    pub condition_type: ConditionType,
+    pub id: ConditionId,
+    pub sub_id: u32,
+    /// Kvarvarande tid i ms, -1 betyder tills den tas bort
+    pub ticks: i32,
+    pub is_buff: bool,
+    pub data: ConditionData,
+}
+
+impl Condition {
+    /// Typer utan egen sort blir generiska
+    pub fn new(id: ConditionId, condition_type: ConditionType, ticks: i32) -> Self {
+        Self {
+            condition_type,
+            id,
+            sub_id: 0,
+            ticks,
+            is_buff: false,
+            data: ConditionData::for_type(condition_type).unwrap_or_default(),
+        }
+    }
+
+    pub fn with_sub_id(mut self, sub_id: u32) -> Self {
+        self.sub_id = sub_id;
+        self
+    }
+
+    pub fn is_permanent(&self) -> bool {
+        self.ticks == -1
+    }
+
+    /// Håller en varelse i strid, t.ex. så att ett monster inte blir inaktivt
+    /// medan det brinner. Motsvarar `Condition::isAggressive`.
+    pub fn is_aggressive(&self) -> bool {
+        !self.is_buff && self.condition_type.intersects(ConditionType::AGGRESSIVE)
+    }
+
+    /// Motsvarar `Condition::getIcons` med underklassernas tillägg
+    pub fn icons(&self) -> u16 {
+        let icon = match self.condition_type {
+            ConditionType::POISON => icons::POISON,
+            ConditionType::FIRE => icons::BURN,
+            ConditionType::ENERGY => icons::ENERGY,
+            ConditionType::DROWN => icons::DROWNING,
+            ConditionType::FREEZING => icons::FREEZING,
+            ConditionType::DAZZLED => icons::DAZZLED,
+            ConditionType::CURSED => icons::CURSED,
+            ConditionType::BLEEDING => icons::BLEEDING,
+            ConditionType::MANASHIELD => icons::MANASHIELD,
+            ConditionType::INFIGHT => icons::SWORDS,
+            ConditionType::DRUNK => icons::DRUNK,
+            ConditionType::HASTE => icons::HASTE,
+            ConditionType::PARALYZE => icons::PARALYZE,
+            _ => 0,
+        };
+        if self.is_buff {
+            icon | icons::PARTY_BUFF
+        } else {
+            icon
+        }
+    }
+
+    /// Räkna ned `interval` ms. Returnerar false när conditionen har gått ut.
+    /// Motsvarar `Condition::executeCondition`.
+    pub fn execute(&mut self, interval: u32) -> bool {
+        if self.is_permanent() {
+            return true;
+        }
+        self.ticks = (self.ticks - interval as i32).max(0);
+        self.ticks > 0
+    }
+
+    /// Om `other` ska ersätta den här: samma typ och inte kortare.
+    /// Motsvarar `Condition::updateCondition`.
+    pub fn should_update(&self, other: &Condition) -> bool {
+        if self.condition_type != other.condition_type {
+            return false;
+        }
+        if self.is_permanent() && other.ticks > 0 {
+            return false;
+        }
+        !(other.ticks >= 0 && self.ticks > other.ticks)
+    }
+
+    /// Sparas i `players.conditions`, motsvarar `Condition::isPersistent`
+    pub fn is_persistent(&self) -> bool {
+        !self.is_permanent() && matches!(self.id, ConditionId::Default | ConditionId::Combat)
+    }
+}
@@ -0,0 +1,653 @@
+//! Det som spelare, monster och NPC:er har gemensamt, motsvarar `Creature`
+//! i TFS. Varelsen vet inget om kartan; världen håller positionerna i sitt
+//! spektatorindex och schemalägger think, gång och attacker.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use common::{Direction, Position};
+use items::Item;
+
+use crate::condition::{Condition, ConditionId, ConditionType};
+
+/// Varje varelse tänker en gång per sekund, i tio omgångar om 100 ms
+pub const EVENT_CREATURECOUNT: usize = 10;
+pub const EVENT_CREATURE_THINK_INTERVAL: u32 = 1000;
+pub const EVENT_CHECK_CREATURE_INTERVAL: u64 = (EVENT_CREATURE_THINK_INTERVAL / EVENT_CREATURECOUNT as u32) as u64;
+
+/// Konstanterna i klientens formel för steglängd, `Creature::speedA/B/C`
+const SPEED_A: f64 = 857.36;
+const SPEED_B: f64 = 261.29;
+const SPEED_C: f64 = -4795.01;
+
+pub const PLAYER_BASE_SPEED: u32 = 220;
+/// Farten med `PlayerFlags::SET_MAX_SPEED`
+pub const PLAYER_MAX_SPEED: u32 = 1500;
+
+static PLAYER_AUTO_ID: AtomicU32 = AtomicU32::new(0x1000_0000);
+static MONSTER_AUTO_ID: AtomicU32 = AtomicU32::new(0x4000_0000);
+static NPC_AUTO_ID: AtomicU32 = AtomicU32::new(0x8000_0000);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CreatureType {
+    Player,
+    Monster,
+    Npc,
+}
+
+impl CreatureType {
+    /// Id-intervallet avslöjar typen, som i TFS
+    pub fn of_id(id: u32) -> Option<Self> {
+        match id {
+            0x1000_0000..=0x3FFF_FFFF => Some(CreatureType::Player),
+            0x4000_0000..=0x7FFF_FFFF => Some(CreatureType::Monster),
+            0x8000_0000..=u32::MAX => Some(CreatureType::Npc),
+            _ => None,
+        }
+    }
+}
+
+/// Ljusstyrka och färg, motsvarar `LightInfo`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LightInfo {
+    pub level: u8,
+    pub color: u8,
+}
+
+impl LightInfo {
+    pub const fn new(level: u8, color: u8) -> Self {
+        Self { level, color }
+    }
+
+    /// Motsvarar `Item::getLightInfo`
+    pub fn of_item(item: &Item) -> Self {
+        let it = item.item_type();
+        Self::new(it.light_level, it.light_color)
+    }
+
+    /// Starkaste ljuset bland items, t.ex. en spelares utrustning.
+    /// Motsvarar loopen i `Player::updateItemsLight`.
+    pub fn brightest<'a>(items: impl IntoIterator<Item = &'a Item>) -> Self {
+        items
+            .into_iter()
+            .map(Self::of_item)
+            .fold(Self::default(), |best, light| if light.level > best.level { light } else { best })
+    }
+
+    /// En varelses synliga ljus: det egna (t.ex. från en ljus-spell) eller
+    /// utrustningens, det som är starkast. Motsvarar `Player::getCreatureLight`.
+    pub fn creature_light(internal: LightInfo, items: LightInfo) -> Self {
+        if internal.level > items.level {
+            internal
+        } else {
+            items
+        }
+    }
+}
+
+/// Motsvarar `Outfit_t`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Outfit {
+    pub look_type: u16,
+    pub look_type_ex: u16,
+    pub look_head: u8,
+    pub look_body: u8,
+    pub look_legs: u8,
+    pub look_feet: u8,
+    pub look_addons: u8,
+    pub look_mount: u16,
+    pub look_mount_head: u8,
+    pub look_mount_body: u8,
+    pub look_mount_legs: u8,
+    pub look_mount_feet: u8,
+}
+
+/// Hur en träff blockerades, motsvarar `BlockType_t`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BlockType {
+    #[default]
+    None,
+    Defense,
+    Armor,
+    Immunity,
+}
+
+/// Skadan en anfallare gjort, motsvarar `CountBlock_t`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CountBlock {
+    pub total: i32,
+    /// `otsys_time` för senaste träffen
+    pub ticks: u64,
+}
+
+/// Motsvarar `Skulls_t`
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Skull {
+    #[default]
+    None = 0,
+    Yellow = 1,
+    Green = 2,
+    White = 3,
+    Red = 4,
+    Black = 5,
+    Orange = 6,
+}
+
+impl Skull {
+    pub fn from_u8(value: u8) -> Self {
+        match value {
+            1 => Skull::Yellow,
+            2 => Skull::Green,
+            3 => Skull::White,
+            4 => Skull::Red,
+            5 => Skull::Black,
+            6 => Skull::Orange,
+            _ => Skull::None,
+        }
+    }
+
+    /// Namnen i monsterfilerna, motsvarar `getSkullType`
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name.to_ascii_lowercase().as_str() {
+            "none" => Skull::None,
+            "yellow" => Skull::Yellow,
+            "green" => Skull::Green,
+            "white" => Skull::White,
+            "red" => Skull::Red,
+            "black" => Skull::Black,
+            "orange" => Skull::Orange,
+            _ => return None,
+        })
+    }
+}
+
+/// Partysymbolen, motsvarar `PartyShields_t`
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PartyShield {
+    #[default]
+    None = 0,
+    WhiteYellow = 1,
+    WhiteBlue = 2,
+    Blue = 3,
+    Yellow = 4,
+    BlueSharedExp = 5,
+    YellowSharedExp = 6,
+    BlueNoSharedExpBlink = 7,
+    YellowNoSharedExpBlink = 8,
+    BlueNoSharedExp = 9,
+    YellowNoSharedExp = 10,
+    Gray = 11,
+}
+
+/// Guildsymbolen, motsvarar `GuildEmblems_t`
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GuildEmblem {
+    #[default]
+    None = 0,
+    Green = 1,
+    Red = 2,
+    Blue = 3,
+    Member = 4,
+    Other = 5,
+}
+
+/// Händelser scripten kan lyssna på, motsvarar `CreatureEventType_t`
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CreatureEventType {
+    Login,
+    Logout,
+    Think,
+    PrepareDeath,
+    Death,
+    Kill,
+    Advance,
+    ModalWindow,
+    TextEdit,
+    HealthChange,
+    ManaChange,
+    ExtendedOpcode,
+}
+
+impl CreatureEventType {
+    /// Typen som den skrivs i creaturescripts.xml
+    pub fn from_name(name: &str) -> Option<Self> {
+        use CreatureEventType::*;
+        Some(match name.to_ascii_lowercase().as_str() {
+            "login" => Login,
+            "logout" => Logout,
+            "think" => Think,
+            "preparedeath" => PrepareDeath,
+            "death" => Death,
+            "kill" => Kill,
+            "advance" => Advance,
+            "modalwindow" => ModalWindow,
+            "textedit" => TextEdit,
+            "healthchange" => HealthChange,
+            "manachange" => ManaChange,
+            "extendedopcode" => ExtendedOpcode,
+            _ => return None,
+        })
+    }
+
+    /// Lua-funktionen scriptet ska definiera
+    pub fn function_name(self) -> &'static str {
+        use CreatureEventType::*;
+        match self {
+            Login => "onLogin",
+            Logout => "onLogout",
+            Think => "onThink",
+            PrepareDeath => "onPrepareDeath",
+            Death => "onDeath",
+            Kill => "onKill",
+            Advance => "onAdvance",
+            ModalWindow => "onModalWindow",
+            TextEdit => "onTextEdit",
+            HealthChange => "onHealthChange",
+            ManaChange => "onManaChange",
+            ExtendedOpcode => "onExtendedOpcode",
+        }
+    }
+
+    fn bit(self) -> u32 {
+        1 << self as u32
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Creature {
+    /// 0 tills varelsen läggs till i spelet, se `set_id`
+    pub id: u32,
+    pub creature_type: CreatureType,
+    pub name: String,
+
+    pub position: Position,
+    pub direction: Direction,
+
+    pub health: i32,
+    pub health_max: i32,
+    pub mana: u32,
+    pub mana_max: u32,
+
+    pub base_speed: u32,
+    var_speed: i32,
+
+    pub outfit: Outfit,
+    /// Outfiten utan conditions som t.ex. utseendebyte
+    pub default_outfit: Outfit,
+    pub skull: Skull,
+    pub shield: PartyShield,
+    pub emblem: GuildEmblem,
+    pub internal_light: LightInfo,
+    pub hidden_health: bool,
+    /// Falskt under t.ex. berserk, då varelsen inte blockerar med sköld
+    pub can_use_defense: bool,
+
+    conditions: Vec<Condition>,
+
+    pub attacked_creature: Option<u32>,
+    pub follow_creature: Option<u32>,
+    pub master: Option<u32>,
+    pub summons: Vec<u32>,
+
+    /// Anfallarnas skada, töms när varelsen inte längre är i strid
+    damage_map: BTreeMap<u32, CountBlock>,
+    /// Den som träffade sist, motsvarar `lastHitCreatureId`
+    pub last_hit_creature: Option<u32>,
+
+    list_walk_dir: VecDeque<Direction>,
+    pub last_step: u64,
+    pub last_step_cost: u32,
+    pub has_follow_path: bool,
+    force_update_follow_path: bool,
+    walk_update_ticks: u32,
+    block_ticks: u32,
+    pub block_count: u32,
+
+    events: Vec<(String, CreatureEventType)>,
+    event_bits: u32,
+}
+
+impl Creature {
+    pub fn new(creature_type: CreatureType, name: impl Into<String>) -> Self {
+        Self {
+            id: 0,
+            creature_type,
+            name: name.into(),
+            position: Position::default(),
+            direction: Direction::South,
+            health: 1000,
+            health_max: 1000,
+            mana: 0,
+            mana_max: 0,
+            base_speed: PLAYER_BASE_SPEED,
+            var_speed: 0,
+            outfit: Outfit::default(),
+            default_outfit: Outfit::default(),
+            skull: Skull::None,
+            shield: PartyShield::None,
+            emblem: GuildEmblem::None,
+            internal_light: LightInfo::default(),
+            hidden_health: false,
+            can_use_defense: true,
+            conditions: Vec::new(),
+            attacked_creature: None,
+            follow_creature: None,
+            master: None,
+            summons: Vec::new(),
+            damage_map: BTreeMap::new(),
+            last_hit_creature: None,
+            list_walk_dir: VecDeque::new(),
+            last_step: 0,
+            last_step_cost: 1,
+            has_follow_path: false,
+            force_update_follow_path: false,
+            walk_update_ticks: 0,
+            block_ticks: 0,
+            block_count: 0,
+            events: Vec::new(),
+            event_bits: 0,
+        }
+    }
+
+    /// Ge varelsen ett id ur sin typs intervall, om den inte redan har ett.
+    /// Motsvarar `setID` i Player, Monster och Npc.
+    pub fn set_id(&mut self) -> u32 {
+        if self.id == 0 {
+            let counter = match self.creature_type {
+                CreatureType::Player => &PLAYER_AUTO_ID,
+                CreatureType::Monster => &MONSTER_AUTO_ID,
+                CreatureType::Npc => &NPC_AUTO_ID,
+            };
+            self.id = counter.fetch_add(1, Ordering::Relaxed);
+        }
+        self.id
+    }
+
+    pub fn is_player(&self) -> bool {
+        self.creature_type == CreatureType::Player
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.health <= 0
+    }
+
+    pub fn is_summon(&self) -> bool {
+        self.master.is_some()
+    }
+
+    /// Ändra hälsan inom 0..=max och returnera den faktiska ändringen.
+    /// Motsvarar `Creature::changeHealth`.
+    pub fn change_health(&mut self, delta: i32) -> i32 {
+        let old = self.health;
+        self.health = (self.health + delta).clamp(0, self.health_max);
+        self.health - old
+    }
+
+    /// Motsvarar `Creature::changeMana`
+    pub fn change_mana(&mut self, delta: i32) -> i32 {
+        let old = self.mana as i64;
+        self.mana = (old + delta as i64).clamp(0, self.mana_max as i64) as u32;
+        (self.mana as i64 - old) as i32
+    }
+
+    /// Hälsan i procent som klienten visar den
+    pub fn health_percent(&self) -> u8 {
+        if self.hidden_health || self.health_max <= 0 {
+            return 100;
+        }
+        ((self.health.max(0) as i64 * 100 / self.health_max as i64).min(100)) as u8
+    }
+
+    // === Hastighet ===
+
+    pub fn speed(&self) -> u32 {
+        (self.base_speed as i64 + self.var_speed as i64).max(0) as u32
+    }
+
+    pub fn var_speed(&self) -> i32 {
+        self.var_speed
+    }
+
+    /// Haste, paralyze och liknande. Motsvarar `Game::changeSpeed`.
+    pub fn change_speed(&mut self, delta: i32) {
+        self.var_speed += delta;
+    }
+
+    pub fn set_var_speed(&mut self, var_speed: i32) {
+        self.var_speed = var_speed;
+    }
+
+    /// Tid i ms för ett steg på mark med `ground_speed`. Motsvarar
+    /// `Creature::getStepDuration`, utan monstrets dubbling nära målet.
+    pub fn step_duration(&self, ground_speed: u16, dir: Direction) -> u64 {
+        let step_speed = self.speed() as f64;
+        let calculated = if step_speed > -SPEED_B {
+            let value = (SPEED_A * ((step_speed / 2.0) + SPEED_B).ln() + SPEED_C + 0.5).floor();
+            if value <= 0.0 { 1.0 } else { value }
+        } else {
+            1.0
+        };
+
+        let ground_speed = if ground_speed == 0 { 150 } else { ground_speed } as f64;
+        let duration = (1000.0 * ground_speed / calculated).floor();
+        let step = ((duration / 50.0).ceil() * 50.0) as u64;
+        if dir.is_diagonal() {
+            step * 3
+        } else {
+            step
+        }
+    }
+
+    // === Gång och följ ===
+
+    /// Börja gå en väg, t.ex. från pathfindern. Motsvarar `Creature::startAutoWalk`.
+    pub fn start_auto_walk(&mut self, path: impl IntoIterator<Item = Direction>) {
+        self.list_walk_dir.clear();
+        self.list_walk_dir.extend(path);
+    }
+
+    /// Nästa steg att ta, om något. Motsvarar `Creature::getNextStep`.
+    pub fn next_step(&mut self) -> Option<Direction> {
+        self.list_walk_dir.pop_front()
+    }
+
+    pub fn has_walk_path(&self) -> bool {
+        !self.list_walk_dir.is_empty()
+    }
+
+    pub fn stop_walk(&mut self) {
+        self.list_walk_dir.clear();
+    }
+
+    /// Motsvarar `Creature::setFollowCreature`. Returnerar true om något ändrades.
+    pub fn set_follow_creature(&mut self, target: Option<u32>) -> bool {
+        if self.follow_creature == target {
+            return false;
+        }
+        self.follow_creature = target;
+        self.has_follow_path = false;
+        self.force_update_follow_path = target.is_some();
+        if target.is_none() {
+            self.list_walk_dir.clear();
+        }
+        true
+    }
+
+    /// Vägen till den vi följer behöver räknas om, t.ex. för att den flyttat sig
+    pub fn force_update_follow_path(&mut self) {
+        if self.follow_creature.is_some() {
+            self.force_update_follow_path = true;
+        }
+    }
+
+    pub fn set_attacked_creature(&mut self, target: Option<u32>) -> bool {
+        if self.attacked_creature == target {
+            return false;
+        }
+        self.attacked_creature = target;
+        true
+    }
+
+    /// Varelsen tänker. Returnerar true när vägen till den vi följer ska
+    /// räknas om (`goToFollowCreature`). Motsvarar `Creature::onThink`.
+    pub fn on_think(&mut self, interval: u32) -> bool {
+        self.block_ticks += interval;
+        if self.block_ticks >= 1000 {
+            self.block_count = (self.block_count + 1).min(2);
+            self.block_ticks = 0;
+        }
+
+        if self.follow_creature.is_none() {
+            return false;
+        }
+        self.walk_update_ticks += interval;
+        if self.force_update_follow_path || self.walk_update_ticks >= 2000 {
+            self.walk_update_ticks = 0;
+            self.force_update_follow_path = false;
+            return true;
+        }
+        false
+    }
+
+    // === Skada ===
+
+    /// Motsvarar `Creature::addDamagePoints`
+    pub fn add_damage_points(&mut self, attacker: u32, damage_points: i32, now: u64) {
+        if damage_points <= 0 {
+            return;
+        }
+        let block = self.damage_map.entry(attacker).or_default();
+        block.total += damage_points;
+        block.ticks = now;
+        self.last_hit_creature = Some(attacker);
+    }
+
+    pub fn damage_map(&self) -> impl Iterator<Item = (u32, &CountBlock)> {
+        self.damage_map.iter().map(|(&id, block)| (id, block))
+    }
+
+    /// Anfallaren har träffat inom `in_fight_ticks` ms. Motsvarar
+    /// `Creature::hasBeenAttacked`.
+    pub fn has_been_attacked(&self, attacker: u32, now: u64, in_fight_ticks: i32) -> bool {
+        self.damage_map
+            .get(&attacker)
+            .is_some_and(|block| now.saturating_sub(block.ticks) <= in_fight_ticks.max(0) as u64)
+    }
+
+    /// Anfallarens andel av all skada. Motsvarar `Creature::getDamageRatio`.
+    pub fn get_damage_ratio(&self, attacker: u32) -> f64 {
+        let total: i64 = self.damage_map.values().map(|block| block.total as i64).sum();
+        if total == 0 {
+            return 0.0;
+        }
+        self.damage_map.get(&attacker).map_or(0, |block| block.total as i64) as f64 / total as f64
+    }
+
+    /// Varelsen är inte längre i strid. Motsvarar `Creature::onIdleStatus`.
+    pub fn on_idle_status(&mut self) {
+        if self.health > 0 {
+            self.damage_map.clear();
+            self.last_hit_creature = None;
+        }
+    }
+
+    // === Conditions ===
+
+    /// En generisk condition utan effekter, som in fight: en befintlig av
+    /// samma typ, id och sub-id förlängs i stället för att läggas till igen.
+    /// Övriga sorter läggs till med `rules::condition::add_condition`.
+    pub fn add_condition(&mut self, condition: Condition) -> bool {
+        if self.is_dead() {
+            return false;
+        }
+        if let Some(existing) = self.get_condition_mut(condition.condition_type, condition.id, condition.sub_id) {
+            if existing.should_update(&condition) {
+                existing.ticks = condition.ticks;
+            }
+            return true;
+        }
+        self.conditions.push(condition);
+        true
+    }
+
+    pub fn get_condition(&self, condition_type: ConditionType, id: ConditionId, sub_id: u32) -> Option<&Condition> {
+        self.conditions
+            .iter()
+            .find(|c| c.condition_type == condition_type && c.id == id && c.sub_id == sub_id)
+    }
+
+    pub fn get_condition_mut(&mut self, condition_type: ConditionType, id: ConditionId, sub_id: u32) -> Option<&mut Condition> {
+        self.conditions
+            .iter_mut()
+            .find(|c| c.condition_type == condition_type && c.id == id && c.sub_id == sub_id)
+    }
+
+    pub fn has_condition(&self, condition_type: ConditionType) -> bool {
+        self.conditions.iter().any(|c| c.condition_type.intersects(condition_type))
+    }
+
+    /// Motsvarar `Creature::removeCondition(type, id)`
+    pub fn remove_condition(&mut self, condition_type: ConditionType, id: ConditionId) -> Vec<Condition> {
+        self.take_conditions(|c| c.condition_type == condition_type && c.id == id)
+    }
+
+    /// Alla conditions av en typ, oavsett varifrån de kom
+    pub fn remove_condition_type(&mut self, condition_type: ConditionType) -> Vec<Condition> {
+        self.take_conditions(|c| c.condition_type.intersects(condition_type))
+    }
+
+    pub fn conditions(&self) -> &[Condition] {
+        &self.conditions
+    }
+
+    /// För `rules::condition`, som startar, tickar och avslutar dem
+    pub fn conditions_mut(&mut self) -> &mut Vec<Condition> {
+        &mut self.conditions
+    }
+
+    fn take_conditions(&mut self, mut pred: impl FnMut(&Condition) -> bool) -> Vec<Condition> {
+        let (removed, kept) = std::mem::take(&mut self.conditions).into_iter().partition(|c| pred(c));
+        self.conditions = kept;
+        removed
+    }
+
+    // === Scripthändelser ===
+
+    /// Motsvarar `Creature::registerCreatureEvent`
+    pub fn register_event(&mut self, name: &str, event_type: CreatureEventType) -> bool {
+        if self.events.iter().any(|(n, _)| n.eq_ignore_ascii_case(name)) {
+            return false;
+        }
+        self.events.push((name.to_string(), event_type));
+        self.event_bits |= event_type.bit();
+        true
+    }
+
+    /// Motsvarar `Creature::unregisterCreatureEvent`
+    pub fn unregister_event(&mut self, name: &str) -> bool {
+        let Some(index) = self.events.iter().position(|(n, _)| n.eq_ignore_ascii_case(name)) else {
+            return false;
+        };
+        let (_, event_type) = self.events.remove(index);
+        if !self.events.iter().any(|(_, t)| *t == event_type) {
+            self.event_bits &= !event_type.bit();
+        }
+        true
+    }
+
+    pub fn has_event_type(&self, event_type: CreatureEventType) -> bool {
+        self.event_bits & event_type.bit() != 0
+    }
+
+    /// Namnen på registrerade händelser av en typ, i registreringsordning
+    pub fn events(&self, event_type: CreatureEventType) -> impl Iterator<Item = &str> {
+        self.events
+            .iter()
+            .filter(move |(_, t)| *t == event_type)
+            .map(|(name, _)| name.as_str())
+    }
+}
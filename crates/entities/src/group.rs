@@ -0,0 +1,215 @@
+//! Grupperna från data/XML/groups.xml och deras flaggor, motsvarar `Group`,
+//! `Groups` och `PlayerFlags` i TFS. Spelaren har en kopia av sin grupp
+//! (`Player::group`) och frågar den med `Player::has_flag`.
+
+use std::path::Path;
+
+use common::tracing::warn;
+use common::{Error, Result};
+
+/// Motsvarar `PlayerFlags`, en bit per flagga
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct PlayerFlags(pub u64);
+
+impl PlayerFlags {
+    pub const NONE: Self = Self(0);
+    pub const CANNOT_USE_COMBAT: Self = Self(1 << 0);
+    pub const CANNOT_ATTACK_PLAYER: Self = Self(1 << 1);
+    pub const CANNOT_ATTACK_MONSTER: Self = Self(1 << 2);
+    pub const CANNOT_BE_ATTACKED: Self = Self(1 << 3);
+    pub const CAN_CONVINCE_ALL: Self = Self(1 << 4);
+    pub const CAN_SUMMON_ALL: Self = Self(1 << 5);
+    pub const CAN_ILLUSION_ALL: Self = Self(1 << 6);
+    pub const CAN_SENSE_INVISIBILITY: Self = Self(1 << 7);
+    pub const IGNORED_BY_MONSTERS: Self = Self(1 << 8);
+    pub const NOT_GAIN_IN_FIGHT: Self = Self(1 << 9);
+    pub const HAS_INFINITE_MANA: Self = Self(1 << 10);
+    pub const HAS_INFINITE_SOUL: Self = Self(1 << 11);
+    pub const HAS_NO_EXHAUSTION: Self = Self(1 << 12);
+    pub const CANNOT_USE_SPELLS: Self = Self(1 << 13);
+    pub const CANNOT_PICKUP_ITEM: Self = Self(1 << 14);
+    pub const CAN_ALWAYS_LOGIN: Self = Self(1 << 15);
+    pub const CAN_BROADCAST: Self = Self(1 << 16);
+    pub const CAN_EDIT_HOUSES: Self = Self(1 << 17);
+    pub const CANNOT_BE_BANNED: Self = Self(1 << 18);
+    pub const CANNOT_BE_PUSHED: Self = Self(1 << 19);
+    pub const HAS_INFINITE_CAPACITY: Self = Self(1 << 20);
+    pub const CAN_PUSH_ALL_CREATURES: Self = Self(1 << 21);
+    pub const CAN_TALK_RED_PRIVATE: Self = Self(1 << 22);
+    pub const CAN_TALK_RED_CHANNEL: Self = Self(1 << 23);
+    pub const TALK_ORANGE_HELP_CHANNEL: Self = Self(1 << 24);
+    pub const NOT_GAIN_EXPERIENCE: Self = Self(1 << 25);
+    pub const NOT_GAIN_MANA: Self = Self(1 << 26);
+    pub const NOT_GAIN_HEALTH: Self = Self(1 << 27);
+    pub const NOT_GAIN_SKILL: Self = Self(1 << 28);
+    pub const SET_MAX_SPEED: Self = Self(1 << 29);
+    pub const SPECIAL_VIP: Self = Self(1 << 30);
+    pub const NOT_GENERATE_LOOT: Self = Self(1 << 31);
+    pub const CAN_TALK_RED_CHANNEL_ANONYMOUS: Self = Self(1 << 32);
+    pub const IGNORE_PROTECTION_ZONE: Self = Self(1 << 33);
+    pub const IGNORE_SPELL_CHECK: Self = Self(1 << 34);
+    pub const IGNORE_WEAPON_CHECK: Self = Self(1 << 35);
+    pub const CANNOT_BE_MUTED: Self = Self(1 << 36);
+    pub const IS_ALWAYS_PREMIUM: Self = Self(1 << 37);
+
+    /// Namnen i groups.xml och i Lua (`PlayerFlag_` + namnet), motsvarar
+    /// `ParsePlayerFlagMap`
+    pub const ALL: [(&'static str, Self); 38] = [
+        ("CannotUseCombat", Self::CANNOT_USE_COMBAT),
+        ("CannotAttackPlayer", Self::CANNOT_ATTACK_PLAYER),
+        ("CannotAttackMonster", Self::CANNOT_ATTACK_MONSTER),
+        ("CannotBeAttacked", Self::CANNOT_BE_ATTACKED),
+        ("CanConvinceAll", Self::CAN_CONVINCE_ALL),
+        ("CanSummonAll", Self::CAN_SUMMON_ALL),
+        ("CanIllusionAll", Self::CAN_ILLUSION_ALL),
+        ("CanSenseInvisibility", Self::CAN_SENSE_INVISIBILITY),
+        ("IgnoredByMonsters", Self::IGNORED_BY_MONSTERS),
+        ("NotGainInFight", Self::NOT_GAIN_IN_FIGHT),
+        ("HasInfiniteMana", Self::HAS_INFINITE_MANA),
+        ("HasInfiniteSoul", Self::HAS_INFINITE_SOUL),
+        ("HasNoExhaustion", Self::HAS_NO_EXHAUSTION),
+        ("CannotUseSpells", Self::CANNOT_USE_SPELLS),
+        ("CannotPickupItem", Self::CANNOT_PICKUP_ITEM),
+        ("CanAlwaysLogin", Self::CAN_ALWAYS_LOGIN),
+        ("CanBroadcast", Self::CAN_BROADCAST),
+        ("CanEditHouses", Self::CAN_EDIT_HOUSES),
+        ("CannotBeBanned", Self::CANNOT_BE_BANNED),
+        ("CannotBePushed", Self::CANNOT_BE_PUSHED),
+        ("HasInfiniteCapacity", Self::HAS_INFINITE_CAPACITY),
+        ("CanPushAllCreatures", Self::CAN_PUSH_ALL_CREATURES),
+        ("CanTalkRedPrivate", Self::CAN_TALK_RED_PRIVATE),
+        ("CanTalkRedChannel", Self::CAN_TALK_RED_CHANNEL),
+        ("TalkOrangeHelpChannel", Self::TALK_ORANGE_HELP_CHANNEL),
+        ("NotGainExperience", Self::NOT_GAIN_EXPERIENCE),
+        ("NotGainMana", Self::NOT_GAIN_MANA),
+        ("NotGainHealth", Self::NOT_GAIN_HEALTH),
+        ("NotGainSkill", Self::NOT_GAIN_SKILL),
+        ("SetMaxSpeed", Self::SET_MAX_SPEED),
+        ("SpecialVIP", Self::SPECIAL_VIP),
+        ("NotGenerateLoot", Self::NOT_GENERATE_LOOT),
+        ("CanTalkRedChannelAnonymous", Self::CAN_TALK_RED_CHANNEL_ANONYMOUS),
+        ("IgnoreProtectionZone", Self::IGNORE_PROTECTION_ZONE),
+        ("IgnoreSpellCheck", Self::IGNORE_SPELL_CHECK),
+        ("IgnoreWeaponCheck", Self::IGNORE_WEAPON_CHECK),
+        ("CannotBeMuted", Self::CANNOT_BE_MUTED),
+        ("IsAlwaysPremium", Self::IS_ALWAYS_PREMIUM),
+    ];
+
+    /// Skiftlägesokänsligt, groups.xml skriver namnen med små bokstäver
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().find(|(flag, _)| flag.eq_ignore_ascii_case(name)).map(|&(_, flag)| flag)
+    }
+
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn intersects(self, other: Self) -> bool {
+        self.0 & other.0 != 0
+    }
+}
+
+impl std::ops::BitOr for PlayerFlags {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl std::ops::BitOrAssign for PlayerFlags {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+/// En grupp, motsvarar `Group`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Group {
+    pub id: u16,
+    pub name: String,
+    pub flags: PlayerFlags,
+    /// Spelledare: syns inte i listor, ser osynliga, går igenom allt
+    pub access: bool,
+    /// 0 = gränsen för fritt eller premiumkonto
+    pub max_depot_items: u32,
+    pub max_vip_entries: u32,
+}
+
+impl Default for Group {
+    /// Vanliga spelare, grupp 1 utan flaggor
+    fn default() -> Self {
+        Self {
+            id: 1,
+            name: "player".to_string(),
+            flags: PlayerFlags::NONE,
+            access: false,
+            max_depot_items: 0,
+            max_vip_entries: 0,
+        }
+    }
+}
+
+/// Alla grupper, motsvarar `Groups`
+#[derive(Debug, Clone, Default)]
+pub struct Groups {
+    groups: Vec<Group>,
+}
+
+impl Groups {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Motsvarar `Groups::load`. Flaggorna kan ges som ett tal i `flags`
+    /// och som `<flag namn="1" />` under `<flags>`.
+    pub fn load_from_xml(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| Error::World(format!("Cannot open {}: {e}", path.display())))?;
+        let doc = roxmltree::Document::parse(&text)
+            .map_err(|e| Error::World(format!("Cannot parse {}: {e}", path.display())))?;
+
+        let mut groups = Self::new();
+        for node in doc.root_element().children().filter(|n| n.has_tag_name("group")) {
+            let number = |name: &str| node.attribute(name).and_then(|v| v.trim().parse::<u64>().ok()).unwrap_or(0);
+            let mut group = Group {
+                id: number("id") as u16,
+                name: node.attribute("name").unwrap_or_default().to_string(),
+                flags: PlayerFlags(number("flags")),
+                access: node.attribute("access").is_some_and(as_bool),
+                max_depot_items: number("maxdepotitems") as u32,
+                max_vip_entries: number("maxvipentries") as u32,
+            };
+
+            let flag_nodes = node.children().filter(|n| n.has_tag_name("flags")).flat_map(|n| n.children());
+            for flag_node in flag_nodes.filter(|n| n.is_element()) {
+                let Some(attr) = flag_node.attributes().next() else {
+                    continue;
+                };
+                if !as_bool(attr.value()) {
+                    continue;
+                }
+                match PlayerFlags::from_name(attr.name()) {
+                    Some(flag) => group.flags |= flag,
+                    None => warn!("[Groups::load] Unknown flag {} in group {}", attr.name(), group.name),
+                }
+            }
+            groups.groups.push(group);
+        }
+        Ok(groups)
+    }
+
+    pub fn get_group(&self, id: u16) -> Option<&Group> {
+        self.groups.iter().find(|group| group.id == id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Group> {
+        self.groups.iter()
+    }
+}
+
+/// Som pugixmls `as_bool`: sant om värdet börjar med 1, t, T, y eller Y
+pub(crate) fn as_bool(value: &str) -> bool {
+    matches!(value.trim().chars().next(), Some('1' | 't' | 'T' | 'y' | 'Y'))
+}
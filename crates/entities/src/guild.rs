@@ -0,0 +1,278 @@
+//! Gillen, motsvarar `Guild` och gilledelarna av `Player` och `Game` i TFS.
+//!
+//! Ett gille läses in från databasen (`persistence::ioguild`) när den första
+//! medlemmen loggar in och släpps när den sista loggar ut. Spelaren har bara
+//! sitt medlemskap (`GuildMembership`); rangerna, medlemmarna som är inne,
+//! gillekanalen och krigen hålls här. Krigen ligger på gillet och inte på
+//! spelaren som i TFS, så emblem och frags stämmer så fort ett krig börjar
+//! eller slutar.
+
+use std::collections::{BTreeSet, HashMap};
+
+use common::SpeakClass;
+
+use crate::creature::GuildEmblem;
+
+/// Rangnivåerna som triggern `oncreate_guilds` skapar
+pub const GUILD_RANK_LEADER: u8 = 3;
+pub const GUILD_RANK_VICE_LEADER: u8 = 2;
+pub const GUILD_RANK_MEMBER: u8 = 1;
+
+/// `status` i `guild_wars`
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GuildWarStatus {
+    /// Förklarat, väntar på svar
+    Pending = 0,
+    Active = 1,
+    Rejected = 2,
+    /// Tillbakadraget av gillet som förklarade
+    Canceled = 3,
+    Ended = 4,
+}
+
+impl GuildWarStatus {
+    pub fn from_u8(value: u8) -> Option<Self> {
+        Some(match value {
+            0 => GuildWarStatus::Pending,
+            1 => GuildWarStatus::Active,
+            2 => GuildWarStatus::Rejected,
+            3 => GuildWarStatus::Canceled,
+            4 => GuildWarStatus::Ended,
+            _ => return None,
+        })
+    }
+}
+
+/// En rad i `guild_ranks`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GuildRank {
+    pub id: u32,
+    pub name: String,
+    pub level: u8,
+}
+
+/// Spelarens rad i `guild_membership`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GuildMembership {
+    pub guild_id: u32,
+    pub rank_id: u32,
+    /// Högst 15 tecken, visas inom parentes efter namnet
+    pub nick: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Guild {
+    id: u32,
+    name: String,
+    pub motd: String,
+    /// Antal medlemmar i databasen, inloggade eller inte
+    pub member_count: u32,
+    ranks: Vec<GuildRank>,
+    members_online: BTreeSet<u32>,
+    channel_users: BTreeSet<u32>,
+    /// Gillen som det här gillet har ett pågående krig med
+    wars: BTreeSet<u32>,
+}
+
+impl Guild {
+    pub fn new(id: u32, name: impl Into<String>) -> Self {
+        Self {
+            id,
+            name: name.into(),
+            motd: String::new(),
+            member_count: 0,
+            ranks: Vec::new(),
+            members_online: BTreeSet::new(),
+            channel_users: BTreeSet::new(),
+            wars: BTreeSet::new(),
+        }
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn ranks(&self) -> &[GuildRank] {
+        &self.ranks
+    }
+
+    pub fn add_rank(&mut self, id: u32, name: impl Into<String>, level: u8) {
+        self.ranks.push(GuildRank { id, name: name.into(), level });
+    }
+
+    pub fn get_rank_by_id(&self, id: u32) -> Option<&GuildRank> {
+        self.ranks.iter().find(|rank| rank.id == id)
+    }
+
+    /// Skiftlägesokänsligt, som `Guild::getRankByName`
+    pub fn get_rank_by_name(&self, name: &str) -> Option<&GuildRank> {
+        self.ranks.iter().find(|rank| rank.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn get_rank_by_level(&self, level: u8) -> Option<&GuildRank> {
+        self.ranks.iter().find(|rank| rank.level == level)
+    }
+
+    pub fn members_online(&self) -> impl Iterator<Item = u32> + '_ {
+        self.members_online.iter().copied()
+    }
+
+    pub fn is_member_online(&self, player_id: u32) -> bool {
+        self.members_online.contains(&player_id)
+    }
+
+    pub fn channel_users(&self) -> impl Iterator<Item = u32> + '_ {
+        self.channel_users.iter().copied()
+    }
+
+    pub fn wars(&self) -> impl Iterator<Item = u32> + '_ {
+        self.wars.iter().copied()
+    }
+
+    pub fn is_at_war(&self) -> bool {
+        !self.wars.is_empty()
+    }
+
+    pub fn is_at_war_with(&self, guild_id: u32) -> bool {
+        self.wars.contains(&guild_id)
+    }
+}
+
+/// De inlästa gillena, motsvarar `Game::guilds`
+#[derive(Debug, Default)]
+pub struct Guilds {
+    guilds: HashMap<u32, Guild>,
+}
+
+impl Guilds {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, guild_id: u32) -> Option<&Guild> {
+        self.guilds.get(&guild_id)
+    }
+
+    pub fn get_mut(&mut self, guild_id: u32) -> Option<&mut Guild> {
+        self.guilds.get_mut(&guild_id)
+    }
+
+    pub fn contains(&self, guild_id: u32) -> bool {
+        self.guilds.contains_key(&guild_id)
+    }
+
+    /// Lägg till ett nyss inläst gille, motsvarar `Game::addGuild`
+    pub fn insert(&mut self, guild: Guild) {
+        self.guilds.insert(guild.id, guild);
+    }
+
+    /// Spelaren har loggat in; false om gillet inte är inläst
+    pub fn add_member(&mut self, guild_id: u32, player_id: u32) -> bool {
+        match self.guilds.get_mut(&guild_id) {
+            Some(guild) => {
+                guild.members_online.insert(player_id);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Spelaren har loggat ut eller lämnat gillet. Gillet släpps när ingen
+    /// medlem är inne längre, som `Guild::removeMember`.
+    pub fn remove_member(&mut self, guild_id: u32, player_id: u32) {
+        let Some(guild) = self.guilds.get_mut(&guild_id) else {
+            return;
+        };
+        guild.members_online.remove(&player_id);
+        guild.channel_users.remove(&player_id);
+        if guild.members_online.is_empty() {
+            self.guilds.remove(&guild_id);
+        }
+    }
+
+    /// Har de två gillena krig med varandra. Motsvarar `Player::isInWar`
+    /// med gillena för de två spelarna.
+    pub fn is_in_war(&self, guild: Option<u32>, other: Option<u32>) -> bool {
+        let (Some(guild), Some(other)) = (guild, other) else {
+            return false;
+        };
+        guild != other
+            && self.get(guild).is_some_and(|guild| guild.is_at_war_with(other))
+            && self.get(other).is_some_and(|other| other.is_at_war_with(guild))
+    }
+
+    /// Ett krig har accepterats. Gillen som inte är inlästa får kriget när
+    /// de läses in.
+    pub fn start_war(&mut self, guild1: u32, guild2: u32) {
+        if let Some(guild) = self.guilds.get_mut(&guild1) {
+            guild.wars.insert(guild2);
+        }
+        if let Some(guild) = self.guilds.get_mut(&guild2) {
+            guild.wars.insert(guild1);
+        }
+    }
+
+    pub fn end_war(&mut self, guild1: u32, guild2: u32) {
+        if let Some(guild) = self.guilds.get_mut(&guild1) {
+            guild.wars.remove(&guild2);
+        }
+        if let Some(guild) = self.guilds.get_mut(&guild2) {
+            guild.wars.remove(&guild1);
+        }
+    }
+
+    /// Emblemet `viewer` ser på `player`, med spelarnas gillen. Motsvarar
+    /// `Player::getGuildEmblem`.
+    pub fn emblem(&self, viewer: Option<u32>, player: Option<u32>) -> GuildEmblem {
+        let Some(guild) = player.and_then(|id| self.get(id)) else {
+            return GuildEmblem::None;
+        };
+        let same_guild = viewer == Some(guild.id);
+        if !guild.is_at_war() {
+            if same_guild {
+                GuildEmblem::Member
+            } else {
+                GuildEmblem::Other
+            }
+        } else if same_guild {
+            GuildEmblem::Green
+        } else if self.is_in_war(viewer, player) {
+            GuildEmblem::Red
+        } else {
+            GuildEmblem::Blue
+        }
+    }
+
+    /// Gå med i gillekanalen (`CHANNEL_GUILD`); bara inloggade medlemmar.
+    /// Spelet skickar kanalen och dagens meddelande, `Game::sendGuildMotd`.
+    pub fn open_channel(&mut self, guild_id: u32, player_id: u32) -> bool {
+        match self.guilds.get_mut(&guild_id) {
+            Some(guild) if guild.members_online.contains(&player_id) => {
+                guild.channel_users.insert(player_id);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn close_channel(&mut self, guild_id: u32, player_id: u32) -> bool {
+        self.guilds
+            .get_mut(&guild_id)
+            .is_some_and(|guild| guild.channel_users.remove(&player_id))
+    }
+
+    /// Vice-ledare och ledare talar orange i gillekanalen, andra gult.
+    /// Motsvarar gillefallet i `Chat::talkToChannel`.
+    pub fn channel_speak_class(rank_level: u8) -> SpeakClass {
+        if rank_level > GUILD_RANK_MEMBER {
+            SpeakClass::ChannelO
+        } else {
+            SpeakClass::ChannelY
+        }
+    }
+}
@@ -1,9 +1,23 @@
 pub mod player;
 pub mod creature;
+pub mod condition;
 pub mod monster;
 pub mod npc;
 pub mod guild;
 pub mod party;
+pub mod vip;
+pub mod group;
+pub mod outfit;
+pub mod mount;
 
-// re-export viktiga typer, t.ex.
-// pub use player::Player;
+pub use condition::{Condition, ConditionData, ConditionId, ConditionType};
+pub use creature::{Creature, CreatureEventType, CreatureType, GuildEmblem, LightInfo, Outfit, PartyShield};
+pub use group::{Group, Groups, PlayerFlags};
+pub use guild::{Guild, GuildMembership, GuildRank, GuildWarStatus, Guilds};
+pub use mount::{Mount, Mounts};
+pub use monster::{Monster, MonsterAction, MonsterType, MonsterTypes, MonsterView};
+pub use npc::{Npc, NpcView, ShopInfo, SpeechBubble};
+pub use outfit::{OutfitType, Outfits};
+pub use party::{Parties, Party, PartyEvent, PartyMember, PartyView};
+pub use player::{AccountType, FightMode, Player, Skill, Stat};
+pub use vip::{VipConfig, VipEntry, VipError, VipStatus};
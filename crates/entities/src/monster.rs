@@ -0,0 +1,1704 @@
+//! Monstertyper och monster, motsvarar `MonsterType`, `Monsters` och
+//! `Monster` i TFS. Typerna läses från data/monster/monsters.xml (eller
+//! registreras från Lua av `scripting`) och delas mellan alla monster av
+//! samma sort.
+//!
+//! Monstret fattar sina beslut här men vet inget om kartan: det frågar
+//! omvärlden genom `MonsterView` och lämnar det som ska hända i världen
+//! (spells, repliker, summons) som `MonsterAction`s till spelet.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use common::tracing::warn;
+use common::{uniform_random, CombatType, Direction, Error, MagicEffect, Position, Result, ShootType};
+use items::Items;
+
+use crate::condition::ConditionType;
+use crate::creature::{Creature, CreatureType, LightInfo, Outfit, Skull};
+
+/// Loot-chans anges i hundratusendelar
+pub const MAX_LOOTCHANCE: u32 = 100_000;
+
+/// Hur långt från målet ett flyende monster försöker komma, `Map::maxClientViewportX`
+const MAX_CLIENT_VIEWPORT_X: i32 = 8;
+
+/// Längsta räckvidd för en monster-spell, `Map::maxViewportX * 2`
+const MAX_SPELL_RANGE: u32 = 22;
+
+/// Motsvarar `RaceType_t`
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RaceType {
+    None = 0,
+    Venom = 1,
+    #[default]
+    Blood = 2,
+    Undead = 3,
+    Fire = 4,
+    Energy = 5,
+}
+
+impl RaceType {
+    /// Namn eller nummer, som `race` i monsterfilerna
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name.to_ascii_lowercase().as_str() {
+            "venom" | "1" => RaceType::Venom,
+            "blood" | "2" => RaceType::Blood,
+            "undead" | "3" => RaceType::Undead,
+            "fire" | "4" => RaceType::Fire,
+            "energy" | "5" => RaceType::Energy,
+            _ => return None,
+        })
+    }
+}
+
+/// Ett item monstret kan tappa, motsvarar `LootBlock`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LootBlock {
+    pub id: u16,
+    pub count_max: u32,
+    /// Av `MAX_LOOTCHANCE`
+    pub chance: u32,
+    /// Laddningar eller vätsketyp, -1 om inte satt
+    pub sub_type: i32,
+    pub action_id: i32,
+    pub text: String,
+    /// Innehållet om itemet är en behållare
+    pub child_loot: Vec<LootBlock>,
+}
+
+impl Default for LootBlock {
+    fn default() -> Self {
+        Self {
+            id: 0,
+            count_max: 1,
+            chance: 0,
+            sub_type: -1,
+            action_id: -1,
+            text: String::new(),
+            child_loot: Vec::new(),
+        }
+    }
+}
+
+/// Motsvarar `voiceBlock_t`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VoiceBlock {
+    pub text: String,
+    pub yell: bool,
+}
+
+/// Motsvarar `summonBlock_t`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SummonBlock {
+    pub name: String,
+    pub chance: u32,
+    /// Millisekunder mellan försöken
+    pub interval: u32,
+    /// Högst så många av den här sorten samtidigt
+    pub max: u32,
+    /// Placeras även om rutan är blockerad
+    pub force: bool,
+}
+
+/// En attack eller ett försvar, motsvarar `spellBlock_t` plus det
+/// `Monsters::deserializeSpell` läser in. Själva kastandet görs av combat
+/// utifrån fälten; `name` är "melee", en skadetyp som "fire", en condition
+/// som "poisoncondition", "speed", "outfit", ... eller en spell i spells.xml.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonsterSpell {
+    pub name: String,
+    /// Lua-script i data/monster/scripts i stället för ett namn
+    pub script: Option<String>,
+    pub chance: u32,
+    /// Millisekunder mellan försöken, `speed` i TFS
+    pub interval: u32,
+    /// 0 = ingen gräns
+    pub range: u32,
+    pub min_combat_value: i32,
+    pub max_combat_value: i32,
+    pub is_melee: bool,
+    pub attack: i32,
+    pub skill: i32,
+    pub combat_type: CombatType,
+    pub condition_type: ConditionType,
+    pub condition_min_damage: i32,
+    pub condition_max_damage: i32,
+    pub condition_start_damage: i32,
+    pub tick_interval: u32,
+    /// Hur länge speed-, outfit-, osynlighets- och drunk-conditions varar
+    pub duration: u32,
+    pub min_speed_change: i32,
+    pub max_speed_change: i32,
+    pub drunkenness: u8,
+    pub radius: u8,
+    pub length: u8,
+    pub spread: u8,
+    pub need_target: bool,
+    pub need_direction: bool,
+    pub effect: MagicEffect,
+    pub shoot_effect: ShootType,
+    /// Outfit-attacken gör om målet till ett monster eller ett item
+    pub outfit_monster: Option<String>,
+    pub outfit_item: u16,
+}
+
+impl Default for MonsterSpell {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            script: None,
+            chance: 100,
+            interval: 2000,
+            range: 0,
+            min_combat_value: 0,
+            max_combat_value: 0,
+            is_melee: false,
+            attack: 0,
+            skill: 0,
+            combat_type: CombatType::NONE,
+            condition_type: ConditionType::NONE,
+            condition_min_damage: 0,
+            condition_max_damage: 0,
+            condition_start_damage: 0,
+            tick_interval: 2000,
+            duration: 10000,
+            min_speed_change: 0,
+            max_speed_change: 0,
+            drunkenness: 25,
+            radius: 0,
+            length: 0,
+            spread: 0,
+            need_target: false,
+            need_direction: false,
+            effect: MagicEffect::None,
+            shoot_effect: ShootType::NONE,
+            outfit_monster: None,
+            outfit_item: 0,
+        }
+    }
+}
+
+impl MonsterSpell {
+    /// Melee med attack och skill i stället för min/max. Motsvarar
+    /// `Weapons::getMaxMeleeDamage`.
+    pub fn set_attack_value(&mut self, attack: i32, skill: i32) {
+        self.attack = attack;
+        self.skill = skill;
+        self.min_combat_value = 0;
+        self.max_combat_value = -max_melee_damage(skill, attack);
+    }
+
+    /// Min och max, med det största beloppet som max
+    pub fn set_combat_value(&mut self, min: i32, max: i32) {
+        self.min_combat_value = min;
+        self.max_combat_value = max;
+        if self.min_combat_value.abs() > self.max_combat_value.abs() {
+            std::mem::swap(&mut self.min_combat_value, &mut self.max_combat_value);
+        }
+    }
+
+    /// Sätt typen och det som följer av den, som i `deserializeSpell`
+    pub fn set_type(&mut self, name: &str) {
+        let name = name.to_ascii_lowercase();
+        self.is_melee = name == "melee";
+        if let Some(combat_type) = combat_type_of_spell(&name) {
+            self.combat_type = combat_type;
+        } else if let Some((condition_type, tick_interval)) = condition_of_spell(&name) {
+            self.condition_type = condition_type;
+            self.tick_interval = tick_interval;
+        } else {
+            match name.as_str() {
+                "speed" => self.condition_type = ConditionType::HASTE,
+                "outfit" => self.condition_type = ConditionType::OUTFIT,
+                "invisible" => self.condition_type = ConditionType::INVISIBLE,
+                "drunk" => self.condition_type = ConditionType::DRUNK,
+                _ => {}
+            }
+        }
+        self.name = name;
+    }
+}
+
+fn max_melee_damage(skill: i32, attack: i32) -> i32 {
+    ((skill as f64 * (attack as f64 * 0.05)) + (attack as f64 * 0.5)).ceil() as i32
+}
+
+/// Spell-namn som är en ren skadetyp
+fn combat_type_of_spell(name: &str) -> Option<CombatType> {
+    Some(match name {
+        "physical" | "bleed" => CombatType::PHYSICAL,
+        "drown" => CombatType::DROWN,
+        "fire" => CombatType::FIRE,
+        "energy" => CombatType::ENERGY,
+        "poison" | "earth" => CombatType::EARTH,
+        "ice" => CombatType::ICE,
+        "holy" => CombatType::HOLY,
+        "death" => CombatType::DEATH,
+        "lifedrain" => CombatType::LIFEDRAIN,
+        "manadrain" => CombatType::MANADRAIN,
+        "healing" => CombatType::HEALING,
+        _ => return None,
+    })
+}
+
+/// "poisoncondition", "firecondition", ... och tick-intervallet för dem
+fn condition_of_spell(name: &str) -> Option<(ConditionType, u32)> {
+    Some(match name.strip_suffix("condition")? {
+        "poison" | "earth" => (ConditionType::POISON, 4000),
+        "fire" => (ConditionType::FIRE, 9000),
+        "energy" => (ConditionType::ENERGY, 10000),
+        "drown" => (ConditionType::DROWN, 5000),
+        "freeze" => (ConditionType::FREEZING, 8000),
+        "cursed" | "curse" => (ConditionType::CURSED, 4000),
+        "dazzle" => (ConditionType::DAZZLED, 10000),
+        "physical" | "bleed" => (ConditionType::BLEEDING, 4000),
+        _ => return None,
+    })
+}
+
+/// Immuniteten som den skrivs i `<immunity>` eller `combatImmunities`, som
+/// skade- och condition-bitar
+fn immunity_of_name(name: &str) -> Option<(CombatType, ConditionType)> {
+    Some(match name.to_ascii_lowercase().as_str() {
+        "physical" => (CombatType::PHYSICAL, ConditionType::BLEEDING),
+        "energy" => (CombatType::ENERGY, ConditionType::ENERGY),
+        "fire" => (CombatType::FIRE, ConditionType::FIRE),
+        "poison" | "earth" => (CombatType::EARTH, ConditionType::POISON),
+        "drown" => (CombatType::DROWN, ConditionType::DROWN),
+        "ice" => (CombatType::ICE, ConditionType::FREEZING),
+        "holy" => (CombatType::HOLY, ConditionType::DAZZLED),
+        "death" => (CombatType::DEATH, ConditionType::CURSED),
+        "lifedrain" => (CombatType::LIFEDRAIN, ConditionType::NONE),
+        "manadrain" => (CombatType::MANADRAIN, ConditionType::NONE),
+        "paralyze" => (CombatType::NONE, ConditionType::PARALYZE),
+        "outfit" => (CombatType::NONE, ConditionType::OUTFIT),
+        "drunk" => (CombatType::NONE, ConditionType::DRUNK),
+        "invisible" | "invisibility" => (CombatType::NONE, ConditionType::INVISIBLE),
+        "bleed" => (CombatType::NONE, ConditionType::BLEEDING),
+        _ => return None,
+    })
+}
+
+/// Motsvarar `MonsterType` med `MonsterInfo`
+#[derive(Debug, Clone)]
+pub struct MonsterType {
+    pub name: String,
+    pub name_description: String,
+
+    pub experience: u64,
+    pub outfit: Outfit,
+    pub corpse: u16,
+    pub race: RaceType,
+    pub base_speed: u32,
+    pub health: i32,
+    pub health_max: i32,
+    pub mana_cost: u32,
+    pub skull: Skull,
+    pub light: LightInfo,
+    pub armor: i32,
+    pub defense: i32,
+
+    pub max_summons: u32,
+    /// Hur ofta (ms) monstret funderar på att byta mål, och chansen att det gör det
+    pub change_target_speed: u32,
+    pub change_target_chance: u32,
+    /// 1 = går fram till målet, större = håller avstånd
+    pub target_distance: i32,
+    /// Flyr när hälsan är så här låg
+    pub run_away_health: i32,
+    /// Chansen att stå still och slå i stället för att dansa runt målet
+    pub static_attack_chance: u32,
+    pub yell_speed_ticks: u32,
+    pub yell_chance: u32,
+
+    pub is_summonable: bool,
+    pub is_illusionable: bool,
+    pub is_convinceable: bool,
+    pub is_attackable: bool,
+    pub is_hostile: bool,
+    pub is_pushable: bool,
+    pub is_boss: bool,
+    pub is_challengeable: bool,
+    pub ignore_spawn_block: bool,
+    pub can_push_items: bool,
+    pub can_push_creatures: bool,
+    pub can_walk_on_energy: bool,
+    pub can_walk_on_fire: bool,
+    pub can_walk_on_poison: bool,
+    pub hidden_health: bool,
+
+    pub attack_spells: Vec<MonsterSpell>,
+    pub defense_spells: Vec<MonsterSpell>,
+    pub summons: Vec<SummonBlock>,
+    pub voices: Vec<VoiceBlock>,
+    pub loot: Vec<LootBlock>,
+    /// Skadetyp -> procent mindre skada (mer, om negativ), `<element>`
+    pub element_map: BTreeMap<CombatType, i32>,
+    pub damage_immunities: CombatType,
+    pub condition_immunities: ConditionType,
+    /// Creature events som registreras på varje monster av typen
+    pub scripts: Vec<String>,
+}
+
+impl MonsterType {
+    /// Standardvärdena i `MonsterInfo`
+    pub fn new(name: impl Into<String>) -> Self {
+        let name = name.into();
+        Self {
+            name_description: format!("a {}", name.to_lowercase()),
+            name,
+            experience: 0,
+            outfit: Outfit::default(),
+            corpse: 0,
+            race: RaceType::Blood,
+            base_speed: 200,
+            health: 100,
+            health_max: 100,
+            mana_cost: 0,
+            skull: Skull::None,
+            light: LightInfo::default(),
+            armor: 0,
+            defense: 0,
+            max_summons: 0,
+            change_target_speed: 0,
+            change_target_chance: 0,
+            target_distance: 1,
+            run_away_health: 0,
+            static_attack_chance: 95,
+            yell_speed_ticks: 0,
+            yell_chance: 0,
+            is_summonable: false,
+            is_illusionable: false,
+            is_convinceable: false,
+            is_attackable: true,
+            is_hostile: true,
+            is_pushable: true,
+            is_boss: false,
+            is_challengeable: true,
+            ignore_spawn_block: false,
+            can_push_items: false,
+            can_push_creatures: false,
+            can_walk_on_energy: true,
+            can_walk_on_fire: true,
+            can_walk_on_poison: true,
+            hidden_health: false,
+            attack_spells: Vec::new(),
+            defense_spells: Vec::new(),
+            summons: Vec::new(),
+            voices: Vec::new(),
+            loot: Vec::new(),
+            element_map: BTreeMap::new(),
+            damage_immunities: CombatType::NONE,
+            condition_immunities: ConditionType::NONE,
+            scripts: Vec::new(),
+        }
+    }
+
+    pub fn is_immune_to_combat(&self, combat_type: CombatType) -> bool {
+        self.damage_immunities.intersects(combat_type)
+    }
+
+    pub fn is_immune_to_condition(&self, condition_type: ConditionType) -> bool {
+        self.condition_immunities.intersects(condition_type)
+    }
+
+    /// Procent extra skada av en typ, 0 om typen saknas
+    pub fn element_percent(&self, combat_type: CombatType) -> i32 {
+        self.element_map.get(&combat_type).copied().unwrap_or(0)
+    }
+
+    /// Immun mot både skadan och conditionen med namnet, som `<immunity>`
+    pub fn add_immunity(&mut self, name: &str) -> bool {
+        let Some((combat, condition)) = immunity_of_name(name) else {
+            return false;
+        };
+        self.damage_immunities = self.damage_immunities | combat;
+        self.condition_immunities = self.condition_immunities | condition;
+        true
+    }
+
+    /// Bara skadan, som `mType:combatImmunities(name)`
+    pub fn add_combat_immunity(&mut self, name: &str) -> bool {
+        match immunity_of_name(name) {
+            Some((combat, _)) if combat != CombatType::NONE => {
+                self.damage_immunities = self.damage_immunities | combat;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Bara conditionen, som `mType:conditionImmunities(name)`
+    pub fn add_condition_immunity(&mut self, name: &str) -> bool {
+        match immunity_of_name(name) {
+            Some((_, condition)) if condition != ConditionType::NONE => {
+                self.condition_immunities = self.condition_immunities | condition;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Läs en monsterfil. Motsvarar `Monsters::loadMonster`.
+    pub fn load_from_xml(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| Error::World(format!("Cannot open {}: {e}", path.display())))?;
+        let doc = roxmltree::Document::parse(&text)
+            .map_err(|e| Error::World(format!("Cannot parse {}: {e}", path.display())))?;
+        let root = doc.root_element();
+        if !root.has_tag_name("monster") {
+            return Err(Error::World(format!("Missing monster node in {}", path.display())));
+        }
+        let Some(name) = attr_str(&root, "name") else {
+            return Err(Error::World(format!("Missing name in {}", path.display())));
+        };
+
+        let mut mtype = MonsterType::new(name);
+        if let Some(description) = attr_str(&root, "nameDescription") {
+            mtype.name_description = description.to_string();
+        }
+        if let Some(race) = attr_str(&root, "race") {
+            match RaceType::from_name(race) {
+                Some(race) => mtype.race = race,
+                None => warn!("[Monsters::loadMonster] Unknown race type {race}. {}", path.display()),
+            }
+        }
+        if let Some(experience) = attr(&root, "experience") {
+            mtype.experience = experience;
+        }
+        if let Some(speed) = attr(&root, "speed") {
+            mtype.base_speed = speed;
+        }
+        if let Some(mana_cost) = attr(&root, "manacost") {
+            mtype.mana_cost = mana_cost;
+        }
+        if let Some(skull) = attr_str(&root, "skull") {
+            mtype.skull = Skull::from_name(skull).unwrap_or_default();
+        }
+
+        for node in root.children().filter(|n| n.is_element()) {
+            match node.tag_name().name().to_ascii_lowercase().as_str() {
+                "health" => {
+                    mtype.health = attr(&node, "now").unwrap_or(100);
+                    mtype.health_max = attr(&node, "max").unwrap_or(100);
+                    if mtype.health > mtype.health_max {
+                        mtype.health = mtype.health_max;
+                        warn!("[Monsters::loadMonster] Health now is greater than health max. {}", path.display());
+                    }
+                }
+                "flags" => mtype.load_flags(&node, path),
+                "targetchange" => {
+                    if let Some(speed) = attr(&node, "speed").or_else(|| attr(&node, "interval")) {
+                        mtype.change_target_speed = speed;
+                    }
+                    if let Some(chance) = attr(&node, "chance") {
+                        mtype.change_target_chance = chance;
+                    }
+                }
+                "look" => {
+                    if let Some(look_type) = attr(&node, "type") {
+                        mtype.outfit.look_type = look_type;
+                        mtype.outfit.look_head = attr(&node, "head").unwrap_or(0);
+                        mtype.outfit.look_body = attr(&node, "body").unwrap_or(0);
+                        mtype.outfit.look_legs = attr(&node, "legs").unwrap_or(0);
+                        mtype.outfit.look_feet = attr(&node, "feet").unwrap_or(0);
+                        mtype.outfit.look_addons = attr(&node, "addons").unwrap_or(0);
+                    } else if let Some(look_type_ex) = attr(&node, "typeex") {
+                        mtype.outfit.look_type_ex = look_type_ex;
+                    }
+                    if let Some(mount) = attr(&node, "mount") {
+                        mtype.outfit.look_mount = mount;
+                    }
+                    if let Some(corpse) = attr(&node, "corpse") {
+                        mtype.corpse = corpse;
+                    }
+                }
+                "attacks" => {
+                    for spell in node.children().filter(|n| n.has_tag_name("attack")) {
+                        match deserialize_spell(&spell, path) {
+                            Some(spell) => mtype.attack_spells.push(spell),
+                            None => warn!("[Monsters::loadMonster] Cannot load spell. {}", path.display()),
+                        }
+                    }
+                }
+                "defenses" => {
+                    if let Some(defense) = attr(&node, "defense") {
+                        mtype.defense = defense;
+                    }
+                    if let Some(armor) = attr(&node, "armor") {
+                        mtype.armor = armor;
+                    }
+                    for spell in node.children().filter(|n| n.has_tag_name("defense")) {
+                        match deserialize_spell(&spell, path) {
+                            Some(spell) => mtype.defense_spells.push(spell),
+                            None => warn!("[Monsters::loadMonster] Cannot load spell. {}", path.display()),
+                        }
+                    }
+                }
+                "immunities" => mtype.load_immunities(&node, path),
+                "voices" => {
+                    if let Some(speed) = attr(&node, "speed").or_else(|| attr(&node, "interval")) {
+                        mtype.yell_speed_ticks = speed;
+                    }
+                    if let Some(chance) = attr(&node, "chance") {
+                        mtype.yell_chance = chance;
+                    }
+                    for voice in node.children().filter(|n| n.has_tag_name("voice")) {
+                        let Some(sentence) = attr_str(&voice, "sentence") else {
+                            warn!("[Monsters::loadMonster] Missing voice sentence. {}", path.display());
+                            continue;
+                        };
+                        mtype.voices.push(VoiceBlock {
+                            text: sentence.to_string(),
+                            yell: attr_str(&voice, "yell").is_some_and(boolean_string),
+                        });
+                    }
+                }
+                "loot" => {
+                    for item in node.children().filter(|n| n.has_tag_name("item")) {
+                        match load_loot_item(&item, path) {
+                            Some(loot) => mtype.loot.push(loot),
+                            None => warn!("[Monsters::loadMonster] Cannot load loot. {}", path.display()),
+                        }
+                    }
+                }
+                "elements" => {
+                    for element in node.children().filter(|n| n.has_tag_name("element")) {
+                        for attribute in element.attributes() {
+                            let key = attribute.name().to_ascii_lowercase();
+                            let combat_type = key.strip_suffix("percent").and_then(CombatType::from_name);
+                            match (combat_type, attribute.value().parse()) {
+                                (Some(combat_type), Ok(percent)) => {
+                                    mtype.element_map.insert(combat_type, percent);
+                                }
+                                _ => warn!(
+                                    "[Monsters::loadMonster] Unknown element {}. {}",
+                                    attribute.name(),
+                                    path.display()
+                                ),
+                            }
+                        }
+                    }
+                }
+                "summons" => {
+                    if let Some(max_summons) = attr::<u32>(&node, "maxSummons").or_else(|| attr(&node, "maxsummons")) {
+                        mtype.max_summons = max_summons;
+                    }
+                    for summon in node.children().filter(|n| n.has_tag_name("summon")) {
+                        let Some(name) = attr_str(&summon, "name") else {
+                            warn!("[Monsters::loadMonster] Missing summon name. {}", path.display());
+                            continue;
+                        };
+                        mtype.summons.push(SummonBlock {
+                            name: name.to_string(),
+                            chance: attr(&summon, "chance").unwrap_or(100u32).min(100),
+                            interval: attr(&summon, "speed").or_else(|| attr(&summon, "interval")).unwrap_or(1000u32).max(1),
+                            max: attr(&summon, "max").unwrap_or(mtype.max_summons),
+                            force: attr_str(&summon, "force").is_some_and(boolean_string),
+                        });
+                    }
+                }
+                "script" => {
+                    for event in node.children().filter(|n| n.has_tag_name("event")) {
+                        match attr_str(&event, "name") {
+                            Some(name) => mtype.scripts.push(name.to_string()),
+                            None => warn!("[Monsters::loadMonster] Missing name for script event. {}", path.display()),
+                        }
+                    }
+                }
+                other => warn!("[Monsters::loadMonster] Unknown monster node {other}. {}", path.display()),
+            }
+        }
+        Ok(mtype)
+    }
+
+    fn load_flags(&mut self, node: &roxmltree::Node, path: &Path) {
+        for flag in node.children().filter(|n| n.has_tag_name("flag")) {
+            let Some(attribute) = flag.attributes().next() else {
+                continue;
+            };
+            let value = attribute.value();
+            let as_bool = boolean_string(value);
+            let as_int = value.parse::<i32>().unwrap_or(0);
+            match attribute.name().to_ascii_lowercase().as_str() {
+                "summonable" => self.is_summonable = as_bool,
+                "attackable" => self.is_attackable = as_bool,
+                "hostile" => self.is_hostile = as_bool,
+                "ignorespawnblock" => self.ignore_spawn_block = as_bool,
+                "illusionable" => self.is_illusionable = as_bool,
+                "challengeable" => self.is_challengeable = as_bool,
+                "convinceable" => self.is_convinceable = as_bool,
+                "pushable" => self.is_pushable = as_bool,
+                "isboss" => self.is_boss = as_bool,
+                "canpushitems" => self.can_push_items = as_bool,
+                "canpushcreatures" => self.can_push_creatures = as_bool,
+                "staticattack" => {
+                    if !(0..=100).contains(&as_int) {
+                        warn!("[Monsters::loadMonster] Invalid staticattack value. {}", path.display());
+                    }
+                    self.static_attack_chance = as_int.clamp(0, 100) as u32;
+                }
+                "lightlevel" => self.light.level = as_int as u8,
+                "lightcolor" => self.light.color = as_int as u8,
+                "targetdistance" => self.target_distance = as_int.max(1),
+                "runonhealth" => self.run_away_health = as_int,
+                "hidehealth" => self.hidden_health = as_bool,
+                "canwalkonenergy" => self.can_walk_on_energy = as_bool,
+                "canwalkonfire" => self.can_walk_on_fire = as_bool,
+                "canwalkonpoison" => self.can_walk_on_poison = as_bool,
+                other => warn!("[Monsters::loadMonster] Unknown flag attribute {other}. {}", path.display()),
+            }
+        }
+
+        // ett monster som kan knuffa andra kan inte själv knuffas
+        if self.can_push_creatures {
+            self.is_pushable = false;
+        }
+    }
+
+    fn load_immunities(&mut self, node: &roxmltree::Node, path: &Path) {
+        for immunity in node.children().filter(|n| n.has_tag_name("immunity")) {
+            if let Some(name) = attr_str(&immunity, "name") {
+                if !self.add_immunity(name) {
+                    warn!("[Monsters::loadMonster] Unknown immunity name {name}. {}", path.display());
+                }
+                continue;
+            }
+            for attribute in immunity.attributes() {
+                if !boolean_string(attribute.value()) {
+                    continue;
+                }
+                if !self.add_immunity(attribute.name()) {
+                    warn!("[Monsters::loadMonster] Unknown immunity {}. {}", attribute.name(), path.display());
+                }
+            }
+        }
+    }
+}
+
+/// Motsvarar `Monsters::deserializeSpell` för XML
+fn deserialize_spell(node: &roxmltree::Node, path: &Path) -> Option<MonsterSpell> {
+    let mut spell = MonsterSpell::default();
+    if let Some(name) = attr_str(node, "name") {
+        spell.set_type(name);
+    } else if let Some(script) = attr_str(node, "script") {
+        spell.script = Some(script.to_string());
+    } else {
+        return None;
+    }
+
+    if let Some(interval) = attr::<u32>(node, "speed").or_else(|| attr(node, "interval")) {
+        spell.interval = interval.max(1);
+    }
+    if let Some(chance) = attr::<u32>(node, "chance") {
+        if chance > 100 {
+            warn!("[Monsters::deserializeSpell] {} - Chance value out of bounds. {}", spell.name, path.display());
+        }
+        spell.chance = chance.min(100);
+    }
+    if let Some(range) = attr::<u32>(node, "range") {
+        spell.range = range.min(MAX_SPELL_RANGE);
+    }
+    let min = attr(node, "min").unwrap_or(0);
+    let max = attr(node, "max").unwrap_or(0);
+    spell.set_combat_value(min, max);
+    spell.need_target = attr_str(node, "target").is_some_and(boolean_string);
+    spell.need_direction = attr_str(node, "direction").is_some_and(boolean_string);
+    if let Some(radius) = attr(node, "radius") {
+        spell.radius = radius;
+    }
+    if let Some(length) = attr::<u8>(node, "length") {
+        spell.length = length;
+        if length > 0 {
+            spell.spread = 3;
+        }
+    }
+    if let Some(spread) = attr(node, "spread") {
+        spell.spread = spread;
+    }
+
+    if spell.is_melee {
+        if let (Some(attack), Some(skill)) = (attr(node, "attack"), attr(node, "skill")) {
+            spell.set_attack_value(attack, skill);
+        }
+        // skada över tid från ett vanligt slag, t.ex. `poison="5"`
+        let melee_conditions: [(&str, ConditionType, u32); 9] = [
+            ("fire", ConditionType::FIRE, 9000),
+            ("poison", ConditionType::POISON, 4000),
+            ("earth", ConditionType::POISON, 4000),
+            ("energy", ConditionType::ENERGY, 10000),
+            ("drown", ConditionType::DROWN, 5000),
+            ("freeze", ConditionType::FREEZING, 8000),
+            ("dazzle", ConditionType::DAZZLED, 10000),
+            ("curse", ConditionType::CURSED, 4000),
+            ("bleed", ConditionType::BLEEDING, 4000),
+        ];
+        for (key, condition_type, tick_interval) in melee_conditions {
+            if let Some(damage) = attr::<i32>(node, key) {
+                spell.condition_type = condition_type;
+                spell.condition_min_damage = damage.abs();
+                spell.condition_max_damage = damage.abs();
+                spell.tick_interval = tick_interval;
+                break;
+            }
+        }
+        if let Some(tick_interval) = attr(node, "tick") {
+            spell.tick_interval = tick_interval;
+        }
+    } else if condition_of_spell(&spell.name).is_some() {
+        // min/max är skadan per tick, inte en direkt skada
+        spell.condition_min_damage = spell.min_combat_value.abs();
+        spell.condition_max_damage = spell.max_combat_value.abs();
+        spell.condition_start_damage = attr::<i32>(node, "start").unwrap_or(0).abs();
+        spell.min_combat_value = 0;
+        spell.max_combat_value = 0;
+        if let Some(tick_interval) = attr(node, "tick") {
+            spell.tick_interval = tick_interval;
+        }
+    } else {
+        match spell.name.as_str() {
+            "speed" => {
+                let speed_change = attr::<i32>(node, "speedchange").unwrap_or(0).clamp(-1000, 1000);
+                spell.min_speed_change = speed_change;
+                spell.max_speed_change = speed_change;
+                if speed_change < 0 {
+                    spell.condition_type = ConditionType::PARALYZE;
+                }
+            }
+            "outfit" => {
+                spell.outfit_monster = attr_str(node, "monster").map(str::to_string);
+                spell.outfit_item = attr(node, "item").unwrap_or(0);
+            }
+            "drunk" => {
+                if let Some(drunkenness) = attr(node, "drunkenness") {
+                    spell.drunkenness = drunkenness;
+                }
+            }
+            _ => {}
+        }
+    }
+    if let Some(duration) = attr(node, "duration") {
+        spell.duration = duration;
+    }
+
+    for attribute in node.children().filter(|n| n.has_tag_name("attribute")) {
+        let (Some(key), Some(value)) = (attr_str(&attribute, "key"), attr_str(&attribute, "value")) else {
+            continue;
+        };
+        match key.to_ascii_lowercase().as_str() {
+            "shooteffect" => match ShootType::from_name(value) {
+                Some(shoot) => spell.shoot_effect = shoot,
+                None => warn!("[Monsters::deserializeSpell] Unknown shootEffect {value}. {}", path.display()),
+            },
+            "areaeffect" => match MagicEffect::from_name(value) {
+                Some(effect) => spell.effect = effect,
+                None => warn!("[Monsters::deserializeSpell] Unknown areaEffect {value}. {}", path.display()),
+            },
+            other => warn!("[Monsters::deserializeSpell] Effect type {other} does not exist. {}", path.display()),
+        }
+    }
+    Some(spell)
+}
+
+/// Motsvarar `Monsters::loadLootItem`; namn slås upp bland items
+fn load_loot_item(node: &roxmltree::Node, path: &Path) -> Option<LootBlock> {
+    let mut loot = LootBlock::default();
+    if let Some(id) = attr(node, "id") {
+        loot.id = id;
+    } else if let Some(name) = attr_str(node, "name") {
+        let Some(id) = Items::get_id_by_name(name) else {
+            warn!("[Monsters::loadLootItem] Unknown loot item {name}. {}", path.display());
+            return None;
+        };
+        loot.id = id;
+    }
+    if loot.id == 0 {
+        return None;
+    }
+
+    loot.count_max = attr(node, "countmax").unwrap_or(1u32).max(1);
+    if let Some(chance) = attr::<u32>(node, "chance").or_else(|| attr(node, "chance1")) {
+        if chance > MAX_LOOTCHANCE {
+            warn!("[Monsters::loadLootItem] Invalid chance for loot item {}. {}", loot.id, path.display());
+        }
+        loot.chance = chance.min(MAX_LOOTCHANCE);
+    } else {
+        loot.chance = MAX_LOOTCHANCE;
+    }
+    if let Some(sub_type) = attr(node, "subtype") {
+        loot.sub_type = sub_type;
+    }
+    if let Some(action_id) = attr(node, "actionId").or_else(|| attr(node, "actionid")) {
+        loot.action_id = action_id;
+    }
+    if let Some(text) = attr_str(node, "text") {
+        loot.text = text.to_string();
+    }
+
+    // innehåll i behållare, direkt under itemet eller i <inside>
+    let children = node.children().filter(|n| n.has_tag_name("item")).chain(
+        node.children()
+            .filter(|n| n.has_tag_name("inside"))
+            .flat_map(|inside| inside.children().filter(|n| n.has_tag_name("item"))),
+    );
+    for child in children {
+        if let Some(child) = load_loot_item(&child, path) {
+            loot.child_loot.push(child);
+        }
+    }
+    Some(loot)
+}
+
+/// Attribut oavsett skiftläge; monsterfilerna är inte konsekventa
+/// (`nameDescription`/`namedescription`, `areaEffect`/`areaeffect`)
+pub(crate) fn attr_str<'a>(node: &roxmltree::Node<'a, '_>, name: &str) -> Option<&'a str> {
+    node.attributes()
+        .find(|a| a.name().eq_ignore_ascii_case(name))
+        .map(|a| a.value())
+}
+
+pub(crate) fn attr<T: std::str::FromStr>(node: &roxmltree::Node, name: &str) -> Option<T> {
+    attr_str(node, name).and_then(|v| v.trim().parse().ok())
+}
+
+/// Motsvarar `booleanString`: allt utom "0", "false", "no" och tomt är sant
+pub(crate) fn boolean_string(value: &str) -> bool {
+    !matches!(value.chars().next(), None | Some('0' | 'f' | 'F' | 'n' | 'N'))
+}
+
+/// Alla monstertyper, motsvarar `Monsters`. Utan `forceMonsterTypesOnLoad`
+/// läses en typ först när den behövs.
+#[derive(Debug, Default)]
+pub struct MonsterTypes {
+    /// Nyckeln är namnet i gemener
+    types: HashMap<String, Arc<MonsterType>>,
+    unloaded: HashMap<String, PathBuf>,
+}
+
+impl MonsterTypes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Läs monsters.xml. Filerna ligger relativt till den. Motsvarar
+    /// `Monsters::loadFromXml`.
+    pub fn load_from_xml(&mut self, path: impl AsRef<Path>, force_load: bool) -> Result<usize> {
+        let path = path.as_ref();
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| Error::World(format!("Cannot open {}: {e}", path.display())))?;
+        let doc = roxmltree::Document::parse(&text)
+            .map_err(|e| Error::World(format!("Cannot parse {}: {e}", path.display())))?;
+
+        let mut count = 0;
+        for node in doc.root_element().children().filter(|n| n.has_tag_name("monster")) {
+            let (Some(name), Some(file)) = (node.attribute("name"), node.attribute("file")) else {
+                warn!("[Monsters::loadFromXml] Missing name or file for monster in {}", path.display());
+                continue;
+            };
+            let key = name.to_lowercase();
+            if self.types.contains_key(&key) || self.unloaded.contains_key(&key) {
+                warn!("[Monsters::loadFromXml] Duplicate monster with name {name}");
+                continue;
+            }
+
+            let file = dir.join(file);
+            if force_load {
+                match MonsterType::load_from_xml(&file) {
+                    Ok(mtype) => {
+                        self.types.insert(key, Arc::new(mtype));
+                    }
+                    Err(e) => {
+                        warn!("[Monsters::loadFromXml] {e}");
+                        continue;
+                    }
+                }
+            } else {
+                self.unloaded.insert(key, file);
+            }
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Lägg till eller ersätt en typ, t.ex. en som registrerats från Lua
+    pub fn add_monster_type(&mut self, mtype: MonsterType) -> Arc<MonsterType> {
+        let key = mtype.name.to_lowercase();
+        self.unloaded.remove(&key);
+        let mtype = Arc::new(mtype);
+        self.types.insert(key, mtype.clone());
+        mtype
+    }
+
+    /// Hämta en typ och läs in den om den inte är det än. Motsvarar
+    /// `Monsters::getMonsterType`.
+    pub fn get_monster_type(&mut self, name: &str) -> Option<Arc<MonsterType>> {
+        let key = name.to_lowercase();
+        if let Some(mtype) = self.types.get(&key) {
+            return Some(mtype.clone());
+        }
+        let file = self.unloaded.remove(&key)?;
+        match MonsterType::load_from_xml(&file) {
+            Ok(mtype) => {
+                let mtype = Arc::new(mtype);
+                self.types.insert(key, mtype.clone());
+                Some(mtype)
+            }
+            Err(e) => {
+                warn!("[Monsters::getMonsterType] {e}");
+                None
+            }
+        }
+    }
+
+    /// Bara typer som redan är inlästa
+    pub fn get_loaded(&self, name: &str) -> Option<&Arc<MonsterType>> {
+        self.types.get(&name.to_lowercase())
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        let key = name.to_lowercase();
+        self.types.contains_key(&key) || self.unloaded.contains_key(&key)
+    }
+
+    /// Inlästa och olästa typer
+    pub fn len(&self) -> usize {
+        self.types.len() + self.unloaded.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn loaded_count(&self) -> usize {
+        self.types.len()
+    }
+}
+
+/// Hur monstret letar mål, motsvarar `TargetSearchType_t`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TargetSearchType {
+    /// Ett slumpat mål monstret kan anfalla härifrån, annars det första
+    #[default]
+    Default,
+    /// Vilket mål som helst, även utom räckhåll
+    Random,
+    /// Bara mål inom räckhåll
+    AttackRange,
+    /// Det närmaste målet
+    Nearest,
+}
+
+/// Vägsökningen mot (eller från) den monstret följer. Spelet gör om den
+/// till `FindPathParams`. Motsvarar `Monster::getPathSearchParams`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FollowParams {
+    pub full_path_search: bool,
+    pub clear_sight: bool,
+    pub keep_distance: bool,
+    pub max_search_dist: i32,
+    pub min_target_dist: i32,
+    pub max_target_dist: i32,
+}
+
+/// Det monstret behöver veta om omvärlden. Spelet implementerar den med
+/// världen och sina varelser.
+pub trait MonsterView {
+    fn position_of(&self, id: u32) -> Option<Position>;
+
+    /// Kan monstret anfalla varelsen: finns, går att anfalla, syns, står
+    /// utanför skyddszon och på samma våning. Motsvarar `Monster::isTarget`.
+    fn is_target(&self, monster: &Monster, id: u32) -> bool;
+
+    fn is_sight_clear(&self, from: Position, to: Position) -> bool;
+
+    /// Kan monstret ta ett steg till `pos`: inom spawnen, ingen synlig
+    /// varelse och ingen ruta som vägrar. Motsvarar `Monster::canWalkTo`.
+    fn can_walk_to(&self, monster: &Monster, pos: Position) -> bool;
+
+    /// Vem varelsen anfaller, för summons som hjälper sin master
+    fn attacked_creature_of(&self, id: u32) -> Option<u32>;
+
+    fn creature_name(&self, id: u32) -> Option<&str>;
+}
+
+/// Det monstret vill göra i världen
+#[derive(Debug, Clone, PartialEq)]
+pub enum MonsterAction {
+    /// Kasta `attack_spells[index]` (eller `defense_spells[index]`) mot `target`
+    CastSpell { defensive: bool, index: usize, target: u32 },
+    Say { text: String, yell: bool },
+    Summon { name: String, force: bool },
+    /// Nytt mål; spelet startar attack-eventet (`checkCreatureAttack`)
+    AttackTarget(u32),
+    /// Monstret vänder sig, t.ex. mot målet innan det slår
+    Turn(Direction),
+    /// Vägen till den monstret följer ska räknas om
+    UpdateFollowPath,
+    /// Inaktiva monster tas ur creature checks, aktiva läggs tillbaka
+    IdleChanged(bool),
+    /// Inga mål kvar; gå tillbaka mot spawnen om monstret gått för långt
+    WalkToSpawn,
+}
+
+/// Ett monster i världen, motsvarar `Monster`
+#[derive(Debug, Clone)]
+pub struct Monster {
+    pub creature: Creature,
+    pub mtype: Arc<MonsterType>,
+
+    target_list: Vec<u32>,
+    friend_list: Vec<u32>,
+    is_idle: bool,
+    extra_melee_attack: bool,
+    random_stepping: bool,
+    /// Summons går bara mot mastern när den är inom synhåll
+    pub is_master_in_range: bool,
+
+    target_change_ticks: u32,
+    target_change_cooldown: i32,
+    attack_ticks: u32,
+    defense_ticks: u32,
+    yell_ticks: u32,
+    last_melee_attack: u64,
+}
+
+impl Monster {
+    /// Ett nytt monster av en typ, som `Monster::Monster`. Typens creature
+    /// events registreras av den som äger `CreatureEvents`.
+    pub fn new(mtype: Arc<MonsterType>) -> Self {
+        let mut creature = Creature::new(CreatureType::Monster, mtype.name.clone());
+        creature.health = mtype.health;
+        creature.health_max = mtype.health_max;
+        creature.base_speed = mtype.base_speed;
+        creature.outfit = mtype.outfit;
+        creature.default_outfit = mtype.outfit;
+        creature.skull = mtype.skull;
+        creature.internal_light = mtype.light;
+        creature.hidden_health = mtype.hidden_health;
+
+        Self {
+            creature,
+            mtype,
+            target_list: Vec::new(),
+            friend_list: Vec::new(),
+            is_idle: true,
+            extra_melee_attack: false,
+            random_stepping: false,
+            is_master_in_range: false,
+            target_change_ticks: 0,
+            target_change_cooldown: 0,
+            attack_ticks: 0,
+            defense_ticks: 0,
+            yell_ticks: 0,
+            last_melee_attack: 0,
+        }
+    }
+
+    pub fn id(&self) -> u32 {
+        self.creature.id
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.is_idle
+    }
+
+    pub fn is_random_stepping(&self) -> bool {
+        self.random_stepping
+    }
+
+    pub fn target_list(&self) -> &[u32] {
+        &self.target_list
+    }
+
+    pub fn friend_list(&self) -> &[u32] {
+        &self.friend_list
+    }
+
+    /// Flyr monstret? Motsvarar `Monster::isFleeing`.
+    pub fn is_fleeing(&self) -> bool {
+        !self.creature.is_summon() && self.creature.health <= self.mtype.run_away_health
+    }
+
+    // === Mål och vänner ===
+
+    pub fn add_target(&mut self, id: u32, push_front: bool) {
+        if self.target_list.contains(&id) {
+            return;
+        }
+        if push_front {
+            self.target_list.insert(0, id);
+        } else {
+            self.target_list.push(id);
+        }
+    }
+
+    pub fn remove_target(&mut self, id: u32) {
+        self.target_list.retain(|&t| t != id);
+    }
+
+    pub fn add_friend(&mut self, id: u32) {
+        if !self.friend_list.contains(&id) {
+            self.friend_list.push(id);
+        }
+    }
+
+    pub fn remove_friend(&mut self, id: u32) {
+        self.friend_list.retain(|&f| f != id);
+    }
+
+    /// En varelse har kommit inom synhåll. Spelet avgör om den är en
+    /// motståndare (spelare, spelares summons) eller en vän (andra monster).
+    /// Motsvarar `Monster::onCreatureFound`.
+    pub fn on_creature_found(&mut self, id: u32, is_opponent: bool, is_friend: bool) -> Vec<MonsterAction> {
+        let mut actions = Vec::new();
+        if id == self.id() {
+            return actions;
+        }
+        if is_friend {
+            self.add_friend(id);
+        }
+        if is_opponent {
+            self.add_target(id, false);
+        }
+        self.update_idle_status(&mut actions);
+        actions
+    }
+
+    /// En varelse har försvunnit ur sikte eller ur spelet. Motsvarar
+    /// `Monster::onCreatureLeave` och `Creature::onCreatureDisappear`.
+    pub fn on_creature_leave(&mut self, id: u32) -> Vec<MonsterAction> {
+        let mut actions = Vec::new();
+        if self.creature.attacked_creature == Some(id) {
+            self.creature.set_attacked_creature(None);
+            self.attack_ticks = 0;
+            self.extra_melee_attack = true;
+        }
+        if self.creature.follow_creature == Some(id) {
+            self.creature.set_follow_creature(None);
+        }
+
+        self.remove_friend(id);
+        if self.target_list.contains(&id) {
+            self.remove_target(id);
+            self.update_idle_status(&mut actions);
+            if !self.creature.is_summon() && self.target_list.is_empty() {
+                actions.push(MonsterAction::WalkToSpawn);
+            }
+        }
+        actions
+    }
+
+    /// Motsvarar `Monster::updateIdleStatus`: utan mål och utan aggressiva
+    /// conditions finns inget att göra
+    fn update_idle_status(&mut self, actions: &mut Vec<MonsterAction>) {
+        let idle = !self.creature.is_summon()
+            && self.target_list.is_empty()
+            && !self.creature.conditions().iter().any(|c| c.is_aggressive());
+        self.set_idle(idle, actions);
+    }
+
+    fn set_idle(&mut self, idle: bool, actions: &mut Vec<MonsterAction>) {
+        if self.creature.is_dead() || self.is_idle == idle {
+            return;
+        }
+        self.is_idle = idle;
+        if idle {
+            self.target_list.clear();
+            self.friend_list.clear();
+        }
+        actions.push(MonsterAction::IdleChanged(idle));
+    }
+
+    /// Motsvarar `Monster::searchTarget`
+    pub fn search_target(&mut self, view: &dyn MonsterView, search_type: TargetSearchType, actions: &mut Vec<MonsterAction>) -> bool {
+        let pos = self.creature.position;
+        let follow = self.creature.follow_creature;
+        let result_list: Vec<u32> = self
+            .target_list
+            .iter()
+            .copied()
+            .filter(|&id| Some(id) != follow && view.is_target(self, id))
+            .filter(|&id| search_type == TargetSearchType::Random || self.can_use_attack(view, pos, id))
+            .collect();
+
+        let distance_to = |id: u32| {
+            view.position_of(id)
+                .map(|p| Position::get_distance_x(&pos, &p) + Position::get_distance_y(&pos, &p))
+                .unwrap_or(i32::MAX)
+        };
+
+        match search_type {
+            TargetSearchType::Nearest => {
+                let target = if result_list.is_empty() {
+                    self.target_list.iter().copied().filter(|&id| view.is_target(self, id)).min_by_key(|&id| distance_to(id))
+                } else {
+                    result_list.iter().copied().min_by_key(|&id| distance_to(id))
+                };
+                if let Some(target) = target {
+                    if self.select_target(view, target, actions) {
+                        return true;
+                    }
+                }
+            }
+            _ => {
+                if !result_list.is_empty() {
+                    let index = uniform_random(0, result_list.len() as i64 - 1) as usize;
+                    return self.select_target(view, result_list[index], actions);
+                }
+                if search_type == TargetSearchType::AttackRange {
+                    return false;
+                }
+            }
+        }
+
+        // ta det första målet i listan
+        let targets = self.target_list.clone();
+        targets
+            .into_iter()
+            .any(|target| Some(target) != self.creature.follow_creature && self.select_target(view, target, actions))
+    }
+
+    /// Motsvarar `Monster::selectTarget`
+    pub fn select_target(&mut self, view: &dyn MonsterView, target: u32, actions: &mut Vec<MonsterAction>) -> bool {
+        if !view.is_target(self, target) || !self.target_list.contains(&target) {
+            return false;
+        }
+        if (self.mtype.is_hostile || self.creature.is_summon())
+            && self.creature.set_attacked_creature(Some(target))
+            && !self.creature.is_summon()
+        {
+            actions.push(MonsterAction::AttackTarget(target));
+        }
+        self.creature.set_follow_creature(Some(target))
+    }
+
+    /// Kan monstret nå målet med någon attack härifrån? Motsvarar
+    /// `Monster::canUseAttack`.
+    pub fn can_use_attack(&self, view: &dyn MonsterView, pos: Position, target: u32) -> bool {
+        if !self.mtype.is_hostile {
+            return true;
+        }
+        let Some(target_pos) = view.position_of(target) else {
+            return false;
+        };
+        let distance = Position::get_distance(&pos, &target_pos) as u32;
+        self.mtype
+            .attack_spells
+            .iter()
+            .find(|spell| spell.range != 0 && distance <= spell.range)
+            .is_some_and(|_| view.is_sight_clear(pos, target_pos))
+    }
+
+    // === Tänka ===
+
+    /// En gång per `EVENT_CREATURE_THINK_INTERVAL`. Motsvarar `Monster::onThink`.
+    pub fn on_think(&mut self, view: &dyn MonsterView, interval: u32) -> Vec<MonsterAction> {
+        let mut actions = Vec::new();
+        if self.creature.on_think(interval) {
+            actions.push(MonsterAction::UpdateFollowPath);
+        }
+
+        self.update_idle_status(&mut actions);
+        if self.is_idle {
+            return actions;
+        }
+
+        if self.creature.is_summon() {
+            self.think_summon(view, &mut actions);
+        } else if !self.target_list.is_empty() {
+            if self.creature.follow_creature.is_none() || !self.creature.has_follow_path {
+                self.search_target(view, TargetSearchType::Default, &mut actions);
+            } else if self.is_fleeing() {
+                if let Some(attacked) = self.creature.attacked_creature {
+                    if !self.can_use_attack(view, self.creature.position, attacked) {
+                        self.search_target(view, TargetSearchType::AttackRange, &mut actions);
+                    }
+                }
+            }
+        }
+
+        self.on_think_target(view, interval, &mut actions);
+        self.on_think_yell(interval, &mut actions);
+        self.on_think_defense(view, interval, &mut actions);
+        actions
+    }
+
+    /// Summons anfaller det mastern anfaller och följer annars mastern
+    fn think_summon(&mut self, view: &dyn MonsterView, actions: &mut Vec<MonsterAction>) {
+        let master = self.creature.master;
+        match self.creature.attacked_creature {
+            None => {
+                if let Some(target) = master.and_then(|m| view.attacked_creature_of(m)) {
+                    self.add_target(target, true);
+                    self.select_target(view, target, actions);
+                } else if self.creature.follow_creature != master {
+                    self.creature.set_follow_creature(master);
+                }
+            }
+            Some(attacked) if attacked == self.id() => {
+                self.creature.set_follow_creature(None);
+            }
+            Some(attacked) => {
+                if self.creature.follow_creature != Some(attacked) {
+                    self.creature.set_follow_creature(Some(attacked));
+                }
+            }
+        }
+    }
+
+    /// Byt mål då och då. Motsvarar `Monster::onThinkTarget`.
+    fn on_think_target(&mut self, view: &dyn MonsterView, interval: u32, actions: &mut Vec<MonsterAction>) {
+        if self.creature.is_summon() || self.mtype.change_target_speed == 0 {
+            return;
+        }
+
+        if self.target_change_cooldown > 0 {
+            self.target_change_cooldown -= interval as i32;
+            if self.target_change_cooldown > 0 {
+                return;
+            }
+            self.target_change_cooldown = 0;
+            self.target_change_ticks = self.mtype.change_target_speed;
+        }
+
+        self.target_change_ticks += interval;
+        if self.target_change_ticks < self.mtype.change_target_speed {
+            return;
+        }
+        self.target_change_ticks = 0;
+        self.target_change_cooldown = self.mtype.change_target_speed as i32;
+
+        if self.mtype.change_target_chance as i64 >= uniform_random(1, 100) {
+            let search_type = if self.mtype.target_distance <= 1 {
+                TargetSearchType::Random
+            } else {
+                TargetSearchType::Nearest
+            };
+            self.search_target(view, search_type, actions);
+        }
+    }
+
+    /// Motsvarar `Monster::onThinkYell`
+    fn on_think_yell(&mut self, interval: u32, actions: &mut Vec<MonsterAction>) {
+        if self.mtype.yell_speed_ticks == 0 {
+            return;
+        }
+        self.yell_ticks += interval;
+        if self.yell_ticks < self.mtype.yell_speed_ticks {
+            return;
+        }
+        self.yell_ticks = 0;
+
+        if !self.mtype.voices.is_empty() && self.mtype.yell_chance as i64 >= uniform_random(1, 100) {
+            let index = uniform_random(0, self.mtype.voices.len() as i64 - 1) as usize;
+            let voice = &self.mtype.voices[index];
+            actions.push(MonsterAction::Say { text: voice.text.clone(), yell: voice.yell });
+        }
+    }
+
+    /// Försvarsspells och summons. Motsvarar `Monster::onThinkDefense`.
+    fn on_think_defense(&mut self, view: &dyn MonsterView, interval: u32, actions: &mut Vec<MonsterAction>) {
+        let mut reset_ticks = true;
+        self.defense_ticks += interval;
+
+        for (index, spell) in self.mtype.defense_spells.iter().enumerate() {
+            if spell.interval > self.defense_ticks {
+                reset_ticks = false;
+                continue;
+            }
+            // redan använd det här varvet
+            if self.defense_ticks % spell.interval >= interval {
+                continue;
+            }
+            if spell.chance as i64 >= uniform_random(1, 100) {
+                actions.push(MonsterAction::CastSpell { defensive: true, index, target: self.creature.id });
+            }
+        }
+
+        let mut summon_count = self.creature.summons.len() as u32;
+        if !self.creature.is_summon() && summon_count < self.mtype.max_summons && self.creature.has_follow_path {
+            for summon in &self.mtype.summons {
+                if summon.interval > self.defense_ticks {
+                    reset_ticks = false;
+                    continue;
+                }
+                if summon_count >= self.mtype.max_summons {
+                    continue;
+                }
+                if self.defense_ticks % summon.interval >= interval {
+                    continue;
+                }
+                let same_kind = self
+                    .creature
+                    .summons
+                    .iter()
+                    .filter(|&&id| view.creature_name(id).is_some_and(|n| n.eq_ignore_ascii_case(&summon.name)))
+                    .count() as u32;
+                if same_kind >= summon.max {
+                    continue;
+                }
+                if (summon.chance as i64) < uniform_random(1, 100) {
+                    continue;
+                }
+                actions.push(MonsterAction::Summon { name: summon.name.clone(), force: summon.force });
+                summon_count += 1;
+            }
+        }
+
+        if reset_ticks {
+            self.defense_ticks = 0;
+        }
+    }
+
+    // === Anfalla ===
+
+    /// Attackerna mot målet, från attack-eventet. Motsvarar `Monster::doAttacking`.
+    pub fn do_attacking(&mut self, view: &dyn MonsterView, interval: u32, now: u64) -> Vec<MonsterAction> {
+        let mut actions = Vec::new();
+        let Some(target) = self.creature.attacked_creature else {
+            return actions;
+        };
+        if self.creature.is_summon() && target == self.id() {
+            return actions;
+        }
+        let Some(target_pos) = view.position_of(target) else {
+            return actions;
+        };
+
+        let pos = self.creature.position;
+        let mut update_look = true;
+        let mut reset_ticks = interval != 0;
+        self.attack_ticks += interval;
+
+        let mtype = self.mtype.clone();
+        for (index, spell) in mtype.attack_spells.iter().enumerate() {
+            let mut in_range = false;
+            if self.can_use_spell(pos, target_pos, spell, interval, now, &mut in_range, &mut reset_ticks)
+                && spell.chance as i64 >= uniform_random(1, 100)
+            {
+                if update_look {
+                    self.update_look_direction(target_pos, &mut actions);
+                    update_look = false;
+                }
+                actions.push(MonsterAction::CastSpell { defensive: false, index, target });
+                if spell.is_melee {
+                    self.extra_melee_attack = false;
+                }
+            }
+            if !in_range && spell.is_melee {
+                // slaget missade räckvidden, försök igen så fort målet är nära
+                self.extra_melee_attack = true;
+            }
+        }
+
+        if update_look {
+            self.update_look_direction(target_pos, &mut actions);
+        }
+        if reset_ticks {
+            self.attack_ticks = 0;
+        }
+        actions
+    }
+
+    /// Motsvarar `Monster::canUseSpell`
+    #[allow(clippy::too_many_arguments)]
+    fn can_use_spell(
+        &mut self,
+        pos: Position,
+        target_pos: Position,
+        spell: &MonsterSpell,
+        interval: u32,
+        now: u64,
+        in_range: &mut bool,
+        reset_ticks: &mut bool,
+    ) -> bool {
+        *in_range = true;
+        if spell.is_melee && self.is_fleeing() {
+            return false;
+        }
+
+        if self.extra_melee_attack {
+            self.last_melee_attack = now;
+        } else if spell.is_melee && now.saturating_sub(self.last_melee_attack) < spell.interval as u64 {
+            return false;
+        }
+
+        if !spell.is_melee || !self.extra_melee_attack {
+            if spell.interval > self.attack_ticks {
+                *reset_ticks = false;
+                return false;
+            }
+            if self.attack_ticks % spell.interval >= interval {
+                // redan använd det här varvet
+                return false;
+            }
+        }
+
+        if spell.range != 0 && Position::get_distance(&pos, &target_pos) as u32 > spell.range {
+            *in_range = false;
+            return false;
+        }
+        true
+    }
+
+    /// Vänd sig mot målet, utan att byta axel i onödan när det står
+    /// diagonalt. Motsvarar `Monster::updateLookDirection`.
+    fn update_look_direction(&mut self, target_pos: Position, actions: &mut Vec<MonsterAction>) {
+        let pos = self.creature.position;
+        let offset_x = target_pos.x as i32 - pos.x as i32;
+        let offset_y = target_pos.y as i32 - pos.y as i32;
+        let dir = self.creature.direction;
+
+        let new_dir = match offset_x.abs().cmp(&offset_y.abs()) {
+            std::cmp::Ordering::Greater if offset_x < 0 => Direction::West,
+            std::cmp::Ordering::Greater => Direction::East,
+            std::cmp::Ordering::Less if offset_y < 0 => Direction::North,
+            std::cmp::Ordering::Less => Direction::South,
+            std::cmp::Ordering::Equal => match (offset_x < 0, offset_y < 0, dir) {
+                (true, true, Direction::South) => Direction::West,
+                (true, true, Direction::East) => Direction::North,
+                (true, false, Direction::North) => Direction::West,
+                (true, false, Direction::East) => Direction::South,
+                (false, true, Direction::South) => Direction::East,
+                (false, true, Direction::West) => Direction::North,
+                (false, false, Direction::North) => Direction::East,
+                (false, false, Direction::West) => Direction::South,
+                _ => dir,
+            },
+        };
+
+        if new_dir != dir {
+            self.creature.direction = new_dir;
+            actions.push(MonsterAction::Turn(new_dir));
+        }
+    }
+
+    // === Gå ===
+
+    /// Hur vägen mot den monstret följer ska sökas. Motsvarar
+    /// `Monster::getPathSearchParams`.
+    pub fn follow_params(&self, view: &dyn MonsterView, target: u32) -> FollowParams {
+        let mut params = FollowParams {
+            full_path_search: !self.creature.has_follow_path,
+            clear_sight: true,
+            keep_distance: false,
+            max_search_dist: 12,
+            min_target_dist: 1,
+            max_target_dist: self.mtype.target_distance,
+        };
+
+        if self.creature.is_summon() {
+            if self.creature.master == Some(target) {
+                params.max_target_dist = 2;
+                params.full_path_search = true;
+            } else if self.mtype.target_distance <= 1 {
+                params.full_path_search = true;
+            } else {
+                params.full_path_search = !self.can_use_attack(view, self.creature.position, target);
+            }
+        } else if self.is_fleeing() {
+            // längre bort än klienten ser
+            params.max_target_dist = MAX_CLIENT_VIEWPORT_X;
+            params.clear_sight = false;
+            params.keep_distance = true;
+            params.full_path_search = false;
+        } else if self.mtype.target_distance <= 1 {
+            params.full_path_search = true;
+        } else {
+            params.full_path_search = !self.can_use_attack(view, self.creature.position, target);
+        }
+        params
+    }
+
+    /// Nästa steg: längs vägen till målet, runt målet, eller slumpvis när
+    /// ingen är i närheten. None stoppar gången. Motsvarar `Monster::getNextStep`.
+    pub fn next_step(&mut self, view: &dyn MonsterView, now: u64) -> Option<Direction> {
+        if self.is_idle || self.creature.is_dead() {
+            // ingen ser oss, ingen anledning att gå
+            return None;
+        }
+
+        let pos = self.creature.position;
+        let following = self.creature.follow_creature.is_some() && self.creature.has_follow_path;
+        let summon_with_master = self.creature.is_summon() && self.is_master_in_range;
+
+        if !following && !summon_with_master {
+            if now.saturating_sub(self.creature.last_step) >= 1000 {
+                self.random_stepping = true;
+                return self.random_step(view, pos);
+            }
+            return None;
+        }
+
+        self.random_stepping = false;
+        if let Some(dir) = self.creature.next_step() {
+            return Some(dir);
+        }
+
+        // dansa runt målet
+        let attacked = self.creature.attacked_creature;
+        if attacked.is_some() && attacked == self.creature.follow_creature {
+            if self.is_fleeing() {
+                return self.dance_step(view, pos, false, false);
+            }
+            if (self.mtype.static_attack_chance as i64) < uniform_random(1, 100) {
+                return self.dance_step(view, pos, true, true);
+            }
+        }
+        None
+    }
+
+    /// Motsvarar `Monster::getRandomStep`
+    fn random_step(&self, view: &dyn MonsterView, pos: Position) -> Option<Direction> {
+        let mut dirs = [Direction::North, Direction::West, Direction::East, Direction::South];
+        for i in (1..dirs.len()).rev() {
+            dirs.swap(i, uniform_random(0, i as i64) as usize);
+        }
+        dirs.into_iter()
+            .find(|&dir| pos.get_next_position(dir).is_some_and(|next| view.can_walk_to(self, next)))
+    }
+
+    /// Ett steg som håller samma avstånd till målet. Med `keep_attack` bara
+    /// till rutor varifrån monstret fortfarande når målet, med `keep_distance`
+    /// aldrig bort från målet. Motsvarar `Monster::getDanceStep`.
+    fn dance_step(&self, view: &dyn MonsterView, pos: Position, keep_attack: bool, keep_distance: bool) -> Option<Direction> {
+        let target = self.creature.attacked_creature?;
+        let center = view.position_of(target)?;
+
+        let offset_x = pos.x as i32 - center.x as i32;
+        let offset_y = pos.y as i32 - center.y as i32;
+        let distance_x = offset_x.abs();
+        let distance_y = offset_y.abs();
+        let center_to_dist = distance_x.max(distance_y);
+
+        let can_attack_now = keep_attack && self.can_use_attack(view, pos, target);
+        let candidates = [
+            (Direction::North, !keep_distance || offset_y >= 0, distance_x.max((offset_y - 1).abs())),
+            (Direction::South, !keep_distance || offset_y <= 0, distance_x.max((offset_y + 1).abs())),
+            (Direction::East, !keep_distance || offset_x <= 0, distance_y.max((offset_x + 1).abs())),
+            (Direction::West, !keep_distance || offset_x >= 0, distance_y.max((offset_x - 1).abs())),
+        ];
+
+        let dirs: Vec<Direction> = candidates
+            .into_iter()
+            .filter(|&(_, allowed, dist)| allowed && dist == center_to_dist)
+            .filter_map(|(dir, _, _)| {
+                let next = pos.get_next_position(dir)?;
+                if !view.can_walk_to(self, next) {
+                    return None;
+                }
+                (!can_attack_now || self.can_use_attack(view, next, target)).then_some(dir)
+            })
+            .collect();
+
+        if dirs.is_empty() {
+            return None;
+        }
+        Some(dirs[uniform_random(0, dirs.len() as i64 - 1) as usize])
+    }
+}
@@ -0,0 +1,83 @@
+//! Hästarna från data/XML/mounts.xml, motsvarar `Mount` och `Mounts` i
+//! TFS. Spelaren har id:na den tämjt och den valda hästen; hästens fart
+//! läggs på varelsens fart när spelaren rider (`rules::outfit`).
+
+use std::path::Path;
+
+use common::tracing::warn;
+use common::{Error, Result};
+
+use crate::group::as_bool;
+
+/// En rad i mounts.xml, motsvarar `Mount`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mount {
+    pub id: u16,
+    /// Utseendet, `Outfit::look_mount` när spelaren rider
+    pub client_id: u16,
+    pub name: String,
+    /// Läggs till farten när spelaren sitter på hästen
+    pub speed: i32,
+    pub premium: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Mounts {
+    mounts: Vec<Mount>,
+}
+
+impl Mounts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Motsvarar `Mounts::loadFromXml`. En häst utan id hoppas över, och
+    /// id:t måste vara unikt.
+    pub fn load_from_xml(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| Error::World(format!("Cannot open {}: {e}", path.display())))?;
+        let doc = roxmltree::Document::parse(&text)
+            .map_err(|e| Error::World(format!("Cannot parse {}: {e}", path.display())))?;
+
+        let mut mounts = Self::new();
+        for node in doc.root_element().children().filter(|n| n.has_tag_name("mount")) {
+            let number = |name: &str| node.attribute(name).and_then(|v| v.trim().parse::<i64>().ok()).unwrap_or(0);
+            let id = number("id") as u16;
+            if id == 0 {
+                warn!("[Mounts::loadFromXml] Mount id \"{}\" is not within 1 and 65535 range", number("id"));
+                continue;
+            }
+            if mounts.get_mount_by_id(id).is_some() {
+                warn!("[Mounts::loadFromXml] Duplicate mount with id: {id}");
+                continue;
+            }
+            mounts.mounts.push(Mount {
+                id,
+                client_id: number("clientid") as u16,
+                name: node.attribute("name").unwrap_or_default().to_string(),
+                speed: number("speed") as i32,
+                premium: node.attribute("premium").is_some_and(as_bool),
+            });
+        }
+        Ok(mounts)
+    }
+
+    pub fn get_mount_by_id(&self, id: u16) -> Option<&Mount> {
+        self.mounts.iter().find(|mount| mount.id == id)
+    }
+
+    /// Skiftlägesokänsligt, som `Mounts::getMountByName`
+    pub fn get_mount_by_name(&self, name: &str) -> Option<&Mount> {
+        self.mounts.iter().find(|mount| mount.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn get_mount_by_client_id(&self, client_id: u16) -> Option<&Mount> {
+        self.mounts.iter().find(|mount| mount.client_id == client_id)
+    }
+
+    /// I filens ordning, som i utseendefönstret
+    pub fn iter(&self) -> impl Iterator<Item = &Mount> {
+        self.mounts.iter()
+    }
+}
@@ -0,0 +1,333 @@
+//! NPC:er, motsvarar `Npc` i TFS. Varje NPC har en egen fil,
+//! data/npc/<namn>.xml, med utseende, gångsätt, parametrar och vilket
+//! script i data/npc/scripts som sköter dialogen.
+//!
+//! Själva dialogen (hälsning, avsked, nyckelord, butik och resor) ligger
+//! i Lua-biblioteket data/npc/lib och körs av `scripting`. Här finns det
+//! som inte kräver Lua: gång inom radien runt startpositionen, vem NPC:n
+//! pratar med och vilka spelare som har dess butik öppen.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use common::tracing::warn;
+use common::{uniform_random, Direction, Error, Position, Result};
+
+use crate::creature::{Creature, CreatureType, Skull};
+use crate::monster::{attr, attr_str, boolean_string};
+
+/// Hur länge NPC:n står still mellan stegen om filen inte säger annat
+pub const DEFAULT_WALK_TICKS: u32 = 1500;
+
+/// Motsvarar `SpeechBubble_t`
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SpeechBubble {
+    #[default]
+    None = 0,
+    Normal = 1,
+    Trade = 2,
+    Quest = 3,
+    QuestTrader = 4,
+}
+
+impl SpeechBubble {
+    pub fn from_u8(value: u8) -> Self {
+        match value {
+            1 => SpeechBubble::Normal,
+            2 => SpeechBubble::Trade,
+            3 => SpeechBubble::Quest,
+            4 => SpeechBubble::QuestTrader,
+            _ => SpeechBubble::None,
+        }
+    }
+}
+
+/// En rad i handelsfönstret, motsvarar `ShopInfo`. Ett pris på 0 betyder
+/// att NPC:n inte köper respektive säljer varan.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShopInfo {
+    pub item_id: u16,
+    pub sub_type: i32,
+    pub buy_price: u32,
+    pub sell_price: u32,
+    pub real_name: String,
+}
+
+/// Det NPC:n behöver veta om kartan för att gå
+pub trait NpcView {
+    /// Går rutan att gå till: finns, är ledig, och (beroende på NPC:n)
+    /// ingen trappa, teleport eller för hög stapel. Motsvarar rut-delen av
+    /// `Npc::canWalkTo`.
+    fn can_walk_to(&self, npc: &Npc, pos: Position) -> bool;
+}
+
+#[derive(Debug, Clone)]
+pub struct Npc {
+    /// Namn, hälsa, hastighet och utseende
+    pub creature: Creature,
+    /// Filen NPC:n lästes från, för omladdning
+    pub filename: PathBuf,
+    /// Dialogscriptet, None om NPC:n bara står där
+    pub script: Option<PathBuf>,
+    parameters: BTreeMap<String, String>,
+
+    pub master_pos: Position,
+    /// Hur långt från `master_pos` NPC:n får gå, -1 betyder obegränsat och
+    /// 0 att den står still
+    pub master_radius: i32,
+    /// Millisekunder mellan stegen, 0 betyder att NPC:n aldrig går själv
+    pub walk_ticks: u32,
+    pub floor_change: bool,
+    pub attackable: bool,
+    pub ignore_height: bool,
+    pub speech_bubble: SpeechBubble,
+
+    /// Spelaren NPC:n vänder sig mot, satt av `doNpcSetCreatureFocus`
+    focus_creature: Option<u32>,
+    /// Spelare som ser NPC:n; utan dem går den inte
+    spectators: BTreeSet<u32>,
+    /// Spelare som har NPC:ns handelsfönster öppet
+    shop_players: BTreeSet<u32>,
+}
+
+impl Npc {
+    pub fn new(name: impl Into<String>) -> Self {
+        let mut creature = Creature::new(CreatureType::Npc, name);
+        creature.base_speed = 100;
+        Self {
+            creature,
+            filename: PathBuf::new(),
+            script: None,
+            parameters: BTreeMap::new(),
+            master_pos: Position::default(),
+            master_radius: -1,
+            walk_ticks: DEFAULT_WALK_TICKS,
+            floor_change: false,
+            attackable: false,
+            ignore_height: false,
+            speech_bubble: SpeechBubble::None,
+            focus_creature: None,
+            spectators: BTreeSet::new(),
+            shop_players: BTreeSet::new(),
+        }
+    }
+
+    /// Läs data/npc/<name>.xml. Motsvarar `Npc::createNpc`.
+    pub fn create(npc_dir: impl AsRef<Path>, name: &str) -> Result<Self> {
+        Self::load_from_xml(npc_dir.as_ref().join(format!("{name}.xml")))
+    }
+
+    /// Motsvarar `Npc::loadFromXml`. Scriptet letas upp i scripts/ bredvid filen.
+    pub fn load_from_xml(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| Error::World(format!("Cannot open {}: {e}", path.display())))?;
+        let doc = roxmltree::Document::parse(&text)
+            .map_err(|e| Error::World(format!("Cannot parse {}: {e}", path.display())))?;
+        let root = doc.root_element();
+        if !root.has_tag_name("npc") {
+            return Err(Error::World(format!("Missing npc node in {}", path.display())));
+        }
+
+        let mut npc = Npc::new(attr_str(&root, "name").unwrap_or_default());
+        npc.filename = path.to_path_buf();
+        npc.attackable = attr_str(&root, "attackable").is_some_and(boolean_string);
+        npc.floor_change = attr_str(&root, "floorchange").is_some_and(boolean_string);
+        if let Some(speed) = attr(&root, "speed") {
+            npc.creature.base_speed = speed;
+        }
+        if let Some(walk_ticks) = attr(&root, "walkinterval") {
+            npc.walk_ticks = walk_ticks;
+        }
+        if let Some(radius) = attr(&root, "walkradius") {
+            npc.master_radius = radius;
+        }
+        if let Some(ignore_height) = attr_str(&root, "ignoreheight") {
+            npc.ignore_height = boolean_string(ignore_height);
+        }
+        if let Some(bubble) = attr(&root, "speechbubble") {
+            npc.speech_bubble = SpeechBubble::from_u8(bubble);
+        }
+        if let Some(skull) = attr_str(&root, "skull") {
+            npc.creature.skull = Skull::from_name(skull).unwrap_or_default();
+        }
+        if let Some(script) = attr_str(&root, "script") {
+            let dir = path.parent().unwrap_or_else(|| Path::new(""));
+            npc.script = Some(dir.join("scripts").join(script));
+        }
+
+        for node in root.children().filter(|n| n.is_element()) {
+            match node.tag_name().name().to_ascii_lowercase().as_str() {
+                "health" => {
+                    let creature = &mut npc.creature;
+                    creature.health = attr(&node, "now").unwrap_or(100);
+                    creature.health_max = attr(&node, "max").unwrap_or(100);
+                    if creature.health > creature.health_max {
+                        creature.health = creature.health_max;
+                        warn!("[Npc::loadFromXml] Health now is greater than health max in {}", path.display());
+                    }
+                }
+                "look" => {
+                    let outfit = &mut npc.creature.default_outfit;
+                    if let Some(look_type) = attr(&node, "type") {
+                        outfit.look_type = look_type;
+                        outfit.look_head = attr(&node, "head").unwrap_or(0);
+                        outfit.look_body = attr(&node, "body").unwrap_or(0);
+                        outfit.look_legs = attr(&node, "legs").unwrap_or(0);
+                        outfit.look_feet = attr(&node, "feet").unwrap_or(0);
+                        outfit.look_addons = attr(&node, "addons").unwrap_or(0);
+                    } else if let Some(look_type_ex) = attr(&node, "typeex") {
+                        outfit.look_type_ex = look_type_ex;
+                    }
+                    if let Some(mount) = attr(&node, "mount") {
+                        outfit.look_mount = mount;
+                    }
+                    npc.creature.outfit = *outfit;
+                }
+                "parameters" => {
+                    for parameter in node.children().filter(|n| n.has_tag_name("parameter")) {
+                        let (Some(key), Some(value)) = (attr_str(&parameter, "key"), attr_str(&parameter, "value")) else {
+                            warn!("[Npc::loadFromXml] Parameter without key or value in {}", path.display());
+                            continue;
+                        };
+                        npc.parameters.insert(key.to_string(), value.to_string());
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(npc)
+    }
+
+    pub fn id(&self) -> u32 {
+        self.creature.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.creature.name
+    }
+
+    /// Motsvarar `getNpcParameter`
+    pub fn parameter(&self, key: &str) -> Option<&str> {
+        self.parameters.get(key).map(String::as_str)
+    }
+
+    pub fn parameters(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.parameters.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    /// Sätts av spawnen; radien från XML-filen vinner om den finns
+    pub fn set_master_pos(&mut self, pos: Position, radius: i32) {
+        self.master_pos = pos;
+        if self.master_radius == -1 {
+            self.master_radius = radius;
+        }
+    }
+
+    pub fn focus_creature(&self) -> Option<u32> {
+        self.focus_creature
+    }
+
+    /// Vänd NPC:n mot den den pratar med. Returnerar riktningen NPC:n ska
+    /// vända sig åt. Motsvarar `Npc::setCreatureFocus`.
+    pub fn set_creature_focus(&mut self, focus: Option<(u32, Position)>) -> Option<Direction> {
+        let Some((id, pos)) = focus else {
+            self.focus_creature = None;
+            return None;
+        };
+        self.focus_creature = Some(id);
+        let dir = self.direction_to(pos);
+        self.creature.direction = dir;
+        Some(dir)
+    }
+
+    /// Motsvarar `Npc::turnToCreature`: bara de fyra huvudriktningarna,
+    /// den axel med störst avstånd vinner
+    pub fn direction_to(&self, pos: Position) -> Direction {
+        let dx = Position::get_offset_x(&self.creature.position, &pos);
+        let dy = Position::get_offset_y(&self.creature.position, &pos);
+        let tan = if dx != 0 { dy as f32 / dx as f32 } else { 10.0 };
+        if tan.abs() < 1.0 {
+            if dx > 0 {
+                Direction::West
+            } else {
+                Direction::East
+            }
+        } else if dy > 0 {
+            Direction::North
+        } else {
+            Direction::South
+        }
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.spectators.is_empty()
+    }
+
+    /// En spelare kom inom synhåll. Returnerar true om NPC:n vaknade.
+    pub fn add_spectator(&mut self, player_id: u32) -> bool {
+        let was_idle = self.is_idle();
+        self.spectators.insert(player_id);
+        was_idle
+    }
+
+    /// En spelare försvann ur synhåll. Returnerar true om NPC:n somnade.
+    pub fn remove_spectator(&mut self, player_id: u32) -> bool {
+        self.spectators.remove(&player_id) && self.is_idle()
+    }
+
+    pub fn add_shop_player(&mut self, player_id: u32) {
+        self.shop_players.insert(player_id);
+    }
+
+    pub fn remove_shop_player(&mut self, player_id: u32) {
+        self.shop_players.remove(&player_id);
+    }
+
+    /// Spelare vars handelsfönster ska stängas när NPC:n försvinner
+    pub fn shop_players(&self) -> impl Iterator<Item = u32> + '_ {
+        self.shop_players.iter().copied()
+    }
+
+    /// Inom radien runt startpositionen? Motsvarar `Spawns::isInZone`.
+    pub fn is_in_walk_zone(&self, pos: Position) -> bool {
+        if self.master_radius == -1 {
+            return true;
+        }
+        Position::get_distance_x(&self.master_pos, &pos) <= self.master_radius
+            && Position::get_distance_y(&self.master_pos, &pos) <= self.master_radius
+    }
+
+    /// Motsvarar `Npc::canWalkTo`
+    pub fn can_walk_to(&self, view: &dyn NpcView, pos: Position) -> bool {
+        self.master_radius != 0 && self.is_in_walk_zone(pos) && view.can_walk_to(self, pos)
+    }
+
+    /// Nästa steg: längs en väg från `selfMoveTo`/`selfFollow`, annars ett
+    /// slumpat steg om ingen har NPC:ns uppmärksamhet och den stått still
+    /// länge nog. Motsvarar `Npc::getNextStep`.
+    pub fn next_step(&mut self, view: &dyn NpcView, now: u64) -> Option<Direction> {
+        if let Some(dir) = self.creature.next_step() {
+            return Some(dir);
+        }
+        if self.walk_ticks == 0 || self.focus_creature.is_some() || self.is_idle() {
+            return None;
+        }
+        if now.saturating_sub(self.creature.last_step) < self.walk_ticks as u64 {
+            return None;
+        }
+        self.random_step(view)
+    }
+
+    /// Motsvarar `Npc::getRandomStep`
+    fn random_step(&self, view: &dyn NpcView) -> Option<Direction> {
+        let pos = self.creature.position;
+        let mut dirs = [Direction::North, Direction::West, Direction::East, Direction::South];
+        for i in (1..dirs.len()).rev() {
+            dirs.swap(i, uniform_random(0, i as i64) as usize);
+        }
+        dirs.into_iter()
+            .find(|&dir| pos.get_next_position(dir).is_some_and(|next| self.can_walk_to(view, next)))
+    }
+}
@@ -0,0 +1,89 @@
+//! Utseendena spelare kan välja, från data/XML/outfits.xml. Motsvarar
+//! `Outfit` och `Outfits` i TFS. Vilka spelaren låst upp och vilka addons
+//! den har finns i `Player`; reglerna för fönstret finns i `rules::outfit`.
+
+use std::path::Path;
+
+use common::tracing::warn;
+use common::{Error, Result};
+
+use crate::group::as_bool;
+use crate::player::PlayerSex;
+
+/// En rad i outfits.xml, motsvarar `Outfit`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutfitType {
+    pub name: String,
+    pub look_type: u16,
+    pub premium: bool,
+    /// Alla har den utan addons; annars måste den läggas till spelaren
+    pub unlocked: bool,
+}
+
+/// Alla utseenden per kön, i filens ordning
+#[derive(Debug, Clone, Default)]
+pub struct Outfits {
+    female: Vec<OutfitType>,
+    male: Vec<OutfitType>,
+}
+
+impl Outfits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Motsvarar `Outfits::loadFromXml`. Rader med `enabled="no"` hoppas över.
+    pub fn load_from_xml(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| Error::World(format!("Cannot open {}: {e}", path.display())))?;
+        let doc = roxmltree::Document::parse(&text)
+            .map_err(|e| Error::World(format!("Cannot parse {}: {e}", path.display())))?;
+
+        let mut outfits = Self::new();
+        for node in doc.root_element().children().filter(|n| n.has_tag_name("outfit")) {
+            if !node.attribute("enabled").is_none_or(as_bool) {
+                continue;
+            }
+            let Some(sex) = node.attribute("type") else {
+                warn!("[Outfits::loadFromXml] Missing outfit type.");
+                continue;
+            };
+            let sex = match sex.trim().parse::<u8>() {
+                Ok(0) => PlayerSex::Female,
+                Ok(1) => PlayerSex::Male,
+                _ => {
+                    warn!("[Outfits::loadFromXml] Invalid outfit type {sex}.");
+                    continue;
+                }
+            };
+            let Some(look_type) = node.attribute("looktype").and_then(|v| v.trim().parse::<u16>().ok()) else {
+                warn!("[Outfits::loadFromXml] Missing looktype on outfit.");
+                continue;
+            };
+            let outfit = OutfitType {
+                name: node.attribute("name").unwrap_or_default().to_string(),
+                look_type,
+                premium: node.attribute("premium").is_some_and(as_bool),
+                unlocked: node.attribute("unlocked").is_none_or(as_bool),
+            };
+            match sex {
+                PlayerSex::Female => outfits.female.push(outfit),
+                PlayerSex::Male => outfits.male.push(outfit),
+            }
+        }
+        Ok(outfits)
+    }
+
+    pub fn get_outfits(&self, sex: PlayerSex) -> &[OutfitType] {
+        match sex {
+            PlayerSex::Female => &self.female,
+            PlayerSex::Male => &self.male,
+        }
+    }
+
+    /// Motsvarar `Outfits::getOutfitByLookType`
+    pub fn get_outfit_by_look_type(&self, sex: PlayerSex, look_type: u16) -> Option<&OutfitType> {
+        self.get_outfits(sex).iter().find(|outfit| outfit.look_type == look_type)
+    }
+}
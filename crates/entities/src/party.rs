@@ -0,0 +1,734 @@
+//! Partyn, motsvarar `Party` och party-delarna av `Player` och `Game` i TFS.
+//!
+//! Ett party håller bara spelar-id. Det den behöver veta om spelarna
+//! (namn, nivå, position) frågar den `PartyView` om, och det spelarna ska
+//! få se (meddelanden, sköldar, skallar) läggs som `PartyEvent` i en kö som
+//! spelet tömmer. Händelserna i data/events (`Party:onJoin`, `onLeave`,
+//! `onDisband`, `onShareExperience`) körs av spelet innan anropen hit.
+
+use std::collections::{BTreeSet, HashMap};
+
+use common::Position;
+
+use crate::creature::PartyShield;
+use crate::group::PlayerFlags;
+use crate::player::{Player, PlayerSex};
+
+/// Hur nära ledaren (i rutor och våningar) man måste stå för delad erfarenhet
+pub const EXPERIENCE_SHARE_RANGE: i32 = 30;
+pub const EXPERIENCE_SHARE_FLOORS: i32 = 1;
+
+/// Det partyt behöver veta om en spelare
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartyMember {
+    pub name: String,
+    pub sex: PlayerSex,
+    pub level: u32,
+    pub position: Position,
+    /// Spelaren har `PlayerFlag_NotGainInFight` och behöver inte slåss
+    /// eller hela för att få del av erfarenheten
+    pub ignore_activity: bool,
+}
+
+impl PartyMember {
+    pub fn from_player(player: &Player) -> Self {
+        Self {
+            name: player.name().to_string(),
+            sex: player.sex,
+            level: player.level,
+            position: player.creature.position,
+            ignore_activity: player.has_flag(PlayerFlags::NOT_GAIN_IN_FIGHT),
+        }
+    }
+}
+
+pub trait PartyView {
+    /// `None` om spelaren inte är inloggad
+    fn member(&self, player_id: u32) -> Option<PartyMember>;
+
+    /// Spelare som ser spelaren, spelaren själv inräknad
+    fn spectators(&self, player_id: u32) -> Vec<u32>;
+}
+
+/// Det spelarna ska få veta, motsvarar send-anropen i party.cpp
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PartyEvent {
+    /// `MESSAGE_INFO_DESCR` till spelaren
+    Message { player: u32, text: String },
+    /// `viewer` ska få `player`s sköld på nytt (sendCreatureShield)
+    Shield { viewer: u32, player: u32 },
+    /// `viewer` ska få `player`s skalle på nytt (sendCreatureSkull), partymedlemmar ser grön
+    Skull { viewer: u32, player: u32 },
+    /// Spelaren är inte längre med och partykanalen ska stängas
+    CloseChannel(u32),
+}
+
+#[derive(Debug, Clone)]
+pub struct Party {
+    id: u32,
+    leader: u32,
+    /// Medlemmar utom ledaren, i den ordning de gick med
+    members: Vec<u32>,
+    invites: Vec<u32>,
+    /// När medlemmen senast anföll eller helade, för delad erfarenhet
+    ticks: HashMap<u32, u64>,
+    /// Spelare som har partykanalen öppen
+    channel_users: BTreeSet<u32>,
+    shared_exp_active: bool,
+    shared_exp_enabled: bool,
+}
+
+impl Party {
+    fn new(id: u32, leader: u32) -> Self {
+        Self {
+            id,
+            leader,
+            members: Vec::new(),
+            invites: Vec::new(),
+            ticks: HashMap::new(),
+            channel_users: BTreeSet::new(),
+            shared_exp_active: false,
+            shared_exp_enabled: false,
+        }
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn leader(&self) -> u32 {
+        self.leader
+    }
+
+    pub fn members(&self) -> &[u32] {
+        &self.members
+    }
+
+    pub fn invites(&self) -> &[u32] {
+        &self.invites
+    }
+
+    pub fn is_player_invited(&self, player_id: u32) -> bool {
+        self.invites.contains(&player_id)
+    }
+
+    /// Ledaren eller en medlem
+    pub fn contains(&self, player_id: u32) -> bool {
+        self.leader == player_id || self.members.contains(&player_id)
+    }
+
+    /// Inga medlemmar och inga inbjudningar; ett sådant party upplöses
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty() && self.invites.is_empty()
+    }
+
+    pub fn is_shared_experience_active(&self) -> bool {
+        self.shared_exp_active
+    }
+
+    pub fn is_shared_experience_enabled(&self) -> bool {
+        self.shared_exp_enabled
+    }
+
+    /// Delad erfarenhet är påslagen och alla uppfyller villkoren just nu;
+    /// då delas erfarenhet från monster, se `Parties::experience_receivers`
+    pub fn shares_experience(&self) -> bool {
+        self.shared_exp_active && self.shared_exp_enabled
+    }
+
+    /// Ledaren och medlemmarna
+    pub fn players(&self) -> impl Iterator<Item = u32> + '_ {
+        std::iter::once(self.leader).chain(self.members.iter().copied())
+    }
+
+    pub fn channel_users(&self) -> impl Iterator<Item = u32> + '_ {
+        self.channel_users.iter().copied()
+    }
+}
+
+/// Alla partyn, med vem som är med i vilket
+#[derive(Debug, Clone)]
+pub struct Parties {
+    parties: HashMap<u32, Party>,
+    /// Spelare (ledare och medlemmar) till party-id
+    member_of: HashMap<u32, u32>,
+    last_id: u32,
+    /// Hur länge en medlem räknas som aktiv efter att ha slagits, i ms
+    /// (`pzLocked` i config.lua, som i TFS)
+    activity_time: u64,
+    events: Vec<PartyEvent>,
+}
+
+impl Default for Parties {
+    fn default() -> Self {
+        Self::new(60_000)
+    }
+}
+
+impl Parties {
+    pub fn new(activity_time: u64) -> Self {
+        Self {
+            parties: HashMap::new(),
+            member_of: HashMap::new(),
+            last_id: 0,
+            activity_time,
+            events: Vec::new(),
+        }
+    }
+
+    pub fn get(&self, party_id: u32) -> Option<&Party> {
+        self.parties.get(&party_id)
+    }
+
+    pub fn party_of(&self, player_id: u32) -> Option<&Party> {
+        self.member_of.get(&player_id).and_then(|id| self.parties.get(id))
+    }
+
+    /// Partyn som bjudit in spelaren, motsvarar `Player::invitePartyList`
+    pub fn invitations_of(&self, player_id: u32) -> impl Iterator<Item = &Party> {
+        self.parties.values().filter(move |party| party.is_player_invited(player_id))
+    }
+
+    /// Är `leader` ledare för ett party som bjudit in `player_id`.
+    /// Motsvarar `Player::isInviting`.
+    pub fn is_inviting(&self, leader: u32, player_id: u32) -> bool {
+        self.party_of(leader)
+            .is_some_and(|party| party.leader == leader && party.is_player_invited(player_id))
+    }
+
+    /// Med i samma party, motsvarar `Player::isPartner`
+    pub fn is_partner(&self, a: u32, b: u32) -> bool {
+        a != b && self.member_of.get(&a).is_some_and(|party| self.member_of.get(&b) == Some(party))
+    }
+
+    /// Töm kön med det som ska skickas till spelarna
+    pub fn drain_events(&mut self) -> Vec<PartyEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    fn message(&mut self, player: u32, text: impl Into<String>) {
+        self.events.push(PartyEvent::Message { player, text: text.into() });
+    }
+
+    fn shield(&mut self, viewer: u32, player: u32) {
+        self.events.push(PartyEvent::Shield { viewer, player });
+    }
+
+    fn skull(&mut self, viewer: u32, player: u32) {
+        self.events.push(PartyEvent::Skull { viewer, player });
+    }
+
+    /// Alla som ser spelaren ska få dess sköld på nytt, motsvarar
+    /// `Game::updatePlayerShield`
+    fn update_player_shield(&mut self, view: &dyn PartyView, player: u32) {
+        for viewer in view.spectators(player) {
+            self.shield(viewer, player);
+        }
+    }
+
+    /// `player` ska se sköld och skalle för alla i partyt, motsvarar
+    /// `Player::sendPlayerPartyIcons`
+    fn send_party_icons(&mut self, player: u32, other: u32) {
+        self.shield(player, other);
+        self.skull(player, other);
+    }
+
+    /// Till ledaren och medlemmarna, och till de inbjudna om `invitations`.
+    /// Motsvarar `Party::broadcastPartyMessage`.
+    fn broadcast(&mut self, party_id: u32, text: &str, invitations: bool) {
+        let Some(party) = self.parties.get(&party_id) else {
+            return;
+        };
+        let mut receivers: Vec<u32> = party.members.clone();
+        receivers.push(party.leader);
+        if invitations {
+            receivers.extend(&party.invites);
+        }
+        for player in receivers {
+            self.message(player, text);
+        }
+    }
+
+    /// `leader` bjuder in `invited`, och partyt skapas om ledaren inte har
+    /// något. Motsvarar `Game::playerInviteToParty` och
+    /// `Party::invitePlayer`.
+    pub fn invite(&mut self, view: &dyn PartyView, leader: u32, invited: u32) -> bool {
+        if leader == invited || self.is_inviting(invited, leader) {
+            return false;
+        }
+        let (Some(leader_info), Some(invited_info)) = (view.member(leader), view.member(invited)) else {
+            return false;
+        };
+        if self.member_of.contains_key(&invited) {
+            self.message(leader, format!("{} is already in a party.", invited_info.name));
+            return false;
+        }
+
+        let party_id = match self.member_of.get(&leader) {
+            Some(&party_id) => party_id,
+            None => {
+                self.last_id += 1;
+                let party_id = self.last_id;
+                self.parties.insert(party_id, Party::new(party_id, leader));
+                self.member_of.insert(leader, party_id);
+                party_id
+            }
+        };
+        let party = &self.parties[&party_id];
+        if party.leader != leader || party.is_player_invited(invited) {
+            return false;
+        }
+
+        let mut text = format!("{} has been invited.", invited_info.name);
+        if party.is_empty() {
+            text.push_str(" Open the party channel to communicate with your members.");
+            self.update_player_shield(view, leader);
+            self.skull(leader, leader);
+        }
+        self.message(leader, text);
+
+        self.parties.get_mut(&party_id).expect("party exists").invites.push(invited);
+        self.shield(leader, invited);
+        self.shield(invited, leader);
+        self.message(
+            invited,
+            format!("{} has invited you to {} party.", leader_info.name, possessive(leader_info.sex)),
+        );
+        true
+    }
+
+    /// `player` tar emot inbjudan från `leader`. Motsvarar
+    /// `Game::playerJoinParty` och `Party::joinParty`.
+    pub fn join(&mut self, view: &dyn PartyView, player: u32, leader: u32, now: u64) -> bool {
+        if !self.is_inviting(leader, player) {
+            return false;
+        }
+        let (Some(info), Some(leader_info)) = (view.member(player), view.member(leader)) else {
+            return false;
+        };
+        if self.member_of.contains_key(&player) {
+            self.message(player, "You are already in a party.");
+            return false;
+        }
+        let party_id = self.member_of[&leader];
+
+        self.parties.get_mut(&party_id).expect("party exists").invites.retain(|&id| id != player);
+        self.broadcast(party_id, &format!("{} has joined the party.", info.name), false);
+        self.member_of.insert(player, party_id);
+        self.update_player_shield(view, player);
+
+        let members = self.parties[&party_id].members.clone();
+        for &member in &members {
+            self.skull(member, player);
+            self.send_party_icons(player, member);
+        }
+        self.skull(player, player);
+        self.skull(leader, player);
+        self.send_party_icons(player, leader);
+
+        self.parties.get_mut(&party_id).expect("party exists").members.push(player);
+        self.update_shared_experience(view, party_id, now);
+
+        let suffix = if leader_info.name.ends_with('s') { "" } else { "s" };
+        self.message(
+            player,
+            format!(
+                "You have joined {}'{suffix} party. Open the party channel to communicate with your companions.",
+                leader_info.name
+            ),
+        );
+        true
+    }
+
+    /// Ledaren drar tillbaka inbjudan. Motsvarar
+    /// `Game::playerRevokePartyInvitation` och `Party::revokeInvitation`.
+    pub fn revoke_invitation(&mut self, view: &dyn PartyView, leader: u32, invited: u32) -> bool {
+        let Some(party) = self.party_of(leader) else {
+            return false;
+        };
+        if party.leader != leader || !party.is_player_invited(invited) {
+            return false;
+        }
+        let party_id = party.id;
+        let (Some(leader_info), Some(invited_info)) = (view.member(leader), view.member(invited)) else {
+            return false;
+        };
+        self.message(
+            invited,
+            format!("{} has revoked {} invitation.", leader_info.name, possessive(leader_info.sex)),
+        );
+        self.message(leader, format!("Invitation for {} has been revoked.", invited_info.name));
+        self.remove_invite(party_id, invited)
+    }
+
+    /// Ta bort en inbjudan (även när den inbjudna loggar ut). Ett tomt
+    /// party upplöses. Motsvarar `Party::removeInvite`.
+    pub fn remove_invite(&mut self, party_id: u32, invited: u32) -> bool {
+        let Some(party) = self.parties.get_mut(&party_id) else {
+            return false;
+        };
+        let Some(index) = party.invites.iter().position(|&id| id == invited) else {
+            return false;
+        };
+        party.invites.remove(index);
+        let leader = party.leader;
+        let empty = party.is_empty();
+        self.shield(leader, invited);
+        self.shield(invited, leader);
+        if empty {
+            self.disband(party_id);
+        }
+        true
+    }
+
+    /// Ledaren lämnar över till en medlem. Motsvarar
+    /// `Game::playerPassPartyLeadership` och `Party::passPartyLeadership`.
+    pub fn pass_leadership(&mut self, view: &dyn PartyView, leader: u32, new_leader: u32, now: u64) -> bool {
+        let Some(party) = self.party_of(leader) else {
+            return false;
+        };
+        if party.leader != leader || !self.is_partner(leader, new_leader) {
+            return false;
+        }
+        let party_id = party.id;
+        self.set_leader(view, party_id, new_leader, now)
+    }
+
+    fn set_leader(&mut self, view: &dyn PartyView, party_id: u32, new_leader: u32, now: u64) -> bool {
+        let Some(info) = view.member(new_leader) else {
+            return false;
+        };
+        let party = self.parties.get_mut(&party_id).expect("party exists");
+        if party.leader == new_leader || !party.members.contains(&new_leader) {
+            return false;
+        }
+        // tas bort innan meddelandet så att den nya ledaren inte får det två gånger
+        party.members.retain(|&id| id != new_leader);
+        self.broadcast(party_id, &format!("{} is now the leader of the party.", info.name), true);
+
+        let party = self.parties.get_mut(&party_id).expect("party exists");
+        let old_leader = party.leader;
+        party.leader = new_leader;
+        party.members.insert(0, old_leader);
+        self.update_shared_experience(view, party_id, now);
+
+        let party = &self.parties[&party_id];
+        let viewers: Vec<u32> = party.members.iter().chain(&party.invites).copied().collect();
+        for viewer in viewers.into_iter().chain([new_leader]) {
+            self.shield(viewer, old_leader);
+            self.shield(viewer, new_leader);
+        }
+        self.message(new_leader, "You are now the leader of the party.");
+        true
+    }
+
+    /// Spelaren lämnar partyt; är det ledaren tar första medlemmen över.
+    /// Spelaren får inte vara i strid, det kontrollerar spelet. Motsvarar
+    /// `Party::leaveParty`.
+    pub fn leave(&mut self, view: &dyn PartyView, player: u32, now: u64) -> bool {
+        let Some(&party_id) = self.member_of.get(&player) else {
+            return false;
+        };
+        let Some(info) = view.member(player) else {
+            return false;
+        };
+
+        let party = &self.parties[&party_id];
+        let mut missing_leader = false;
+        if party.leader == player {
+            if party.members.is_empty() || (party.members.len() == 1 && party.invites.is_empty()) {
+                missing_leader = true;
+            } else {
+                let next = party.members[0];
+                self.set_leader(view, party_id, next, now);
+            }
+        }
+
+        let party = self.parties.get_mut(&party_id).expect("party exists");
+        party.members.retain(|&id| id != player);
+        party.channel_users.remove(&player);
+        let leader = party.leader;
+        let members = party.members.clone();
+        self.member_of.remove(&player);
+        self.events.push(PartyEvent::CloseChannel(player));
+        self.update_player_shield(view, player);
+
+        for &member in &members {
+            self.skull(member, player);
+            self.send_party_icons(player, member);
+        }
+        self.skull(leader, player);
+        self.skull(player, player);
+        self.send_party_icons(player, leader);
+        self.message(player, "You have left the party.");
+
+        self.clear_player_points(view, party_id, player, now);
+        self.broadcast(party_id, &format!("{} has left the party.", info.name), false);
+
+        if missing_leader || self.parties[&party_id].is_empty() {
+            self.disband(party_id);
+        }
+        true
+    }
+
+    /// Upplös partyt. `Party:onDisband` körs av spelet innan. Motsvarar
+    /// `Party::disband`.
+    pub fn disband(&mut self, party_id: u32) {
+        let Some(party) = self.parties.remove(&party_id) else {
+            return;
+        };
+        let leader = party.leader;
+        self.member_of.remove(&leader);
+        self.events.push(PartyEvent::CloseChannel(leader));
+        self.shield(leader, leader);
+        self.skull(leader, leader);
+        self.message(leader, "Your party has been disbanded.");
+
+        for &invited in &party.invites {
+            self.shield(leader, invited);
+            self.shield(invited, leader);
+        }
+
+        for &member in &party.members {
+            self.member_of.remove(&member);
+            self.events.push(PartyEvent::CloseChannel(member));
+            self.message(member, "Your party has been disbanded.");
+        }
+        for &member in &party.members {
+            self.shield(member, member);
+            self.shield(leader, member);
+            self.shield(member, leader);
+            for &other in &party.members {
+                self.skull(other, member);
+            }
+            self.skull(member, leader);
+            self.skull(leader, member);
+        }
+    }
+
+    /// Spelaren loggar ut: lämna partyt och glöm inbjudningarna. Motsvarar
+    /// delarna i `Player::onRemoveCreature` och `Player::clearPartyInvitations`.
+    pub fn remove_player(&mut self, view: &dyn PartyView, player: u32, now: u64) {
+        if self.member_of.contains_key(&player) {
+            self.leave(view, player, now);
+        }
+        let invited: Vec<u32> = self.invitations_of(player).map(|party| party.id).collect();
+        for party_id in invited {
+            self.remove_invite(party_id, player);
+        }
+    }
+
+    /// Ledaren slår av eller på delad erfarenhet. Spelaren får inte vara i
+    /// strid utanför skyddszon, det kontrollerar spelet. Motsvarar
+    /// `Party::setSharedExperience`.
+    pub fn set_shared_experience(&mut self, view: &dyn PartyView, leader: u32, active: bool, now: u64) -> bool {
+        let Some(party) = self.party_of(leader) else {
+            return false;
+        };
+        if party.leader != leader {
+            return false;
+        }
+        if party.shared_exp_active == active {
+            return true;
+        }
+        let party_id = party.id;
+        let enabled = active && self.can_enable_shared_experience(view, party_id, now);
+        let party = self.parties.get_mut(&party_id).expect("party exists");
+        party.shared_exp_active = active;
+        if active {
+            party.shared_exp_enabled = enabled;
+            if enabled {
+                self.message(leader, "Shared Experience is now active.");
+            } else {
+                self.message(leader, "Shared Experience has been activated, but some members of your party are inactive.");
+            }
+        } else {
+            self.message(leader, "Shared Experience has been deactivated.");
+        }
+        self.update_all_party_icons(party_id);
+        true
+    }
+
+    /// Kan spelaren få del av erfarenheten: nivå minst två tredjedelar av
+    /// den högsta i partyt, nära ledaren och nyligen aktiv. Motsvarar
+    /// `Party::canUseSharedExperience`.
+    pub fn can_use_shared_experience(&self, view: &dyn PartyView, player: u32, now: u64) -> bool {
+        let Some(party) = self.party_of(player) else {
+            return false;
+        };
+        if party.members.is_empty() {
+            return false;
+        }
+        let (Some(info), Some(leader)) = (view.member(player), view.member(party.leader)) else {
+            return false;
+        };
+
+        let highest_level = party
+            .members
+            .iter()
+            .filter_map(|&id| view.member(id))
+            .map(|member| member.level)
+            .fold(leader.level, u32::max);
+        let min_level = (highest_level as f64 * 2.0 / 3.0).ceil() as u32;
+        if info.level < min_level {
+            return false;
+        }
+        if !Position::are_in_range(
+            &leader.position,
+            &info.position,
+            EXPERIENCE_SHARE_RANGE,
+            EXPERIENCE_SHARE_RANGE,
+            EXPERIENCE_SHARE_FLOORS,
+        ) {
+            return false;
+        }
+        if !info.ignore_activity {
+            let Some(&last) = party.ticks.get(&player) else {
+                return false;
+            };
+            if now.saturating_sub(last) > self.activity_time {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn can_enable_shared_experience(&self, view: &dyn PartyView, party_id: u32, now: u64) -> bool {
+        let party = &self.parties[&party_id];
+        party.players().all(|id| self.can_use_shared_experience(view, id, now))
+    }
+
+    /// Räkna om om alla uppfyller villkoren och visa nya sköldar om det
+    /// ändrats. Motsvarar `Party::updateSharedExperience`.
+    pub fn update_shared_experience(&mut self, view: &dyn PartyView, party_id: u32, now: u64) {
+        let Some(party) = self.parties.get(&party_id) else {
+            return;
+        };
+        if !party.shared_exp_active {
+            return;
+        }
+        let enabled = self.can_enable_shared_experience(view, party_id, now);
+        let party = self.parties.get_mut(&party_id).expect("party exists");
+        if party.shared_exp_enabled != enabled {
+            party.shared_exp_enabled = enabled;
+            self.update_all_party_icons(party_id);
+        }
+    }
+
+    /// Spelaren anföll eller helade för `points`. Motsvarar `Party::updatePlayerTicks`.
+    pub fn update_player_ticks(&mut self, view: &dyn PartyView, player: u32, points: u32, now: u64) {
+        if points == 0 {
+            return;
+        }
+        let Some(&party_id) = self.member_of.get(&player) else {
+            return;
+        };
+        if view.member(player).is_some_and(|info| info.ignore_activity) {
+            return;
+        }
+        self.parties.get_mut(&party_id).expect("party exists").ticks.insert(player, now);
+        self.update_shared_experience(view, party_id, now);
+    }
+
+    fn clear_player_points(&mut self, view: &dyn PartyView, party_id: u32, player: u32, now: u64) {
+        let Some(party) = self.parties.get_mut(&party_id) else {
+            return;
+        };
+        if party.ticks.remove(&player).is_some() {
+            self.update_shared_experience(view, party_id, now);
+        }
+    }
+
+    /// Vilka som ska få erfarenheten när partyt delar. Beloppet räknas ut
+    /// av `Party:onShareExperience` och var och en får det som
+    /// `Player::onGainSharedExperience`. Motsvarar `Party::shareExperience`.
+    pub fn experience_receivers(&self, party_id: u32) -> Vec<u32> {
+        let Some(party) = self.parties.get(&party_id) else {
+            return Vec::new();
+        };
+        party.members.iter().copied().chain([party.leader]).collect()
+    }
+
+    /// Alla i partyt ska se varandras sköldar på nytt, motsvarar `Party::updateAllPartyIcons`
+    fn update_all_party_icons(&mut self, party_id: u32) {
+        let party = &self.parties[&party_id];
+        let leader = party.leader;
+        let members = party.members.clone();
+        for &member in &members {
+            for &other in &members {
+                self.shield(member, other);
+            }
+            self.shield(member, leader);
+            self.shield(leader, member);
+        }
+        self.shield(leader, leader);
+    }
+
+    /// Hur `viewer` ser `player`s sköld. Motsvarar `Player::getPartyShield`.
+    pub fn party_shield(&self, view: &dyn PartyView, viewer: u32, player: u32, now: u64) -> PartyShield {
+        if let Some(party) = self.party_of(viewer) {
+            let shared = |enabled, usable, blink| {
+                if party.shared_exp_enabled {
+                    enabled
+                } else if self.can_use_shared_experience(view, player, now) {
+                    usable
+                } else {
+                    blink
+                }
+            };
+            if party.leader == player {
+                if party.shared_exp_active {
+                    return shared(
+                        PartyShield::YellowSharedExp,
+                        PartyShield::YellowNoSharedExp,
+                        PartyShield::YellowNoSharedExpBlink,
+                    );
+                }
+                return PartyShield::Yellow;
+            }
+            if party.members.contains(&player) {
+                if party.shared_exp_active {
+                    return shared(
+                        PartyShield::BlueSharedExp,
+                        PartyShield::BlueNoSharedExp,
+                        PartyShield::BlueNoSharedExpBlink,
+                    );
+                }
+                return PartyShield::Blue;
+            }
+            if self.is_inviting(viewer, player) {
+                return PartyShield::WhiteBlue;
+            }
+        }
+        if self.is_inviting(player, viewer) {
+            return PartyShield::WhiteYellow;
+        }
+        if self.member_of.contains_key(&player) {
+            return PartyShield::Gray;
+        }
+        PartyShield::None
+    }
+
+    /// Medlemmen öppnar partykanalen. Motsvarar `Chat::createChannel` för
+    /// `CHANNEL_PARTY` och `ChatChannel::addUser`.
+    pub fn open_channel(&mut self, player: u32) -> bool {
+        let Some(&party_id) = self.member_of.get(&player) else {
+            return false;
+        };
+        self.parties.get_mut(&party_id).expect("party exists").channel_users.insert(player)
+    }
+
+    pub fn close_channel(&mut self, player: u32) -> bool {
+        let Some(&party_id) = self.member_of.get(&player) else {
+            return false;
+        };
+        self.parties.get_mut(&party_id).expect("party exists").channel_users.remove(&player)
+    }
+}
+
+fn possessive(sex: PlayerSex) -> &'static str {
+    match sex {
+        PlayerSex::Female => "her",
+        PlayerSex::Male => "his",
+    }
+}
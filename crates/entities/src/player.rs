@@ -0,0 +1,867 @@
+//! Spelaren, motsvarar datadelen av `Player` i TFS. Det som sparas i
+//! databasen finns här; inläsning och sparning sköts av `persistence::ioplayer`.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::net::IpAddr;
+
+use common::Position;
+use items::{AmmoType, Container, Item, Items, WeaponType};
+
+use crate::condition::{icons, Condition, ConditionId, ConditionType};
+use crate::creature::{BlockType, Creature, CreatureType, Outfit, Skull, PLAYER_BASE_SPEED, PLAYER_MAX_SPEED};
+use crate::group::{Group, PlayerFlags};
+use crate::guild::GuildMembership;
+use crate::npc::ShopInfo;
+use crate::vip::{VipError, MAX_VIP_ENTRIES};
+
+/// Utrustningsplatser, `slots_t`
+pub const CONST_SLOT_HEAD: u8 = 1;
+pub const CONST_SLOT_NECKLACE: u8 = 2;
+pub const CONST_SLOT_BACKPACK: u8 = 3;
+pub const CONST_SLOT_ARMOR: u8 = 4;
+pub const CONST_SLOT_RIGHT: u8 = 5;
+pub const CONST_SLOT_LEFT: u8 = 6;
+pub const CONST_SLOT_LEGS: u8 = 7;
+pub const CONST_SLOT_FEET: u8 = 8;
+pub const CONST_SLOT_RING: u8 = 9;
+pub const CONST_SLOT_AMMO: u8 = 10;
+
+pub const CONST_SLOT_FIRST: u8 = CONST_SLOT_HEAD;
+pub const CONST_SLOT_LAST: u8 = CONST_SLOT_AMMO;
+
+/// Högsta depå-id; sid 0-100 i `player_depotitems` är reserverade för kistorna
+pub const MAX_DEPOT_ID: u32 = 100;
+
+/// Motsvarar `skills_t`
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Skill {
+    Fist = 0,
+    Club = 1,
+    Sword = 2,
+    Axe = 3,
+    Distance = 4,
+    Shield = 5,
+    Fishing = 6,
+}
+
+impl Skill {
+    pub const ALL: [Skill; 7] = [
+        Skill::Fist,
+        Skill::Club,
+        Skill::Sword,
+        Skill::Axe,
+        Skill::Distance,
+        Skill::Shield,
+        Skill::Fishing,
+    ];
+
+    pub fn from_u8(value: u8) -> Option<Self> {
+        Self::ALL.get(value as usize).copied()
+    }
+
+    /// Kolumnnamnet i `players`, utan `_tries`
+    pub fn column(self) -> &'static str {
+        match self {
+            Skill::Fist => "skill_fist",
+            Skill::Club => "skill_club",
+            Skill::Sword => "skill_sword",
+            Skill::Axe => "skill_axe",
+            Skill::Distance => "skill_dist",
+            Skill::Shield => "skill_shielding",
+            Skill::Fishing => "skill_fishing",
+        }
+    }
+
+    /// Motsvarar `getSkillName`
+    pub fn name(self) -> &'static str {
+        match self {
+            Skill::Fist => "fist fighting",
+            Skill::Club => "club fighting",
+            Skill::Sword => "sword fighting",
+            Skill::Axe => "axe fighting",
+            Skill::Distance => "distance fighting",
+            Skill::Shield => "shielding",
+            Skill::Fishing => "fishing",
+        }
+    }
+}
+
+/// Motsvarar `stats_t`, det conditions kan höja eller sänka
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Stat {
+    MaxHitPoints = 0,
+    MaxManaPoints = 1,
+    SoulPoints = 2,
+    MagicPoints = 3,
+}
+
+impl Stat {
+    pub const ALL: [Stat; 4] = [Stat::MaxHitPoints, Stat::MaxManaPoints, Stat::SoulPoints, Stat::MagicPoints];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SkillValue {
+    pub level: u16,
+    pub tries: u64,
+    /// Procent till nästa nivå, räknas om när vokationen är känd
+    pub percent: u8,
+}
+
+impl Default for SkillValue {
+    fn default() -> Self {
+        Self { level: 10, tries: 0, percent: 0 }
+    }
+}
+
+/// Motsvarar `PlayerSex_t`
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PlayerSex {
+    #[default]
+    Female = 0,
+    Male = 1,
+}
+
+impl PlayerSex {
+    pub fn from_u8(value: u8) -> Self {
+        if value == 1 {
+            PlayerSex::Male
+        } else {
+            PlayerSex::Female
+        }
+    }
+}
+
+/// Kontots typ i `accounts.type`, motsvarar `AccountType_t`
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum AccountType {
+    #[default]
+    Normal = 1,
+    Tutor = 2,
+    SeniorTutor = 3,
+    GameMaster = 4,
+    CommunityManager = 5,
+    God = 6,
+}
+
+impl AccountType {
+    pub fn from_u8(value: u8) -> Self {
+        match value {
+            2 => AccountType::Tutor,
+            3 => AccountType::SeniorTutor,
+            4 => AccountType::GameMaster,
+            5 => AccountType::CommunityManager,
+            6 => AccountType::God,
+            _ => AccountType::Normal,
+        }
+    }
+}
+
+/// Motsvarar `fightMode_t`
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FightMode {
+    #[default]
+    Attack = 1,
+    Balanced = 2,
+    Defense = 3,
+}
+
+impl FightMode {
+    pub fn from_u8(value: u8) -> Self {
+        match value {
+            2 => FightMode::Balanced,
+            3 => FightMode::Defense,
+            _ => FightMode::Attack,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Player {
+    /// Namn, hälsa, mana, outfit, riktning och skalle
+    pub creature: Creature,
+    pub guid: u32,
+    pub account_id: u32,
+    pub account_type: AccountType,
+    /// Typen ändrades av `Player:setAccountType` och skrivs till kontot när
+    /// spelaren sparas
+    pub account_type_changed: bool,
+    /// Kopia av gruppen i `Groups`, se `has_flag`
+    pub group: Group,
+    pub vocation: u16,
+
+    pub level: u32,
+    pub experience: u64,
+    /// Procent till nästa nivå, räknas om när vokationen är känd
+    pub level_percent: u8,
+    pub mag_level: u32,
+    pub mana_spent: u64,
+    pub mag_level_percent: u8,
+    pub soul: u8,
+    /// I hundradels oz, som i TFS; databasen har hela oz
+    pub capacity: u32,
+    pub skills: [SkillValue; 7],
+    /// Tillägg från conditions, se `set_var_skill`
+    var_skills: [i32; 7],
+    var_stats: [i32; 4],
+
+    pub sex: PlayerSex,
+    pub current_mount: u16,
+    pub randomize_mount: bool,
+    /// När spelaren senast steg upp på eller av hästen, i ms
+    pub last_toggle_mount: u64,
+    /// Hästen försvann när spelaren gick in i en skyddszon och kommer
+    /// tillbaka när den går ut
+    pub was_mounted: bool,
+
+    pub town_id: u32,
+    pub login_position: Position,
+
+    /// Sekunder kvar av fragen, se `rules::skull`
+    pub skull_ticks: i64,
+    /// En bit per blessing, se `has_blessing`
+    pub blessings: u8,
+    pub bank_balance: u64,
+    pub stamina_minutes: u16,
+    /// Millisekunder
+    pub offline_training_time: i32,
+    pub offline_training_skill: i32,
+
+    pub last_login: u64,
+    pub last_logout: u64,
+    pub last_ip: Option<IpAddr>,
+    /// Total speltid i sekunder
+    pub online_time: i64,
+
+    /// Sparade conditions som de ligger i `players.conditions`. Läses och
+    /// skrivs med `rules::condition::{unserialize_conditions, serialize_conditions}`.
+    pub conditions: Vec<u8>,
+
+    /// `None` utanför gillen, eller om gillet eller rangen inte finns
+    pub guild: Option<GuildMembership>,
+
+    /// Från kontot, unix-tid
+    pub premium_ends_at: u64,
+
+    pub fight_mode: FightMode,
+    pub chase_mode: bool,
+    /// Spelaren kan inte anfalla omärkta spelare
+    pub secure_mode: bool,
+    /// Anföll en spelare nyligen och får inte gå in i skyddszoner eller
+    /// logga ut förrän in fight-conditionen gått ut
+    pub pz_locked: bool,
+    /// `otsys_time` för senaste attacken, för försvaret i `FightMode::Attack`
+    pub last_attack: u64,
+    /// Hur senaste träffen blockerades och om den ger skillpoäng.
+    /// Motsvarar `lastAttackBlockType` och `addAttackSkillPoint`.
+    pub last_attack_block_type: BlockType,
+    pub add_attack_skill_point: bool,
+    /// Träffar mot rustning eller sköld som fortfarande ger skillpoäng
+    pub blood_hit_count: u8,
+    /// Spelarna den här spelaren anfallit utan att de anfallit först,
+    /// töms när in fight-conditionen går ut
+    attacked_players: BTreeSet<u32>,
+    /// Guid:arna i kontots VIP-lista
+    vip_list: BTreeSet<u32>,
+
+    inventory: [Option<Item>; CONST_SLOT_LAST as usize + 1],
+    depot_chests: BTreeMap<u32, Container>,
+    pub inbox: Container,
+    pub store_inbox: Container,
+
+    learned_spells: Vec<String>,
+    storage: BTreeMap<u32, i32>,
+    /// looktype -> addons
+    outfits: BTreeMap<u16, u8>,
+    mounts: BTreeSet<u16>,
+
+    /// NPC:n vars handelsfönster spelaren har öppet
+    shop_owner: Option<u32>,
+    shop_item_list: Vec<ShopInfo>,
+}
+
+impl Player {
+    /// Standardvärdena från schema.sql
+    pub fn new(name: impl Into<String>) -> Self {
+        let mut creature = Creature::new(CreatureType::Player, name);
+        creature.health = 150;
+        creature.health_max = 150;
+        creature.outfit = Outfit { look_type: 136, ..Outfit::default() };
+        creature.default_outfit = creature.outfit;
+
+        Self {
+            creature,
+            guid: 0,
+            account_id: 0,
+            account_type: AccountType::Normal,
+            account_type_changed: false,
+            group: Group::default(),
+            vocation: 0,
+            level: 1,
+            experience: 0,
+            level_percent: 0,
+            mag_level: 0,
+            mana_spent: 0,
+            mag_level_percent: 0,
+            soul: 0,
+            capacity: 40000,
+            skills: [SkillValue::default(); 7],
+            var_skills: [0; 7],
+            var_stats: [0; 4],
+            sex: PlayerSex::Female,
+            current_mount: 0,
+            randomize_mount: false,
+            last_toggle_mount: 0,
+            was_mounted: false,
+            town_id: 1,
+            login_position: Position::default(),
+            skull_ticks: 0,
+            blessings: 0,
+            bank_balance: 0,
+            stamina_minutes: 2520,
+            offline_training_time: 43200 * 1000,
+            offline_training_skill: -1,
+            last_login: 0,
+            last_logout: 0,
+            last_ip: None,
+            online_time: 0,
+            conditions: Vec::new(),
+            guild: None,
+            premium_ends_at: 0,
+            fight_mode: FightMode::Attack,
+            chase_mode: false,
+            secure_mode: false,
+            pz_locked: false,
+            last_attack: 0,
+            last_attack_block_type: BlockType::None,
+            add_attack_skill_point: false,
+            blood_hit_count: 0,
+            attacked_players: BTreeSet::new(),
+            vip_list: BTreeSet::new(),
+            inventory: Default::default(),
+            depot_chests: BTreeMap::new(),
+            inbox: Container::new(0),
+            store_inbox: Container::new(0),
+            learned_spells: Vec::new(),
+            storage: BTreeMap::new(),
+            outfits: BTreeMap::new(),
+            mounts: BTreeSet::new(),
+            shop_owner: None,
+            shop_item_list: Vec::new(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.creature.name
+    }
+
+    /// Motsvarar `Player::isPremium`
+    pub fn is_premium(&self, free_premium: bool, now: u64) -> bool {
+        free_premium || self.has_flag(PlayerFlags::IS_ALWAYS_PREMIUM) || self.premium_ends_at > now
+    }
+
+    /// Motsvarar `Player::setAccountType`
+    pub fn set_account_type(&mut self, account_type: AccountType) {
+        self.account_type_changed |= self.account_type != account_type;
+        self.account_type = account_type;
+    }
+
+    /// Gruppen har flaggan, motsvarar `Player::hasFlag`
+    pub fn has_flag(&self, flag: PlayerFlags) -> bool {
+        self.group.flags.intersects(flag)
+    }
+
+    /// Motsvarar `Player::isAccessPlayer`
+    pub fn is_access_player(&self) -> bool {
+        self.group.access
+    }
+
+    /// Motsvarar `Player::isPushable`; spelet kollar också att spelaren
+    /// inte just tagit ett steg
+    pub fn is_pushable(&self) -> bool {
+        !self.has_flag(PlayerFlags::CANNOT_BE_PUSHED)
+    }
+
+    /// Motsvarar `Player::canSeeInvisibility`
+    pub fn can_see_invisibility(&self) -> bool {
+        self.has_flag(PlayerFlags::CAN_SENSE_INVISIBILITY) || self.group.access
+    }
+
+    /// Sekunder kvar av den längsta mute-conditionen, alltid 0 med
+    /// `PlayerFlags::CANNOT_BE_MUTED`. Motsvarar `Player::isMuted`.
+    pub fn is_muted(&self) -> u32 {
+        if self.has_flag(PlayerFlags::CANNOT_BE_MUTED) {
+            return 0;
+        }
+        self.creature
+            .conditions()
+            .iter()
+            .filter(|c| c.condition_type == ConditionType::MUTED)
+            .map(|c| c.ticks.max(0) as u32 / 1000)
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub fn vip_list(&self) -> impl Iterator<Item = u32> + '_ {
+        self.vip_list.iter().copied()
+    }
+
+    pub fn has_vip(&self, guid: u32) -> bool {
+        self.vip_list.contains(&guid)
+    }
+
+    /// Vid inläsning, utan gräns. Motsvarar `Player::addVIPInternal`.
+    pub fn add_vip_internal(&mut self, guid: u32) -> bool {
+        self.vip_list.insert(guid)
+    }
+
+    /// `max_entries` kommer från `VipConfig::max_entries` och `special_vip`
+    /// från `IOLoginData::get_guid_by_name_ex`. Motsvarar kontrollerna i
+    /// `Game::playerRequestAddVip` och `Player::addVIP`; databasen sköts av
+    /// anroparen.
+    pub fn add_vip(&mut self, guid: u32, special_vip: bool, max_entries: usize) -> Result<(), VipError> {
+        if special_vip && !self.has_flag(PlayerFlags::SPECIAL_VIP) {
+            return Err(VipError::NotAllowed);
+        }
+        if self.vip_list.len() >= max_entries.min(MAX_VIP_ENTRIES) {
+            return Err(VipError::ListFull);
+        }
+        if !self.vip_list.insert(guid) {
+            return Err(VipError::AlreadyInList);
+        }
+        Ok(())
+    }
+
+    pub fn remove_vip(&mut self, guid: u32) -> bool {
+        self.vip_list.remove(&guid)
+    }
+
+    pub fn guild_id(&self) -> Option<u32> {
+        self.guild.as_ref().map(|membership| membership.guild_id)
+    }
+
+    /// Erfarenheten som krävs för `level`, motsvarar `Player::getExpForLevel`
+    pub fn get_exp_for_level(level: u32) -> u64 {
+        let level = level as u64;
+        // som i TFS räknas det utan tecken; mellanleden blir negativa för låga nivåer
+        let exp = level.wrapping_sub(6).wrapping_mul(level).wrapping_add(17).wrapping_mul(level).wrapping_sub(12);
+        (exp / 6).wrapping_mul(100)
+    }
+
+    /// Procent av vägen till nästa nivå; 0 om den är bortom. Motsvarar
+    /// `Player::getPercentLevel`.
+    pub fn get_percent_level(count: u64, next_level_count: u64) -> u8 {
+        if next_level_count == 0 {
+            return 0;
+        }
+        let result = count as u128 * 100 / next_level_count as u128;
+        if result > 100 {
+            return 0;
+        }
+        result as u8
+    }
+
+    /// 220 plus två per nivå över ett, eller högsta farten med
+    /// `PlayerFlags::SET_MAX_SPEED`. Motsvarar `Player::updateBaseSpeed`.
+    pub fn update_base_speed(&mut self) {
+        self.creature.base_speed = if self.has_flag(PlayerFlags::SET_MAX_SPEED) {
+            PLAYER_MAX_SPEED
+        } else {
+            PLAYER_BASE_SPEED + 2 * (self.level.max(1) - 1)
+        };
+    }
+
+    pub fn skill(&self, skill: Skill) -> &SkillValue {
+        &self.skills[skill as usize]
+    }
+
+    pub fn skill_mut(&mut self, skill: Skill) -> &mut SkillValue {
+        &mut self.skills[skill as usize]
+    }
+
+    /// Nivån med tilläggen från conditions, motsvarar `Player::getSkillLevel`
+    pub fn skill_level(&self, skill: Skill) -> u16 {
+        (self.skill(skill).level as i32 + self.var_skills[skill as usize]).clamp(0, u16::MAX as i32) as u16
+    }
+
+    /// Motsvarar `Player::setVarSkill`
+    pub fn set_var_skill(&mut self, skill: Skill, modifier: i32) {
+        self.var_skills[skill as usize] += modifier;
+    }
+
+    /// Magisk nivå med tilläggen, motsvarar `Player::getMagicLevel`
+    pub fn magic_level(&self) -> u32 {
+        (self.mag_level as i64 + self.var_stats[Stat::MagicPoints as usize] as i64).max(0) as u32
+    }
+
+    pub fn var_stat(&self, stat: Stat) -> i32 {
+        self.var_stats[stat as usize]
+    }
+
+    /// Högre eller lägre max hälsa och mana hamnar direkt i varelsen, så
+    /// hälsan hålls under det nya maxvärdet. Motsvarar `Player::setVarStats`.
+    pub fn set_var_stats(&mut self, stat: Stat, modifier: i32) {
+        self.var_stats[stat as usize] += modifier;
+        let creature = &mut self.creature;
+        match stat {
+            Stat::MaxHitPoints => {
+                creature.health_max += modifier;
+                creature.health = creature.health.min(creature.health_max.max(1));
+            }
+            Stat::MaxManaPoints => {
+                creature.mana_max = (creature.mana_max as i64 + modifier as i64).max(0) as u32;
+                creature.mana = creature.mana.min(creature.mana_max);
+            }
+            _ => {}
+        }
+    }
+
+    /// Max hälsa och mana utan conditions, det som sparas
+    pub fn base_health_max(&self) -> i32 {
+        self.creature.health_max - self.var_stats[Stat::MaxHitPoints as usize]
+    }
+
+    pub fn base_mana_max(&self) -> u32 {
+        (self.creature.mana_max as i64 - self.var_stats[Stat::MaxManaPoints as usize] as i64).max(0) as u32
+    }
+
+    pub fn inventory_item(&self, slot: u8) -> Option<&Item> {
+        self.inventory.get(slot as usize)?.as_ref()
+    }
+
+    pub fn inventory_item_mut(&mut self, slot: u8) -> Option<&mut Item> {
+        self.inventory.get_mut(slot as usize)?.as_mut()
+    }
+
+    /// Skölden och vapnet i händerna. Motsvarar `Player::getShieldAndWeapon`.
+    pub fn get_shield_and_weapon(&self) -> (Option<&Item>, Option<&Item>) {
+        let mut shield = None;
+        let mut weapon = None;
+        for slot in [CONST_SLOT_LEFT, CONST_SLOT_RIGHT] {
+            let Some(item) = self.inventory_item(slot) else {
+                continue;
+            };
+            match item.weapon_type() {
+                WeaponType::None => {}
+                WeaponType::Shield => {
+                    if shield.is_none_or(|s: &Item| item.get_defense() > s.get_defense()) {
+                        shield = Some(item);
+                    }
+                }
+                _ => weapon = Some(item),
+            }
+        }
+        (shield, weapon)
+    }
+
+    /// Platsen för vapnet spelaren anfaller med. Ett distansvapen som
+    /// skjuter ammunition ger ammunitionsplatsen, eller inget om fel sort
+    /// ligger där, om inte `ignore_ammo`. Motsvarar `Player::getWeapon`.
+    pub fn get_weapon_slot(&self, ignore_ammo: bool) -> Option<u8> {
+        [CONST_SLOT_LEFT, CONST_SLOT_RIGHT].into_iter().find_map(|slot| {
+            let item = self.inventory_item(slot)?;
+            match item.weapon_type() {
+                WeaponType::None | WeaponType::Shield | WeaponType::Ammo => None,
+                WeaponType::Distance if !ignore_ammo && item.ammo_type() != AmmoType::None => {
+                    let ammo = self.inventory_item(CONST_SLOT_AMMO)?;
+                    (ammo.ammo_type() == item.ammo_type()).then_some(CONST_SLOT_AMMO)
+                }
+                _ => Some(slot),
+            }
+        })
+    }
+
+    pub fn get_weapon(&self, ignore_ammo: bool) -> Option<&Item> {
+        self.inventory_item(self.get_weapon_slot(ignore_ammo)?)
+    }
+
+    /// Motsvarar `Player::getWeaponType`
+    pub fn get_weapon_type(&self) -> WeaponType {
+        self.get_weapon(false).map_or(WeaponType::None, |item| item.weapon_type())
+    }
+
+    /// Hur mycket stridsläget delar skadan med, `Player::getAttackFactor`
+    pub fn get_attack_factor(&self) -> f32 {
+        match self.fight_mode {
+            FightMode::Attack => 1.0,
+            FightMode::Balanced => 1.2,
+            FightMode::Defense => 2.0,
+        }
+    }
+
+    /// Målet blockerade spelarens träff. Var trettionde träff måste gå
+    /// igenom för att rustning och sköld ska fortsätta ge skillpoäng.
+    /// Motsvarar `Player::onAttackedCreatureBlockHit`.
+    pub fn on_attacked_creature_block_hit(&mut self, block_type: BlockType) {
+        self.last_attack_block_type = block_type;
+        self.add_attack_skill_point = match block_type {
+            BlockType::None => {
+                self.blood_hit_count = 30;
+                true
+            }
+            BlockType::Defense | BlockType::Armor if self.blood_hit_count > 0 => {
+                self.blood_hit_count -= 1;
+                true
+            }
+            _ => false,
+        };
+    }
+
+    /// Skillen vapnet använder, 0 för sådant som inte har någon.
+    /// Motsvarar `Player::getWeaponSkill`.
+    pub fn get_weapon_skill(&self, item: Option<&Item>) -> u16 {
+        let Some(item) = item else {
+            return self.skill_level(Skill::Fist);
+        };
+        match item.weapon_type() {
+            WeaponType::Sword => self.skill_level(Skill::Sword),
+            WeaponType::Club => self.skill_level(Skill::Club),
+            WeaponType::Axe => self.skill_level(Skill::Axe),
+            WeaponType::Distance => self.skill_level(Skill::Distance),
+            _ => 0,
+        }
+    }
+
+    /// Ge spelaren in fight-conditionen i `ticks` ms, och pz-lås med
+    /// `pz_lock`. Motsvarar `Player::addInFightTicks`.
+    pub fn add_in_fight_ticks(&mut self, pz_lock: bool, ticks: i32) {
+        if pz_lock {
+            self.pz_locked = true;
+        }
+        self.creature.add_condition(Condition::new(ConditionId::Default, ConditionType::INFIGHT, ticks));
+    }
+
+    /// En condition har gått ut eller tagits bort. När striden är över
+    /// försvinner en vit skalle; röd och svart sitter kvar tills fragen gått
+    /// ut. Returnerar true om skallen ändrades. Motsvarar
+    /// `Player::onEndCondition`.
+    pub fn on_end_condition(&mut self, condition_type: ConditionType) -> bool {
+        if condition_type != ConditionType::INFIGHT {
+            return false;
+        }
+        self.creature.on_idle_status();
+        self.pz_locked = false;
+        self.attacked_players.clear();
+        if matches!(self.creature.skull, Skull::None | Skull::Red | Skull::Black) {
+            return false;
+        }
+        self.creature.skull = Skull::None;
+        true
+    }
+
+    /// Motsvarar `Player::hasAttacked`
+    pub fn has_attacked(&self, player: u32) -> bool {
+        self.attacked_players.contains(&player)
+    }
+
+    pub fn add_attacked(&mut self, player: u32) {
+        self.attacked_players.insert(player);
+    }
+
+    pub fn remove_attacked(&mut self, player: u32) {
+        self.attacked_players.remove(&player);
+    }
+
+    /// Blessingen med biten `value`; 0 till 4 är de fem vanliga och 5 är
+    /// twist of fate. Lua räknar från 1.
+    pub fn has_blessing(&self, value: u8) -> bool {
+        value < 8 && self.blessings & (1 << value) != 0
+    }
+
+    pub fn add_blessing(&mut self, value: u8) {
+        if value < 8 {
+            self.blessings |= 1 << value;
+        }
+    }
+
+    pub fn remove_blessing(&mut self, value: u8) {
+        if value < 8 {
+            self.blessings &= !(1 << value);
+        }
+    }
+
+    /// Conditionernas ikoner, röda svärd när spelaren är pz-låst och duvan
+    /// i skyddszon. Motsvarar `Player::getClientIcons`.
+    pub fn client_icons(&self, in_protection_zone: bool) -> u16 {
+        let mut icons = self.creature.conditions().iter().fold(0, |icons, c| icons | c.icons());
+        if self.pz_locked {
+            icons |= icons::REDSWORDS;
+        }
+        if in_protection_zone {
+            icons = (icons | icons::PIGEON) & !icons::SWORDS;
+        }
+        icons
+    }
+
+    /// Vikten av allt spelaren bär, i hundradels oz
+    pub fn inventory_weight(&self) -> u32 {
+        self.inventory().map(|(_, item)| item.get_weight()).sum()
+    }
+
+    /// Det spelaren kan bära till, obegränsat med
+    /// `PlayerFlags::HAS_INFINITE_CAPACITY`. Motsvarar `Player::getFreeCapacity`.
+    pub fn free_capacity(&self) -> u32 {
+        if self.has_flag(PlayerFlags::HAS_INFINITE_CAPACITY) {
+            return u32::MAX;
+        }
+        self.capacity.saturating_sub(self.inventory_weight())
+    }
+
+    /// Spelaren orkar bära itemet. Med `PlayerFlags::CANNOT_PICKUP_ITEM` kan
+    /// den inte ta upp något alls. Motsvarar `Player::hasCapacity`.
+    pub fn has_capacity(&self, item: &Item) -> bool {
+        !self.has_flag(PlayerFlags::CANNOT_PICKUP_ITEM) && item.get_weight() <= self.free_capacity()
+    }
+
+    /// Lägg ett item i en plats och få tillbaka det som låg där.
+    /// Platser utanför `CONST_SLOT_FIRST..=CONST_SLOT_LAST` ger tillbaka itemet.
+    pub fn set_inventory_item(&mut self, slot: u8, item: Option<Item>) -> Option<Item> {
+        if !(CONST_SLOT_FIRST..=CONST_SLOT_LAST).contains(&slot) {
+            return item;
+        }
+        std::mem::replace(&mut self.inventory[slot as usize], item)
+    }
+
+    /// Utrustningen med platsnummer, i platsordning
+    pub fn inventory(&self) -> impl Iterator<Item = (u8, &Item)> {
+        (CONST_SLOT_FIRST..=CONST_SLOT_LAST).filter_map(|slot| Some((slot, self.inventory_item(slot)?)))
+    }
+
+    /// Motsvarar `Player::getDepotChest`
+    pub fn get_depot_chest(&mut self, depot_id: u32, auto_create: bool) -> Option<&mut Container> {
+        if auto_create && depot_id <= MAX_DEPOT_ID {
+            return Some(self.depot_chests.entry(depot_id).or_default());
+        }
+        self.depot_chests.get_mut(&depot_id)
+    }
+
+    pub fn depot_chests(&self) -> impl Iterator<Item = (u32, &Container)> {
+        self.depot_chests.iter().map(|(&id, chest)| (id, chest))
+    }
+
+    pub fn has_learned_instant_spell(&self, name: &str) -> bool {
+        self.learned_spells.iter().any(|s| s.eq_ignore_ascii_case(name))
+    }
+
+    pub fn learn_instant_spell(&mut self, name: &str) {
+        if !self.has_learned_instant_spell(name) {
+            self.learned_spells.push(name.to_string());
+        }
+    }
+
+    pub fn forget_instant_spell(&mut self, name: &str) {
+        self.learned_spells.retain(|s| !s.eq_ignore_ascii_case(name));
+    }
+
+    pub fn learned_spells(&self) -> &[String] {
+        &self.learned_spells
+    }
+
+    pub fn get_storage_value(&self, key: u32) -> Option<i32> {
+        self.storage.get(&key).copied()
+    }
+
+    /// None tar bort nyckeln, som `setStorageValue(key, -1)` i scripten
+    pub fn add_storage_value(&mut self, key: u32, value: Option<i32>) {
+        match value {
+            Some(value) => {
+                self.storage.insert(key, value);
+            }
+            None => {
+                self.storage.remove(&key);
+            }
+        }
+    }
+
+    pub fn storage(&self) -> impl Iterator<Item = (u32, i32)> + '_ {
+        self.storage.iter().map(|(&key, &value)| (key, value))
+    }
+
+    /// Addons läggs till de spelaren redan har. Motsvarar `Player::addOutfit`.
+    pub fn add_outfit(&mut self, look_type: u16, addons: u8) {
+        *self.outfits.entry(look_type).or_default() |= addons;
+    }
+
+    pub fn remove_outfit(&mut self, look_type: u16) -> bool {
+        self.outfits.remove(&look_type).is_some()
+    }
+
+    /// Motsvarar `Player::removeOutfitAddon`, false om spelaren inte har outfiten
+    pub fn remove_outfit_addon(&mut self, look_type: u16, addons: u8) -> bool {
+        match self.outfits.get_mut(&look_type) {
+            Some(current) => {
+                *current &= !addons;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Addons för en outfit spelaren har, None om den saknas
+    pub fn outfit_addons(&self, look_type: u16) -> Option<u8> {
+        self.outfits.get(&look_type).copied()
+    }
+
+    pub fn outfits(&self) -> impl Iterator<Item = (u16, u8)> + '_ {
+        self.outfits.iter().map(|(&look_type, &addons)| (look_type, addons))
+    }
+
+    pub fn tame_mount(&mut self, mount_id: u16) -> bool {
+        self.mounts.insert(mount_id)
+    }
+
+    /// Tar inte bort hästens fart, se `rules::outfit::untame_mount`
+    pub fn untame_mount(&mut self, mount_id: u16) -> bool {
+        if self.current_mount == mount_id {
+            self.current_mount = 0;
+        }
+        self.mounts.remove(&mount_id)
+    }
+
+    /// Spelaren sitter på sin häst, motsvarar `Player::isMounted`
+    pub fn is_mounted(&self) -> bool {
+        self.creature.default_outfit.look_mount != 0
+    }
+
+    pub fn has_mount(&self, mount_id: u16) -> bool {
+        self.mounts.contains(&mount_id)
+    }
+
+    pub fn mounts(&self) -> impl Iterator<Item = u16> + '_ {
+        self.mounts.iter().copied()
+    }
+
+    pub fn shop_owner(&self) -> Option<u32> {
+        self.shop_owner
+    }
+
+    pub fn shop_item_list(&self) -> &[ShopInfo] {
+        &self.shop_item_list
+    }
+
+    /// Motsvarar `Player::openShopWindow` minus paketet
+    pub fn open_shop_window(&mut self, npc_id: u32, items: Vec<ShopInfo>) {
+        self.shop_owner = Some(npc_id);
+        self.shop_item_list = items;
+    }
+
+    /// Returnerar NPC:n vars fönster stängdes. Motsvarar `Player::closeShopWindow`
+    /// minus anropet till NPC:n och paketet.
+    pub fn close_shop_window(&mut self) -> Option<u32> {
+        self.shop_item_list.clear();
+        self.shop_owner.take()
+    }
+
+    /// Säljer butiken varan? För vätskor måste även sorten stämma.
+    /// Motsvarar `Player::hasShopItemForSale`.
+    pub fn has_shop_item_for_sale(&self, item_id: u16, sub_type: u8) -> bool {
+        let fluid = Items::get(item_id).is_fluid_container();
+        self.shop_item_list.iter().any(|info| {
+            info.item_id == item_id && info.buy_price != 0 && (!fluid || info.sub_type == sub_type as i32)
+        })
+    }
+}
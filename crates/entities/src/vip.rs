@@ -0,0 +1,112 @@
+//! VIP-listan, motsvarar VIP-delarna av `Player`, `Game` och `IOLoginData`
+//! i TFS. Listan hör till kontot (`account_viplist`); spelaren har bara
+//! guid:arna, beskrivning, ikon och notify läses från databasen när
+//! listan skickas vid inloggning.
+
+use common::Config;
+
+use crate::player::Player;
+
+/// Högsta ikonen klienten har, `VIPICON_LAST`
+pub const VIP_ICON_LAST: u32 = 10;
+pub const VIP_DESCRIPTION_LENGTH: usize = 128;
+/// Klienten visar inte fler, oavsett grupp och premium
+pub const MAX_VIP_ENTRIES: usize = 200;
+
+/// Motsvarar `VipStatus_t`
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VipStatus {
+    Offline = 0,
+    Online = 1,
+    Pending = 2,
+}
+
+impl VipStatus {
+    /// Texten till den som har spelaren i listan, motsvarar `Player::notifyStatusChange`
+    pub fn message(self, name: &str) -> Option<String> {
+        match self {
+            VipStatus::Online => Some(format!("{name} has logged in.")),
+            VipStatus::Offline => Some(format!("{name} has logged out.")),
+            VipStatus::Pending => None,
+        }
+    }
+}
+
+/// En rad i `account_viplist` med spelarens namn, motsvarar `VIPEntry`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VipEntry {
+    pub guid: u32,
+    pub name: String,
+    pub description: String,
+    pub icon: u32,
+    pub notify: bool,
+}
+
+/// Varför en spelare inte kunde läggas till
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VipError {
+    ListFull,
+    AlreadyInList,
+    /// Ingen spelare med namnet
+    NotFound,
+    /// Spelaren har `PlayerFlag_SpecialVIP`, vilket bara andra med flaggan
+    /// får lägga till
+    NotAllowed,
+}
+
+impl VipError {
+    pub fn message(self) -> &'static str {
+        match self {
+            VipError::ListFull => "You cannot add more buddies.",
+            VipError::AlreadyInList => "This player is already in your list.",
+            VipError::NotFound => "A player with this name does not exist.",
+            VipError::NotAllowed => "You can not add this player.",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct VipConfig {
+    pub free_limit: u32,
+    pub premium_limit: u32,
+    pub free_premium: bool,
+}
+
+impl Default for VipConfig {
+    fn default() -> Self {
+        Self { free_limit: 20, premium_limit: 100, free_premium: false }
+    }
+}
+
+impl VipConfig {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            free_limit: config.vip_free_limit.max(0) as u32,
+            premium_limit: config.vip_premium_limit.max(0) as u32,
+            free_premium: config.free_premium,
+        }
+    }
+
+    /// Gruppens `maxvipentries` om den är satt, annars gränsen för fritt
+    /// eller premiumkonto. Motsvarar `Player::getMaxVIPEntries`.
+    pub fn max_entries(&self, player: &Player, now: u64) -> usize {
+        let limit = if player.group.max_vip_entries != 0 {
+            player.group.max_vip_entries
+        } else if player.is_premium(self.free_premium, now) {
+            self.premium_limit
+        } else {
+            self.free_limit
+        };
+        (limit as usize).min(MAX_VIP_ENTRIES)
+    }
+}
+
+/// Spelarna som har `guid` i sin lista och ska få veta att den loggat in
+/// eller ut, som loopen i `Game::updatePlayerStatus`. Spelaren själv räknas
+/// inte.
+pub fn vip_watchers<'a>(players: impl Iterator<Item = &'a Player> + 'a, guid: u32) -> impl Iterator<Item = u32> + 'a {
+    players
+        .filter(move |player| player.guid != guid && player.has_vip(guid))
+        .map(|player| player.creature.id)
+}
@@ -0,0 +1,71 @@
+use crate::item::Item;
+
+/// Innehållet i en container-item. Index 0 är överst, som i klienten.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Container {
+    pub capacity: u16,
+    items: Vec<Item>,
+}
+
+impl Container {
+    pub fn new(capacity: u16) -> Self {
+        Self {
+            capacity,
+            items: Vec::new(),
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.capacity != 0 && self.items.len() >= self.capacity as usize
+    }
+
+    pub fn items(&self) -> &[Item] {
+        &self.items
+    }
+
+    pub fn items_mut(&mut self) -> &mut Vec<Item> {
+        &mut self.items
+    }
+
+    pub fn get_item(&self, index: usize) -> Option<&Item> {
+        self.items.get(index)
+    }
+
+    /// Lägg överst (index 0), som när man släpper något i en backpack
+    pub fn add_item_front(&mut self, item: Item) {
+        self.items.insert(0, item);
+    }
+
+    /// Lägg sist, används vid laddning från databasen
+    pub fn add_item_back(&mut self, item: Item) {
+        self.items.push(item);
+    }
+
+    pub fn remove_item(&mut self, index: usize) -> Option<Item> {
+        if index < self.items.len() {
+            Some(self.items.remove(index))
+        } else {
+            None
+        }
+    }
+
+    pub fn get_weight(&self) -> u32 {
+        self.items.iter().map(|i| i.get_weight()).sum()
+    }
+
+    /// Antal items rekursivt (motsvarar `getItemHoldingCount`)
+    pub fn get_item_holding_count(&self) -> u32 {
+        self.items
+            .iter()
+            .map(|i| 1 + i.get_container().map(|c| c.get_item_holding_count()).unwrap_or(0))
+            .sum()
+    }
+}
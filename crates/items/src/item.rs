@@ -0,0 +1,446 @@
+use common::{CombatType, ShootType};
+use once_cell::sync::Lazy;
+
+use crate::container::Container;
+use crate::loader::Items;
+
+static NO_ATTRIBUTES: Lazy<ItemAttributes> = Lazy::new(ItemAttributes::default);
+
+/// Motsvarar `itemgroup_t` i TFS
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ItemGroup {
+    #[default]
+    None,
+    Ground,
+    Container,
+    Weapon,
+    Ammunition,
+    Armor,
+    Charges,
+    Teleport,
+    MagicField,
+    Writeable,
+    Key,
+    Splash,
+    Fluid,
+    Door,
+    Deprecated,
+}
+
+/// Motsvarar `ItemTypes_t` i TFS (sätts från `type` i items.xml)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ItemKind {
+    #[default]
+    None,
+    Depot,
+    Mailbox,
+    TrashHolder,
+    Container,
+    Door,
+    MagicField,
+    Teleport,
+    Bed,
+    Key,
+    Rune,
+}
+
+/// Motsvarar `WeaponType_t`, sätts från `weaponType` i items.xml
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WeaponType {
+    #[default]
+    None,
+    Sword,
+    Club,
+    Axe,
+    Shield,
+    Distance,
+    Wand,
+    Ammo,
+}
+
+/// Motsvarar `Ammo_t`. Ett distansvapen med ammunitionstyp skjuter bara
+/// ammunition av samma typ.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AmmoType {
+    #[default]
+    None,
+    Bolt,
+    Arrow,
+    Spear,
+    ThrowingStar,
+    ThrowingKnife,
+    Stone,
+    Snowball,
+}
+
+impl AmmoType {
+    /// `ammoType` i items.xml, motsvarar `getAmmoType` i tools.cpp
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name.to_ascii_lowercase().as_str() {
+            "spear" => AmmoType::Spear,
+            "bolt" => AmmoType::Bolt,
+            "arrow" => AmmoType::Arrow,
+            "poisonarrow" => AmmoType::Arrow,
+            "burstarrow" => AmmoType::Arrow,
+            "throwingstar" => AmmoType::ThrowingStar,
+            "throwingknife" => AmmoType::ThrowingKnife,
+            "smallstone" => AmmoType::Stone,
+            "largerock" => AmmoType::Stone,
+            "snowball" => AmmoType::Snowball,
+            "powerbolt" => AmmoType::Bolt,
+            "infernalbolt" => AmmoType::Bolt,
+            "huntingspear" => AmmoType::Spear,
+            "enchantedspear" => AmmoType::Spear,
+            "royalspear" => AmmoType::Spear,
+            "sniperarrow" => AmmoType::Arrow,
+            "onyxarrow" => AmmoType::Arrow,
+            "piercingbolt" => AmmoType::Bolt,
+            "etherealspear" => AmmoType::Spear,
+            "flasharrow" => AmmoType::Arrow,
+            "flammingarrow" => AmmoType::Arrow,
+            "shiverarrow" => AmmoType::Arrow,
+            "eartharrow" => AmmoType::Arrow,
+            _ => return None,
+        })
+    }
+}
+
+/// Floor change-bitar, samma värden som `TILESTATE_FLOORCHANGE_*` i TFS
+pub mod floor_change {
+    pub const DOWN: u8 = 1 << 0;
+    pub const NORTH: u8 = 1 << 1;
+    pub const SOUTH: u8 = 1 << 2;
+    pub const EAST: u8 = 1 << 3;
+    pub const WEST: u8 = 1 << 4;
+    pub const SOUTH_ALT: u8 = 1 << 5;
+    pub const EAST_ALT: u8 = 1 << 6;
+}
+
+/// Statisk data för ett item-id (items.otb + items.xml)
+#[derive(Debug, Clone, Default)]
+pub struct ItemType {
+    pub id: u16,
+    pub client_id: u16,
+    pub name: String,
+    pub article: String,
+    pub plural_name: String,
+    pub description: String,
+
+    pub group: ItemGroup,
+    pub kind: ItemKind,
+
+    pub weight: u32,
+    /// Markhastighet (bara för ground)
+    pub speed: u16,
+    pub light_level: u8,
+    pub light_color: u8,
+
+    pub always_on_top: bool,
+    pub top_order: u8,
+    pub block_solid: bool,
+    pub block_projectile: bool,
+    pub block_path_find: bool,
+    pub has_height: bool,
+    pub moveable: bool,
+    pub pickupable: bool,
+    pub stackable: bool,
+    pub hangable: bool,
+    pub is_vertical: bool,
+    pub is_horizontal: bool,
+    pub allow_pickupable: bool,
+    pub force_serialize: bool,
+    pub can_write_text: bool,
+    pub floor_change: u8,
+
+    pub charges: u32,
+    pub max_items: u16,
+    pub decay_to: i32,
+    pub decay_time: u32,
+    pub show_duration: bool,
+    pub rotate_to: u16,
+
+    /// Från items.xml, 0 om itemet inte har värdet
+    pub weapon_type: WeaponType,
+    pub attack: i32,
+    pub defense: i32,
+    pub extra_defense: i32,
+    pub armor: i32,
+    pub ammo_type: AmmoType,
+    /// Projektilen distansvapen och wands skjuter
+    pub shoot_type: ShootType,
+    /// 0 räknas som 1, standardvärdet i TFS
+    pub shoot_range: u8,
+    /// Procent, 0 låter avståndet och skillen avgöra
+    pub hit_chance: i8,
+    /// Tak för träffchansen; `None` ger 90 för vapen med ammunition och annars 75
+    pub max_hit_chance: Option<u32>,
+    /// Elementskadan närstrids- och distansvapen lägger till (abilities)
+    pub element_type: CombatType,
+    pub element_damage: u16,
+}
+
+impl ItemType {
+    pub fn is_ground(&self) -> bool {
+        self.group == ItemGroup::Ground
+    }
+
+    pub fn is_container(&self) -> bool {
+        self.group == ItemGroup::Container
+    }
+
+    pub fn is_splash(&self) -> bool {
+        self.group == ItemGroup::Splash
+    }
+
+    pub fn is_fluid_container(&self) -> bool {
+        self.group == ItemGroup::Fluid
+    }
+
+    pub fn is_teleport(&self) -> bool {
+        self.kind == ItemKind::Teleport
+    }
+
+    pub fn is_magic_field(&self) -> bool {
+        self.kind == ItemKind::MagicField
+    }
+
+    pub fn is_mailbox(&self) -> bool {
+        self.kind == ItemKind::Mailbox
+    }
+
+    pub fn is_trash_holder(&self) -> bool {
+        self.kind == ItemKind::TrashHolder
+    }
+
+    pub fn is_bed(&self) -> bool {
+        self.kind == ItemKind::Bed
+    }
+
+    pub fn is_depot(&self) -> bool {
+        self.kind == ItemKind::Depot
+    }
+
+    pub fn is_door(&self) -> bool {
+        self.kind == ItemKind::Door
+    }
+
+    pub fn is_rune(&self) -> bool {
+        self.kind == ItemKind::Rune
+    }
+
+    pub fn has_sub_type(&self) -> bool {
+        self.is_fluid_container() || self.is_splash() || self.stackable || self.charges != 0
+    }
+}
+
+/// Egenskaper som frågas av tile/pathfinding, motsvarar `ITEMPROPERTY` i TFS
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemProperty {
+    BlockSolid,
+    HasHeight,
+    BlockProjectile,
+    BlockPath,
+    IsVertical,
+    IsHorizontal,
+    Moveable,
+    ImmovableBlockSolid,
+    ImmovableBlockPath,
+    ImmovableNoFieldBlockPath,
+    NoFieldBlockPath,
+    SupportHangable,
+}
+
+/// Dynamiska attribut per item-instans (`ItemAttributes` i TFS)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ItemAttributes {
+    pub action_id: u16,
+    pub unique_id: u16,
+    pub text: Option<String>,
+    pub written_date: u32,
+    pub writer: Option<String>,
+    pub description: Option<String>,
+    pub charges: u16,
+    pub duration: i32,
+    pub decaying: u8,
+    pub corpse_owner: u32,
+    /// Husdörrens id (ATTR_HOUSEDOORID), kopplar dörren till en access-lista
+    pub door_id: u8,
+    pub depot_id: u16,
+    pub sleeper_guid: u32,
+    pub sleep_start: u32,
+    pub tele_dest: Option<common::Position>,
+    pub name: Option<String>,
+    pub article: Option<String>,
+    pub plural_name: Option<String>,
+    pub weight: Option<u32>,
+    pub attack: Option<i32>,
+    pub defense: Option<i32>,
+    pub extra_defense: Option<i32>,
+    pub armor: Option<i32>,
+    pub hit_chance: Option<i8>,
+    pub shoot_range: Option<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Item {
+    pub id: u16,
+    /// count för stackbara, fluid-typ för fluids, annars charges
+    pub count: u16,
+    /// Allokeras först när något attribut sätts; de flesta items på kartan har inga
+    attributes: Option<Box<ItemAttributes>>,
+    pub container: Option<Box<Container>>,
+}
+
+impl Item {
+    /// Motsvarar `Item::CreateItem`
+    pub fn new(id: u16, count: u16) -> Self {
+        let it = Items::get(id);
+        let count = if it.stackable { count.max(1) } else { count };
+        let mut item = Self {
+            id,
+            count,
+            attributes: None,
+            container: None,
+        };
+        if it.is_container() || it.is_depot() {
+            item.container = Some(Box::new(Container::new(it.max_items)));
+        }
+        if it.charges != 0 && count == 0 {
+            item.attributes_mut().charges = it.charges as u16;
+        }
+        item
+    }
+
+    pub fn attributes(&self) -> &ItemAttributes {
+        self.attributes.as_deref().unwrap_or(&NO_ATTRIBUTES)
+    }
+
+    pub fn attributes_mut(&mut self) -> &mut ItemAttributes {
+        self.attributes.get_or_insert_with(Default::default)
+    }
+
+    pub fn has_attributes(&self) -> bool {
+        self.attributes.as_deref().is_some_and(|a| *a != *NO_ATTRIBUTES)
+    }
+
+    pub fn item_type(&self) -> &'static ItemType {
+        Items::get(self.id)
+    }
+
+    pub fn is_always_on_top(&self) -> bool {
+        self.item_type().always_on_top
+    }
+
+    pub fn top_order(&self) -> u8 {
+        self.item_type().top_order
+    }
+
+    pub fn is_ground(&self) -> bool {
+        self.item_type().is_ground()
+    }
+
+    pub fn is_stackable(&self) -> bool {
+        self.item_type().stackable
+    }
+
+    pub fn get_container(&self) -> Option<&Container> {
+        self.container.as_deref()
+    }
+
+    pub fn get_container_mut(&mut self) -> Option<&mut Container> {
+        self.container.as_deref_mut()
+    }
+
+    /// Count som klienten ser: stackcount, fluid eller charges
+    pub fn get_sub_type(&self) -> u16 {
+        let it = self.item_type();
+        let uses_count = it.is_fluid_container() || it.is_splash() || it.stackable;
+        if !uses_count && it.charges != 0 {
+            self.attributes().charges
+        } else {
+            self.count
+        }
+    }
+
+    /// Motsvarar `Item::setSubType`
+    pub fn set_sub_type(&mut self, n: u16) {
+        let it = self.item_type();
+        let uses_count = it.is_fluid_container() || it.is_splash() || it.stackable;
+        if !uses_count && it.charges != 0 {
+            self.attributes_mut().charges = n;
+        } else {
+            self.count = n;
+        }
+    }
+
+    pub fn get_weight(&self) -> u32 {
+        let base = self.attributes().weight.unwrap_or(self.item_type().weight);
+        let own = if self.is_stackable() { base * self.count.max(1) as u32 } else { base };
+        own + self.container.as_ref().map(|c| c.get_weight()).unwrap_or(0)
+    }
+
+    /// Itemets eget värde om det satts, annars typens. Motsvarar `Item::getAttack`.
+    pub fn get_attack(&self) -> i32 {
+        self.attributes().attack.unwrap_or(self.item_type().attack)
+    }
+
+    pub fn get_defense(&self) -> i32 {
+        self.attributes().defense.unwrap_or(self.item_type().defense)
+    }
+
+    pub fn get_extra_defense(&self) -> i32 {
+        self.attributes().extra_defense.unwrap_or(self.item_type().extra_defense)
+    }
+
+    pub fn get_armor(&self) -> i32 {
+        self.attributes().armor.unwrap_or(self.item_type().armor)
+    }
+
+    pub fn weapon_type(&self) -> WeaponType {
+        self.item_type().weapon_type
+    }
+
+    pub fn ammo_type(&self) -> AmmoType {
+        self.item_type().ammo_type
+    }
+
+    /// Motsvarar `Item::getShootRange`
+    pub fn get_shoot_range(&self) -> u8 {
+        self.attributes().shoot_range.unwrap_or(self.item_type().shoot_range).max(1)
+    }
+
+    pub fn get_hit_chance(&self) -> i8 {
+        self.attributes().hit_chance.unwrap_or(self.item_type().hit_chance)
+    }
+
+    /// Antalet för stackbara, annars 1. Motsvarar `Item::getItemCount`.
+    pub fn get_item_count(&self) -> u16 {
+        if self.is_stackable() {
+            self.count.max(1)
+        } else {
+            1
+        }
+    }
+
+    pub fn has_property(&self, prop: ItemProperty) -> bool {
+        let it = self.item_type();
+        let unique_id = self.attributes().unique_id;
+        let immovable = !it.moveable || unique_id != 0;
+        match prop {
+            ItemProperty::BlockSolid => it.block_solid,
+            ItemProperty::Moveable => it.moveable && unique_id == 0,
+            ItemProperty::HasHeight => it.has_height,
+            ItemProperty::BlockProjectile => it.block_projectile,
+            ItemProperty::BlockPath => it.block_path_find,
+            ItemProperty::IsVertical => it.is_vertical,
+            ItemProperty::IsHorizontal => it.is_horizontal,
+            ItemProperty::ImmovableBlockSolid => it.block_solid && immovable,
+            ItemProperty::ImmovableBlockPath => it.block_path_find && immovable,
+            ItemProperty::NoFieldBlockPath => !it.is_magic_field() && it.block_path_find,
+            ItemProperty::ImmovableNoFieldBlockPath => {
+                !it.is_magic_field() && it.block_path_find && immovable
+            }
+            ItemProperty::SupportHangable => it.is_horizontal || it.is_vertical,
+        }
+    }
+}
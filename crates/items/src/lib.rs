@@ -1,3 +1,8 @@
 pub mod item;
 pub mod container;
 pub mod loader;
+pub mod serialize;
+
+pub use container::Container;
+pub use item::{AmmoType, Item, ItemType, WeaponType};
+pub use loader::Items;
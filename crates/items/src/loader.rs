@@ -0,0 +1,226 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use common::tracing::warn;
+use common::{OtbLoader, OtbNode, PropStream};
+use once_cell::sync::{Lazy, OnceCell};
+
+use crate::item::{floor_change, ItemGroup, ItemKind, ItemType};
+
+static ITEMS: OnceCell<Items> = OnceCell::new();
+static UNKNOWN: Lazy<ItemType> = Lazy::new(ItemType::default);
+
+/// Item-registret, motsvarar `Items` / `Item::items` i TFS
+#[derive(Default)]
+pub struct Items {
+    /// items.otb-versionen (major, minor, build)
+    pub major_version: u32,
+    pub minor_version: u32,
+    pub build_number: u32,
+    types: Vec<ItemType>,
+    client_ids: HashMap<u16, u16>,
+    names: HashMap<String, u16>,
+}
+
+impl Items {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, it: ItemType) {
+        let id = it.id as usize;
+        if self.types.len() <= id {
+            self.types.resize_with(id + 1, ItemType::default);
+        }
+        if it.client_id != 0 {
+            self.client_ids.insert(it.client_id, it.id);
+        }
+        if !it.name.is_empty() {
+            self.names.entry(it.name.to_lowercase()).or_insert(it.id);
+        }
+        self.types[id] = it;
+    }
+
+    pub fn len(&self) -> usize {
+        self.types.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.types.is_empty()
+    }
+
+    /// Gör registret globalt. Kan bara göras en gång (vid uppstart).
+    pub fn install(self) -> bool {
+        ITEMS.set(self).is_ok()
+    }
+
+    pub fn instance() -> Option<&'static Items> {
+        ITEMS.get()
+    }
+
+    /// Slå upp en typ. Okända id ger en tom default-typ, precis som i TFS.
+    pub fn get(id: u16) -> &'static ItemType {
+        ITEMS
+            .get()
+            .and_then(|items| items.types.get(id as usize))
+            .unwrap_or(&UNKNOWN)
+    }
+
+    /// Alla typer i id-ordning, även de tomma luckorna
+    pub fn iter(&self) -> impl Iterator<Item = &ItemType> {
+        self.types.iter()
+    }
+
+    pub fn get_by_client_id(client_id: u16) -> Option<&'static ItemType> {
+        let items = ITEMS.get()?;
+        let id = *items.client_ids.get(&client_id)?;
+        items.types.get(id as usize)
+    }
+
+    pub fn get_id_by_name(name: &str) -> Option<u16> {
+        ITEMS.get()?.names.get(&name.to_lowercase()).copied()
+    }
+}
+
+/// Attribut och flaggor i items.otb, motsvarar itemloader.h i TFS
+mod otb {
+    pub const ROOT_ATTR_VERSION: u8 = 0x01;
+
+    pub const ITEM_ATTR_SERVERID: u8 = 0x10;
+    pub const ITEM_ATTR_CLIENTID: u8 = 0x11;
+    pub const ITEM_ATTR_SPEED: u8 = 0x14;
+    pub const ITEM_ATTR_LIGHT2: u8 = 0x2A;
+    pub const ITEM_ATTR_TOPORDER: u8 = 0x2B;
+
+    pub const FLAG_BLOCK_SOLID: u32 = 1 << 0;
+    pub const FLAG_BLOCK_PROJECTILE: u32 = 1 << 1;
+    pub const FLAG_BLOCK_PATHFIND: u32 = 1 << 2;
+    pub const FLAG_HAS_HEIGHT: u32 = 1 << 3;
+    pub const FLAG_PICKUPABLE: u32 = 1 << 5;
+    pub const FLAG_MOVEABLE: u32 = 1 << 6;
+    pub const FLAG_STACKABLE: u32 = 1 << 7;
+    pub const FLAG_FLOORCHANGEDOWN: u32 = 1 << 8;
+    pub const FLAG_FLOORCHANGENORTH: u32 = 1 << 9;
+    pub const FLAG_FLOORCHANGEEAST: u32 = 1 << 10;
+    pub const FLAG_FLOORCHANGESOUTH: u32 = 1 << 11;
+    pub const FLAG_FLOORCHANGEWEST: u32 = 1 << 12;
+    pub const FLAG_ALWAYSONTOP: u32 = 1 << 13;
+    pub const FLAG_HANGABLE: u32 = 1 << 16;
+    pub const FLAG_VERTICAL: u32 = 1 << 17;
+    pub const FLAG_HORIZONTAL: u32 = 1 << 18;
+}
+
+impl Items {
+    /// Läs items.otb. Motsvarar `Items::loadFromOtb`; items.xml läggs ovanpå
+    /// med `insert` i efterhand.
+    pub fn load_from_otb(path: impl AsRef<Path>) -> common::Result<Self> {
+        let loader = OtbLoader::open(path, &[0u8; 4])?;
+        let root = loader.root();
+        let invalid = || common::Error::World("Invalid items.otb format".into());
+
+        let mut items = Items::new();
+        let mut stream = PropStream::new(&root.props);
+        stream.read_u32().ok_or_else(invalid)?; // flaggor, oanvända
+        if stream.read_u8() == Some(otb::ROOT_ATTR_VERSION) {
+            let len = stream.read_u16().ok_or_else(invalid)?;
+            if len < 12 {
+                return Err(invalid());
+            }
+            items.major_version = stream.read_u32().ok_or_else(invalid)?;
+            items.minor_version = stream.read_u32().ok_or_else(invalid)?;
+            items.build_number = stream.read_u32().ok_or_else(invalid)?;
+        }
+
+        if items.major_version == u32::MAX {
+            warn!("items.otb using generic client version");
+        } else if items.major_version != 3 {
+            return Err(common::Error::World(format!(
+                "Unsupported items.otb major version {}",
+                items.major_version
+            )));
+        }
+
+        for node in &root.children {
+            let it = Self::parse_otb_item(node).ok_or_else(invalid)?;
+            if it.id != 0 {
+                items.insert(it);
+            }
+        }
+        Ok(items)
+    }
+
+    fn parse_otb_item(node: &OtbNode) -> Option<ItemType> {
+        let mut stream = PropStream::new(&node.props);
+        let flags = stream.read_u32()?;
+
+        let mut it = ItemType {
+            group: match node.node_type {
+                1 => ItemGroup::Ground,
+                2 => ItemGroup::Container,
+                3 => ItemGroup::Weapon,
+                4 => ItemGroup::Ammunition,
+                5 => ItemGroup::Armor,
+                6 => ItemGroup::Charges,
+                7 => ItemGroup::Teleport,
+                8 => ItemGroup::MagicField,
+                9 => ItemGroup::Writeable,
+                10 => ItemGroup::Key,
+                11 => ItemGroup::Splash,
+                12 => ItemGroup::Fluid,
+                13 => ItemGroup::Door,
+                14 => ItemGroup::Deprecated,
+                _ => ItemGroup::None,
+            },
+            ..Default::default()
+        };
+        it.kind = match it.group {
+            ItemGroup::Container => ItemKind::Container,
+            ItemGroup::Door => ItemKind::Door,
+            ItemGroup::MagicField => ItemKind::MagicField,
+            ItemGroup::Teleport => ItemKind::Teleport,
+            _ => ItemKind::None,
+        };
+
+        while let Some(attr) = stream.read_u8() {
+            let len = stream.read_u16()? as usize;
+            match attr {
+                otb::ITEM_ATTR_SERVERID if len == 2 => it.id = stream.read_u16()?,
+                otb::ITEM_ATTR_CLIENTID if len == 2 => it.client_id = stream.read_u16()?,
+                otb::ITEM_ATTR_SPEED if len == 2 => it.speed = stream.read_u16()?,
+                otb::ITEM_ATTR_LIGHT2 if len == 4 => {
+                    it.light_level = stream.read_u16()? as u8;
+                    it.light_color = stream.read_u16()? as u8;
+                }
+                otb::ITEM_ATTR_TOPORDER if len == 1 => it.top_order = stream.read_u8()?,
+                _ => {
+                    stream.read_bytes(len)?;
+                }
+            }
+        }
+
+        let has = |flag: u32| flags & flag != 0;
+        it.block_solid = has(otb::FLAG_BLOCK_SOLID);
+        it.block_projectile = has(otb::FLAG_BLOCK_PROJECTILE);
+        it.block_path_find = has(otb::FLAG_BLOCK_PATHFIND);
+        it.has_height = has(otb::FLAG_HAS_HEIGHT);
+        it.pickupable = has(otb::FLAG_PICKUPABLE);
+        it.moveable = has(otb::FLAG_MOVEABLE);
+        it.stackable = has(otb::FLAG_STACKABLE);
+        it.always_on_top = has(otb::FLAG_ALWAYSONTOP);
+        it.hangable = has(otb::FLAG_HANGABLE);
+        it.is_vertical = has(otb::FLAG_VERTICAL);
+        it.is_horizontal = has(otb::FLAG_HORIZONTAL);
+        for (flag, bit) in [
+            (otb::FLAG_FLOORCHANGEDOWN, floor_change::DOWN),
+            (otb::FLAG_FLOORCHANGENORTH, floor_change::NORTH),
+            (otb::FLAG_FLOORCHANGEEAST, floor_change::EAST),
+            (otb::FLAG_FLOORCHANGESOUTH, floor_change::SOUTH),
+            (otb::FLAG_FLOORCHANGEWEST, floor_change::WEST),
+        ] {
+            if has(flag) {
+                it.floor_change |= bit;
+            }
+        }
+        Some(it)
+    }
+}
@@ -0,0 +1,367 @@
+//! Binärformatet för item-attribut. Samma format som `Item::serializeAttr`,
+//! `Item::readAttr` och `IOMapSerialize::saveItem` i TFS så att data i
+//! `tile_store` och `player_*items` går att läsa från båda hållen.
+
+use common::{Position, PropStream, PropWriteStream};
+
+use crate::item::Item;
+
+/// Attributtyper, motsvarar `AttrTypes_t` i TFS
+pub mod attr {
+    pub const END: u8 = 0;
+    pub const DESCRIPTION: u8 = 1;
+    pub const EXT_FILE: u8 = 2;
+    pub const TILE_FLAGS: u8 = 3;
+    pub const ACTION_ID: u8 = 4;
+    pub const UNIQUE_ID: u8 = 5;
+    pub const TEXT: u8 = 6;
+    pub const DESC: u8 = 7;
+    pub const TELE_DEST: u8 = 8;
+    pub const ITEM: u8 = 9;
+    pub const DEPOT_ID: u8 = 10;
+    pub const EXT_SPAWN_FILE: u8 = 11;
+    pub const RUNE_CHARGES: u8 = 12;
+    pub const EXT_HOUSE_FILE: u8 = 13;
+    pub const HOUSEDOORID: u8 = 14;
+    pub const COUNT: u8 = 15;
+    pub const DURATION: u8 = 16;
+    pub const DECAYING_STATE: u8 = 17;
+    pub const WRITTENDATE: u8 = 18;
+    pub const WRITTENBY: u8 = 19;
+    pub const SLEEPERGUID: u8 = 20;
+    pub const SLEEPSTART: u8 = 21;
+    pub const CHARGES: u8 = 22;
+    pub const CONTAINER_ITEMS: u8 = 23;
+    pub const NAME: u8 = 24;
+    pub const ARTICLE: u8 = 25;
+    pub const PLURALNAME: u8 = 26;
+    pub const WEIGHT: u8 = 27;
+    pub const ATTACK: u8 = 28;
+    pub const DEFENSE: u8 = 29;
+    pub const EXTRADEFENSE: u8 = 30;
+    pub const ARMOR: u8 = 31;
+    pub const HITCHANCE: u8 = 32;
+    pub const SHOOTRANGE: u8 = 33;
+    pub const CUSTOM_ATTRIBUTES: u8 = 34;
+    pub const DECAYTO: u8 = 35;
+    pub const WRAPID: u8 = 36;
+    pub const STOREITEM: u8 = 37;
+    pub const ATTACK_SPEED: u8 = 38;
+}
+
+/// `ItemDecayState_t`
+const DECAYING_FALSE: u8 = 0;
+const DECAYING_PENDING: u8 = 2;
+
+impl Item {
+    /// Motsvarar `Item::serializeAttr` (utan ATTR_END)
+    pub fn serialize_attr(&self, stream: &mut PropWriteStream) {
+        let it = self.item_type();
+        let a = self.attributes();
+
+        if it.stackable || it.is_fluid_container() || it.is_splash() {
+            stream.write_u8(attr::COUNT);
+            stream.write_u8(self.get_sub_type() as u8);
+        }
+        if a.charges != 0 {
+            stream.write_u8(attr::CHARGES);
+            stream.write_u16(a.charges);
+        }
+        if it.moveable && a.action_id != 0 {
+            stream.write_u8(attr::ACTION_ID);
+            stream.write_u16(a.action_id);
+        }
+        if let Some(text) = a.text.as_deref().filter(|t| !t.is_empty()) {
+            stream.write_u8(attr::TEXT);
+            stream.write_string(text);
+        }
+        if a.written_date != 0 {
+            stream.write_u8(attr::WRITTENDATE);
+            stream.write_u32(a.written_date);
+        }
+        if let Some(writer) = a.writer.as_deref().filter(|w| !w.is_empty()) {
+            stream.write_u8(attr::WRITTENBY);
+            stream.write_string(writer);
+        }
+        if let Some(desc) = a.description.as_deref().filter(|d| !d.is_empty()) {
+            stream.write_u8(attr::DESC);
+            stream.write_string(desc);
+        }
+        if a.duration != 0 {
+            stream.write_u8(attr::DURATION);
+            stream.write_i32(a.duration);
+        }
+        if a.decaying != DECAYING_FALSE {
+            stream.write_u8(attr::DECAYING_STATE);
+            stream.write_u8(a.decaying);
+        }
+        if let Some(name) = &a.name {
+            stream.write_u8(attr::NAME);
+            stream.write_string(name);
+        }
+        if let Some(article) = &a.article {
+            stream.write_u8(attr::ARTICLE);
+            stream.write_string(article);
+        }
+        if let Some(plural) = &a.plural_name {
+            stream.write_u8(attr::PLURALNAME);
+            stream.write_string(plural);
+        }
+        if let Some(weight) = a.weight {
+            stream.write_u8(attr::WEIGHT);
+            stream.write_u32(weight);
+        }
+        if let Some(attack) = a.attack {
+            stream.write_u8(attr::ATTACK);
+            stream.write_i32(attack);
+        }
+        if let Some(defense) = a.defense {
+            stream.write_u8(attr::DEFENSE);
+            stream.write_i32(defense);
+        }
+        if let Some(extra) = a.extra_defense {
+            stream.write_u8(attr::EXTRADEFENSE);
+            stream.write_i32(extra);
+        }
+        if let Some(armor) = a.armor {
+            stream.write_u8(attr::ARMOR);
+            stream.write_i32(armor);
+        }
+        if let Some(hit_chance) = a.hit_chance {
+            stream.write_u8(attr::HITCHANCE);
+            stream.write_i8(hit_chance);
+        }
+        if let Some(range) = a.shoot_range {
+            stream.write_u8(attr::SHOOTRANGE);
+            stream.write_u8(range);
+        }
+
+        // Teleport::serializeAttr / BedItem::serializeAttr
+        if let Some(dest) = a.tele_dest {
+            stream.write_u8(attr::TELE_DEST);
+            stream.write_u16(dest.x);
+            stream.write_u16(dest.y);
+            stream.write_u8(dest.z);
+        }
+        if a.sleeper_guid != 0 {
+            stream.write_u8(attr::SLEEPERGUID);
+            stream.write_u32(a.sleeper_guid);
+            stream.write_u8(attr::SLEEPSTART);
+            stream.write_u32(a.sleep_start);
+        }
+    }
+
+    /// Hela itemet inklusive innehåll. Motsvarar `IOMapSerialize::saveItem`.
+    pub fn serialize(&self, stream: &mut PropWriteStream) {
+        stream.write_u16(self.id);
+        self.serialize_attr(stream);
+        if let Some(container) = self.get_container() {
+            stream.write_u8(attr::CONTAINER_ITEMS);
+            stream.write_u32(container.size() as u32);
+            // baklänges eftersom inläsningen lägger varje item först
+            for item in container.items().iter().rev() {
+                item.serialize(stream);
+            }
+        }
+        stream.write_u8(attr::END);
+    }
+
+    /// Läs ett item som skrivits med `serialize`. Motsvarar `IOMapSerialize::loadItem`
+    /// för items som skapas på nytt.
+    pub fn unserialize(stream: &mut PropStream) -> Option<Item> {
+        let id = stream.read_u16()?;
+        let mut item = Item::new(id, 0);
+        item.unserialize_into(stream).then_some(item)
+    }
+
+    /// Läs attribut (och innehåll) in i ett befintligt item, t.ex. en dörr
+    /// eller säng som redan finns på kartan.
+    pub fn unserialize_into(&mut self, stream: &mut PropStream) -> bool {
+        let Some(children) = self.unserialize_attr(stream) else {
+            return false;
+        };
+        let Some(children) = children else {
+            return true;
+        };
+
+        for _ in 0..children {
+            let Some(child) = Item::unserialize(stream) else {
+                return false;
+            };
+            if let Some(container) = self.get_container_mut() {
+                container.add_item_front(child);
+            }
+        }
+        stream.read_u8() == Some(attr::END)
+    }
+
+    /// Läs attribut fram till ATTR_END. Returnerar `Some(Some(n))` om listan
+    /// avslutades med ATTR_CONTAINER_ITEMS, None vid läsfel.
+    pub fn unserialize_attr(&mut self, stream: &mut PropStream) -> Option<Option<u32>> {
+        loop {
+            let Some(attr_type) = stream.read_u8() else {
+                // TFS godtar att strömmen tar slut utan ATTR_END
+                return Some(None);
+            };
+            if attr_type == attr::END {
+                return Some(None);
+            }
+            if attr_type == attr::CONTAINER_ITEMS {
+                self.get_container()?;
+                return Some(Some(stream.read_u32()?));
+            }
+            self.read_attr(attr_type, stream)?;
+        }
+    }
+
+    /// Motsvarar `Item::readAttr` plus underklassernas varianter
+    fn read_attr(&mut self, attr_type: u8, stream: &mut PropStream) -> Option<()> {
+        match attr_type {
+            attr::COUNT | attr::RUNE_CHARGES => {
+                let count = stream.read_u8()?;
+                self.set_sub_type(count as u16);
+            }
+            attr::CHARGES => {
+                let charges = stream.read_u16()?;
+                self.set_sub_type(charges);
+            }
+            attr::ACTION_ID => self.attributes_mut().action_id = stream.read_u16()?,
+            attr::UNIQUE_ID => self.attributes_mut().unique_id = stream.read_u16()?,
+            attr::TEXT => self.attributes_mut().text = Some(stream.read_string()?),
+            attr::WRITTENDATE => self.attributes_mut().written_date = stream.read_u32()?,
+            attr::WRITTENBY => self.attributes_mut().writer = Some(stream.read_string()?),
+            attr::DESC => self.attributes_mut().description = Some(stream.read_string()?),
+            attr::DURATION => self.attributes_mut().duration = stream.read_i32()?.max(0),
+            attr::DECAYING_STATE => {
+                if stream.read_u8()? != DECAYING_FALSE {
+                    self.attributes_mut().decaying = DECAYING_PENDING;
+                }
+            }
+            attr::NAME => self.attributes_mut().name = Some(stream.read_string()?),
+            attr::ARTICLE => self.attributes_mut().article = Some(stream.read_string()?),
+            attr::PLURALNAME => self.attributes_mut().plural_name = Some(stream.read_string()?),
+            attr::WEIGHT => self.attributes_mut().weight = Some(stream.read_u32()?),
+            attr::ATTACK => self.attributes_mut().attack = Some(stream.read_i32()?),
+            attr::DEFENSE => self.attributes_mut().defense = Some(stream.read_i32()?),
+            attr::EXTRADEFENSE => self.attributes_mut().extra_defense = Some(stream.read_i32()?),
+            attr::ARMOR => self.attributes_mut().armor = Some(stream.read_i32()?),
+            attr::HITCHANCE => self.attributes_mut().hit_chance = Some(stream.read_i8()?),
+            attr::SHOOTRANGE => self.attributes_mut().shoot_range = Some(stream.read_u8()?),
+            attr::HOUSEDOORID => self.attributes_mut().door_id = stream.read_u8()?,
+            attr::DEPOT_ID => self.attributes_mut().depot_id = stream.read_u16()?,
+            attr::TELE_DEST => {
+                let (x, y, z) = (stream.read_u16()?, stream.read_u16()?, stream.read_u8()?);
+                self.attributes_mut().tele_dest = Some(Position::new(x, y, z));
+            }
+            attr::SLEEPERGUID => self.attributes_mut().sleeper_guid = stream.read_u32()?,
+            attr::SLEEPSTART => self.attributes_mut().sleep_start = stream.read_u32()?,
+            attr::TILE_FLAGS => {
+                stream.read_u32()?;
+            }
+
+            // Attribut vi inte använder än; läses förbi så att data från TFS går att ladda
+            attr::DECAYTO | attr::ATTACK_SPEED => {
+                stream.read_u32()?;
+            }
+            attr::WRAPID => {
+                stream.read_u16()?;
+            }
+            attr::STOREITEM => {
+                stream.read_u8()?;
+            }
+            attr::CUSTOM_ATTRIBUTES => skip_custom_attributes(stream)?,
+            _ => return None,
+        }
+        Some(())
+    }
+}
+
+/// `ItemAttributes::CustomAttribute`: nyckel + boost::variant-index + värde
+fn skip_custom_attributes(stream: &mut PropStream) -> Option<()> {
+    let size = stream.read_u64()?;
+    for _ in 0..size {
+        stream.read_string()?;
+        match stream.read_u8()? {
+            1 => {
+                stream.read_string()?;
+            }
+            2 | 3 => {
+                stream.read_u64()?;
+            }
+            4 => {
+                stream.read_u8()?;
+            }
+            _ => {}
+        }
+    }
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::item::{ItemGroup, ItemType};
+    use crate::Items;
+
+    const BAG: u16 = 1987;
+    const COINS: u16 = 2148;
+    const SWORD: u16 = 2376;
+
+    fn install_items() {
+        let mut items = Items::new();
+        let bag = ItemType { id: BAG, group: ItemGroup::Container, max_items: 8, moveable: true, ..Default::default() };
+        items.insert(bag);
+        items.insert(ItemType { id: COINS, stackable: true, moveable: true, ..Default::default() });
+        items.insert(ItemType { id: SWORD, moveable: true, ..Default::default() });
+        items.install();
+    }
+
+    fn round_trip(item: &Item) -> Option<Item> {
+        let mut out = PropWriteStream::new();
+        item.serialize(&mut out);
+        Item::unserialize(&mut PropStream::new(out.get_stream()))
+    }
+
+    #[test]
+    fn attributes_survive_round_trip() {
+        install_items();
+        let mut sword = Item::new(SWORD, 0);
+        let a = sword.attributes_mut();
+        a.action_id = 2000;
+        a.text = Some("Till Bob".into());
+        a.writer = Some("Alice".into());
+        a.written_date = 1_600_000_000;
+        a.duration = 5000;
+        a.decaying = DECAYING_PENDING;
+        a.name = Some("blade".into());
+        a.attack = Some(20);
+        a.hit_chance = Some(-5);
+        a.tele_dest = Some(Position::new(100, 200, 7));
+
+        assert_eq!(round_trip(&sword), Some(sword));
+    }
+
+    #[test]
+    fn container_keeps_content_and_order() {
+        install_items();
+        let mut bag = Item::new(BAG, 0);
+        let container = bag.get_container_mut().unwrap();
+        container.add_item_back(Item::new(COINS, 57));
+        container.add_item_back(Item::new(SWORD, 0));
+        container.add_item_back(Item::new(BAG, 0));
+
+        let loaded = round_trip(&bag).unwrap();
+        assert_eq!(loaded.get_container().unwrap().items()[0].count, 57);
+        assert_eq!(loaded, bag);
+    }
+
+    #[test]
+    fn unknown_attribute_fails() {
+        install_items();
+        let mut out = PropWriteStream::new();
+        out.write_u16(SWORD);
+        out.write_u8(0xFE);
+        out.write_u8(attr::END);
+        assert!(Item::unserialize(&mut PropStream::new(out.get_stream())).is_none());
+    }
+}
@@ -0,0 +1,267 @@
+use anyhow::Result;
+use common::tracing::warn;
+use entities::{Guild, GuildMembership, GuildWarStatus, Guilds, Player};
+
+use crate::database::Database;
+
+/// Ett krig i `guild_wars`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GuildWar {
+    pub id: u32,
+    /// Gillet som förklarade kriget
+    pub guild1: u32,
+    pub guild2: u32,
+    pub status: GuildWarStatus,
+}
+
+/// Gillen och gillekrig i databasen. Motsvarar `IOGuild` i TFS, och
+/// gilledelen av `IOLoginData::loadPlayer`.
+pub struct IOGuild;
+
+impl IOGuild {
+    /// Läs gillet med rangerna, medlemsantalet och de pågående krigen.
+    /// Motsvarar `IOGuild::loadGuild`.
+    pub async fn load_guild(guild_id: u32) -> Result<Option<(Guild, Vec<u32>)>> {
+        let db = Database::instance();
+        let query = format!("SELECT `name`, `motd` FROM `guilds` WHERE `id` = {guild_id}");
+        let Some(result) = db.store_query(&query).await? else {
+            return Ok(None);
+        };
+        let mut guild = Guild::new(guild_id, result.get_string("name"));
+        guild.motd = result.get_string("motd");
+
+        let query = format!("SELECT `id`, `name`, `level` FROM `guild_ranks` WHERE `guild_id` = {guild_id}");
+        if let Some(mut result) = db.store_query(&query).await? {
+            loop {
+                guild.add_rank(result.get_number("id"), result.get_string("name"), result.get_number("level"));
+                if !result.next() {
+                    break;
+                }
+            }
+        }
+
+        let query = format!("SELECT COUNT(*) AS `members` FROM `guild_membership` WHERE `guild_id` = {guild_id}");
+        if let Some(result) = db.store_query(&query).await? {
+            guild.member_count = result.get_number("members");
+        }
+
+        let wars = Self::get_war_list(guild_id).await?;
+        Ok(Some((guild, wars)))
+    }
+
+    /// Motsvarar `IOGuild::getGuildIdByName`
+    pub async fn get_guild_id_by_name(name: &str) -> Result<Option<u32>> {
+        let db = Database::instance();
+        let query = format!("SELECT `id` FROM `guilds` WHERE `name` = {}", db.escape_string(name));
+        Ok(db.store_query(&query).await?.map(|result| result.get_number("id")))
+    }
+
+    /// Gillen som `guild_id` har ett pågående krig med. Motsvarar `IOGuild::getWarList`.
+    pub async fn get_war_list(guild_id: u32) -> Result<Vec<u32>> {
+        let query = format!(
+            "SELECT `guild1`, `guild2` FROM `guild_wars` WHERE (`guild1` = {guild_id} OR `guild2` = {guild_id}) \
+             AND `ended` = 0 AND `status` = {}",
+            GuildWarStatus::Active as u8
+        );
+        let mut wars = Vec::new();
+        if let Some(mut result) = Database::instance().store_query(&query).await? {
+            loop {
+                let guild1: u32 = result.get_number("guild1");
+                wars.push(if guild1 == guild_id { result.get_number("guild2") } else { guild1 });
+                if !result.next() {
+                    break;
+                }
+            }
+        }
+        Ok(wars)
+    }
+
+    /// Spelarens rad i `guild_membership`, ingen kontroll av gillet
+    pub async fn load_membership(guid: u32) -> Result<Option<GuildMembership>> {
+        let query = format!("SELECT `guild_id`, `rank_id`, `nick` FROM `guild_membership` WHERE `player_id` = {guid}");
+        Ok(Database::instance().store_query(&query).await?.map(|result| GuildMembership {
+            guild_id: result.get_number("guild_id"),
+            rank_id: result.get_number("rank_id"),
+            nick: result.get_string("nick"),
+        }))
+    }
+
+    /// Ge en inloggande spelare sitt gille. Gillet läses in om ingen annan
+    /// medlem är inne, och en rang som skapats sedan dess läses till. Finns
+    /// inte gillet eller rangen tas medlemskapet bort från spelaren, som i
+    /// `IOLoginData::loadPlayer`.
+    pub async fn login(guilds: &mut Guilds, player: &mut Player) -> Result<()> {
+        let Some(membership) = player.guild.clone() else {
+            return Ok(());
+        };
+        let guild_id = membership.guild_id;
+
+        if !guilds.contains(guild_id) {
+            let Some((guild, wars)) = Self::load_guild(guild_id).await? else {
+                warn!("[IOGuild::login] {} is a member of guild {guild_id} which doesn't exist", player.name());
+                player.guild = None;
+                return Ok(());
+            };
+            guilds.insert(guild);
+            for other in wars {
+                guilds.start_war(guild_id, other);
+            }
+        }
+
+        let has_rank = guilds.get(guild_id).is_some_and(|guild| guild.get_rank_by_id(membership.rank_id).is_some());
+        if !has_rank {
+            let query = format!("SELECT `id`, `name`, `level` FROM `guild_ranks` WHERE `id` = {}", membership.rank_id);
+            let rank = Database::instance().store_query(&query).await?;
+            match (rank, guilds.get_mut(guild_id)) {
+                (Some(result), Some(guild)) => {
+                    guild.add_rank(result.get_number("id"), result.get_string("name"), result.get_number("level"));
+                }
+                _ => {
+                    player.guild = None;
+                    // släpp gillet om det lästes in för spelaren
+                    guilds.remove_member(guild_id, player.creature.id);
+                    return Ok(());
+                }
+            }
+        }
+
+        guilds.add_member(guild_id, player.creature.id);
+        Ok(())
+    }
+
+    pub async fn get_war(war_id: u32) -> Result<Option<GuildWar>> {
+        let query = format!("SELECT `id`, `guild1`, `guild2`, `status` FROM `guild_wars` WHERE `id` = {war_id}");
+        let Some(result) = Database::instance().store_query(&query).await? else {
+            return Ok(None);
+        };
+        Ok(GuildWarStatus::from_u8(result.get_number("status")).map(|status| GuildWar {
+            id: result.get_number("id"),
+            guild1: result.get_number("guild1"),
+            guild2: result.get_number("guild2"),
+            status,
+        }))
+    }
+
+    /// Kriget mellan gillena som väntar på svar eller pågår, om det finns
+    pub async fn find_war(guild1: u32, guild2: u32) -> Result<Option<GuildWar>> {
+        let query = format!(
+            "SELECT `id` FROM `guild_wars` WHERE `status` IN ({}, {}) AND ((`guild1` = {guild1} AND `guild2` = {guild2}) \
+             OR (`guild1` = {guild2} AND `guild2` = {guild1}))",
+            GuildWarStatus::Pending as u8,
+            GuildWarStatus::Active as u8
+        );
+        match Database::instance().store_query(&query).await? {
+            Some(result) => Self::get_war(result.get_number("id")).await,
+            None => Ok(None),
+        }
+    }
+
+    /// `guild1` förklarar krig mot `guild2`. `None` om något av gillena
+    /// inte finns eller de redan har ett krig som väntar eller pågår.
+    pub async fn declare_war(guild1: u32, guild2: u32) -> Result<Option<u32>> {
+        if guild1 == guild2 || Self::find_war(guild1, guild2).await?.is_some() {
+            return Ok(None);
+        }
+        let db = Database::instance();
+        let mut names = Vec::with_capacity(2);
+        for guild_id in [guild1, guild2] {
+            let query = format!("SELECT `name` FROM `guilds` WHERE `id` = {guild_id}");
+            match db.store_query(&query).await? {
+                Some(result) => names.push(result.get_string("name")),
+                None => return Ok(None),
+            }
+        }
+
+        let query = format!(
+            "INSERT INTO `guild_wars` (`guild1`, `guild2`, `name1`, `name2`, `status`, `started`) \
+             VALUES ({guild1}, {guild2}, {}, {}, {}, {})",
+            db.escape_string(&names[0]),
+            db.escape_string(&names[1]),
+            GuildWarStatus::Pending as u8,
+            common::unix_time()
+        );
+        db.execute(&query).await?;
+        Ok(Self::find_war(guild1, guild2).await?.map(|war| war.id))
+    }
+
+    /// `guild_id` svarar på en krigsförklaring; bara gillet som fick den
+    /// kan svara. Accepteras kriget börjar det direkt för inlästa gillen.
+    pub async fn answer_war(guilds: &mut Guilds, war_id: u32, guild_id: u32, accept: bool) -> Result<bool> {
+        let Some(war) = Self::get_war(war_id).await? else {
+            return Ok(false);
+        };
+        if war.status != GuildWarStatus::Pending || war.guild2 != guild_id {
+            return Ok(false);
+        }
+
+        let now = common::unix_time();
+        let query = if accept {
+            format!("UPDATE `guild_wars` SET `status` = {}, `started` = {now} WHERE `id` = {war_id}", GuildWarStatus::Active as u8)
+        } else {
+            format!("UPDATE `guild_wars` SET `status` = {}, `ended` = {now} WHERE `id` = {war_id}", GuildWarStatus::Rejected as u8)
+        };
+        Database::instance().execute(&query).await?;
+        if accept {
+            guilds.start_war(war.guild1, war.guild2);
+        }
+        Ok(true)
+    }
+
+    /// Dra tillbaka en krigsförklaring som inte är besvarad
+    pub async fn cancel_war(war_id: u32, guild_id: u32) -> Result<bool> {
+        let Some(war) = Self::get_war(war_id).await? else {
+            return Ok(false);
+        };
+        if war.status != GuildWarStatus::Pending || war.guild1 != guild_id {
+            return Ok(false);
+        }
+        let query = format!(
+            "UPDATE `guild_wars` SET `status` = {}, `ended` = {} WHERE `id` = {war_id}",
+            GuildWarStatus::Canceled as u8,
+            common::unix_time()
+        );
+        Database::instance().execute(&query).await?;
+        Ok(true)
+    }
+
+    /// Avsluta ett pågående krig; vilket som helst av gillena kan avsluta det
+    pub async fn end_war(guilds: &mut Guilds, war_id: u32, guild_id: u32) -> Result<bool> {
+        let Some(war) = Self::get_war(war_id).await? else {
+            return Ok(false);
+        };
+        if war.status != GuildWarStatus::Active || (war.guild1 != guild_id && war.guild2 != guild_id) {
+            return Ok(false);
+        }
+        let query = format!(
+            "UPDATE `guild_wars` SET `status` = {}, `ended` = {} WHERE `id` = {war_id}",
+            GuildWarStatus::Ended as u8,
+            common::unix_time()
+        );
+        Database::instance().execute(&query).await?;
+        guilds.end_war(war.guild1, war.guild2);
+        Ok(true)
+    }
+
+    /// Spara ett frag mellan två gillen i krig. Spelet anropar det när en
+    /// spelare dödas av en annan och `Guilds::is_in_war` håller för deras
+    /// gillen, som playerdeath.lua gör i TFS.
+    pub async fn record_war_kill(killer: &str, target: &str, killer_guild: u32, target_guild: u32) -> Result<bool> {
+        let Some(war) = Self::find_war(killer_guild, target_guild).await? else {
+            return Ok(false);
+        };
+        if war.status != GuildWarStatus::Active {
+            return Ok(false);
+        }
+        let db = Database::instance();
+        let query = format!(
+            "INSERT INTO `guildwar_kills` (`killer`, `target`, `killerguild`, `targetguild`, `time`, `warid`) \
+             VALUES ({}, {}, {killer_guild}, {target_guild}, {}, {})",
+            db.escape_string(killer),
+            db.escape_string(target),
+            common::unix_time(),
+            war.id
+        );
+        db.execute(&query).await?;
+        Ok(true)
+    }
+}
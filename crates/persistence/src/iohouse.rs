@@ -0,0 +1,166 @@
+use anyhow::Result;
+use common::tracing::{info, warn};
+use entities::{Groups, Player};
+use items::Item;
+use world::house::{ITEM_LETTER_STAMPED, MAX_RENT_WARNINGS};
+use world::{HouseEviction, HouseOwner, Towns, World};
+
+use crate::database::Database;
+use crate::iologindata::IOLoginData;
+use crate::ioplayer::IOPlayer;
+
+/// De inloggade spelarna, som `Game::getPlayerByGUID`: kör funktionen på
+/// spelaren med guid:en och ger false om den inte är inloggad
+pub type OnlinePlayers<'a> = &'a mut dyn FnMut(u32, &mut dyn FnMut(&mut Player)) -> bool;
+
+/// Databasdelen av `House`/`Houses` i TFS: ägarbyten, hyra och vräkning
+pub struct IOHouse;
+
+impl IOHouse {
+    /// Byt ägare (0 = ingen). Motsvarar `House::setOwner` med `updateDatabase`.
+    pub async fn set_owner(
+        world: &mut World,
+        groups: &Groups,
+        online: OnlinePlayers<'_>,
+        house_id: u32,
+        guid: u32,
+    ) -> Result<bool> {
+        let Some(house) = world.houses.get_house(house_id) else {
+            return Ok(false);
+        };
+
+        if house.owner() != guid {
+            let query = format!(
+                "UPDATE `houses` SET `owner` = {guid}, `bid` = 0, `bid_end` = 0, `last_bid` = 0, \
+                 `highest_bidder` = 0 WHERE `id` = {house_id}"
+            );
+            Database::instance().execute(&query).await?;
+        }
+
+        let owner = match guid {
+            0 => None,
+            _ => IOLoginData::get_name_and_account_by_guid(guid)
+                .await?
+                .map(|(name, account_id)| HouseOwner { guid, account_id, name }),
+        };
+
+        if let Some(eviction) = world.set_house_owner(house_id, owner) {
+            Self::transfer_to_depot(eviction, &world.towns, groups, online).await?;
+        }
+        Ok(true)
+    }
+
+    /// Ändra spelaren i minnet om den är inloggad, annars laddas den, ändras
+    /// och sparas som `tmpPlayer` i TFS. False om spelaren inte finns.
+    async fn with_player(
+        guid: u32,
+        towns: &Towns,
+        groups: &Groups,
+        online: OnlinePlayers<'_>,
+        mut f: impl FnMut(&mut Player),
+    ) -> Result<bool> {
+        if online(guid, &mut f) {
+            return Ok(true);
+        }
+        let Some(mut player) = IOPlayer::load_by_guid(guid, towns, groups).await? else {
+            return Ok(false);
+        };
+        f(&mut player);
+        IOPlayer::save(&player).await
+    }
+
+    /// Skicka vräkta items till förra ägarens depå i husets stad.
+    /// Motsvarar `House::transferToDepot`.
+    pub async fn transfer_to_depot(
+        eviction: HouseEviction,
+        towns: &Towns,
+        groups: &Groups,
+        online: OnlinePlayers<'_>,
+    ) -> Result<()> {
+        if eviction.town_id == 0 || eviction.previous_owner == 0 || eviction.items.is_empty() {
+            return Ok(());
+        }
+        let mut items = eviction.items;
+        let moved = Self::with_player(eviction.previous_owner, towns, groups, online, |player| {
+            if let Some(depot) = player.get_depot_chest(eviction.town_id, true) {
+                for item in std::mem::take(&mut items) {
+                    depot.add_item_front(item);
+                }
+            }
+        })
+        .await?;
+        if !moved {
+            let (owner, house_id) = (eviction.previous_owner, eviction.house_id);
+            warn!("[IOHouse::transfer_to_depot] Player {owner} of house {house_id} not found");
+        }
+        Ok(())
+    }
+
+    /// Dra hyran från ägarnas bankkonto. Den som inte kan betala får en
+    /// varning i sin inbox; efter sju varningar förlorar de huset.
+    /// Motsvarar `Houses::payHouses`, körs vid uppstart.
+    pub async fn pay_houses(world: &mut World, groups: &Groups, online: OnlinePlayers<'_>) -> Result<()> {
+        let period = world.houses.config.rent_period;
+        let Some(period_seconds) = period.seconds() else {
+            return Ok(());
+        };
+        let db = Database::instance();
+        let now = common::unix_time();
+
+        for house_id in world.houses.houses_due_rent(now) {
+            let Some(house) = world.houses.get_house(house_id) else {
+                continue;
+            };
+            let (owner, rent, warnings) = (house.owner(), house.rent, house.rent_warnings);
+
+            // inloggade ägare betalar från saldot i minnet, annars skriver
+            // nästa sparning över avdraget
+            let mut paid = false;
+            let is_online = online(owner, &mut |player: &mut Player| {
+                if player.bank_balance >= rent as u64 {
+                    player.bank_balance -= rent as u64;
+                    paid = true;
+                }
+            });
+            if !is_online {
+                let query = format!("SELECT `balance` FROM `players` WHERE `id` = {owner}");
+                let Some(result) = db.store_query(&query).await? else {
+                    // ägaren finns inte längre
+                    Self::set_owner(world, groups, online, house_id, 0).await?;
+                    continue;
+                };
+                let balance: u64 = result.get_number("balance");
+                paid = balance >= rent as u64;
+                if paid {
+                    db.execute(&format!(
+                        "UPDATE `players` SET `balance` = `balance` - {rent} WHERE `id` = {owner}"
+                    ))
+                    .await?;
+                }
+            }
+
+            if paid {
+                if let Some(house) = world.houses.get_house_mut(house_id) {
+                    house.paid_until = now + period_seconds;
+                }
+            } else if warnings < MAX_RENT_WARNINGS {
+                let mut letter = Item::new(ITEM_LETTER_STAMPED, 0);
+                if let Some(house) = world.houses.get_house_mut(house_id) {
+                    letter.attributes_mut().text = Some(house.rent_warning_text(period));
+                    house.rent_warnings += 1;
+                }
+                let mut letter = Some(letter);
+                Self::with_player(owner, &world.towns, groups, online, |player| {
+                    if let Some(letter) = letter.take() {
+                        player.inbox.add_item_front(letter);
+                    }
+                })
+                .await?;
+            } else {
+                info!("House {house_id} was taken from player {owner} after {MAX_RENT_WARNINGS} unpaid rents");
+                Self::set_owner(world, groups, online, house_id, 0).await?;
+            }
+        }
+        Ok(())
+    }
+}
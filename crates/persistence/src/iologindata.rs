@@ -0,0 +1,160 @@
+use anyhow::Result;
+use common::PropWriteStream;
+use entities::player::AccountType;
+use entities::vip::{VIP_DESCRIPTION_LENGTH, VIP_ICON_LAST};
+use entities::{Groups, PlayerFlags, VipEntry};
+use items::Item;
+
+use crate::database::{Database, DbInsert};
+
+/// Första sid för vanliga items; 0-100 är reserverade för depåer
+pub const FIRST_ITEM_SID: i32 = 100;
+
+/// Motsvarar `IOLoginData` i TFS
+pub struct IOLoginData;
+
+impl IOLoginData {
+    pub async fn get_name_by_guid(guid: u32) -> Result<Option<String>> {
+        let query = format!("SELECT `name` FROM `players` WHERE `id` = {guid}");
+        Ok(Database::instance()
+            .store_query(&query)
+            .await?
+            .map(|result| result.get_string("name")))
+    }
+
+    /// Namn och konto för en spelare, None om spelaren inte finns
+    pub async fn get_name_and_account_by_guid(guid: u32) -> Result<Option<(String, u32)>> {
+        let query = format!("SELECT `name`, `account_id` FROM `players` WHERE `id` = {guid}");
+        Ok(Database::instance()
+            .store_query(&query)
+            .await?
+            .map(|result| (result.get_string("name"), result.get_number("account_id"))))
+    }
+
+    pub async fn get_guid_by_name(name: &str) -> Result<Option<u32>> {
+        let db = Database::instance();
+        let query = format!("SELECT `id` FROM `players` WHERE `name` = {}", db.escape_string(name));
+        Ok(db.store_query(&query).await?.map(|result| result.get_number("id")))
+    }
+
+    /// Guid, om gruppen har `PlayerFlag_SpecialVIP` och namnet som det är
+    /// skrivet i databasen. Motsvarar `IOLoginData::getGuidByNameEx`.
+    pub async fn get_guid_by_name_ex(name: &str, groups: &Groups) -> Result<Option<(u32, bool, String)>> {
+        let db = Database::instance();
+        let query = format!("SELECT `name`, `id`, `group_id` FROM `players` WHERE `name` = {}", db.escape_string(name));
+        Ok(db.store_query(&query).await?.map(|result| {
+            let special_vip = groups
+                .get_group(result.get_number("group_id"))
+                .is_some_and(|group| group.flags.intersects(PlayerFlags::SPECIAL_VIP));
+            (result.get_number("id"), special_vip, result.get_string("name"))
+        }))
+    }
+
+    /// Motsvarar `IOLoginData::setAccountType`
+    pub async fn set_account_type(account_id: u32, account_type: AccountType) -> Result<()> {
+        Database::instance().execute(&Self::account_type_query(account_id, account_type)).await
+    }
+
+    pub(crate) fn account_type_query(account_id: u32, account_type: AccountType) -> String {
+        format!("UPDATE `accounts` SET `type` = {} WHERE `id` = {account_id}", account_type as u8)
+    }
+
+    /// Lägg items (med innehåll) i en insert. `running_id` är senast använda
+    /// sid och räknas upp. Motsvarar `IOLoginData::saveItems`.
+    pub async fn save_items(
+        player_id: u32,
+        items: &[(i32, &Item)],
+        stmt: &mut DbInsert,
+        running_id: &mut i32,
+    ) -> Result<()> {
+        let db = Database::instance();
+        let mut stream = PropWriteStream::new();
+        let mut queue: Vec<(i32, &Item)> = items.to_vec();
+
+        // bredden först, som i TFS: en containers barn får containerns sid som pid
+        let mut index = 0;
+        while index < queue.len() {
+            let (pid, item) = queue[index];
+            index += 1;
+            *running_id += 1;
+
+            stream.clear();
+            item.serialize_attr(&mut stream);
+            let row = format!(
+                "{player_id},{pid},{},{},{},{}",
+                running_id,
+                item.id,
+                item.get_sub_type(),
+                db.escape_blob(stream.get_stream())
+            );
+            stmt.add_row(&row).await?;
+
+            if let Some(container) = item.get_container() {
+                let parent = *running_id;
+                queue.extend(container.items().iter().map(|child| (parent, child)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Kontots VIP-lista med namnen, motsvarar `IOLoginData::getVIPEntries`
+    pub async fn get_vip_entries(account_id: u32) -> Result<Vec<VipEntry>> {
+        let query = format!(
+            "SELECT `player_id`, (SELECT `name` FROM `players` WHERE `id` = `player_id`) AS `name`, \
+             `description`, `icon`, `notify` FROM `account_viplist` WHERE `account_id` = {account_id}"
+        );
+        let mut entries = Vec::new();
+        if let Some(mut result) = Database::instance().store_query(&query).await? {
+            loop {
+                entries.push(VipEntry {
+                    guid: result.get_number("player_id"),
+                    name: result.get_string("name"),
+                    description: result.get_string("description"),
+                    icon: result.get_number("icon"),
+                    notify: result.get_number::<u8>("notify") != 0,
+                });
+                if !result.next() {
+                    break;
+                }
+            }
+        }
+        Ok(entries)
+    }
+
+    pub async fn add_vip_entry(account_id: u32, guid: u32, description: &str, icon: u32, notify: bool) -> Result<()> {
+        let db = Database::instance();
+        let query = format!(
+            "INSERT INTO `account_viplist` (`account_id`, `player_id`, `description`, `icon`, `notify`) \
+             VALUES ({account_id}, {guid}, {}, {}, {})",
+            db.escape_string(vip_description(description)),
+            icon.min(VIP_ICON_LAST),
+            notify as u8
+        );
+        db.execute(&query).await
+    }
+
+    pub async fn edit_vip_entry(account_id: u32, guid: u32, description: &str, icon: u32, notify: bool) -> Result<()> {
+        let db = Database::instance();
+        let query = format!(
+            "UPDATE `account_viplist` SET `description` = {}, `icon` = {}, `notify` = {} \
+             WHERE `account_id` = {account_id} AND `player_id` = {guid}",
+            db.escape_string(vip_description(description)),
+            icon.min(VIP_ICON_LAST),
+            notify as u8
+        );
+        db.execute(&query).await
+    }
+
+    pub async fn remove_vip_entry(account_id: u32, guid: u32) -> Result<()> {
+        let query = format!("DELETE FROM `account_viplist` WHERE `account_id` = {account_id} AND `player_id` = {guid}");
+        Database::instance().execute(&query).await
+    }
+}
+
+/// Beskrivningen kortad till kolumnens längd, på en teckengräns
+fn vip_description(description: &str) -> &str {
+    match description.char_indices().nth(VIP_DESCRIPTION_LENGTH) {
+        Some((end, _)) => &description[..end],
+        None => description,
+    }
+}
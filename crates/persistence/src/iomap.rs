@@ -0,0 +1,285 @@
+use anyhow::Result;
+use common::tracing::warn;
+use common::{Position, PropStream, PropWriteStream};
+use items::{Item, Items};
+use world::house::{GUEST_LIST, SUBOWNER_LIST};
+use world::{HouseOwner, Tile, Towns, World};
+
+use crate::database::{Database, DbInsert, DbTransaction};
+
+/// Motsvarar `IOMapSerialize` i TFS: husens ägare, listor och items
+pub struct IOMapSerialize;
+
+impl IOMapSerialize {
+    /// Läs ägare, hyra och access-listor från `houses`/`house_lists`
+    pub async fn load_house_info(world: &mut World) -> Result<bool> {
+        let db = Database::instance();
+        let Some(mut result) = db
+            .store_query(
+                "SELECT `h`.`id`, `h`.`owner`, `h`.`paid`, `h`.`warnings`, \
+                 COALESCE(`p`.`name`, '') AS `name`, COALESCE(`p`.`account_id`, 0) AS `account_id` \
+                 FROM `houses` AS `h` LEFT JOIN `players` AS `p` ON `p`.`id` = `h`.`owner`",
+            )
+            .await?
+        else {
+            return Ok(false);
+        };
+
+        loop {
+            let id: u32 = result.get_number("id");
+            let guid: u32 = result.get_number("owner");
+            let name = result.get_string("name");
+            // ägare som inte längre finns räknas som inget ägande
+            let owner = (guid != 0 && !name.is_empty()).then(|| HouseOwner {
+                guid,
+                account_id: result.get_number("account_id"),
+                name,
+            });
+
+            if world.houses.get_house(id).is_some() {
+                world.set_house_owner(id, owner);
+                if let Some(house) = world.houses.get_house_mut(id) {
+                    house.paid_until = result.get_number("paid");
+                    house.rent_warnings = result.get_number("warnings");
+                }
+            }
+            if !result.next() {
+                break;
+            }
+        }
+
+        if let Some(mut result) = db
+            .store_query("SELECT `house_id`, `listid`, `list` FROM `house_lists`")
+            .await?
+        {
+            loop {
+                let id: u32 = result.get_number("house_id");
+                if let Some(house) = world.houses.get_house_mut(id) {
+                    house.set_access_list(result.get_number("listid"), &result.get_string("list"));
+                }
+                if !result.next() {
+                    break;
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    /// Motsvarar `IOMapSerialize::saveHouseInfo`
+    pub async fn save_house_info(world: &World) -> Result<()> {
+        let db = Database::instance();
+        let mut transaction = DbTransaction::begin().await?;
+
+        for house in world.houses.iter() {
+            let query = format!("SELECT `id` FROM `houses` WHERE `id` = {}", house.id);
+            let size = house.tiles().len();
+            let beds = world.house_bed_count(house.id);
+            let query = if transaction.store_query(&query).await?.is_some() {
+                format!(
+                    "UPDATE `houses` SET `owner` = {}, `paid` = {}, `warnings` = {}, `name` = {}, \
+                     `town_id` = {}, `rent` = {}, `size` = {size}, `beds` = {beds} WHERE `id` = {}",
+                    house.owner(),
+                    house.paid_until,
+                    house.rent_warnings,
+                    db.escape_string(&house.name),
+                    house.town_id,
+                    house.rent,
+                    house.id
+                )
+            } else {
+                format!(
+                    "INSERT INTO `houses` (`id`, `owner`, `paid`, `warnings`, `name`, `town_id`, `rent`, `size`, `beds`) \
+                     VALUES ({}, {}, {}, {}, {}, {}, {}, {size}, {beds})",
+                    house.id,
+                    house.owner(),
+                    house.paid_until,
+                    house.rent_warnings,
+                    db.escape_string(&house.name),
+                    house.town_id,
+                    house.rent
+                )
+            };
+            transaction.execute(&query).await?;
+        }
+
+        for house in world.houses.iter() {
+            let query = format!("DELETE FROM `house_lists` WHERE `house_id` = {}", house.id);
+            transaction.execute(&query).await?;
+
+            let mut stmt = DbInsert::new("INSERT INTO `house_lists` (`house_id`, `listid`, `list`) VALUES ");
+            let lists = [GUEST_LIST, SUBOWNER_LIST]
+                .into_iter()
+                .chain(house.doors().iter().map(|d| d.door_id as u32));
+            for list_id in lists {
+                if let Some(text) = house.get_access_list(list_id).filter(|t| !t.is_empty()) {
+                    stmt.add_row(&format!("{},{list_id},{}", house.id, db.escape_string(text)))
+                        .await?;
+                }
+            }
+            stmt.execute_in(&mut transaction).await?;
+        }
+
+        transaction.commit().await
+    }
+
+    /// Läs husens items från `tile_store`. Motsvarar `IOMapSerialize::loadHouseItems`.
+    pub async fn load_house_items(world: &mut World) -> Result<()> {
+        let Some(mut result) = Database::instance()
+            .store_query("SELECT `data` FROM `tile_store`")
+            .await?
+        else {
+            return Ok(());
+        };
+
+        loop {
+            if let Some(data) = result.get_stream("data") {
+                Self::load_tile(world, &mut PropStream::new(&data));
+            }
+            if !result.next() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Spara alla hustiles till `tile_store`. Motsvarar `IOMapSerialize::saveHouseItems`.
+    pub async fn save_house_items(world: &World) -> Result<()> {
+        let db = Database::instance();
+        let mut transaction = DbTransaction::begin().await?;
+        transaction.execute("DELETE FROM `tile_store`").await?;
+
+        let mut stmt = DbInsert::new("INSERT INTO `tile_store` (`house_id`, `data`) VALUES ");
+        let mut stream = PropWriteStream::new();
+        for house in world.houses.iter() {
+            for pos in house.tiles() {
+                let Some(tile) = world.map.get_tile(pos) else {
+                    continue;
+                };
+                Self::save_tile(&mut stream, tile);
+                if !stream.get_stream().is_empty() {
+                    stmt.add_row(&format!("{},{}", house.id, db.escape_blob(stream.get_stream())))
+                        .await?;
+                }
+                stream.clear();
+            }
+        }
+        stmt.execute_in(&mut transaction).await?;
+        transaction.commit().await
+    }
+
+    fn load_tile(world: &mut World, stream: &mut PropStream) {
+        let (Some(x), Some(y), Some(z)) = (stream.read_u16(), stream.read_u16(), stream.read_u8()) else {
+            return;
+        };
+        let pos = Position::new(x, y, z);
+        if world.map.get_tile(&pos).is_none() {
+            return;
+        }
+        let Some(count) = stream.read_u32() else {
+            return;
+        };
+        for _ in 0..count {
+            if !Self::load_item(world, pos, stream) {
+                break;
+            }
+        }
+    }
+
+    /// Motsvarar `IOMapSerialize::loadItem` för items direkt på en tile
+    fn load_item(world: &mut World, pos: Position, stream: &mut PropStream) -> bool {
+        let Some(id) = stream.read_u16() else {
+            return false;
+        };
+        let it = Items::get(id);
+
+        if it.moveable || it.force_serialize {
+            let mut item = Item::new(id, 0);
+            if !item.unserialize_into(stream) {
+                warn!("Unserialization error in IOMapSerialize::load_item() {id}");
+                return false;
+            }
+            world.load_house_item(pos, item);
+            return true;
+        }
+
+        // fasta items som dörrar, sängar och svarta tavlor finns redan på kartan
+        let existing = world.map.get_tile_mut(&pos).and_then(|tile| {
+            tile.items_mut().find(|i| {
+                let found = i.item_type();
+                i.id == id || (it.is_door() && found.is_door()) || (it.is_bed() && found.is_bed())
+            })
+        });
+        match existing {
+            Some(item) => {
+                if !item.unserialize_into(stream) {
+                    warn!("Unserialization error in IOMapSerialize::load_item() {id}");
+                    return false;
+                }
+                item.id = id;
+            }
+            None => {
+                // kartan har ändrats sedan det sparades, läs bara förbi datan
+                let mut dummy = Item::new(id, 0);
+                if !dummy.unserialize_into(stream) {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    /// Motsvarar `IOMapSerialize::saveTile`
+    fn save_tile(stream: &mut PropWriteStream, tile: &Tile) {
+        let items: Vec<&Item> = tile
+            .down_items()
+            .iter()
+            .chain(tile.top_items())
+            .filter(|item| {
+                let it = item.item_type();
+                it.moveable
+                    || it.force_serialize
+                    || it.is_door()
+                    || it.is_bed()
+                    || it.can_write_text
+                    || item.get_container().is_some_and(|c| !c.is_empty())
+            })
+            .collect();
+        if items.is_empty() {
+            return;
+        }
+
+        stream.write_u16(tile.position.x);
+        stream.write_u16(tile.position.y);
+        stream.write_u8(tile.position.z);
+        stream.write_u32(items.len() as u32);
+        // baklänges så att inläsningen, som lägger varje item överst, får samma ordning
+        for item in items.into_iter().rev() {
+            item.serialize(stream);
+        }
+    }
+
+    /// Spegla kartans städer till `towns`-tabellen, det som startup.lua gör
+    /// i TFS. Körs vid uppstart efter att kartan laddats.
+    pub async fn sync_towns(towns: &Towns) -> Result<()> {
+        let db = Database::instance();
+        let mut transaction = DbTransaction::begin().await?;
+        // allt tas bort först så att namnbyten inte krockar med UNIQUE på `name`
+        transaction.execute("DELETE FROM `towns`").await?;
+
+        let mut stmt = DbInsert::new("INSERT INTO `towns` (`id`, `name`, `posx`, `posy`, `posz`) VALUES ");
+        for town in towns.iter() {
+            let pos = town.temple_position;
+            stmt.add_row(&format!(
+                "{},{},{},{},{}",
+                town.id,
+                db.escape_string(&town.name),
+                pos.x,
+                pos.y,
+                pos.z
+            ))
+            .await?;
+        }
+        stmt.execute_in(&mut transaction).await?;
+        transaction.commit().await
+    }
+}
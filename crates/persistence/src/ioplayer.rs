@@ -0,0 +1,502 @@
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+
+use anyhow::{anyhow, Result};
+use common::tracing::warn;
+use common::{Direction, Position, PropStream};
+use entities::creature::Skull;
+use entities::player::{AccountType, PlayerSex, CONST_SLOT_FIRST, CONST_SLOT_LAST, MAX_DEPOT_ID};
+use entities::{Groups, Player, Skill};
+use items::Item;
+use world::Towns;
+
+use crate::database::{Database, DbInsert, DbResult, DbTransaction};
+use crate::ioguild::IOGuild;
+use crate::iologindata::{IOLoginData, FIRST_ITEM_SID};
+
+const PLAYER_COLUMNS: &str = "`id`, `name`, `account_id`, `group_id`, `sex`, `vocation`, `experience`, `level`, \
+     `maglevel`, `health`, `healthmax`, `blessings`, `mana`, `manamax`, `manaspent`, `soul`, `lookbody`, \
+     `lookfeet`, `lookhead`, `looklegs`, `looktype`, `lookaddons`, `lookmount`, `lookmounthead`, \
+     `lookmountbody`, `lookmountlegs`, `lookmountfeet`, `currentmount`, `randomizemount`, `direction`, \
+     `posx`, `posy`, `posz`, `cap`, `lastlogin`, `lastlogout`, `lastip`, `conditions`, `skulltime`, `skull`, \
+     `town_id`, `balance`, `offlinetraining_time`, `offlinetraining_skill`, `stamina`, `onlinetime`, \
+     `skill_fist`, `skill_fist_tries`, `skill_club`, `skill_club_tries`, `skill_sword`, `skill_sword_tries`, \
+     `skill_axe`, `skill_axe_tries`, `skill_dist`, `skill_dist_tries`, `skill_shielding`, \
+     `skill_shielding_tries`, `skill_fishing`, `skill_fishing_tries`";
+
+/// Så många dödsfall sparas per spelare, de äldsta tas bort
+const MAX_DEATH_RECORDS: u64 = 5;
+
+/// Mördarna i en rad i `player_deaths`. En summon räknas som sin master,
+/// och utan mördare är namnet "field item", som i playerdeath.lua.
+#[derive(Debug, Clone)]
+pub struct DeathRecord {
+    pub killed_by: String,
+    pub is_player: bool,
+    pub mostdamage_by: String,
+    pub mostdamage_is_player: bool,
+    pub unjustified: bool,
+    pub mostdamage_unjustified: bool,
+}
+
+/// sid -> (item, pid), som `ItemMap` i TFS
+type ItemMap = BTreeMap<i32, (Item, i32)>;
+
+/// Spelarens rad i `players` och tabellerna runt den. Motsvarar
+/// `IOLoginData::loadPlayer` och `IOLoginData::savePlayer` i TFS.
+pub struct IOPlayer;
+
+impl IOPlayer {
+    pub async fn load_by_guid(guid: u32, towns: &Towns, groups: &Groups) -> Result<Option<Player>> {
+        let query = format!("SELECT {PLAYER_COLUMNS} FROM `players` WHERE `id` = {guid}");
+        match Database::instance().store_query(&query).await? {
+            Some(result) => Self::load(&result, towns, groups).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn load_by_name(name: &str, towns: &Towns, groups: &Groups) -> Result<Option<Player>> {
+        let db = Database::instance();
+        let query = format!(
+            "SELECT {PLAYER_COLUMNS} FROM `players` WHERE `name` = {}",
+            db.escape_string(name)
+        );
+        match db.store_query(&query).await? {
+            Some(result) => Self::load(&result, towns, groups).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    async fn load(result: &DbResult, towns: &Towns, groups: &Groups) -> Result<Player> {
+        let mut player = Player::new(result.get_string("name"));
+        player.guid = result.get_number("id");
+        player.account_id = result.get_number("account_id");
+        let group_id = result.get_number("group_id");
+        player.group = groups
+            .get_group(group_id)
+            .cloned()
+            .ok_or_else(|| anyhow!("[IOPlayer::load] {} has group id {group_id} which doesn't exist", player.name()))?;
+        player.sex = PlayerSex::from_u8(result.get_number("sex"));
+        player.vocation = result.get_number("vocation");
+
+        let query = format!("SELECT `type`, `premium_ends_at` FROM `accounts` WHERE `id` = {}", player.account_id);
+        if let Some(account) = Database::instance().store_query(&query).await? {
+            player.account_type = AccountType::from_u8(account.get_number("type"));
+            player.premium_ends_at = account.get_number("premium_ends_at");
+        }
+
+        player.level = result.get_number::<u32>("level").max(1);
+        player.update_base_speed();
+        player.experience = result.get_number("experience");
+        player.mag_level = result.get_number("maglevel");
+        player.mana_spent = result.get_number("manaspent");
+        player.creature.health = result.get_number("health");
+        player.creature.health_max = result.get_number("healthmax");
+        player.creature.mana = result.get_number("mana");
+        player.creature.mana_max = result.get_number("manamax");
+        player.soul = result.get_number("soul");
+        player.capacity = result.get_number::<u32>("cap") * 100;
+        player.blessings = result.get_number("blessings");
+        player.bank_balance = result.get_number("balance");
+
+        for skill in Skill::ALL {
+            let value = player.skill_mut(skill);
+            value.level = result.get_number(skill.column());
+            value.tries = result.get_number(&format!("{}_tries", skill.column()));
+        }
+
+        player.creature.outfit.look_type = result.get_number("looktype");
+        player.creature.outfit.look_head = result.get_number("lookhead");
+        player.creature.outfit.look_body = result.get_number("lookbody");
+        player.creature.outfit.look_legs = result.get_number("looklegs");
+        player.creature.outfit.look_feet = result.get_number("lookfeet");
+        player.creature.outfit.look_addons = result.get_number("lookaddons");
+        player.creature.outfit.look_mount = result.get_number("lookmount");
+        player.creature.outfit.look_mount_head = result.get_number("lookmounthead");
+        player.creature.outfit.look_mount_body = result.get_number("lookmountbody");
+        player.creature.outfit.look_mount_legs = result.get_number("lookmountlegs");
+        player.creature.outfit.look_mount_feet = result.get_number("lookmountfeet");
+        player.creature.default_outfit = player.creature.outfit;
+        player.current_mount = result.get_number("currentmount");
+        player.randomize_mount = result.get_number::<u8>("randomizemount") != 0;
+        player.creature.direction = Direction::from_u8(result.get_number("direction")).unwrap_or_default();
+
+        player.creature.skull = Skull::from_u8(result.get_number("skull"));
+        player.skull_ticks = result.get_number("skulltime");
+        player.last_login = result.get_number("lastlogin");
+        player.last_logout = result.get_number("lastlogout");
+        player.last_ip = result.get_stream("lastip").and_then(|bytes| ip_from_bytes(&bytes));
+        player.online_time = result.get_number("onlinetime");
+        player.offline_training_time = result.get_number::<i32>("offlinetraining_time") * 1000;
+        player.offline_training_skill = result.get_number("offlinetraining_skill");
+        player.stamina_minutes = result.get_number("stamina");
+        player.conditions = result.get_stream("conditions").unwrap_or_default();
+
+        player.town_id = result.get_number("town_id");
+        let saved = Position::new(result.get_number("posx"), result.get_number("posy"), result.get_number("posz"));
+        player.login_position = towns.login_position(player.town_id, saved).ok_or_else(|| {
+            anyhow!("[IOPlayer::load] {} has town_id {} which doesn't exist", player.name(), player.town_id)
+        })?;
+
+        Self::load_item_tables(&mut player).await?;
+        Self::load_lists(&mut player).await?;
+        player.guild = IOGuild::load_membership(player.guid).await?;
+        Ok(player)
+    }
+
+    async fn load_item_tables(player: &mut Player) -> Result<()> {
+        let guid = player.guid;
+
+        let items = Self::load_items("player_items", guid).await?;
+        Self::build_item_tree(items, |pid, item| {
+            if (CONST_SLOT_FIRST as i32..=CONST_SLOT_LAST as i32).contains(&pid) {
+                player.set_inventory_item(pid as u8, Some(item));
+            }
+        });
+
+        let items = Self::load_items("player_depotitems", guid).await?;
+        Self::build_item_tree(items, |pid, item| {
+            if (0..=MAX_DEPOT_ID as i32).contains(&pid) {
+                if let Some(chest) = player.get_depot_chest(pid as u32, true) {
+                    chest.add_item_front(item);
+                }
+            }
+        });
+
+        let items = Self::load_items("player_inboxitems", guid).await?;
+        Self::build_item_tree(items, |pid, item| {
+            if pid == 0 {
+                player.inbox.add_item_front(item);
+            }
+        });
+
+        let items = Self::load_items("player_storeinboxitems", guid).await?;
+        Self::build_item_tree(items, |pid, item| {
+            if pid == 0 {
+                player.store_inbox.add_item_front(item);
+            }
+        });
+        Ok(())
+    }
+
+    async fn load_lists(player: &mut Player) -> Result<()> {
+        let db = Database::instance();
+        let guid = player.guid;
+
+        let query = format!("SELECT `name` FROM `player_spells` WHERE `player_id` = {guid}");
+        if let Some(mut result) = db.store_query(&query).await? {
+            loop {
+                player.learn_instant_spell(&result.get_string("name"));
+                if !result.next() {
+                    break;
+                }
+            }
+        }
+
+        let query = format!("SELECT `key`, `value` FROM `player_storage` WHERE `player_id` = {guid}");
+        if let Some(mut result) = db.store_query(&query).await? {
+            loop {
+                player.add_storage_value(result.get_number("key"), Some(result.get_number("value")));
+                if !result.next() {
+                    break;
+                }
+            }
+        }
+
+        let query = format!("SELECT `player_id` FROM `account_viplist` WHERE `account_id` = {}", player.account_id);
+        if let Some(mut result) = db.store_query(&query).await? {
+            loop {
+                player.add_vip_internal(result.get_number("player_id"));
+                if !result.next() {
+                    break;
+                }
+            }
+        }
+
+        let query = format!("SELECT `outfit_id`, `addons` FROM `player_outfits` WHERE `player_id` = {guid}");
+        if let Some(mut result) = db.store_query(&query).await? {
+            loop {
+                player.add_outfit(result.get_number("outfit_id"), result.get_number("addons"));
+                if !result.next() {
+                    break;
+                }
+            }
+        }
+
+        let query = format!("SELECT `mount_id` FROM `player_mounts` WHERE `player_id` = {guid}");
+        if let Some(mut result) = db.store_query(&query).await? {
+            loop {
+                player.tame_mount(result.get_number("mount_id"));
+                if !result.next() {
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Motsvarar `IOLoginData::loadItems`
+    async fn load_items(table: &str, guid: u32) -> Result<ItemMap> {
+        let mut items = ItemMap::new();
+        let query = format!(
+            "SELECT `pid`, `sid`, `itemtype`, `count`, `attributes` FROM `{table}` \
+             WHERE `player_id` = {guid} ORDER BY `sid` DESC"
+        );
+        let Some(mut result) = Database::instance().store_query(&query).await? else {
+            return Ok(items);
+        };
+
+        loop {
+            let sid: i32 = result.get_number("sid");
+            let mut item = Item::new(result.get_number("itemtype"), result.get_number("count"));
+            let attributes = result.get_stream("attributes").unwrap_or_default();
+            if item.unserialize_attr(&mut PropStream::new(&attributes)).is_none() {
+                warn!("[IOPlayer::load_items] Failed to unserialize attributes of item {} ({table}, sid {sid})", item.id);
+            }
+            items.insert(sid, (item, result.get_number("pid")));
+            if !result.next() {
+                break;
+            }
+        }
+        Ok(items)
+    }
+
+    /// Lägg varje item i sin förälder, högsta sid först så att barnen är på
+    /// plats innan föräldern flyttas. Items vars pid inte är ett annat items
+    /// sid (plats, depå eller inbox) lämnas till `place`, och hamnar i samma
+    /// ordning som de sparades eftersom de läggs överst baklänges.
+    fn build_item_tree(mut items: ItemMap, mut place: impl FnMut(i32, Item)) {
+        while let Some((_, (item, pid))) = items.pop_last() {
+            match items.get_mut(&pid) {
+                Some((parent, _)) => {
+                    if let Some(container) = parent.get_container_mut() {
+                        container.add_item_front(item);
+                    }
+                }
+                None => place(pid, item),
+            }
+        }
+    }
+
+    /// Spara spelaren. Allt utom när `save` är avstängt skrivs i en och
+    /// samma transaktion. Returnerar false om spelaren inte finns.
+    pub async fn save(player: &Player) -> Result<bool> {
+        let db = Database::instance();
+        let guid = player.guid;
+
+        let query = format!("SELECT `save` FROM `players` WHERE `id` = {guid}");
+        let Some(result) = db.store_query(&query).await? else {
+            return Ok(false);
+        };
+
+        if result.get_number::<u8>("save") == 0 {
+            let mut query = format!("UPDATE `players` SET `lastlogin` = {}", player.last_login);
+            if let Some(ip) = player.last_ip {
+                query.push_str(&format!(", `lastip` = {}", db.escape_blob(&ip_to_bytes(ip))));
+            }
+            query.push_str(&format!(" WHERE `id` = {guid}"));
+            db.execute(&query).await?;
+            return Ok(true);
+        }
+
+        let mut transaction = DbTransaction::begin().await?;
+        transaction.execute(&Self::player_update_query(player)).await?;
+        // `Player:setAccountType` ändrar bara spelaren
+        if player.account_type_changed {
+            transaction.execute(&IOLoginData::account_type_query(player.account_id, player.account_type)).await?;
+        }
+
+        transaction.execute(&format!("DELETE FROM `player_spells` WHERE `player_id` = {guid}")).await?;
+        let mut stmt = DbInsert::new("INSERT INTO `player_spells` (`player_id`, `name`) VALUES ");
+        for spell in player.learned_spells() {
+            stmt.add_row(&format!("{guid},{}", db.escape_string(spell))).await?;
+        }
+        stmt.execute_in(&mut transaction).await?;
+
+        let inventory: Vec<(i32, &Item)> = player.inventory().map(|(slot, item)| (slot as i32, item)).collect();
+        Self::save_item_table(&mut transaction, "player_items", guid, &inventory).await?;
+
+        let depot: Vec<(i32, &Item)> = player
+            .depot_chests()
+            .flat_map(|(depot_id, chest)| chest.items().iter().map(move |item| (depot_id as i32, item)))
+            .collect();
+        Self::save_item_table(&mut transaction, "player_depotitems", guid, &depot).await?;
+
+        let inbox: Vec<(i32, &Item)> = player.inbox.items().iter().map(|item| (0, item)).collect();
+        Self::save_item_table(&mut transaction, "player_inboxitems", guid, &inbox).await?;
+
+        let store_inbox: Vec<(i32, &Item)> = player.store_inbox.items().iter().map(|item| (0, item)).collect();
+        Self::save_item_table(&mut transaction, "player_storeinboxitems", guid, &store_inbox).await?;
+
+        transaction.execute(&format!("DELETE FROM `player_storage` WHERE `player_id` = {guid}")).await?;
+        let mut stmt = DbInsert::new("INSERT INTO `player_storage` (`player_id`, `key`, `value`) VALUES ");
+        for (key, value) in player.storage() {
+            stmt.add_row(&format!("{guid},{key},{value}")).await?;
+        }
+        stmt.execute_in(&mut transaction).await?;
+
+        transaction.execute(&format!("DELETE FROM `player_outfits` WHERE `player_id` = {guid}")).await?;
+        let mut stmt = DbInsert::new("INSERT INTO `player_outfits` (`player_id`, `outfit_id`, `addons`) VALUES ");
+        for (look_type, addons) in player.outfits() {
+            stmt.add_row(&format!("{guid},{look_type},{addons}")).await?;
+        }
+        stmt.execute_in(&mut transaction).await?;
+
+        transaction.execute(&format!("DELETE FROM `player_mounts` WHERE `player_id` = {guid}")).await?;
+        let mut stmt = DbInsert::new("INSERT INTO `player_mounts` (`player_id`, `mount_id`) VALUES ");
+        for mount_id in player.mounts() {
+            stmt.add_row(&format!("{guid},{mount_id}")).await?;
+        }
+        stmt.execute_in(&mut transaction).await?;
+
+        transaction.commit().await?;
+        Ok(true)
+    }
+
+    /// Spara spelaren när den loggar ut. Tiden sätts först, som
+    /// `Player::onRemoveCreature` i TFS, så att scripten vid nästa inloggning
+    /// vet hur länge spelaren varit borta.
+    pub async fn save_on_logout(player: &mut Player) -> Result<bool> {
+        player.last_logout = common::unix_time();
+        Self::save(player).await
+    }
+
+    fn player_update_query(player: &Player) -> String {
+        let db = Database::instance();
+        // utan utseendebyten från conditions
+        let outfit = &player.creature.default_outfit;
+        let pos = player.login_position;
+        // bara röd och svart skalle överlever utloggning
+        let skull = match player.creature.skull {
+            Skull::Red | Skull::Black => player.creature.skull,
+            _ => Skull::None,
+        };
+
+        let mut query = format!(
+            "UPDATE `players` SET `level` = {}, `group_id` = {}, `vocation` = {}, `health` = {}, \
+             `healthmax` = {}, `experience` = {}, `lookbody` = {}, `lookfeet` = {}, `lookhead` = {}, \
+             `looklegs` = {}, `looktype` = {}, `lookaddons` = {}, `lookmount` = {}, `lookmounthead` = {}, \
+             `lookmountbody` = {}, `lookmountlegs` = {}, `lookmountfeet` = {}, `currentmount` = {}, \
+             `randomizemount` = {}, `direction` = {}, `maglevel` = {}, `mana` = {}, `manamax` = {}, \
+             `manaspent` = {}, `soul` = {}, `town_id` = {}, `posx` = {}, `posy` = {}, `posz` = {}, \
+             `cap` = {}, `sex` = {}, `lastlogin` = {}, `conditions` = {}, `skulltime` = {}, `skull` = {}, \
+             `lastlogout` = {}, `balance` = {}, `offlinetraining_time` = {}, `offlinetraining_skill` = {}, \
+             `stamina` = {}, `onlinetime` = {}, `blessings` = {}",
+            player.level,
+            player.group.id,
+            player.vocation,
+            player.creature.health,
+            player.base_health_max(),
+            player.experience,
+            outfit.look_body,
+            outfit.look_feet,
+            outfit.look_head,
+            outfit.look_legs,
+            outfit.look_type,
+            outfit.look_addons,
+            outfit.look_mount,
+            outfit.look_mount_head,
+            outfit.look_mount_body,
+            outfit.look_mount_legs,
+            outfit.look_mount_feet,
+            player.current_mount,
+            player.randomize_mount as u8,
+            player.creature.direction as u8,
+            player.mag_level,
+            player.creature.mana,
+            player.base_mana_max(),
+            player.mana_spent,
+            player.soul,
+            player.town_id,
+            pos.x,
+            pos.y,
+            pos.z,
+            player.capacity / 100,
+            player.sex as u8,
+            player.last_login,
+            db.escape_blob(&player.conditions),
+            player.skull_ticks,
+            skull as u8,
+            player.last_logout,
+            player.bank_balance,
+            player.offline_training_time / 1000,
+            player.offline_training_skill,
+            player.stamina_minutes,
+            player.online_time,
+            player.blessings
+        );
+        if let Some(ip) = player.last_ip {
+            query.push_str(&format!(", `lastip` = {}", db.escape_blob(&ip_to_bytes(ip))));
+        }
+        for skill in Skill::ALL {
+            let value = player.skill(skill);
+            let column = skill.column();
+            query.push_str(&format!(", `{column}` = {}, `{column}_tries` = {}", value.level, value.tries));
+        }
+        query.push_str(&format!(" WHERE `id` = {}", player.guid));
+        query
+    }
+
+    /// Skriv om en av item-tabellerna från början, sid räknas från
+    /// `FIRST_ITEM_SID` som i TFS
+    async fn save_item_table(
+        transaction: &mut DbTransaction,
+        table: &str,
+        guid: u32,
+        items: &[(i32, &Item)],
+    ) -> Result<()> {
+        transaction.execute(&format!("DELETE FROM `{table}` WHERE `player_id` = {guid}")).await?;
+        let mut stmt = DbInsert::new(&format!(
+            "INSERT INTO `{table}` (`player_id`, `pid`, `sid`, `itemtype`, `count`, `attributes`) VALUES "
+        ));
+        let mut running_id = FIRST_ITEM_SID;
+        IOLoginData::save_items(guid, items, &mut stmt, &mut running_id).await?;
+        stmt.execute_in(transaction).await
+    }
+
+    /// Spara spelarens död med nivån den dog på och ta bort de äldsta om
+    /// det blir fler än fem. Motsvarar databasdelen av playerdeath.lua.
+    pub async fn add_death(player: &Player, record: &DeathRecord) -> Result<()> {
+        let db = Database::instance();
+        let guid = player.guid;
+        let query = format!(
+            "INSERT INTO `player_deaths` (`player_id`, `time`, `level`, `killed_by`, `is_player`, `mostdamage_by`, \
+             `mostdamage_is_player`, `unjustified`, `mostdamage_unjustified`) \
+             VALUES ({guid}, {}, {}, {}, {}, {}, {}, {}, {})",
+            common::unix_time(),
+            player.level,
+            db.escape_string(&record.killed_by),
+            record.is_player as u8,
+            db.escape_string(&record.mostdamage_by),
+            record.mostdamage_is_player as u8,
+            record.unjustified as u8,
+            record.mostdamage_unjustified as u8
+        );
+        db.execute(&query).await?;
+
+        let query = format!("SELECT COUNT(*) AS `count` FROM `player_deaths` WHERE `player_id` = {guid}");
+        let records = db.store_query(&query).await?.map_or(0, |result| result.get_number::<u64>("count"));
+        if records > MAX_DEATH_RECORDS {
+            let limit = records - MAX_DEATH_RECORDS;
+            db.execute(&format!("DELETE FROM `player_deaths` WHERE `player_id` = {guid} ORDER BY `time` LIMIT {limit}"))
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+/// `lastip` är 4 byte för IPv4 och 16 för IPv6; standardvärdet '0' blir None
+fn ip_from_bytes(bytes: &[u8]) -> Option<IpAddr> {
+    match bytes.len() {
+        4 => Some(IpAddr::from(<[u8; 4]>::try_from(bytes).ok()?)),
+        16 => Some(IpAddr::from(<[u8; 16]>::try_from(bytes).ok()?)),
+        _ => None,
+    }
+}
+
+fn ip_to_bytes(ip: IpAddr) -> Vec<u8> {
+    match ip {
+        IpAddr::V4(ip) => ip.octets().to_vec(),
+        IpAddr::V6(ip) => ip.octets().to_vec(),
+    }
+}
@@ -1,4 +1,7 @@
+#[path = "../../../src/db/database.rs"]
 pub mod database;
 pub mod iologindata;
 pub mod ioplayer;
 pub mod iomap;
+pub mod iohouse;
+pub mod ioguild;
@@ -0,0 +1,1451 @@
+//! Striden, motsvarar `Combat`, `AreaCombat` och stridsdelarna av `Game`,
+//! `Creature`, `Player` och `Monster` i TFS.
+//!
+//! En `Combat` är det ett spell- eller vapenscript bygger upp: skadetyp,
+//! effekter, formel, område och conditions. Det striden behöver veta om
+//! varelser och rutor frågar den `CombatView` om, och det som ska hända
+//! (skada, effekter, conditions) läggs i ordning som `CombatEvent` som
+//! spelet utför. Lua-callbacks (`onGetFormulaValues`, `onTargetTile`,
+//! `onTargetCreature`) går genom `CombatCallbacks`.
+
+use std::collections::BTreeMap;
+
+use common::{normal_random, uniform_random, CombatType, Config, Direction, MagicEffect, Position, ReturnValue, ShootType};
+use entities::creature::Skull;
+use entities::player::{CONST_SLOT_ARMOR, CONST_SLOT_FEET, CONST_SLOT_HEAD, CONST_SLOT_LEGS, CONST_SLOT_NECKLACE, CONST_SLOT_RING};
+use entities::{Condition, ConditionData, ConditionType, CreatureType, FightMode, Monster, Player, PlayerFlags, Skill};
+use items::WeaponType;
+
+use crate::vocation::Vocation;
+
+pub use entities::creature::BlockType;
+
+/// Fält som byts mot sin no-pvp-variant när en spelare skapar dem där
+/// pvp inte gäller, `ITEM_*` i const.h
+const ITEM_FIREFIELD_PVP_FULL: u16 = 1487;
+const ITEM_FIREFIELD_NOPVP: u16 = 1500;
+const ITEM_POISONFIELD_PVP: u16 = 1490;
+const ITEM_POISONFIELD_NOPVP: u16 = 1503;
+const ITEM_ENERGYFIELD_PVP: u16 = 1491;
+const ITEM_ENERGYFIELD_NOPVP: u16 = 1504;
+const ITEM_MAGICWALL: u16 = 1497;
+const ITEM_MAGICWALL_NOPVP: u16 = 20669;
+const ITEM_WILDGROWTH: u16 = 1499;
+const ITEM_WILDGROWTH_NOPVP: u16 = 20670;
+
+/// `worldType` i config.lua, motsvarar `WorldType_t`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WorldType {
+    NoPvp,
+    #[default]
+    Pvp,
+    PvpEnforced,
+}
+
+impl WorldType {
+    /// Som i `otserv.cpp`; okända namn ger pvp
+    pub fn from_name(name: &str) -> Self {
+        match name.to_ascii_lowercase().as_str() {
+            "no-pvp" | "nopvp" => WorldType::NoPvp,
+            "pvp-enforced" | "pvpenforced" => WorldType::PvpEnforced,
+            _ => WorldType::Pvp,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CombatConfig {
+    pub world_type: WorldType,
+    /// Spelare under nivån kan varken anfalla eller anfallas av spelare
+    pub protection_level: u32,
+    /// Millisekunder i strid efter en attack
+    pub pz_locked: i32,
+}
+
+impl Default for CombatConfig {
+    fn default() -> Self {
+        Self { world_type: WorldType::Pvp, protection_level: 1, pz_locked: 60000 }
+    }
+}
+
+impl CombatConfig {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            world_type: WorldType::from_name(&config.world_type),
+            protection_level: config.protection_level.max(0) as u32,
+            pz_locked: config.pz_locked,
+        }
+    }
+}
+
+/// Varifrån skadan kommer, motsvarar `CombatOrigin`
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CombatOrigin {
+    None = 0,
+    Condition = 1,
+    #[default]
+    Spell = 2,
+    Melee = 3,
+    Ranged = 4,
+}
+
+impl CombatOrigin {
+    pub fn from_u8(value: u8) -> Option<Self> {
+        Some(match value {
+            0 => CombatOrigin::None,
+            1 => CombatOrigin::Condition,
+            2 => CombatOrigin::Spell,
+            3 => CombatOrigin::Melee,
+            4 => CombatOrigin::Ranged,
+            _ => return None,
+        })
+    }
+}
+
+/// En del av skadan, negativ för skada och positiv för helning
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CombatValue {
+    pub combat_type: CombatType,
+    pub value: i32,
+}
+
+/// Motsvarar `CombatDamage`. Vapen med element har en andra del.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CombatDamage {
+    pub primary: CombatValue,
+    pub secondary: CombatValue,
+    pub origin: CombatOrigin,
+    pub block_type: BlockType,
+    pub critical: bool,
+    pub leeched: bool,
+}
+
+impl CombatDamage {
+    pub fn new(combat_type: CombatType, value: i32, origin: CombatOrigin) -> Self {
+        Self { primary: CombatValue { combat_type, value }, origin, ..Self::default() }
+    }
+}
+
+/// Motsvarar `formulaType_t`
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FormulaType {
+    #[default]
+    Undefined = 0,
+    LevelMagic = 1,
+    Skill = 2,
+    Damage = 3,
+}
+
+impl FormulaType {
+    pub fn from_u8(value: u8) -> Option<Self> {
+        Some(match value {
+            0 => FormulaType::Undefined,
+            1 => FormulaType::LevelMagic,
+            2 => FormulaType::Skill,
+            3 => FormulaType::Damage,
+            _ => return None,
+        })
+    }
+}
+
+/// `Combat:setFormula`. Med `Damage` är `mina`/`maxa` skadan direkt.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Formula {
+    pub kind: FormulaType,
+    pub mina: f64,
+    pub minb: f64,
+    pub maxa: f64,
+    pub maxb: f64,
+}
+
+/// Motsvarar `CombatParam_t`, `COMBAT_PARAM_*` i scripten
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CombatParam {
+    Type = 1,
+    Effect = 2,
+    DistanceEffect = 3,
+    BlockShield = 4,
+    BlockArmor = 5,
+    TargetCasterOrTopmost = 6,
+    CreateItem = 7,
+    Aggressive = 8,
+    Dispel = 9,
+    UseCharges = 10,
+}
+
+impl CombatParam {
+    pub const ALL: [CombatParam; 10] = [
+        CombatParam::Type,
+        CombatParam::Effect,
+        CombatParam::DistanceEffect,
+        CombatParam::BlockShield,
+        CombatParam::BlockArmor,
+        CombatParam::TargetCasterOrTopmost,
+        CombatParam::CreateItem,
+        CombatParam::Aggressive,
+        CombatParam::Dispel,
+        CombatParam::UseCharges,
+    ];
+
+    pub fn from_u8(value: u8) -> Option<Self> {
+        Self::ALL.get((value as usize).checked_sub(1)?).copied()
+    }
+}
+
+/// Motsvarar `CombatParams`
+#[derive(Debug, Clone, PartialEq)]
+pub struct CombatParams {
+    pub combat_type: CombatType,
+    pub impact_effect: MagicEffect,
+    pub distance_effect: ShootType,
+    pub blocked_by_armor: bool,
+    pub blocked_by_shield: bool,
+    /// Bara översta varelsen på rutan, eller kastaren på sin egen ruta
+    pub target_caster_or_topmost: bool,
+    pub aggressive: bool,
+    pub use_charges: bool,
+    pub ignore_resistances: bool,
+    /// Conditions som tas bort från målet
+    pub dispel_type: ConditionType,
+    /// Fält som skapas på rutorna
+    pub item_id: u16,
+    pub origin: CombatOrigin,
+}
+
+impl Default for CombatParams {
+    fn default() -> Self {
+        Self {
+            combat_type: CombatType::NONE,
+            impact_effect: MagicEffect::None,
+            distance_effect: ShootType::NONE,
+            blocked_by_armor: false,
+            blocked_by_shield: false,
+            target_caster_or_topmost: false,
+            aggressive: true,
+            use_charges: false,
+            ignore_resistances: false,
+            dispel_type: ConditionType::NONE,
+            item_id: 0,
+            origin: CombatOrigin::Spell,
+        }
+    }
+}
+
+/// Ett område som matris, motsvarar `MatrixArea`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatrixArea {
+    rows: usize,
+    cols: usize,
+    cells: Vec<bool>,
+    /// (rad, kolumn) som hamnar på målet
+    center: (usize, usize),
+}
+
+impl MatrixArea {
+    /// 1 är med, 2 är mitten och 3 är både och. Motsvarar `createArea`.
+    pub fn new(values: &[u32], rows: usize) -> Self {
+        let rows = rows.max(1);
+        let cols = values.len() / rows;
+        let mut area = Self { rows, cols, cells: vec![false; rows * cols], center: (0, 0) };
+        for (i, &value) in values.iter().take(rows * cols).enumerate() {
+            let (row, col) = (i / cols, i % cols);
+            if value == 1 || value == 3 {
+                area.cells[i] = true;
+            }
+            if value == 2 || value == 3 {
+                area.center = (row, col);
+            }
+        }
+        area
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> bool {
+        self.cells[row * self.cols + col]
+    }
+
+    /// Vrid medurs, så att ett område som pekar norrut pekar österut
+    fn rotate90(&self) -> Self {
+        let mut cells = vec![false; self.cells.len()];
+        for row in 0..self.rows {
+            for col in 0..self.cols {
+                cells[col * self.rows + (self.rows - 1 - row)] = self.get(row, col);
+            }
+        }
+        let (row, col) = self.center;
+        Self { rows: self.cols, cols: self.rows, cells, center: (col, self.rows - 1 - row) }
+    }
+
+    /// Spegla vänster-höger
+    fn mirror(&self) -> Self {
+        let mut area = self.clone();
+        for row in 0..self.rows {
+            for col in 0..self.cols {
+                area.cells[row * self.cols + (self.cols - 1 - col)] = self.get(row, col);
+            }
+        }
+        area.center.1 = self.cols - 1 - self.center.1;
+        area
+    }
+
+    /// Spegla upp-ned
+    fn flip(&self) -> Self {
+        let mut area = self.clone();
+        for row in 0..self.rows {
+            for col in 0..self.cols {
+                area.cells[(self.rows - 1 - row) * self.cols + col] = self.get(row, col);
+            }
+        }
+        area.center.0 = self.rows - 1 - self.center.0;
+        area
+    }
+}
+
+/// Ett område per riktning, motsvarar `AreaCombat`. Områdena ritas som om
+/// kastaren tittar norrut; diagonala områden (`extArea`) som norrväst.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct AreaCombat {
+    areas: BTreeMap<u8, MatrixArea>,
+    has_ext_area: bool,
+}
+
+impl AreaCombat {
+    /// `createCombatArea(area, extArea)`, en rad i taget
+    pub fn new(area: &[Vec<u32>], ext_area: &[Vec<u32>]) -> Self {
+        let mut combat_area = Self::default();
+        combat_area.setup_area(&area.concat(), area.len());
+        combat_area.setup_ext_area(&ext_area.concat(), ext_area.len());
+        combat_area
+    }
+
+    /// Motsvarar `AreaCombat::setupArea(vec, rows)`
+    pub fn setup_area(&mut self, values: &[u32], rows: usize) {
+        if values.is_empty() {
+            return;
+        }
+        let north = MatrixArea::new(values, rows);
+        let east = north.rotate90();
+        let south = east.rotate90();
+        let west = south.rotate90();
+        self.areas.insert(Direction::North as u8, north);
+        self.areas.insert(Direction::East as u8, east);
+        self.areas.insert(Direction::South as u8, south);
+        self.areas.insert(Direction::West as u8, west);
+    }
+
+    /// Motsvarar `AreaCombat::setupExtArea`
+    pub fn setup_ext_area(&mut self, values: &[u32], rows: usize) {
+        if values.is_empty() {
+            return;
+        }
+        self.has_ext_area = true;
+        let north_west = MatrixArea::new(values, rows);
+        let north_east = north_west.mirror();
+        self.areas.insert(Direction::SouthWest as u8, north_west.flip());
+        self.areas.insert(Direction::SouthEast as u8, north_east.flip());
+        self.areas.insert(Direction::NorthWest as u8, north_west);
+        self.areas.insert(Direction::NorthEast as u8, north_east);
+    }
+
+    /// En cirkel, för monstrens `radius`. Motsvarar `AreaCombat::setupArea(radius)`.
+    pub fn circle(radius: u8) -> Self {
+        const AREA: [[u8; 13]; 13] = [
+            [0, 0, 0, 0, 0, 0, 8, 0, 0, 0, 0, 0, 0],
+            [0, 0, 0, 0, 8, 8, 7, 8, 8, 0, 0, 0, 0],
+            [0, 0, 0, 8, 7, 6, 6, 6, 7, 8, 0, 0, 0],
+            [0, 0, 8, 7, 6, 5, 5, 5, 6, 7, 8, 0, 0],
+            [0, 8, 7, 6, 5, 4, 4, 4, 5, 6, 7, 8, 0],
+            [0, 8, 6, 5, 4, 3, 2, 3, 4, 5, 6, 8, 0],
+            [8, 7, 6, 5, 4, 2, 1, 2, 4, 5, 6, 7, 8],
+            [0, 8, 6, 5, 4, 3, 2, 3, 4, 5, 6, 8, 0],
+            [0, 8, 7, 6, 5, 4, 4, 4, 5, 6, 7, 8, 0],
+            [0, 0, 8, 7, 6, 5, 5, 5, 6, 7, 8, 0, 0],
+            [0, 0, 0, 8, 7, 6, 6, 6, 7, 8, 0, 0, 0],
+            [0, 0, 0, 0, 8, 8, 7, 8, 8, 0, 0, 0, 0],
+            [0, 0, 0, 0, 0, 0, 8, 0, 0, 0, 0, 0, 0],
+        ];
+        let values: Vec<u32> = AREA
+            .iter()
+            .flatten()
+            .map(|&cell| match cell {
+                1 => 3,
+                cell if cell > 0 && cell <= radius => 1,
+                _ => 0,
+            })
+            .collect();
+        let mut area = Self::default();
+        area.setup_area(&values, 13);
+        area
+    }
+
+    /// En stråle eller våg, för monstrens `length` och `spread`.
+    /// Motsvarar `AreaCombat::setupArea(length, spread)`.
+    pub fn wave(length: u8, spread: u8) -> Self {
+        let rows = length as usize;
+        let (length, spread) = (length as usize, spread as usize);
+        let cols = (length - length.checked_rem(spread).unwrap_or(0)).checked_div(spread).map_or(1, |n| n * 2 + 1);
+        let mut col_spread = cols;
+        let mut values = Vec::with_capacity(rows * cols);
+        for y in 1..=rows {
+            let min_col = cols - col_spread + 1;
+            let max_col = cols - (cols - col_spread);
+            for x in 1..=cols {
+                values.push(if y == rows && x == (cols - (cols % 2)) / 2 + 1 {
+                    3
+                } else if x >= min_col && x <= max_col {
+                    1
+                } else {
+                    0
+                });
+            }
+            if spread > 0 && y % spread == 0 {
+                col_spread = col_spread.saturating_sub(1);
+            }
+        }
+        let mut area = Self::default();
+        area.setup_area(&values, rows);
+        area
+    }
+
+    /// Området i riktningen från `center` mot `target`. Motsvarar `AreaCombat::getArea`.
+    fn get_area(&self, center: Position, target: Position) -> Option<&MatrixArea> {
+        let dx = Position::get_offset_x(&target, &center);
+        let dy = Position::get_offset_y(&target, &center);
+        let mut dir = if dx < 0 {
+            Direction::West
+        } else if dx > 0 {
+            Direction::East
+        } else if dy < 0 {
+            Direction::North
+        } else {
+            Direction::South
+        };
+        if self.has_ext_area {
+            dir = match (dx.signum(), dy.signum()) {
+                (-1, -1) => Direction::NorthWest,
+                (1, -1) => Direction::NorthEast,
+                (-1, 1) => Direction::SouthWest,
+                (1, 1) => Direction::SouthEast,
+                _ => dir,
+            };
+        }
+        self.areas.get(&(dir as u8))
+    }
+
+    /// Rutorna området täcker när det läggs på `target`, sedda från
+    /// `center`. Rutor utan fri sikt från målet hoppas över. Motsvarar
+    /// `AreaCombat::getList`.
+    pub fn get_list(&self, view: &dyn CombatView, center: Position, target: Position) -> Vec<Position> {
+        let Some(area) = self.get_area(center, target) else {
+            return Vec::new();
+        };
+        let (center_row, center_col) = area.center;
+        let mut list = Vec::new();
+        for row in 0..area.rows {
+            for col in 0..area.cols {
+                if !area.get(row, col) {
+                    continue;
+                }
+                let Some(pos) = target.translated(col as i32 - center_col as i32, row as i32 - center_row as i32, 0) else {
+                    continue;
+                };
+                if view.is_sight_clear(target, pos, true) {
+                    list.push(pos);
+                }
+            }
+        }
+        list
+    }
+}
+
+/// Spelarflaggorna som rör strid, från gruppen (`PlayerFlag_*`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CombatFlags {
+    pub cannot_use_combat: bool,
+    pub cannot_attack_player: bool,
+    pub cannot_attack_monster: bool,
+    pub cannot_be_attacked: bool,
+    pub ignore_protection_zone: bool,
+    pub not_gain_in_fight: bool,
+}
+
+impl CombatFlags {
+    pub fn from_player(player: &Player) -> Self {
+        Self {
+            cannot_use_combat: player.has_flag(PlayerFlags::CANNOT_USE_COMBAT),
+            cannot_attack_player: player.has_flag(PlayerFlags::CANNOT_ATTACK_PLAYER),
+            cannot_attack_monster: player.has_flag(PlayerFlags::CANNOT_ATTACK_MONSTER),
+            cannot_be_attacked: player.has_flag(PlayerFlags::CANNOT_BE_ATTACKED),
+            ignore_protection_zone: player.has_flag(PlayerFlags::IGNORE_PROTECTION_ZONE),
+            not_gain_in_fight: player.has_flag(PlayerFlags::NOT_GAIN_IN_FIGHT),
+        }
+    }
+}
+
+/// Det striden behöver veta om en varelse
+#[derive(Debug, Clone, PartialEq)]
+pub struct Combatant {
+    pub id: u32,
+    pub creature_type: CreatureType,
+    pub position: Position,
+    /// Summonens master och dess typ
+    pub master: Option<(u32, CreatureType)>,
+    pub level: u32,
+    pub magic_level: u32,
+    pub skull: Skull,
+    /// Yrket tillåter pvp, `Vocation::allowsPvp`
+    pub allows_pvp: bool,
+    pub secure_mode: bool,
+    pub attackable: bool,
+    pub in_ghost_mode: bool,
+    pub flags: CombatFlags,
+    pub armor: i32,
+    pub defense: i32,
+    /// `blockCount > 0` och varelsen kan försvara sig med sköld eller vapen
+    pub can_block: bool,
+    /// Vapnet spelaren anfaller med, för `ShootType::WEAPON_TYPE`
+    pub weapon_type: WeaponType,
+    pub damage_immunities: CombatType,
+    pub condition_immunities: ConditionType,
+    /// Procent mindre skada per typ, mer om den är negativ. Monstrens `<element>`.
+    pub element_mods: BTreeMap<CombatType, i32>,
+    /// Min- och maxskada för spellen monstret kastar, `Monster::getCombatValues`
+    pub combat_values: Option<(i32, i32)>,
+}
+
+impl Combatant {
+    pub fn new(id: u32, creature_type: CreatureType, position: Position) -> Self {
+        Self {
+            id,
+            creature_type,
+            position,
+            master: None,
+            level: 0,
+            magic_level: 0,
+            skull: Skull::None,
+            allows_pvp: true,
+            secure_mode: false,
+            attackable: true,
+            in_ghost_mode: false,
+            flags: CombatFlags::default(),
+            armor: 0,
+            defense: 0,
+            can_block: false,
+            weapon_type: WeaponType::None,
+            damage_immunities: CombatType::NONE,
+            condition_immunities: ConditionType::NONE,
+            element_mods: BTreeMap::new(),
+            combat_values: None,
+        }
+    }
+
+    /// Spelaren med yrket, motsvarar getters på `Player`
+    pub fn from_player(player: &Player, vocation: Option<&Vocation>, now: u64) -> Self {
+        let creature = &player.creature;
+        let mut combatant = Self::new(creature.id, CreatureType::Player, creature.position);
+        combatant.level = player.level;
+        combatant.magic_level = player.magic_level();
+        combatant.skull = creature.skull;
+        combatant.allows_pvp = vocation.is_none_or(|voc| voc.allow_pvp);
+        combatant.secure_mode = player.secure_mode;
+        combatant.flags = CombatFlags::from_player(player);
+        combatant.armor = player_armor(player, vocation);
+        combatant.defense = player_defense(player, vocation, now);
+        combatant.can_block = creature.block_count > 0 && creature.can_use_defense;
+        combatant.weapon_type = player.get_weapon_type();
+        combatant
+    }
+
+    /// Monstret med typens rustning, immuniteter och element
+    pub fn from_monster(monster: &Monster) -> Self {
+        let creature = &monster.creature;
+        let mtype = &monster.mtype;
+        let mut combatant = Self::new(creature.id, CreatureType::Monster, creature.position);
+        combatant.master = creature.master.and_then(|id| Some((id, CreatureType::of_id(id)?)));
+        combatant.skull = creature.skull;
+        combatant.attackable = mtype.is_attackable;
+        combatant.armor = mtype.armor;
+        combatant.defense = mtype.defense;
+        combatant.can_block = creature.block_count > 0 && creature.can_use_defense;
+        combatant.damage_immunities = mtype.damage_immunities;
+        combatant.condition_immunities = mtype.condition_immunities;
+        combatant.element_mods = mtype.element_map.clone();
+        combatant
+    }
+
+    pub fn is_player(&self) -> bool {
+        self.creature_type == CreatureType::Player
+    }
+
+    pub fn is_monster(&self) -> bool {
+        self.creature_type == CreatureType::Monster
+    }
+
+    /// Spelaren som äger varelsen, den själv eller summonens master
+    pub fn player_owner(&self) -> Option<u32> {
+        match self.master {
+            Some((master, CreatureType::Player)) => Some(master),
+            Some(_) => None,
+            None => self.is_player().then_some(self.id),
+        }
+    }
+
+    /// En spelares summon
+    fn is_player_summon(&self) -> bool {
+        matches!(self.master, Some((_, CreatureType::Player)))
+    }
+
+    pub fn is_immune(&self, combat_type: CombatType) -> bool {
+        self.damage_immunities.intersects(combat_type)
+    }
+
+    pub fn is_immune_to_condition(&self, condition_type: ConditionType) -> bool {
+        self.condition_immunities.intersects(condition_type)
+    }
+}
+
+/// Det striden behöver veta om en ruta
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CombatTile {
+    /// Blockerar projektiler, byter våning eller är en teleport
+    pub blocks_combat: bool,
+    /// Något på rutan är i vägen, `TILESTATE_BLOCKSOLID`
+    pub block_solid: bool,
+    pub protection_zone: bool,
+    pub no_pvp_zone: bool,
+    pub pvp_zone: bool,
+}
+
+/// Det striden behöver veta om omvärlden. Spelet implementerar den med
+/// kartan och sina varelser.
+pub trait CombatView {
+    fn combatant(&self, id: u32) -> Option<Combatant>;
+
+    /// En ruta som inte finns räknas som en tom ruta
+    fn tile(&self, pos: Position) -> CombatTile;
+
+    /// Varelserna på rutan, den översta först
+    fn creatures_at(&self, pos: Position) -> Vec<u32>;
+
+    fn is_sight_clear(&self, from: Position, to: Position, same_floor: bool) -> bool;
+
+    /// Skallen `viewer` ser på spelaren, motsvarar `Player::getSkullClient`
+    fn skull_client(&self, viewer: u32, player: u32) -> Skull;
+
+    /// Samma party eller gille; de låser inte varandra
+    fn is_partner_or_guild_mate(&self, player: u32, other: u32) -> bool;
+}
+
+/// Lua-callbacks som `Combat:setCallback` satt
+pub trait CombatCallbacks {
+    /// `onGetFormulaValues` för spelaren, `None` utan callback
+    fn formula_values(&mut self, _player: &Combatant, _formula: FormulaType) -> Option<(i32, i32)> {
+        None
+    }
+
+    /// Största skadan (negativ) och elementskadan spelarens vapen gör, för
+    /// `FormulaType::Skill`. `None` utan registrerat vapen. Med `use_charges`
+    /// tappar vapnet en laddning.
+    fn weapon_damage(&mut self, _player: &Combatant, _use_charges: bool) -> Option<(i32, CombatValue)> {
+        None
+    }
+
+    fn on_target_tile(&mut self, _caster: Option<u32>, _pos: Position) {}
+
+    fn on_target_creature(&mut self, _caster: Option<u32>, _target: u32) {}
+}
+
+impl CombatCallbacks for () {}
+
+/// Det spelet ska göra, i den ordning det ska ske
+#[derive(Debug, Clone, PartialEq)]
+pub enum CombatEvent {
+    MagicEffect { pos: Position, effect: MagicEffect },
+    DistanceEffect { from: Position, to: Position, effect: ShootType },
+    /// `Game::combatChangeHealth`: skada (negativ) eller helning, redan blockerad
+    ChangeHealth { attacker: Option<u32>, target: u32, damage: CombatDamage },
+    /// `Game::combatChangeMana`
+    ChangeMana { attacker: Option<u32>, target: u32, damage: CombatDamage },
+    /// Målet försvarade sig och en av dess blockeringar går åt
+    UseBlock(u32),
+    AddCondition { target: u32, owner: Option<u32>, condition: Condition },
+    Dispel { target: u32, condition_type: ConditionType },
+    CreateItem { pos: Position, item_id: u16, owner: Option<u32> },
+    /// Spelaren hamnar i strid. Med `pvp_target` anföll den en spelare
+    /// utanför partyt och gillet, och spelet kör först
+    /// `skull::on_attacked_player`, som pz-låser och ger skallar.
+    InFight { player: u32, pvp_target: Option<u32> },
+}
+
+/// Vad en Lua-`Combat` riktas mot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CombatTarget {
+    Creature(u32),
+    Position(Position),
+    /// En rune på en ruta utan varelse: med område som `Position`, utan
+    /// bara projektilen och en puff
+    TargetPosition(Position),
+}
+
+/// En strid som scripten bygger upp, motsvarar `Combat`
+#[derive(Debug, Clone, Default)]
+pub struct Combat {
+    pub params: CombatParams,
+    pub formula: Formula,
+    area: Option<AreaCombat>,
+    conditions: Vec<Condition>,
+}
+
+impl Combat {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Motsvarar `Combat::setParam`
+    pub fn set_param(&mut self, param: CombatParam, value: u32) -> bool {
+        let params = &mut self.params;
+        match param {
+            CombatParam::Type => params.combat_type = CombatType(value as u16),
+            CombatParam::Effect => params.impact_effect = MagicEffect::from_u8(value as u8).unwrap_or_default(),
+            CombatParam::DistanceEffect => params.distance_effect = ShootType(value as u8),
+            CombatParam::BlockShield => params.blocked_by_shield = value != 0,
+            CombatParam::BlockArmor => params.blocked_by_armor = value != 0,
+            CombatParam::TargetCasterOrTopmost => params.target_caster_or_topmost = value != 0,
+            CombatParam::CreateItem => params.item_id = value as u16,
+            CombatParam::Aggressive => params.aggressive = value != 0,
+            CombatParam::Dispel => params.dispel_type = ConditionType(value),
+            CombatParam::UseCharges => params.use_charges = value != 0,
+        }
+        true
+    }
+
+    /// Motsvarar `Combat::getParam`
+    pub fn get_param(&self, param: CombatParam) -> u32 {
+        let params = &self.params;
+        match param {
+            CombatParam::Type => params.combat_type.0 as u32,
+            CombatParam::Effect => params.impact_effect as u32,
+            CombatParam::DistanceEffect => params.distance_effect.0 as u32,
+            CombatParam::BlockShield => params.blocked_by_shield as u32,
+            CombatParam::BlockArmor => params.blocked_by_armor as u32,
+            CombatParam::TargetCasterOrTopmost => params.target_caster_or_topmost as u32,
+            CombatParam::CreateItem => params.item_id as u32,
+            CombatParam::Aggressive => params.aggressive as u32,
+            CombatParam::Dispel => params.dispel_type.0,
+            CombatParam::UseCharges => params.use_charges as u32,
+        }
+    }
+
+    /// Motsvarar `Combat::setPlayerCombatValues`
+    pub fn set_formula(&mut self, kind: FormulaType, mina: f64, minb: f64, maxa: f64, maxb: f64) {
+        self.formula = Formula { kind, mina, minb, maxa, maxb };
+    }
+
+    pub fn set_area(&mut self, area: AreaCombat) {
+        self.area = Some(area);
+    }
+
+    pub fn area(&self) -> Option<&AreaCombat> {
+        self.area.as_ref()
+    }
+
+    pub fn has_area(&self) -> bool {
+        self.area.is_some()
+    }
+
+    pub fn add_condition(&mut self, condition: Condition) {
+        self.conditions.push(condition);
+    }
+
+    pub fn clear_conditions(&mut self) {
+        self.conditions.clear();
+    }
+
+    pub fn conditions(&self) -> &[Condition] {
+        &self.conditions
+    }
+
+    /// Skadan innan blockering. Monster använder värdena från spellen de
+    /// kastar, spelare formeln eller `onGetFormulaValues`. Motsvarar
+    /// `Combat::getCombatDamage`.
+    pub fn get_combat_damage(&self, caster: Option<&Combatant>, callbacks: &mut dyn CombatCallbacks) -> CombatDamage {
+        let mut damage = CombatDamage::new(self.params.combat_type, 0, self.params.origin);
+        let formula = &self.formula;
+        if formula.kind == FormulaType::Damage {
+            damage.primary.value = random_between(formula.mina, formula.maxa);
+            return damage;
+        }
+        let Some(caster) = caster else {
+            return damage;
+        };
+        if let Some((min, max)) = caster.combat_values {
+            damage.primary.value = random_between(min as f64, max as f64);
+        } else if caster.is_player() {
+            if let Some((min, max)) = callbacks.formula_values(caster, formula.kind) {
+                damage.primary.value = random_between(min as f64, max as f64);
+            } else if formula.kind == FormulaType::LevelMagic {
+                let level_formula = (caster.level * 2 + caster.magic_level * 3) as f64;
+                damage.primary.value = random_between(
+                    level_formula.mul_add(formula.mina, formula.minb),
+                    level_formula.mul_add(formula.maxa, formula.maxb),
+                );
+            } else if formula.kind == FormulaType::Skill {
+                match callbacks.weapon_damage(caster, self.params.use_charges) {
+                    Some((max_damage, element)) => {
+                        damage.primary.value =
+                            random_between(formula.minb, (max_damage as f64).mul_add(formula.maxa, formula.maxb));
+                        damage.secondary = element;
+                    }
+                    None => damage.primary.value = random_between(formula.minb, formula.maxb),
+                }
+            }
+        }
+        damage
+    }
+
+    /// Kör striden från `caster` mot en varelse eller ett område runt en
+    /// position. Motsvarar de två `Combat::doCombat`.
+    pub fn do_combat(
+        &self,
+        config: &CombatConfig,
+        view: &dyn CombatView,
+        caster: Option<u32>,
+        target: CombatTarget,
+        callbacks: &mut dyn CombatCallbacks,
+    ) -> Vec<CombatEvent> {
+        let caster = caster.and_then(|id| view.combatant(id));
+        let mut events = Vec::new();
+        match target {
+            CombatTarget::Creature(target) => {
+                if let Some(target) = view.combatant(target) {
+                    self.do_combat_creature(config, view, caster.as_ref(), &target, callbacks, &mut events);
+                }
+            }
+            CombatTarget::Position(pos) => {
+                self.do_combat_area(config, view, caster.as_ref(), pos, callbacks, &mut events);
+            }
+            CombatTarget::TargetPosition(pos) if self.has_area() => {
+                self.do_combat_area(config, view, caster.as_ref(), pos, callbacks, &mut events);
+            }
+            CombatTarget::TargetPosition(pos) => {
+                if let Some(caster) = &caster {
+                    self.add_distance_effect(caster, pos, &mut events);
+                }
+                events.push(CombatEvent::MagicEffect { pos, effect: MagicEffect::Poff });
+            }
+        }
+        events
+    }
+
+    fn can_combat(&self, config: &CombatConfig, view: &dyn CombatView, caster: Option<&Combatant>, target: &Combatant) -> bool {
+        !self.params.aggressive
+            || (caster.map(|c| c.id) != Some(target.id) && can_do_combat(config, view, caster, target) == ReturnValue::NoError)
+    }
+
+    fn do_combat_creature(
+        &self,
+        config: &CombatConfig,
+        view: &dyn CombatView,
+        caster: Option<&Combatant>,
+        target: &Combatant,
+        callbacks: &mut dyn CombatCallbacks,
+        events: &mut Vec<CombatEvent>,
+    ) {
+        let params = &self.params;
+        let is_self = caster.map(|c| c.id) == Some(target.id);
+        let can_combat = self.can_combat(config, view, caster, target);
+
+        if params.combat_type != CombatType::NONE {
+            let damage = self.get_combat_damage(caster, callbacks);
+            if (is_self || can_combat) && params.impact_effect != MagicEffect::None {
+                events.push(CombatEvent::MagicEffect { pos: target.position, effect: params.impact_effect });
+            }
+            if can_combat {
+                if let Some(caster) = caster {
+                    self.add_distance_effect(caster, target.position, events);
+                }
+                self.do_target_combat(view, caster, target, damage, events);
+                callbacks.on_target_creature(caster.map(|c| c.id), target.id);
+            }
+            return;
+        }
+
+        if !can_combat {
+            return;
+        }
+        if params.origin != CombatOrigin::Melee {
+            self.add_conditions(caster, target, events);
+        }
+        if params.dispel_type != ConditionType::NONE {
+            events.push(CombatEvent::Dispel { target: target.id, condition_type: params.dispel_type });
+        }
+        self.combat_tile_effects(config, view, caster, target.position, callbacks, events);
+        callbacks.on_target_creature(caster.map(|c| c.id), target.id);
+        if let Some(caster) = caster {
+            self.add_distance_effect(caster, target.position, events);
+        }
+    }
+
+    fn do_combat_area(
+        &self,
+        config: &CombatConfig,
+        view: &dyn CombatView,
+        caster: Option<&Combatant>,
+        pos: Position,
+        callbacks: &mut dyn CombatCallbacks,
+        events: &mut Vec<CombatEvent>,
+    ) {
+        let params = &self.params;
+        let center = caster.map_or(pos, |c| c.position);
+        let tiles = match &self.area {
+            Some(area) => area.get_list(view, center, pos),
+            None => vec![pos],
+        };
+        let damage = (params.combat_type != CombatType::NONE).then(|| self.get_combat_damage(caster, callbacks));
+
+        if let Some(caster) = caster {
+            self.add_distance_effect(caster, pos, events);
+        }
+
+        let mut targets = Vec::new();
+        for tile in tiles {
+            if can_do_combat_tile(view, caster, tile, params.aggressive) != ReturnValue::NoError {
+                continue;
+            }
+            self.combat_tile_effects(config, view, caster, tile, callbacks, events);
+
+            let creatures = view.creatures_at(tile);
+            let top = creatures.first().copied();
+            for id in creatures {
+                if params.target_caster_or_topmost {
+                    let on_caster_tile = caster.is_some_and(|c| c.position == tile);
+                    let wanted = if on_caster_tile { caster.map(|c| c.id) } else { top };
+                    if wanted != Some(id) {
+                        continue;
+                    }
+                }
+                let Some(target) = view.combatant(id) else {
+                    continue;
+                };
+                if self.can_combat(config, view, caster, &target) {
+                    targets.push(target);
+                }
+            }
+        }
+
+        for target in targets {
+            match damage {
+                Some(damage) => {
+                    self.do_target_combat(view, caster, &target, damage, events);
+                }
+                None => {
+                    if params.origin != CombatOrigin::Melee {
+                        self.add_conditions(caster, &target, events);
+                    }
+                    if params.dispel_type != ConditionType::NONE {
+                        events.push(CombatEvent::Dispel { target: target.id, condition_type: params.dispel_type });
+                    }
+                }
+            }
+            callbacks.on_target_creature(caster.map(|c| c.id), target.id);
+        }
+    }
+
+    /// En färdig skada mot målet, som en träff med ett vapen: projektilen,
+    /// blockeringen och conditions. Returnerar hur träffen blockerades.
+    /// Motsvarar den statiska `Combat::doTargetCombat`.
+    pub fn do_target_damage(
+        &self,
+        view: &dyn CombatView,
+        caster: &Combatant,
+        target: &Combatant,
+        damage: CombatDamage,
+    ) -> (BlockType, Vec<CombatEvent>) {
+        let mut events = Vec::new();
+        self.add_distance_effect(caster, target.position, &mut events);
+        let block_type = self.do_target_combat(view, Some(caster), target, damage, &mut events);
+        (block_type, events)
+    }
+
+    /// Blockera, dela skadan mellan spelare och lägg conditions. Motsvarar
+    /// `Combat::doTargetCombat` och varje mål i `doAreaCombat`.
+    fn do_target_combat(
+        &self,
+        view: &dyn CombatView,
+        caster: Option<&Combatant>,
+        target: &Combatant,
+        mut damage: CombatDamage,
+        events: &mut Vec<CombatEvent>,
+    ) -> BlockType {
+        let params = &self.params;
+        let attacker = caster.map(|c| c.id);
+
+        if damage.primary.combat_type == CombatType::MANADRAIN {
+            events.push(CombatEvent::ChangeMana { attacker, target: target.id, damage });
+        } else {
+            let (blocked, block_events) = combat_block_hit(
+                &mut damage,
+                target,
+                params.blocked_by_shield,
+                params.blocked_by_armor,
+                params.item_id != 0,
+                params.ignore_resistances,
+            );
+            events.extend(block_events);
+            if blocked {
+                return damage.block_type;
+            }
+            // spelare gör halv skada mot spelare utan svart skalle
+            if caster.is_some_and(|c| c.is_player() && c.id != target.id)
+                && target.is_player()
+                && target.skull != Skull::Black
+                && damage.primary.combat_type != CombatType::HEALING
+            {
+                damage.primary.value /= 2;
+                damage.secondary.value /= 2;
+            }
+            events.push(CombatEvent::ChangeHealth { attacker, target: target.id, damage });
+        }
+
+        if matches!(damage.block_type, BlockType::None | BlockType::Armor) {
+            self.add_conditions(caster, target, events);
+        }
+        if params.dispel_type != ConditionType::NONE {
+            events.push(CombatEvent::Dispel { target: target.id, condition_type: params.dispel_type });
+        }
+        if let (Some(caster), true) = (caster, params.aggressive) {
+            if let Some(pvp) = on_attacked_creature(view, caster, target) {
+                if let Some(player) = caster.player_owner() {
+                    events.push(CombatEvent::InFight { player, pvp_target: pvp.then_some(target.id) });
+                }
+            }
+        }
+        damage.block_type
+    }
+
+    fn add_conditions(&self, caster: Option<&Combatant>, target: &Combatant, events: &mut Vec<CombatEvent>) {
+        let is_self = caster.map(|c| c.id) == Some(target.id);
+        for condition in &self.conditions {
+            if is_self || !target.is_immune_to_condition(condition.condition_type) {
+                let mut condition = condition.clone();
+                if let (Some(caster), ConditionData::Damage(damage)) = (caster, &mut condition.data) {
+                    damage.owner = caster.id;
+                }
+                events.push(CombatEvent::AddCondition { target: target.id, owner: caster.map(|c| c.id), condition });
+            }
+        }
+    }
+
+    /// Projektilen från kastaren. `ShootType::WEAPON_TYPE` blir virveln för
+    /// spelarens vapen. Motsvarar `Combat::addDistanceEffect`.
+    fn add_distance_effect(&self, caster: &Combatant, to: Position, events: &mut Vec<CombatEvent>) {
+        let mut effect = self.params.distance_effect;
+        if effect == ShootType::WEAPON_TYPE {
+            effect = match caster.weapon_type {
+                WeaponType::Axe if caster.is_player() => ShootType::WHIRLWIND_AXE,
+                WeaponType::Sword if caster.is_player() => ShootType::WHIRLWIND_SWORD,
+                WeaponType::Club if caster.is_player() => ShootType::WHIRLWIND_CLUB,
+                _ => ShootType::NONE,
+            };
+        }
+        if effect != ShootType::NONE {
+            events.push(CombatEvent::DistanceEffect { from: caster.position, to, effect });
+        }
+    }
+
+    /// Fält, tile-callback och träffeffekt på en ruta. Motsvarar
+    /// `Combat::combatTileEffects`.
+    fn combat_tile_effects(
+        &self,
+        config: &CombatConfig,
+        view: &dyn CombatView,
+        caster: Option<&Combatant>,
+        pos: Position,
+        callbacks: &mut dyn CombatCallbacks,
+        events: &mut Vec<CombatEvent>,
+    ) {
+        let params = &self.params;
+        if params.item_id != 0 {
+            let mut item_id = params.item_id;
+            if let Some(player) = caster.and_then(|c| c.player_owner()) {
+                if config.world_type == WorldType::NoPvp || view.tile(pos).no_pvp_zone {
+                    item_id = match item_id {
+                        ITEM_FIREFIELD_PVP_FULL => ITEM_FIREFIELD_NOPVP,
+                        ITEM_POISONFIELD_PVP => ITEM_POISONFIELD_NOPVP,
+                        ITEM_ENERGYFIELD_PVP => ITEM_ENERGYFIELD_NOPVP,
+                        ITEM_MAGICWALL => ITEM_MAGICWALL_NOPVP,
+                        ITEM_WILDGROWTH => ITEM_WILDGROWTH_NOPVP,
+                        other => other,
+                    };
+                } else if matches!(item_id, ITEM_FIREFIELD_PVP_FULL | ITEM_POISONFIELD_PVP | ITEM_ENERGYFIELD_PVP) {
+                    events.push(CombatEvent::InFight { player, pvp_target: None });
+                }
+            }
+            events.push(CombatEvent::CreateItem { pos, item_id, owner: caster.map(|c| c.id) });
+        }
+        callbacks.on_target_tile(caster.map(|c| c.id), pos);
+        if params.impact_effect != MagicEffect::None {
+            events.push(CombatEvent::MagicEffect { pos, effect: params.impact_effect });
+        }
+    }
+}
+
+/// Rustningen på huvud, hals, kropp, ben, fötter och ring gånger yrkets
+/// multiplikator. Motsvarar `Player::getArmor`.
+pub fn player_armor(player: &Player, vocation: Option<&Vocation>) -> i32 {
+    let armor: i32 = [CONST_SLOT_HEAD, CONST_SLOT_NECKLACE, CONST_SLOT_ARMOR, CONST_SLOT_LEGS, CONST_SLOT_FEET, CONST_SLOT_RING]
+        .into_iter()
+        .filter_map(|slot| player.inventory_item(slot))
+        .map(|item| item.get_armor())
+        .sum();
+    (armor as f32 * vocation.map_or(1.0, |voc| voc.armor_multiplier)) as i32
+}
+
+/// Försvaret med sköld eller vapen. Den som anfallit inom attacktiden i
+/// `FightMode::Attack` försvarar sig bara till hälften. Motsvarar
+/// `Player::getDefense` med `getDefenseFactor`.
+pub fn player_defense(player: &Player, vocation: Option<&Vocation>, now: u64) -> i32 {
+    let (shield, weapon) = player.get_shield_and_weapon();
+    let mut defense_skill = player.skill_level(Skill::Fist) as i32;
+    let mut defense_value = 7;
+    if let Some(weapon) = weapon {
+        defense_value = weapon.get_defense() + weapon.get_extra_defense();
+        defense_skill = player.get_weapon_skill(Some(weapon)) as i32;
+    }
+    if let Some(shield) = shield {
+        defense_value = shield.get_defense() + weapon.map_or(0, |weapon| weapon.get_extra_defense());
+        defense_skill = player.skill_level(Skill::Shield) as i32;
+    }
+    if defense_skill == 0 {
+        return if player.fight_mode == FightMode::Defense { 2 } else { 1 };
+    }
+
+    let attack_speed = vocation.map_or(1500, |voc| voc.attack_speed) as u64;
+    let defense_factor = match player.fight_mode {
+        FightMode::Attack if now.saturating_sub(player.last_attack) < attack_speed => 0.5,
+        FightMode::Attack | FightMode::Defense => 1.0,
+        FightMode::Balanced => 0.75,
+    };
+    let multiplier = vocation.map_or(1.0, |voc| voc.defense_multiplier) as f64;
+    ((defense_skill as f64 / 4.0 + 2.23) * defense_value as f64 * 0.15 * defense_factor * multiplier) as i32
+}
+
+/// `normal_random` åt båda hållen, som i TFS där formlerna ofta ger
+/// negativa värden med min och max omkastade
+/// Skadetypen för en skadecondition, motsvarar `Combat::ConditionToDamageType`
+pub fn condition_to_damage_type(condition_type: ConditionType) -> CombatType {
+    match condition_type {
+        ConditionType::FIRE => CombatType::FIRE,
+        ConditionType::ENERGY => CombatType::ENERGY,
+        ConditionType::BLEEDING => CombatType::PHYSICAL,
+        ConditionType::DROWN => CombatType::DROWN,
+        ConditionType::POISON => CombatType::EARTH,
+        ConditionType::FREEZING => CombatType::ICE,
+        ConditionType::DAZZLED => CombatType::HOLY,
+        ConditionType::CURSED => CombatType::DEATH,
+        _ => CombatType::NONE,
+    }
+}
+
+fn random_between(a: f64, b: f64) -> i32 {
+    let (min, max) = if a <= b { (a, b) } else { (b, a) };
+    normal_random(min as i64, max as i64) as i32
+}
+
+/// Båda står i pvp-zon. Motsvarar `Combat::isInPvpZone`.
+pub fn is_in_pvp_zone(view: &dyn CombatView, attacker: &Combatant, target: &Combatant) -> bool {
+    view.tile(attacker.position).pvp_zone && view.tile(target.position).pvp_zone
+}
+
+/// Spelarna får inte skada varandra. Motsvarar `Combat::isProtected`.
+pub fn is_protected(config: &CombatConfig, view: &dyn CombatView, attacker: &Combatant, target: &Combatant) -> bool {
+    if target.level < config.protection_level || attacker.level < config.protection_level {
+        return true;
+    }
+    if !attacker.allows_pvp || !target.allows_pvp {
+        return true;
+    }
+    attacker.skull == Skull::Black && view.skull_client(target.id, attacker.id) == Skull::None
+}
+
+/// Kan `attacker` skada `target` alls. Motsvarar `Combat::canDoCombat(Creature*, Creature*)`.
+pub fn can_do_combat(
+    config: &CombatConfig,
+    view: &dyn CombatView,
+    attacker: Option<&Combatant>,
+    target: &Combatant,
+) -> ReturnValue {
+    let Some(attacker) = attacker else {
+        return ReturnValue::NoError;
+    };
+    let master_player = match attacker.master {
+        Some((master, CreatureType::Player)) => view.combatant(master),
+        _ => None,
+    };
+
+    if target.is_player() {
+        if target.flags.cannot_be_attacked {
+            return ReturnValue::YouMayNotAttackThisPlayer;
+        }
+        let target_tile = view.tile(target.position);
+        if attacker.is_player() {
+            if attacker.flags.cannot_attack_player || is_protected(config, view, attacker, target) {
+                return ReturnValue::YouMayNotAttackThisPlayer;
+            }
+            if target_tile.no_pvp_zone {
+                return ReturnValue::ActionNotPermittedInANoPvpZone;
+            }
+            let attacker_tile = view.tile(attacker.position);
+            if attacker_tile.no_pvp_zone && !target_tile.no_pvp_zone && !target_tile.protection_zone {
+                return ReturnValue::ActionNotPermittedInANoPvpZone;
+            }
+        }
+        if let Some(master) = &master_player {
+            if master.flags.cannot_attack_player {
+                return ReturnValue::YouMayNotAttackThisPlayer;
+            }
+            if target_tile.no_pvp_zone {
+                return ReturnValue::ActionNotPermittedInANoPvpZone;
+            }
+            if is_protected(config, view, master, target) {
+                return ReturnValue::YouMayNotAttackThisPlayer;
+            }
+        }
+    } else if target.is_monster() {
+        if attacker.is_player() {
+            if attacker.flags.cannot_attack_monster {
+                return ReturnValue::YouMayNotAttackThisCreature;
+            }
+            if target.is_player_summon() && view.tile(target.position).no_pvp_zone {
+                return ReturnValue::ActionNotPermittedInANoPvpZone;
+            }
+        } else if attacker.is_monster() && !target.is_player_summon() && !attacker.is_player_summon() {
+            return ReturnValue::YouMayNotAttackThisCreature;
+        }
+    }
+
+    if config.world_type == WorldType::NoPvp
+        && (attacker.is_player() || attacker.is_player_summon())
+        && !is_in_pvp_zone(view, attacker, target)
+    {
+        if target.is_player() {
+            return ReturnValue::YouMayNotAttackThisPlayer;
+        }
+        if target.is_player_summon() {
+            return ReturnValue::YouMayNotAttackThisCreature;
+        }
+    }
+    ReturnValue::NoError
+}
+
+/// Kan spelaren välja varelsen som mål: skyddszoner, no-pvp-zoner och
+/// secure mode. Motsvarar `Combat::canTargetCreature`.
+pub fn can_target_creature(
+    config: &CombatConfig,
+    view: &dyn CombatView,
+    attacker: &Combatant,
+    target: &Combatant,
+) -> ReturnValue {
+    if attacker.id == target.id {
+        return ReturnValue::YouMayNotAttackThisPlayer;
+    }
+
+    if !attacker.flags.ignore_protection_zone {
+        let attacker_tile = view.tile(attacker.position);
+        let target_tile = view.tile(target.position);
+        if attacker_tile.protection_zone || target_tile.protection_zone {
+            return ReturnValue::ActionNotPermittedInProtectionZone;
+        }
+        if target.player_owner().is_some() {
+            if attacker_tile.no_pvp_zone {
+                return ReturnValue::ActionNotPermittedInANoPvpZone;
+            }
+            if target_tile.no_pvp_zone {
+                return ReturnValue::YouMayNotAttackAPersonInProtectionZone;
+            }
+        }
+    }
+
+    if attacker.flags.cannot_use_combat || !target.attackable {
+        return if target.is_player() {
+            ReturnValue::YouMayNotAttackThisPlayer
+        } else {
+            ReturnValue::YouMayNotAttackThisCreature
+        };
+    }
+
+    if target.is_player() {
+        if is_protected(config, view, attacker, target) {
+            return ReturnValue::YouMayNotAttackThisPlayer;
+        }
+        if attacker.secure_mode
+            && !is_in_pvp_zone(view, attacker, target)
+            && view.skull_client(attacker.id, target.id) == Skull::None
+        {
+            return ReturnValue::TurnSecureModeToAttackUnmarkedPlayers;
+        }
+    }
+    can_do_combat(config, view, Some(attacker), target)
+}
+
+/// Kan striden träffa rutan. Motsvarar `Combat::canDoCombat(Creature*, Tile*, bool)`.
+pub fn can_do_combat_tile(view: &dyn CombatView, caster: Option<&Combatant>, pos: Position, aggressive: bool) -> ReturnValue {
+    let tile = view.tile(pos);
+    if tile.blocks_combat {
+        return ReturnValue::NotEnoughRoom;
+    }
+    if let Some(caster) = caster {
+        if caster.position.z < pos.z {
+            return ReturnValue::FirstGoDownstairs;
+        }
+        if caster.position.z > pos.z {
+            return ReturnValue::FirstGoUpstairs;
+        }
+        if caster.is_player() && caster.flags.ignore_protection_zone {
+            return ReturnValue::NoError;
+        }
+    }
+    if aggressive && tile.protection_zone {
+        return ReturnValue::ActionNotPermittedInProtectionZone;
+    }
+    ReturnValue::NoError
+}
+
+/// Dra av försvar, rustning och motstånd från en skada (positiv här).
+/// Motsvarar `Creature::blockHit` med `Monster::blockHit`s element.
+pub fn block_hit(
+    target: &Combatant,
+    combat_type: CombatType,
+    damage: &mut i32,
+    check_defense: bool,
+    mut check_armor: bool,
+    ignore_resistances: bool,
+) -> BlockType {
+    let mut block_type = BlockType::None;
+    if target.is_immune(combat_type) {
+        *damage = 0;
+        block_type = BlockType::Immunity;
+    } else if check_defense || check_armor {
+        if check_defense && target.can_block {
+            let defense = target.defense;
+            *damage -= uniform_random((defense / 2) as i64, defense as i64) as i32;
+            if *damage <= 0 {
+                *damage = 0;
+                block_type = BlockType::Defense;
+                check_armor = false;
+            }
+        }
+        if check_armor {
+            let armor = target.armor;
+            if armor > 3 {
+                *damage -= uniform_random((armor / 2) as i64, (armor - (armor % 2 + 1)) as i64) as i32;
+            } else if armor > 0 {
+                *damage -= 1;
+            }
+            if *damage <= 0 {
+                *damage = 0;
+                block_type = BlockType::Armor;
+            }
+        }
+    }
+
+    if !ignore_resistances && *damage != 0 {
+        if let Some(&element_mod) = target.element_mods.get(&combat_type) {
+            if element_mod != 0 {
+                *damage = (*damage as f64 * ((100 - element_mod) as f64 / 100.0)).round() as i32;
+                if *damage <= 0 {
+                    *damage = 0;
+                    block_type = BlockType::Armor;
+                }
+            }
+        }
+    }
+    block_type
+}
+
+/// Blockera båda delarna av skadan. Sant om allt blockerades. Motsvarar
+/// `Game::combatBlockHit`.
+pub fn combat_block_hit(
+    damage: &mut CombatDamage,
+    target: &Combatant,
+    check_defense: bool,
+    check_armor: bool,
+    field: bool,
+    ignore_resistances: bool,
+) -> (bool, Vec<CombatEvent>) {
+    let mut events = Vec::new();
+    if damage.primary.combat_type == CombatType::NONE && damage.secondary.combat_type == CombatType::NONE {
+        return (true, events);
+    }
+    if target.is_player() && target.in_ghost_mode {
+        return (true, events);
+    }
+    if damage.primary.value > 0 {
+        return (false, events);
+    }
+
+    // fält blockeras inte av försvar
+    let check_defense = check_defense && !field;
+    if check_defense && target.can_block {
+        events.push(CombatEvent::UseBlock(target.id));
+    }
+    let block = |value: &mut CombatValue, check_defense, check_armor, events: &mut Vec<CombatEvent>| {
+        if value.combat_type == CombatType::NONE {
+            return BlockType::None;
+        }
+        let mut amount = -value.value;
+        let block_type = block_hit(target, value.combat_type, &mut amount, check_defense, check_armor, ignore_resistances);
+        value.value = -amount;
+        if let Some(effect) = block_effect(block_type, value.combat_type) {
+            events.push(CombatEvent::MagicEffect { pos: target.position, effect });
+        }
+        block_type
+    };
+    let primary = block(&mut damage.primary, check_defense, check_armor, &mut events);
+    let secondary = block(&mut damage.secondary, false, false, &mut events);
+    damage.block_type = primary;
+    (primary != BlockType::None && secondary != BlockType::None, events)
+}
+
+/// Effekten när en skada blockeras, `sendBlockEffect` i `Game::combatBlockHit`
+fn block_effect(block_type: BlockType, combat_type: CombatType) -> Option<MagicEffect> {
+    match block_type {
+        BlockType::None => None,
+        BlockType::Defense => Some(MagicEffect::Poff),
+        BlockType::Armor => Some(MagicEffect::BlockHit),
+        BlockType::Immunity => match combat_type {
+            CombatType::UNDEFINED => None,
+            CombatType::ENERGY | CombatType::FIRE | CombatType::PHYSICAL | CombatType::ICE | CombatType::DEATH => {
+                Some(MagicEffect::BlockHit)
+            }
+            CombatType::EARTH => Some(MagicEffect::GreenRings),
+            CombatType::HOLY => Some(MagicEffect::HolyDamage),
+            _ => Some(MagicEffect::Poff),
+        },
+    }
+}
+
+/// Anfallaren har anfallit målet. `Some(pvp)` om den som äger anfallaren
+/// ska i strid, med `pvp` när målet är en spelare utanför partyt och
+/// gillet; då avgör `skull::on_attacked_player` pz-låset och skallen.
+/// Motsvarar början av `Player::onAttackedCreature`.
+pub fn on_attacked_creature(
+    view: &dyn CombatView,
+    attacker: &Combatant,
+    target: &Combatant,
+) -> Option<bool> {
+    let player = attacker.player_owner()?;
+    if view.tile(target.position).pvp_zone {
+        return None;
+    }
+    if target.id == player {
+        return Some(false);
+    }
+    let flags = if player == attacker.id { attacker.flags } else { view.combatant(player)?.flags };
+    if flags.not_gain_in_fight {
+        return None;
+    }
+    Some(target.is_player() && !view.is_partner_or_guild_mate(player, target.id))
+}
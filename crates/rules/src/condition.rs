@@ -0,0 +1,1297 @@
+//! Conditionernas beteende, motsvarar condition.cpp i TFS. Datat bärs av
+//! varelsen i `entities::condition`; här finns parametrarna scripten sätter,
+//! skadesekvenserna, vad som händer när en condition läggs till, tickar och
+//! tar slut, samt formatet i `players.conditions`.
+//!
+//! Det som kräver kartan eller andra varelser, som att skicka hastighet och
+//! outfit till klienterna eller att låta skadan gå genom striden, kommer
+//! tillbaka som `ConditionEvent`.
+
+use common::{uniform_random, PropStream, PropWriteStream};
+use entities::condition::{DamageCondition, IntervalInfo, LightCondition};
+use entities::creature::{LightInfo, Outfit, EVENT_CREATURE_THINK_INTERVAL};
+use entities::{Condition, ConditionData, ConditionId, ConditionType, Creature, CreatureType, Monster, Npc, Player, Skill, Stat};
+
+use crate::combat::{condition_to_damage_type, CombatDamage, CombatOrigin};
+
+/// Motsvarar `ConditionParam_t`, `CONDITION_PARAM_*` i scripten
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConditionParam {
+    Owner = 1,
+    Ticks = 2,
+    HealthGain = 4,
+    HealthTicks = 5,
+    ManaGain = 6,
+    ManaTicks = 7,
+    Delayed = 8,
+    Speed = 9,
+    LightLevel = 10,
+    LightColor = 11,
+    SoulGain = 12,
+    SoulTicks = 13,
+    MinValue = 14,
+    MaxValue = 15,
+    StartValue = 16,
+    TickInterval = 17,
+    ForceUpdate = 18,
+    SkillMelee = 19,
+    SkillFist = 20,
+    SkillClub = 21,
+    SkillSword = 22,
+    SkillAxe = 23,
+    SkillDistance = 24,
+    SkillShield = 25,
+    SkillFishing = 26,
+    StatMaxHitPoints = 27,
+    StatMaxManaPoints = 28,
+    StatMagicPoints = 30,
+    StatMaxHitPointsPercent = 31,
+    StatMaxManaPointsPercent = 32,
+    StatMagicPointsPercent = 34,
+    PeriodicDamage = 35,
+    SkillMeleePercent = 36,
+    SkillFistPercent = 37,
+    SkillClubPercent = 38,
+    SkillSwordPercent = 39,
+    SkillAxePercent = 40,
+    SkillDistancePercent = 41,
+    SkillShieldPercent = 42,
+    SkillFishingPercent = 43,
+    BuffSpell = 44,
+    SubId = 45,
+    Field = 46,
+    DisableDefense = 47,
+}
+
+impl ConditionParam {
+    pub const ALL: [ConditionParam; 44] = [
+        ConditionParam::Owner,
+        ConditionParam::Ticks,
+        ConditionParam::HealthGain,
+        ConditionParam::HealthTicks,
+        ConditionParam::ManaGain,
+        ConditionParam::ManaTicks,
+        ConditionParam::Delayed,
+        ConditionParam::Speed,
+        ConditionParam::LightLevel,
+        ConditionParam::LightColor,
+        ConditionParam::SoulGain,
+        ConditionParam::SoulTicks,
+        ConditionParam::MinValue,
+        ConditionParam::MaxValue,
+        ConditionParam::StartValue,
+        ConditionParam::TickInterval,
+        ConditionParam::ForceUpdate,
+        ConditionParam::SkillMelee,
+        ConditionParam::SkillFist,
+        ConditionParam::SkillClub,
+        ConditionParam::SkillSword,
+        ConditionParam::SkillAxe,
+        ConditionParam::SkillDistance,
+        ConditionParam::SkillShield,
+        ConditionParam::SkillFishing,
+        ConditionParam::StatMaxHitPoints,
+        ConditionParam::StatMaxManaPoints,
+        ConditionParam::StatMagicPoints,
+        ConditionParam::StatMaxHitPointsPercent,
+        ConditionParam::StatMaxManaPointsPercent,
+        ConditionParam::StatMagicPointsPercent,
+        ConditionParam::PeriodicDamage,
+        ConditionParam::SkillMeleePercent,
+        ConditionParam::SkillFistPercent,
+        ConditionParam::SkillClubPercent,
+        ConditionParam::SkillSwordPercent,
+        ConditionParam::SkillAxePercent,
+        ConditionParam::SkillDistancePercent,
+        ConditionParam::SkillShieldPercent,
+        ConditionParam::SkillFishingPercent,
+        ConditionParam::BuffSpell,
+        ConditionParam::SubId,
+        ConditionParam::Field,
+        ConditionParam::DisableDefense,
+    ];
+
+    pub fn from_u8(value: u8) -> Option<Self> {
+        Self::ALL.iter().copied().find(|param| *param as u8 == value)
+    }
+}
+
+/// Attributen i `players.conditions`, motsvarar `ConditionAttr_t`
+mod attr {
+    pub const TYPE: u8 = 1;
+    pub const ID: u8 = 2;
+    pub const TICKS: u8 = 3;
+    pub const HEALTHTICKS: u8 = 4;
+    pub const HEALTHGAIN: u8 = 5;
+    pub const MANATICKS: u8 = 6;
+    pub const MANAGAIN: u8 = 7;
+    pub const DELAYED: u8 = 8;
+    pub const OWNER: u8 = 9;
+    pub const INTERVALDATA: u8 = 10;
+    pub const SPEEDDELTA: u8 = 11;
+    pub const FORMULA_MINA: u8 = 12;
+    pub const FORMULA_MINB: u8 = 13;
+    pub const FORMULA_MAXA: u8 = 14;
+    pub const FORMULA_MAXB: u8 = 15;
+    pub const LIGHTCOLOR: u8 = 16;
+    pub const LIGHTLEVEL: u8 = 17;
+    pub const LIGHTTICKS: u8 = 18;
+    pub const LIGHTINTERVAL: u8 = 19;
+    pub const SOULTICKS: u8 = 20;
+    pub const SOULGAIN: u8 = 21;
+    pub const SKILLS: u8 = 22;
+    pub const STATS: u8 = 23;
+    pub const OUTFIT: u8 = 24;
+    pub const PERIODDAMAGE: u8 = 25;
+    pub const ISBUFF: u8 = 26;
+    pub const SUBID: u8 = 27;
+    pub const END: u8 = 254;
+}
+
+/// Det spelet ska göra eller skicka när en condition startar, tickar
+/// eller slutar
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConditionEvent {
+    /// Skada från gift, eld och de andra. Ska som i `ConditionDamage::doDamage`
+    /// stoppas av immunitet och `can_do_combat` (med poff) och sedan gå
+    /// genom `combat_block_hit` utan försvar och rustning.
+    Damage { target: u32, owner: Option<u32>, damage: CombatDamage, field: bool },
+    /// `creature->onAttacked()`, håller spelare i strid
+    Attacked(u32),
+    SpeedChanged(u32),
+    OutfitChanged(u32),
+    LightChanged(u32),
+    /// Osynlig eller synlig igen, motsvarar `internalCreatureChangeVisible`
+    VisibilityChanged { target: u32, visible: bool },
+    /// Hälsa och mana som regenererades. Med `buff` får spelaren och de
+    /// runt omkring veta hur mycket.
+    Regenerated { target: u32, health: i32, mana: i32, buff: bool },
+    /// Själpoäng, som spelet ger upp till yrkets max
+    Soul { player: u32, gain: u32 },
+    StatsChanged(u32),
+    SkillsChanged(u32),
+    SpellCooldown { player: u32, spell_id: u32, ticks: i32 },
+    SpellGroupCooldown { player: u32, group: u32, ticks: i32 },
+    /// Spelarens ikoner ska skickas om
+    IconsChanged(u32),
+    /// Skallen ändrades, motsvarar `Game::updateCreatureSkull`
+    SkullChanged(u32),
+}
+
+/// Varelsen conditionen sitter på. Spelare och monster har lite mer än
+/// `Creature`, som i TFS `getPlayer()` och `setNormalCreatureLight`.
+pub trait ConditionTarget {
+    fn creature(&self) -> &Creature;
+    fn creature_mut(&mut self) -> &mut Creature;
+
+    fn player_mut(&mut self) -> Option<&mut Player> {
+        None
+    }
+
+    /// Ljuset utan conditions
+    fn normal_light(&self) -> LightInfo {
+        LightInfo::default()
+    }
+}
+
+impl ConditionTarget for Creature {
+    fn creature(&self) -> &Creature {
+        self
+    }
+
+    fn creature_mut(&mut self) -> &mut Creature {
+        self
+    }
+}
+
+impl ConditionTarget for Player {
+    fn creature(&self) -> &Creature {
+        &self.creature
+    }
+
+    fn creature_mut(&mut self) -> &mut Creature {
+        &mut self.creature
+    }
+
+    fn player_mut(&mut self) -> Option<&mut Player> {
+        Some(self)
+    }
+}
+
+impl ConditionTarget for Monster {
+    fn creature(&self) -> &Creature {
+        &self.creature
+    }
+
+    fn creature_mut(&mut self) -> &mut Creature {
+        &mut self.creature
+    }
+
+    fn normal_light(&self) -> LightInfo {
+        self.mtype.light
+    }
+}
+
+impl ConditionTarget for Npc {
+    fn creature(&self) -> &Creature {
+        &self.creature
+    }
+
+    fn creature_mut(&mut self) -> &mut Creature {
+        &mut self.creature
+    }
+}
+
+/// Motsvarar `Condition::createCondition`; `None` för typer som inte finns.
+/// `param` är hastighetsändringen för haste och paralyze.
+pub fn create_condition(
+    id: ConditionId,
+    condition_type: ConditionType,
+    ticks: i32,
+    param: i32,
+    buff: bool,
+    sub_id: u32,
+) -> Option<Condition> {
+    let data = ConditionData::for_type(condition_type)?;
+    let mut condition = Condition::new(id, condition_type, ticks).with_sub_id(sub_id);
+    condition.is_buff = buff;
+    condition.data = data;
+    if let ConditionData::Speed(speed) = &mut condition.data {
+        speed.speed_delta = param;
+    }
+    Some(condition)
+}
+
+/// Motsvarar `Condition::setParam` i basklassen och underklasserna.
+/// Returnerar false för parametrar som inte hör till sorten.
+pub fn set_param(condition: &mut Condition, param: ConditionParam, value: i32) -> bool {
+    use ConditionParam as P;
+    match param {
+        P::Ticks => {
+            condition.ticks = value;
+            return true;
+        }
+        P::BuffSpell => {
+            condition.is_buff = value != 0;
+            return true;
+        }
+        P::SubId => {
+            condition.sub_id = value as u32;
+            return true;
+        }
+        _ => {}
+    }
+
+    match &mut condition.data {
+        ConditionData::Damage(damage) => match param {
+            P::Owner => damage.owner = value as u32,
+            P::ForceUpdate => damage.force_update = value != 0,
+            P::Delayed => damage.delayed = value != 0,
+            P::MaxValue => damage.max_damage = value.abs(),
+            P::MinValue => damage.min_damage = value.abs(),
+            P::StartValue => damage.start_damage = value.abs(),
+            P::TickInterval => damage.tick_interval = value.abs(),
+            P::PeriodicDamage => damage.period_damage = value,
+            P::Field => damage.field = value != 0,
+            _ => return false,
+        },
+        ConditionData::Speed(speed) => {
+            if param != P::Speed {
+                return false;
+            }
+            speed.speed_delta = value;
+            condition.condition_type = if value > 0 { ConditionType::HASTE } else { ConditionType::PARALYZE };
+        }
+        ConditionData::Light(light) => match param {
+            P::LightLevel => light.light.level = value as u8,
+            P::LightColor => light.light.color = value as u8,
+            _ => return false,
+        },
+        ConditionData::Regeneration(regen) => match param {
+            P::HealthGain => regen.health_gain = value as u32,
+            P::HealthTicks => regen.health_ticks = value as u32,
+            P::ManaGain => regen.mana_gain = value as u32,
+            P::ManaTicks => regen.mana_ticks = value as u32,
+            _ => return false,
+        },
+        ConditionData::Soul(soul) => match param {
+            P::SoulGain => soul.soul_gain = value as u32,
+            P::SoulTicks => soul.soul_ticks = value as u32,
+            _ => return false,
+        },
+        ConditionData::Attributes(attributes) => {
+            let skill = |skill: Skill| skill as usize;
+            let stat = |stat: Stat| stat as usize;
+            match param {
+                P::SkillMelee => {
+                    for s in [Skill::Club, Skill::Axe, Skill::Sword] {
+                        attributes.skills[skill(s)] = value;
+                    }
+                }
+                P::SkillMeleePercent => {
+                    for s in [Skill::Club, Skill::Axe, Skill::Sword] {
+                        attributes.skills_percent[skill(s)] = value;
+                    }
+                }
+                P::SkillFist => attributes.skills[skill(Skill::Fist)] = value,
+                P::SkillFistPercent => attributes.skills_percent[skill(Skill::Fist)] = value,
+                P::SkillClub => attributes.skills[skill(Skill::Club)] = value,
+                P::SkillClubPercent => attributes.skills_percent[skill(Skill::Club)] = value,
+                P::SkillSword => attributes.skills[skill(Skill::Sword)] = value,
+                P::SkillSwordPercent => attributes.skills_percent[skill(Skill::Sword)] = value,
+                P::SkillAxe => attributes.skills[skill(Skill::Axe)] = value,
+                P::SkillAxePercent => attributes.skills_percent[skill(Skill::Axe)] = value,
+                P::SkillDistance => attributes.skills[skill(Skill::Distance)] = value,
+                P::SkillDistancePercent => attributes.skills_percent[skill(Skill::Distance)] = value,
+                P::SkillShield => attributes.skills[skill(Skill::Shield)] = value,
+                P::SkillShieldPercent => attributes.skills_percent[skill(Skill::Shield)] = value,
+                P::SkillFishing => attributes.skills[skill(Skill::Fishing)] = value,
+                P::SkillFishingPercent => attributes.skills_percent[skill(Skill::Fishing)] = value,
+                P::StatMaxHitPoints => attributes.stats[stat(Stat::MaxHitPoints)] = value,
+                P::StatMaxManaPoints => attributes.stats[stat(Stat::MaxManaPoints)] = value,
+                P::StatMagicPoints => attributes.stats[stat(Stat::MagicPoints)] = value,
+                P::StatMaxHitPointsPercent => attributes.stats_percent[stat(Stat::MaxHitPoints)] = value.max(0),
+                P::StatMaxManaPointsPercent => attributes.stats_percent[stat(Stat::MaxManaPoints)] = value.max(0),
+                P::StatMagicPointsPercent => attributes.stats_percent[stat(Stat::MagicPoints)] = value.max(0),
+                P::DisableDefense => attributes.disable_defense = value != 0,
+                _ => return false,
+            }
+        }
+        ConditionData::Generic | ConditionData::Outfit(_) => return false,
+    }
+    true
+}
+
+/// Haste och paralyze som formel av grundhastigheten, motsvarar
+/// `ConditionSpeed::setFormulaVars`
+pub fn set_speed_formula(condition: &mut Condition, mina: f32, minb: f32, maxa: f32, maxb: f32) -> bool {
+    let ConditionData::Speed(speed) = &mut condition.data else {
+        return false;
+    };
+    speed.mina = mina;
+    speed.minb = minb;
+    speed.maxa = maxa;
+    speed.maxb = maxb;
+    true
+}
+
+/// Motsvarar `ConditionOutfit::setOutfit`
+pub fn set_outfit(condition: &mut Condition, outfit: Outfit) -> bool {
+    let ConditionData::Outfit(current) = &mut condition.data else {
+        return false;
+    };
+    *current = outfit;
+    true
+}
+
+/// `rounds` slag med `time` ms mellan; -1 rundor ger skada varje intervall
+/// tills conditionen tas bort. Motsvarar `ConditionDamage::addDamage`.
+pub fn add_damage(condition: &mut Condition, rounds: i32, time: i32, value: i32) -> bool {
+    let ConditionData::Damage(damage) = &mut condition.data else {
+        return false;
+    };
+    let time = time.max(EVENT_CREATURE_THINK_INTERVAL as i32);
+    if rounds == -1 {
+        damage.period_damage = value;
+        damage.tick_interval = time;
+        condition.ticks = -1;
+        return true;
+    }
+    if damage.period_damage > 0 {
+        return true;
+    }
+    for _ in 0..rounds {
+        damage.damage_list.push_back(IntervalInfo { time_left: time, value, interval: time });
+        if condition.ticks != -1 {
+            condition.ticks += time;
+        }
+    }
+    true
+}
+
+/// Skadorna i en sekvens som börjar på `start` och sjunker med ett i taget
+/// tills summan blir ungefär `amount`. Motsvarar `ConditionDamage::generateDamageList`.
+pub fn generate_damage_list(amount: i32, start: i32) -> Vec<i32> {
+    let amount = amount.abs();
+    let mut list = Vec::new();
+    let mut sum = 0;
+    for i in (1..=start).rev() {
+        let n = start + 1 - i;
+        let med = (n * amount) / start;
+        loop {
+            sum += i;
+            list.push(i);
+            let x1 = (1.0 - (sum as f32 + i as f32) as f64 / med as f64).abs();
+            let x2 = (1.0 - sum as f32 as f64 / med as f64).abs();
+            if x1 >= x2 {
+                break;
+            }
+        }
+    }
+    list
+}
+
+/// Skapa skadesekvensen ur min- och maxvärdet om den inte finns.
+/// Motsvarar `ConditionDamage::init`.
+fn init_damage(ticks: &mut i32, damage: &mut DamageCondition) -> bool {
+    if damage.period_damage != 0 {
+        return true;
+    }
+    if damage.damage_list.is_empty() {
+        *ticks = 0;
+        let amount = uniform_random(damage.min_damage as i64, damage.max_damage as i64) as i32;
+        if amount != 0 {
+            if damage.start_damage > damage.max_damage {
+                damage.start_damage = damage.max_damage;
+            } else if damage.start_damage == 0 {
+                damage.start_damage = 1.max((amount as f64 / 20.0).ceil() as i32);
+            }
+            let time = damage.tick_interval.max(EVENT_CREATURE_THINK_INTERVAL as i32);
+            for value in generate_damage_list(amount, damage.start_damage) {
+                damage.damage_list.push_back(IntervalInfo { time_left: time, value: -value, interval: time });
+                *ticks += time;
+            }
+        }
+    }
+    !damage.damage_list.is_empty()
+}
+
+/// Motsvarar `ConditionDamage::getNextDamage`
+fn next_damage(ticks: i32, damage: &mut DamageCondition) -> Option<i32> {
+    if damage.period_damage != 0 {
+        return Some(damage.period_damage);
+    }
+    let value = damage.damage_list.front()?.value;
+    if ticks != -1 {
+        damage.damage_list.pop_front();
+    }
+    Some(value)
+}
+
+/// Motsvarar `ConditionDamage::getTotalDamage`
+fn total_damage(damage: &DamageCondition) -> i32 {
+    if damage.damage_list.is_empty() {
+        (damage.min_damage + (damage.max_damage - damage.min_damage) / 2).abs()
+    } else {
+        damage.damage_list.iter().map(|info| info.value).sum::<i32>().abs()
+    }
+}
+
+/// Motsvarar `ConditionDamage::doDamage` fram till striden
+fn do_damage(
+    target: u32,
+    condition_type: ConditionType,
+    damage: &DamageCondition,
+    value: i32,
+    events: &mut Vec<ConditionEvent>,
+) {
+    let owner = (damage.owner != 0).then_some(damage.owner);
+    let mut combat_damage = CombatDamage::new(condition_to_damage_type(condition_type), value, CombatOrigin::Condition);
+    let players = CreatureType::of_id(target) == Some(CreatureType::Player)
+        && owner.and_then(CreatureType::of_id) == Some(CreatureType::Player);
+    if damage.field && players {
+        combat_damage.primary.value = (combat_damage.primary.value as f64 / 2.0).round() as i32;
+    }
+    events.push(ConditionEvent::Damage { target, owner, damage: combat_damage, field: damage.field });
+}
+
+/// Hastighetsändringen ur formeln, räknad på grundhastigheten över 40.
+/// Motsvarar `ConditionSpeed::getFormulaValues`.
+fn speed_delta(base_speed: u32, speed: &entities::condition::SpeedCondition) -> i32 {
+    let difference = base_speed as f32 - 40.0;
+    let min = (speed.mina * difference + speed.minb) as i64;
+    let max = (speed.maxa * difference + speed.maxb) as i64;
+    uniform_random(min, max) as i32
+}
+
+fn light_change_interval(ticks: i32, light: LightInfo) -> u32 {
+    if light.level == 0 {
+        return 0;
+    }
+    (ticks / light.level as i32).max(0) as u32
+}
+
+/// Motsvarar `ConditionAttributes::updatePercentSkills/Stats` och
+/// `updateSkills/Stats`; `sign` -1 tar bort tilläggen igen
+fn apply_attributes(
+    player: &mut Player,
+    attributes: &mut entities::condition::AttributesCondition,
+    sign: i32,
+    events: &mut Vec<ConditionEvent>,
+) {
+    if sign > 0 {
+        for skill in Skill::ALL {
+            let percent = attributes.skills_percent[skill as usize];
+            if percent != 0 {
+                let base = player.skill(skill).level as f32;
+                attributes.skills[skill as usize] = (base * ((percent - 100) as f32 / 100.0)) as i32;
+            }
+        }
+        for stat in Stat::ALL {
+            let percent = attributes.stats_percent[stat as usize];
+            if percent == 0 {
+                continue;
+            }
+            let base = match stat {
+                Stat::MaxHitPoints => player.creature.health_max as f32,
+                Stat::MaxManaPoints => player.creature.mana_max as f32,
+                Stat::MagicPoints => player.mag_level as f32,
+                Stat::SoulPoints => continue,
+            };
+            attributes.stats[stat as usize] = (base * ((percent - 100) as f32 / 100.0)) as i32;
+        }
+    }
+
+    let id = player.creature.id;
+    let mut skills_changed = false;
+    for skill in Skill::ALL {
+        let value = attributes.skills[skill as usize];
+        if value != 0 {
+            player.set_var_skill(skill, value * sign);
+            skills_changed = true;
+        }
+    }
+    if skills_changed {
+        events.push(ConditionEvent::SkillsChanged(id));
+    }
+    let mut stats_changed = false;
+    for stat in Stat::ALL {
+        let value = attributes.stats[stat as usize];
+        if value != 0 {
+            player.set_var_stats(stat, value * sign);
+            stats_changed = true;
+        }
+    }
+    if stats_changed {
+        events.push(ConditionEvent::StatsChanged(id));
+    }
+}
+
+/// Motsvarar `startCondition` i underklasserna
+fn start_condition(target: &mut impl ConditionTarget, condition: &mut Condition, events: &mut Vec<ConditionEvent>) -> bool {
+    let id = target.creature().id;
+    let condition_type = condition.condition_type;
+    match &mut condition.data {
+        ConditionData::Damage(damage) => {
+            events.push(ConditionEvent::Attacked(id));
+            if !init_damage(&mut condition.ticks, damage) {
+                return false;
+            }
+            if !damage.delayed {
+                if let Some(value) = next_damage(condition.ticks, damage) {
+                    do_damage(id, condition_type, damage, value, events);
+                }
+            }
+        }
+        ConditionData::Speed(speed) => {
+            if speed.speed_delta == 0 {
+                speed.speed_delta = speed_delta(target.creature().base_speed, speed);
+            }
+            target.creature_mut().change_speed(speed.speed_delta);
+            events.push(ConditionEvent::SpeedChanged(id));
+        }
+        ConditionData::Outfit(outfit) => {
+            if outfit.look_type == 0 && outfit.look_type_ex == 0 {
+                return false;
+            }
+            target.creature_mut().outfit = *outfit;
+            events.push(ConditionEvent::OutfitChanged(id));
+        }
+        ConditionData::Light(light) => {
+            light.internal_light_ticks = 0;
+            light.light_change_interval = light_change_interval(condition.ticks, light.light);
+            target.creature_mut().internal_light = light.light;
+            events.push(ConditionEvent::LightChanged(id));
+        }
+        ConditionData::Attributes(attributes) => {
+            if let Some(player) = target.player_mut() {
+                apply_attributes(player, attributes, 1, events);
+            }
+            if attributes.disable_defense {
+                target.creature_mut().can_use_defense = false;
+            }
+        }
+        ConditionData::Regeneration(_) | ConditionData::Soul(_) => {}
+        ConditionData::Generic => match condition_type {
+            ConditionType::INVISIBLE => events.push(ConditionEvent::VisibilityChanged { target: id, visible: false }),
+            ConditionType::SPELLCOOLDOWN | ConditionType::SPELLGROUPCOOLDOWN => {
+                spell_cooldown_event(target, condition, events);
+            }
+            _ => {}
+        },
+    }
+    true
+}
+
+fn spell_cooldown_event(target: &mut impl ConditionTarget, condition: &Condition, events: &mut Vec<ConditionEvent>) {
+    let Some(player) = target.player_mut() else {
+        return;
+    };
+    if condition.sub_id == 0 {
+        return;
+    }
+    let (player, ticks) = (player.creature.id, condition.ticks);
+    events.push(if condition.condition_type == ConditionType::SPELLCOOLDOWN {
+        ConditionEvent::SpellCooldown { player, spell_id: condition.sub_id, ticks }
+    } else {
+        ConditionEvent::SpellGroupCooldown { player, group: condition.sub_id, ticks }
+    });
+}
+
+/// Ett varv för conditionen. Returnerar false när den har gått ut.
+/// Motsvarar `executeCondition` i underklasserna.
+fn execute_condition(
+    target: &mut impl ConditionTarget,
+    condition: &mut Condition,
+    interval: u32,
+    in_protection_zone: bool,
+    events: &mut Vec<ConditionEvent>,
+) -> bool {
+    let id = target.creature().id;
+    let condition_type = condition.condition_type;
+    let mut interval = interval;
+    match &mut condition.data {
+        ConditionData::Damage(damage) => {
+            if damage.period_damage != 0 {
+                damage.period_damage_tick += interval as i32;
+                if damage.period_damage_tick >= damage.tick_interval {
+                    damage.period_damage_tick = 0;
+                    do_damage(id, condition_type, damage, damage.period_damage, events);
+                }
+            } else if let Some(info) = damage.damage_list.front_mut() {
+                let remove = condition.ticks != -1;
+                info.time_left -= interval as i32;
+                if info.time_left <= 0 {
+                    let value = info.value;
+                    if remove {
+                        damage.damage_list.pop_front();
+                    } else {
+                        info.time_left = info.interval;
+                    }
+                    do_damage(id, condition_type, damage, value, events);
+                }
+                if !remove {
+                    interval = 0;
+                }
+            }
+        }
+        ConditionData::Light(light) => {
+            light.internal_light_ticks += interval;
+            if light.internal_light_ticks >= light.light_change_interval {
+                light.internal_light_ticks = 0;
+                let creature = target.creature_mut();
+                if creature.internal_light.level > 0 {
+                    creature.internal_light.level -= 1;
+                    events.push(ConditionEvent::LightChanged(id));
+                }
+            }
+        }
+        ConditionData::Regeneration(regen) => {
+            regen.internal_health_ticks += interval;
+            regen.internal_mana_ticks += interval;
+            if !in_protection_zone {
+                let mut health = 0;
+                let mut mana = 0;
+                if regen.internal_health_ticks >= regen.health_ticks {
+                    regen.internal_health_ticks = 0;
+                    health = target.creature_mut().change_health(regen.health_gain as i32);
+                }
+                if regen.internal_mana_ticks >= regen.mana_ticks {
+                    regen.internal_mana_ticks = 0;
+                    if let Some(player) = target.player_mut() {
+                        mana = player.creature.change_mana(regen.mana_gain as i32);
+                    }
+                }
+                if health > 0 || mana > 0 {
+                    events.push(ConditionEvent::Regenerated { target: id, health, mana, buff: condition.is_buff });
+                }
+            }
+        }
+        ConditionData::Soul(soul) if !in_protection_zone && target.player_mut().is_some() => {
+            soul.internal_soul_ticks += interval;
+            if soul.internal_soul_ticks >= soul.soul_ticks {
+                soul.internal_soul_ticks = 0;
+                events.push(ConditionEvent::Soul { player: id, gain: soul.soul_gain });
+            }
+        }
+        _ => {}
+    }
+    condition.execute(interval)
+}
+
+/// Motsvarar `endCondition` i underklasserna
+fn end_condition(target: &mut impl ConditionTarget, condition: &mut Condition, events: &mut Vec<ConditionEvent>) {
+    let id = target.creature().id;
+    match &mut condition.data {
+        ConditionData::Speed(speed) => {
+            target.creature_mut().change_speed(-speed.speed_delta);
+            events.push(ConditionEvent::SpeedChanged(id));
+        }
+        ConditionData::Outfit(_) => {
+            let creature = target.creature_mut();
+            creature.outfit = creature.default_outfit;
+            events.push(ConditionEvent::OutfitChanged(id));
+        }
+        ConditionData::Light(_) => {
+            let light = target.normal_light();
+            target.creature_mut().internal_light = light;
+            events.push(ConditionEvent::LightChanged(id));
+        }
+        ConditionData::Attributes(attributes) => {
+            if let Some(player) = target.player_mut() {
+                apply_attributes(player, attributes, -1, events);
+            }
+            if attributes.disable_defense {
+                target.creature_mut().can_use_defense = true;
+            }
+        }
+        ConditionData::Generic if condition.condition_type == ConditionType::INVISIBLE => {
+            events.push(ConditionEvent::VisibilityChanged { target: id, visible: true });
+        }
+        _ => {}
+    }
+}
+
+/// En ny condition av samma typ, id och sub-id läggs ovanpå `prev`.
+/// Motsvarar `addCondition` i underklasserna.
+fn merge_condition(target: &mut impl ConditionTarget, prev: &mut Condition, new: Condition, events: &mut Vec<ConditionEvent>) {
+    let id = target.creature().id;
+    let condition_type = prev.condition_type;
+    let update = prev.should_update(&new);
+    match (&mut prev.data, new.data) {
+        (ConditionData::Damage(damage), ConditionData::Damage(added)) => {
+            let update = added.force_update
+                || (!(prev.ticks == -1 && new.ticks > 0) && total_damage(&added) > total_damage(damage));
+            if !update {
+                return;
+            }
+            prev.ticks = new.ticks;
+            damage.owner = added.owner;
+            damage.max_damage = added.max_damage;
+            damage.min_damage = added.min_damage;
+            damage.start_damage = added.start_damage;
+            damage.tick_interval = added.tick_interval;
+            damage.period_damage = added.period_damage;
+            let next_time_left = damage.damage_list.front().map_or(damage.tick_interval, |info| info.time_left);
+            damage.damage_list = added.damage_list;
+            if init_damage(&mut prev.ticks, damage) {
+                if let Some(info) = damage.damage_list.front_mut() {
+                    info.time_left = next_time_left;
+                }
+                if !damage.delayed {
+                    if let Some(value) = next_damage(prev.ticks, damage) {
+                        do_damage(id, condition_type, damage, value, events);
+                    }
+                }
+            }
+        }
+        (ConditionData::Speed(speed), ConditionData::Speed(added)) => {
+            if prev.condition_type != new.condition_type || (prev.ticks == -1 && new.ticks > 0) {
+                return;
+            }
+            prev.ticks = new.ticks;
+            let old_delta = speed.speed_delta;
+            *speed = added;
+            if speed.speed_delta == 0 {
+                speed.speed_delta = speed_delta(target.creature().base_speed, speed);
+            }
+            let change = speed.speed_delta - old_delta;
+            if change != 0 {
+                target.creature_mut().change_speed(change);
+                events.push(ConditionEvent::SpeedChanged(id));
+            }
+        }
+        (ConditionData::Outfit(outfit), ConditionData::Outfit(added)) if update => {
+            prev.ticks = new.ticks;
+            *outfit = added;
+            target.creature_mut().outfit = added;
+            events.push(ConditionEvent::OutfitChanged(id));
+        }
+        (ConditionData::Light(light), ConditionData::Light(added)) if update => {
+            prev.ticks = new.ticks;
+            *light = LightCondition {
+                light: added.light,
+                internal_light_ticks: 0,
+                light_change_interval: light_change_interval(new.ticks, added.light),
+            };
+            target.creature_mut().internal_light = added.light;
+            events.push(ConditionEvent::LightChanged(id));
+        }
+        (ConditionData::Regeneration(regen), ConditionData::Regeneration(added)) if update => {
+            prev.ticks = new.ticks;
+            regen.health_ticks = added.health_ticks;
+            regen.health_gain = added.health_gain;
+            regen.mana_ticks = added.mana_ticks;
+            regen.mana_gain = added.mana_gain;
+        }
+        (ConditionData::Soul(soul), ConditionData::Soul(added)) if update => {
+            prev.ticks = new.ticks;
+            soul.soul_gain = added.soul_gain;
+            soul.soul_ticks = added.soul_ticks;
+        }
+        (ConditionData::Attributes(attributes), ConditionData::Attributes(added)) if update => {
+            prev.ticks = new.ticks;
+            if let Some(player) = target.player_mut() {
+                apply_attributes(player, attributes, -1, events);
+            }
+            *attributes = added;
+            if let Some(player) = target.player_mut() {
+                apply_attributes(player, attributes, 1, events);
+            }
+            target.creature_mut().can_use_defense = !attributes.disable_defense;
+        }
+        (ConditionData::Generic, _) if update => {
+            prev.ticks = new.ticks;
+            if matches!(condition_type, ConditionType::SPELLCOOLDOWN | ConditionType::SPELLGROUPCOOLDOWN) && prev.ticks > 0 {
+                spell_cooldown_event(target, prev, events);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Haste och paralyze tar ut varandra, och spelarens ikoner ändras.
+/// Motsvarar `Creature::onAddCondition` och `Player::onAddCondition`.
+fn on_add_condition(target: &mut impl ConditionTarget, condition_type: ConditionType, events: &mut Vec<ConditionEvent>) {
+    let opposite = match condition_type {
+        ConditionType::PARALYZE => ConditionType::HASTE,
+        ConditionType::HASTE => ConditionType::PARALYZE,
+        _ => ConditionType::NONE,
+    };
+    if opposite != ConditionType::NONE && target.creature().has_condition(opposite) {
+        events.extend(remove_condition_type(target, opposite));
+    }
+    if let Some(player) = target.player_mut() {
+        events.push(ConditionEvent::IconsChanged(player.creature.id));
+    }
+}
+
+/// Motsvarar `Creature::onEndCondition` och `Player::onEndCondition`
+fn on_end_condition(target: &mut impl ConditionTarget, condition_type: ConditionType, events: &mut Vec<ConditionEvent>) {
+    if let Some(player) = target.player_mut() {
+        if player.on_end_condition(condition_type) {
+            events.push(ConditionEvent::SkullChanged(player.creature.id));
+        }
+        events.push(ConditionEvent::IconsChanged(player.creature.id));
+    }
+}
+
+/// Lägg till eller slå ihop med en befintlig av samma typ, id och sub-id.
+/// Returnerar false om conditionen inte kunde starta, t.ex. en outfit
+/// utan utseende. Motsvarar `Creature::addCondition`; att skjuta upp haste
+/// medan varelsen går sköts av spelet.
+pub fn add_condition(target: &mut impl ConditionTarget, mut condition: Condition) -> (bool, Vec<ConditionEvent>) {
+    let mut events = Vec::new();
+    let conditions = target.creature_mut().conditions_mut();
+    if let Some(index) = conditions.iter().position(|c| {
+        c.condition_type == condition.condition_type && c.id == condition.id && c.sub_id == condition.sub_id
+    }) {
+        let mut prev = conditions.remove(index);
+        merge_condition(target, &mut prev, condition, &mut events);
+        let conditions = target.creature_mut().conditions_mut();
+        conditions.insert(index.min(conditions.len()), prev);
+        return (true, events);
+    }
+
+    if !start_condition(target, &mut condition, &mut events) {
+        return (false, events);
+    }
+    let condition_type = condition.condition_type;
+    target.creature_mut().conditions_mut().push(condition);
+    on_add_condition(target, condition_type, &mut events);
+    (true, events)
+}
+
+/// Texten spelaren får när en attack gett en condition, motsvarar
+/// `Player::onAddCombatCondition`
+pub fn combat_condition_message(condition_type: ConditionType) -> Option<&'static str> {
+    Some(match condition_type {
+        ConditionType::POISON => "You are poisoned.",
+        ConditionType::DROWN => "You are drowning.",
+        ConditionType::PARALYZE => "You are paralyzed.",
+        ConditionType::DRUNK => "You are drunk.",
+        ConditionType::CURSED => "You are cursed.",
+        ConditionType::FREEZING => "You are freezing.",
+        ConditionType::DAZZLED => "You are dazzled.",
+        ConditionType::BLEEDING => "You are bleeding.",
+        _ => return None,
+    })
+}
+
+fn remove_conditions(target: &mut impl ConditionTarget, mut pred: impl FnMut(&Condition) -> bool) -> Vec<ConditionEvent> {
+    let mut events = Vec::new();
+    let conditions = std::mem::take(target.creature_mut().conditions_mut());
+    let (removed, kept): (Vec<_>, Vec<_>) = conditions.into_iter().partition(|c| pred(c));
+    *target.creature_mut().conditions_mut() = kept;
+    for mut condition in removed {
+        end_condition(target, &mut condition, &mut events);
+        on_end_condition(target, condition.condition_type, &mut events);
+    }
+    events
+}
+
+/// Ta bort en condition, motsvarar `Creature::removeCondition(Condition*)`
+/// efter `getCondition(type, id, subId)`
+pub fn remove_condition(
+    target: &mut impl ConditionTarget,
+    condition_type: ConditionType,
+    id: ConditionId,
+    sub_id: u32,
+) -> Option<Vec<ConditionEvent>> {
+    target.creature().get_condition(condition_type, id, sub_id)?;
+    let mut found = false;
+    Some(remove_conditions(target, |c| {
+        let matches = !found && c.condition_type == condition_type && c.id == id && c.sub_id == sub_id;
+        found |= matches;
+        matches
+    }))
+}
+
+/// De conditions som sparas med spelaren tas bort när den dör, som i
+/// `Player::death`
+pub fn remove_persistent_conditions(target: &mut impl ConditionTarget) -> Vec<ConditionEvent> {
+    remove_conditions(target, |c| c.is_persistent())
+}
+
+/// Alla conditions av typen, motsvarar `Creature::removeCondition(type)`
+pub fn remove_condition_type(target: &mut impl ConditionTarget, condition_type: ConditionType) -> Vec<ConditionEvent> {
+    remove_conditions(target, |c| c.condition_type == condition_type)
+}
+
+/// Ett varv för alla conditions; de som gått ut tas bort. Regeneration och
+/// själ står still i skyddszoner. Motsvarar `Creature::executeConditions`.
+pub fn execute_conditions(target: &mut impl ConditionTarget, interval: u32, in_protection_zone: bool) -> Vec<ConditionEvent> {
+    let mut events = Vec::new();
+    let conditions = std::mem::take(target.creature_mut().conditions_mut());
+    let mut kept = Vec::with_capacity(conditions.len());
+    let mut ended = Vec::new();
+    for mut condition in conditions {
+        if execute_condition(target, &mut condition, interval, in_protection_zone, &mut events) {
+            kept.push(condition);
+        } else {
+            ended.push(condition);
+        }
+    }
+    *target.creature_mut().conditions_mut() = kept;
+    for mut condition in ended {
+        end_condition(target, &mut condition, &mut events);
+        on_end_condition(target, condition.condition_type, &mut events);
+    }
+    events
+}
+
+// === players.conditions ===
+
+fn write_outfit(stream: &mut PropWriteStream, outfit: &Outfit) {
+    // `Outfit_t` som den ligger i minnet, med en byte utfyllnad på slutet
+    stream.write_u16(outfit.look_type);
+    stream.write_u16(outfit.look_type_ex);
+    stream.write_u16(outfit.look_mount);
+    for value in [
+        outfit.look_head,
+        outfit.look_body,
+        outfit.look_legs,
+        outfit.look_feet,
+        outfit.look_addons,
+        outfit.look_mount_head,
+        outfit.look_mount_body,
+        outfit.look_mount_legs,
+        outfit.look_mount_feet,
+        0,
+    ] {
+        stream.write_u8(value);
+    }
+}
+
+fn read_outfit(stream: &mut PropStream) -> Option<Outfit> {
+    let mut outfit = Outfit {
+        look_type: stream.read_u16()?,
+        look_type_ex: stream.read_u16()?,
+        look_mount: stream.read_u16()?,
+        ..Outfit::default()
+    };
+    outfit.look_head = stream.read_u8()?;
+    outfit.look_body = stream.read_u8()?;
+    outfit.look_legs = stream.read_u8()?;
+    outfit.look_feet = stream.read_u8()?;
+    outfit.look_addons = stream.read_u8()?;
+    outfit.look_mount_head = stream.read_u8()?;
+    outfit.look_mount_body = stream.read_u8()?;
+    outfit.look_mount_legs = stream.read_u8()?;
+    outfit.look_mount_feet = stream.read_u8()?;
+    stream.skip(1).then_some(outfit)
+}
+
+/// Motsvarar `Condition::serialize` med underklassernas tillägg
+fn serialize_condition(condition: &Condition, stream: &mut PropWriteStream) {
+    stream.write_u8(attr::TYPE);
+    stream.write_u32(condition.condition_type.0);
+    stream.write_u8(attr::ID);
+    stream.write_u32(condition.id.as_i8() as i32 as u32);
+    stream.write_u8(attr::TICKS);
+    stream.write_u32(condition.ticks as u32);
+    stream.write_u8(attr::ISBUFF);
+    stream.write_u8(condition.is_buff as u8);
+    stream.write_u8(attr::SUBID);
+    stream.write_u32(condition.sub_id);
+
+    match &condition.data {
+        ConditionData::Damage(damage) => {
+            stream.write_u8(attr::DELAYED);
+            stream.write_u8(damage.delayed as u8);
+            stream.write_u8(attr::PERIODDAMAGE);
+            stream.write_i32(damage.period_damage);
+            for info in &damage.damage_list {
+                stream.write_u8(attr::INTERVALDATA);
+                stream.write_i32(info.time_left);
+                stream.write_i32(info.value);
+                stream.write_i32(info.interval);
+            }
+        }
+        ConditionData::Speed(speed) => {
+            stream.write_u8(attr::SPEEDDELTA);
+            stream.write_i32(speed.speed_delta);
+            for (attribute, value) in [
+                (attr::FORMULA_MINA, speed.mina),
+                (attr::FORMULA_MINB, speed.minb),
+                (attr::FORMULA_MAXA, speed.maxa),
+                (attr::FORMULA_MAXB, speed.maxb),
+            ] {
+                stream.write_u8(attribute);
+                stream.write_f32(value);
+            }
+        }
+        ConditionData::Outfit(outfit) => {
+            stream.write_u8(attr::OUTFIT);
+            write_outfit(stream, outfit);
+        }
+        ConditionData::Light(light) => {
+            stream.write_u8(attr::LIGHTCOLOR);
+            stream.write_u8(light.light.color);
+            stream.write_u8(attr::LIGHTLEVEL);
+            stream.write_u8(light.light.level);
+            stream.write_u8(attr::LIGHTTICKS);
+            stream.write_u32(light.internal_light_ticks);
+            stream.write_u8(attr::LIGHTINTERVAL);
+            stream.write_u32(light.light_change_interval);
+        }
+        ConditionData::Regeneration(regen) => {
+            for (attribute, value) in [
+                (attr::HEALTHTICKS, regen.health_ticks),
+                (attr::HEALTHGAIN, regen.health_gain),
+                (attr::MANATICKS, regen.mana_ticks),
+                (attr::MANAGAIN, regen.mana_gain),
+            ] {
+                stream.write_u8(attribute);
+                stream.write_u32(value);
+            }
+        }
+        ConditionData::Soul(soul) => {
+            stream.write_u8(attr::SOULGAIN);
+            stream.write_u32(soul.soul_gain);
+            stream.write_u8(attr::SOULTICKS);
+            stream.write_u32(soul.soul_ticks);
+        }
+        ConditionData::Attributes(attributes) => {
+            for value in attributes.skills {
+                stream.write_u8(attr::SKILLS);
+                stream.write_i32(value);
+            }
+            for value in attributes.stats {
+                stream.write_u8(attr::STATS);
+                stream.write_i32(value);
+            }
+        }
+        ConditionData::Generic => {}
+    }
+}
+
+/// Ett attribut efter `TYPE` och `ID`. `None` betyder fel i datat.
+/// Motsvarar `unserializeProp` i basklassen och underklasserna.
+/// `counters` håller reda på hur många skills och stats som lästs.
+fn unserialize_prop(
+    condition: &mut Condition,
+    attribute: u8,
+    stream: &mut PropStream,
+    counters: &mut (usize, usize),
+) -> Option<()> {
+    match attribute {
+        attr::TYPE => condition.condition_type = ConditionType(stream.read_u32()?),
+        attr::ID => condition.id = ConditionId::from_i8(stream.read_u32()? as i32 as i8)?,
+        attr::TICKS => condition.ticks = stream.read_u32()? as i32,
+        attr::ISBUFF => condition.is_buff = stream.read_u8()? != 0,
+        attr::SUBID => condition.sub_id = stream.read_u32()?,
+        _ => {
+            let ticks = &mut condition.ticks;
+            match (&mut condition.data, attribute) {
+                (ConditionData::Damage(damage), attr::DELAYED) => damage.delayed = stream.read_u8()? != 0,
+                (ConditionData::Damage(damage), attr::PERIODDAMAGE) => damage.period_damage = stream.read_i32()?,
+                (ConditionData::Damage(_), attr::OWNER) => {
+                    stream.skip(4).then_some(())?;
+                }
+                (ConditionData::Damage(damage), attr::INTERVALDATA) => {
+                    let info = IntervalInfo {
+                        time_left: stream.read_i32()?,
+                        value: stream.read_i32()?,
+                        interval: stream.read_i32()?,
+                    };
+                    damage.damage_list.push_back(info);
+                    if *ticks != -1 {
+                        *ticks += info.interval;
+                    }
+                }
+                (ConditionData::Speed(speed), attr::SPEEDDELTA) => speed.speed_delta = stream.read_i32()?,
+                (ConditionData::Speed(speed), attr::FORMULA_MINA) => speed.mina = stream.read_f32()?,
+                (ConditionData::Speed(speed), attr::FORMULA_MINB) => speed.minb = stream.read_f32()?,
+                (ConditionData::Speed(speed), attr::FORMULA_MAXA) => speed.maxa = stream.read_f32()?,
+                (ConditionData::Speed(speed), attr::FORMULA_MAXB) => speed.maxb = stream.read_f32()?,
+                (ConditionData::Outfit(outfit), attr::OUTFIT) => *outfit = read_outfit(stream)?,
+                (ConditionData::Light(light), attr::LIGHTCOLOR) => light.light.color = stream.read_u8()?,
+                (ConditionData::Light(light), attr::LIGHTLEVEL) => light.light.level = stream.read_u8()?,
+                (ConditionData::Light(light), attr::LIGHTTICKS) => light.internal_light_ticks = stream.read_u32()?,
+                (ConditionData::Light(light), attr::LIGHTINTERVAL) => light.light_change_interval = stream.read_u32()?,
+                (ConditionData::Regeneration(regen), attr::HEALTHTICKS) => regen.health_ticks = stream.read_u32()?,
+                (ConditionData::Regeneration(regen), attr::HEALTHGAIN) => regen.health_gain = stream.read_u32()?,
+                (ConditionData::Regeneration(regen), attr::MANATICKS) => regen.mana_ticks = stream.read_u32()?,
+                (ConditionData::Regeneration(regen), attr::MANAGAIN) => regen.mana_gain = stream.read_u32()?,
+                (ConditionData::Soul(soul), attr::SOULGAIN) => soul.soul_gain = stream.read_u32()?,
+                (ConditionData::Soul(soul), attr::SOULTICKS) => soul.soul_ticks = stream.read_u32()?,
+                (ConditionData::Attributes(attributes), attr::SKILLS) => {
+                    let value = stream.read_i32()?;
+                    *attributes.skills.get_mut(counters.0)? = value;
+                    counters.0 += 1;
+                }
+                (ConditionData::Attributes(attributes), attr::STATS) => {
+                    let value = stream.read_i32()?;
+                    *attributes.stats.get_mut(counters.1)? = value;
+                    counters.1 += 1;
+                }
+                _ => return None,
+            }
+        }
+    }
+    Some(())
+}
+
+/// Nästa condition i strömmen, `Err` om den inte gick att läsa men
+/// strömmen kan läsas vidare. Motsvarar `Condition::createCondition(PropStream&)`
+/// och `Condition::unserialize`.
+fn unserialize_condition(stream: &mut PropStream) -> Option<Result<Condition, ()>> {
+    if stream.read_u8()? != attr::TYPE {
+        return None;
+    }
+    let condition_type = ConditionType(stream.read_u32()?);
+    if stream.read_u8()? != attr::ID {
+        return None;
+    }
+    let id = stream.read_u32()? as i32;
+    let condition = ConditionId::from_i8(id as i8)
+        .filter(|_| i8::try_from(id).is_ok())
+        .and_then(|id| create_condition(id, condition_type, 0, 0, false, 0));
+
+    // resten läses även för okända typer, så att nästa hittas
+    let mut condition = condition.ok_or(());
+    let mut counters = (0, 0);
+    loop {
+        let attribute = stream.read_u8()?;
+        if attribute == attr::END {
+            return Some(condition);
+        }
+        match &mut condition {
+            Ok(c) => unserialize_prop(c, attribute, stream, &mut counters)?,
+            Err(()) => return None,
+        }
+    }
+}
+
+/// Spelarens sparbara conditions i formatet i `players.conditions`.
+/// Motsvarar loopen i `IOLoginData::savePlayer`.
+pub fn serialize_conditions(conditions: &[Condition]) -> Vec<u8> {
+    let mut stream = PropWriteStream::new();
+    for condition in conditions.iter().filter(|c| c.is_persistent()) {
+        serialize_condition(condition, &mut stream);
+        stream.write_u8(attr::END);
+    }
+    stream.into_inner()
+}
+
+/// Conditions ur `players.conditions`, att lägga till när spelaren loggat in.
+/// Läsningen slutar vid första felet, som i `IOLoginData::loadPlayer`.
+pub fn unserialize_conditions(data: &[u8]) -> Vec<Condition> {
+    let mut stream = PropStream::new(data);
+    let mut conditions = Vec::new();
+    while let Some(condition) = unserialize_condition(&mut stream) {
+        if let Ok(condition) = condition {
+            conditions.push(condition);
+        }
+    }
+    conditions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn poison(rounds: i32, value: i32) -> Condition {
+        let mut condition = create_condition(ConditionId::Combat, ConditionType::POISON, 0, 0, false, 0).unwrap();
+        add_damage(&mut condition, rounds, 2000, value);
+        condition
+    }
+
+    fn damage_left(creature: &Creature) -> Vec<i32> {
+        match &creature.conditions()[0].data {
+            ConditionData::Damage(damage) => damage.damage_list.iter().map(|info| info.value).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    #[test]
+    fn damage_list_falls_from_start_and_sums_to_amount() {
+        for (amount, start) in [(100, 5), (300, 15), (40, 2), (-100, 5)] {
+            let list = generate_damage_list(amount, start);
+            assert_eq!(list.first(), Some(&start));
+            assert_eq!(list.last(), Some(&1));
+            assert!(list.windows(2).all(|w| w[0] >= w[1]), "{list:?}");
+            let sum: i32 = list.iter().sum();
+            assert!((sum - amount.abs()).abs() <= start, "{amount}: {sum}");
+        }
+    }
+
+    #[test]
+    fn weaker_damage_does_not_replace_stronger() {
+        let mut creature = Creature::new(CreatureType::Monster, "rat");
+        let (added, events) = add_condition(&mut creature, poison(5, -10));
+        assert!(added);
+        assert!(events.iter().any(|e| matches!(e, ConditionEvent::Damage { .. })));
+        assert_eq!(damage_left(&creature), vec![-10; 4]);
+
+        let (_, events) = add_condition(&mut creature, poison(2, -5));
+        assert!(events.is_empty());
+        assert_eq!(creature.conditions().len(), 1);
+        assert_eq!(damage_left(&creature), vec![-10; 4]);
+
+        let (_, events) = add_condition(&mut creature, poison(10, -20));
+        assert!(events.iter().any(|e| matches!(e, ConditionEvent::Damage { .. })));
+        assert_eq!(damage_left(&creature), vec![-20; 9]);
+    }
+
+    #[test]
+    fn haste_merges_and_paralyze_removes_it() {
+        let mut creature = Creature::new(CreatureType::Monster, "rat");
+        creature.base_speed = 200;
+        let speed = |condition_type, delta| {
+            create_condition(ConditionId::Default, condition_type, 10_000, delta, false, 0).unwrap()
+        };
+
+        add_condition(&mut creature, speed(ConditionType::HASTE, 50));
+        assert_eq!(creature.speed(), 250);
+        add_condition(&mut creature, speed(ConditionType::HASTE, 80));
+        assert_eq!(creature.speed(), 280);
+        assert_eq!(creature.conditions().len(), 1);
+
+        add_condition(&mut creature, speed(ConditionType::PARALYZE, -100));
+        assert!(!creature.has_condition(ConditionType::HASTE));
+        assert_eq!(creature.speed(), 100);
+    }
+}
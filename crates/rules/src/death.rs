@@ -0,0 +1,323 @@
+//! En spelares död: förlusten av erfarenhet och skills, blessings, det som
+//! tappas i liket och liket självt. Motsvarar `Player::death`,
+//! `Player::getLostPercent`, `Player::getGainedExperience`,
+//! `Player::getCorpse` och droploot.lua i TFS.
+//!
+//! Spelet gör det i den här ordningen när en spelare dött utanför en
+//! pvp-zon (där förlorar spelaren inget och tappar inget):
+//!
+//! 1. `skull::on_killed_player` för den som träffade sist och den som
+//!    gjorde mest skada, och `get_gained_experience` för anfallarna
+//! 2. `drop_loot` och `create_corpse`, och onDeath-händelserna med liket
+//! 3. `lose_skills` och `lost_experience` med `death_loss_percent`;
+//!    erfarenheten går genom `onLoseExperience` innan `lose_experience`
+//! 4. `revive`, innan spelaren skickas till templet
+
+use common::{uniform_random, Config};
+use entities::creature::Skull;
+use entities::player::{PlayerSex, CONST_SLOT_AMMO, CONST_SLOT_BACKPACK, CONST_SLOT_HEAD, CONST_SLOT_NECKLACE};
+use entities::{Player, PlayerFlags, Skill};
+use items::Item;
+
+use crate::condition::{self, ConditionEvent};
+use crate::experience::{self, Advance, MINIMUM_SKILL_LEVEL};
+use crate::vocation::{Vocation, VOCATION_NONE};
+
+pub const ITEM_MALE_CORPSE: u16 = 3058;
+pub const ITEM_FEMALE_CORPSE: u16 = 3065;
+pub const ITEM_AMULETOFLOSS: u16 = 2173;
+pub const ITEM_BAG: u16 = 1987;
+
+/// Biten för twist of fate i `Player::blessings`
+const BLESSING_TWIST_OF_FATE: u8 = 5;
+
+/// Promille att tappa en sak, per antal blessings. Behållare har samma
+/// siffra i procent. Motsvarar `Player.getLossPercent` i data/lib.
+const LOSS_PERCENT: [u32; 6] = [100, 70, 45, 25, 10, 0];
+
+#[derive(Debug, Clone)]
+pub struct DeathConfig {
+    /// Fast procent erfarenhet och skills att förlora, -1 för TFS formel
+    pub death_lose_percent: i32,
+    pub experience_by_killing_players: bool,
+    /// Största nivåskillnaden för erfarenhet från spelare
+    pub exp_from_players_level_range: u32,
+    /// Millisekunder en anfallare räknas som i striden
+    pub pz_locked: i32,
+}
+
+impl Default for DeathConfig {
+    fn default() -> Self {
+        Self {
+            death_lose_percent: -1,
+            experience_by_killing_players: false,
+            exp_from_players_level_range: 75,
+            pz_locked: 60000,
+        }
+    }
+}
+
+impl DeathConfig {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            death_lose_percent: config.death_lose_percent,
+            experience_by_killing_players: config.experience_by_killing_players,
+            exp_from_players_level_range: config.exp_from_players_level_range.max(0) as u32,
+            pz_locked: config.pz_locked,
+        }
+    }
+}
+
+/// De fem vanliga blessingsen spelaren har, utan twist of fate
+pub fn blessing_count(player: &Player) -> u32 {
+    (0..BLESSING_TWIST_OF_FATE).filter(|&blessing| player.has_blessing(blessing)).count() as u32
+}
+
+/// Andelen av erfarenheten, manan och skillsen spelaren förlorar vid döden.
+/// Befordran och blessings minskar den. Motsvarar `Player::getLostPercent`.
+pub fn get_lost_percent(player: &Player, promoted: bool, config: &DeathConfig) -> f64 {
+    let blessings = blessing_count(player);
+    if config.death_lose_percent != -1 {
+        let mut percent = config.death_lose_percent;
+        if promoted {
+            percent -= 3;
+        }
+        percent -= blessings as i32;
+        return percent.max(0) as f64 / 100.0;
+    }
+
+    let loss_percent = if player.level >= 25 && player.experience > 0 {
+        let level = player.level as f64 + player.level_percent as f64 / 100.0;
+        (level + 50.0) * 50.0 * (level * level - 5.0 * level + 8.0) / player.experience as f64
+    } else {
+        10.0
+    };
+    let mut reduction = 0.0;
+    if promoted {
+        reduction += 30.0;
+    }
+    reduction += blessings as f64 * 8.0;
+    loss_percent * (1.0 - reduction / 100.0) / 100.0
+}
+
+/// Promille för att en sak i utrustningen tappas, eller procent för en
+/// behållare
+pub fn item_loss_percent(player: &Player) -> u32 {
+    LOSS_PERCENT[blessing_count(player).min(5) as usize]
+}
+
+/// Erfarenheten en spelare får för sin del av skadan på en annan spelare
+/// som dog. `lost_percent` är offrets `get_lost_percent`. Motsvarar
+/// `Player::getGainedExperience`.
+pub fn get_gained_experience(
+    player: &Player,
+    attacker: &Player,
+    lost_percent: f64,
+    config: &DeathConfig,
+) -> u64 {
+    if !config.experience_by_killing_players || attacker.creature.id == player.creature.id {
+        return 0;
+    }
+    if attacker.level.abs_diff(player.level) > config.exp_from_players_level_range {
+        return 0;
+    }
+    let lost_experience = (player.experience as f64 * lost_percent) as u64;
+    (lost_experience as f64 * player.creature.get_damage_ratio(attacker.creature.id) * 0.75).floor().max(0.0) as u64
+}
+
+/// Procent av förlusten som gäller när spelaren dödades av fler och högre
+/// spelare än den själv. `attacker_levels` är nivåerna för spelarna som
+/// skadat den inom `pz_locked`. Motsvarar början av `Player::death`.
+pub fn unfair_fight_reduction(player: &Player, attacker_levels: impl IntoIterator<Item = u32>) -> u8 {
+    let sum_levels: u64 = attacker_levels.into_iter().map(u64::from).sum();
+    if sum_levels <= player.level as u64 {
+        return 100;
+    }
+    let reduce = player.level as f64 / sum_levels as f64;
+    ((reduce * 100.0 + 0.5).floor() as u8).max(20)
+}
+
+/// Andelen spelaren förlorar den här gången, med `unfair_fight_reduction`
+pub fn death_loss_percent(lost_percent: f64, unfair_fight_reduction: u8) -> f64 {
+    lost_percent * (unfair_fight_reduction as f64 / 100.0)
+}
+
+/// Magisk nivå och skills minskar med andelen av allt spelaren samlat.
+/// Som i TFS får spelaren inga meddelanden om det.
+pub fn lose_skills(player: &mut Player, vocation: &Vocation, loss_percent: f64) {
+    // kraven växer exponentiellt och räknas med mättnad
+    let sum_mana = (1..=player.mag_level)
+        .fold(player.mana_spent, |sum, level| sum.saturating_add(vocation.get_req_mana(level)));
+    let mana = (sum_mana as f64 * loss_percent) as u64;
+    experience::remove_mana_spent(player, vocation, mana);
+
+    for skill in Skill::ALL {
+        let value = player.skill(skill);
+        let sum_tries = (MINIMUM_SKILL_LEVEL + 1..=value.level)
+            .fold(value.tries, |sum, level| sum.saturating_add(vocation.get_req_skill_tries(skill, level)));
+        experience::remove_skill_tries(player, vocation, skill, (sum_tries as f64 * loss_percent) as u64);
+    }
+}
+
+/// Erfarenheten spelaren förlorar, innan `onLoseExperience`
+pub fn lost_experience(player: &Player, loss_percent: f64) -> u64 {
+    (player.experience as f64 * loss_percent) as u64
+}
+
+/// Ta bort erfarenheten som blev kvar efter `onLoseExperience`. Spelare med
+/// yrke behåller den till och med nivå 7. Spelet skickar nedgraderingen.
+pub fn lose_experience(player: &mut Player, vocation: &Vocation, exp: u64) -> Option<Advance> {
+    if exp == 0 || (vocation.id != VOCATION_NONE && player.level <= 7) {
+        return None;
+    }
+    experience::remove_experience(player, vocation, exp)
+}
+
+/// Efter förlusten: blessingsen förbrukas, hälsan och manan fylls på och
+/// de sparade conditions tas bort. Twist of fate skyddar bara mot spelare
+/// och går då åt; dör spelaren annars finns bara den kvar. Med svart skalle
+/// vaknar spelaren med 40 i hälsa och ingen mana.
+pub fn revive(player: &mut Player, last_hit_by_player: bool) -> Vec<ConditionEvent> {
+    if player.has_blessing(BLESSING_TWIST_OF_FATE) {
+        if last_hit_by_player {
+            player.remove_blessing(BLESSING_TWIST_OF_FATE);
+        } else {
+            player.blessings = 0;
+            player.add_blessing(BLESSING_TWIST_OF_FATE);
+        }
+    } else {
+        player.blessings = 0;
+    }
+
+    let creature = &mut player.creature;
+    if creature.skull == Skull::Black {
+        creature.health = 40;
+        creature.mana = 0;
+    } else {
+        creature.health = creature.health_max;
+        creature.mana = creature.mana_max;
+    }
+    condition::remove_persistent_conditions(player)
+}
+
+/// Det spelaren tappar i liket. Spelare utan yrke eller med
+/// `PlayerFlag_NotGenerateLoot` tappar inget. En amulet of loss skyddar
+/// allt och går åt, om spelaren inte har röd eller svart skalle. Får
+/// spelaren ingen ryggsäck kvar får den en väska. Motsvarar droploot.lua.
+pub fn drop_loot(player: &mut Player, vocation: &Vocation, killed_by_player: bool) -> Vec<Item> {
+    let mut loot = Vec::new();
+    if player.has_flag(PlayerFlags::NOT_GENERATE_LOOT) || vocation.id == VOCATION_NONE {
+        return loot;
+    }
+
+    let red_or_black = matches!(player.creature.skull, Skull::Red | Skull::Black);
+    let amulet = player.inventory_item(CONST_SLOT_NECKLACE).map(|item| item.id);
+    if amulet == Some(ITEM_AMULETOFLOSS) && !red_or_black {
+        if !killed_by_player || !player.has_blessing(BLESSING_TWIST_OF_FATE) {
+            player.set_inventory_item(CONST_SLOT_NECKLACE, None);
+        }
+    } else {
+        let loss_percent = item_loss_percent(player);
+        for slot in CONST_SLOT_HEAD..=CONST_SLOT_AMMO {
+            let Some(item) = player.inventory_item(slot) else {
+                continue;
+            };
+            let max = if item.get_container().is_some() { 100 } else { 1000 };
+            let dropped = red_or_black || uniform_random(1, max) <= loss_percent as i64;
+            if dropped && (red_or_black || loss_percent != 0) {
+                loot.extend(player.set_inventory_item(slot, None));
+            }
+        }
+    }
+
+    if player.inventory_item(CONST_SLOT_BACKPACK).is_none() {
+        player.set_inventory_item(CONST_SLOT_BACKPACK, Some(Item::new(ITEM_BAG, 1)));
+    }
+    loot
+}
+
+/// Liket med det spelaren tappade. Det som inte får plats försvinner.
+/// `killer` är mördarens namnbeskrivning, t.ex. "a dragon". Motsvarar
+/// `Player::getCorpse` och `getLookCorpse`.
+pub fn create_corpse(player: &Player, killer: Option<&str>, loot: Vec<Item>) -> Item {
+    let corpse_id = match player.sex {
+        PlayerSex::Female => ITEM_FEMALE_CORPSE,
+        _ => ITEM_MALE_CORPSE,
+    };
+    let mut corpse = Item::new(corpse_id, 0);
+    if let Some(container) = corpse.get_container_mut() {
+        for item in loot {
+            if container.is_full() {
+                break;
+            }
+            container.add_item_front(item);
+        }
+        let name = &player.creature.name;
+        let description = match killer {
+            Some(killer) => {
+                let pronoun = if player.sex == PlayerSex::Female { "She" } else { "He" };
+                format!("You recognize {name}. {pronoun} was killed by {killer}.")
+            }
+            None => format!("You recognize {name}."),
+        };
+        corpse.attributes_mut().description = Some(description);
+    }
+    corpse
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-6, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn low_levels_lose_ten_percent_less_promotion_and_blessings() {
+        let config = DeathConfig::default();
+        let mut player = Player::new("Bob");
+        player.level = 10;
+        assert_close(get_lost_percent(&player, false, &config), 0.10);
+        assert_close(get_lost_percent(&player, true, &config), 0.07);
+
+        player.add_blessing(1);
+        player.add_blessing(2);
+        // twist of fate skyddar inte erfarenheten
+        player.add_blessing(BLESSING_TWIST_OF_FATE);
+        assert_close(get_lost_percent(&player, true, &config), 0.10 * (1.0 - 0.46));
+    }
+
+    #[test]
+    fn high_levels_follow_the_formula() {
+        let mut player = Player::new("Bob");
+        player.level = 100;
+        player.experience = Player::get_exp_for_level(100);
+        let expected = 150.0 * 50.0 * (100.0 * 100.0 - 500.0 + 8.0) / player.experience as f64 / 100.0;
+        assert_close(get_lost_percent(&player, false, &DeathConfig::default()), expected);
+    }
+
+    #[test]
+    fn fixed_percent_from_config() {
+        let config = DeathConfig { death_lose_percent: 10, ..Default::default() };
+        let mut player = Player::new("Bob");
+        player.add_blessing(1);
+        player.add_blessing(2);
+        assert_close(get_lost_percent(&player, true, &config), 0.05);
+
+        let config = DeathConfig { death_lose_percent: 2, ..Default::default() };
+        assert_close(get_lost_percent(&player, true, &config), 0.0);
+    }
+
+    #[test]
+    fn unfair_fights_reduce_the_loss_down_to_a_fifth() {
+        let mut player = Player::new("Bob");
+        player.level = 100;
+        assert_eq!(unfair_fight_reduction(&player, []), 100);
+        assert_eq!(unfair_fight_reduction(&player, [50, 50]), 100);
+        assert_eq!(unfair_fight_reduction(&player, [100, 100]), 50);
+        assert_eq!(unfair_fight_reduction(&player, [150, 150]), 33);
+        assert_eq!(unfair_fight_reduction(&player, [200, 300, 500]), 20);
+        assert_close(death_loss_percent(0.10, 50), 0.05);
+    }
+}
@@ -0,0 +1,451 @@
+//! Erfarenhet, nivåer och skills: stegen för erfarenhet och det som händer
+//! när spelaren går upp eller ned. Motsvarar `Game::getExperienceStage`,
+//! `Player::addExperience`, `removeExperience`, `addManaSpent` och
+//! `addSkillAdvance` i TFS.
+//!
+//! Händelserna i events.xml (`onGainExperience`, `onLoseExperience` och
+//! `onGainSkillTries`) körs av `scripting::experience` innan ändringen görs
+//! här; funktionerna får det som blev kvar.
+
+use std::path::Path;
+
+use common::configmanager::ExperienceStage;
+use common::{Config, Error, Result};
+use entities::creature::PLAYER_MAX_SPEED;
+use entities::{Player, PlayerFlags, Skill};
+
+use crate::vocation::Vocation;
+
+/// `SKILL_MAGLEVEL` och `SKILL_LEVEL` efter skillsen i `skills_t`
+pub const SKILL_MAGLEVEL: u8 = 7;
+pub const SKILL_LEVEL: u8 = 8;
+
+/// Lägre än så här kan en skill inte sänkas
+pub const MINIMUM_SKILL_LEVEL: u16 = 10;
+
+/// Det som gick upp eller ned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdvanceSkill {
+    Skill(Skill),
+    MagicLevel,
+    Level,
+}
+
+impl AdvanceSkill {
+    /// Värdet i `skills_t`, som `onAdvance` och `onGainSkillTries` får
+    pub fn id(self) -> u8 {
+        match self {
+            AdvanceSkill::Skill(skill) => skill as u8,
+            AdvanceSkill::MagicLevel => SKILL_MAGLEVEL,
+            AdvanceSkill::Level => SKILL_LEVEL,
+        }
+    }
+}
+
+/// En ny nivå; `from > to` när spelaren förlorade nivåer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Advance {
+    pub skill: AdvanceSkill,
+    pub from: u32,
+    pub to: u32,
+}
+
+impl Advance {
+    /// Meddelandet spelaren får som `MESSAGE_EVENT_ADVANCE`
+    pub fn message(&self) -> String {
+        match self.skill {
+            AdvanceSkill::Level if self.from > self.to => {
+                format!("You were downgraded from Level {} to Level {}.", self.from, self.to)
+            }
+            AdvanceSkill::Level => format!("You advanced from Level {} to Level {}.", self.from, self.to),
+            AdvanceSkill::MagicLevel if self.from > self.to => {
+                format!("You were downgraded to magic level {}.", self.to)
+            }
+            AdvanceSkill::MagicLevel => format!("You advanced to magic level {}.", self.to),
+            AdvanceSkill::Skill(skill) if self.from > self.to => {
+                format!("You were downgraded in {} from level {} to level {}.", skill.name(), self.from, self.to)
+            }
+            AdvanceSkill::Skill(skill) => format!("You advanced in {}.", skill.name()),
+        }
+    }
+}
+
+/// Multiplikatorn för erfarenhet per nivå, motsvarar `stages` i `Game`
+#[derive(Debug, Clone, Default)]
+pub struct ExperienceStages {
+    /// Tom när stegen är avstängda; då gäller `rate`
+    stages: Vec<ExperienceStage>,
+    rate: f64,
+}
+
+impl ExperienceStages {
+    /// `experienceStages` i config.lua, eller `rateExp` om det inte finns några
+    pub fn from_config(config: &Config) -> Self {
+        Self { stages: config.experience_stages.clone(), rate: config.rate_exp as f64 }
+    }
+
+    /// Läs stages.xml. Är stegen avstängda där gäller config.lua. Motsvarar
+    /// `Game::loadExperienceStages`.
+    pub fn load_from_xml(path: impl AsRef<Path>, config: &Config) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| Error::World(format!("Cannot open {}: {e}", path.display())))?;
+        let doc = roxmltree::Document::parse(&text)
+            .map_err(|e| Error::World(format!("Cannot parse {}: {e}", path.display())))?;
+
+        let root = doc.root_element();
+        let enabled = root
+            .children()
+            .find(|n| n.has_tag_name("config"))
+            .and_then(|n| attr_bool(&n, "enabled"))
+            .unwrap_or(false);
+        if !enabled {
+            return Ok(Self::from_config(config));
+        }
+
+        let mut stages = Vec::new();
+        for node in root.children().filter(|n| n.has_tag_name("stage")) {
+            let minlevel = attr(&node, "minlevel").unwrap_or(1);
+            let maxlevel = attr(&node, "maxlevel");
+            let multiplier = attr(&node, "multiplier").unwrap_or(1.0);
+            stages.push(ExperienceStage { minlevel, maxlevel, multiplier });
+        }
+        Ok(Self { stages, rate: config.rate_exp as f64 })
+    }
+
+    /// Motsvarar `Game::getExperienceStage`. Som i TFS gäller ett senare
+    /// steg över ett tidigare, och en nivå utan steg får 0.
+    pub fn get_experience_stage(&self, level: u32) -> f64 {
+        if self.stages.is_empty() {
+            return self.rate;
+        }
+        self.stages
+            .iter()
+            .rev()
+            .find(|stage| level >= stage.minlevel && stage.maxlevel.is_none_or(|maxlevel| level <= maxlevel))
+            .map_or(0.0, |stage| stage.multiplier as f64)
+    }
+}
+
+/// Spelaren är på högsta nivån och kan inte få mer erfarenhet
+pub fn is_max_level(level: u32) -> bool {
+    Player::get_exp_for_level(level) >= Player::get_exp_for_level(level + 1)
+}
+
+/// Spelaren kan inte komma högre i magisk nivå
+pub fn is_max_magic_level(vocation: &Vocation, mag_level: u32) -> bool {
+    vocation.get_req_mana(mag_level) >= vocation.get_req_mana(mag_level + 1)
+}
+
+/// Spelaren kan inte komma högre i skillen
+pub fn is_max_skill(vocation: &Vocation, skill: Skill, level: u16) -> bool {
+    vocation.get_req_skill_tries(skill, level) >= vocation.get_req_skill_tries(skill, level + 1)
+}
+
+/// Procenten till nästa nivå för nivån, magisk nivå och skillsen, samt
+/// farten. Görs när spelaren loggat in och yrket är känt.
+pub fn update_percents(player: &mut Player, vocation: &Vocation) {
+    let curr_level_exp = Player::get_exp_for_level(player.level);
+    let next_level_exp = Player::get_exp_for_level(player.level + 1);
+    player.level_percent = if next_level_exp > curr_level_exp {
+        Player::get_percent_level(player.experience.saturating_sub(curr_level_exp), next_level_exp - curr_level_exp)
+    } else {
+        0
+    };
+    let next_req_mana = vocation.get_req_mana(player.mag_level + 1);
+    player.mag_level_percent = Player::get_percent_level(player.mana_spent, next_req_mana);
+    for skill in Skill::ALL {
+        let value = player.skill(skill);
+        let next_req_tries = vocation.get_req_skill_tries(skill, value.level + 1);
+        player.skill_mut(skill).percent = Player::get_percent_level(value.tries, next_req_tries);
+    }
+    update_base_speed(player, vocation);
+}
+
+/// Motsvarar `Player::updateBaseSpeed`
+pub fn update_base_speed(player: &mut Player, vocation: &Vocation) {
+    player.creature.base_speed = if player.has_flag(PlayerFlags::SET_MAX_SPEED) {
+        PLAYER_MAX_SPEED
+    } else {
+        vocation.base_speed + 2 * (player.level.max(1) - 1)
+    };
+}
+
+/// Hälsa och mana fylls på och farten räknas om efter en ny nivå
+fn on_level_change(player: &mut Player, vocation: &Vocation) {
+    let creature = &mut player.creature;
+    creature.health = creature.health_max.max(1);
+    creature.mana = creature.mana_max;
+    update_base_speed(player, vocation);
+}
+
+/// Lägg till erfarenhet som redan gått genom `onGainExperience`. Spelet
+/// skickar statistiken, farten och `onAdvance`. Motsvarar resten av
+/// `Player::addExperience`.
+pub fn add_experience(player: &mut Player, vocation: &Vocation, exp: u64) -> Option<Advance> {
+    let mut curr_level_exp = Player::get_exp_for_level(player.level);
+    let mut next_level_exp = Player::get_exp_for_level(player.level + 1);
+    if curr_level_exp >= next_level_exp {
+        player.level_percent = 0;
+        return None;
+    }
+
+    player.experience += exp;
+    let prev_level = player.level;
+    while player.experience >= next_level_exp {
+        player.level += 1;
+        let creature = &mut player.creature;
+        creature.health_max += vocation.gain_hp as i32;
+        creature.health += vocation.gain_hp as i32;
+        creature.mana_max += vocation.gain_mana;
+        creature.mana += vocation.gain_mana;
+        player.capacity += vocation.gain_cap;
+
+        curr_level_exp = next_level_exp;
+        next_level_exp = Player::get_exp_for_level(player.level + 1);
+        if curr_level_exp >= next_level_exp {
+            break;
+        }
+    }
+
+    player.level_percent = if next_level_exp > curr_level_exp {
+        Player::get_percent_level(player.experience - curr_level_exp, next_level_exp - curr_level_exp)
+    } else {
+        0
+    };
+
+    if prev_level == player.level {
+        return None;
+    }
+    on_level_change(player, vocation);
+    Some(Advance { skill: AdvanceSkill::Level, from: prev_level, to: player.level })
+}
+
+/// Ta bort erfarenhet som redan gått genom `onLoseExperience`. Motsvarar
+/// resten av `Player::removeExperience`.
+pub fn remove_experience(player: &mut Player, vocation: &Vocation, exp: u64) -> Option<Advance> {
+    player.experience = player.experience.saturating_sub(exp);
+
+    let old_level = player.level;
+    let mut curr_level_exp = Player::get_exp_for_level(player.level);
+    while player.level > 1 && player.experience < curr_level_exp {
+        player.level -= 1;
+        let creature = &mut player.creature;
+        creature.health_max = (creature.health_max - vocation.gain_hp as i32).max(0);
+        creature.mana_max = creature.mana_max.saturating_sub(vocation.gain_mana);
+        player.capacity = player.capacity.saturating_sub(vocation.gain_cap);
+        curr_level_exp = Player::get_exp_for_level(player.level);
+    }
+
+    let next_level_exp = Player::get_exp_for_level(player.level + 1);
+    player.level_percent = if next_level_exp > curr_level_exp {
+        Player::get_percent_level(player.experience - curr_level_exp, next_level_exp - curr_level_exp)
+    } else {
+        0
+    };
+
+    if old_level == player.level {
+        return None;
+    }
+    on_level_change(player, vocation);
+    Some(Advance { skill: AdvanceSkill::Level, from: old_level, to: player.level })
+}
+
+/// Lägg till mana som redan gått genom `onGainSkillTries` med
+/// `SKILL_MAGLEVEL`. Motsvarar resten av `Player::addManaSpent`.
+pub fn add_mana_spent(player: &mut Player, vocation: &Vocation, mut amount: u64) -> Vec<Advance> {
+    let mut advances = Vec::new();
+    let mut curr_req_mana = vocation.get_req_mana(player.mag_level);
+    let mut next_req_mana = vocation.get_req_mana(player.mag_level + 1);
+    if curr_req_mana >= next_req_mana {
+        return advances;
+    }
+
+    while player.mana_spent + amount >= next_req_mana {
+        amount -= next_req_mana - player.mana_spent;
+        player.mag_level += 1;
+        player.mana_spent = 0;
+        advances.push(Advance { skill: AdvanceSkill::MagicLevel, from: player.mag_level - 1, to: player.mag_level });
+
+        curr_req_mana = next_req_mana;
+        next_req_mana = vocation.get_req_mana(player.mag_level + 1);
+        if curr_req_mana >= next_req_mana {
+            return advances;
+        }
+    }
+
+    player.mana_spent += amount;
+    player.mag_level_percent = Player::get_percent_level(player.mana_spent, next_req_mana);
+    advances
+}
+
+/// Lägg till tries som redan gått genom `onGainSkillTries`. Motsvarar
+/// resten av `Player::addSkillAdvance`.
+pub fn add_skill_advance(player: &mut Player, vocation: &Vocation, skill: Skill, mut count: u64) -> Vec<Advance> {
+    let mut advances = Vec::new();
+    let mut curr_req_tries = vocation.get_req_skill_tries(skill, player.skill(skill).level);
+    let mut next_req_tries = vocation.get_req_skill_tries(skill, player.skill(skill).level + 1);
+    if curr_req_tries >= next_req_tries {
+        return advances;
+    }
+
+    while player.skill(skill).tries + count >= next_req_tries {
+        let value = player.skill_mut(skill);
+        count -= next_req_tries - value.tries;
+        value.level += 1;
+        value.tries = 0;
+        value.percent = 0;
+        let level = value.level as u32;
+        advances.push(Advance { skill: AdvanceSkill::Skill(skill), from: level - 1, to: level });
+
+        curr_req_tries = next_req_tries;
+        next_req_tries = vocation.get_req_skill_tries(skill, player.skill(skill).level + 1);
+        if curr_req_tries >= next_req_tries {
+            count = 0;
+            break;
+        }
+    }
+
+    let value = player.skill_mut(skill);
+    value.tries += count;
+    value.percent = if next_req_tries > curr_req_tries {
+        Player::get_percent_level(value.tries, next_req_tries)
+    } else {
+        0
+    };
+    advances
+}
+
+/// Ta bort spenderad mana, ned genom magiska nivåer om det behövs.
+/// Motsvarar `Player::removeManaSpent`.
+pub fn remove_mana_spent(player: &mut Player, vocation: &Vocation, mut amount: u64) -> Option<Advance> {
+    if amount == 0 {
+        return None;
+    }
+
+    let old_level = player.mag_level;
+    while amount > player.mana_spent && player.mag_level > 0 {
+        amount -= player.mana_spent;
+        player.mana_spent = vocation.get_req_mana(player.mag_level);
+        player.mag_level -= 1;
+    }
+    player.mana_spent = player.mana_spent.saturating_sub(amount);
+
+    let next_req_mana = vocation.get_req_mana(player.mag_level + 1);
+    player.mag_level_percent = if next_req_mana > vocation.get_req_mana(player.mag_level) {
+        Player::get_percent_level(player.mana_spent, next_req_mana)
+    } else {
+        0
+    };
+
+    (old_level != player.mag_level).then_some(Advance {
+        skill: AdvanceSkill::MagicLevel,
+        from: old_level,
+        to: player.mag_level,
+    })
+}
+
+/// Ta bort tries, ned genom nivåer men inte under `MINIMUM_SKILL_LEVEL`.
+/// Motsvarar `Player::removeSkillTries`.
+pub fn remove_skill_tries(player: &mut Player, vocation: &Vocation, skill: Skill, mut count: u64) -> Option<Advance> {
+    let value = player.skill_mut(skill);
+    let old_level = value.level;
+    while count > value.tries {
+        count -= value.tries;
+        if value.level <= MINIMUM_SKILL_LEVEL {
+            value.level = MINIMUM_SKILL_LEVEL;
+            value.tries = 0;
+            count = 0;
+            break;
+        }
+        value.tries = vocation.get_req_skill_tries(skill, value.level);
+        value.level -= 1;
+    }
+    value.tries = value.tries.saturating_sub(count);
+    // som i TFS räknas procenten mot nivån spelaren har, inte nästa
+    value.percent = Player::get_percent_level(value.tries, vocation.get_req_skill_tries(skill, value.level));
+
+    (old_level != value.level).then_some(Advance {
+        skill: AdvanceSkill::Skill(skill),
+        from: old_level as u32,
+        to: value.level as u32,
+    })
+}
+
+fn attr_str<'a>(node: &roxmltree::Node<'a, '_>, name: &str) -> Option<&'a str> {
+    node.attributes()
+        .find(|a| a.name().eq_ignore_ascii_case(name))
+        .map(|a| a.value())
+}
+
+fn attr<T: std::str::FromStr>(node: &roxmltree::Node, name: &str) -> Option<T> {
+    attr_str(node, name).and_then(|v| v.trim().parse().ok())
+}
+
+/// Som pugixmls `as_bool`: sant om värdet börjar med 1, t, T, y eller Y
+fn attr_bool(node: &roxmltree::Node, name: &str) -> Option<bool> {
+    attr_str(node, name).map(|v| matches!(v.trim().chars().next(), Some('1' | 't' | 'T' | 'y' | 'Y')))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn experience_raises_and_lowers_levels() {
+        let vocation = Vocation::new(0);
+        let mut player = Player::new("Bob");
+
+        assert_eq!(add_experience(&mut player, &vocation, 99), None);
+        let advance = add_experience(&mut player, &vocation, 4101);
+        assert_eq!(advance, Some(Advance { skill: AdvanceSkill::Level, from: 1, to: 8 }));
+        assert_eq!(player.experience, Player::get_exp_for_level(8));
+        assert_eq!(player.creature.health_max, 150 + 7 * 5);
+        assert_eq!(player.creature.health, player.creature.health_max);
+        assert_eq!(player.capacity, 40000 + 7 * 500);
+        assert_eq!(player.creature.base_speed, 220 + 2 * 7);
+
+        let advance = remove_experience(&mut player, &vocation, 4100);
+        assert_eq!(advance, Some(Advance { skill: AdvanceSkill::Level, from: 8, to: 2 }));
+        assert_eq!(player.creature.health_max, 150 + 5);
+        assert_eq!(player.level_percent, 0);
+    }
+
+    #[test]
+    fn skill_tries_can_advance_several_levels() {
+        let vocation = Vocation::new(0);
+        let mut player = Player::new("Bob");
+
+        // klubba: 50 tries till nivå 11, 100 till 12 och 200 till 13
+        let advances = add_skill_advance(&mut player, &vocation, Skill::Club, 175);
+        assert_eq!(advances.len(), 2);
+        assert_eq!(advances[1], Advance { skill: AdvanceSkill::Skill(Skill::Club), from: 11, to: 12 });
+        let club = player.skill(Skill::Club);
+        assert_eq!((club.level, club.tries, club.percent), (12, 25, 12));
+    }
+
+    #[test]
+    fn mana_spent_raises_magic_level() {
+        let vocation = Vocation::new(0);
+        let mut player = Player::new("Bob");
+
+        let advances = add_mana_spent(&mut player, &vocation, 2000);
+        assert_eq!(advances, vec![Advance { skill: AdvanceSkill::MagicLevel, from: 0, to: 1 }]);
+        assert_eq!((player.mana_spent, player.mag_level_percent), (400, 6));
+    }
+
+    #[test]
+    fn later_stage_wins_and_gaps_give_nothing() {
+        let stages = ExperienceStages {
+            stages: vec![
+                ExperienceStage { minlevel: 1, maxlevel: Some(50), multiplier: 5.0 },
+                ExperienceStage { minlevel: 40, maxlevel: Some(80), multiplier: 3.0 },
+            ],
+            rate: 1.0,
+        };
+        assert_eq!(stages.get_experience_stage(10), 5.0);
+        assert_eq!(stages.get_experience_stage(45), 3.0);
+        assert_eq!(stages.get_experience_stage(81), 0.0);
+        assert_eq!(ExperienceStages { stages: Vec::new(), rate: 2.0 }.get_experience_stage(81), 2.0);
+    }
+}
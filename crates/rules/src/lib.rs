@@ -2,4 +2,9 @@ pub mod combat;
 pub mod spells;
 pub mod condition;
 pub mod vocation;
+pub mod experience;
 pub mod weapons;
+pub mod death;
+pub mod skull;
+pub mod stamina;
+pub mod outfit;
@@ -0,0 +1,400 @@
+//! Utseendefönstret och hästarna. Motsvarar `Player::canWear`,
+//! `Player::toggleMount`, `Game::playerChangeOutfit` och
+//! `ProtocolGame::sendOutfitWindow` i TFS. Spelet skickar den nya outfiten
+//! till `Creature:onChangeOutfit` (internalCreatureChangeOutfit) och farten
+//! till klienterna när `OutfitUpdate` säger att de ändrats.
+
+use common::{Config, ReturnValue};
+use entities::{ConditionType, Mount, Mounts, Outfit, OutfitType, Outfits, Player};
+
+/// Utseendet spelledare alltid kan välja
+pub const GAMEMASTER_LOOK_TYPE: u16 = 75;
+
+/// Millisekunder mellan två byten med hästen
+pub const MOUNT_EXHAUST: u64 = 3000;
+
+/// Inställningarna i config.lua som rör utseendet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutfitConfig {
+    /// `allowChangeOutfit`
+    pub allow_change_outfit: bool,
+    pub free_premium: bool,
+}
+
+impl Default for OutfitConfig {
+    fn default() -> Self {
+        Self { allow_change_outfit: true, free_premium: false }
+    }
+}
+
+impl OutfitConfig {
+    pub fn from_config(config: &Config) -> Self {
+        Self { allow_change_outfit: config.allow_change_outfit, free_premium: config.free_premium }
+    }
+}
+
+/// Ett utseende i fönstret
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutfitChoice {
+    pub name: String,
+    pub look_type: u16,
+    pub addons: u8,
+}
+
+/// Det `sendOutfitWindow` skickar
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutfitWindow {
+    pub current: Outfit,
+    pub outfits: Vec<OutfitChoice>,
+    /// Klientens id och namnet på hästarna spelaren har
+    pub mounts: Vec<(u16, String)>,
+}
+
+/// Resultatet av ett byte. `outfit` ska ges till `Creature:onChangeOutfit`
+/// och sedan visas, om inget villkor döljer det.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct OutfitUpdate {
+    pub outfit: Option<Outfit>,
+    pub speed_changed: bool,
+}
+
+/// Motsvarar `Player::toggleMount`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToggleMount {
+    Changed(OutfitUpdate),
+    /// Spelaren har ingen häst vald, fönstret ska öppnas
+    OutfitWindow,
+    Cancel(ReturnValue),
+    Unchanged,
+}
+
+/// Motsvarar `Player::getOutfitAddons`, None om spelaren inte kan välja utseendet
+pub fn get_outfit_addons(player: &Player, outfit: &OutfitType, premium: bool) -> Option<u8> {
+    if player.group.access {
+        return Some(3);
+    }
+    if outfit.premium && !premium {
+        return None;
+    }
+    match player.outfit_addons(outfit.look_type) {
+        Some(addons) => Some(addons),
+        None => outfit.unlocked.then_some(0),
+    }
+}
+
+/// Motsvarar `Player::canWear`
+pub fn can_wear(player: &Player, outfits: &Outfits, look_type: u16, addons: u8, premium: bool) -> bool {
+    // bara de två addonsen finns
+    if addons > 3 {
+        return false;
+    }
+    if player.group.access {
+        return true;
+    }
+    let Some(outfit) = outfits.get_outfit_by_look_type(player.sex, look_type) else {
+        return false;
+    };
+    if outfit.premium && !premium {
+        return false;
+    }
+    if outfit.unlocked && addons == 0 {
+        return true;
+    }
+    match player.outfit_addons(look_type) {
+        Some(current) => (current & addons) == addons,
+        None => false,
+    }
+}
+
+/// Motsvarar `Player::hasMount`
+pub fn has_mount(player: &Player, mount: &Mount, premium: bool) -> bool {
+    if player.group.access {
+        return true;
+    }
+    if mount.premium && !premium {
+        return false;
+    }
+    player.has_mount(mount.id)
+}
+
+/// Motsvarar `ProtocolGame::sendOutfitWindow`, None om det inte finns några
+/// utseenden för spelarens kön
+pub fn outfit_window(player: &Player, outfits: &Outfits, mounts: &Mounts, premium: bool) -> Option<OutfitWindow> {
+    let types = outfits.get_outfits(player.sex);
+    let mut current = player.creature.default_outfit;
+    if current.look_type == 0 {
+        current = Outfit { look_type: types.first()?.look_type, ..Outfit::default() };
+    }
+    if let Some(mount) = mounts.get_mount_by_id(player.current_mount) {
+        current.look_mount = mount.client_id;
+    }
+
+    let mut choices = Vec::new();
+    if player.group.access {
+        choices.push(OutfitChoice { name: "Gamemaster".to_string(), look_type: GAMEMASTER_LOOK_TYPE, addons: 0 });
+    }
+    for outfit in types {
+        let Some(addons) = get_outfit_addons(player, outfit, premium) else {
+            continue;
+        };
+        choices.push(OutfitChoice { name: outfit.name.clone(), look_type: outfit.look_type, addons });
+        if choices.len() == u8::MAX as usize {
+            break;
+        }
+    }
+
+    let mounts = mounts
+        .iter()
+        .filter(|mount| has_mount(player, mount, premium))
+        .map(|mount| (mount.client_id, mount.name.clone()))
+        .collect();
+    Some(OutfitWindow { current, outfits: choices, mounts })
+}
+
+/// Motsvarar `Player::dismount`, sätter inte outfiten på varelsen
+pub fn dismount(player: &mut Player, mounts: &Mounts) -> bool {
+    let mut speed_changed = false;
+    if let Some(mount) = mounts.get_mount_by_id(player.current_mount) {
+        if mount.speed > 0 {
+            player.creature.change_speed(-mount.speed);
+            speed_changed = true;
+        }
+    }
+    player.creature.default_outfit.look_mount = 0;
+    speed_changed
+}
+
+/// Motsvarar `Player::untameMount`. Sitter spelaren på hästen stiger den av
+/// först. Första värdet är true om spelaren hade hästen.
+pub fn untame_mount(player: &mut Player, mounts: &Mounts, mount_id: u16) -> (bool, OutfitUpdate) {
+    let mut update = OutfitUpdate::default();
+    if player.current_mount == mount_id && player.is_mounted() {
+        update.speed_changed = dismount(player, mounts);
+        update.outfit = Some(player.creature.default_outfit);
+    }
+    (player.untame_mount(mount_id), update)
+}
+
+/// Motsvarar `Game::playerChangeOutfit`, när spelaren väljer i fönstret
+pub fn change_outfit(
+    player: &mut Player,
+    outfits: &Outfits,
+    mounts: &Mounts,
+    mut outfit: Outfit,
+    premium: bool,
+    config: &OutfitConfig,
+) -> OutfitUpdate {
+    let mut update = OutfitUpdate::default();
+    if !config.allow_change_outfit {
+        return update;
+    }
+
+    if outfits.get_outfit_by_look_type(player.sex, outfit.look_type).is_none() {
+        outfit.look_mount = 0;
+    }
+    if outfit.look_mount != 0 {
+        let Some(mount) = mounts.get_mount_by_client_id(outfit.look_mount) else {
+            return update;
+        };
+        if !has_mount(player, mount, premium) {
+            return update;
+        }
+        if player.is_mounted() {
+            if let Some(previous) = mounts.get_mount_by_id(player.current_mount) {
+                player.creature.change_speed(mount.speed - previous.speed);
+                update.speed_changed = mount.speed != previous.speed;
+            }
+        } else {
+            // fönstret väljer bara hästen, spelaren stiger upp med Ctrl+R
+            outfit.look_mount = 0;
+        }
+        player.current_mount = mount.id;
+    } else if player.is_mounted() {
+        update.speed_changed = dismount(player, mounts);
+    }
+
+    if can_wear(player, outfits, outfit.look_type, outfit.look_addons, premium) {
+        player.creature.default_outfit = outfit;
+        if !player.creature.has_condition(ConditionType::OUTFIT) {
+            update.outfit = Some(outfit);
+        }
+    }
+    update
+}
+
+/// Motsvarar `Player::toggleMount`. `now` är i millisekunder.
+pub fn toggle_mount(
+    player: &mut Player,
+    outfits: &Outfits,
+    mounts: &Mounts,
+    mount: bool,
+    protection_zone: bool,
+    premium: bool,
+    now: u64,
+) -> ToggleMount {
+    if now.saturating_sub(player.last_toggle_mount) < MOUNT_EXHAUST && !player.was_mounted {
+        return ToggleMount::Cancel(ReturnValue::YouAreExhausted);
+    }
+
+    let mut speed_changed = false;
+    if mount {
+        if player.is_mounted() {
+            return ToggleMount::Unchanged;
+        }
+        if !player.group.access && protection_zone {
+            return ToggleMount::Cancel(ReturnValue::ActionNotPermittedInProtectionZone);
+        }
+        if outfits.get_outfit_by_look_type(player.sex, player.creature.default_outfit.look_type).is_none() {
+            return ToggleMount::Unchanged;
+        }
+        if player.current_mount == 0 {
+            return ToggleMount::OutfitWindow;
+        }
+        let Some(current) = mounts.get_mount_by_id(player.current_mount) else {
+            return ToggleMount::Unchanged;
+        };
+        if !has_mount(player, current, premium) {
+            player.current_mount = 0;
+            return ToggleMount::OutfitWindow;
+        }
+        if current.premium && !premium {
+            return ToggleMount::Cancel(ReturnValue::YouNeedPremiumAccount);
+        }
+        if player.creature.has_condition(ConditionType::OUTFIT) {
+            return ToggleMount::Cancel(ReturnValue::NotPossible);
+        }
+        player.creature.default_outfit.look_mount = current.client_id;
+        if current.speed != 0 {
+            player.creature.change_speed(current.speed);
+            speed_changed = true;
+        }
+    } else {
+        if !player.is_mounted() {
+            return ToggleMount::Unchanged;
+        }
+        speed_changed = dismount(player, mounts);
+    }
+
+    player.last_toggle_mount = now;
+    ToggleMount::Changed(OutfitUpdate { outfit: Some(player.creature.default_outfit), speed_changed })
+}
+
+/// Hästen i `Player::onChangeZone`: den försvinner i skyddszoner och
+/// kommer tillbaka när spelaren går ut. `now` är i millisekunder.
+pub fn on_change_zone(
+    player: &mut Player,
+    outfits: &Outfits,
+    mounts: &Mounts,
+    protection_zone: bool,
+    premium: bool,
+    now: u64,
+) -> ToggleMount {
+    if protection_zone {
+        if player.group.access || !player.is_mounted() {
+            return ToggleMount::Unchanged;
+        }
+        let speed_changed = dismount(player, mounts);
+        player.was_mounted = true;
+        return ToggleMount::Changed(OutfitUpdate { outfit: Some(player.creature.default_outfit), speed_changed });
+    }
+    if !player.was_mounted {
+        return ToggleMount::Unchanged;
+    }
+    let result = toggle_mount(player, outfits, mounts, true, false, premium, now);
+    player.was_mounted = false;
+    result
+}
+
+/// Hästens fart när spelaren loggar in på hästen, som i
+/// `Player::onCreatureAppear`. En häst spelaren inte längre har tas bort.
+/// True om farten ändrades.
+pub fn on_login(player: &mut Player, mounts: &Mounts, premium: bool) -> bool {
+    if !player.is_mounted() {
+        return false;
+    }
+    match mounts.get_mount_by_id(player.current_mount) {
+        Some(mount) if has_mount(player, mount, premium) => {
+            player.creature.change_speed(mount.speed);
+            mount.speed != 0
+        }
+        _ => {
+            player.creature.default_outfit.look_mount = 0;
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use entities::player::PlayerSex;
+
+    const CITIZEN: u16 = 128;
+    const NOBLEMAN: u16 = 132;
+    const PIRATE: u16 = 151;
+
+    fn outfits() -> Outfits {
+        // testerna körs parallellt, så varje tråd får en egen fil
+        let name = format!("outfits-test-{}-{:?}.xml", std::process::id(), std::thread::current().id());
+        let path = std::env::temp_dir().join(name);
+        std::fs::write(
+            &path,
+            r#"<outfits>
+                <outfit type="1" looktype="128" name="Citizen" premium="no" unlocked="yes" />
+                <outfit type="1" looktype="132" name="Nobleman" premium="yes" unlocked="yes" />
+                <outfit type="1" looktype="151" name="Pirate" premium="yes" unlocked="no" />
+            </outfits>"#,
+        )
+        .unwrap();
+        let outfits = Outfits::load_from_xml(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        outfits
+    }
+
+    fn player() -> Player {
+        let mut player = Player::new("Bob");
+        player.sex = PlayerSex::Male;
+        player
+    }
+
+    #[test]
+    fn addons_must_all_be_owned() {
+        let outfits = outfits();
+        let mut player = player();
+        assert!(can_wear(&player, &outfits, CITIZEN, 0, false));
+        assert!(!can_wear(&player, &outfits, CITIZEN, 1, false));
+
+        player.add_outfit(CITIZEN, 1);
+        assert!(can_wear(&player, &outfits, CITIZEN, 1, false));
+        assert!(!can_wear(&player, &outfits, CITIZEN, 2, false));
+        assert!(!can_wear(&player, &outfits, CITIZEN, 3, false));
+
+        player.add_outfit(CITIZEN, 2);
+        assert!(can_wear(&player, &outfits, CITIZEN, 3, false));
+        assert!(!can_wear(&player, &outfits, CITIZEN, 4, false));
+    }
+
+    #[test]
+    fn premium_and_locked_outfits() {
+        let outfits = outfits();
+        let mut player = player();
+        assert!(!can_wear(&player, &outfits, NOBLEMAN, 0, false));
+        assert!(can_wear(&player, &outfits, NOBLEMAN, 0, true));
+
+        assert!(!can_wear(&player, &outfits, PIRATE, 0, true));
+        player.add_outfit(PIRATE, 0);
+        assert!(can_wear(&player, &outfits, PIRATE, 0, true));
+        assert!(!can_wear(&player, &outfits, PIRATE, 0, false));
+        // finns inte för män
+        assert!(!can_wear(&player, &outfits, 136, 0, true));
+    }
+
+    #[test]
+    fn access_wears_anything_but_unknown_addons() {
+        let outfits = outfits();
+        let mut player = player();
+        player.group.access = true;
+        assert!(can_wear(&player, &outfits, PIRATE, 3, false));
+        assert!(!can_wear(&player, &outfits, PIRATE, 4, false));
+    }
+}
@@ -0,0 +1,196 @@
+//! Skallar och frags: vit skalle för den som anfaller en omärkt spelare,
+//! röd och svart för för många omotiverade mord. Fragen räknas i sekunder i
+//! `Player::skull_ticks` och minskar medan spelaren är inloggad. Motsvarar
+//! skalldelarna av `Player::onAttackedCreature`, `onKilledCreature`,
+//! `addUnjustifiedDead` och `checkSkullTicks` i TFS.
+
+use common::{Config, MessageClass};
+use entities::creature::Skull;
+use entities::{Condition, ConditionId, ConditionType, Player, PlayerFlags};
+
+use crate::combat::WorldType;
+
+#[derive(Debug, Clone)]
+pub struct SkullConfig {
+    pub world_type: WorldType,
+    /// Omotiverade mord för röd och svart skalle, 0 stänger av dem
+    pub kills_to_red_skull: i32,
+    pub kills_to_black_skull: i32,
+    /// Sekunder varje omotiverat mord ligger kvar
+    pub time_to_decrease_frags: i64,
+    /// Millisekunder spelaren är i strid efter ett mord
+    pub white_skull_time: i32,
+}
+
+impl Default for SkullConfig {
+    fn default() -> Self {
+        Self {
+            world_type: WorldType::Pvp,
+            kills_to_red_skull: 3,
+            kills_to_black_skull: 6,
+            time_to_decrease_frags: 24 * 60 * 60,
+            white_skull_time: 15 * 60 * 1000,
+        }
+    }
+}
+
+impl SkullConfig {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            world_type: WorldType::from_name(&config.world_type),
+            kills_to_red_skull: config.kills_to_red_skull,
+            kills_to_black_skull: config.kills_to_black_skull,
+            time_to_decrease_frags: config.time_to_decrease_frags as i64,
+            white_skull_time: config.white_skull_time.saturating_mul(1000),
+        }
+    }
+}
+
+/// Det anfallaren behöver veta om en spelare den anfallit eller dödat
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TargetPlayer {
+    pub id: u32,
+    pub name: String,
+    pub skull: Skull,
+    /// Målet har själv anfallit anfallaren, `Player::hasAttacked`
+    pub has_attacked: bool,
+    /// Deras gillen har krig med varandra
+    pub in_war: bool,
+}
+
+impl TargetPlayer {
+    pub fn new(target: &Player, attacker: u32, in_war: bool) -> Self {
+        Self {
+            id: target.creature.id,
+            name: target.creature.name.clone(),
+            skull: target.creature.skull,
+            has_attacked: target.has_attacked(attacker),
+            in_war,
+        }
+    }
+}
+
+/// Vem som ska få se anfallarens skalle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkullUpdate {
+    /// Skallen ändrades och alla runt omkring ska få den,
+    /// `Game::updateCreatureSkull`
+    Changed,
+    /// Bara målet ser den annorlunda nu, `sendCreatureSkull`
+    Target,
+}
+
+/// Anfallaren har anfallit en spelare utanför sitt party och gille,
+/// utanför pvp-zonerna. Den som anfaller först blir pz-låst och får vit
+/// skalle; den som slår tillbaka mot någon med gul skalle blir inte låst.
+/// Spelet ger in fight-conditionen efteråt. Motsvarar resten av
+/// `Player::onAttackedCreature`.
+pub fn on_attacked_player(attacker: &mut Player, target: &TargetPlayer, world_type: WorldType) -> Option<SkullUpdate> {
+    if world_type == WorldType::PvpEnforced {
+        attacker.pz_locked = true;
+    }
+
+    let skull = attacker.creature.skull;
+    // målet har gul skalle för anfallaren, `getSkullClient`
+    let yellow = world_type != WorldType::NoPvp && target.skull == Skull::None && !target.in_war && target.has_attacked;
+    if skull == Skull::None && yellow {
+        attacker.add_attacked(target.id);
+        return Some(SkullUpdate::Target);
+    }
+    if target.has_attacked {
+        return None;
+    }
+
+    attacker.pz_locked = true;
+    if target.in_war {
+        return None;
+    }
+    attacker.add_attacked(target.id);
+    if target.skull == Skull::None && skull == Skull::None {
+        attacker.creature.skull = Skull::White;
+        return Some(SkullUpdate::Changed);
+    }
+    (skull == Skull::None).then_some(SkullUpdate::Target)
+}
+
+/// Efter `on_killed_player`
+#[derive(Debug, Clone, PartialEq)]
+pub struct KilledPlayer {
+    /// Mordet var omotiverat, för `player_deaths` och onDeath
+    pub unjustified: bool,
+    /// Varningen mördaren ska få
+    pub message: Option<(MessageClass, String)>,
+    pub skull_changed: bool,
+}
+
+/// Mördaren dödade en spelare utanför en pvp-zon. Ett mord på någon utan
+/// skalle som inte anföll först är omotiverat och ger frags. Den som
+/// träffade sist stannar i strid i `white_skull_time`. `partner` är samma
+/// party eller gille. Motsvarar spelardelen av `Player::onKilledCreature`.
+pub fn on_killed_player(
+    killer: &mut Player,
+    target: &TargetPlayer,
+    partner: bool,
+    last_hit: bool,
+    config: &SkullConfig,
+) -> KilledPlayer {
+    let mut result = KilledPlayer { unjustified: false, message: None, skull_changed: false };
+    if killer.has_flag(PlayerFlags::NOT_GAIN_IN_FIGHT) || partner || target.id == killer.creature.id {
+        return result;
+    }
+    if !killer.has_attacked(target.id) || target.has_attacked {
+        return result;
+    }
+
+    if target.skull == Skull::None && !target.in_war {
+        result.unjustified = true;
+        add_unjustified_dead(killer, target, config, &mut result);
+    }
+    if last_hit && killer.creature.has_condition(ConditionType::INFIGHT) {
+        killer.pz_locked = true;
+        let condition = Condition::new(ConditionId::Default, ConditionType::INFIGHT, config.white_skull_time);
+        killer.creature.add_condition(condition);
+    }
+    result
+}
+
+/// Motsvarar `Player::addUnjustifiedDead`
+fn add_unjustified_dead(killer: &mut Player, target: &TargetPlayer, config: &SkullConfig, result: &mut KilledPlayer) {
+    if config.world_type == WorldType::PvpEnforced {
+        return;
+    }
+    let text = format!("Warning! The murder of {} was not justified.", target.name);
+    result.message = Some((MessageClass::EventAdvance, text));
+    killer.skull_ticks += config.time_to_decrease_frags;
+
+    let skull = killer.creature.skull;
+    if skull == Skull::Black {
+        return;
+    }
+    let frags_over = |kills: i32| kills != 0 && killer.skull_ticks > (kills as i64 - 1) * config.time_to_decrease_frags;
+    let new_skull = if frags_over(config.kills_to_black_skull) {
+        Skull::Black
+    } else if skull != Skull::Red && frags_over(config.kills_to_red_skull) {
+        Skull::Red
+    } else {
+        return;
+    };
+    killer.creature.skull = new_skull;
+    result.skull_changed = true;
+}
+
+/// Fragen minskar med `seconds`. Röd och svart skalle försvinner när de
+/// gått ut och spelaren inte är i strid. Returnerar true om skallen
+/// ändrades. Spelet anropar den varje sekund utom i pvp-enforced-världar.
+/// Motsvarar `Player::checkSkullTicks`.
+pub fn check_skull_ticks(player: &mut Player, seconds: i64) -> bool {
+    player.skull_ticks = (player.skull_ticks - seconds).max(0);
+    if matches!(player.creature.skull, Skull::Red | Skull::Black)
+        && player.skull_ticks < 1
+        && !player.creature.has_condition(ConditionType::INFIGHT)
+    {
+        player.creature.skull = Skull::None;
+        return true;
+    }
+    false
+}
@@ -0,0 +1,824 @@
+//! Besvärjelserna, motsvarar `Spell`, `InstantSpell`, `RuneSpell` och
+//! ordtolkningen i `Spells` i TFS.
+//!
+//! Här finns det som inte kräver Lua: uppgifterna från spells.xml (eller
+//! `Spell()` i scripten), vilka ord en text är och kontrollerna innan en
+//! spelare får kasta. Registret och scripten finns i `scripting::spells`.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use common::tracing::warn;
+use common::{Config, Error, Position, Result, ReturnValue};
+use entities::creature::Skull;
+use entities::{Condition, ConditionId, ConditionType, Player, PlayerFlags};
+use items::WeaponType;
+
+use crate::combat::{can_do_combat_tile, is_in_pvp_zone, CombatView, Combatant};
+use crate::condition;
+use crate::vocation::{Vocation, Vocations};
+
+/// Hur långt en besvärjelse utan `range` når, `Map::maxClientViewportX/Y`
+const MAX_VIEWPORT_X: i32 = 8;
+const MAX_VIEWPORT_Y: i32 = 6;
+
+/// Motsvarar `SpellGroup_t`. Nedkylningen för en grupp är en condition med
+/// gruppen som sub-id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[repr(u8)]
+pub enum SpellGroup {
+    #[default]
+    None = 0,
+    Attack = 1,
+    Healing = 2,
+    Support = 3,
+    Special = 4,
+    Conjure = 5,
+    Crippling = 6,
+    Focus = 7,
+    UltimateStrikes = 8,
+}
+
+impl SpellGroup {
+    pub fn from_u8(value: u8) -> Self {
+        match value {
+            1 => SpellGroup::Attack,
+            2 => SpellGroup::Healing,
+            3 => SpellGroup::Support,
+            4 => SpellGroup::Special,
+            5 => SpellGroup::Conjure,
+            6 => SpellGroup::Crippling,
+            7 => SpellGroup::Focus,
+            8 => SpellGroup::UltimateStrikes,
+            _ => SpellGroup::None,
+        }
+    }
+
+    /// Namnet eller siffran, motsvarar `stringToSpellGroup`
+    pub fn from_name(name: &str) -> Self {
+        match name.to_lowercase().as_str() {
+            "attack" | "1" => SpellGroup::Attack,
+            "healing" | "2" => SpellGroup::Healing,
+            "support" | "3" => SpellGroup::Support,
+            "special" | "4" => SpellGroup::Special,
+            "conjure" | "5" => SpellGroup::Conjure,
+            "crippling" | "6" => SpellGroup::Crippling,
+            "focus" | "7" => SpellGroup::Focus,
+            "ultimatestrikes" | "8" => SpellGroup::UltimateStrikes,
+            _ => SpellGroup::None,
+        }
+    }
+}
+
+/// Motsvarar `SpellType_t`, `SPELL_*` i scripten
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum SpellType {
+    Undefined = 0,
+    Instant = 1,
+    Rune = 2,
+}
+
+/// Inställningarna i config.lua som rör besvärjelser
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpellConfig {
+    /// `emoteSpells`: orden sägs som monstertal i stället för vanligt tal
+    pub emote_spells: bool,
+    /// `removeChargesFromRunes`
+    pub remove_charges_from_runes: bool,
+    pub free_premium: bool,
+    /// Millisekunder i strid efter en aggressiv besvärjelse
+    pub pz_locked: i32,
+}
+
+impl Default for SpellConfig {
+    fn default() -> Self {
+        Self { emote_spells: false, remove_charges_from_runes: true, free_premium: false, pz_locked: 60000 }
+    }
+}
+
+impl SpellConfig {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            emote_spells: config.emote_spells,
+            remove_charges_from_runes: config.remove_charges_from_runes,
+            free_premium: config.free_premium,
+            pz_locked: config.pz_locked,
+        }
+    }
+}
+
+/// Spelarflaggorna som rör besvärjelser, från gruppen (`PlayerFlag_*`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SpellFlags {
+    pub cannot_use_spells: bool,
+    pub ignore_spell_check: bool,
+    pub has_infinite_mana: bool,
+    pub has_infinite_soul: bool,
+    pub has_no_exhaustion: bool,
+}
+
+impl SpellFlags {
+    pub fn from_player(player: &Player) -> Self {
+        Self {
+            cannot_use_spells: player.has_flag(PlayerFlags::CANNOT_USE_SPELLS),
+            ignore_spell_check: player.has_flag(PlayerFlags::IGNORE_SPELL_CHECK),
+            has_infinite_mana: player.has_flag(PlayerFlags::HAS_INFINITE_MANA),
+            has_infinite_soul: player.has_flag(PlayerFlags::HAS_INFINITE_SOUL),
+            has_no_exhaustion: player.has_flag(PlayerFlags::HAS_NO_EXHAUSTION),
+        }
+    }
+}
+
+/// Spelaren som kastar, med det som inte finns på `Player`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpellCaster {
+    pub combatant: Combatant,
+    pub premium: bool,
+    /// Spelaren står i en skyddszon
+    pub in_protection_zone: bool,
+    pub flags: SpellFlags,
+}
+
+impl SpellCaster {
+    pub fn new(
+        player: &Player,
+        vocation: Option<&Vocation>,
+        config: &SpellConfig,
+        in_protection_zone: bool,
+        now: u64,
+    ) -> Self {
+        Self {
+            combatant: Combatant::from_player(player, vocation, now),
+            premium: player.is_premium(config.free_premium, now),
+            in_protection_zone,
+            flags: SpellFlags::from_player(player),
+        }
+    }
+}
+
+/// Det alla besvärjelser har, motsvarar `Spell`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spell {
+    pub name: String,
+    pub id: u8,
+    pub group: SpellGroup,
+    pub secondary_group: SpellGroup,
+    /// Millisekunder, som nedkylningarnas conditions
+    pub cooldown: u32,
+    pub group_cooldown: u32,
+    pub secondary_group_cooldown: u32,
+    pub level: u32,
+    pub magic_level: u32,
+    pub mana: u32,
+    /// Procent av maxmana, om `mana` är 0
+    pub mana_percent: u32,
+    pub soul: u32,
+    /// -1 är så långt spelaren ser
+    pub range: i32,
+    pub need_target: bool,
+    pub need_weapon: bool,
+    pub self_target: bool,
+    pub blocking_solid: bool,
+    pub blocking_creature: bool,
+    pub aggressive: bool,
+    pub pz_lock: bool,
+    /// Måste läras (`player_spells`) i stället för att bero på yrket
+    pub learnable: bool,
+    pub enabled: bool,
+    pub premium: bool,
+    /// `blockwalls`: kräver fri sikt till målet
+    pub check_line_of_sight: bool,
+    /// Yrkena som får kasta den och om den visas i deras beskrivning; tom
+    /// betyder alla. Motsvarar `vocSpellMap`.
+    pub vocations: BTreeMap<u16, bool>,
+}
+
+impl Default for Spell {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            id: 0,
+            group: SpellGroup::None,
+            secondary_group: SpellGroup::None,
+            cooldown: 0,
+            group_cooldown: 0,
+            secondary_group_cooldown: 0,
+            level: 0,
+            magic_level: 0,
+            mana: 0,
+            mana_percent: 0,
+            soul: 0,
+            range: -1,
+            need_target: false,
+            need_weapon: false,
+            self_target: false,
+            blocking_solid: false,
+            blocking_creature: false,
+            aggressive: true,
+            pz_lock: false,
+            learnable: false,
+            enabled: true,
+            premium: false,
+            check_line_of_sight: true,
+            vocations: BTreeMap::new(),
+        }
+    }
+}
+
+impl Spell {
+    /// Motsvarar `Spell::getManaCost`
+    pub fn mana_cost(&self, player: &Player) -> u32 {
+        if self.mana != 0 {
+            return self.mana;
+        }
+        if self.mana_percent != 0 {
+            return (player.creature.mana_max as u64 * self.mana_percent as u64 / 100) as u32;
+        }
+        0
+    }
+
+    pub fn has_vocation(&self, vocation: u16) -> bool {
+        self.vocations.is_empty() || self.vocations.contains_key(&vocation)
+    }
+
+    /// `vocation` ur spells.xml eller `spell:vocation("sorcerer;true")`: namnet
+    /// och om besvärjelsen visas i yrkets beskrivning
+    pub fn add_vocation(&mut self, vocations: &Vocations, name: &str, show_in_description: bool) -> bool {
+        match vocations.get_vocation_id(name) {
+            Some(id) => {
+                self.vocations.insert(id, show_in_description);
+                true
+            }
+            None => {
+                warn!("[Spell::addVocMap] Wrong vocation name: {name}");
+                false
+            }
+        }
+    }
+
+    /// Nedkylningarna efter ett kast, motsvarar början av `Spell::postCastSpell`
+    pub fn cooldown_conditions(&self) -> Vec<Condition> {
+        let mut conditions = Vec::new();
+        let mut add = |condition_type, ticks: u32, sub_id: u32| {
+            if ticks > 0 {
+                conditions.extend(condition::create_condition(
+                    ConditionId::Default,
+                    condition_type,
+                    ticks as i32,
+                    0,
+                    false,
+                    sub_id,
+                ));
+            }
+        };
+        add(ConditionType::SPELLCOOLDOWN, self.cooldown, self.id as u32);
+        add(ConditionType::SPELLGROUPCOOLDOWN, self.group_cooldown, self.group as u32);
+        add(ConditionType::SPELLGROUPCOOLDOWN, self.secondary_group_cooldown, self.secondary_group as u32);
+        conditions
+    }
+}
+
+/// En besvärjelse som sägs, motsvarar `InstantSpell`
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct InstantSpell {
+    pub spell: Spell,
+    pub words: String,
+    /// Orden följs av en parameter, `utevo res "rat"`
+    pub has_param: bool,
+    /// Parametern är ett spelarnamn, med `~` som jokertecken
+    pub has_player_name_param: bool,
+    /// Riktas mot rutan framför spelaren
+    pub need_direction: bool,
+    /// Mot målet om spelaren har ett, annars rutan framför
+    pub caster_target_or_direction: bool,
+}
+
+impl InstantSpell {
+    /// Får spelaren kasta den alls, utan att se på nivå, mana och
+    /// nedkylning. Motsvarar `InstantSpell::canCast`.
+    pub fn can_cast(&self, player: &Player, flags: &SpellFlags) -> bool {
+        if flags.cannot_use_spells {
+            return false;
+        }
+        if flags.ignore_spell_check {
+            return true;
+        }
+        if self.spell.learnable {
+            player.has_learned_instant_spell(&self.spell.name)
+        } else {
+            self.spell.has_vocation(player.vocation)
+        }
+    }
+
+    /// Motsvarar `luaPlayerCanLearnSpell`
+    pub fn can_learn(&self, player: &Player, flags: &SpellFlags) -> bool {
+        if flags.ignore_spell_check {
+            return true;
+        }
+        self.spell.vocations.contains_key(&player.vocation)
+            && player.level >= self.spell.level
+            && player.magic_level() >= self.spell.magic_level
+    }
+
+    /// Det spelaren säger efter ett lyckat kast, med parametern inom citattecken
+    pub fn spoken_words(&self, param: &str) -> String {
+        if self.has_param && !param.is_empty() {
+            format!("{} \"{param}\"", self.words)
+        } else {
+            self.words.clone()
+        }
+    }
+}
+
+/// En besvärjelse i en rune, motsvarar `RuneSpell`
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RuneSpell {
+    pub spell: Spell,
+    pub rune_id: u16,
+    /// Laddningar en ny rune får, 0 om den inte tar slut
+    pub charges: u32,
+    pub allow_far_use: bool,
+    pub check_floor: bool,
+}
+
+impl RuneSpell {
+    pub fn has_charges(&self) -> bool {
+        self.charges > 0
+    }
+}
+
+/// En instant eller en rune, som i registret
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpellKind {
+    Instant(InstantSpell),
+    Rune(RuneSpell),
+}
+
+impl SpellKind {
+    pub fn new(spell_type: SpellType) -> Option<Self> {
+        match spell_type {
+            SpellType::Instant => Some(SpellKind::Instant(InstantSpell::default())),
+            SpellType::Rune => Some(SpellKind::Rune(RuneSpell::default())),
+            SpellType::Undefined => None,
+        }
+    }
+
+    pub fn spell(&self) -> &Spell {
+        match self {
+            SpellKind::Instant(instant) => &instant.spell,
+            SpellKind::Rune(rune) => &rune.spell,
+        }
+    }
+
+    pub fn spell_mut(&mut self) -> &mut Spell {
+        match self {
+            SpellKind::Instant(instant) => &mut instant.spell,
+            SpellKind::Rune(rune) => &mut rune.spell,
+        }
+    }
+
+    pub fn spell_type(&self) -> SpellType {
+        match self {
+            SpellKind::Instant(_) => SpellType::Instant,
+            SpellKind::Rune(_) => SpellType::Rune,
+        }
+    }
+}
+
+/// En besvärjelse ur spells.xml och scriptet i `scripts/` den kör
+#[derive(Debug, Clone, PartialEq)]
+pub struct XmlSpell {
+    pub spell: SpellKind,
+    pub script: String,
+}
+
+/// Läs spells.xml. Motsvarar `Spells::registerEvent` med
+/// `Spell::configureSpell` och underklassernas `configureEvent`.
+pub fn load_from_xml(path: impl AsRef<Path>, vocations: &Vocations) -> Result<Vec<XmlSpell>> {
+    let path = path.as_ref();
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| Error::World(format!("Cannot open {}: {e}", path.display())))?;
+    let doc = roxmltree::Document::parse(&text)
+        .map_err(|e| Error::World(format!("Cannot parse {}: {e}", path.display())))?;
+
+    let mut spells = Vec::new();
+    for node in doc.root_element().children().filter(|n| n.is_element()) {
+        let spell_type = match node.tag_name().name() {
+            "instant" => SpellType::Instant,
+            "rune" => SpellType::Rune,
+            _ => continue,
+        };
+        let Some(script) = attr_str(&node, "script") else {
+            warn!("[Spells::registerEvent] Missing script for spell: {:?}", attr_str(&node, "name"));
+            continue;
+        };
+        let Some(spell) = configure_spell(&node, spell_type, vocations) else {
+            continue;
+        };
+        spells.push(XmlSpell { spell, script: script.to_string() });
+    }
+    Ok(spells)
+}
+
+fn configure_spell(node: &roxmltree::Node, spell_type: SpellType, vocations: &Vocations) -> Option<SpellKind> {
+    let Some(name) = attr_str(node, "name") else {
+        warn!("[Spell::configureSpell] Spell without name");
+        return None;
+    };
+    let mut kind = SpellKind::new(spell_type)?;
+    let spell = kind.spell_mut();
+    spell.name = name.to_string();
+
+    if let Some(id) = attr(node, "spellid") {
+        spell.id = id;
+    }
+    if let Some(group) = attr_str(node, "group") {
+        spell.group = SpellGroup::from_name(group);
+        if spell.group == SpellGroup::None {
+            warn!("[Spell::configureSpell] Unknown group: {group}");
+        }
+    }
+    if let Some(value) = attr(node, "groupcooldown") {
+        spell.group_cooldown = value;
+    }
+    if let Some(group) = attr_str(node, "secondarygroup") {
+        spell.secondary_group = SpellGroup::from_name(group);
+        if spell.secondary_group == SpellGroup::None {
+            warn!("[Spell::configureSpell] Unknown secondarygroup: {group}");
+        }
+    }
+    if let Some(value) = attr(node, "secondarygroupcooldown") {
+        spell.secondary_group_cooldown = value;
+    }
+    if let Some(value) = attr(node, "level").or_else(|| attr(node, "lvl")) {
+        spell.level = value;
+    }
+    if let Some(value) = attr(node, "magiclevel").or_else(|| attr(node, "maglv")) {
+        spell.magic_level = value;
+    }
+    if let Some(value) = attr(node, "mana") {
+        spell.mana = value;
+    }
+    if let Some(value) = attr(node, "manapercent") {
+        spell.mana_percent = value;
+    }
+    if let Some(value) = attr(node, "soul") {
+        spell.soul = value;
+    }
+    if let Some(value) = attr(node, "range") {
+        spell.range = value;
+    }
+    if let Some(value) = attr(node, "cooldown").or_else(|| attr(node, "exhaustion")) {
+        spell.cooldown = value;
+    }
+    if let Some(value) = attr_bool(node, "premium").or_else(|| attr_bool(node, "prem")) {
+        spell.premium = value;
+    }
+    if let Some(value) = attr_bool(node, "enabled") {
+        spell.enabled = value;
+    }
+    if let Some(value) = attr_bool(node, "needtarget") {
+        spell.need_target = value;
+    }
+    if let Some(value) = attr_bool(node, "needweapon") {
+        spell.need_weapon = value;
+    }
+    if let Some(value) = attr_bool(node, "selftarget") {
+        spell.self_target = value;
+    }
+    if let Some(value) = attr_bool(node, "needlearn") {
+        spell.learnable = value;
+    }
+    if let Some(value) = attr_bool(node, "blocking") {
+        spell.blocking_solid = value;
+        spell.blocking_creature = value;
+    }
+    if let Some(block_type) = attr_str(node, "blocktype") {
+        match block_type.to_lowercase().as_str() {
+            "all" => {
+                spell.blocking_solid = true;
+                spell.blocking_creature = true;
+            }
+            "solid" => spell.blocking_solid = true,
+            "creature" => spell.blocking_creature = true,
+            _ => warn!("[Spell::configureSpell] Blocktype \"{block_type}\" does not exist."),
+        }
+    }
+    if let Some(value) = attr_bool(node, "aggressive") {
+        spell.aggressive = value;
+    }
+    if let Some(value) = attr_bool(node, "pzlock") {
+        spell.pz_lock = value;
+    }
+    if let Some(value) = attr_bool(node, "blockwalls") {
+        spell.check_line_of_sight = value;
+    }
+
+    for child in node.children().filter(|n| n.has_tag_name("vocation")) {
+        let Some(name) = attr_str(&child, "name") else {
+            continue;
+        };
+        let show = attr_bool(&child, "showInDescription").unwrap_or(true);
+        spell.add_vocation(vocations, name, show);
+    }
+
+    match &mut kind {
+        SpellKind::Instant(instant) => {
+            let Some(words) = attr_str(node, "words") else {
+                warn!("[InstantSpell::configureEvent] Instant spell without words: {name}");
+                return None;
+            };
+            instant.words = words.to_string();
+            if let Some(value) = attr_bool(node, "params") {
+                instant.has_param = value;
+            }
+            if let Some(value) = attr_bool(node, "playernameparam") {
+                instant.has_player_name_param = value;
+            }
+            if let Some(value) = attr_bool(node, "direction") {
+                instant.need_direction = value;
+            }
+            if let Some(value) = attr_bool(node, "casterTargetOrDirection") {
+                instant.caster_target_or_direction = value;
+            }
+        }
+        SpellKind::Rune(rune) => {
+            let Some(rune_id) = attr(node, "id") else {
+                warn!("[RuneSpell::configureEvent] Rune spell without id: {name}");
+                return None;
+            };
+            rune.rune_id = rune_id;
+            if let Some(value) = attr(node, "charges") {
+                rune.charges = value;
+            }
+            if let Some(value) = attr_bool(node, "allowfaruse") {
+                rune.allow_far_use = value;
+            }
+            if let Some(value) = attr_bool(node, "checkfloor") {
+                rune.check_floor = value;
+            }
+        }
+    }
+    Some(kind)
+}
+
+/// Besvärjelsen vars ord texten börjar med, de längsta orden om flera
+/// passar. Efter orden får bara komma ett mellanslag och en parameter om
+/// besvärjelsen tar en. `spells` är orden, `has_param` och det som ska
+/// returneras. Motsvarar `Spells::getInstantSpell`.
+pub fn get_instant_spell<'a, T>(spells: impl IntoIterator<Item = (&'a str, bool, T)>, text: &str) -> Option<T> {
+    let mut result: Option<(usize, bool, T)> = None;
+    for (words, has_param, value) in spells {
+        let len = words.len();
+        let matches = text.get(..len).is_some_and(|prefix| prefix.eq_ignore_ascii_case(words));
+        if matches && result.as_ref().is_none_or(|(best, _, _)| len > *best) {
+            let exact = text.len() == len;
+            result = Some((len, has_param, value));
+            if exact {
+                break;
+            }
+        }
+    }
+
+    let (len, has_param, value) = result?;
+    if text.len() > len {
+        if !has_param {
+            return None;
+        }
+        if text.len() - len < 2 || text.as_bytes()[len] != b' ' {
+            return None;
+        }
+    }
+    Some(value)
+}
+
+/// Parametern efter orden: texten inom citattecken eller ett ensamt ord.
+/// `None` om texten inte går att tolka, som ett ord för mycket eller något
+/// efter det avslutande citattecknet. Motsvarar delen av
+/// `Spells::playerSaySpell` som läser parametern.
+pub fn parse_param(text: &str, words_len: usize) -> Option<String> {
+    let param_text = text.get(words_len..).unwrap_or("");
+    if !param_text.starts_with(' ') {
+        return Some(String::new());
+    }
+    if let Some(open) = param_text[1..].find('"').map(|i| i + 1) {
+        let close = match param_text[open + 1..].find('"') {
+            Some(i) => {
+                let close = open + 1 + i;
+                if param_text.trim_end_matches(' ').len() != close + 1 {
+                    return None;
+                }
+                close
+            }
+            None => param_text.len(),
+        };
+        return Some(param_text[open + 1..close].to_string());
+    }
+    let param = param_text.trim();
+    if param.contains(' ') {
+        return None;
+    }
+    Some(param.to_string())
+}
+
+fn has_condition(player: &Player, condition_type: ConditionType, sub_id: u32) -> bool {
+    player
+        .creature
+        .conditions()
+        .iter()
+        .any(|c| c.condition_type == condition_type && c.sub_id == sub_id)
+}
+
+/// Kontrollerna varje kast gör, motsvarar `Spell::playerSpellCheck`.
+/// `NotPossible` när spelaren inte får kasta alls eller besvärjelsen är avstängd.
+pub fn player_spell_check(spell: &Spell, instant: bool, player: &Player, caster: &SpellCaster) -> ReturnValue {
+    if caster.flags.cannot_use_spells {
+        return ReturnValue::NotPossible;
+    }
+    if caster.flags.ignore_spell_check {
+        return ReturnValue::NoError;
+    }
+    if !spell.enabled {
+        return ReturnValue::NotPossible;
+    }
+
+    if spell.aggressive || spell.pz_lock {
+        let has_target = spell.range > 0 && player.creature.attacked_creature.is_some();
+        if !has_target && player.creature.skull == Skull::Black {
+            return ReturnValue::NotPossible;
+        }
+        if caster.in_protection_zone {
+            return ReturnValue::ActionNotPermittedInProtectionZone;
+        }
+    }
+
+    if has_condition(player, ConditionType::SPELLGROUPCOOLDOWN, spell.group as u32)
+        || has_condition(player, ConditionType::SPELLCOOLDOWN, spell.id as u32)
+        || (spell.secondary_group != SpellGroup::None
+            && has_condition(player, ConditionType::SPELLGROUPCOOLDOWN, spell.secondary_group as u32))
+    {
+        return ReturnValue::YouAreExhausted;
+    }
+
+    if player.level < spell.level {
+        return ReturnValue::NotEnoughLevel;
+    }
+    if player.magic_level() < spell.magic_level {
+        return ReturnValue::NotEnoughMagicLevel;
+    }
+    if player.creature.mana < spell.mana_cost(player) && !caster.flags.has_infinite_mana {
+        return ReturnValue::NotEnoughMana;
+    }
+    if (player.soul as u32) < spell.soul && !caster.flags.has_infinite_soul {
+        return ReturnValue::NotEnoughSoul;
+    }
+
+    if instant && spell.learnable {
+        if !player.has_learned_instant_spell(&spell.name) {
+            return ReturnValue::YouNeedToLearnThisSpell;
+        }
+    } else if !spell.has_vocation(player.vocation) {
+        return ReturnValue::YourVocationCannotUseThisSpell;
+    }
+
+    if spell.need_weapon {
+        let (_, weapon) = player.get_shield_and_weapon();
+        let weapon_type = weapon.map_or(WeaponType::None, |weapon| weapon.weapon_type());
+        if !matches!(weapon_type, WeaponType::Sword | WeaponType::Club | WeaponType::Axe) {
+            return ReturnValue::YouNeedAWeaponToUseThisSpell;
+        }
+    }
+
+    if spell.premium && !caster.premium {
+        return ReturnValue::YouNeedPremiumAccount;
+    }
+    ReturnValue::NoError
+}
+
+/// Motsvarar `Map::canThrowObjectTo` för besvärjelser
+fn can_throw_object_to(view: &dyn CombatView, from: Position, to: Position, check_line_of_sight: bool, range: i32) -> bool {
+    let (range_x, range_y) = if range == -1 { (MAX_VIEWPORT_X, MAX_VIEWPORT_Y) } else { (range, range) };
+    if Position::get_distance_x(&from, &to) > range_x || Position::get_distance_y(&from, &to) > range_y {
+        return false;
+    }
+    !check_line_of_sight || view.is_sight_clear(from, to, true)
+}
+
+/// Når besvärjelsen målet, motsvarar `Spell::canThrowSpell`
+pub fn can_throw_spell(spell: &Spell, view: &dyn CombatView, from: Position, to: Position) -> bool {
+    from.z == to.z && can_throw_object_to(view, from, to, spell.check_line_of_sight, spell.range)
+}
+
+/// Rutan en instant riktas mot, motsvarar `InstantSpell::playerInstantSpellCheck`
+pub fn instant_spell_check(spell: &Spell, caster: &SpellCaster, view: &dyn CombatView, to: Position) -> ReturnValue {
+    let from = caster.combatant.position;
+    if from.z > to.z {
+        return ReturnValue::FirstGoUpstairs;
+    }
+    if from.z < to.z {
+        return ReturnValue::FirstGoDownstairs;
+    }
+    let ret = can_do_combat_tile(view, Some(&caster.combatant), to, spell.aggressive);
+    if ret != ReturnValue::NoError {
+        return ret;
+    }
+    if spell.blocking_creature && !view.creatures_at(to).is_empty() {
+        return ReturnValue::NotEnoughRoom;
+    }
+    if spell.blocking_solid && view.tile(to).block_solid {
+        return ReturnValue::NotEnoughRoom;
+    }
+    ReturnValue::NoError
+}
+
+/// Varelsen längst ner på rutan, den en rune träffar.
+/// Motsvarar `Tile::getBottomVisibleCreature`.
+pub fn rune_target(view: &dyn CombatView, pos: Position) -> Option<u32> {
+    view.creatures_at(pos).last().copied()
+}
+
+/// Rutan en rune används på, efter `player_spell_check`. Motsvarar resten
+/// av `RuneSpell::playerRuneSpellCheck`.
+pub fn rune_spell_check(rune: &RuneSpell, caster: &SpellCaster, view: &dyn CombatView, to: Position) -> ReturnValue {
+    let spell = &rune.spell;
+    let from = caster.combatant.position;
+    if from.z > to.z {
+        return ReturnValue::FirstGoUpstairs;
+    }
+    if from.z < to.z {
+        return ReturnValue::FirstGoDownstairs;
+    }
+    if spell.range != -1 && !can_throw_object_to(view, from, to, spell.check_line_of_sight, spell.range) {
+        return ReturnValue::DestinationOutOfReach;
+    }
+    let ret = can_do_combat_tile(view, Some(&caster.combatant), to, spell.aggressive);
+    if ret != ReturnValue::NoError {
+        return ret;
+    }
+
+    let target = rune_target(view, to);
+    if (spell.blocking_creature && target.is_some()) || (spell.blocking_solid && view.tile(to).block_solid) {
+        return ReturnValue::NotEnoughRoom;
+    }
+    if spell.need_target && target.is_none() {
+        return ReturnValue::CanOnlyUseThisRuneOnCreatures;
+    }
+
+    if spell.aggressive && spell.need_target && caster.combatant.secure_mode {
+        if let Some(target) = target.and_then(|id| view.combatant(id)) {
+            if target.is_player()
+                && target.id != caster.combatant.id
+                && view.skull_client(caster.combatant.id, target.id) == Skull::None
+                && !is_in_pvp_zone(view, &caster.combatant, &target)
+            {
+                return ReturnValue::TurnSecureModeToAttackUnmarkedPlayers;
+            }
+        }
+    }
+    ReturnValue::NoError
+}
+
+/// Efter ett kast: tid i strid och kostnaden. Nedkylningarna från
+/// `cooldown_conditions` lägger spelet på själv om inte spelaren har
+/// `has_no_exhaustion`. Returnerar manan spelaren använt, som räknas mot
+/// magisk nivå. Motsvarar `Spell::postCastSpell`.
+pub fn post_cast_spell(
+    spell: &Spell,
+    player: &mut Player,
+    caster: &SpellCaster,
+    config: &SpellConfig,
+    finished_cast: bool,
+    pay_cost: bool,
+) -> u32 {
+    if finished_cast && spell.aggressive {
+        player.add_in_fight_ticks(false, config.pz_locked);
+    }
+    if !pay_cost {
+        return 0;
+    }
+
+    let mana_cost = spell.mana_cost(player);
+    if mana_cost > 0 {
+        player.creature.mana = player.creature.mana.saturating_sub(mana_cost);
+    }
+    if !caster.flags.has_infinite_soul && spell.soul > 0 {
+        player.soul = player.soul.saturating_sub(spell.soul.min(u8::MAX as u32) as u8);
+    }
+    mana_cost
+}
+
+fn attr_str<'a>(node: &roxmltree::Node<'a, '_>, name: &str) -> Option<&'a str> {
+    node.attributes()
+        .find(|a| a.name().eq_ignore_ascii_case(name))
+        .map(|a| a.value())
+}
+
+fn attr<T: std::str::FromStr>(node: &roxmltree::Node, name: &str) -> Option<T> {
+    attr_str(node, name).and_then(|v| v.trim().parse().ok())
+}
+
+/// Som pugixmls `as_bool`: sant om värdet börjar med 1, t, T, y eller Y
+fn attr_bool(node: &roxmltree::Node, name: &str) -> Option<bool> {
+    attr_str(node, name).map(|v| matches!(v.trim().chars().next(), Some('1' | 't' | 'T' | 'y' | 'Y')))
+}
@@ -0,0 +1,148 @@
+//! Stamina och offline-träning. Som i TFS sköts det mesta av scripten:
+//! `Player:onGainExperience` i data/events förbrukar staminan och ger bonus
+//! eller straff, regeneratestamina.lua och offlinetraining.lua räknar ut
+//! det spelaren fått medan den var utloggad. Tiden räknas från
+//! `Player::last_logout`, som `IOPlayer::save_on_logout` sätter. Här
+//! finns gränserna för värdena och `Player::addOfflineTrainingTries`.
+
+use entities::Player;
+
+use crate::experience::{self, Advance, AdvanceSkill};
+use crate::vocation::Vocation;
+
+/// Full stamina i minuter, de sista 120 ger bonus
+pub const MAX_STAMINA_MINUTES: u16 = 2520;
+
+/// Mest offline-träning en spelare kan ha sparad, i millisekunder
+pub const MAX_OFFLINE_TRAINING_TIME: i32 = 12 * 3600 * 1000;
+
+/// Motsvarar `Player:setStamina`
+pub fn set_stamina(player: &mut Player, minutes: u16) {
+    player.stamina_minutes = minutes.min(MAX_STAMINA_MINUTES);
+}
+
+/// Motsvarar `Player::addOfflineTrainingTime`
+pub fn add_offline_training_time(player: &mut Player, time: i32) {
+    player.offline_training_time = player.offline_training_time.saturating_add(time).min(MAX_OFFLINE_TRAINING_TIME);
+}
+
+/// Motsvarar `Player::removeOfflineTrainingTime`
+pub fn remove_offline_training_time(player: &mut Player, time: i32) {
+    player.offline_training_time = player.offline_training_time.saturating_sub(time).max(0);
+}
+
+/// Tries för nästa nivå och det spelaren har mot den, None om skillen
+/// inte kan gå upp mer
+fn next_requirement(player: &Player, vocation: &Vocation, skill: AdvanceSkill) -> Option<(u64, u64)> {
+    let (curr_req, next_req, tries) = match skill {
+        AdvanceSkill::Skill(skill) => {
+            let value = player.skill(skill);
+            let curr_req = vocation.get_req_skill_tries(skill, value.level);
+            (curr_req, vocation.get_req_skill_tries(skill, value.level + 1), value.tries)
+        }
+        AdvanceSkill::MagicLevel => {
+            let curr_req = vocation.get_req_mana(player.mag_level);
+            (curr_req, vocation.get_req_mana(player.mag_level + 1), player.mana_spent)
+        }
+        AdvanceSkill::Level => return None,
+    };
+    (curr_req < next_req).then_some((next_req, tries))
+}
+
+fn skill_level(player: &Player, skill: AdvanceSkill) -> u32 {
+    match skill {
+        AdvanceSkill::Skill(skill) => player.skill(skill).level as u32,
+        AdvanceSkill::MagicLevel => player.mag_level,
+        AdvanceSkill::Level => player.level,
+    }
+}
+
+/// Procent mot nästa nivå med decimaler, för meddelandet
+fn progress(player: &Player, vocation: &Vocation, skill: AdvanceSkill) -> f64 {
+    next_requirement(player, vocation, skill).map_or(0.0, |(next_req, tries)| tries as f64 * 100.0 / next_req as f64)
+}
+
+/// Motsvarar `getSkillName`
+fn skill_name(skill: AdvanceSkill) -> &'static str {
+    match skill {
+        AdvanceSkill::Skill(skill) => skill.name(),
+        AdvanceSkill::MagicLevel => "magic level",
+        AdvanceSkill::Level => "level",
+    }
+}
+
+/// Namnet med stor bokstav i varje ord, som `ucwords`
+fn skill_title(skill: AdvanceSkill) -> String {
+    skill_name(skill)
+        .split(' ')
+        .map(|word| {
+            let mut chars = word.chars();
+            chars.next().map_or_else(String::new, |first| first.to_uppercase().chain(chars).collect())
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Skillen går att träna, annars körs inte `onGainSkillTries`
+pub fn can_train_offline(player: &Player, vocation: &Vocation, skill: AdvanceSkill) -> bool {
+    next_requirement(player, vocation, skill).is_some()
+}
+
+/// Efter `add_offline_training_tries`
+#[derive(Debug, Clone, PartialEq)]
+pub struct OfflineTraining {
+    /// En per nivå, för `onAdvance`
+    pub advances: Vec<Advance>,
+    /// Nivån eller procenten ändrades och spelaren ska få sina skills
+    pub updated: bool,
+    /// `MESSAGE_EVENT_ADVANCE` i ordning: den nya nivån om den ändrades,
+    /// sedan hur skillen ändrades
+    pub messages: Vec<String>,
+}
+
+/// Lägg till tries som redan gått genom `onGainSkillTries`, med
+/// meddelandena offlinetraining.lua förväntar sig. Motsvarar resten av
+/// `Player::addOfflineTrainingTries`.
+pub fn add_offline_training_tries(
+    player: &mut Player,
+    vocation: &Vocation,
+    skill: AdvanceSkill,
+    tries: u64,
+) -> OfflineTraining {
+    let old_level = skill_level(player, skill);
+    let old_progress = progress(player, vocation, skill);
+    let percent = |player: &Player| match skill {
+        AdvanceSkill::Skill(skill) => player.skill(skill).percent,
+        _ => player.mag_level_percent,
+    };
+    let old_percent = percent(player);
+
+    let advances = match skill {
+        AdvanceSkill::Skill(skill) => experience::add_skill_advance(player, vocation, skill, tries),
+        AdvanceSkill::MagicLevel => experience::add_mana_spent(player, vocation, tries),
+        AdvanceSkill::Level => Vec::new(),
+    };
+    if skill == AdvanceSkill::MagicLevel && !can_train_offline(player, vocation, skill) {
+        player.mag_level_percent = 0;
+    }
+
+    let new_level = skill_level(player, skill);
+    let mut messages = Vec::new();
+    if new_level != old_level {
+        messages.push(match skill {
+            AdvanceSkill::MagicLevel => format!("You advanced to magic level {new_level}."),
+            _ => format!("You advanced to {} level {new_level}.", skill_name(skill)),
+        });
+    }
+    messages.push(format!(
+        "Your {} skill changed from level {old_level} (with {old_progress:.2}% progress towards level {}) to level \
+         {new_level} (with {:.2}% progress towards level {})",
+        skill_title(skill),
+        old_level + 1,
+        progress(player, vocation, skill),
+        new_level + 1,
+    ));
+
+    let updated = !advances.is_empty() || percent(player) != old_percent;
+    OfflineTraining { advances, updated, messages }
+}
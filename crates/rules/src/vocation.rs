@@ -0,0 +1,280 @@
+//! Yrkena från data/XML/vocations.xml, motsvarar `Vocation` och `Vocations`
+//! i TFS. Spelaren har bara yrkets id (`Player::vocation`); det som beror
+//! på yrket (regenerering, kapacitet per nivå, skill- och manakurvor,
+//! attackhastighet) slås upp här.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use common::tracing::warn;
+use common::{Error, Result};
+use entities::Skill;
+
+pub const VOCATION_NONE: u16 = 0;
+
+/// Tries för första nivån över tio, per skill. Motsvarar `Vocation::skillBase`.
+const SKILL_BASE: [u64; 7] = [50, 50, 50, 50, 30, 100, 20];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Vocation {
+    pub id: u16,
+    /// Yrket klienten visar
+    pub client_id: u8,
+    pub name: String,
+    pub description: String,
+    pub allow_pvp: bool,
+
+    /// I hundradels oz, som `Player::capacity`
+    pub gain_cap: u32,
+    pub gain_hp: u32,
+    pub gain_mana: u32,
+    /// Sekunder mellan regenereringarna
+    pub gain_health_ticks: u32,
+    pub gain_health_amount: u32,
+    pub gain_mana_ticks: u32,
+    pub gain_mana_amount: u32,
+    pub gain_soul_ticks: u16,
+    pub soul_max: u8,
+
+    pub mana_multiplier: f64,
+    pub skill_multipliers: [f64; 7],
+    /// Millisekunder mellan attacker utan vapen med egen hastighet
+    pub attack_speed: u32,
+    pub base_speed: u32,
+    /// Sekunder utan svar på ping innan spelaren kickas, 0 = standard
+    pub no_pong_kick_time: u32,
+
+    /// Yrket det här är en befordran av; sig självt om det inte är det
+    pub from_vocation: u16,
+
+    pub melee_damage_multiplier: f32,
+    pub dist_damage_multiplier: f32,
+    pub defense_multiplier: f32,
+    pub armor_multiplier: f32,
+}
+
+impl Vocation {
+    /// Standardvärdena från vocation.h
+    pub fn new(id: u16) -> Self {
+        Self {
+            id,
+            client_id: 0,
+            name: "none".to_string(),
+            description: String::new(),
+            allow_pvp: true,
+            gain_cap: 500,
+            gain_hp: 5,
+            gain_mana: 5,
+            gain_health_ticks: 6,
+            gain_health_amount: 1,
+            gain_mana_ticks: 6,
+            gain_mana_amount: 1,
+            gain_soul_ticks: 120,
+            soul_max: 100,
+            mana_multiplier: 4.0,
+            skill_multipliers: [1.5, 2.0, 2.0, 2.0, 2.0, 1.5, 1.1],
+            attack_speed: 1500,
+            base_speed: 220,
+            no_pong_kick_time: 60,
+            from_vocation: VOCATION_NONE,
+            melee_damage_multiplier: 1.0,
+            dist_damage_multiplier: 1.0,
+            defense_multiplier: 1.0,
+            armor_multiplier: 1.0,
+        }
+    }
+
+    /// Tries som krävs för att nå `level`. Motsvarar `Vocation::getReqSkillTries`.
+    pub fn get_req_skill_tries(&self, skill: Skill, level: u16) -> u64 {
+        let skill = skill as usize;
+        (SKILL_BASE[skill] as f64 * self.skill_multipliers[skill].powi(level as i32 - 11)) as u64
+    }
+
+    /// Mana som krävs för att nå `mag_level`. Motsvarar `Vocation::getReqMana`.
+    pub fn get_req_mana(&self, mag_level: u32) -> u64 {
+        if mag_level == 0 {
+            return 0;
+        }
+        let req_mana = (1600.0 * self.mana_multiplier.powi(mag_level as i32 - 1)) as u64;
+        // som i TFS: ned till ett tjugotal, ett tjugotal till om resten är minst tio
+        let rest = req_mana % 20;
+        if rest < 10 {
+            req_mana - rest
+        } else {
+            req_mana.saturating_sub(rest + 20)
+        }
+    }
+}
+
+/// Alla yrken, motsvarar `Vocations`
+#[derive(Debug, Clone, Default)]
+pub struct Vocations {
+    vocations: BTreeMap<u16, Vocation>,
+    /// `classicAttackSpeed`: attacker schemaläggs med yrkets (eller vapnets)
+    /// hastighet i stället för att vänta på nästa tänk
+    pub classic_attack_speed: bool,
+}
+
+impl Vocations {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Motsvarar `Vocations::loadFromXml`
+    pub fn load_from_xml(path: impl AsRef<Path>, classic_attack_speed: bool) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| Error::World(format!("Cannot open {}: {e}", path.display())))?;
+        let doc = roxmltree::Document::parse(&text)
+            .map_err(|e| Error::World(format!("Cannot parse {}: {e}", path.display())))?;
+
+        let mut vocations = Self { vocations: BTreeMap::new(), classic_attack_speed };
+        for node in doc.root_element().children().filter(|n| n.has_tag_name("vocation")) {
+            let Some(id) = attr::<u16>(&node, "id") else {
+                warn!("[Vocations::loadFromXml] Missing vocation id");
+                continue;
+            };
+            let mut voc = Vocation::new(id);
+            voc.from_vocation = id;
+
+            if let Some(client_id) = attr(&node, "clientid") {
+                voc.client_id = client_id;
+            }
+            if let Some(name) = attr_str(&node, "name") {
+                voc.name = name.to_string();
+            }
+            if let Some(allow_pvp) = attr::<u8>(&node, "allowpvp") {
+                voc.allow_pvp = allow_pvp != 0;
+            }
+            if let Some(description) = attr_str(&node, "description") {
+                voc.description = description.to_string();
+            }
+            if let Some(gain_cap) = attr::<u32>(&node, "gaincap") {
+                voc.gain_cap = gain_cap * 100;
+            }
+            if let Some(value) = attr(&node, "gainhp") {
+                voc.gain_hp = value;
+            }
+            if let Some(value) = attr(&node, "gainmana") {
+                voc.gain_mana = value;
+            }
+            if let Some(value) = attr(&node, "gainhpticks") {
+                voc.gain_health_ticks = value;
+            }
+            if let Some(value) = attr(&node, "gainhpamount") {
+                voc.gain_health_amount = value;
+            }
+            if let Some(value) = attr(&node, "gainmanaticks") {
+                voc.gain_mana_ticks = value;
+            }
+            if let Some(value) = attr(&node, "gainmanaamount") {
+                voc.gain_mana_amount = value;
+            }
+            if let Some(value) = attr(&node, "manamultiplier") {
+                voc.mana_multiplier = value;
+            }
+            if let Some(value) = attr(&node, "attackspeed") {
+                voc.attack_speed = value;
+            }
+            if let Some(value) = attr(&node, "basespeed") {
+                voc.base_speed = value;
+            }
+            if let Some(value) = attr(&node, "soulmax") {
+                voc.soul_max = value;
+            }
+            if let Some(value) = attr(&node, "gainsoulticks") {
+                voc.gain_soul_ticks = value;
+            }
+            if let Some(value) = attr(&node, "fromvoc") {
+                voc.from_vocation = value;
+            }
+            if let Some(value) = attr(&node, "nopongkicktime") {
+                voc.no_pong_kick_time = value;
+            }
+
+            for child in node.children().filter(|n| n.is_element()) {
+                if child.has_tag_name("skill") {
+                    let Some(skill) = attr::<u8>(&child, "id").and_then(Skill::from_u8) else {
+                        warn!("[Vocations::loadFromXml] No valid skill id for vocation: {id}");
+                        continue;
+                    };
+                    if let Some(multiplier) = attr(&child, "multiplier") {
+                        voc.skill_multipliers[skill as usize] = multiplier;
+                    }
+                } else if child.has_tag_name("formula") {
+                    if let Some(value) = attr(&child, "meleeDamage") {
+                        voc.melee_damage_multiplier = value;
+                    }
+                    if let Some(value) = attr(&child, "distDamage") {
+                        voc.dist_damage_multiplier = value;
+                    }
+                    if let Some(value) = attr(&child, "defense") {
+                        voc.defense_multiplier = value;
+                    }
+                    if let Some(value) = attr(&child, "armor") {
+                        voc.armor_multiplier = value;
+                    }
+                }
+            }
+            vocations.vocations.insert(id, voc);
+        }
+        Ok(vocations)
+    }
+
+    pub fn get_vocation(&self, id: u16) -> Option<&Vocation> {
+        let vocation = self.vocations.get(&id);
+        if vocation.is_none() {
+            warn!("[Vocations::getVocation] Vocation {id} not found.");
+        }
+        vocation
+    }
+
+    /// Skiftlägesokänsligt, motsvarar `Vocations::getVocationId`
+    pub fn get_vocation_id(&self, name: &str) -> Option<u16> {
+        self.vocations.values().find(|voc| voc.name.eq_ignore_ascii_case(name)).map(|voc| voc.id)
+    }
+
+    /// Yrket som `id` befordras till, motsvarar `Vocations::getPromotedVocation`
+    pub fn get_promoted_vocation(&self, id: u16) -> Option<u16> {
+        self.vocations
+            .values()
+            .find(|voc| voc.from_vocation == id && voc.id != id)
+            .map(|voc| voc.id)
+    }
+
+    /// Yrket är befordrat och kan inte befordras mer. Motsvarar
+    /// `Player::isPromoted`.
+    pub fn is_promoted(&self, id: u16) -> bool {
+        id != VOCATION_NONE && self.get_promoted_vocation(id).is_none()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Vocation> {
+        self.vocations.values()
+    }
+
+    /// Millisekunder mellan spelarens attacker: vapnets hastighet om den är
+    /// satt, annars yrkets. Motsvarar `Player::getAttackSpeed`.
+    pub fn attack_speed(&self, vocation: u16, weapon_attack_speed: u32) -> u32 {
+        match weapon_attack_speed {
+            0 => self.vocations.get(&vocation).map_or_else(|| Vocation::new(vocation).attack_speed, |voc| voc.attack_speed),
+            speed => speed,
+        }
+    }
+
+    /// Med `classicAttackSpeed` schemaläggs nästa attack efter `attack_speed`
+    /// millisekunder; annars görs den vid nästa tänk när tiden gått.
+    /// Motsvarar grenen i `Player::doAttacking`.
+    pub fn next_attack_delay(&self, attack_speed: u32) -> Option<u32> {
+        self.classic_attack_speed.then_some(attack_speed)
+    }
+}
+
+fn attr_str<'a>(node: &roxmltree::Node<'a, '_>, name: &str) -> Option<&'a str> {
+    node.attributes()
+        .find(|a| a.name().eq_ignore_ascii_case(name))
+        .map(|a| a.value())
+}
+
+fn attr<T: std::str::FromStr>(node: &roxmltree::Node, name: &str) -> Option<T> {
+    attr_str(node, name).and_then(|v| v.trim().parse().ok())
+}
@@ -0,0 +1,819 @@
+//! Vapnen, motsvarar `Weapon`, `WeaponMelee`, `WeaponDistance`,
+//! `WeaponWand` och datadelen av `Weapons` i TFS.
+//!
+//! Här finns uppgifterna från weapons.xml (eller `Weapon()` i scripten),
+//! formlerna och vad en attack ger. En träff blir `CombatEvent` som för
+//! spellen; ett vapen med script kör sitt `onUseWeapon` i stället, vilket
+//! sköts av `scripting::weapons`. Det spelaren betalar och det vapnet
+//! förbrukar står i `WeaponUse` och dras med `on_used_weapon`.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use common::tracing::warn;
+use common::{normal_random, uniform_random, CombatType, Config, Error, MagicEffect, Position, Result, ShootType};
+use entities::{Player, PlayerFlags, Skill};
+use items::{AmmoType, Item, ItemType, WeaponType};
+
+use crate::combat::{BlockType, Combat, CombatDamage, CombatEvent, CombatOrigin, CombatParams, CombatView, Combatant};
+use crate::vocation::{Vocation, Vocations};
+
+/// Skadan en knytnäve räknas med som attackvärde
+const FIST_ATTACK: i32 = 7;
+
+/// Motsvarar klasserna `WeaponMelee`, `WeaponDistance` och `WeaponWand`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WeaponKind {
+    Melee,
+    Distance,
+    Wand,
+}
+
+impl WeaponKind {
+    /// Sorten för en `WEAPON_*`-typ, `Weapon(type)` i scripten
+    pub fn of(weapon_type: WeaponType) -> Option<Self> {
+        match weapon_type {
+            WeaponType::Sword | WeaponType::Club | WeaponType::Axe => Some(WeaponKind::Melee),
+            WeaponType::Distance | WeaponType::Ammo => Some(WeaponKind::Distance),
+            WeaponType::Wand => Some(WeaponKind::Wand),
+            WeaponType::None | WeaponType::Shield => None,
+        }
+    }
+}
+
+/// Vad som händer med vapnet efter en attack, motsvarar `WeaponAction_t`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WeaponAction {
+    #[default]
+    None,
+    RemoveCount,
+    RemoveCharge,
+    Move,
+}
+
+impl WeaponAction {
+    /// `action` i weapons.xml, motsvarar `getWeaponAction`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "move" => Some(WeaponAction::Move),
+            "removecharge" => Some(WeaponAction::RemoveCharge),
+            "removecount" => Some(WeaponAction::RemoveCount),
+            _ => None,
+        }
+    }
+}
+
+/// Inställningarna i config.lua som rör vapen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WeaponConfig {
+    /// `removeWeaponAmmunition`
+    pub remove_ammunition: bool,
+    /// `removeWeaponCharges`
+    pub remove_charges: bool,
+    pub free_premium: bool,
+}
+
+impl Default for WeaponConfig {
+    fn default() -> Self {
+        Self { remove_ammunition: true, remove_charges: true, free_premium: false }
+    }
+}
+
+impl WeaponConfig {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            remove_ammunition: config.remove_weapon_ammunition,
+            remove_charges: config.remove_weapon_charges,
+            free_premium: config.free_premium,
+        }
+    }
+}
+
+/// Spelarflaggorna som rör vapen, från gruppen (`PlayerFlag_*`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct WeaponFlags {
+    pub ignore_weapon_check: bool,
+    pub not_gain_skill: bool,
+    pub has_infinite_soul: bool,
+}
+
+impl WeaponFlags {
+    pub fn from_player(player: &Player) -> Self {
+        Self {
+            ignore_weapon_check: player.has_flag(PlayerFlags::IGNORE_WEAPON_CHECK),
+            not_gain_skill: player.has_flag(PlayerFlags::NOT_GAIN_SKILL),
+            has_infinite_soul: player.has_flag(PlayerFlags::HAS_INFINITE_SOUL),
+        }
+    }
+}
+
+/// Spelaren som anfaller, med det som inte finns på `Player`
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeaponUser {
+    pub combatant: Combatant,
+    pub premium: bool,
+    pub flags: WeaponFlags,
+    pub melee_damage_multiplier: f32,
+    pub dist_damage_multiplier: f32,
+}
+
+impl WeaponUser {
+    pub fn new(player: &Player, vocation: Option<&Vocation>, config: &WeaponConfig, now: u64) -> Self {
+        Self {
+            combatant: Combatant::from_player(player, vocation, now),
+            premium: player.is_premium(config.free_premium, now),
+            flags: WeaponFlags::from_player(player),
+            melee_damage_multiplier: vocation.map_or(1.0, |voc| voc.melee_damage_multiplier),
+            dist_damage_multiplier: vocation.map_or(1.0, |voc| voc.dist_damage_multiplier),
+        }
+    }
+}
+
+/// Ett vapen, motsvarar `Weapon` med underklasserna
+#[derive(Debug, Clone, PartialEq)]
+pub struct Weapon {
+    pub id: u16,
+    pub kind: WeaponKind,
+    pub level: u32,
+    pub magic_level: u32,
+    pub mana: u32,
+    /// Procent av maxmana, om `mana` är 0
+    pub mana_percent: u32,
+    pub soul: u32,
+    pub premium: bool,
+    pub enabled: bool,
+    /// Kan användas under nivån, med halva skadan
+    pub wield_unproperly: bool,
+    /// Procent chans att vapnet går sönder i stället för `action`
+    pub break_chance: u8,
+    pub action: WeaponAction,
+    /// Yrkena som får använda vapnet och om det visas i beskrivningen; tom
+    /// betyder alla. Motsvarar `vocWeaponMap`.
+    pub vocations: BTreeMap<u16, bool>,
+    pub params: CombatParams,
+    /// Elementet närstrids- och distansvapen lägger till
+    pub element_type: CombatType,
+    pub element_damage: u16,
+    /// Skadan en wand gör
+    pub min_change: i32,
+    pub max_change: i32,
+}
+
+impl Weapon {
+    /// Ett vapen med standardvärdena för sorten
+    pub fn new(id: u16, kind: WeaponKind) -> Self {
+        let mut params = CombatParams { combat_type: CombatType::PHYSICAL, ..CombatParams::default() };
+        match kind {
+            WeaponKind::Melee => {
+                params.blocked_by_armor = true;
+                params.blocked_by_shield = true;
+            }
+            WeaponKind::Distance => params.blocked_by_armor = true,
+            WeaponKind::Wand => {}
+        }
+        Self {
+            id,
+            kind,
+            level: 0,
+            magic_level: 0,
+            mana: 0,
+            mana_percent: 0,
+            soul: 0,
+            premium: false,
+            enabled: true,
+            wield_unproperly: false,
+            break_chance: 0,
+            action: WeaponAction::None,
+            vocations: BTreeMap::new(),
+            params,
+            element_type: CombatType::NONE,
+            element_damage: 0,
+            min_change: 0,
+            max_change: 0,
+        }
+    }
+
+    /// Det vapnet tar från itemtypen, motsvarar `configureWeapon`
+    pub fn configure_weapon(&mut self, it: &ItemType) {
+        if self.kind != WeaponKind::Melee {
+            self.params.distance_effect = it.shoot_type;
+        }
+        if self.kind != WeaponKind::Wand && it.element_type != CombatType::NONE {
+            self.element_type = it.element_type;
+            self.element_damage = it.element_damage;
+            self.params.use_charges = true;
+        }
+    }
+
+    /// Motsvarar `Weapon::getManaCost`
+    pub fn mana_cost(&self, player: &Player) -> u32 {
+        if self.mana != 0 {
+            return self.mana;
+        }
+        if self.mana_percent != 0 {
+            return (player.creature.mana_max as u64 * self.mana_percent as u64 / 100) as u32;
+        }
+        0
+    }
+
+    /// `vocation` ur weapons.xml eller `weapon:vocation(name)`
+    pub fn add_vocation(&mut self, vocations: &Vocations, name: &str, show_in_description: bool) -> bool {
+        match vocations.get_vocation_id(name) {
+            Some(id) => {
+                self.vocations.insert(id, show_in_description);
+                true
+            }
+            None => {
+                warn!("[Weapon::configureEvent] Wrong vocation name: {name}");
+                false
+            }
+        }
+    }
+
+    /// Procent av skadan spelaren gör med vapnet, 0 om det inte går att
+    /// använda alls. Motsvarar `Weapon::playerWeaponCheck`.
+    pub fn player_weapon_check(&self, player: &Player, user: &WeaponUser, target: Position, shoot_range: u8) -> i32 {
+        let pos = user.combatant.position;
+        if pos.z != target.z
+            || Position::get_distance_x(&pos, &target).max(Position::get_distance_y(&pos, &target)) > shoot_range as i32
+        {
+            return 0;
+        }
+        if user.flags.ignore_weapon_check {
+            return 100;
+        }
+        if !self.enabled
+            || player.creature.mana < self.mana_cost(player)
+            || (player.soul as u32) < self.soul
+            || (self.premium && !user.premium)
+            || !(self.vocations.is_empty() || self.vocations.contains_key(&player.vocation))
+        {
+            return 0;
+        }
+
+        let mut damage_modifier = 100;
+        if player.level < self.level {
+            damage_modifier = if self.wield_unproperly { damage_modifier / 2 } else { 0 };
+        }
+        if player.magic_level() < self.magic_level {
+            damage_modifier = if self.wield_unproperly { damage_modifier / 2 } else { 0 };
+        }
+        damage_modifier
+    }
+
+    /// Skadan (negativ), eller den största med `max_damage`. Motsvarar
+    /// `getWeaponDamage` för de tre sorterna.
+    pub fn weapon_damage(
+        &self,
+        player: &Player,
+        user: &WeaponUser,
+        target: Option<&Combatant>,
+        item: &Item,
+        max_damage: bool,
+    ) -> i32 {
+        let attack_factor = player.get_attack_factor();
+        match self.kind {
+            WeaponKind::Melee => {
+                let attack_skill = player.get_weapon_skill(Some(item)) as i32;
+                let attack_value = item.get_attack().max(0);
+                let max_value = (max_weapon_damage(player.level, attack_skill, attack_value, attack_factor) as f32
+                    * user.melee_damage_multiplier) as i32;
+                if max_damage {
+                    return -max_value;
+                }
+                -normal_random(0, max_value as i64) as i32
+            }
+            WeaponKind::Distance => {
+                let attack_value = item.get_attack() + bow_attack(player, item);
+                let attack_skill = player.skill_level(Skill::Distance) as i32;
+                let max_value = (max_weapon_damage(player.level, attack_skill, attack_value, attack_factor) as f32
+                    * user.dist_damage_multiplier) as i32;
+                if max_damage {
+                    return -max_value;
+                }
+                let min_value = match target {
+                    Some(target) if target.is_player() => (player.level as f64 * 0.1).ceil() as i32,
+                    Some(_) => (player.level as f64 * 0.2).ceil() as i32,
+                    None => 0,
+                };
+                -normal_random(min_value as i64, max_value as i64) as i32
+            }
+            WeaponKind::Wand => {
+                if max_damage {
+                    return -self.max_change;
+                }
+                -normal_random(self.min_change as i64, self.max_change as i64) as i32
+            }
+        }
+    }
+
+    /// Elementskadan (negativ), 0 utan element. Motsvarar `getElementDamage`.
+    pub fn element_damage(&self, player: &Player, user: &WeaponUser, item: &Item) -> i32 {
+        if self.element_type == CombatType::NONE || self.kind == WeaponKind::Wand {
+            return 0;
+        }
+        let attack_factor = player.get_attack_factor();
+        let (attack_skill, attack_value, multiplier) = match self.kind {
+            WeaponKind::Melee => {
+                (player.get_weapon_skill(Some(item)) as i32, self.element_damage as i32, user.melee_damage_multiplier)
+            }
+            _ => (
+                player.skill_level(Skill::Distance) as i32,
+                self.element_damage as i32 + bow_attack(player, item),
+                user.dist_damage_multiplier,
+            ),
+        };
+        let max_value = max_weapon_damage(player.level, attack_skill, attack_value, attack_factor);
+        -normal_random(0, (max_value as f32 * multiplier) as i64) as i32
+    }
+
+    /// Största skadan och elementskadan för `COMBAT_FORMULA_SKILL`, det
+    /// `Combat::getCombatDamage` tar av vapnet
+    pub fn formula_damage(&self, player: &Player, user: &WeaponUser, item: &Item) -> (i32, CombatType, i32) {
+        (
+            self.weapon_damage(player, user, None, item, true),
+            self.element_type,
+            self.element_damage(player, user, item),
+        )
+    }
+
+    /// Skillen attacken tränar, `None` för wands. Motsvarar `getSkillType`.
+    fn skill_type(&self, item: &Item) -> Option<Skill> {
+        match self.kind {
+            WeaponKind::Melee => match item.weapon_type() {
+                WeaponType::Sword => Some(Skill::Sword),
+                WeaponType::Club => Some(Skill::Club),
+                WeaponType::Axe => Some(Skill::Axe),
+                _ => None,
+            },
+            WeaponKind::Distance => Some(Skill::Distance),
+            WeaponKind::Wand => None,
+        }
+    }
+
+    /// Anfall `target` med vapnet i `slot`; ammunition har bågen i
+    /// `main_weapon`. `None` om vapnet inte kan användas. Ett vapen med
+    /// script får målet i `WeaponUse::target` men ingen skada. Motsvarar
+    /// `Weapon::useWeapon` och `WeaponDistance::useWeapon`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn use_weapon(
+        &self,
+        player: &Player,
+        user: &WeaponUser,
+        slot: u8,
+        main_weapon: Option<&Weapon>,
+        view: &dyn CombatView,
+        target: &Combatant,
+        scripted: bool,
+    ) -> Option<WeaponUse> {
+        let item = player.inventory_item(slot)?;
+        let it = item.item_type();
+
+        let damage_modifier = if self.kind == WeaponKind::Distance && it.weapon_type == WeaponType::Ammo {
+            let bow = player.get_weapon(true)?;
+            main_weapon.unwrap_or(self).player_weapon_check(player, user, target.position, bow.get_shoot_range())
+        } else {
+            self.player_weapon_check(player, user, target.position, item.get_shoot_range())
+        };
+        if damage_modifier == 0 {
+            return None;
+        }
+
+        if self.kind == WeaponKind::Distance && hit_chance(player, user, item, target) < uniform_random(1, 100) as i32 {
+            let pos = miss_position(view, user.combatant.position, target.position);
+            return Some(self.internal_use_tile(player, user, slot, item, pos, scripted));
+        }
+
+        let mut weapon_use = WeaponUse::new(self, player, item, slot, WeaponTarget::Creature(target.id), target.position);
+        if !scripted {
+            let origin = match it.weapon_type {
+                WeaponType::Ammo | WeaponType::Distance => CombatOrigin::Ranged,
+                _ => CombatOrigin::Melee,
+            };
+            let value = self.weapon_damage(player, user, Some(target), item, false) * damage_modifier / 100;
+            let mut damage = CombatDamage::new(self.params.combat_type, value, origin);
+            damage.secondary.combat_type = self.element_type;
+            damage.secondary.value = self.element_damage(player, user, item);
+
+            let (block_type, events) = self.combat().do_target_damage(view, &user.combatant, target, damage);
+            weapon_use.block_type = Some(block_type);
+            weapon_use.events = events;
+        }
+        Some(weapon_use)
+    }
+
+    /// En miss som hamnar på en ruta, motsvarar `internalUseWeapon` med en
+    /// ruta och `Combat::postCombatEffects`
+    fn internal_use_tile(
+        &self,
+        player: &Player,
+        user: &WeaponUser,
+        slot: u8,
+        item: &Item,
+        pos: Position,
+        scripted: bool,
+    ) -> WeaponUse {
+        let mut weapon_use = WeaponUse::new(self, player, item, slot, WeaponTarget::Position(pos), pos);
+        if !scripted {
+            let params = &self.params;
+            if params.distance_effect != ShootType::NONE {
+                weapon_use.events.push(CombatEvent::DistanceEffect {
+                    from: user.combatant.position,
+                    to: pos,
+                    effect: params.distance_effect,
+                });
+            }
+            if params.impact_effect != MagicEffect::None {
+                weapon_use.events.push(CombatEvent::MagicEffect { pos, effect: params.impact_effect });
+            }
+            weapon_use.events.push(CombatEvent::MagicEffect { pos, effect: MagicEffect::Poff });
+        }
+        weapon_use
+    }
+
+    fn combat(&self) -> Combat {
+        let mut combat = Combat::new();
+        combat.params = self.params.clone();
+        combat
+    }
+}
+
+/// Attacken på bågen när `item` är ammunition
+fn bow_attack(player: &Player, item: &Item) -> i32 {
+    if item.weapon_type() != WeaponType::Ammo {
+        return 0;
+    }
+    player.get_weapon(true).map_or(0, |bow| bow.get_attack())
+}
+
+/// Motsvarar `Weapons::getMaxWeaponDamage`
+pub fn max_weapon_damage(level: u32, attack_skill: i32, attack_value: i32, attack_factor: f32) -> i32 {
+    ((level / 5) as f64 + ((attack_skill as f64 / 4.0 + 1.0) * (attack_value as f64 / 3.0) * 1.03) / attack_factor as f64)
+        .round() as i32
+}
+
+/// Procent chans att träffa med ett distansvapen. Utan `hitChance` på
+/// itemet beror den på avståndet och skillen, med taket från
+/// `maxHitChance`. Ammunition får bågens tillägg.
+fn hit_chance(player: &Player, user: &WeaponUser, item: &Item, target: &Combatant) -> i32 {
+    let it = item.item_type();
+    let mut chance = if it.hit_chance == 0 {
+        let skill = player.skill_level(Skill::Distance) as u32;
+        let (pos, to) = (user.combatant.position, target.position);
+        let distance = Position::get_distance_x(&pos, &to).max(Position::get_distance_y(&pos, &to));
+        let max_hit_chance = it.max_hit_chance.unwrap_or(if it.ammo_type != AmmoType::None { 90 } else { 75 });
+        let scaled = |cap: u32, factor: f32| (skill.min(cap) as f32 * factor) as i32;
+        match (max_hit_chance, distance) {
+            (75, 1 | 5) => skill.min(74) as i32 + 1,
+            (75, 2) => scaled(28, 2.40) + 8,
+            (75, 3) => scaled(45, 1.55) + 6,
+            (75, 4) => scaled(58, 1.25) + 3,
+            (75, 6) => scaled(90, 0.80) + 3,
+            (75, 7) => scaled(104, 0.70) + 2,
+            (90, 1 | 5) => scaled(74, 1.20) + 1,
+            (90, 2) => scaled(28, 3.20),
+            (90, 3) => skill.min(45) as i32 * 2,
+            (90, 4) => scaled(58, 1.55),
+            (90, 6 | 7) => skill.min(90) as i32,
+            (100, 1 | 5) => scaled(73, 1.35) + 1,
+            (100, 2) => scaled(30, 3.20) + 4,
+            (100, 3) => scaled(48, 2.05) + 2,
+            (100, 4) => scaled(65, 1.50) + 2,
+            (100, 6) => scaled(87, 1.20) - 4,
+            (100, 7) => scaled(90, 1.10) + 1,
+            (75 | 90 | 100, _) => it.hit_chance as i32,
+            (max_hit_chance, _) => max_hit_chance as i32,
+        }
+    } else {
+        it.hit_chance as i32
+    };
+    if item.weapon_type() == WeaponType::Ammo {
+        if let Some(bow) = player.get_weapon(true) {
+            chance += bow.get_hit_chance() as i32;
+        }
+    }
+    chance
+}
+
+/// Rutan en miss hamnar på: målets, eller en slumpad ruta runt det som
+/// inte är i vägen när målet inte står bredvid
+fn miss_position(view: &dyn CombatView, from: Position, target: Position) -> Position {
+    if Position::are_in_range(&from, &target, 1, 1, 0) {
+        return target;
+    }
+    let mut offsets = [(-1, -1), (0, -1), (1, -1), (-1, 0), (0, 0), (1, 0), (-1, 1), (0, 1), (1, 1)];
+    for i in (1..offsets.len()).rev() {
+        offsets.swap(i, uniform_random(0, i as i64) as usize);
+    }
+    offsets
+        .into_iter()
+        .filter_map(|(dx, dy)| target.translated(dx, dy, 0))
+        .find(|pos| !view.tile(*pos).block_solid)
+        .unwrap_or(target)
+}
+
+/// Vad en attack träffade
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WeaponTarget {
+    Creature(u32),
+    /// En miss, eller ett vapen med script som missade
+    Position(Position),
+}
+
+/// Vad som händer med vapnet efter attacken
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WeaponConsumption {
+    None,
+    /// Ett mindre i högen, eller borta om det var det sista
+    RemoveCount,
+    RemoveCharge,
+    /// Vapnet flyttas till målets ruta, som ett kastat spjut. Det sköter
+    /// spelet.
+    Move(Position),
+}
+
+/// En attack med ett vapen eller en knytnäve
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeaponUse {
+    pub target: WeaponTarget,
+    /// Det spelet ska göra; tomt för vapen med script
+    pub events: Vec<CombatEvent>,
+    /// Hur träffen blockerades, `None` för missar och script
+    pub block_type: Option<BlockType>,
+    /// Platsen för itemet som användes, `None` för knytnäven
+    pub slot: Option<u8>,
+    /// Skillen som tränas; poängen räknas i `on_used_weapon` när
+    /// blockeringen är känd
+    pub skill: Option<Skill>,
+    pub mana: u32,
+    pub soul: u32,
+    pub consumption: WeaponConsumption,
+}
+
+impl WeaponUse {
+    fn new(weapon: &Weapon, player: &Player, item: &Item, slot: u8, target: WeaponTarget, to: Position) -> Self {
+        let consumption = if weapon.break_chance != 0 && uniform_random(1, 100) <= weapon.break_chance as i64 {
+            WeaponConsumption::RemoveCount
+        } else {
+            match weapon.action {
+                WeaponAction::None => WeaponConsumption::None,
+                WeaponAction::RemoveCount => WeaponConsumption::RemoveCount,
+                WeaponAction::RemoveCharge => WeaponConsumption::RemoveCharge,
+                WeaponAction::Move => WeaponConsumption::Move(to),
+            }
+        };
+        Self {
+            target,
+            events: Vec::new(),
+            block_type: None,
+            slot: Some(slot),
+            skill: weapon.skill_type(item),
+            mana: weapon.mana_cost(player),
+            soul: weapon.soul,
+            consumption,
+        }
+    }
+}
+
+/// Skillpoängen för en attack, från `getSkillType` för de tre sorterna
+fn skill_points(player: &Player, skill: Skill) -> u32 {
+    if !player.add_attack_skill_point {
+        return 0;
+    }
+    match (skill, player.last_attack_block_type) {
+        (Skill::Fist, _) => 1,
+        (Skill::Distance, BlockType::None) => 2,
+        (Skill::Distance, BlockType::Defense | BlockType::Armor) => 1,
+        (_, BlockType::Immunity) => 0,
+        _ => 1,
+    }
+}
+
+/// Slå med knytnäven, bara mot någon bredvid. Motsvarar `Weapon::useFist`.
+pub fn use_fist(player: &Player, user: &WeaponUser, view: &dyn CombatView, target: &Combatant) -> Option<WeaponUse> {
+    if !Position::are_in_range(&user.combatant.position, &target.position, 1, 1, 0) {
+        return None;
+    }
+    let attack_skill = player.skill_level(Skill::Fist) as i32;
+    let max_damage = max_weapon_damage(player.level, attack_skill, FIST_ATTACK, player.get_attack_factor());
+
+    let mut combat = Combat::new();
+    combat.params.combat_type = CombatType::PHYSICAL;
+    combat.params.blocked_by_armor = true;
+    combat.params.blocked_by_shield = true;
+    let damage = CombatDamage::new(CombatType::PHYSICAL, -normal_random(0, max_damage as i64) as i32, CombatOrigin::Melee);
+    let (block_type, events) = combat.do_target_damage(view, &user.combatant, target, damage);
+
+    Some(WeaponUse {
+        target: WeaponTarget::Creature(target.id),
+        events,
+        block_type: Some(block_type),
+        slot: None,
+        skill: Some(Skill::Fist),
+        mana: 0,
+        soul: 0,
+        consumption: WeaponConsumption::None,
+    })
+}
+
+/// Det attacken kostar spelaren: blockeringen, mana, själ och ammunition
+/// eller laddningar. Returnerar skillen och poängen den ger; de och
+/// `WeaponUse::mana` som spenderad mana läggs till av `scripting::experience`
+/// så att `onGainSkillTries` körs. Att flytta ett kastat vapen
+/// (`WeaponConsumption::Move`) sköter spelet. Motsvarar `Weapon::onUsedWeapon`.
+pub fn on_used_weapon(
+    player: &mut Player,
+    weapon_use: &WeaponUse,
+    flags: &WeaponFlags,
+    config: &WeaponConfig,
+) -> Option<(Skill, u64)> {
+    if let Some(block_type) = weapon_use.block_type {
+        player.on_attacked_creature_block_hit(block_type);
+    }
+    let skill_tries = weapon_use
+        .skill
+        .filter(|_| !flags.not_gain_skill)
+        .map(|skill| (skill, skill_points(player, skill) as u64));
+
+    if weapon_use.mana != 0 {
+        player.creature.mana = player.creature.mana.saturating_sub(weapon_use.mana);
+    }
+    if !flags.has_infinite_soul && weapon_use.soul > 0 {
+        player.soul = player.soul.saturating_sub(weapon_use.soul.min(u8::MAX as u32) as u8);
+    }
+
+    if let Some(slot) = weapon_use.slot {
+        match weapon_use.consumption {
+            WeaponConsumption::RemoveCount if config.remove_ammunition => decrement_item_count(player, slot),
+            WeaponConsumption::RemoveCharge if config.remove_charges => {
+                if let Some(item) = player.inventory_item_mut(slot) {
+                    let charges = item.attributes().charges;
+                    if charges != 0 {
+                        item.attributes_mut().charges = charges - 1;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    skill_tries
+}
+
+/// Ett mindre i högen, eller bort med itemet. Motsvarar
+/// `Weapon::decrementItemCount`.
+fn decrement_item_count(player: &mut Player, slot: u8) {
+    let Some(item) = player.inventory_item_mut(slot) else {
+        return;
+    };
+    if item.get_item_count() > 1 {
+        item.count -= 1;
+    } else {
+        player.set_inventory_item(slot, None);
+    }
+}
+
+/// Ett vapen ur weapons.xml och dess script
+#[derive(Debug, Clone)]
+pub struct XmlWeapon {
+    pub weapon: Weapon,
+    /// Filen i data/weapons/scripts, `None` för vapen utan script
+    pub script: Option<String>,
+}
+
+/// Läs weapons.xml. Motsvarar `Weapons::registerEvent` med
+/// `Weapon::configureEvent` för de tre sorterna.
+pub fn load_from_xml(path: impl AsRef<Path>, vocations: &Vocations) -> Result<Vec<XmlWeapon>> {
+    let path = path.as_ref();
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| Error::World(format!("Cannot open {}: {e}", path.display())))?;
+    let doc = roxmltree::Document::parse(&text)
+        .map_err(|e| Error::World(format!("Cannot parse {}: {e}", path.display())))?;
+
+    let mut weapons = Vec::new();
+    for node in doc.root_element().children().filter(|n| n.is_element()) {
+        let kind = match node.tag_name().name() {
+            "melee" => WeaponKind::Melee,
+            "distance" => WeaponKind::Distance,
+            "wand" => WeaponKind::Wand,
+            _ => continue,
+        };
+        let Some(weapon) = configure_weapon(&node, kind, vocations) else {
+            continue;
+        };
+        let script = attr_str(&node, "script").map(str::to_string);
+        weapons.push(XmlWeapon { weapon, script });
+    }
+    Ok(weapons)
+}
+
+fn configure_weapon(node: &roxmltree::Node, kind: WeaponKind, vocations: &Vocations) -> Option<Weapon> {
+    let Some(id) = attr::<u16>(node, "id") else {
+        warn!("[Weapon::configureEvent] Weapon without id.");
+        return None;
+    };
+    let mut weapon = Weapon::new(id, kind);
+
+    if let Some(level) = attr(node, "level") {
+        weapon.level = level;
+    }
+    if let Some(magic_level) = attr(node, "maglv").or_else(|| attr(node, "maglevel")) {
+        weapon.magic_level = magic_level;
+    }
+    if let Some(mana) = attr(node, "mana") {
+        weapon.mana = mana;
+    }
+    if let Some(mana_percent) = attr(node, "manapercent") {
+        weapon.mana_percent = mana_percent;
+    }
+    if let Some(soul) = attr(node, "soul") {
+        weapon.soul = soul;
+    }
+    if let Some(premium) = attr_bool(node, "prem") {
+        weapon.premium = premium;
+    }
+    if let Some(break_chance) = attr::<u16>(node, "breakchance") {
+        weapon.break_chance = break_chance.min(100) as u8;
+    }
+    if let Some(action) = attr_str(node, "action") {
+        match WeaponAction::from_name(action) {
+            Some(action) => weapon.action = action,
+            None => warn!("[Weapon::configureEvent] Unknown action {action}"),
+        }
+    }
+    if let Some(enabled) = attr_bool(node, "enabled") {
+        weapon.enabled = enabled;
+    }
+    if let Some(unproperly) = attr_bool(node, "unproperly") {
+        weapon.wield_unproperly = unproperly;
+    }
+    for child in node.children().filter(|n| n.has_tag_name("vocation")) {
+        let Some(name) = attr_str(&child, "name") else {
+            continue;
+        };
+        let show = attr_bool(&child, "showInDescription").unwrap_or(true);
+        weapon.add_vocation(vocations, name, show);
+    }
+
+    if kind == WeaponKind::Wand {
+        if let Some(min) = attr(node, "min") {
+            weapon.min_change = min;
+        }
+        if let Some(max) = attr(node, "max") {
+            weapon.max_change = max;
+        }
+        if let Some(name) = attr_str(node, "type") {
+            match wand_combat_type(name) {
+                Some(combat_type) => weapon.params.combat_type = combat_type,
+                None => warn!("[WeaponWand::configureEvent] Type \"{name}\" does not exist."),
+            }
+        }
+    }
+
+    weapon.configure_weapon(items::Items::get(id));
+    Some(weapon)
+}
+
+/// Skadetypen för `type` på en wand, eller `weapon:element()`
+pub fn wand_combat_type(name: &str) -> Option<CombatType> {
+    match name.to_ascii_lowercase().as_str() {
+        "earth" => Some(CombatType::EARTH),
+        "ice" => Some(CombatType::ICE),
+        "energy" => Some(CombatType::ENERGY),
+        "fire" => Some(CombatType::FIRE),
+        "death" => Some(CombatType::DEATH),
+        "holy" => Some(CombatType::HOLY),
+        _ => None,
+    }
+}
+
+/// Vapen för itemtyperna som har ett `weaponType` men inget i weapons.xml.
+/// Distansvapen som skjuter ammunition får inget; det är ammunitionen som
+/// är vapnet. Motsvarar `Weapons::loadDefaults`.
+pub fn default_weapons<'a>(items: impl IntoIterator<Item = &'a ItemType>) -> Vec<Weapon> {
+    items
+        .into_iter()
+        .filter(|it| it.id >= 100)
+        .filter(|it| !(it.weapon_type == WeaponType::Distance && it.ammo_type != AmmoType::None))
+        .filter_map(|it| {
+            let kind = WeaponKind::of(it.weapon_type).filter(|kind| *kind != WeaponKind::Wand)?;
+            let mut weapon = Weapon::new(it.id, kind);
+            weapon.configure_weapon(it);
+            Some(weapon)
+        })
+        .collect()
+}
+
+fn attr_str<'a>(node: &roxmltree::Node<'a, '_>, name: &str) -> Option<&'a str> {
+    node.attributes()
+        .find(|a| a.name().eq_ignore_ascii_case(name))
+        .map(|a| a.value())
+}
+
+fn attr<T: std::str::FromStr>(node: &roxmltree::Node, name: &str) -> Option<T> {
+    attr_str(node, name).and_then(|v| v.trim().parse().ok())
+}
+
+/// Som pugixmls `as_bool`: sant om värdet börjar med 1, t, T, y eller Y
+fn attr_bool(node: &roxmltree::Node, name: &str) -> Option<bool> {
+    attr_str(node, name).map(|v| matches!(v.trim().chars().next(), Some('1' | 't' | 'T' | 'y' | 'Y')))
+}
@@ -0,0 +1,254 @@
+//! Lua-klassen `Combat` och `createCombatArea`, motsvarar luaCombat* och
+//! `luaCreateCombatArea` i TFS. Striden själv finns i `rules::combat`;
+//! `Combat:execute` lämnar den till spelet genom `ScriptWorld::execute_combat`.
+
+use std::collections::HashMap;
+
+use common::tracing::warn;
+use common::Position;
+use entities::Player;
+use mlua::{AnyUserData, Function, Lua, MetaMethod, MultiValue, RegistryKey, Table, UserData, UserDataMethods, Value};
+use rules::combat::{
+    AreaCombat, Combat, CombatCallbacks, CombatOrigin, CombatParam, CombatTarget, CombatValue, Combatant, FormulaType,
+};
+use items::WeaponType;
+
+use crate::condition::LuaCondition;
+use crate::creature::{creature_id, push_creature, read_player, register_class, world};
+use crate::position::push_position;
+use crate::script_manager::global_table;
+use crate::variant::{get_variant, LuaVariant};
+use crate::weapons;
+
+/// Motsvarar `CallBackParam_t`, `CALLBACK_PARAM_*` i scripten
+pub const CALLBACK_PARAM_LEVELMAGICVALUE: u8 = 1;
+pub const CALLBACK_PARAM_SKILLVALUE: u8 = 2;
+pub const CALLBACK_PARAM_TARGETTILE: u8 = 3;
+pub const CALLBACK_PARAM_TARGETCREATURE: u8 = 4;
+
+/// Ett område från `createCombatArea`
+#[derive(Clone)]
+pub struct LuaCombatArea(pub AreaCombat);
+
+impl UserData for LuaCombatArea {}
+
+/// En strid med sina callbacks, som funktionerna de pekade ut när
+/// `setCallback` anropades
+pub struct LuaCombat {
+    pub combat: Combat,
+    callbacks: HashMap<u8, RegistryKey>,
+}
+
+/// Callbacks under `Combat:execute`. Fel i scripten loggas och striden
+/// fortsätter, som i TFS.
+struct ScriptCallbacks<'a> {
+    lua: &'a Lua,
+    callbacks: &'a HashMap<u8, RegistryKey>,
+}
+
+impl ScriptCallbacks<'_> {
+    fn function(&self, key: u8) -> Option<Function<'_>> {
+        self.lua.registry_value(self.callbacks.get(&key)?).ok()
+    }
+
+    fn call<'lua>(&'lua self, key: u8, args: impl mlua::IntoLuaMulti<'lua>) -> Option<MultiValue<'lua>> {
+        let function = self.function(key)?;
+        match function.call(args) {
+            Ok(values) => Some(values),
+            Err(e) => {
+                warn!("[Combat callback] {e}");
+                None
+            }
+        }
+    }
+}
+
+/// Skill, attack och attackfaktor för `onGetFormulaValues` med
+/// `CALLBACK_PARAM_SKILLVALUE`. Motsvarar första delen av
+/// `ValueCallback::getMinMaxValues`.
+fn skill_formula_args(player: &Player) -> (u16, i32, f64) {
+    let weapon = player.get_weapon(false);
+    let mut attack = weapon.map_or(7, |weapon| weapon.get_attack());
+    if weapon.is_some_and(|weapon| weapon.weapon_type() == WeaponType::Ammo) {
+        attack += player.get_weapon(true).map_or(0, |bow| bow.get_attack());
+    }
+    (player.get_weapon_skill(weapon), attack, player.get_attack_factor() as f64)
+}
+
+impl CombatCallbacks for ScriptCallbacks<'_> {
+    fn formula_values(&mut self, player: &Combatant, formula: FormulaType) -> Option<(i32, i32)> {
+        let lua = self.lua;
+        let creature = push_creature(lua, player.id).ok()?;
+        let values = match formula {
+            FormulaType::LevelMagic => {
+                self.call(CALLBACK_PARAM_LEVELMAGICVALUE, (creature, player.level, player.magic_level))?
+            }
+            FormulaType::Skill => {
+                let (skill, attack, factor) = read_player(lua, player.id, skill_formula_args).ok()??;
+                self.call(CALLBACK_PARAM_SKILLVALUE, (creature, skill, attack, factor))?
+            }
+            _ => return None,
+        };
+        let mut values = values.into_iter().map(|value| lua.coerce_number(value).ok().flatten().unwrap_or(0.0));
+        let min = values.next().unwrap_or(0.0);
+        let max = values.next().unwrap_or(0.0);
+        Some((min as i32, max as i32))
+    }
+
+    fn weapon_damage(&mut self, player: &Combatant, use_charges: bool) -> Option<(i32, CombatValue)> {
+        weapons::formula_damage(self.lua, player, use_charges)
+    }
+
+    fn on_target_tile(&mut self, caster: Option<u32>, pos: Position) {
+        let (Ok(creature), Ok(pos)) = (push_creature(self.lua, caster.unwrap_or(0)), push_position(self.lua, pos)) else {
+            return;
+        };
+        self.call(CALLBACK_PARAM_TARGETTILE, (creature, pos));
+    }
+
+    fn on_target_creature(&mut self, caster: Option<u32>, target: u32) {
+        let (Ok(creature), Ok(target)) = (push_creature(self.lua, caster.unwrap_or(0)), push_creature(self.lua, target)) else {
+            return;
+        };
+        self.call(CALLBACK_PARAM_TARGETCREATURE, (creature, target));
+    }
+}
+
+/// En rad i en områdestabell, motsvarar `getAreaList`
+fn area_rows(table: Option<Table>) -> mlua::Result<Vec<Vec<u32>>> {
+    let Some(table) = table else {
+        return Ok(Vec::new());
+    };
+    table.sequence_values::<Table>().map(|row| row?.sequence_values::<u32>().collect()).collect()
+}
+
+/// Siffror och sanningsvärden, som `setParameter` tar
+fn param_value(value: &Value) -> u32 {
+    match value {
+        Value::Boolean(value) => *value as u32,
+        Value::Integer(value) => *value as u32,
+        Value::Number(value) => *value as u32,
+        _ => 0,
+    }
+}
+
+impl UserData for LuaCombat {
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method_mut("setParameter", |_, this, (key, value): (u8, Value)| {
+            Ok(CombatParam::from_u8(key).is_some_and(|param| this.combat.set_param(param, param_value(&value))))
+        });
+        methods.add_method("getParameter", |_, this, key: u8| {
+            Ok(CombatParam::from_u8(key).map(|param| this.combat.get_param(param)))
+        });
+        methods.add_method_mut(
+            "setFormula",
+            |_, this, (kind, mina, minb, maxa, maxb): (u8, f64, f64, f64, f64)| {
+                let Some(kind) = FormulaType::from_u8(kind) else {
+                    return Ok(false);
+                };
+                this.combat.set_formula(kind, mina, minb, maxa, maxb);
+                Ok(true)
+            },
+        );
+        methods.add_method_mut("setArea", |_, this, area: AnyUserData| {
+            let area = area.borrow::<LuaCombatArea>()?;
+            this.combat.set_area(area.0.clone());
+            Ok(true)
+        });
+        methods.add_method_mut("setOrigin", |_, this, origin: u8| {
+            let Some(origin) = CombatOrigin::from_u8(origin) else {
+                return Ok(false);
+            };
+            this.combat.params.origin = origin;
+            Ok(true)
+        });
+        // addCondition(condition): striden får en kopia
+        methods.add_method_mut("addCondition", |lua, this, condition: AnyUserData| {
+            let Some(condition) = condition.borrow_mut::<LuaCondition>()?.condition(lua)? else {
+                return Ok(false);
+            };
+            this.combat.add_condition(condition);
+            Ok(true)
+        });
+        methods.add_method_mut("clearConditions", |_, this, ()| {
+            this.combat.clear_conditions();
+            Ok(true)
+        });
+        // setCallback(key, function): funktionen med namnet slås upp nu, så
+        // att senare script kan använda samma namn
+        methods.add_method_mut("setCallback", |lua, this, (key, name): (u8, String)| {
+            if !(CALLBACK_PARAM_LEVELMAGICVALUE..=CALLBACK_PARAM_TARGETCREATURE).contains(&key) {
+                return Ok(false);
+            }
+            let Some(function) = lua.globals().get::<_, Option<Function>>(name.as_str())? else {
+                warn!("[Combat:setCallback] Function {name} does not exist");
+                return Ok(false);
+            };
+            if key == CALLBACK_PARAM_LEVELMAGICVALUE {
+                this.combat.formula.kind = FormulaType::LevelMagic;
+            } else if key == CALLBACK_PARAM_SKILLVALUE {
+                this.combat.formula.kind = FormulaType::Skill;
+            }
+            this.callbacks.insert(key, lua.create_registry_value(function)?);
+            Ok(true)
+        });
+
+        // execute(creature, variant), motsvarar `luaCombatExecute`
+        methods.add_method("execute", |lua, this, (creature, variant): (Value, Value)| {
+            let world = world(lua)?;
+            let caster = match &creature {
+                Value::UserData(_) => match creature_id(lua, &creature)? {
+                    Some(id) => Some(id),
+                    None => return Ok(false),
+                },
+                _ => None,
+            };
+            let target = match get_variant(&variant)? {
+                LuaVariant::Number(id) => {
+                    if this.combat.has_area() {
+                        match world.creature_position(id) {
+                            Some(pos) => CombatTarget::Position(pos),
+                            None => return Ok(false),
+                        }
+                    } else if world.creature_name(id).is_some() {
+                        CombatTarget::Creature(id)
+                    } else {
+                        return Ok(false);
+                    }
+                }
+                LuaVariant::Position(pos) => CombatTarget::Position(pos),
+                LuaVariant::TargetPosition(pos) => CombatTarget::TargetPosition(pos),
+                LuaVariant::String(name) => match world.find_creature(&name) {
+                    Some(id) if id != 0 => CombatTarget::Creature(id),
+                    _ => return Ok(false),
+                },
+                LuaVariant::None => return Ok(false),
+            };
+            let mut callbacks = ScriptCallbacks { lua, callbacks: &this.callbacks };
+            Ok(world.execute_combat(&this.combat, caster, target, &mut callbacks))
+        });
+
+        methods.add_meta_method(MetaMethod::Index, |lua, _, key: Value| {
+            global_table(lua, "Combat")?.get::<_, Value>(key)
+        });
+    }
+}
+
+pub(crate) fn register(lua: &Lua) -> mlua::Result<()> {
+    register_class(
+        lua,
+        "Combat",
+        lua.create_function(|_, _: Table| Ok(LuaCombat { combat: Combat::new(), callbacks: HashMap::new() }))?,
+    )?;
+
+    // createCombatArea(area[, extArea])
+    let create_area = lua.create_function(|_, (area, ext_area): (Option<Table>, Option<Table>)| {
+        let area = area_rows(area)?;
+        if area.is_empty() {
+            return Err(mlua::Error::RuntimeError("createCombatArea: invalid area table".into()));
+        }
+        Ok(LuaCombatArea(AreaCombat::new(&area, &area_rows(ext_area)?)))
+    })?;
+    lua.globals().set("createCombatArea", create_area)?;
+    Ok(())
+}
@@ -0,0 +1,183 @@
+//! Lua-klassen `Condition`, motsvarar luaCondition* i TFS. En ny condition
+//! ägs av scriptet tills den läggs på en varelse, som då får en kopia.
+//! `Creature:getCondition` ger i stället en som pekar på varelsens egen, så
+//! att t.ex. `setTicks` ändrar den som sitter på varelsen.
+
+use common::otsys_time;
+use entities::creature::Outfit;
+use entities::{Condition, ConditionId, ConditionType};
+use mlua::{Lua, MetaMethod, Table, UserData, UserDataMethods, Value};
+use rules::condition::{self as rules_condition, ConditionParam};
+
+use crate::creature::{register_class, world};
+use crate::script_manager::global_table;
+
+pub enum LuaCondition {
+    Owned(Condition),
+    /// Conditionen med typen, id:t och sub-id:t på varelsen
+    Attached {
+        creature: u32,
+        condition_type: ConditionType,
+        id: ConditionId,
+        sub_id: u32,
+    },
+}
+
+impl LuaCondition {
+    /// Kör `f` på conditionen, nil om den inte längre finns på varelsen
+    pub(crate) fn with<T>(&mut self, lua: &Lua, f: impl FnOnce(&mut Condition) -> T) -> mlua::Result<Option<T>> {
+        match self {
+            LuaCondition::Owned(condition) => Ok(Some(f(condition))),
+            LuaCondition::Attached { creature, condition_type, id, sub_id } => {
+                let mut f = Some(f);
+                let mut value = None;
+                let mut key = None;
+                world(lua)?.with_condition(*creature, *condition_type, *id, *sub_id, &mut |condition| {
+                    if let Some(f) = f.take() {
+                        value = Some(f(condition));
+                        key = Some((condition.condition_type, condition.sub_id));
+                    }
+                });
+                // `setParameter` kan ändra typen och sub-id:t
+                if let Some((new_type, new_sub_id)) = key {
+                    *condition_type = new_type;
+                    *sub_id = new_sub_id;
+                }
+                Ok(value)
+            }
+        }
+    }
+
+    /// En kopia att lägga på en varelse
+    pub fn condition(&mut self, lua: &Lua) -> mlua::Result<Option<Condition>> {
+        self.with(lua, |condition| condition.clone())
+    }
+}
+
+/// Utseendet ur en tabell, motsvarar `getOutfit`
+pub(crate) fn get_outfit(table: &Table) -> mlua::Result<Outfit> {
+    Ok(Outfit {
+        look_type: table.get::<_, Option<u16>>("lookType")?.unwrap_or(0),
+        look_type_ex: table.get::<_, Option<u16>>("lookTypeEx")?.unwrap_or(0),
+        look_head: table.get::<_, Option<u8>>("lookHead")?.unwrap_or(0),
+        look_body: table.get::<_, Option<u8>>("lookBody")?.unwrap_or(0),
+        look_legs: table.get::<_, Option<u8>>("lookLegs")?.unwrap_or(0),
+        look_feet: table.get::<_, Option<u8>>("lookFeet")?.unwrap_or(0),
+        look_addons: table.get::<_, Option<u8>>("lookAddons")?.unwrap_or(0),
+        look_mount: table.get::<_, Option<u16>>("lookMount")?.unwrap_or(0),
+        look_mount_head: table.get::<_, Option<u8>>("lookMountHead")?.unwrap_or(0),
+        look_mount_body: table.get::<_, Option<u8>>("lookMountBody")?.unwrap_or(0),
+        look_mount_legs: table.get::<_, Option<u8>>("lookMountLegs")?.unwrap_or(0),
+        look_mount_feet: table.get::<_, Option<u8>>("lookMountFeet")?.unwrap_or(0),
+    })
+}
+
+/// Utseendet som tabell, motsvarar `pushOutfit`
+pub(crate) fn push_outfit<'lua>(lua: &'lua Lua, outfit: &Outfit) -> mlua::Result<Table<'lua>> {
+    let table = lua.create_table()?;
+    table.set("lookType", outfit.look_type)?;
+    table.set("lookTypeEx", outfit.look_type_ex)?;
+    table.set("lookHead", outfit.look_head)?;
+    table.set("lookBody", outfit.look_body)?;
+    table.set("lookLegs", outfit.look_legs)?;
+    table.set("lookFeet", outfit.look_feet)?;
+    table.set("lookAddons", outfit.look_addons)?;
+    table.set("lookMount", outfit.look_mount)?;
+    table.set("lookMountHead", outfit.look_mount_head)?;
+    table.set("lookMountBody", outfit.look_mount_body)?;
+    table.set("lookMountLegs", outfit.look_mount_legs)?;
+    table.set("lookMountFeet", outfit.look_mount_feet)?;
+    Ok(table)
+}
+
+/// Siffror och sanningsvärden, som `setParameter` tar
+fn param_value(value: &Value) -> i32 {
+    match value {
+        Value::Boolean(value) => *value as i32,
+        Value::Integer(value) => *value as i32,
+        Value::Number(value) => *value as i32,
+        _ => 0,
+    }
+}
+
+/// Id:t från scripten, där -1 är `CONDITIONID_DEFAULT`
+pub(crate) fn condition_id(value: Option<i8>) -> mlua::Result<ConditionId> {
+    let value = value.unwrap_or(ConditionId::Combat.as_i8());
+    ConditionId::from_i8(value).ok_or_else(|| mlua::Error::RuntimeError(format!("invalid condition id {value}")))
+}
+
+impl UserData for LuaCondition {
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method_mut("getId", |lua, this, ()| this.with(lua, |c| c.id.as_i8()));
+        methods.add_method_mut("getSubId", |lua, this, ()| this.with(lua, |c| c.sub_id));
+        methods.add_method_mut("getType", |lua, this, ()| this.with(lua, |c| c.condition_type.0));
+        methods.add_method_mut("getIcons", |lua, this, ()| this.with(lua, |c| c.icons()));
+        methods.add_method_mut("getEndTime", |lua, this, ()| {
+            this.with(lua, |c| if c.is_permanent() { 0 } else { otsys_time() + c.ticks as u64 })
+        });
+        methods.add_method_mut("clone", |lua, this, ()| {
+            Ok(this.condition(lua)?.map(LuaCondition::Owned))
+        });
+        methods.add_method_mut("getTicks", |lua, this, ()| this.with(lua, |c| c.ticks));
+        methods.add_method_mut("setTicks", |lua, this, ticks: i32| {
+            Ok(this.with(lua, |c| c.ticks = ticks)?.is_some())
+        });
+        methods.add_method_mut("setParameter", |lua, this, (key, value): (u8, Value)| {
+            let Some(param) = ConditionParam::from_u8(key) else {
+                return Ok(false);
+            };
+            let value = param_value(&value);
+            Ok(this.with(lua, |c| rules_condition::set_param(c, param, value))?.unwrap_or(false))
+        });
+        methods.add_method_mut("setFormula", |lua, this, (mina, minb, maxa, maxb): (f32, f32, f32, f32)| {
+            Ok(this.with(lua, |c| rules_condition::set_speed_formula(c, mina, minb, maxa, maxb))?.unwrap_or(false))
+        });
+        // setOutfit(outfit) eller setOutfit(lookTypeEx, lookType, lookHead,
+        // lookBody, lookLegs, lookFeet[, lookAddons[, lookMount]])
+        methods.add_method_mut("setOutfit", |lua, this, args: mlua::Variadic<Value>| {
+            let outfit = match args.first() {
+                Some(Value::Table(table)) => get_outfit(table)?,
+                _ => {
+                    let arg = |index: usize| {
+                        let value = args.get(index).cloned().unwrap_or(Value::Nil);
+                        lua.coerce_integer(value).ok().flatten().unwrap_or(0)
+                    };
+                    Outfit {
+                        look_type_ex: arg(0) as u16,
+                        look_type: arg(1) as u16,
+                        look_head: arg(2) as u8,
+                        look_body: arg(3) as u8,
+                        look_legs: arg(4) as u8,
+                        look_feet: arg(5) as u8,
+                        look_addons: arg(6) as u8,
+                        look_mount: arg(7) as u16,
+                        ..Outfit::default()
+                    }
+                }
+            };
+            Ok(this.with(lua, |c| rules_condition::set_outfit(c, outfit))?.unwrap_or(false))
+        });
+        methods.add_method_mut("addDamage", |lua, this, (rounds, time, value): (i32, i32, i32)| {
+            Ok(this.with(lua, |c| rules_condition::add_damage(c, rounds, time, value))?.unwrap_or(false))
+        });
+        // Skräpsamlingen tar hand om den; finns för gamla script
+        methods.add_method("delete", |_, _, ()| Ok(()));
+
+        methods.add_meta_method(MetaMethod::Index, |lua, _, key: Value| {
+            global_table(lua, "Condition")?.get::<_, Value>(key)
+        });
+    }
+}
+
+pub(crate) fn register(lua: &Lua) -> mlua::Result<()> {
+    // Condition(conditionType[, conditionId = CONDITIONID_COMBAT[, subId = 0]])
+    register_class(
+        lua,
+        "Condition",
+        lua.create_function(|_, (_, condition_type, id, sub_id): (Table, u32, Option<i8>, Option<u32>)| {
+            let id = condition_id(id)?;
+            Ok(rules_condition::create_condition(id, ConditionType(condition_type), 0, 0, false, sub_id.unwrap_or(0))
+                .map(LuaCondition::Owned))
+        })?,
+    )
+}
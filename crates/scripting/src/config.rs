@@ -0,0 +1,153 @@
+//! `configManager` och `configKeys`, motsvarar luaConfigManager* i TFS.
+//! Nycklarna är namnen själva, `configKeys.RATE_SKILL == "RATE_SKILL"`, och
+//! värdena läses en gång från config.lua.
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use common::Config;
+use mlua::Lua;
+
+use crate::script_manager::global_table;
+
+enum ConfigValue {
+    Boolean(bool),
+    Number(i64),
+    String(String),
+}
+
+/// Nycklarna i TFS:s `boolean_config_t`, `string_config_t` och
+/// `integer_config_t` som har en motsvarighet i `Config`
+fn values(config: &Config) -> Vec<(&'static str, ConfigValue)> {
+    use ConfigValue::{Boolean, Number, String};
+    vec![
+        ("ALLOW_CHANGEOUTFIT", Boolean(config.allow_change_outfit)),
+        ("ONE_PLAYER_ON_ACCOUNT", Boolean(config.one_player_online_per_account)),
+        ("AIMBOT_HOTKEY_ENABLED", Boolean(config.hotkey_aimbot_enabled)),
+        ("REMOVE_RUNE_CHARGES", Boolean(config.remove_charges_from_runes)),
+        ("REMOVE_POTION_CHARGES", Boolean(config.remove_charges_from_potions)),
+        ("REMOVE_WEAPON_AMMO", Boolean(config.remove_weapon_ammunition)),
+        ("REMOVE_WEAPON_CHARGES", Boolean(config.remove_weapon_charges)),
+        ("EXPERIENCE_FROM_PLAYERS", Boolean(config.experience_by_killing_players)),
+        ("FREE_PREMIUM", Boolean(config.free_premium)),
+        ("REPLACE_KICK_ON_LOGIN", Boolean(config.replace_kick_on_login)),
+        ("ALLOW_CLONES", Boolean(config.allow_clones)),
+        ("ALLOW_WALKTHROUGH", Boolean(config.allow_walkthrough)),
+        ("BIND_ONLY_GLOBAL_ADDRESS", Boolean(config.bind_only_global_address)),
+        ("OPTIMIZE_DATABASE", Boolean(config.startup_database_optimization)),
+        ("MARKET_PREMIUM", Boolean(config.premium_to_create_market_offer)),
+        ("EMOTE_SPELLS", Boolean(config.emote_spells)),
+        ("STAMINA_SYSTEM", Boolean(config.stamina_system)),
+        ("WARN_UNSAFE_SCRIPTS", Boolean(config.warn_unsafe_scripts)),
+        ("CONVERT_UNSAFE_SCRIPTS", Boolean(config.convert_unsafe_scripts)),
+        ("CLASSIC_EQUIPMENT_SLOTS", Boolean(config.classic_equipment_slots)),
+        ("CLASSIC_ATTACK_SPEED", Boolean(config.classic_attack_speed)),
+        ("SCRIPTS_CONSOLE_LOGS", Boolean(config.show_scripts_log_in_console)),
+        ("SERVER_SAVE_NOTIFY_MESSAGE", Boolean(config.server_save_notify_message)),
+        ("SERVER_SAVE_CLEAN_MAP", Boolean(config.server_save_clean_map)),
+        ("SERVER_SAVE_CLOSE", Boolean(config.server_save_close)),
+        ("SERVER_SAVE_SHUTDOWN", Boolean(config.server_save_shutdown)),
+        ("ONLINE_OFFLINE_CHARLIST", Boolean(config.show_online_status_in_charlist)),
+        ("YELL_ALLOW_PREMIUM", Boolean(config.yell_always_allow_premium)),
+        ("FORCE_MONSTERTYPE_LOAD", Boolean(config.force_monster_types_on_load)),
+        ("DEFAULT_WORLD_LIGHT", Boolean(config.default_world_light)),
+        ("HOUSE_OWNED_BY_ACCOUNT", Boolean(config.house_owned_by_account)),
+        ("LUA_ITEM_DESC", Boolean(config.lua_item_desc)),
+        ("CLEAN_PROTECTION_ZONES", Boolean(config.clean_protection_zones)),
+        ("HOUSE_DOOR_SHOW_PRICE", Boolean(config.house_door_show_price)),
+        ("ONLY_INVITED_CAN_MOVE_HOUSE_ITEMS", Boolean(config.only_invited_can_move_house_items)),
+        ("REMOVE_ON_DESPAWN", Boolean(config.remove_on_despawn)),
+        ("PLAYER_CONSOLE_LOGS", Boolean(config.show_player_log_in_console)),
+        ("MAP_NAME", String(config.map_name.clone())),
+        ("HOUSE_RENT_PERIOD", String(config.house_rent_period.clone())),
+        ("SERVER_NAME", String(config.server_name.clone())),
+        ("OWNER_NAME", String(config.owner_name.clone())),
+        ("OWNER_EMAIL", String(config.owner_email.clone())),
+        ("URL", String(config.url.clone())),
+        ("LOCATION", String(config.location.clone())),
+        ("IP", String(config.ip.clone())),
+        ("MOTD", String(config.motd.clone())),
+        ("WORLD_TYPE", String(config.world_type.clone())),
+        ("MYSQL_HOST", String(config.mysql_host.clone())),
+        ("MYSQL_USER", String(config.mysql_user.clone())),
+        ("MYSQL_PASS", String(config.mysql_pass.clone())),
+        ("MYSQL_DB", String(config.mysql_database.clone())),
+        ("MYSQL_SOCK", String(config.mysql_sock.clone())),
+        ("DEFAULT_PRIORITY", String(config.default_priority.clone())),
+        ("MAP_AUTHOR", String(config.map_author.clone())),
+        ("SQL_PORT", Number(config.mysql_port as i64)),
+        ("MAX_PLAYERS", Number(config.max_players as i64)),
+        ("PZ_LOCKED", Number(config.pz_locked as i64)),
+        ("DEFAULT_DESPAWNRANGE", Number(config.despawn_range as i64)),
+        ("DEFAULT_DESPAWNRADIUS", Number(config.despawn_radius as i64)),
+        ("DEFAULT_WALKTOSPAWNRADIUS", Number(config.walk_to_spawn_radius as i64)),
+        ("RATE_EXPERIENCE", Number(config.rate_exp as i64)),
+        ("RATE_SKILL", Number(config.rate_skill as i64)),
+        ("RATE_LOOT", Number(config.rate_loot as i64)),
+        ("RATE_MAGIC", Number(config.rate_magic as i64)),
+        ("RATE_SPAWN", Number(config.rate_spawn as i64)),
+        ("HOUSE_PRICE", Number(config.house_price_each_sqm as i64)),
+        ("KILLS_TO_RED", Number(config.kills_to_red_skull as i64)),
+        ("KILLS_TO_BLACK", Number(config.kills_to_black_skull as i64)),
+        ("MAX_MESSAGEBUFFER", Number(config.max_message_buffer as i64)),
+        ("ACTIONS_DELAY_INTERVAL", Number(config.time_between_actions as i64)),
+        ("EX_ACTIONS_DELAY_INTERVAL", Number(config.time_between_ex_actions as i64)),
+        ("KICK_AFTER_MINUTES", Number(config.kick_idle_player_after_minutes as i64)),
+        ("PROTECTION_LEVEL", Number(config.protection_level as i64)),
+        ("DEATH_LOSE_PERCENT", Number(config.death_lose_percent as i64)),
+        ("STATUSQUERY_TIMEOUT", Number(config.status_timeout as i64)),
+        ("FRAG_TIME", Number(config.time_to_decrease_frags as i64)),
+        ("WHITE_SKULL_TIME", Number(config.white_skull_time as i64)),
+        ("GAME_PORT", Number(config.game_protocol_port as i64)),
+        ("LOGIN_PORT", Number(config.login_protocol_port as i64)),
+        ("STATUS_PORT", Number(config.status_protocol_port as i64)),
+        ("STAIRHOP_DELAY", Number(config.stair_jump_exhaustion as i64)),
+        ("MARKET_OFFER_DURATION", Number(config.market_offer_duration as i64)),
+        ("CHECK_EXPIRED_MARKET_OFFERS_EACH_MINUTES", Number(config.check_expired_market_offers_each_minutes as i64)),
+        ("MAX_MARKET_OFFERS_AT_A_TIME_PER_PLAYER", Number(config.max_market_offers_at_a_time_per_player as i64)),
+        ("EXP_FROM_PLAYERS_LEVEL_RANGE", Number(config.exp_from_players_level_range as i64)),
+        ("MAX_PACKETS_PER_SECOND", Number(config.max_packets_per_second as i64)),
+        ("SERVER_SAVE_NOTIFY_DURATION", Number(config.server_save_notify_duration as i64)),
+        ("YELL_MINIMUM_LEVEL", Number(config.yell_minimum_level as i64)),
+        ("VIP_FREE_LIMIT", Number(config.vip_free_limit as i64)),
+        ("VIP_PREMIUM_LIMIT", Number(config.vip_premium_limit as i64)),
+        ("DEPOT_FREE_LIMIT", Number(config.depot_free_limit as i64)),
+        ("DEPOT_PREMIUM_LIMIT", Number(config.depot_premium_limit as i64)),
+    ]
+}
+
+/// `configManager.getString/getNumber/getBoolean(key)`. Som i TFS ger en
+/// nyckel av fel sort tomt, 0 eller false.
+pub fn register(lua: &Lua, config: &Config) -> mlua::Result<()> {
+    let values = values(config);
+    let keys = global_table(lua, "configKeys")?;
+    for (key, _) in &values {
+        keys.set(*key, *key)?;
+    }
+    let values: Rc<HashMap<&'static str, ConfigValue>> = Rc::new(values.into_iter().collect());
+
+    let manager = global_table(lua, "configManager")?;
+    let strings = values.clone();
+    manager.set(
+        "getString",
+        lua.create_function(move |_, key: String| match strings.get(key.as_str()) {
+            Some(ConfigValue::String(value)) => Ok(value.clone()),
+            _ => Ok(String::new()),
+        })?,
+    )?;
+    let numbers = values.clone();
+    manager.set(
+        "getNumber",
+        lua.create_function(move |_, key: String| match numbers.get(key.as_str()) {
+            Some(ConfigValue::Number(value)) => Ok(*value),
+            _ => Ok(0),
+        })?,
+    )?;
+    manager.set(
+        "getBoolean",
+        lua.create_function(move |_, key: String| match values.get(key.as_str()) {
+            Some(ConfigValue::Boolean(value)) => Ok(*value),
+            _ => Ok(false),
+        })?,
+    )
+}
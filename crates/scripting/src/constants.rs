@@ -0,0 +1,493 @@
+//! Globala konstanter för scripten, motsvarar `registerEnum` i
+//! `LuaScriptInterface::registerFunctions`.
+
+use common::{CombatType, Direction, MagicEffect, MessageClass, ReturnValue, ShootType, SpeakClass};
+use entities::condition::{ConditionId, ConditionType};
+use entities::creature::Skull;
+use entities::player::{AccountType, PlayerSex};
+use entities::{PlayerFlags, Skill, SpeechBubble};
+use items::WeaponType;
+use mlua::Lua;
+use rules::combat::{CombatOrigin, CombatParam, FormulaType};
+use rules::condition::ConditionParam;
+use rules::experience::{SKILL_LEVEL, SKILL_MAGLEVEL};
+use rules::spells::{SpellGroup, SpellType};
+use world::house::{GUEST_LIST, SUBOWNER_LIST};
+
+use crate::{combat, variant};
+
+/// `CONST_ME_*` i samma ordning som `MagicEffect`
+const MAGIC_EFFECTS: [&str; 77] = [
+    "CONST_ME_NONE",
+    "CONST_ME_DRAWBLOOD",
+    "CONST_ME_LOSEENERGY",
+    "CONST_ME_POFF",
+    "CONST_ME_BLOCKHIT",
+    "CONST_ME_EXPLOSIONAREA",
+    "CONST_ME_EXPLOSIONHIT",
+    "CONST_ME_FIREAREA",
+    "CONST_ME_YELLOW_RINGS",
+    "CONST_ME_GREEN_RINGS",
+    "CONST_ME_HITAREA",
+    "CONST_ME_TELEPORT",
+    "CONST_ME_ENERGYHIT",
+    "CONST_ME_MAGIC_BLUE",
+    "CONST_ME_MAGIC_RED",
+    "CONST_ME_MAGIC_GREEN",
+    "CONST_ME_HITBYFIRE",
+    "CONST_ME_HITBYPOISON",
+    "CONST_ME_MORTAREA",
+    "CONST_ME_SOUND_GREEN",
+    "CONST_ME_SOUND_RED",
+    "CONST_ME_POISONAREA",
+    "CONST_ME_SOUND_YELLOW",
+    "CONST_ME_SOUND_PURPLE",
+    "CONST_ME_SOUND_BLUE",
+    "CONST_ME_SOUND_WHITE",
+    "CONST_ME_BUBBLES",
+    "CONST_ME_CRAPS",
+    "CONST_ME_GIFT_WRAPS",
+    "CONST_ME_FIREWORK_YELLOW",
+    "CONST_ME_FIREWORK_RED",
+    "CONST_ME_FIREWORK_BLUE",
+    "CONST_ME_STUN",
+    "CONST_ME_SLEEP",
+    "CONST_ME_WATERCREATURE",
+    "CONST_ME_GROUNDSHAKER",
+    "CONST_ME_HEARTS",
+    "CONST_ME_FIREATTACK",
+    "CONST_ME_ENERGYAREA",
+    "CONST_ME_SMALLCLOUDS",
+    "CONST_ME_HOLYDAMAGE",
+    "CONST_ME_BIGCLOUDS",
+    "CONST_ME_ICEAREA",
+    "CONST_ME_ICETORNADO",
+    "CONST_ME_ICEATTACK",
+    "CONST_ME_STONES",
+    "CONST_ME_SMALLPLANTS",
+    "CONST_ME_CARNIPHILA",
+    "CONST_ME_PURPLEENERGY",
+    "CONST_ME_YELLOWENERGY",
+    "CONST_ME_HOLYAREA",
+    "CONST_ME_BIGPLANTS",
+    "CONST_ME_CAKE",
+    "CONST_ME_GIANTICE",
+    "CONST_ME_WATERSPLASH",
+    "CONST_ME_PLANTATTACK",
+    "CONST_ME_TUTORIALARROW",
+    "CONST_ME_TUTORIALSQUARE",
+    "CONST_ME_MIRRORHORIZONTAL",
+    "CONST_ME_MIRRORVERTICAL",
+    "CONST_ME_SKULLHORIZONTAL",
+    "CONST_ME_SKULLVERTICAL",
+    "CONST_ME_ASSASSIN",
+    "CONST_ME_STEPSHORIZONTAL",
+    "CONST_ME_BLOODYSTEPS",
+    "CONST_ME_STEPSVERTICAL",
+    "CONST_ME_YALAHARIGHOST",
+    "CONST_ME_BATS",
+    "CONST_ME_SMOKE",
+    "CONST_ME_INSECTS",
+    "CONST_ME_DRAGONHEAD",
+    "CONST_ME_ORCSHAMAN",
+    "CONST_ME_ORCSHAMAN_FIRE",
+    "CONST_ME_THUNDER",
+    "CONST_ME_FERUMBRAS",
+    "CONST_ME_CONFETTI_HORIZONTAL",
+    "CONST_ME_CONFETTI_VERTICAL",
+];
+
+const COMBAT_TYPES: [(&str, CombatType); 14] = [
+    ("COMBAT_NONE", CombatType::NONE),
+    ("COMBAT_PHYSICALDAMAGE", CombatType::PHYSICAL),
+    ("COMBAT_ENERGYDAMAGE", CombatType::ENERGY),
+    ("COMBAT_EARTHDAMAGE", CombatType::EARTH),
+    ("COMBAT_POISONDAMAGE", CombatType::EARTH),
+    ("COMBAT_FIREDAMAGE", CombatType::FIRE),
+    ("COMBAT_UNDEFINEDDAMAGE", CombatType::UNDEFINED),
+    ("COMBAT_LIFEDRAIN", CombatType::LIFEDRAIN),
+    ("COMBAT_MANADRAIN", CombatType::MANADRAIN),
+    ("COMBAT_HEALING", CombatType::HEALING),
+    ("COMBAT_DROWNDAMAGE", CombatType::DROWN),
+    ("COMBAT_ICEDAMAGE", CombatType::ICE),
+    ("COMBAT_HOLYDAMAGE", CombatType::HOLY),
+    ("COMBAT_DEATHDAMAGE", CombatType::DEATH),
+];
+
+const CONDITION_TYPES: [(&str, ConditionType); 29] = [
+    ("CONDITION_NONE", ConditionType::NONE),
+    ("CONDITION_POISON", ConditionType::POISON),
+    ("CONDITION_FIRE", ConditionType::FIRE),
+    ("CONDITION_ENERGY", ConditionType::ENERGY),
+    ("CONDITION_BLEEDING", ConditionType::BLEEDING),
+    ("CONDITION_HASTE", ConditionType::HASTE),
+    ("CONDITION_PARALYZE", ConditionType::PARALYZE),
+    ("CONDITION_OUTFIT", ConditionType::OUTFIT),
+    ("CONDITION_INVISIBLE", ConditionType::INVISIBLE),
+    ("CONDITION_LIGHT", ConditionType::LIGHT),
+    ("CONDITION_MANASHIELD", ConditionType::MANASHIELD),
+    ("CONDITION_INFIGHT", ConditionType::INFIGHT),
+    ("CONDITION_DRUNK", ConditionType::DRUNK),
+    ("CONDITION_EXHAUST_WEAPON", ConditionType::EXHAUST_WEAPON),
+    ("CONDITION_REGENERATION", ConditionType::REGENERATION),
+    ("CONDITION_SOUL", ConditionType::SOUL),
+    ("CONDITION_DROWN", ConditionType::DROWN),
+    ("CONDITION_MUTED", ConditionType::MUTED),
+    ("CONDITION_CHANNELMUTEDTICKS", ConditionType::CHANNELMUTEDTICKS),
+    ("CONDITION_YELLTICKS", ConditionType::YELLTICKS),
+    ("CONDITION_ATTRIBUTES", ConditionType::ATTRIBUTES),
+    ("CONDITION_FREEZING", ConditionType::FREEZING),
+    ("CONDITION_DAZZLED", ConditionType::DAZZLED),
+    ("CONDITION_CURSED", ConditionType::CURSED),
+    ("CONDITION_EXHAUST_COMBAT", ConditionType::EXHAUST_COMBAT),
+    ("CONDITION_EXHAUST_HEAL", ConditionType::EXHAUST_HEAL),
+    ("CONDITION_PACIFIED", ConditionType::PACIFIED),
+    ("CONDITION_SPELLCOOLDOWN", ConditionType::SPELLCOOLDOWN),
+    ("CONDITION_SPELLGROUPCOOLDOWN", ConditionType::SPELLGROUPCOOLDOWN),
+];
+
+const CONDITION_IDS: [(&str, ConditionId); 12] = [
+    ("CONDITIONID_DEFAULT", ConditionId::Default),
+    ("CONDITIONID_COMBAT", ConditionId::Combat),
+    ("CONDITIONID_HEAD", ConditionId::Head),
+    ("CONDITIONID_NECKLACE", ConditionId::Necklace),
+    ("CONDITIONID_BACKPACK", ConditionId::Backpack),
+    ("CONDITIONID_ARMOR", ConditionId::Armor),
+    ("CONDITIONID_RIGHT", ConditionId::Right),
+    ("CONDITIONID_LEFT", ConditionId::Left),
+    ("CONDITIONID_LEGS", ConditionId::Legs),
+    ("CONDITIONID_FEET", ConditionId::Feet),
+    ("CONDITIONID_RING", ConditionId::Ring),
+    ("CONDITIONID_AMMO", ConditionId::Ammo),
+];
+
+/// `ConditionParam_t`, i samma ordning som `ConditionParam`
+const CONDITION_PARAMS: [&str; 44] = [
+    "CONDITION_PARAM_OWNER",
+    "CONDITION_PARAM_TICKS",
+    "CONDITION_PARAM_HEALTHGAIN",
+    "CONDITION_PARAM_HEALTHTICKS",
+    "CONDITION_PARAM_MANAGAIN",
+    "CONDITION_PARAM_MANATICKS",
+    "CONDITION_PARAM_DELAYED",
+    "CONDITION_PARAM_SPEED",
+    "CONDITION_PARAM_LIGHT_LEVEL",
+    "CONDITION_PARAM_LIGHT_COLOR",
+    "CONDITION_PARAM_SOULGAIN",
+    "CONDITION_PARAM_SOULTICKS",
+    "CONDITION_PARAM_MINVALUE",
+    "CONDITION_PARAM_MAXVALUE",
+    "CONDITION_PARAM_STARTVALUE",
+    "CONDITION_PARAM_TICKINTERVAL",
+    "CONDITION_PARAM_FORCEUPDATE",
+    "CONDITION_PARAM_SKILL_MELEE",
+    "CONDITION_PARAM_SKILL_FIST",
+    "CONDITION_PARAM_SKILL_CLUB",
+    "CONDITION_PARAM_SKILL_SWORD",
+    "CONDITION_PARAM_SKILL_AXE",
+    "CONDITION_PARAM_SKILL_DISTANCE",
+    "CONDITION_PARAM_SKILL_SHIELD",
+    "CONDITION_PARAM_SKILL_FISHING",
+    "CONDITION_PARAM_STAT_MAXHITPOINTS",
+    "CONDITION_PARAM_STAT_MAXMANAPOINTS",
+    "CONDITION_PARAM_STAT_MAGICPOINTS",
+    "CONDITION_PARAM_STAT_MAXHITPOINTSPERCENT",
+    "CONDITION_PARAM_STAT_MAXMANAPOINTSPERCENT",
+    "CONDITION_PARAM_STAT_MAGICPOINTSPERCENT",
+    "CONDITION_PARAM_PERIODICDAMAGE",
+    "CONDITION_PARAM_SKILL_MELEEPERCENT",
+    "CONDITION_PARAM_SKILL_FISTPERCENT",
+    "CONDITION_PARAM_SKILL_CLUBPERCENT",
+    "CONDITION_PARAM_SKILL_SWORDPERCENT",
+    "CONDITION_PARAM_SKILL_AXEPERCENT",
+    "CONDITION_PARAM_SKILL_DISTANCEPERCENT",
+    "CONDITION_PARAM_SKILL_SHIELDPERCENT",
+    "CONDITION_PARAM_SKILL_FISHINGPERCENT",
+    "CONDITION_PARAM_BUFF_SPELL",
+    "CONDITION_PARAM_SUBID",
+    "CONDITION_PARAM_FIELD",
+    "CONDITION_PARAM_DISABLE_DEFENSE",
+];
+
+const SKULLS: [(&str, Skull); 7] = [
+    ("SKULL_NONE", Skull::None),
+    ("SKULL_YELLOW", Skull::Yellow),
+    ("SKULL_GREEN", Skull::Green),
+    ("SKULL_WHITE", Skull::White),
+    ("SKULL_RED", Skull::Red),
+    ("SKULL_BLACK", Skull::Black),
+    ("SKULL_ORANGE", Skull::Orange),
+];
+
+const DIRECTIONS: [(&str, Direction); 8] = [
+    ("DIRECTION_NORTH", Direction::North),
+    ("DIRECTION_EAST", Direction::East),
+    ("DIRECTION_SOUTH", Direction::South),
+    ("DIRECTION_WEST", Direction::West),
+    ("DIRECTION_SOUTHWEST", Direction::SouthWest),
+    ("DIRECTION_SOUTHEAST", Direction::SouthEast),
+    ("DIRECTION_NORTHWEST", Direction::NorthWest),
+    ("DIRECTION_NORTHEAST", Direction::NorthEast),
+];
+
+const SPEAK_CLASSES: [(&str, SpeakClass); 17] = [
+    ("TALKTYPE_SAY", SpeakClass::Say),
+    ("TALKTYPE_WHISPER", SpeakClass::Whisper),
+    ("TALKTYPE_YELL", SpeakClass::Yell),
+    ("TALKTYPE_PRIVATE_FROM", SpeakClass::PrivateFrom),
+    ("TALKTYPE_PRIVATE_TO", SpeakClass::PrivateTo),
+    ("TALKTYPE_CHANNEL_Y", SpeakClass::ChannelY),
+    ("TALKTYPE_CHANNEL_O", SpeakClass::ChannelO),
+    ("TALKTYPE_PRIVATE_NP", SpeakClass::PrivateNp),
+    ("TALKTYPE_PRIVATE_PN", SpeakClass::PrivatePn),
+    ("TALKTYPE_BROADCAST", SpeakClass::Broadcast),
+    ("TALKTYPE_CHANNEL_R1", SpeakClass::ChannelR1),
+    ("TALKTYPE_PRIVATE_RED_FROM", SpeakClass::PrivateRedFrom),
+    ("TALKTYPE_PRIVATE_RED_TO", SpeakClass::PrivateRedTo),
+    ("TALKTYPE_MONSTER_SAY", SpeakClass::MonsterSay),
+    ("TALKTYPE_MONSTER_YELL", SpeakClass::MonsterYell),
+    ("TALKTYPE_POTION", SpeakClass::Potion),
+    ("TALKTYPE_CHANNEL_R2", SpeakClass::ChannelR2),
+];
+
+const MESSAGE_CLASSES: [(&str, MessageClass); 21] = [
+    ("MESSAGE_STATUS_CONSOLE_BLUE", MessageClass::StatusConsoleBlue),
+    ("MESSAGE_STATUS_CONSOLE_RED", MessageClass::StatusConsoleRed),
+    ("MESSAGE_STATUS_DEFAULT", MessageClass::StatusDefault),
+    ("MESSAGE_STATUS_WARNING", MessageClass::StatusWarning),
+    ("MESSAGE_EVENT_ADVANCE", MessageClass::EventAdvance),
+    ("MESSAGE_STATUS_SMALL", MessageClass::StatusSmall),
+    ("MESSAGE_INFO_DESCR", MessageClass::InfoDescr),
+    ("MESSAGE_DAMAGE_DEALT", MessageClass::DamageDealt),
+    ("MESSAGE_DAMAGE_RECEIVED", MessageClass::DamageReceived),
+    ("MESSAGE_HEALED", MessageClass::Healed),
+    ("MESSAGE_EXPERIENCE", MessageClass::Experience),
+    ("MESSAGE_DAMAGE_OTHERS", MessageClass::DamageOthers),
+    ("MESSAGE_HEALED_OTHERS", MessageClass::HealedOthers),
+    ("MESSAGE_EXPERIENCE_OTHERS", MessageClass::ExperienceOthers),
+    ("MESSAGE_EVENT_DEFAULT", MessageClass::EventDefault),
+    ("MESSAGE_LOOT", MessageClass::Loot),
+    ("MESSAGE_GUILD", MessageClass::Guild),
+    ("MESSAGE_PARTY_MANAGEMENT", MessageClass::PartyManagement),
+    ("MESSAGE_PARTY", MessageClass::Party),
+    ("MESSAGE_EVENT_ORANGE", MessageClass::EventOrange),
+    ("MESSAGE_STATUS_CONSOLE_ORANGE", MessageClass::StatusConsoleOrange),
+];
+
+const SPEECH_BUBBLES: [(&str, SpeechBubble); 5] = [
+    ("SPEECHBUBBLE_NONE", SpeechBubble::None),
+    ("SPEECHBUBBLE_NORMAL", SpeechBubble::Normal),
+    ("SPEECHBUBBLE_TRADE", SpeechBubble::Trade),
+    ("SPEECHBUBBLE_QUEST", SpeechBubble::Quest),
+    ("SPEECHBUBBLE_QUESTTRADER", SpeechBubble::QuestTrader),
+];
+
+/// De `RETURNVALUE_*` scripten jämför med
+const RETURN_VALUES: [(&str, ReturnValue); 15] = [
+    ("RETURNVALUE_NOERROR", ReturnValue::NoError),
+    ("RETURNVALUE_NOTPOSSIBLE", ReturnValue::NotPossible),
+    ("RETURNVALUE_NOTENOUGHROOM", ReturnValue::NotEnoughRoom),
+    ("RETURNVALUE_CONTAINERNOTENOUGHROOM", ReturnValue::ContainerNotEnoughRoom),
+    ("RETURNVALUE_NOTENOUGHCAPACITY", ReturnValue::NotEnoughCapacity),
+    ("RETURNVALUE_CREATUREDOESNOTEXIST", ReturnValue::CreatureDoesNotExist),
+    ("RETURNVALUE_PLAYERWITHTHISNAMEISNOTONLINE", ReturnValue::PlayerWithThisNameIsNotOnline),
+    ("RETURNVALUE_NOTENOUGHMANA", ReturnValue::NotEnoughMana),
+    ("RETURNVALUE_NOTENOUGHSOUL", ReturnValue::NotEnoughSoul),
+    ("RETURNVALUE_YOUAREEXHAUSTED", ReturnValue::YouAreExhausted),
+    ("RETURNVALUE_YOUNEEDPREMIUMACCOUNT", ReturnValue::YouNeedPremiumAccount),
+    ("RETURNVALUE_YOUNEEDAMAGICITEMTOCASTSPELL", ReturnValue::YouNeedAMagicItemToCastSpell),
+    ("RETURNVALUE_NOPARTYMEMBERSINRANGE", ReturnValue::NoPartyMembersInRange),
+    ("RETURNVALUE_PLAYERISPZLOCKED", ReturnValue::PlayerIsPzLockedEnterPvpZone),
+    ("RETURNVALUE_NOTENOUGHLEVEL", ReturnValue::NotEnoughLevel),
+];
+
+/// `combatParam_t`, i samma ordning som `CombatParam`
+const COMBAT_PARAMS: [&str; 10] = [
+    "COMBAT_PARAM_TYPE",
+    "COMBAT_PARAM_EFFECT",
+    "COMBAT_PARAM_DISTANCEEFFECT",
+    "COMBAT_PARAM_BLOCKSHIELD",
+    "COMBAT_PARAM_BLOCKARMOR",
+    "COMBAT_PARAM_TARGETCASTERORTOPMOST",
+    "COMBAT_PARAM_CREATEITEM",
+    "COMBAT_PARAM_AGGRESSIVE",
+    "COMBAT_PARAM_DISPEL",
+    "COMBAT_PARAM_USECHARGES",
+];
+
+const FORMULA_TYPES: [(&str, FormulaType); 4] = [
+    ("COMBAT_FORMULA_UNDEFINED", FormulaType::Undefined),
+    ("COMBAT_FORMULA_LEVELMAGIC", FormulaType::LevelMagic),
+    ("COMBAT_FORMULA_SKILL", FormulaType::Skill),
+    ("COMBAT_FORMULA_DAMAGE", FormulaType::Damage),
+];
+
+const COMBAT_ORIGINS: [(&str, CombatOrigin); 5] = [
+    ("ORIGIN_NONE", CombatOrigin::None),
+    ("ORIGIN_CONDITION", CombatOrigin::Condition),
+    ("ORIGIN_SPELL", CombatOrigin::Spell),
+    ("ORIGIN_MELEE", CombatOrigin::Melee),
+    ("ORIGIN_RANGED", CombatOrigin::Ranged),
+];
+
+const CALLBACK_PARAMS: [(&str, u8); 4] = [
+    ("CALLBACK_PARAM_LEVELMAGICVALUE", combat::CALLBACK_PARAM_LEVELMAGICVALUE),
+    ("CALLBACK_PARAM_SKILLVALUE", combat::CALLBACK_PARAM_SKILLVALUE),
+    ("CALLBACK_PARAM_TARGETTILE", combat::CALLBACK_PARAM_TARGETTILE),
+    ("CALLBACK_PARAM_TARGETCREATURE", combat::CALLBACK_PARAM_TARGETCREATURE),
+];
+
+const VARIANTS: [(&str, u8); 5] = [
+    ("VARIANT_NONE", variant::VARIANT_NONE),
+    ("VARIANT_NUMBER", variant::VARIANT_NUMBER),
+    ("VARIANT_POSITION", variant::VARIANT_POSITION),
+    ("VARIANT_TARGETPOSITION", variant::VARIANT_TARGETPOSITION),
+    ("VARIANT_STRING", variant::VARIANT_STRING),
+];
+
+const SPELL_TYPES: [(&str, SpellType); 3] = [
+    ("SPELL_UNDEFINED", SpellType::Undefined),
+    ("SPELL_INSTANT", SpellType::Instant),
+    ("SPELL_RUNE", SpellType::Rune),
+];
+
+const SPELL_GROUPS: [(&str, SpellGroup); 9] = [
+    ("SPELLGROUP_NONE", SpellGroup::None),
+    ("SPELLGROUP_ATTACK", SpellGroup::Attack),
+    ("SPELLGROUP_HEALING", SpellGroup::Healing),
+    ("SPELLGROUP_SUPPORT", SpellGroup::Support),
+    ("SPELLGROUP_SPECIAL", SpellGroup::Special),
+    ("SPELLGROUP_CONJURE", SpellGroup::Conjure),
+    ("SPELLGROUP_CRIPPLING", SpellGroup::Crippling),
+    ("SPELLGROUP_FOCUS", SpellGroup::Focus),
+    ("SPELLGROUP_ULTIMATESTRIKES", SpellGroup::UltimateStrikes),
+];
+
+const WEAPON_TYPES: [(&str, WeaponType); 8] = [
+    ("WEAPON_NONE", WeaponType::None),
+    ("WEAPON_SWORD", WeaponType::Sword),
+    ("WEAPON_CLUB", WeaponType::Club),
+    ("WEAPON_AXE", WeaponType::Axe),
+    ("WEAPON_SHIELD", WeaponType::Shield),
+    ("WEAPON_DISTANCE", WeaponType::Distance),
+    ("WEAPON_WAND", WeaponType::Wand),
+    ("WEAPON_AMMO", WeaponType::Ammo),
+];
+
+const SKILLS: [(&str, Skill); 7] = [
+    ("SKILL_FIST", Skill::Fist),
+    ("SKILL_CLUB", Skill::Club),
+    ("SKILL_SWORD", Skill::Sword),
+    ("SKILL_AXE", Skill::Axe),
+    ("SKILL_DISTANCE", Skill::Distance),
+    ("SKILL_SHIELD", Skill::Shield),
+    ("SKILL_FISHING", Skill::Fishing),
+];
+
+const ACCOUNT_TYPES: [(&str, AccountType); 6] = [
+    ("ACCOUNT_TYPE_NORMAL", AccountType::Normal),
+    ("ACCOUNT_TYPE_TUTOR", AccountType::Tutor),
+    ("ACCOUNT_TYPE_SENIORTUTOR", AccountType::SeniorTutor),
+    ("ACCOUNT_TYPE_GAMEMASTER", AccountType::GameMaster),
+    ("ACCOUNT_TYPE_COMMUNITYMANAGER", AccountType::CommunityManager),
+    ("ACCOUNT_TYPE_GOD", AccountType::God),
+];
+
+/// Item-id som scripten behöver, `item_t` i const.h
+const ITEMS: [(&str, u16); 6] = [
+    ("ITEM_GOLD_COIN", 2148),
+    ("ITEM_PLATINUM_COIN", 2152),
+    ("ITEM_CRYSTAL_COIN", 2160),
+    ("ITEM_PARCEL", 2595),
+    ("ITEM_LABEL", 2599),
+    ("ITEM_SHOPPING_BAG", 23782),
+];
+
+pub fn register(lua: &Lua) -> mlua::Result<()> {
+    let globals = lua.globals();
+    for (value, name) in MAGIC_EFFECTS.iter().enumerate() {
+        debug_assert!(MagicEffect::from_u8(value as u8).is_some());
+        globals.set(*name, value as u8)?;
+    }
+    globals.set("CONST_ANI_NONE", ShootType::NONE.0)?;
+    for value in 1..=u8::MAX {
+        if let Some(name) = ShootType(value).name() {
+            globals.set(format!("CONST_ANI_{}", name.to_ascii_uppercase()), value)?;
+        }
+    }
+    globals.set("CONST_ANI_WEAPONTYPE", ShootType::WEAPON_TYPE.0)?;
+    for (name, combat_type) in COMBAT_TYPES {
+        globals.set(name, combat_type.0)?;
+    }
+    for (name, condition_type) in CONDITION_TYPES {
+        globals.set(name, condition_type.0)?;
+    }
+    for (name, id) in CONDITION_IDS {
+        globals.set(name, id.as_i8())?;
+    }
+    for (param, name) in ConditionParam::ALL.iter().zip(CONDITION_PARAMS) {
+        globals.set(name, *param as u8)?;
+    }
+    for (name, skull) in SKULLS {
+        globals.set(name, skull as u8)?;
+    }
+    for (name, dir) in DIRECTIONS {
+        globals.set(name, dir as u8)?;
+    }
+    for (name, class) in SPEAK_CLASSES {
+        globals.set(name, class as u8)?;
+    }
+    for (name, class) in MESSAGE_CLASSES {
+        globals.set(name, class as u8)?;
+    }
+    for (name, bubble) in SPEECH_BUBBLES {
+        globals.set(name, bubble as u8)?;
+    }
+    for (name, value) in RETURN_VALUES {
+        globals.set(name, value as u8)?;
+    }
+    for (param, name) in CombatParam::ALL.iter().zip(COMBAT_PARAMS) {
+        globals.set(name, *param as u8)?;
+    }
+    for (name, formula) in FORMULA_TYPES {
+        globals.set(name, formula as u8)?;
+    }
+    for (name, origin) in COMBAT_ORIGINS {
+        globals.set(name, origin as u8)?;
+    }
+    for (name, value) in CALLBACK_PARAMS.into_iter().chain(VARIANTS) {
+        globals.set(name, value)?;
+    }
+    for (name, spell_type) in SPELL_TYPES {
+        globals.set(name, spell_type as u8)?;
+    }
+    for (name, group) in SPELL_GROUPS {
+        globals.set(name, group as u8)?;
+    }
+    for (name, weapon_type) in WEAPON_TYPES {
+        globals.set(name, weapon_type as u8)?;
+    }
+    for (name, skill) in SKILLS {
+        globals.set(name, skill as u8)?;
+    }
+    globals.set("SKILL_MAGLEVEL", SKILL_MAGLEVEL)?;
+    globals.set("SKILL_LEVEL", SKILL_LEVEL)?;
+    for (name, id) in ITEMS {
+        globals.set(name, id)?;
+    }
+    globals.set("PLAYERSEX_FEMALE", PlayerSex::Female as u8)?;
+    globals.set("PLAYERSEX_MALE", PlayerSex::Male as u8)?;
+    for (name, account_type) in ACCOUNT_TYPES {
+        globals.set(name, account_type as u8)?;
+    }
+    for (name, flag) in PlayerFlags::ALL {
+        globals.set(format!("PlayerFlag_{name}"), flag.0)?;
+    }
+    globals.set("GUEST_LIST", GUEST_LIST)?;
+    globals.set("SUBOWNER_LIST", SUBOWNER_LIST)?;
+    // samma värden som i net/consts.rs
+    globals.set("CHANNEL_GUILD", 0x00)?;
+    globals.set("CHANNEL_PARTY", 0x01)?;
+    globals.set("CHANNEL_PRIVATE", 0xFFFF)?;
+    Ok(())
+}
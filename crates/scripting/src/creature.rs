@@ -0,0 +1,549 @@
+//! Lua-klasserna `Creature`, `Player`, `Monster` och `Npc`, motsvarar
+//! luaCreature*, luaPlayer*, luaMonster* och luaNpc* i TFS.
+//!
+//! Scripten pratar med spelet genom `ScriptWorld`, som spelet lämnar till
+//! `ScriptManager::register_world`. Ett Lua-objekt är bara varelsens id;
+//! finns varelsen inte längre ger metoderna nil. Som i TFS är klasserna
+//! också globala tabeller, så att data/lib/core kan lägga till metoder.
+
+use std::rc::Rc;
+
+use common::{Direction, MagicEffect, MessageClass, Position, ReturnValue, SpeakClass};
+use entities::{
+    Condition, ConditionId, ConditionType, CreatureType, Guilds, Npc, Outfit, Parties, PartyView, Player, ShopInfo,
+    Skill,
+};
+use entities::creature::Skull;
+use items::Item;
+use rules::combat::{Combat, CombatCallbacks, CombatTarget, CombatView};
+use rules::experience::Advance;
+use world::Houses;
+use mlua::{AnyUserData, Lua, MetaMethod, Table, UserData, UserDataMethods, Value};
+
+use crate::condition::{condition_id, LuaCondition};
+use crate::experience;
+use crate::item::LuaItem;
+use crate::guild::{player_guild, player_guild_level, set_player_guild, set_player_guild_level, LuaGuild};
+use crate::party::player_party;
+use crate::vocation::{push_vocation, vocation_id};
+use crate::position::{get_position, push_position};
+use crate::spells::{self, LuaSpell};
+use crate::script_manager::global_table;
+
+/// Det scripten får se och göra i spelet. Alla metoder tar `&self`, så
+/// spelet får själv hålla reda på sin inre föränderlighet; ett script kan
+/// när som helst anropa tillbaka hit medan spelet kör ett event.
+pub trait ScriptWorld {
+    fn creature_name(&self, id: u32) -> Option<String>;
+    fn creature_position(&self, id: u32) -> Option<Position>;
+    fn creature_direction(&self, id: u32) -> Option<Direction>;
+    fn creature_health(&self, id: u32) -> Option<i32>;
+    /// Id för en varelse med namnet, spelare först
+    fn find_creature(&self, name: &str) -> Option<u32>;
+    /// Spelaren med namnet, där `~` sist matchar början av ett namn.
+    /// Motsvarar `Game::getPlayerByNameWildcard`.
+    fn get_player_by_name_wildcard(&self, name: &str) -> std::result::Result<u32, ReturnValue>;
+
+    /// `Creature:say`/`selfSay`. Med `target` hör bara den spelaren, annars
+    /// alla runt `pos` (eller varelsen). Motsvarar `Game::internalCreatureSay`.
+    fn creature_say(&self, id: u32, text: &str, class: SpeakClass, target: Option<u32>, pos: Option<Position>) -> bool;
+    fn creature_move(&self, id: u32, dir: Direction) -> ReturnValue;
+    fn creature_turn(&self, id: u32, dir: Direction) -> bool;
+    /// Gå till `pos` längs en sökt väg, motsvarar `selfMoveTo`
+    fn creature_walk_to(&self, id: u32, pos: Position) -> bool;
+    fn creature_follow(&self, id: u32, target: Option<u32>) -> bool;
+    fn teleport(&self, id: u32, pos: Position) -> bool;
+    fn creature_skull(&self, id: u32) -> Option<Skull>;
+    /// Sätt skallen och skicka den, motsvarar `Creature::setSkull`
+    fn set_creature_skull(&self, id: u32, skull: Skull) -> bool;
+    /// Skicka varelsens skalle till bara `viewer`, motsvarar
+    /// `Player::sendCreatureSkull`
+    fn send_creature_skull(&self, viewer: u32, id: u32);
+    /// Utseendet varelsen visar just nu
+    fn creature_outfit(&self, id: u32) -> Option<Outfit>;
+    /// Sätt varelsens eget utseende och visa det, om `Creature:onChangeOutfit`
+    /// tillåter. Motsvarar `defaultOutfit` och `Game::internalCreatureChangeOutfit`.
+    fn set_creature_outfit(&self, id: u32, outfit: Outfit) -> bool;
+    /// Motsvarar `Player::sendOutfitWindow`, se `rules::outfit::outfit_window`
+    fn send_outfit_window(&self, id: u32);
+    /// Skicka varelsens nya fart till dem som ser den, motsvarar slutet av
+    /// `Game::changeSpeed`
+    fn send_creature_speed(&self, id: u32);
+
+    /// Kör `f` på spelaren, false om den inte finns
+    fn with_player(&self, id: u32, f: &mut dyn FnMut(&mut Player)) -> bool;
+    /// Kör `f` på NPC:n, false om den inte finns
+    fn with_npc(&self, id: u32, f: &mut dyn FnMut(&mut Npc)) -> bool;
+
+    /// Guld, platina och kristall i inventoryt, motsvarar `Player::getMoney`
+    fn player_money(&self, id: u32) -> u64;
+    fn add_money(&self, id: u32, amount: u64) -> bool;
+    fn remove_money(&self, id: u32, amount: u64) -> bool;
+    /// -1 som `sub_type` räknar alla sorter
+    fn item_count(&self, id: u32, item_id: u16, sub_type: i32) -> u32;
+    fn remove_item(&self, id: u32, item_id: u16, count: u32, sub_type: i32) -> bool;
+    /// Lägg itemet hos spelaren; `item` töms bara om det gick.
+    /// Motsvarar `Game::internalPlayerAddItem`.
+    fn add_item_ex(&self, id: u32, item: &mut Option<Item>, ignore_cap: bool) -> ReturnValue;
+    fn send_text_message(&self, id: u32, class: MessageClass, text: &str);
+    /// Skicka statistiken, motsvarar `Player::sendStats`
+    fn send_stats(&self, id: u32);
+    /// Skicka skillsen, motsvarar `Player::sendSkills`
+    fn send_skills(&self, id: u32);
+    fn send_magic_effect(&self, pos: Position, effect: MagicEffect);
+
+    /// Skicka handelsfönstret och spelarens säljbara saker
+    fn send_shop(&self, player: u32, npc: u32, items: &[ShopInfo]);
+    fn send_close_shop(&self, player: u32);
+    /// Ge spelaren `amount` av varan, på marken om det inte får plats och
+    /// `can_drop_on_map`. Returnerar hur många som gavs. Motsvarar `doSellItem`.
+    fn sell_item(&self, player: u32, item_id: u16, amount: u32, sub_type: i32, action_id: u16, can_drop_on_map: bool) -> u32;
+
+    /// Kör `f` på partyna; spelet skickar själv det som köats i `Parties`
+    fn with_parties(&self, f: &mut dyn FnMut(&mut Parties, &dyn PartyView));
+    /// Spelarens nivå, magiska nivå eller en skill ändrades och meddelandet
+    /// är skickat. Spelet skickar statistiken och, när nivån ändrats, farten,
+    /// hälsan och partyts delade erfarenhet. `onAdvance` körs bara när det
+    /// gick upp. Motsvarar `CreatureEvents::playerAdvance` och det runt anropen.
+    fn player_advance(&self, player: u32, advance: &Advance);
+
+    /// Kör `f` på de inlästa gillena
+    fn with_guilds(&self, f: &mut dyn FnMut(&mut Guilds));
+
+    /// `Combat:execute`: kör striden från `caster` mot `target` och skicka
+    /// det som händer. Motsvarar `Combat::doCombat`.
+    fn execute_combat(&self, combat: &Combat, caster: Option<u32>, target: CombatTarget, callbacks: &mut dyn CombatCallbacks) -> bool;
+    /// Kör `f` med kartan och varelserna som striden ser dem
+    fn with_combat_view(&self, f: &mut dyn FnMut(&dyn CombatView));
+
+    /// Lägg conditionen på varelsen, motsvarar `Creature::addCondition`
+    fn add_condition(&self, id: u32, condition: Condition, force: bool) -> bool;
+    /// Ta bort conditionen med typen, id:t och sub-id:t, false om den inte fanns
+    fn remove_condition(&self, id: u32, condition_type: ConditionType, condition_id: ConditionId, sub_id: u32, force: bool) -> bool;
+    /// Kör `f` på varelsens condition, false om den inte finns
+    fn with_condition(
+        &self,
+        id: u32,
+        condition_type: ConditionType,
+        condition_id: ConditionId,
+        sub_id: u32,
+        f: &mut dyn FnMut(&mut Condition),
+    ) -> bool;
+    /// Motsvarar `Creature::hasCondition(type, subId)`
+    fn has_condition(&self, id: u32, condition_type: ConditionType, sub_id: u32) -> bool;
+
+    /// Huset tilen hör till: None om tilen inte finns, 0 om den inte hör till ett hus
+    fn tile_house_id(&self, pos: Position) -> Option<u32>;
+    /// Kör `f` på husen
+    fn with_houses(&self, f: &mut dyn FnMut(&Houses));
+    /// Byt ägare (0 = ingen) och skicka förra ägarens items till depån. Med
+    /// `update_database` sparar spelet det med `IOHouse::set_owner`.
+    /// Motsvarar `House::setOwner`.
+    fn set_house_owner(&self, house_id: u32, guid: u32, update_database: bool) -> bool;
+    /// Sätt listan och kasta ut de som inte längre är inbjudna, se
+    /// `World::set_house_access_list`
+    fn set_house_access_list(&self, house_id: u32, list_id: u32, text: &str) -> bool;
+    /// Kasta ut `target` ur huset om `kicker` får, motsvarar `House::kickPlayer`
+    fn kick_from_house(&self, house_id: u32, kicker: u32, target: u32) -> bool;
+}
+
+/// Spelets `ScriptWorld`, sparad som app data i Lua-tillståndet
+#[derive(Clone)]
+pub(crate) struct WorldHandle(pub Rc<dyn ScriptWorld>);
+
+pub(crate) fn world(lua: &Lua) -> mlua::Result<Rc<dyn ScriptWorld>> {
+    lua.app_data_ref::<WorldHandle>()
+        .map(|handle| handle.0.clone())
+        .ok_or_else(|| mlua::Error::RuntimeError("no world registered".into()))
+}
+
+#[derive(Clone, Copy)]
+pub struct LuaCreature(pub u32);
+
+#[derive(Clone, Copy)]
+pub struct LuaPlayer(pub u32);
+
+#[derive(Clone, Copy)]
+pub struct LuaMonster(pub u32);
+
+#[derive(Clone, Copy)]
+pub struct LuaNpc(pub u32);
+
+/// Varelsen som rätt klass, som `setCreatureMetatable` i TFS. Nil om id:t
+/// inte finns.
+pub fn push_creature(lua: &Lua, id: u32) -> mlua::Result<Value<'_>> {
+    if id == 0 || world(lua)?.creature_name(id).is_none() {
+        return Ok(Value::Nil);
+    }
+    let userdata = match CreatureType::of_id(id) {
+        Some(CreatureType::Player) => lua.create_userdata(LuaPlayer(id))?,
+        Some(CreatureType::Monster) => lua.create_userdata(LuaMonster(id))?,
+        Some(CreatureType::Npc) => lua.create_userdata(LuaNpc(id))?,
+        None => lua.create_userdata(LuaCreature(id))?,
+    };
+    Ok(Value::UserData(userdata))
+}
+
+/// Id:t ur ett varelseobjekt, ett nummer eller ett namn
+pub fn creature_id(lua: &Lua, value: &Value) -> mlua::Result<Option<u32>> {
+    Ok(match value {
+        Value::Integer(id) => Some(*id as u32),
+        Value::Number(id) => Some(*id as u32),
+        Value::String(name) => world(lua)?.find_creature(name.to_str()?),
+        Value::UserData(userdata) => userdata_id(userdata),
+        _ => None,
+    })
+}
+
+fn userdata_id(userdata: &AnyUserData) -> Option<u32> {
+    if let Ok(c) = userdata.borrow::<LuaPlayer>() {
+        return Some(c.0);
+    }
+    if let Ok(c) = userdata.borrow::<LuaNpc>() {
+        return Some(c.0);
+    }
+    if let Ok(c) = userdata.borrow::<LuaMonster>() {
+        return Some(c.0);
+    }
+    userdata.borrow::<LuaCreature>().ok().map(|c| c.0)
+}
+
+/// Metoderna alla varelser har, plus uppslag i klasstabellerna för det
+/// data/lib lagt till
+macro_rules! creature_methods {
+    ($methods:ident, $classes:expr) => {
+        $methods.add_method("isCreature", |_, _, ()| Ok(true));
+        $methods.add_method("getId", |lua, this, ()| exists(lua, this.0).map(|e| e.then_some(this.0)));
+        $methods.add_method("getName", |lua, this, ()| Ok(world(lua)?.creature_name(this.0)));
+        $methods.add_method("getPosition", |lua, this, ()| match world(lua)?.creature_position(this.0) {
+            Some(pos) => push_position(lua, pos).map(Value::Table),
+            None => Ok(Value::Nil),
+        });
+        $methods.add_method("getDirection", |lua, this, ()| {
+            Ok(world(lua)?.creature_direction(this.0).map(|dir| dir as u8))
+        });
+        $methods.add_method("setDirection", |lua, this, dir: u8| {
+            let Some(dir) = Direction::from_u8(dir) else {
+                return Ok(false);
+            };
+            Ok(world(lua)?.creature_turn(this.0, dir))
+        });
+        $methods.add_method("isRemoved", |lua, this, ()| exists(lua, this.0).map(|e| !e));
+        // say(text, type[, ghost = false[, target = nil[, position]]])
+        $methods.add_method(
+            "say",
+            |lua, this, (text, class, _ghost, target, pos): (String, u8, Option<bool>, Value, Option<Table>)| {
+                let Some(class) = SpeakClass::from_u8(class) else {
+                    return Ok(false);
+                };
+                let target = creature_id(lua, &target)?;
+                let pos = pos.map(|pos| get_position(&pos)).transpose()?;
+                Ok(world(lua)?.creature_say(this.0, &text, class, target, pos))
+            },
+        );
+        $methods.add_method("teleportTo", |lua, this, pos: Table| {
+            Ok(world(lua)?.teleport(this.0, get_position(&pos)?))
+        });
+        // addCondition(condition[, force = false])
+        $methods.add_method("addCondition", |lua, this, (condition, force): (AnyUserData, Option<bool>)| {
+            let Some(condition) = condition.borrow_mut::<LuaCondition>()?.condition(lua)? else {
+                return Ok(false);
+            };
+            Ok(world(lua)?.add_condition(this.0, condition, force.unwrap_or(false)))
+        });
+        // removeCondition(conditionType[, conditionId = CONDITIONID_COMBAT[, subId = 0[, force = false]]])
+        // eller removeCondition(condition[, force = false])
+        $methods.add_method(
+            "removeCondition",
+            |lua, this, (value, id, sub_id, force): (Value, Option<Value>, Option<u32>, Option<bool>)| {
+                let (condition_type, id, sub_id, force) = match &value {
+                    Value::UserData(condition) => {
+                        let key = condition.borrow_mut::<LuaCondition>()?.with(lua, |c| (c.condition_type, c.id, c.sub_id))?;
+                        let Some((condition_type, condition_id, sub_id)) = key else {
+                            return Ok(false);
+                        };
+                        (condition_type, condition_id, sub_id, matches!(id, Some(Value::Boolean(true))))
+                    }
+                    value => {
+                        let condition_type = lua.coerce_integer(value.clone())?.unwrap_or(0) as u32;
+                        let id = id.and_then(|id| lua.coerce_integer(id).ok().flatten()).map(|id| id as i8);
+                        (ConditionType(condition_type), condition_id(id)?, sub_id.unwrap_or(0), force.unwrap_or(false))
+                    }
+                };
+                Ok(world(lua)?.remove_condition(this.0, condition_type, id, sub_id, force))
+            },
+        );
+        // getCondition(conditionType[, conditionId = CONDITIONID_COMBAT[, subId = 0]])
+        $methods.add_method("getCondition", |lua, this, (condition_type, id, sub_id): (u32, Option<i8>, Option<u32>)| {
+            let (condition_type, id, sub_id) = (ConditionType(condition_type), condition_id(id)?, sub_id.unwrap_or(0));
+            if !world(lua)?.with_condition(this.0, condition_type, id, sub_id, &mut |_| {}) {
+                return Ok(None);
+            }
+            Ok(Some(LuaCondition::Attached { creature: this.0, condition_type, id, sub_id }))
+        });
+        // hasCondition(conditionType[, subId = 0])
+        $methods.add_method("hasCondition", |lua, this, (condition_type, sub_id): (u32, Option<u32>)| {
+            Ok(world(lua)?.has_condition(this.0, ConditionType(condition_type), sub_id.unwrap_or(0)))
+        });
+        // canSeeCreature(creature): spelare ser andra spelare även när de är
+        // osynliga, och andra osynliga varelser med `CAN_SENSE_INVISIBILITY`
+        $methods.add_method("canSeeCreature", |lua, this, other: Value| {
+            let Some(other) = creature_id(lua, &other)? else {
+                return Ok(false);
+            };
+            if other == this.0 || !world(lua)?.has_condition(other, ConditionType::INVISIBLE, 0) {
+                return Ok(true);
+            }
+            Ok(match read_player(lua, this.0, Player::can_see_invisibility)? {
+                Some(sees_invisible) => sees_invisible || CreatureType::of_id(other) == Some(CreatureType::Player),
+                None => false,
+            })
+        });
+        $methods.add_meta_method(MetaMethod::Eq, |lua, this, other: Value| {
+            Ok(creature_id(lua, &other)? == Some(this.0))
+        });
+        $methods.add_meta_method(MetaMethod::Index, |lua, _, key: Value| {
+            for class in $classes {
+                let value = global_table(lua, class)?.get::<_, Value>(key.clone())?;
+                if !value.is_nil() {
+                    return Ok(value);
+                }
+            }
+            Ok(Value::Nil)
+        });
+    };
+}
+
+fn exists(lua: &Lua, id: u32) -> mlua::Result<bool> {
+    Ok(world(lua)?.creature_name(id).is_some())
+}
+
+impl UserData for LuaCreature {
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        creature_methods!(methods, ["Creature"]);
+    }
+}
+
+impl UserData for LuaMonster {
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        creature_methods!(methods, ["Monster", "Creature"]);
+        methods.add_method("isMonster", |_, _, ()| Ok(true));
+    }
+}
+
+impl UserData for LuaNpc {
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        creature_methods!(methods, ["Npc", "Creature"]);
+        methods.add_method("isNpc", |_, _, ()| Ok(true));
+
+        methods.add_method("getSpeechBubble", |lua, this, ()| {
+            let mut bubble = None;
+            world(lua)?.with_npc(this.0, &mut |npc| bubble = Some(npc.speech_bubble as u8));
+            Ok(bubble)
+        });
+        methods.add_method("setSpeechBubble", |lua, this, bubble: u8| {
+            Ok(world(lua)?.with_npc(this.0, &mut |npc| npc.speech_bubble = entities::SpeechBubble::from_u8(bubble)))
+        });
+        methods.add_method("setMasterPos", |lua, this, (pos, radius): (Table, Option<i32>)| {
+            let pos = get_position(&pos)?;
+            Ok(world(lua)?.with_npc(this.0, &mut |npc| npc.set_master_pos(pos, radius.unwrap_or(1))))
+        });
+    }
+}
+
+/// Läs ett värde från spelaren, nil om den inte finns
+pub(crate) fn read_player<T>(lua: &Lua, id: u32, f: impl Fn(&Player) -> T) -> mlua::Result<Option<T>> {
+    let mut value = None;
+    world(lua)?.with_player(id, &mut |player| value = Some(f(player)));
+    Ok(value)
+}
+
+/// Läs en skill från spelaren, nil om spelaren eller skillen inte finns
+fn read_skill<T>(lua: &Lua, id: u32, skill: u8, f: impl Fn(&Player, Skill) -> T) -> mlua::Result<Option<T>> {
+    let Some(skill) = Skill::from_u8(skill) else {
+        return Ok(None);
+    };
+    read_player(lua, id, |p| f(p, skill))
+}
+
+impl UserData for LuaPlayer {
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        creature_methods!(methods, ["Player", "Creature"]);
+        methods.add_method("isPlayer", |_, _, ()| Ok(true));
+
+        methods.add_method("getGuid", |lua, this, ()| read_player(lua, this.0, |p| p.guid));
+        methods.add_method("getAccountId", |lua, this, ()| read_player(lua, this.0, |p| p.account_id));
+        methods.add_method("getLevel", |lua, this, ()| read_player(lua, this.0, |p| p.level));
+        methods.add_method("getExperience", |lua, this, ()| read_player(lua, this.0, |p| p.experience));
+        // addExperience(experience[, sendText = false])
+        methods.add_method("addExperience", |lua, this, (exp, send_text): (u64, Option<bool>)| {
+            if !exists(lua, this.0)? {
+                return Ok(None);
+            }
+            experience::add_experience(lua, this.0, exp, None, send_text.unwrap_or(false))?;
+            Ok(Some(true))
+        });
+        // removeExperience(experience[, sendText = false])
+        methods.add_method("removeExperience", |lua, this, (exp, send_text): (u64, Option<bool>)| {
+            if !exists(lua, this.0)? {
+                return Ok(None);
+            }
+            experience::remove_experience(lua, this.0, exp, send_text.unwrap_or(false))?;
+            Ok(Some(true))
+        });
+        methods.add_method("getMagicLevel", |lua, this, ()| read_player(lua, this.0, |p| p.magic_level()));
+        methods.add_method("getBaseMagicLevel", |lua, this, ()| read_player(lua, this.0, |p| p.mag_level));
+        methods.add_method("getManaSpent", |lua, this, ()| read_player(lua, this.0, |p| p.mana_spent));
+        // removeManaSpent(amount[, notify = false])
+        methods.add_method("removeManaSpent", |lua, this, (amount, notify): (u64, Option<bool>)| {
+            experience::remove_mana_spent(lua, this.0, amount, notify.unwrap_or(false))
+        });
+        methods.add_method("getSkillLevel", |lua, this, skill: u8| {
+            read_skill(lua, this.0, skill, |p, skill| p.skill(skill).level)
+        });
+        methods.add_method("getEffectiveSkillLevel", |lua, this, skill: u8| {
+            read_skill(lua, this.0, skill, |p, skill| p.skill_level(skill))
+        });
+        methods.add_method("getSkillPercent", |lua, this, skill: u8| {
+            read_skill(lua, this.0, skill, |p, skill| p.skill(skill).percent)
+        });
+        methods.add_method("getSkillTries", |lua, this, skill: u8| {
+            read_skill(lua, this.0, skill, |p, skill| p.skill(skill).tries)
+        });
+        // removeSkillTries(skillType, tries[, notify = false])
+        methods.add_method("removeSkillTries", |lua, this, (skill, tries, notify): (u8, u64, Option<bool>)| {
+            experience::remove_skill_tries(lua, this.0, skill, tries, notify.unwrap_or(false))
+        });
+        methods.add_method("getSex", |lua, this, ()| read_player(lua, this.0, |p| p.sex as u8));
+        methods.add_method("getSoul", |lua, this, ()| read_player(lua, this.0, |p| p.soul));
+        methods.add_method("getCapacity", |lua, this, ()| read_player(lua, this.0, |p| p.capacity));
+        methods.add_method("getFreeCapacity", |lua, this, ()| read_player(lua, this.0, Player::free_capacity));
+        methods.add_method("getBankBalance", |lua, this, ()| read_player(lua, this.0, |p| p.bank_balance));
+        methods.add_method("setBankBalance", |lua, this, balance: i64| {
+            if balance < 0 {
+                return Err(mlua::Error::RuntimeError("Player:setBankBalance: invalid balance".into()));
+            }
+            Ok(world(lua)?.with_player(this.0, &mut |p| p.bank_balance = balance as u64))
+        });
+        methods.add_method("getStorageValue", |lua, this, key: u32| {
+            Ok(read_player(lua, this.0, |p| p.get_storage_value(key))?.flatten().unwrap_or(-1))
+        });
+        methods.add_method("setStorageValue", |lua, this, (key, value): (u32, Option<i32>)| {
+            Ok(world(lua)?.with_player(this.0, &mut |p| p.add_storage_value(key, value)))
+        });
+
+        methods.add_method("getMoney", |lua, this, ()| Ok(world(lua)?.player_money(this.0)));
+        methods.add_method("addMoney", |lua, this, amount: u64| Ok(world(lua)?.add_money(this.0, amount)));
+        methods.add_method("removeMoney", |lua, this, amount: u64| Ok(world(lua)?.remove_money(this.0, amount)));
+        methods.add_method("getItemCount", |lua, this, (item_id, sub_type): (u16, Option<i32>)| {
+            Ok(world(lua)?.item_count(this.0, item_id, sub_type.unwrap_or(-1)))
+        });
+        methods.add_method("removeItem", |lua, this, (item_id, count, sub_type): (u16, u32, Option<i32>)| {
+            Ok(world(lua)?.remove_item(this.0, item_id, count, sub_type.unwrap_or(-1)))
+        });
+        // addItemEx(item[, canDropOnMap = false])
+        methods.add_method("addItemEx", |lua, this, (item, can_drop_on_map): (AnyUserData, Option<bool>)| {
+            let item = item.borrow::<LuaItem>()?;
+            let mut loose = item.0.borrow_mut();
+            let Some(new_item) = loose.as_ref() else {
+                return Ok(ReturnValue::NotPossible as u8);
+            };
+            let drop_on_map = can_drop_on_map.unwrap_or(false);
+            // det som inte får plats hos spelaren hamnar på marken med canDropOnMap
+            if !drop_on_map {
+                if !new_item.item_type().pickupable {
+                    return Ok(ReturnValue::CannotPickup as u8);
+                }
+                if read_player(lua, this.0, |p| p.has_capacity(new_item))? == Some(false) {
+                    return Ok(ReturnValue::NotEnoughCapacity as u8);
+                }
+            }
+            Ok(world(lua)?.add_item_ex(this.0, &mut loose, drop_on_map) as u8)
+        });
+        methods.add_method("getParty", |lua, this, ()| player_party(lua, this.0));
+        methods.add_method("getVocation", |lua, this, ()| match read_player(lua, this.0, |p| p.vocation)? {
+            Some(id) => push_vocation(lua, id),
+            None => Ok(None),
+        });
+        methods.add_method("setVocation", |lua, this, vocation: Value| {
+            let Some(id) = vocation_id(lua, &vocation)? else {
+                return Ok(false);
+            };
+            if push_vocation(lua, id)?.is_none() {
+                return Ok(false);
+            }
+            Ok(world(lua)?.with_player(this.0, &mut |p| p.vocation = id))
+        });
+        methods.add_method("getInstantSpells", |lua, this, ()| spells::player_instant_spells(lua, this.0));
+        methods.add_method("canCast", |lua, this, spell: LuaSpell| spells::player_can_cast(lua, this.0, &spell));
+        methods.add_method("canLearnSpell", |lua, this, name: String| {
+            spells::player_can_learn_spell(lua, this.0, &name)
+        });
+        methods.add_method("hasLearnedSpell", |lua, this, name: String| {
+            read_player(lua, this.0, |p| p.has_learned_instant_spell(&name))
+        });
+        methods.add_method("learnSpell", |lua, this, name: String| {
+            Ok(world(lua)?.with_player(this.0, &mut |p| p.learn_instant_spell(&name)))
+        });
+        methods.add_method("forgetSpell", |lua, this, name: String| {
+            Ok(world(lua)?.with_player(this.0, &mut |p| p.forget_instant_spell(&name)))
+        });
+        methods.add_method("getGuild", |lua, this, ()| player_guild(lua, this.0));
+        methods.add_method("setGuild", |lua, this, guild: Option<LuaGuild>| {
+            set_player_guild(lua, this.0, guild.map(|guild| guild.0))
+        });
+        methods.add_method("getGuildLevel", |lua, this, ()| player_guild_level(lua, this.0));
+        methods.add_method("setGuildLevel", |lua, this, level: u8| set_player_guild_level(lua, this.0, level));
+        methods.add_method("getGuildNick", |lua, this, ()| {
+            Ok(read_player(lua, this.0, |p| p.guild.as_ref().map(|m| m.nick.clone()))?.flatten())
+        });
+        methods.add_method("setGuildNick", |lua, this, nick: String| {
+            Ok(world(lua)?.with_player(this.0, &mut |p| {
+                if let Some(membership) = p.guild.as_mut() {
+                    membership.nick = nick.clone();
+                }
+            }))
+        });
+        methods.add_method("sendTextMessage", |lua, this, (class, text): (u8, String)| {
+            let Some(class) = MessageClass::from_u8(class) else {
+                return Ok(false);
+            };
+            world(lua)?.send_text_message(this.0, class, &text);
+            Ok(true)
+        });
+    }
+}
+
+/// Klassen som en global tabell som går att anropa, `Player(cid)`
+pub(crate) fn register_class(lua: &Lua, name: &str, constructor: mlua::Function) -> mlua::Result<()> {
+    let class = global_table(lua, name)?;
+    let meta = lua.create_table()?;
+    meta.set("__call", constructor)?;
+    class.set_metatable(Some(meta));
+    Ok(())
+}
+
+pub(crate) fn register(lua: &Lua) -> mlua::Result<()> {
+    register_class(
+        lua,
+        "Creature",
+        lua.create_function(|lua, (_, value): (Table, Value)| match creature_id(lua, &value)? {
+            Some(id) => push_creature(lua, id),
+            None => Ok(Value::Nil),
+        })?,
+    )?;
+    for (name, creature_type) in [
+        ("Player", CreatureType::Player),
+        ("Monster", CreatureType::Monster),
+    ] {
+        register_class(
+            lua,
+            name,
+            lua.create_function(move |lua, (_, value): (Table, Value)| match creature_id(lua, &value)? {
+                Some(id) if CreatureType::of_id(id) == Some(creature_type) => push_creature(lua, id),
+                _ => Ok(Value::Nil),
+            })?,
+        )?;
+    }
+    Ok(())
+}
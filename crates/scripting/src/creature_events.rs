@@ -0,0 +1,231 @@
+//! creaturescripts.xml och händelserna i den, motsvarar `CreatureEvents`
+//! och `CreatureEvent` i TFS.
+//!
+//! Varelsen skickas till scripten som ett Lua-värde som anroparen skapar,
+//! eftersom det är spelet som vet hur en spelare eller ett monster ser ut
+//! i Lua. Här hålls bara funktionerna och vilka varelser som lyssnar.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use common::tracing::warn;
+use common::{Error, Result};
+use entities::{Creature, CreatureEventType};
+use mlua::{FromLuaMulti, Function, IntoLuaMulti, Lua, RegistryKey, Value};
+
+use crate::script_manager::{run_file, script_error};
+
+pub struct CreatureEvent {
+    pub name: String,
+    pub event_type: CreatureEventType,
+    function: RegistryKey,
+}
+
+/// Skadan som `onHealthChange`/`onManaChange` får och kan ändra.
+/// Motsvarar fälten i `CombatDamage` som skickas till scripten.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DamageChange {
+    pub primary_value: i32,
+    pub primary_type: u32,
+    pub secondary_value: i32,
+    pub secondary_type: u32,
+    pub origin: u8,
+}
+
+#[derive(Default)]
+pub struct CreatureEvents {
+    /// Nyckeln är namnet i gemener
+    events: HashMap<String, CreatureEvent>,
+}
+
+impl CreatureEvents {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Läs creaturescripts.xml. Scripten ligger i `scripts/` bredvid filen och
+    /// `lib/creaturescripts.lua` körs först om den finns.
+    pub fn load_from_xml(&mut self, lua: &Lua, path: impl AsRef<Path>) -> Result<usize> {
+        let path = path.as_ref();
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| Error::Script(format!("Cannot open {}: {e}", path.display())))?;
+        let doc = roxmltree::Document::parse(&text)
+            .map_err(|e| Error::Script(format!("Cannot parse {}: {e}", path.display())))?;
+
+        let lib = dir.join("lib").join("creaturescripts.lua");
+        if lib.exists() {
+            run_file(lua, &lib)?;
+        }
+
+        let before = self.events.len();
+        for node in doc.root_element().children().filter(|n| n.has_tag_name("event")) {
+            let Some(name) = node.attribute("name") else {
+                warn!("[CreatureEvents::load_from_xml] Missing name for creature event");
+                continue;
+            };
+            let Some(event_type) = node.attribute("type").and_then(CreatureEventType::from_name) else {
+                warn!("[CreatureEvents::load_from_xml] Invalid type for creature event: {name}");
+                continue;
+            };
+            let Some(script) = node.attribute("script") else {
+                warn!("[CreatureEvents::load_from_xml] Missing script for creature event: {name}");
+                continue;
+            };
+
+            if let Err(e) = self.load_event(lua, name, event_type, &dir.join("scripts").join(script)) {
+                warn!("[CreatureEvents::load_from_xml] Cannot load {name}: {e}");
+            }
+        }
+        Ok(self.events.len() - before)
+    }
+
+    /// Kör scriptet och ta hand om dess `onLogin`/`onDeath`/... Den globala
+    /// funktionen nollställs så att nästa script kan använda samma namn,
+    /// som `LuaScriptInterface::getEvent`.
+    fn load_event(&mut self, lua: &Lua, name: &str, event_type: CreatureEventType, script: &Path) -> Result<()> {
+        run_file(lua, script)?;
+        let function_name = event_type.function_name();
+        let globals = lua.globals();
+        let function: Option<Function> = globals.get(function_name).map_err(script_error)?;
+        let Some(function) = function else {
+            return Err(Error::Script(format!("{function_name} not found in {}", script.display())));
+        };
+        globals.set(function_name, Value::Nil).map_err(script_error)?;
+
+        let key = name.to_lowercase();
+        if self.events.get(&key).is_some_and(|event| event.event_type != event_type) {
+            return Err(Error::Script(format!("duplicate event name with a different type: {name}")));
+        }
+        let function = lua.create_registry_value(function).map_err(script_error)?;
+        self.events.insert(key, CreatureEvent { name: name.to_string(), event_type, function });
+        Ok(())
+    }
+
+    pub fn get_event(&self, name: &str) -> Option<&CreatureEvent> {
+        self.events.get(&name.to_lowercase())
+    }
+
+    /// Motsvarar `Creature::registerCreatureEvent`, som `creature:registerEvent(name)`
+    pub fn register_event(&self, creature: &mut Creature, name: &str) -> bool {
+        match self.get_event(name) {
+            Some(event) => creature.register_event(&event.name, event.event_type),
+            None => false,
+        }
+    }
+
+    /// Alla login-händelser, i namnordning; inloggningen avbryts om någon
+    /// inte returnerar true. Motsvarar `CreatureEvents::playerLogin`.
+    pub fn execute_login<'lua>(&self, lua: &'lua Lua, player: Value<'lua>) -> Result<bool> {
+        self.execute_global(lua, CreatureEventType::Login, player)
+    }
+
+    /// Motsvarar `CreatureEvents::playerLogout`
+    pub fn execute_logout<'lua>(&self, lua: &'lua Lua, player: Value<'lua>) -> Result<bool> {
+        self.execute_global(lua, CreatureEventType::Logout, player)
+    }
+
+    fn execute_global<'lua>(&self, lua: &'lua Lua, event_type: CreatureEventType, player: Value<'lua>) -> Result<bool> {
+        let mut events: Vec<&CreatureEvent> = self.events.values().filter(|e| e.event_type == event_type).collect();
+        events.sort_by(|a, b| a.name.cmp(&b.name));
+        for event in events {
+            let result: Value = call(lua, event, player.clone())?;
+            if !is_true(&result) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Kör varelsens registrerade händelser av en typ och samla svaren,
+    /// t.ex. `onThink(creature, interval)` eller `onKill(creature, target)`
+    pub fn execute<'lua, A, R>(
+        &self,
+        lua: &'lua Lua,
+        creature: &Creature,
+        event_type: CreatureEventType,
+        args: A,
+    ) -> Result<Vec<R>>
+    where
+        A: IntoLuaMulti<'lua> + Clone,
+        R: FromLuaMulti<'lua>,
+    {
+        if !creature.has_event_type(event_type) {
+            return Ok(Vec::new());
+        }
+        creature
+            .events(event_type)
+            .filter_map(|name| self.get_event(name))
+            .map(|event| call(lua, event, args.clone()))
+            .collect()
+    }
+
+    /// Som `execute` men för händelser som kan stoppa det som händer
+    /// (preparedeath, advance, textedit): false om någon inte returnerade true
+    pub fn execute_all<'lua, A>(
+        &self,
+        lua: &'lua Lua,
+        creature: &Creature,
+        event_type: CreatureEventType,
+        args: A,
+    ) -> Result<bool>
+    where
+        A: IntoLuaMulti<'lua> + Clone,
+    {
+        let results: Vec<Value> = self.execute(lua, creature, event_type, args)?;
+        Ok(results.iter().all(is_true))
+    }
+
+    /// `onHealthChange`/`onManaChange`: varje script får skadan som förra
+    /// scriptet lämnade. Motsvarar `CreatureEvent::executeHealthChange`.
+    pub fn execute_damage_change<'lua>(
+        &self,
+        lua: &'lua Lua,
+        creature: &Creature,
+        event_type: CreatureEventType,
+        this: Value<'lua>,
+        attacker: Value<'lua>,
+        mut damage: DamageChange,
+    ) -> Result<DamageChange> {
+        if !creature.has_event_type(event_type) {
+            return Ok(damage);
+        }
+        for event in creature.events(event_type).filter_map(|name| self.get_event(name)) {
+            let args = (
+                this.clone(),
+                attacker.clone(),
+                damage.primary_value,
+                damage.primary_type,
+                damage.secondary_value,
+                damage.secondary_type,
+                damage.origin,
+            );
+            let (primary_value, primary_type, secondary_value, secondary_type): (i32, u32, i32, u32) =
+                call(lua, event, args)?;
+            damage = DamageChange {
+                primary_value: primary_value.abs(),
+                primary_type,
+                secondary_value: secondary_value.abs(),
+                secondary_type,
+                origin: damage.origin,
+            };
+        }
+        Ok(damage)
+    }
+}
+
+fn call<'lua, A, R>(lua: &'lua Lua, event: &CreatureEvent, args: A) -> Result<R>
+where
+    A: IntoLuaMulti<'lua>,
+    R: FromLuaMulti<'lua>,
+{
+    let function: Function = lua.registry_value(&event.function).map_err(script_error)?;
+    function
+        .call(args)
+        .map_err(|e| Error::Script(format!("{} ({:?}): {e}", event.name, event.event_type)))
+}
+
+/// Lua-sanning som `LuaScriptInterface::getBoolean`: nil räknas som false
+pub(crate) fn is_true(value: &Value) -> bool {
+    !matches!(value, Value::Nil | Value::Boolean(false))
+}
@@ -0,0 +1,216 @@
+//! Döden, blessings och skallar för scripten och spelet. Förlusten körs
+//! av `rules::death` och `rules::skull`; här körs `onLoseExperience` och
+//! meddelandena skickas. Här finns också spelarens metoder för blessings,
+//! skallar och dödsstraffet.
+
+use std::rc::Rc;
+
+use common::{MessageClass, Result};
+use entities::creature::Skull;
+use mlua::{Lua, Value};
+use rules::condition::ConditionEvent;
+use rules::death::{self, DeathConfig};
+use rules::skull::{self, SkullConfig, SkullUpdate, TargetPlayer};
+
+use crate::creature::{creature_id, push_creature, read_player, world};
+use crate::experience::{call_count_event, with_vocation};
+use crate::guild::with_guilds;
+use crate::script_manager::{global_table, script_error};
+use crate::vocation;
+
+/// Inställningarna som app data
+#[derive(Clone, Default)]
+struct DeathHandle(Rc<(DeathConfig, SkullConfig)>);
+
+fn config(lua: &Lua) -> Rc<(DeathConfig, SkullConfig)> {
+    lua.app_data_ref::<DeathHandle>().map(|handle| handle.0.clone()).unwrap_or_default()
+}
+
+pub(crate) fn set_config(lua: &Lua, death: DeathConfig, skull: SkullConfig) {
+    lua.set_app_data(DeathHandle(Rc::new((death, skull))));
+}
+
+/// `Player::getLostPercent` med spelarens yrke, nil om spelaren inte finns
+fn lost_percent(lua: &Lua, player: u32) -> mlua::Result<Option<f64>> {
+    let vocations = vocation::registry(lua)?;
+    let config = config(lua);
+    read_player(lua, player, |p| death::get_lost_percent(p, vocations.is_promoted(p.vocation), &config.0))
+}
+
+/// Målet som anfallaren ser det, nil om det inte är en spelare
+fn target_player(lua: &Lua, attacker: u32, target: u32) -> mlua::Result<Option<TargetPlayer>> {
+    let guild = |id| read_player(lua, id, |p| p.guild.as_ref().map(|g| g.guild_id)).map(Option::flatten);
+    let (attacker_guild, target_guild) = (guild(attacker)?, guild(target)?);
+    let in_war = with_guilds(lua, |guilds| guilds.is_in_war(attacker_guild, target_guild)).unwrap_or(false);
+    read_player(lua, target, |p| TargetPlayer::new(p, attacker, in_war))
+}
+
+/// Spelaren anföll en annan spelare utanför partyt och gillet, som
+/// `CombatEvent::InFight` med `pvp_target`
+pub(crate) fn on_attacked_player(lua: &Lua, player: u32, target: u32) -> mlua::Result<()> {
+    let Some(target_player) = target_player(lua, player, target)? else {
+        return Ok(());
+    };
+    let world_type = config(lua).1.world_type;
+    let mut update = None;
+    let world = world(lua)?;
+    world.with_player(player, &mut |p| update = skull::on_attacked_player(p, &target_player, world_type));
+    match update {
+        Some(SkullUpdate::Changed) => _ = world.set_creature_skull(player, Skull::White),
+        Some(SkullUpdate::Target) => world.send_creature_skull(target, player),
+        None => {}
+    }
+    Ok(())
+}
+
+/// Döden för spelet
+pub struct Death<'lua> {
+    pub(crate) lua: &'lua Lua,
+}
+
+impl Death<'_> {
+    /// Förlusten när spelaren dött utanför en pvp-zon: magisk nivå och
+    /// skills, sedan erfarenheten efter `onLoseExperience`, och till sist
+    /// blessingsen, hälsan och de sparade conditions. Spelet skickar
+    /// statistiken och skillsen, och sedan spelaren till templet.
+    /// Motsvarar `Player::death` när spelaren förlorar något.
+    pub fn player_death(
+        &self,
+        player: u32,
+        unfair_fight_reduction: u8,
+        last_hit_by_player: bool,
+    ) -> Result<Vec<ConditionEvent>> {
+        self.player_death_lua(player, unfair_fight_reduction, last_hit_by_player).map_err(script_error)
+    }
+
+    fn player_death_lua(
+        &self,
+        player: u32,
+        unfair_fight_reduction: u8,
+        last_hit_by_player: bool,
+    ) -> mlua::Result<Vec<ConditionEvent>> {
+        let lua = self.lua;
+        let Some(lost_percent) = lost_percent(lua, player)? else {
+            return Ok(Vec::new());
+        };
+        let loss_percent = death::death_loss_percent(lost_percent, unfair_fight_reduction);
+        let exp = with_vocation(lua, player, |p, vocation| {
+            death::lose_skills(p, vocation, loss_percent);
+            death::lost_experience(p, loss_percent)
+        })?
+        .unwrap_or(0);
+
+        let exp = call_count_event(lua, "onLoseExperience", exp, (push_creature(lua, player)?, exp));
+        let advance = with_vocation(lua, player, |p, vocation| death::lose_experience(p, vocation, exp))?.flatten();
+        let world = world(lua)?;
+        if let Some(advance) = advance {
+            world.send_text_message(player, MessageClass::EventAdvance, &advance.message());
+            world.player_advance(player, &advance);
+        }
+
+        let mut events = Vec::new();
+        world.with_player(player, &mut |p| events = death::revive(p, last_hit_by_player));
+        Ok(events)
+    }
+
+    /// Andelen spelaren skulle förlora nu, utan orättvis strid
+    pub fn get_lost_percent(&self, player: u32) -> Result<Option<f64>> {
+        lost_percent(self.lua, player).map_err(script_error)
+    }
+}
+
+/// Blessingen som Lua räknar den, från 1, till biten i `blessings`
+fn blessing_arg(blessing: u8) -> u8 {
+    blessing.wrapping_sub(1)
+}
+
+/// Metoderna ligger i klasstabellerna så att data/lib kan använda dem
+pub(crate) fn register(lua: &Lua) -> mlua::Result<()> {
+    let creature = global_table(lua, "Creature")?;
+    creature.set(
+        "getSkull",
+        lua.create_function(|lua, this: Value| {
+            let Some(id) = creature_id(lua, &this)? else {
+                return Ok(None);
+            };
+            Ok(world(lua)?.creature_skull(id).map(|skull| skull as u8))
+        })?,
+    )?;
+    creature.set(
+        "setSkull",
+        lua.create_function(|lua, (this, skull): (Value, u8)| {
+            let Some(id) = creature_id(lua, &this)? else {
+                return Ok(None);
+            };
+            Ok(Some(world(lua)?.set_creature_skull(id, Skull::from_u8(skull))))
+        })?,
+    )?;
+
+    let player = global_table(lua, "Player")?;
+    player.set(
+        "getSkullTime",
+        lua.create_function(|lua, this: Value| {
+            let Some(id) = creature_id(lua, &this)? else {
+                return Ok(None);
+            };
+            read_player(lua, id, |p| p.skull_ticks)
+        })?,
+    )?;
+    player.set(
+        "setSkullTime",
+        lua.create_function(|lua, (this, ticks): (Value, i64)| {
+            let Some(id) = creature_id(lua, &this)? else {
+                return Ok(None);
+            };
+            Ok(Some(world(lua)?.with_player(id, &mut |p| p.skull_ticks = ticks)))
+        })?,
+    )?;
+    player.set(
+        "getDeathPenalty",
+        lua.create_function(|lua, this: Value| {
+            let Some(id) = creature_id(lua, &this)? else {
+                return Ok(None);
+            };
+            Ok(lost_percent(lua, id)?.map(|percent| percent * 100.0))
+        })?,
+    )?;
+    player.set(
+        "hasBlessing",
+        lua.create_function(|lua, (this, blessing): (Value, u8)| {
+            let Some(id) = creature_id(lua, &this)? else {
+                return Ok(None);
+            };
+            read_player(lua, id, |p| p.has_blessing(blessing_arg(blessing)))
+        })?,
+    )?;
+    player.set(
+        "addBlessing",
+        lua.create_function(|lua, (this, blessing): (Value, u8)| {
+            let Some(id) = creature_id(lua, &this)? else {
+                return Ok(None);
+            };
+            let blessing = blessing_arg(blessing);
+            let mut added = false;
+            world(lua)?.with_player(id, &mut |p| {
+                added = !p.has_blessing(blessing);
+                p.add_blessing(blessing);
+            });
+            Ok(Some(added))
+        })?,
+    )?;
+    player.set(
+        "removeBlessing",
+        lua.create_function(|lua, (this, blessing): (Value, u8)| {
+            let Some(id) = creature_id(lua, &this)? else {
+                return Ok(None);
+            };
+            let blessing = blessing_arg(blessing);
+            let mut removed = false;
+            world(lua)?.with_player(id, &mut |p| {
+                removed = p.has_blessing(blessing);
+                p.remove_blessing(blessing);
+            });
+            Ok(Some(removed))
+        })?,
+    )
+}
@@ -0,0 +1,316 @@
+//! Erfarenhet och skills med händelserna i events.xml. Motsvarar de delar
+//! av `Player::addExperience`, `removeExperience`, `addManaSpent` och
+//! `addSkillAdvance` i TFS som kör `g_events` och skickar meddelandena;
+//! själva ändringen görs av `rules::experience`. Här finns också
+//! `Game.getExperienceStage` och spelarens metoder för erfarenhet och skills.
+
+use std::rc::Rc;
+
+use common::tracing::warn;
+use common::{MessageClass, Result};
+use entities::{Player, PlayerFlags, Skill};
+use mlua::{IntoLuaMulti, Lua, Value};
+use rules::experience::{self, Advance, AdvanceSkill, ExperienceStages, SKILL_MAGLEVEL};
+use rules::vocation::Vocation;
+
+use crate::creature::{creature_id, push_creature, read_player, world};
+use crate::hooks;
+use crate::script_manager::{global_table, script_error};
+use crate::vocation;
+
+/// Stegen som app data
+#[derive(Clone)]
+struct StagesHandle(Rc<ExperienceStages>);
+
+fn stages(lua: &Lua) -> mlua::Result<Rc<ExperienceStages>> {
+    lua.app_data_ref::<StagesHandle>()
+        .map(|handle| handle.0.clone())
+        .ok_or_else(|| mlua::Error::RuntimeError("no experience stages registered".into()))
+}
+
+/// Kör `f` på spelaren och dess yrke, nil om någon av dem saknas
+pub(crate) fn with_vocation<T>(lua: &Lua, id: u32, mut f: impl FnMut(&mut Player, &Vocation) -> T) -> mlua::Result<Option<T>> {
+    let vocations = vocation::registry(lua)?;
+    let mut result = None;
+    world(lua)?.with_player(id, &mut |player| {
+        if let Some(vocation) = vocations.get_vocation(player.vocation) {
+            result = Some(f(player, vocation));
+        }
+    });
+    Ok(result)
+}
+
+/// Det händelsen returnerade som ett antal. Som `getNumber<uint64_t>` blir
+/// nil och annat som inte är ett tal 0.
+fn event_count(lua: &Lua, value: Value) -> u64 {
+    lua.coerce_number(value).ok().flatten().map_or(0, |count| count.max(0.0) as u64)
+}
+
+/// Kör händelsen; blir det fel loggas det och `count` gäller oförändrat
+pub(crate) fn call_count_event<'lua>(lua: &'lua Lua, method: &str, count: u64, args: impl IntoLuaMulti<'lua>) -> u64 {
+    match hooks::call::<Value>(lua, "Player", method, args) {
+        Ok(Some(value)) => event_count(lua, value),
+        Ok(None) => count,
+        Err(e) => {
+            warn!("[Events::eventPlayer{}] {e}", method.trim_start_matches("on"));
+            count
+        }
+    }
+}
+
+/// Meddela spelaren och spelet. Som i TFS kommer `onAdvance` före
+/// meddelandet för nivån men efter för magisk nivå och skills.
+fn notify_advance(lua: &Lua, player: u32, advance: &Advance) -> mlua::Result<()> {
+    let world = world(lua)?;
+    if advance.skill == AdvanceSkill::Level {
+        world.player_advance(player, advance);
+        world.send_text_message(player, MessageClass::EventAdvance, &advance.message());
+    } else {
+        world.send_text_message(player, MessageClass::EventAdvance, &advance.message());
+        world.player_advance(player, advance);
+    }
+    Ok(())
+}
+
+fn experience_points(exp: u64) -> &'static str {
+    if exp != 1 {
+        "experience points"
+    } else {
+        "experience point"
+    }
+}
+
+/// Motsvarar `Player::addExperience(source, exp, sendText)`
+pub(crate) fn add_experience(
+    lua: &Lua,
+    player: u32,
+    exp: u64,
+    source: Option<u32>,
+    send_text: bool,
+) -> mlua::Result<()> {
+    let Some(level) = read_player(lua, player, |p| p.level)? else {
+        return Ok(());
+    };
+    if experience::is_max_level(level) {
+        world(lua)?.with_player(player, &mut |p| p.level_percent = 0);
+        return Ok(());
+    }
+
+    let source = match source {
+        Some(source) => push_creature(lua, source)?,
+        None => Value::Nil,
+    };
+    let args = (push_creature(lua, player)?, source, exp, exp);
+    let exp = call_count_event(lua, "onGainExperience", exp, args);
+    if exp == 0 {
+        return Ok(());
+    }
+
+    let advance = with_vocation(lua, player, |p, vocation| experience::add_experience(p, vocation, exp))?.flatten();
+    if send_text {
+        let text = format!("You gained {exp} {}.", experience_points(exp));
+        world(lua)?.send_text_message(player, MessageClass::Experience, &text);
+    }
+    if let Some(advance) = advance {
+        notify_advance(lua, player, &advance)?;
+    }
+    Ok(())
+}
+
+/// Motsvarar `Player::removeExperience(exp, sendText)`
+pub(crate) fn remove_experience(lua: &Lua, player: u32, exp: u64, send_text: bool) -> mlua::Result<()> {
+    let Some(experience) = read_player(lua, player, |p| p.experience)? else {
+        return Ok(());
+    };
+    if experience == 0 || exp == 0 {
+        return Ok(());
+    }
+
+    let exp = call_count_event(lua, "onLoseExperience", exp, (push_creature(lua, player)?, exp));
+    if exp == 0 {
+        return Ok(());
+    }
+
+    let advance = with_vocation(lua, player, |p, vocation| experience::remove_experience(p, vocation, exp))?.flatten();
+    let world = world(lua)?;
+    if send_text {
+        let lost = experience.min(exp);
+        let text = format!("You lost {lost} {}.", experience_points(lost));
+        world.send_text_message(player, MessageClass::Experience, &text);
+    }
+    if let Some(advance) = advance {
+        // ingen onAdvance när spelaren går ned, som i TFS
+        world.send_text_message(player, MessageClass::EventAdvance, &advance.message());
+        world.player_advance(player, &advance);
+    }
+    Ok(())
+}
+
+/// Motsvarar `Player::addManaSpent`
+pub(crate) fn add_mana_spent(lua: &Lua, player: u32, amount: u64) -> mlua::Result<()> {
+    let max = with_vocation(lua, player, |p, vocation| {
+        p.has_flag(PlayerFlags::NOT_GAIN_MANA) || experience::is_max_magic_level(vocation, p.mag_level)
+    })?;
+    if max != Some(false) {
+        return Ok(());
+    }
+
+    let args = (push_creature(lua, player)?, SKILL_MAGLEVEL, amount);
+    let amount = call_count_event(lua, "onGainSkillTries", amount, args);
+    if amount == 0 {
+        return Ok(());
+    }
+
+    let advances = with_vocation(lua, player, |p, vocation| experience::add_mana_spent(p, vocation, amount))?;
+    for advance in advances.unwrap_or_default() {
+        notify_advance(lua, player, &advance)?;
+    }
+    Ok(())
+}
+
+/// Motsvarar `Player::addSkillAdvance`
+pub(crate) fn add_skill_tries(lua: &Lua, player: u32, skill: Skill, count: u64) -> mlua::Result<()> {
+    let max =
+        with_vocation(lua, player, |p, vocation| experience::is_max_skill(vocation, skill, p.skill(skill).level))?;
+    if max != Some(false) {
+        return Ok(());
+    }
+
+    let args = (push_creature(lua, player)?, skill as u8, count);
+    let count = call_count_event(lua, "onGainSkillTries", count, args);
+    if count == 0 {
+        return Ok(());
+    }
+
+    let advances = with_vocation(lua, player, |p, vocation| experience::add_skill_advance(p, vocation, skill, count))?;
+    for advance in advances.unwrap_or_default() {
+        notify_advance(lua, player, &advance)?;
+    }
+    Ok(())
+}
+
+/// Nedgraderingen i `removeManaSpent` och `removeSkillTries`; bara
+/// meddelandet, inga händelser
+fn notify_downgrade(lua: &Lua, player: u32, advance: Option<Advance>, notify: bool) -> mlua::Result<()> {
+    if let Some(advance) = advance.filter(|_| notify) {
+        let world = world(lua)?;
+        world.send_text_message(player, MessageClass::EventAdvance, &advance.message());
+        world.player_advance(player, &advance);
+    }
+    Ok(())
+}
+
+/// Erfarenheten och skillsen för spelet, som kör händelserna i events.xml
+/// innan något ändras
+pub struct Experience<'lua> {
+    pub(crate) lua: &'lua Lua,
+}
+
+impl Experience<'_> {
+    /// Erfarenhet från `source`, t.ex. ett dödat monster
+    pub fn add_experience(&self, player: u32, exp: u64, source: Option<u32>, send_text: bool) -> Result<()> {
+        add_experience(self.lua, player, exp, source, send_text).map_err(script_error)
+    }
+
+    /// Erfarenheten för ett dödat monster, inget med
+    /// `PlayerFlag_NotGainExperience`. Motsvarar `Player::onGainExperience`
+    /// utan partyt; spelet delar upp erfarenheten först.
+    pub fn on_gain_experience(&self, player: u32, exp: u64, target: u32) -> Result<()> {
+        let not_gain = read_player(self.lua, player, |p| p.has_flag(PlayerFlags::NOT_GAIN_EXPERIENCE));
+        if not_gain.map_err(script_error)? != Some(false) {
+            return Ok(());
+        }
+        self.add_experience(player, exp, Some(target), true)
+    }
+
+    pub fn remove_experience(&self, player: u32, exp: u64, send_text: bool) -> Result<()> {
+        remove_experience(self.lua, player, exp, send_text).map_err(script_error)
+    }
+
+    pub fn add_mana_spent(&self, player: u32, amount: u64) -> Result<()> {
+        add_mana_spent(self.lua, player, amount).map_err(script_error)
+    }
+
+    pub fn add_skill_tries(&self, player: u32, skill: Skill, count: u64) -> Result<()> {
+        add_skill_tries(self.lua, player, skill, count).map_err(script_error)
+    }
+
+    /// Motsvarar `Game::getExperienceStage`
+    pub fn get_experience_stage(&self, level: u32) -> Result<f64> {
+        Ok(stages(self.lua).map_err(script_error)?.get_experience_stage(level))
+    }
+}
+
+/// Skillen med id:t, fel som i TFS om det inte är någon
+fn skill_arg(skill: u8) -> mlua::Result<Skill> {
+    Skill::from_u8(skill).ok_or_else(|| mlua::Error::RuntimeError(format!("invalid skill type {skill}")))
+}
+
+/// `Player.addSkillTries` och `Player.addManaSpent` ligger i klasstabellen,
+/// inte på objektet, så att data/lib/core/player.lua kan lägga sig runt dem
+pub(crate) fn register_player(lua: &Lua) -> mlua::Result<()> {
+    let player = global_table(lua, "Player")?;
+    player.set(
+        "addSkillTries",
+        lua.create_function(|lua, (this, skill, tries): (Value, u8, u64)| {
+            let Some(id) = creature_id(lua, &this)? else {
+                return Ok(None);
+            };
+            add_skill_tries(lua, id, skill_arg(skill)?, tries)?;
+            Ok(Some(true))
+        })?,
+    )?;
+    player.set(
+        "addManaSpent",
+        lua.create_function(|lua, (this, amount): (Value, u64)| {
+            let Some(id) = creature_id(lua, &this)? else {
+                return Ok(None);
+            };
+            add_mana_spent(lua, id, amount)?;
+            Ok(Some(true))
+        })?,
+    )
+}
+
+/// `removeManaSpent(amount[, notify = false])`
+pub(crate) fn remove_mana_spent(lua: &Lua, player: u32, amount: u64, notify: bool) -> mlua::Result<Option<bool>> {
+    let Some(advance) =
+        with_vocation(lua, player, |p, vocation| experience::remove_mana_spent(p, vocation, amount))?
+    else {
+        return Ok(None);
+    };
+    notify_downgrade(lua, player, advance, notify)?;
+    Ok(Some(true))
+}
+
+/// `removeSkillTries(skillType, tries[, notify = false])`
+pub(crate) fn remove_skill_tries(
+    lua: &Lua,
+    player: u32,
+    skill: u8,
+    count: u64,
+    notify: bool,
+) -> mlua::Result<Option<bool>> {
+    let skill = skill_arg(skill)?;
+    let Some(advance) =
+        with_vocation(lua, player, |p, vocation| experience::remove_skill_tries(p, vocation, skill, count))?
+    else {
+        return Ok(None);
+    };
+    notify_downgrade(lua, player, advance, notify)?;
+    Ok(Some(true))
+}
+
+/// `Game.getExperienceStage(level)` med stegen och `Game.getExperienceForLevel(level)`
+pub fn register(lua: &Lua, experience_stages: ExperienceStages) -> mlua::Result<()> {
+    lua.set_app_data(StagesHandle(Rc::new(experience_stages)));
+    let game = global_table(lua, "Game")?;
+    game.set(
+        "getExperienceStage",
+        lua.create_function(|lua, level: u32| Ok(stages(lua)?.get_experience_stage(level)))?,
+    )?;
+    game.set(
+        "getExperienceForLevel",
+        lua.create_function(|_, level: u32| Ok(if level == 0 { 0 } else { Player::get_exp_for_level(level) }))?,
+    )
+}
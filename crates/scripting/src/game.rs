@@ -0,0 +1,38 @@
+//! `Game`-funktioner och de gamla globala motsvarigheterna, motsvarar
+//! luaGame* och luaGet/SetWorld* i TFS.
+
+use mlua::{Function, Lua};
+use world::{LightInfo, WorldLight};
+
+use crate::script_manager::global_table;
+
+/// `Game.getWorldTime()`, `Game.getWorldLight()`, `Game.setWorldLight(level, color)`
+/// samt `getWorldTime`/`getWorldLight`/`setWorldLight` för äldre scripts
+pub fn register_light(lua: &Lua, light: WorldLight) -> mlua::Result<()> {
+    let world_time = {
+        let light = light.clone();
+        lua.create_function(move |_, ()| Ok(light.world_time()))?
+    };
+    let world_light = {
+        let light = light.clone();
+        lua.create_function(move |_, ()| {
+            let info = light.light_info();
+            Ok((info.level, info.color))
+        })?
+    };
+    let set_world_light = lua.create_function(move |_, (level, color): (u8, u8)| {
+        Ok(light.set_world_light(LightInfo::new(level, color)))
+    })?;
+
+    let game = global_table(lua, "Game")?;
+    let globals = lua.globals();
+    for (name, function) in [
+        ("getWorldTime", world_time),
+        ("getWorldLight", world_light),
+        ("setWorldLight", set_world_light),
+    ] {
+        game.set(name, function.clone())?;
+        globals.set::<_, Function>(name, function)?;
+    }
+    Ok(())
+}
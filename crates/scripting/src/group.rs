@@ -0,0 +1,117 @@
+//! Lua-klassen `Group` och spelarens grupp och kontotyp, motsvarar
+//! luaGroup* och `Player:getGroup`/`getAccountType` i TFS. `Player.hasFlag`
+//! finns i data/lib och frågar gruppen.
+
+use std::sync::Arc;
+
+use entities::player::AccountType;
+use entities::{Group, Groups, PlayerFlags};
+use mlua::{Lua, MetaMethod, Table, UserData, UserDataMethods, Value};
+
+use crate::creature::{creature_id, read_player, register_class, world};
+use crate::script_manager::global_table;
+
+/// Registret som app data
+#[derive(Clone)]
+struct GroupRegistry(Arc<Groups>);
+
+#[derive(Clone, Copy)]
+pub struct LuaGroup(pub u16);
+
+fn registry(lua: &Lua) -> mlua::Result<Arc<Groups>> {
+    lua.app_data_ref::<GroupRegistry>()
+        .map(|registry| registry.0.clone())
+        .ok_or_else(|| mlua::Error::RuntimeError("no groups registered".into()))
+}
+
+/// Läs ett värde från gruppen, nil om den inte finns
+fn read_group<T>(lua: &Lua, id: u16, f: impl FnOnce(&Group) -> T) -> mlua::Result<Option<T>> {
+    Ok(registry(lua)?.get_group(id).map(f))
+}
+
+/// Gruppen med id:t, nil om den inte finns
+fn push_group(lua: &Lua, id: u16) -> mlua::Result<Option<LuaGroup>> {
+    read_group(lua, id, |_| LuaGroup(id))
+}
+
+impl UserData for LuaGroup {
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method("getId", |_, this, ()| Ok(this.0));
+        methods.add_method("getName", |lua, this, ()| read_group(lua, this.0, |g| g.name.clone()));
+        methods.add_method("getFlags", |lua, this, ()| read_group(lua, this.0, |g| g.flags.0));
+        methods.add_method("getAccess", |lua, this, ()| read_group(lua, this.0, |g| g.access));
+        methods.add_method("getMaxDepotItems", |lua, this, ()| read_group(lua, this.0, |g| g.max_depot_items));
+        methods.add_method("getMaxVipEntries", |lua, this, ()| read_group(lua, this.0, |g| g.max_vip_entries));
+        methods.add_method("hasFlag", |lua, this, flag: u64| {
+            read_group(lua, this.0, |g| g.flags.intersects(PlayerFlags(flag)))
+        });
+
+        methods.add_meta_method(MetaMethod::Eq, |_, this, other: LuaGroup| Ok(this.0 == other.0));
+        methods.add_meta_method(MetaMethod::Index, |lua, _, key: Value| {
+            global_table(lua, "Group")?.get::<_, Value>(key)
+        });
+    }
+}
+
+impl<'lua> mlua::FromLua<'lua> for LuaGroup {
+    fn from_lua(value: Value<'lua>, _: &'lua Lua) -> mlua::Result<Self> {
+        match value {
+            Value::UserData(userdata) => Ok(*userdata.borrow::<LuaGroup>()?),
+            _ => Err(mlua::Error::FromLuaConversionError { from: value.type_name(), to: "Group", message: None }),
+        }
+    }
+}
+
+pub fn register(lua: &Lua, groups: &Groups) -> mlua::Result<()> {
+    lua.set_app_data(GroupRegistry(Arc::new(groups.clone())));
+
+    // Group(id)
+    register_class(lua, "Group", lua.create_function(|lua, (_, id): (Table, u16)| push_group(lua, id))?)?;
+
+    let player = global_table(lua, "Player")?;
+    player.set(
+        "getGroup",
+        lua.create_function(|lua, this: Value| {
+            let Some(id) = creature_id(lua, &this)? else {
+                return Ok(None);
+            };
+            match read_player(lua, id, |p| p.group.id)? {
+                Some(group) => push_group(lua, group),
+                None => Ok(None),
+            }
+        })?,
+    )?;
+    // spelaren får en kopia av gruppen, som vid inloggningen
+    player.set(
+        "setGroup",
+        lua.create_function(|lua, (this, group): (Value, LuaGroup)| {
+            let Some(id) = creature_id(lua, &this)? else {
+                return Ok(None);
+            };
+            let Some(group) = registry(lua)?.get_group(group.0).cloned() else {
+                return Ok(Some(false));
+            };
+            Ok(Some(world(lua)?.with_player(id, &mut |p| p.group = group.clone())))
+        })?,
+    )?;
+    player.set(
+        "getAccountType",
+        lua.create_function(|lua, this: Value| {
+            let Some(id) = creature_id(lua, &this)? else {
+                return Ok(None);
+            };
+            read_player(lua, id, |p| p.account_type as u8)
+        })?,
+    )?;
+    // databasen ändras när spelaren sparas
+    player.set(
+        "setAccountType",
+        lua.create_function(|lua, (this, account_type): (Value, u8)| {
+            let Some(id) = creature_id(lua, &this)? else {
+                return Ok(None);
+            };
+            let account_type = AccountType::from_u8(account_type);
+            Ok(Some(world(lua)?.with_player(id, &mut |p| p.set_account_type(account_type))))
+        })?,
+    )
+}
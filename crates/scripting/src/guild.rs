@@ -0,0 +1,207 @@
+//! Lua-klassen `Guild` och gillemetoderna på `Player`, motsvarar luaGuild*
+//! och luaPlayer*Guild* i TFS. Ett Lua-objekt är bara gillets id; bara
+//! inlästa gillen (med någon medlem inloggad) går att nå.
+
+use entities::guild::GUILD_RANK_MEMBER;
+use entities::{GuildMembership, GuildRank, Guilds};
+use mlua::{Lua, MetaMethod, Table, UserData, UserDataMethods, Value};
+
+use crate::creature::{creature_id, push_creature, read_player, register_class, world};
+use crate::script_manager::global_table;
+
+#[derive(Clone, Copy)]
+pub struct LuaGuild(pub u32);
+
+/// Kör `f` på gillena, som `with_parties`
+pub(crate) fn with_guilds<T>(lua: &Lua, f: impl FnOnce(&mut Guilds) -> T) -> mlua::Result<T> {
+    let mut f = Some(f);
+    let mut result = None;
+    world(lua)?.with_guilds(&mut |guilds| {
+        if let Some(f) = f.take() {
+            result = Some(f(guilds));
+        }
+    });
+    result.ok_or_else(|| mlua::Error::RuntimeError("guilds not available".into()))
+}
+
+/// `{id, name, level}` som `luaGuildGetRankById`
+fn rank_table<'lua>(lua: &'lua Lua, rank: Option<GuildRank>) -> mlua::Result<Value<'lua>> {
+    let Some(rank) = rank else {
+        return Ok(Value::Nil);
+    };
+    let table = lua.create_table()?;
+    table.set("id", rank.id)?;
+    table.set("name", rank.name)?;
+    table.set("level", rank.level)?;
+    Ok(Value::Table(table))
+}
+
+fn membership(lua: &Lua, player_id: u32) -> mlua::Result<Option<GuildMembership>> {
+    Ok(read_player(lua, player_id, |p| p.guild.clone())?.flatten())
+}
+
+/// Spelarens gille, nil utanför gillen
+pub(crate) fn player_guild(lua: &Lua, player_id: u32) -> mlua::Result<Option<LuaGuild>> {
+    let Some(membership) = membership(lua, player_id)? else {
+        return Ok(None);
+    };
+    let loaded = with_guilds(lua, |guilds| guilds.contains(membership.guild_id))?;
+    Ok(loaded.then_some(LuaGuild(membership.guild_id)))
+}
+
+/// Rangens nivå, nil utanför gillen
+pub(crate) fn player_guild_level(lua: &Lua, player_id: u32) -> mlua::Result<Option<u8>> {
+    let Some(membership) = membership(lua, player_id)? else {
+        return Ok(None);
+    };
+    with_guilds(lua, |guilds| {
+        guilds
+            .get(membership.guild_id)
+            .and_then(|guild| guild.get_rank_by_id(membership.rank_id))
+            .map(|rank| rank.level)
+    })
+}
+
+/// Byt till rangen med nivån i samma gille, motsvarar `luaPlayerSetGuildLevel`
+pub(crate) fn set_player_guild_level(lua: &Lua, player_id: u32, level: u8) -> mlua::Result<bool> {
+    let Some(membership) = membership(lua, player_id)? else {
+        return Ok(false);
+    };
+    let rank = with_guilds(lua, |guilds| {
+        guilds
+            .get(membership.guild_id)
+            .and_then(|guild| guild.get_rank_by_level(level))
+            .map(|rank| rank.id)
+    })?;
+    let Some(rank_id) = rank else {
+        return Ok(false);
+    };
+    Ok(world(lua)?.with_player(player_id, &mut |p| {
+        if let Some(membership) = p.guild.as_mut() {
+            membership.rank_id = rank_id;
+        }
+    }))
+}
+
+/// Gå med i gillet på lägsta rangen, eller lämna det med `None`.
+/// Motsvarar `Player::setGuild`; databasen ändras inte.
+pub(crate) fn set_player_guild(lua: &Lua, player_id: u32, guild_id: Option<u32>) -> mlua::Result<bool> {
+    let old = membership(lua, player_id)?.map(|membership| membership.guild_id);
+    if old == guild_id {
+        return Ok(true);
+    }
+    let new = match guild_id {
+        Some(guild_id) => {
+            let rank = with_guilds(lua, |guilds| {
+                guilds.get(guild_id).and_then(|guild| guild.get_rank_by_level(GUILD_RANK_MEMBER)).map(|rank| rank.id)
+            })?;
+            match rank {
+                Some(rank_id) => Some(GuildMembership { guild_id, rank_id, nick: String::new() }),
+                None => return Ok(false),
+            }
+        }
+        None => None,
+    };
+    if !world(lua)?.with_player(player_id, &mut |p| p.guild = new.clone()) {
+        return Ok(false);
+    }
+    with_guilds(lua, |guilds| {
+        if let Some(guild_id) = guild_id {
+            guilds.add_member(guild_id, player_id);
+        }
+        if let Some(old) = old {
+            guilds.remove_member(old, player_id);
+        }
+    })?;
+    Ok(true)
+}
+
+impl UserData for LuaGuild {
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method("getId", |_, this, ()| Ok(this.0));
+        methods.add_method("getName", |lua, this, ()| {
+            with_guilds(lua, |guilds| guilds.get(this.0).map(|guild| guild.name().to_string()))
+        });
+        methods.add_method("getMembersOnline", |lua, this, ()| {
+            let members = with_guilds(lua, |guilds| {
+                guilds.get(this.0).map(|guild| guild.members_online().collect::<Vec<_>>())
+            })?;
+            let Some(members) = members else {
+                return Ok(Value::Nil);
+            };
+            let table = lua.create_table()?;
+            for id in members {
+                table.push(push_creature(lua, id)?)?;
+            }
+            Ok(Value::Table(table))
+        });
+        methods.add_method("addRank", |lua, this, (id, name, level): (u32, String, u8)| {
+            with_guilds(lua, |guilds| match guilds.get_mut(this.0) {
+                Some(guild) => {
+                    guild.add_rank(id, name, level);
+                    true
+                }
+                None => false,
+            })
+        });
+        methods.add_method("getRankById", |lua, this, id: u32| {
+            let rank = with_guilds(lua, |guilds| guilds.get(this.0).and_then(|guild| guild.get_rank_by_id(id).cloned()))?;
+            rank_table(lua, rank)
+        });
+        methods.add_method("getRankByLevel", |lua, this, level: u8| {
+            let rank = with_guilds(lua, |guilds| {
+                guilds.get(this.0).and_then(|guild| guild.get_rank_by_level(level).cloned())
+            })?;
+            rank_table(lua, rank)
+        });
+        methods.add_method("getMotd", |lua, this, ()| {
+            with_guilds(lua, |guilds| guilds.get(this.0).map(|guild| guild.motd.clone()))
+        });
+        methods.add_method("setMotd", |lua, this, motd: String| {
+            with_guilds(lua, |guilds| match guilds.get_mut(this.0) {
+                Some(guild) => {
+                    guild.motd = motd;
+                    true
+                }
+                None => false,
+            })
+        });
+
+        methods.add_meta_method(MetaMethod::Eq, |_, this, other: LuaGuild| Ok(this.0 == other.0));
+        methods.add_meta_method(MetaMethod::Index, |lua, _, key: Value| {
+            global_table(lua, "Guild")?.get::<_, Value>(key)
+        });
+    }
+}
+
+impl<'lua> mlua::FromLua<'lua> for LuaGuild {
+    fn from_lua(value: Value<'lua>, _: &'lua Lua) -> mlua::Result<Self> {
+        match value {
+            Value::UserData(userdata) => Ok(*userdata.borrow::<LuaGuild>()?),
+            _ => Err(mlua::Error::FromLuaConversionError { from: value.type_name(), to: "Guild", message: None }),
+        }
+    }
+}
+
+pub(crate) fn register(lua: &Lua) -> mlua::Result<()> {
+    // Guild(id), nil om gillet inte är inläst
+    register_class(
+        lua,
+        "Guild",
+        lua.create_function(|lua, (_, id): (Table, u32)| {
+            Ok(with_guilds(lua, |guilds| guilds.contains(id))?.then_some(LuaGuild(id)))
+        })?,
+    )?;
+
+    // isInWar(cid, target), motsvarar `luaIsInWar`
+    let is_in_war = lua.create_function(|lua, (player, target): (Value, Value)| {
+        let (Some(player), Some(target)) = (creature_id(lua, &player)?, creature_id(lua, &target)?) else {
+            return Ok(false);
+        };
+        let guild = membership(lua, player)?.map(|membership| membership.guild_id);
+        let other = membership(lua, target)?.map(|membership| membership.guild_id);
+        with_guilds(lua, |guilds| guilds.is_in_war(guild, other))
+    })?;
+    lua.globals().set("isInWar", is_in_war)?;
+    Ok(())
+}
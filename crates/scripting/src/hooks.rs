@@ -0,0 +1,135 @@
+//! data/events/events.xml, motsvarar `Events` i TFS. Händelserna är
+//! metoder på klasstabellerna (`Party:onJoin`, `Player:onLook`, ...) som
+//! spelet anropar; bara de som är påslagna i filen körs. Scripten ligger i
+//! scripts/<klass>.lua bredvid filen och laddas en gång per klass.
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use common::tracing::warn;
+use common::{Error, Result};
+use entities::Outfit;
+use mlua::{FromLuaMulti, Function, IntoLuaMulti, Lua, Value};
+
+use crate::condition::push_outfit;
+use crate::creature::push_creature;
+use crate::creature_events::is_true;
+use crate::party::LuaParty;
+use crate::script_manager::{global_table, run_file, script_error};
+
+/// Påslagna (klass, metod)
+#[derive(Default)]
+pub(crate) struct EnabledEvents(HashSet<(String, String)>);
+
+/// Läs events.xml och kör klassernas scripts. Returnerar antalet påslagna händelser.
+pub(crate) fn load(lua: &Lua, path: &Path) -> Result<usize> {
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| Error::Script(format!("Cannot open {}: {e}", path.display())))?;
+    let doc = roxmltree::Document::parse(&text)
+        .map_err(|e| Error::Script(format!("Cannot parse {}: {e}", path.display())))?;
+
+    let mut classes = HashSet::new();
+    let mut enabled = EnabledEvents::default();
+    for node in doc.root_element().children().filter(|n| n.has_tag_name("event")) {
+        if !node.attribute("enabled").is_some_and(|value| value == "1" || value == "true") {
+            continue;
+        }
+        let (Some(class), Some(method)) = (node.attribute("class"), node.attribute("method")) else {
+            warn!("[Events::load] Missing class or method for event");
+            continue;
+        };
+        if classes.insert(class.to_string()) {
+            let script = dir.join("scripts").join(format!("{}.lua", class.to_lowercase()));
+            if let Err(e) = run_file(lua, &script) {
+                warn!("[Events::load] Cannot load script: {e}");
+                continue;
+            }
+        }
+
+        let function = global_table(lua, class)
+            .and_then(|table| table.get::<_, Option<Function>>(method))
+            .map_err(script_error)?;
+        if function.is_none() {
+            warn!("[Events::load] Event {class}:{method} not found");
+            continue;
+        }
+        enabled.0.insert((class.to_string(), method.to_string()));
+    }
+
+    let count = enabled.0.len();
+    lua.set_app_data(enabled);
+    Ok(count)
+}
+
+/// Anropa `class:method(...)`. `None` om händelsen inte är påslagen.
+pub(crate) fn call<'lua, R>(lua: &'lua Lua, class: &str, method: &str, args: impl IntoLuaMulti<'lua>) -> mlua::Result<Option<R>>
+where
+    R: FromLuaMulti<'lua>,
+{
+    let enabled = lua
+        .app_data_ref::<EnabledEvents>()
+        .is_some_and(|enabled| enabled.0.contains(&(class.to_string(), method.to_string())));
+    if !enabled {
+        return Ok(None);
+    }
+    let function: Function = global_table(lua, class)?.get(method)?;
+    function.call(args).map(Some)
+}
+
+/// Party-händelserna; nej om scriptet returnerar något annat än true
+pub(crate) fn party_event(lua: &Lua, method: &str, party_id: u32, player: Option<u32>) -> mlua::Result<bool> {
+    let party = lua.create_userdata(LuaParty(party_id))?;
+    let result: Option<Value> = match player {
+        Some(player) => call(lua, "Party", method, (party, push_creature(lua, player)?))?,
+        None => call(lua, "Party", method, party)?,
+    };
+    Ok(result.as_ref().is_none_or(is_true))
+}
+
+/// Erfarenheten som partyt delar, efter `Party:onShareExperience`
+pub(crate) fn party_share_experience(lua: &Lua, party_id: u32, exp: u64) -> mlua::Result<u64> {
+    let party = lua.create_userdata(LuaParty(party_id))?;
+    let result: Option<f64> = call(lua, "Party", "onShareExperience", (party, exp))?;
+    Ok(result.map_or(exp, |exp| exp.max(0.0) as u64))
+}
+
+/// `Creature:onChangeOutfit`, som också kör hästens callbacks. Nej om
+/// scriptet returnerar något annat än true.
+pub(crate) fn change_outfit(lua: &Lua, creature: u32, outfit: &Outfit) -> mlua::Result<bool> {
+    let args = (push_creature(lua, creature)?, push_outfit(lua, outfit)?);
+    let result: Option<Value> = call(lua, "Creature", "onChangeOutfit", args)?;
+    Ok(result.as_ref().is_none_or(is_true))
+}
+
+/// Händelserna i events.xml för spelet. Spelet anropar dem innan det
+/// ändrar något, som `g_events->eventPartyOnJoin` i TFS.
+pub struct Events<'lua> {
+    pub(crate) lua: &'lua Lua,
+}
+
+impl Events<'_> {
+    /// Får spelaren gå med i partyt
+    pub fn on_party_join(&self, party_id: u32, player_id: u32) -> Result<bool> {
+        party_event(self.lua, "onJoin", party_id, Some(player_id)).map_err(script_error)
+    }
+
+    pub fn on_party_leave(&self, party_id: u32, player_id: u32) -> Result<bool> {
+        party_event(self.lua, "onLeave", party_id, Some(player_id)).map_err(script_error)
+    }
+
+    pub fn on_party_disband(&self, party_id: u32) -> Result<bool> {
+        party_event(self.lua, "onDisband", party_id, None).map_err(script_error)
+    }
+
+    /// Hur mycket var och en i partyt får av `exp`
+    pub fn on_party_share_experience(&self, party_id: u32, exp: u64) -> Result<u64> {
+        party_share_experience(self.lua, party_id, exp).map_err(script_error)
+    }
+
+    /// Får varelsen byta utseende eller häst, innan
+    /// `Game::internalCreatureChangeOutfit` visar det
+    pub fn on_change_outfit(&self, creature_id: u32, outfit: &Outfit) -> Result<bool> {
+        change_outfit(self.lua, creature_id, outfit).map_err(script_error)
+    }
+}
@@ -0,0 +1,132 @@
+//! Lua-klassen `House`, `Player:getHouse` och `Game.getHouses`, motsvarar
+//! luaHouse* i TFS. Husen ägs av världen; ett Lua-objekt är bara husets id
+//! och ändringar går genom `ScriptWorld`, som sparar ägarbyten.
+
+use common::Position;
+use mlua::{Lua, MetaMethod, Table, UserData, UserDataMethods, Value};
+use world::House;
+
+use crate::creature::{creature_id, read_player, register_class, world};
+use crate::position::{get_position, push_position};
+use crate::script_manager::global_table;
+use crate::tile::LuaTile;
+
+#[derive(Clone, Copy)]
+pub struct LuaHouse(pub u32);
+
+/// Läs ett värde från huset, nil om det inte finns
+fn read_house<T>(lua: &Lua, id: u32, f: impl Fn(&House) -> T) -> mlua::Result<Option<T>> {
+    let mut value = None;
+    world(lua)?.with_houses(&mut |houses| value = houses.get_house(id).map(&f));
+    Ok(value)
+}
+
+/// Huset med id:t, nil om det inte finns
+pub(crate) fn push_house(lua: &Lua, id: u32) -> mlua::Result<Option<LuaHouse>> {
+    read_house(lua, id, |_| LuaHouse(id))
+}
+
+/// Tiles på positionerna, i husets ordning
+fn push_tiles<'lua>(lua: &'lua Lua, positions: Option<Vec<Position>>) -> mlua::Result<Option<Table<'lua>>> {
+    match positions {
+        Some(positions) => lua.create_sequence_from(positions.into_iter().map(LuaTile)).map(Some),
+        None => Ok(None),
+    }
+}
+
+impl UserData for LuaHouse {
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method("getId", |_, this, ()| Ok(this.0));
+        methods.add_method("getName", |lua, this, ()| read_house(lua, this.0, |h| h.name.clone()));
+        methods.add_method("getExitPosition", |lua, this, ()| match read_house(lua, this.0, |h| h.entry)? {
+            Some(pos) => push_position(lua, pos).map(Some),
+            None => Ok(None),
+        });
+        methods.add_method("getRent", |lua, this, ()| read_house(lua, this.0, |h| h.rent));
+        methods.add_method("getOwnerGuid", |lua, this, ()| read_house(lua, this.0, |h| h.owner()));
+        // setOwnerGuid(guid[, updateDatabase = true])
+        methods.add_method("setOwnerGuid", |lua, this, (guid, update_database): (u32, Option<bool>)| {
+            Ok(world(lua)?.set_house_owner(this.0, guid, update_database.unwrap_or(true)))
+        });
+
+        // dörrarnas tiles; items på kartan finns inte som Lua-objekt
+        methods.add_method("getDoors", |lua, this, ()| {
+            push_tiles(lua, read_house(lua, this.0, |h| h.doors().iter().map(|d| d.position).collect())?)
+        });
+        methods.add_method("getDoorCount", |lua, this, ()| read_house(lua, this.0, |h| h.doors().len()));
+        methods.add_method("getDoorIdByPosition", |lua, this, pos: Table| {
+            let pos = get_position(&pos)?;
+            read_house(lua, this.0, |h| h.get_door_at(pos).map(|d| d.door_id))
+        });
+        methods.add_method("getTiles", |lua, this, ()| {
+            push_tiles(lua, read_house(lua, this.0, |h| h.tiles().to_vec())?)
+        });
+        methods.add_method("getTileCount", |lua, this, ()| read_house(lua, this.0, |h| h.tiles().len()));
+
+        // false om listan inte finns, som i TFS
+        methods.add_method("getAccessList", |lua, this, list_id: u32| {
+            let list = read_house(lua, this.0, |h| h.get_access_list(list_id).map(str::to_string))?;
+            Ok(match list {
+                Some(Some(list)) => Value::String(lua.create_string(&list)?),
+                Some(None) => Value::Boolean(false),
+                None => Value::Nil,
+            })
+        });
+        methods.add_method("setAccessList", |lua, this, (list_id, list): (u32, String)| {
+            Ok(world(lua)?.set_house_access_list(this.0, list_id, &list))
+        });
+        // kickPlayer(player, targetPlayer)
+        methods.add_method("kickPlayer", |lua, this, (player, target): (Value, Value)| {
+            let (Some(player), Some(target)) = (creature_id(lua, &player)?, creature_id(lua, &target)?) else {
+                return Ok(false);
+            };
+            Ok(world(lua)?.kick_from_house(this.0, player, target))
+        });
+
+        methods.add_meta_method(MetaMethod::Eq, |_, this, other: LuaHouse| Ok(this.0 == other.0));
+        methods.add_meta_method(MetaMethod::Index, |lua, _, key: Value| {
+            global_table(lua, "House")?.get::<_, Value>(key)
+        });
+    }
+}
+
+impl<'lua> mlua::FromLua<'lua> for LuaHouse {
+    fn from_lua(value: Value<'lua>, _: &'lua Lua) -> mlua::Result<Self> {
+        match value {
+            Value::UserData(userdata) => Ok(*userdata.borrow::<LuaHouse>()?),
+            _ => Err(mlua::Error::FromLuaConversionError { from: value.type_name(), to: "House", message: None }),
+        }
+    }
+}
+
+pub(crate) fn register(lua: &Lua) -> mlua::Result<()> {
+    // House(id)
+    register_class(lua, "House", lua.create_function(|lua, (_, id): (Table, u32)| push_house(lua, id))?)?;
+
+    global_table(lua, "Game")?.set(
+        "getHouses",
+        lua.create_function(|lua, ()| {
+            let mut ids = Vec::new();
+            world(lua)?.with_houses(&mut |houses| ids = houses.iter().map(|h| h.id).collect());
+            lua.create_sequence_from(ids.into_iter().map(LuaHouse))
+        })?,
+    )?;
+
+    global_table(lua, "Player")?.set(
+        "getHouse",
+        lua.create_function(|lua, this: Value| {
+            let Some(id) = creature_id(lua, &this)? else {
+                return Ok(None);
+            };
+            let Some(guid) = read_player(lua, id, |p| p.guid)? else {
+                return Ok(None);
+            };
+            let mut house = None;
+            world(lua)?.with_houses(&mut |houses| house = houses.get_house_by_owner(guid).map(|h| h.id));
+            match house {
+                Some(house) => push_house(lua, house),
+                None => Ok(None),
+            }
+        })?,
+    )
+}
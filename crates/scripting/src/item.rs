@@ -0,0 +1,112 @@
+//! Lua-klasserna `ItemType` och `Item`, motsvarar luaItemType* och (en del
+//! av) luaItem* i TFS.
+//!
+//! Ett `Item` från `Game.createItem` ligger ännu inte någonstans i världen;
+//! det lämnas över till spelet med `Player:addItemEx`, och efter det är
+//! objektet tomt.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use items::{Item, ItemType, Items};
+use mlua::{Lua, MetaMethod, UserData, UserDataMethods, Value};
+
+use crate::script_manager::global_table;
+
+#[derive(Clone, Copy)]
+pub struct LuaItemType(pub &'static ItemType);
+
+impl UserData for LuaItemType {
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method("getId", |_, this, ()| Ok(this.0.id));
+        methods.add_method("getClientId", |_, this, ()| Ok(this.0.client_id));
+        methods.add_method("getName", |_, this, ()| Ok(this.0.name.clone()));
+        methods.add_method("getPluralName", |_, this, ()| Ok(this.0.plural_name.clone()));
+        methods.add_method("getArticle", |_, this, ()| Ok(this.0.article.clone()));
+        methods.add_method("getDescription", |_, this, ()| Ok(this.0.description.clone()));
+        // getWeight([count = 1])
+        methods.add_method("getWeight", |_, this, count: Option<i64>| {
+            Ok(this.0.weight as u64 * count.unwrap_or(1).max(1) as u64)
+        });
+        methods.add_method("getCapacity", |_, this, ()| Ok(this.0.max_items));
+        methods.add_method("getCharges", |_, this, ()| Ok(this.0.charges));
+        methods.add_method("isStackable", |_, this, ()| Ok(this.0.stackable));
+        methods.add_method("isContainer", |_, this, ()| Ok(this.0.is_container()));
+        methods.add_method("isFluidContainer", |_, this, ()| Ok(this.0.is_fluid_container()));
+        methods.add_method("isRune", |_, this, ()| Ok(this.0.is_rune()));
+        methods.add_method("isMovable", |_, this, ()| Ok(this.0.moveable));
+        methods.add_method("isPickupable", |_, this, ()| Ok(this.0.pickupable));
+        methods.add_method("hasSubType", |_, this, ()| Ok(this.0.has_sub_type()));
+
+        methods.add_meta_method(MetaMethod::Index, |lua, _, key: Value| {
+            global_table(lua, "ItemType")?.get::<_, Value>(key)
+        });
+    }
+}
+
+/// Ett item som ännu inte lagts i världen
+#[derive(Clone, Default)]
+pub struct LuaItem(pub Rc<RefCell<Option<Item>>>);
+
+impl UserData for LuaItem {
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method("getId", |_, this, ()| Ok(this.0.borrow().as_ref().map(|item| item.id)));
+        methods.add_method("getCount", |_, this, ()| Ok(this.0.borrow().as_ref().map(|item| item.count)));
+        methods.add_method("getSubType", |_, this, ()| {
+            Ok(this.0.borrow().as_ref().map(|item| item.get_sub_type()))
+        });
+        // addItem(itemId[, count/subType = 1]) för behållare
+        methods.add_method("addItem", |_, this, (item_id, count): (u16, Option<u16>)| {
+            let mut item = this.0.borrow_mut();
+            let Some(container) = item.as_mut().and_then(|item| item.get_container_mut()) else {
+                return Ok(false);
+            };
+            if container.is_full() {
+                return Ok(false);
+            }
+            container.add_item_front(Item::new(item_id, count.unwrap_or(1)));
+            Ok(true)
+        });
+
+        methods.add_meta_method(MetaMethod::Index, |lua, _, key: Value| {
+            global_table(lua, "Item")?.get::<_, Value>(key)
+        });
+    }
+}
+
+/// `ItemType(id)` eller `ItemType(name)`. Som i TFS ger ett okänt id ändå
+/// ett objekt (för id 0), så `ItemType(x):getId() == 0` fungerar som test.
+fn item_type(value: &Value) -> mlua::Result<&'static ItemType> {
+    let id = match value {
+        Value::Integer(id) => *id as u16,
+        Value::Number(id) => *id as u16,
+        Value::String(name) => Items::get_id_by_name(name.to_str()?).unwrap_or(0),
+        _ => 0,
+    };
+    Ok(Items::get(id))
+}
+
+pub(crate) fn register(lua: &Lua) -> mlua::Result<()> {
+    let class = global_table(lua, "ItemType")?;
+    let meta = lua.create_table()?;
+    meta.set(
+        "__call",
+        lua.create_function(|_, (_, value): (Value, Value)| Ok(LuaItemType(item_type(&value)?)))?,
+    )?;
+    class.set_metatable(Some(meta));
+    global_table(lua, "Item")?;
+
+    // Game.createItem(itemId[, count/subType = 1]); med position läggs
+    // itemet av spelet och hanteras inte här
+    global_table(lua, "Game")?.set(
+        "createItem",
+        lua.create_function(|_, (value, count): (Value, Option<u16>)| {
+            let it = item_type(&value)?;
+            if it.id == 0 {
+                return Ok(None);
+            }
+            Ok(Some(LuaItem(Rc::new(RefCell::new(Some(Item::new(it.id, count.unwrap_or(1))))))))
+        })?,
+    )?;
+    Ok(())
+}
@@ -1,5 +1,41 @@
 pub mod script_manager;
+pub mod creature_events;
 pub mod hooks;
+pub mod game;
+pub mod position;
+pub mod town;
+pub mod constants;
+pub mod monster_type;
+pub mod creature;
+pub mod item;
+pub mod npc;
+pub mod party;
+pub mod guild;
+pub mod timer_events;
+pub mod vocation;
+pub mod variant;
+pub mod combat;
+pub mod condition;
+pub mod spells;
+pub mod weapons;
+pub mod experience;
+pub mod config;
+pub mod death;
+pub mod stamina;
+pub mod group;
+pub mod outfit;
+pub mod tile;
+pub mod house;
+
+pub use creature::ScriptWorld;
+pub use creature_events::{CreatureEvents, DamageChange};
+pub use death::Death;
+pub use experience::Experience;
+pub use hooks::Events;
+pub use npc::NpcScripts;
+pub use script_manager::ScriptManager;
+pub use spells::{RuneUse, Spells};
+pub use weapons::Weapons;
 
 use mlua::Lua;
 
@@ -0,0 +1,450 @@
+//! Monstertyper från Lua: `Game.createMonsterType(name)` och klasserna
+//! `MonsterType`, `MonsterSpell` och `Loot`. Motsvarar luaMonsterType*,
+//! luaMonsterSpell* och luaLoot* i TFS. Scripten i data/monster/lua fyller
+//! typen med `mType:register(monster)` från register_monster_type.lua.
+//!
+//! Typerna samlas här medan scripten körs och flyttas sedan till
+//! `MonsterTypes`; en Lua-typ ersätter en XML-typ med samma namn.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use common::tracing::warn;
+use common::{CombatType, MagicEffect, ShootType};
+use entities::condition::ConditionType;
+use entities::creature::Skull;
+use entities::monster::{LootBlock, MonsterSpell, RaceType, SummonBlock, VoiceBlock, MAX_LOOTCHANCE};
+use entities::MonsterType;
+use items::Items;
+use mlua::{
+    Function, IntoLua, IntoLuaMulti, Lua, MetaMethod, Table, UserData, UserDataMethods, UserDataRef, Value,
+};
+
+use crate::script_manager::global_table;
+
+/// Typerna som skapats sedan de senast hämtades
+pub(crate) type PendingMonsterTypes = Rc<RefCell<Vec<Rc<RefCell<MonsterType>>>>>;
+
+/// Tabellen i registret där `mType.onThink = ...` och liknande sparas,
+/// per typnamn i gemener
+const MONSTER_TYPE_EVENTS: &str = "MonsterTypeEvents";
+
+#[derive(Clone)]
+pub struct LuaMonsterType(Rc<RefCell<MonsterType>>);
+
+/// Get/set i ett: `mType:health()` läser och `mType:health(100)` sätter
+macro_rules! property {
+    ($methods:ident, $name:literal, $field:ident: $ty:ty) => {
+        $methods.add_method($name, |lua, this, value: Option<$ty>| {
+            let mut mtype = this.0.borrow_mut();
+            match value {
+                Some(value) => {
+                    mtype.$field = value;
+                    true.into_lua(lua)
+                }
+                None => mtype.$field.clone().into_lua(lua),
+            }
+        });
+    };
+}
+
+impl UserData for LuaMonsterType {
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        property!(methods, "name", name: String);
+        property!(methods, "nameDescription", name_description: String);
+        property!(methods, "experience", experience: u64);
+        property!(methods, "health", health: i32);
+        property!(methods, "maxHealth", health_max: i32);
+        property!(methods, "runHealth", run_away_health: i32);
+        property!(methods, "maxSummons", max_summons: u32);
+        property!(methods, "manaCost", mana_cost: u32);
+        property!(methods, "baseSpeed", base_speed: u32);
+        property!(methods, "corpseId", corpse: u16);
+        property!(methods, "armor", armor: i32);
+        property!(methods, "defense", defense: i32);
+        property!(methods, "targetDistance", target_distance: i32);
+        property!(methods, "staticAttackChance", static_attack_chance: u32);
+        property!(methods, "changeTargetChance", change_target_chance: u32);
+        property!(methods, "changeTargetSpeed", change_target_speed: u32);
+        property!(methods, "isAttackable", is_attackable: bool);
+        property!(methods, "isHostile", is_hostile: bool);
+        property!(methods, "isSummonable", is_summonable: bool);
+        property!(methods, "isIllusionable", is_illusionable: bool);
+        property!(methods, "isConvinceable", is_convinceable: bool);
+        property!(methods, "isPushable", is_pushable: bool);
+        property!(methods, "isBoss", is_boss: bool);
+        property!(methods, "isChallengeable", is_challengeable: bool);
+        property!(methods, "isIgnoringSpawnBlock", ignore_spawn_block: bool);
+        property!(methods, "isHealthHidden", hidden_health: bool);
+        property!(methods, "canPushItems", can_push_items: bool);
+        property!(methods, "canPushCreatures", can_push_creatures: bool);
+        property!(methods, "canWalkOnEnergy", can_walk_on_energy: bool);
+        property!(methods, "canWalkOnFire", can_walk_on_fire: bool);
+        property!(methods, "canWalkOnPoison", can_walk_on_poison: bool);
+
+        methods.add_method("skull", |lua, this, value: Option<Value>| {
+            let mut mtype = this.0.borrow_mut();
+            match value {
+                Some(Value::String(name)) => mtype.skull = Skull::from_name(name.to_str()?).unwrap_or_default(),
+                Some(Value::Integer(value)) => mtype.skull = Skull::from_u8(value as u8),
+                Some(_) => return false.into_lua(lua),
+                None => return (mtype.skull as u8).into_lua(lua),
+            }
+            true.into_lua(lua)
+        });
+
+        methods.add_method("race", |lua, this, value: Option<String>| {
+            let mut mtype = this.0.borrow_mut();
+            let Some(value) = value else {
+                return (mtype.race as u8).into_lua(lua);
+            };
+            match RaceType::from_name(&value) {
+                Some(race) => mtype.race = race,
+                None => warn!("[MonsterType::race] Unknown race type {value} for {}", mtype.name),
+            }
+            true.into_lua(lua)
+        });
+
+        methods.add_method("outfit", |lua, this, value: Option<Table>| {
+            let mut mtype = this.0.borrow_mut();
+            let Some(table) = value else {
+                let outfit = mtype.outfit;
+                let table = lua.create_table()?;
+                table.set("lookType", outfit.look_type)?;
+                table.set("lookTypeEx", outfit.look_type_ex)?;
+                table.set("lookHead", outfit.look_head)?;
+                table.set("lookBody", outfit.look_body)?;
+                table.set("lookLegs", outfit.look_legs)?;
+                table.set("lookFeet", outfit.look_feet)?;
+                table.set("lookAddons", outfit.look_addons)?;
+                table.set("lookMount", outfit.look_mount)?;
+                return Value::Table(table).into_lua(lua);
+            };
+            let outfit = &mut mtype.outfit;
+            outfit.look_type = table.get::<_, Option<u16>>("lookType")?.unwrap_or(0);
+            outfit.look_type_ex = table.get::<_, Option<u16>>("lookTypeEx")?.unwrap_or(0);
+            outfit.look_head = table.get::<_, Option<u8>>("lookHead")?.unwrap_or(0);
+            outfit.look_body = table.get::<_, Option<u8>>("lookBody")?.unwrap_or(0);
+            outfit.look_legs = table.get::<_, Option<u8>>("lookLegs")?.unwrap_or(0);
+            outfit.look_feet = table.get::<_, Option<u8>>("lookFeet")?.unwrap_or(0);
+            outfit.look_addons = table.get::<_, Option<u8>>("lookAddons")?.unwrap_or(0);
+            outfit.look_mount = table.get::<_, Option<u16>>("lookMount")?.unwrap_or(0);
+            true.into_lua(lua)
+        });
+
+        // light() ger nivå och färg, light(color, level) sätter dem
+        methods.add_method("light", |lua, this, (color, level): (Option<u8>, Option<u8>)| {
+            let mut mtype = this.0.borrow_mut();
+            match color {
+                None => (mtype.light.level, mtype.light.color).into_lua_multi(lua),
+                Some(color) => {
+                    mtype.light.color = color;
+                    mtype.light.level = level.unwrap_or(0);
+                    true.into_lua_multi(lua)
+                }
+            }
+        });
+
+        methods.add_method(
+            "addVoice",
+            |_, this, (text, interval, chance, yell): (String, Option<u32>, Option<u32>, Option<bool>)| {
+                let mut mtype = this.0.borrow_mut();
+                if let Some(interval) = interval {
+                    mtype.yell_speed_ticks = interval;
+                }
+                if let Some(chance) = chance {
+                    mtype.yell_chance = chance;
+                }
+                mtype.voices.push(VoiceBlock { text, yell: yell.unwrap_or(false) });
+                Ok(true)
+            },
+        );
+
+        methods.add_method(
+            "addSummon",
+            |_, this, (name, interval, chance, max): (String, Option<u32>, Option<u32>, Option<i64>)| {
+                let mut mtype = this.0.borrow_mut();
+                mtype.summons.push(SummonBlock {
+                    name,
+                    chance: chance.unwrap_or(100).min(100),
+                    interval: interval.unwrap_or(1000).max(1),
+                    // -1 betyder ingen egen gräns, bara maxSummons
+                    max: max.filter(|&max| max >= 0).map_or(u32::MAX, |max| max as u32),
+                    force: false,
+                });
+                Ok(true)
+            },
+        );
+
+        methods.add_method("registerEvent", |_, this, name: String| {
+            this.0.borrow_mut().scripts.push(name);
+            Ok(true)
+        });
+
+        methods.add_method("addLoot", |_, this, loot: UserDataRef<LuaLoot>| {
+            this.0.borrow_mut().loot.push(loot.0.clone());
+            Ok(true)
+        });
+
+        methods.add_method("getLoot", |lua, this, ()| {
+            let mtype = this.0.borrow();
+            lua.create_sequence_from(
+                mtype.loot.iter().map(|loot| loot_table(lua, loot)).collect::<mlua::Result<Vec<_>>>()?,
+            )
+        });
+
+        methods.add_method("addElement", |_, this, (combat_type, percent): (u16, i32)| {
+            this.0.borrow_mut().element_map.insert(CombatType(combat_type), percent);
+            Ok(true)
+        });
+
+        methods.add_method("combatImmunities", |_, this, name: String| {
+            let mut mtype = this.0.borrow_mut();
+            let added = mtype.add_combat_immunity(&name);
+            if !added {
+                warn!("[MonsterType::combatImmunities] Unknown immunity name {name} for {}", mtype.name);
+            }
+            Ok(added)
+        });
+
+        methods.add_method("conditionImmunities", |_, this, name: String| {
+            let mut mtype = this.0.borrow_mut();
+            let added = mtype.add_condition_immunity(&name);
+            if !added {
+                warn!("[MonsterType::conditionImmunities] Unknown immunity name {name} for {}", mtype.name);
+            }
+            Ok(added)
+        });
+
+        methods.add_method("addAttack", |_, this, spell: UserDataRef<LuaMonsterSpell>| {
+            this.0.borrow_mut().attack_spells.push(spell.0.clone());
+            Ok(true)
+        });
+
+        methods.add_method("addDefense", |_, this, spell: UserDataRef<LuaMonsterSpell>| {
+            this.0.borrow_mut().defense_spells.push(spell.0.clone());
+            Ok(true)
+        });
+
+        // Metoder som scripten själva lagt i `MonsterType`, t.ex. `register`
+        methods.add_meta_method(MetaMethod::Index, |lua, _, key: String| {
+            global_table(lua, "MonsterType")?.get::<_, Value>(key)
+        });
+
+        // `mType.onThink = function(...)` och liknande
+        methods.add_meta_method(MetaMethod::NewIndex, |lua, this, (key, function): (String, Function)| {
+            let events = match lua.named_registry_value::<Option<Table>>(MONSTER_TYPE_EVENTS)? {
+                Some(events) => events,
+                None => {
+                    let events = lua.create_table()?;
+                    lua.set_named_registry_value(MONSTER_TYPE_EVENTS, events.clone())?;
+                    events
+                }
+            };
+            let name = this.0.borrow().name.to_lowercase();
+            let callbacks = match events.get::<_, Option<Table>>(name.as_str())? {
+                Some(callbacks) => callbacks,
+                None => {
+                    let callbacks = lua.create_table()?;
+                    events.set(name, callbacks.clone())?;
+                    callbacks
+                }
+            };
+            callbacks.set(key, function)
+        });
+    }
+}
+
+fn loot_table<'lua>(lua: &'lua Lua, loot: &LootBlock) -> mlua::Result<Table<'lua>> {
+    let table = lua.create_table()?;
+    table.set("itemId", loot.id)?;
+    table.set("chance", loot.chance)?;
+    table.set("maxCount", loot.count_max)?;
+    table.set("subType", loot.sub_type)?;
+    table.set("actionId", loot.action_id)?;
+    table.set("text", loot.text.as_str())?;
+    let children = loot.child_loot.iter().map(|child| loot_table(lua, child)).collect::<mlua::Result<Vec<_>>>()?;
+    table.set("childLoot", lua.create_sequence_from(children)?)?;
+    Ok(table)
+}
+
+#[derive(Clone, Default)]
+pub struct LuaMonsterSpell(pub MonsterSpell);
+
+impl UserData for LuaMonsterSpell {
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method_mut("setType", |_, this, name: String| {
+            this.0.set_type(&name);
+            Ok(true)
+        });
+        methods.add_method_mut("setScriptName", |_, this, script: String| {
+            this.0.script = Some(script);
+            Ok(true)
+        });
+        methods.add_method_mut("setChance", |_, this, chance: u32| {
+            this.0.chance = chance.min(100);
+            Ok(true)
+        });
+        methods.add_method_mut("setInterval", |_, this, interval: u32| {
+            this.0.interval = interval.max(1);
+            Ok(true)
+        });
+        methods.add_method_mut("setRange", |_, this, range: u32| {
+            this.0.range = range;
+            Ok(true)
+        });
+        methods.add_method_mut("setCombatValue", |_, this, (min, max): (i32, i32)| {
+            this.0.set_combat_value(min, max);
+            Ok(true)
+        });
+        methods.add_method_mut("setAttackValue", |_, this, (attack, skill): (i32, i32)| {
+            this.0.set_attack_value(attack, skill);
+            Ok(true)
+        });
+        methods.add_method_mut("setNeedTarget", |_, this, need_target: bool| {
+            this.0.need_target = need_target;
+            Ok(true)
+        });
+        methods.add_method_mut("setNeedDirection", |_, this, need_direction: bool| {
+            this.0.need_direction = need_direction;
+            Ok(true)
+        });
+        methods.add_method_mut("setCombatLength", |_, this, length: u8| {
+            this.0.length = length;
+            Ok(true)
+        });
+        methods.add_method_mut("setCombatSpread", |_, this, spread: u8| {
+            this.0.spread = spread;
+            Ok(true)
+        });
+        methods.add_method_mut("setCombatRadius", |_, this, radius: u8| {
+            this.0.radius = radius;
+            Ok(true)
+        });
+        methods.add_method_mut("setCombatType", |_, this, combat_type: u16| {
+            this.0.combat_type = CombatType(combat_type);
+            Ok(true)
+        });
+        methods.add_method_mut("setCombatEffect", |_, this, effect: u8| {
+            this.0.effect = MagicEffect::from_u8(effect).unwrap_or_default();
+            Ok(true)
+        });
+        methods.add_method_mut("setCombatShootEffect", |_, this, shoot: u8| {
+            this.0.shoot_effect = ShootType(shoot);
+            Ok(true)
+        });
+        methods.add_method_mut("setConditionType", |_, this, condition_type: u32| {
+            this.0.condition_type = ConditionType(condition_type);
+            Ok(true)
+        });
+        methods.add_method_mut("setConditionDamage", |_, this, (min, max, start): (i32, i32, Option<i32>)| {
+            this.0.condition_min_damage = min.abs();
+            this.0.condition_max_damage = max.abs();
+            this.0.condition_start_damage = start.unwrap_or(0).abs();
+            Ok(true)
+        });
+        methods.add_method_mut("setConditionSpeedChange", |_, this, (min, max): (i32, Option<i32>)| {
+            this.0.min_speed_change = min;
+            this.0.max_speed_change = max.unwrap_or(min);
+            Ok(true)
+        });
+        methods.add_method_mut("setConditionDuration", |_, this, duration: u32| {
+            this.0.duration = duration;
+            Ok(true)
+        });
+        methods.add_method_mut("setConditionDrunkenness", |_, this, drunkenness: u8| {
+            this.0.drunkenness = drunkenness;
+            Ok(true)
+        });
+        methods.add_method_mut("setConditionTickInterval", |_, this, interval: u32| {
+            this.0.tick_interval = interval;
+            Ok(true)
+        });
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct LuaLoot(pub LootBlock);
+
+impl UserData for LuaLoot {
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        // id eller itemnamn
+        methods.add_method_mut("setId", |_, this, id: Value| {
+            let id = match id {
+                Value::Integer(id) => Some(id as u16),
+                Value::Number(id) => Some(id as u16),
+                Value::String(name) => {
+                    let name = name.to_str()?;
+                    let id = Items::get_id_by_name(name);
+                    if id.is_none() {
+                        warn!("[Loot::setId] Unknown loot item \"{name}\"");
+                    }
+                    id
+                }
+                _ => None,
+            };
+            match id {
+                Some(id) => {
+                    this.0.id = id;
+                    Ok(true)
+                }
+                None => Ok(false),
+            }
+        });
+        methods.add_method_mut("setMaxCount", |_, this, count: u32| {
+            this.0.count_max = count.max(1);
+            Ok(true)
+        });
+        methods.add_method_mut("setSubType", |_, this, sub_type: i32| {
+            this.0.sub_type = sub_type;
+            Ok(true)
+        });
+        methods.add_method_mut("setChance", |_, this, chance: u32| {
+            this.0.chance = chance.min(MAX_LOOTCHANCE);
+            Ok(true)
+        });
+        methods.add_method_mut("setActionId", |_, this, action_id: i32| {
+            this.0.action_id = action_id;
+            Ok(true)
+        });
+        methods.add_method_mut("setDescription", |_, this, text: String| {
+            this.0.text = text;
+            Ok(true)
+        });
+        methods.add_method_mut("addChildLoot", |_, this, child: UserDataRef<LuaLoot>| {
+            this.0.child_loot.push(child.0.clone());
+            Ok(true)
+        });
+    }
+}
+
+/// `Game.createMonsterType(name)`, `MonsterSpell()`, `Loot()` och tabellen
+/// `MonsterType` som register_monster_type.lua lägger `register` i
+pub(crate) fn register(lua: &Lua, pending: PendingMonsterTypes) -> mlua::Result<()> {
+    global_table(lua, "MonsterType")?;
+
+    global_table(lua, "Game")?.set(
+        "createMonsterType",
+        lua.create_function(move |_, name: String| {
+            if name.is_empty() {
+                return Ok(None);
+            }
+            let mtype = Rc::new(RefCell::new(MonsterType::new(name)));
+            pending.borrow_mut().push(mtype.clone());
+            Ok(Some(LuaMonsterType(mtype)))
+        })?,
+    )?;
+
+    let globals = lua.globals();
+    globals.set("MonsterSpell", lua.create_function(|_, ()| Ok(LuaMonsterSpell::default()))?)?;
+    globals.set("Loot", lua.create_function(|_, ()| Ok(LuaLoot::default()))?)?;
+    Ok(())
+}
+
+/// Callbacks som `mType.onThink = ...` för en typ, nyckeln är händelsen
+pub fn monster_type_events<'lua>(lua: &'lua Lua, name: &str) -> mlua::Result<Option<Table<'lua>>> {
+    match lua.named_registry_value::<Option<Table>>(MONSTER_TYPE_EVENTS)? {
+        Some(events) => events.get(name.to_lowercase()),
+        None => Ok(None),
+    }
+}
@@ -0,0 +1,455 @@
+//! NPC-scripten, motsvarar `NpcScriptInterface`, `NpcEventsHandler` och
+//! NPC-funktionerna (`selfSay`, `openShopWindow`, ...) i TFS.
+//!
+//! Som i TFS körs NPC:erna i samma Lua-tillstånd som resten av scripten.
+//! data/npc/lib/npc.lua (med npcsystem: `NpcHandler`, `KeywordHandler` och
+//! modulerna för butik och resor) laddas en gång. Varje NPC:s script körs
+//! sedan med NPC:n som aktuell, och dess `onCreatureSay`, `onThink` osv.
+//! flyttas från de globala namnen till en tabell per NPC i registret.
+
+use std::cell::Cell;
+
+use common::{Direction, Error, Position, Result, SpeakClass};
+use entities::{CreatureType, Npc, ShopInfo};
+use mlua::{Function, IntoLuaMulti, Lua, Table, Value};
+
+use crate::creature::{creature_id, push_creature, register_class, world};
+use crate::position::{get_position, push_position};
+use crate::script_manager::script_error;
+
+/// Registret: NPC-id -> tabell med NPC:ns händelser
+const NPC_EVENTS: &str = "NpcEvents";
+/// Registret: spelar-id -> `{npc, buy, sell}` för öppna handelsfönster
+const NPC_SHOPS: &str = "NpcShops";
+
+/// Händelserna ett NPC-script kan definiera
+const EVENT_NAMES: [&str; 7] = [
+    "onCreatureAppear",
+    "onCreatureDisappear",
+    "onCreatureMove",
+    "onCreatureSay",
+    "onPlayerCloseChannel",
+    "onPlayerEndTrade",
+    "onThink",
+];
+
+/// NPC:n vars script körs just nu, NPC-delen av `ScriptEnvironment`
+#[derive(Default)]
+struct NpcEnv {
+    npc: Cell<u32>,
+}
+
+fn current_npc(lua: &Lua) -> Option<u32> {
+    let npc = lua.app_data_ref::<NpcEnv>()?.npc.get();
+    (npc != 0).then_some(npc)
+}
+
+/// Kör `f` med `npc` som aktuell NPC och återställ efteråt
+fn with_npc_env<R>(lua: &Lua, npc: u32, f: impl FnOnce() -> mlua::Result<R>) -> mlua::Result<R> {
+    let previous = lua.app_data_ref::<NpcEnv>().map(|env| env.npc.replace(npc)).unwrap_or(0);
+    let result = f();
+    if let Some(env) = lua.app_data_ref::<NpcEnv>() {
+        env.npc.set(previous);
+    }
+    result
+}
+
+fn registry_table<'lua>(lua: &'lua Lua, name: &str) -> mlua::Result<Table<'lua>> {
+    match lua.named_registry_value::<Option<Table>>(name)? {
+        Some(table) => Ok(table),
+        None => {
+            let table = lua.create_table()?;
+            lua.set_named_registry_value(name, table.clone())?;
+            Ok(table)
+        }
+    }
+}
+
+fn direction(value: u8) -> mlua::Result<Direction> {
+    Direction::from_u8(value).ok_or_else(|| mlua::Error::RuntimeError(format!("invalid direction {value}")))
+}
+
+/// Rader i ett handelsfönster från Lua: `{id, subType, buy, sell, name}`
+fn shop_items(items: Table) -> mlua::Result<Vec<ShopInfo>> {
+    let mut list = Vec::new();
+    for item in items.sequence_values::<Table>() {
+        let item = item?;
+        let mut sub_type = item.get::<_, Option<i32>>("subType")?.unwrap_or(0);
+        if sub_type == 0 {
+            sub_type = item.get::<_, Option<i32>>("subtype")?.unwrap_or(0);
+        }
+        list.push(ShopInfo {
+            item_id: item.get::<_, Option<u16>>("id")?.unwrap_or(0),
+            sub_type,
+            buy_price: price(&item, "buy")?,
+            sell_price: price(&item, "sell")?,
+            real_name: item.get::<_, Option<String>>("name")?.unwrap_or_default(),
+        });
+    }
+    Ok(list)
+}
+
+/// Modulerna sätter -1 för varor som inte köps eller säljs, det blir 0 här
+fn price(item: &Table, key: &str) -> mlua::Result<u32> {
+    Ok(item.get::<_, Option<i64>>(key)?.unwrap_or(0).clamp(0, u32::MAX as i64) as u32)
+}
+
+pub(crate) fn register(lua: &Lua) -> mlua::Result<()> {
+    lua.set_app_data(NpcEnv::default());
+    let globals = lua.globals();
+
+    // Npc() ger den aktuella NPC:n, Npc(id/namn) en annan
+    register_class(
+        lua,
+        "Npc",
+        lua.create_function(|lua, (_, value): (Table, Value)| {
+            let id = match value {
+                Value::Nil => current_npc(lua),
+                value => creature_id(lua, &value)?,
+            };
+            match id {
+                Some(id) if CreatureType::of_id(id) == Some(CreatureType::Npc) => push_creature(lua, id),
+                _ => Ok(Value::Nil),
+            }
+        })?,
+    )?;
+
+    globals.set("getNpcCid", lua.create_function(|lua, ()| Ok(current_npc(lua)))?)?;
+
+    globals.set(
+        "getNpcParameter",
+        lua.create_function(|lua, key: String| {
+            let Some(npc) = current_npc(lua) else {
+                return Ok(None);
+            };
+            let mut value = None;
+            world(lua)?.with_npc(npc, &mut |n| value = n.parameter(&key).map(str::to_string));
+            Ok(value)
+        })?,
+    )?;
+
+    // selfSay(words[, target]); med target i NPC-kanalen till den spelaren
+    globals.set(
+        "selfSay",
+        lua.create_function(|lua, (text, target): (String, Option<u32>)| {
+            let Some(npc) = current_npc(lua) else {
+                return Ok(());
+            };
+            let world = world(lua)?;
+            match target {
+                None => {
+                    world.creature_say(npc, &text, SpeakClass::Say, None, None);
+                }
+                Some(0) => {}
+                Some(target) => {
+                    world.creature_say(npc, &text, SpeakClass::PrivateNp, Some(target), None);
+                }
+            }
+            Ok(())
+        })?,
+    )?;
+
+    globals.set(
+        "selfMove",
+        lua.create_function(|lua, dir: u8| {
+            let Some(npc) = current_npc(lua) else {
+                return Ok(false);
+            };
+            Ok(world(lua)?.creature_move(npc, direction(dir)?) == common::ReturnValue::NoError)
+        })?,
+    )?;
+
+    // selfMoveTo(position) eller selfMoveTo(x, y, z)
+    globals.set(
+        "selfMoveTo",
+        lua.create_function(|lua, (first, y, z): (Value, Option<u16>, Option<u8>)| {
+            let Some(npc) = current_npc(lua) else {
+                return Ok(false);
+            };
+            let pos = match first {
+                Value::Table(pos) => get_position(&pos)?,
+                Value::Integer(x) => Position::new(x as u16, y.unwrap_or(0), z.unwrap_or(0)),
+                Value::Number(x) => Position::new(x as u16, y.unwrap_or(0), z.unwrap_or(0)),
+                _ => return Ok(false),
+            };
+            Ok(world(lua)?.creature_walk_to(npc, pos))
+        })?,
+    )?;
+
+    globals.set(
+        "selfTurn",
+        lua.create_function(|lua, dir: u8| {
+            let Some(npc) = current_npc(lua) else {
+                return Ok(false);
+            };
+            Ok(world(lua)?.creature_turn(npc, direction(dir)?))
+        })?,
+    )?;
+
+    globals.set(
+        "selfFollow",
+        lua.create_function(|lua, target: Value| {
+            let Some(npc) = current_npc(lua) else {
+                return Ok(false);
+            };
+            let target = creature_id(lua, &target)?;
+            Ok(world(lua)?.creature_follow(npc, target))
+        })?,
+    )?;
+
+    // Avstånd i rutor, -1 på annan våning
+    globals.set(
+        "getDistanceTo",
+        lua.create_function(|lua, target: Value| {
+            let world = world(lua)?;
+            let npc_pos = current_npc(lua).and_then(|npc| world.creature_position(npc));
+            let target_pos = creature_id(lua, &target)?.and_then(|id| world.creature_position(id));
+            let (Some(npc_pos), Some(target_pos)) = (npc_pos, target_pos) else {
+                return Ok(None);
+            };
+            if npc_pos.z != target_pos.z {
+                return Ok(Some(-1));
+            }
+            Ok(Some(Position::get_distance(&npc_pos, &target_pos)))
+        })?,
+    )?;
+
+    // doNpcSetCreatureFocus(cid), 0 släpper fokus
+    globals.set(
+        "doNpcSetCreatureFocus",
+        lua.create_function(|lua, target: Value| {
+            let Some(npc) = current_npc(lua) else {
+                return Ok(());
+            };
+            let world = world(lua)?;
+            let focus = creature_id(lua, &target)?
+                .filter(|&id| id != 0)
+                .and_then(|id| world.creature_position(id).map(|pos| (id, pos)));
+            let mut turn = None;
+            world.with_npc(npc, &mut |n| turn = n.set_creature_focus(focus));
+            if let Some(dir) = turn {
+                world.creature_turn(npc, dir);
+            }
+            Ok(())
+        })?,
+    )?;
+
+    // openShopWindow(cid, items, onBuy, onSell)
+    globals.set(
+        "openShopWindow",
+        lua.create_function(
+            |lua, (player, items, on_buy, on_sell): (Value, Table, Option<Function>, Option<Function>)| {
+                let Some(npc) = current_npc(lua) else {
+                    return Ok(false);
+                };
+                let Some(player) = creature_id(lua, &player)? else {
+                    return Ok(false);
+                };
+                let items = shop_items(items)?;
+                let world = world(lua)?;
+                if !world.with_player(player, &mut |p| p.open_shop_window(npc, items.clone())) {
+                    return Ok(false);
+                }
+                world.with_npc(npc, &mut |n| n.add_shop_player(player));
+
+                let shop = lua.create_table()?;
+                shop.set("npc", npc)?;
+                shop.set("buy", on_buy)?;
+                shop.set("sell", on_sell)?;
+                registry_table(lua, NPC_SHOPS)?.set(player, shop)?;
+
+                world.send_shop(player, npc, &items);
+                Ok(true)
+            },
+        )?,
+    )?;
+
+    // closeShopWindow(cid), bara om fönstret är den aktuella NPC:ns
+    globals.set(
+        "closeShopWindow",
+        lua.create_function(|lua, player: Value| {
+            let Some(npc) = current_npc(lua) else {
+                return Ok(false);
+            };
+            let Some(player) = creature_id(lua, &player)? else {
+                return Ok(false);
+            };
+            let world = world(lua)?;
+            let mut owner = None;
+            world.with_player(player, &mut |p| owner = p.shop_owner());
+            if owner == Some(npc) {
+                world.send_close_shop(player);
+                world.with_player(player, &mut |p| {
+                    p.close_shop_window();
+                });
+                world.with_npc(npc, &mut |n| n.remove_shop_player(player));
+                registry_table(lua, NPC_SHOPS)?.set(player, Value::Nil)?;
+            }
+            Ok(true)
+        })?,
+    )?;
+
+    // doSellItem(cid, itemid, amount[, subtype[, actionid[, canDropOnMap = true]]])
+    globals.set(
+        "doSellItem",
+        lua.create_function(
+            |lua,
+             (player, item_id, amount, sub_type, action_id, can_drop_on_map): (
+                Value,
+                u16,
+                u32,
+                Option<i32>,
+                Option<u16>,
+                Option<bool>,
+            )| {
+                let Some(player) = creature_id(lua, &player)? else {
+                    return Ok(0);
+                };
+                Ok(world(lua)?.sell_item(
+                    player,
+                    item_id,
+                    amount,
+                    sub_type.unwrap_or(1),
+                    action_id.unwrap_or(0),
+                    can_drop_on_map.unwrap_or(true),
+                ))
+            },
+        )?,
+    )?;
+    Ok(())
+}
+
+/// NPC-scripten för spelet. Spelet uppdaterar själv `Npc` (åskådare,
+/// butiksspelare) och anropar sedan motsvarande händelse här.
+pub struct NpcScripts<'lua> {
+    pub(crate) lua: &'lua Lua,
+}
+
+impl<'lua> NpcScripts<'lua> {
+    /// Kör NPC:ns script och spara dess händelser. False om NPC:n saknar
+    /// script. Motsvarar `NpcEventsHandler::NpcEventsHandler`.
+    pub fn load_script(&self, npc: &Npc) -> Result<bool> {
+        let Some(script) = &npc.script else {
+            return Ok(false);
+        };
+        let lua = self.lua;
+        let source = std::fs::read_to_string(script)
+            .map_err(|e| Error::Script(format!("{}: {e}", script.display())))?;
+        with_npc_env(lua, npc.id(), || {
+            lua.load(source).set_name(script.to_string_lossy()).exec()?;
+            let globals = lua.globals();
+            let events = lua.create_table()?;
+            for name in EVENT_NAMES {
+                if let Some(function) = globals.get::<_, Option<Function>>(name)? {
+                    events.set(name, function)?;
+                    globals.set(name, Value::Nil)?;
+                }
+            }
+            registry_table(lua, NPC_EVENTS)?.set(npc.id(), events)
+        })
+        .map_err(script_error)?;
+        Ok(true)
+    }
+
+    /// Glöm NPC:ns händelser när den tas bort
+    pub fn unload(&self, npc_id: u32) -> Result<()> {
+        registry_table(self.lua, NPC_EVENTS)
+            .and_then(|events| events.set(npc_id, Value::Nil))
+            .map_err(script_error)
+    }
+
+    fn call(&self, npc_id: u32, name: &str, args: impl IntoLuaMulti<'lua>) -> Result<()> {
+        let lua = self.lua;
+        let handler = registry_table(lua, NPC_EVENTS)
+            .and_then(|events| events.get::<_, Option<Table>>(npc_id))
+            .and_then(|events| events.map(|e| e.get::<_, Option<Function>>(name)).transpose())
+            .map_err(script_error)?;
+        let Some(handler) = handler.flatten() else {
+            return Ok(());
+        };
+        with_npc_env(lua, npc_id, || handler.call::<_, ()>(args)).map_err(script_error)
+    }
+
+    /// Varelser skickas som userdata, som `pushUserdata<Creature>` i TFS
+    fn creature(&self, id: u32) -> Result<Value<'lua>> {
+        push_creature(self.lua, id).map_err(script_error)
+    }
+
+    /// En varelse (eller NPC:n själv) dök upp inom synhåll
+    pub fn on_creature_appear(&self, npc_id: u32, creature_id: u32) -> Result<()> {
+        self.call(npc_id, "onCreatureAppear", self.creature(creature_id)?)
+    }
+
+    pub fn on_creature_disappear(&self, npc_id: u32, creature_id: u32) -> Result<()> {
+        self.call(npc_id, "onCreatureDisappear", self.creature(creature_id)?)
+    }
+
+    pub fn on_creature_move(&self, npc_id: u32, creature_id: u32, from: Position, to: Position) -> Result<()> {
+        let from = push_position(self.lua, from).map_err(script_error)?;
+        let to = push_position(self.lua, to).map_err(script_error)?;
+        self.call(npc_id, "onCreatureMove", (self.creature(creature_id)?, from, to))
+    }
+
+    /// En spelare sa något i närheten eller i NPC-kanalen
+    pub fn on_creature_say(&self, npc_id: u32, creature_id: u32, class: SpeakClass, text: &str) -> Result<()> {
+        self.call(npc_id, "onCreatureSay", (self.creature(creature_id)?, class as u8, text))
+    }
+
+    pub fn on_player_close_channel(&self, npc_id: u32, player_id: u32) -> Result<()> {
+        self.call(npc_id, "onPlayerCloseChannel", self.creature(player_id)?)
+    }
+
+    /// Spelaren stängde handelsfönstret eller gick iväg. Spelet har redan
+    /// stängt fönstret på `Player` och `Npc`. Motsvarar `Npc::onPlayerEndTrade`.
+    pub fn on_player_end_trade(&self, npc_id: u32, player_id: u32) -> Result<()> {
+        let shops = registry_table(self.lua, NPC_SHOPS).map_err(script_error)?;
+        let owner = shops
+            .get::<_, Option<Table>>(player_id)
+            .and_then(|shop| shop.map(|s| s.get::<_, u32>("npc")).transpose())
+            .map_err(script_error)?;
+        if owner == Some(npc_id) {
+            shops.set(player_id, Value::Nil).map_err(script_error)?;
+        }
+        self.call(npc_id, "onPlayerEndTrade", self.creature(player_id)?)
+    }
+
+    pub fn on_think(&self, npc_id: u32) -> Result<()> {
+        self.call(npc_id, "onThink", ())
+    }
+
+    /// Köp (`buy`) eller sälj i handelsfönstret. Spelet har kontrollerat att
+    /// varan finns i butiken och skickar själv om säljlistan efteråt.
+    /// False om spelaren inte har något fönster öppet. Motsvarar
+    /// `NpcEventsHandler::onPlayerTrade`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn on_player_trade(
+        &self,
+        player_id: u32,
+        buy: bool,
+        item_id: u16,
+        sub_type: u8,
+        amount: u16,
+        ignore: bool,
+        in_backpacks: bool,
+    ) -> Result<bool> {
+        let lua = self.lua;
+        let shop = registry_table(lua, NPC_SHOPS)
+            .and_then(|shops| shops.get::<_, Option<Table>>(player_id))
+            .map_err(script_error)?;
+        let Some(shop) = shop else {
+            return Ok(false);
+        };
+        let npc_id: u32 = shop.get("npc").map_err(script_error)?;
+        let callback = shop
+            .get::<_, Option<Function>>(if buy { "buy" } else { "sell" })
+            .map_err(script_error)?;
+        if let Some(callback) = callback {
+            with_npc_env(lua, npc_id, || {
+                let player = push_creature(lua, player_id)?;
+                callback.call::<_, ()>((player, item_id, sub_type, amount, ignore, in_backpacks))
+            })
+            .map_err(script_error)?;
+        }
+        Ok(true)
+    }
+}
@@ -0,0 +1,189 @@
+//! Spelarens utseenden och hästar och varelsens outfit, motsvarar
+//! `Player:addOutfit`, `Player:addMount`, `Creature:setOutfit` och de
+//! närliggande i TFS. Reglerna för vad spelaren får bära finns i
+//! `rules::outfit`.
+
+use std::sync::Arc;
+
+use entities::{Mount, Mounts, Outfits, Player};
+use mlua::{Lua, Table, Value};
+use rules::outfit::{self as rules_outfit, OutfitConfig};
+
+use crate::condition::{get_outfit, push_outfit};
+use crate::creature::{creature_id, read_player, world};
+use crate::script_manager::global_table;
+
+/// Utseendena, hästarna och inställningarna som app data
+#[derive(Clone)]
+struct OutfitRegistry(Arc<(Outfits, Mounts, OutfitConfig)>);
+
+fn registry(lua: &Lua) -> mlua::Result<Arc<(Outfits, Mounts, OutfitConfig)>> {
+    lua.app_data_ref::<OutfitRegistry>()
+        .map(|registry| registry.0.clone())
+        .ok_or_else(|| mlua::Error::RuntimeError("no outfits registered".into()))
+}
+
+fn is_premium(player: &Player, config: &OutfitConfig) -> bool {
+    player.is_premium(config.free_premium, common::unix_time())
+}
+
+/// Hästen med id:t eller namnet, som `getMountByID`/`getMountByName`
+fn find_mount<'a>(mounts: &'a Mounts, mount: &Value) -> Option<&'a Mount> {
+    match mount {
+        Value::Integer(id) => mounts.get_mount_by_id(*id as u16),
+        Value::Number(id) => mounts.get_mount_by_id(*id as u16),
+        Value::String(name) => mounts.get_mount_by_name(name.to_str().ok()?),
+        _ => None,
+    }
+}
+
+/// Ge spelaren hästen; nil om hästen inte finns
+fn tame_mount(lua: &Lua, this: &Value, mount: &Value) -> mlua::Result<Option<bool>> {
+    let Some(id) = creature_id(lua, this)? else {
+        return Ok(None);
+    };
+    let registry = registry(lua)?;
+    let Some(mount) = find_mount(&registry.1, mount) else {
+        return Ok(None);
+    };
+    let mut changed = false;
+    world(lua)?.with_player(id, &mut |p| changed = p.tame_mount(mount.id));
+    Ok(Some(changed))
+}
+
+pub(crate) fn register(lua: &Lua, outfits: &Outfits, mounts: &Mounts, config: OutfitConfig) -> mlua::Result<()> {
+    lua.set_app_data(OutfitRegistry(Arc::new((outfits.clone(), mounts.clone(), config))));
+
+    let creature = global_table(lua, "Creature")?;
+    creature.set(
+        "getOutfit",
+        lua.create_function(|lua, this: Value| {
+            let Some(id) = creature_id(lua, &this)? else {
+                return Ok(None);
+            };
+            match world(lua)?.creature_outfit(id) {
+                Some(outfit) => push_outfit(lua, &outfit).map(Some),
+                None => Ok(None),
+            }
+        })?,
+    )?;
+    creature.set(
+        "setOutfit",
+        lua.create_function(|lua, (this, outfit): (Value, Table)| {
+            let Some(id) = creature_id(lua, &this)? else {
+                return Ok(None);
+            };
+            Ok(Some(world(lua)?.set_creature_outfit(id, get_outfit(&outfit)?)))
+        })?,
+    )?;
+
+    let player = global_table(lua, "Player")?;
+    player.set(
+        "addOutfit",
+        lua.create_function(|lua, (this, look_type): (Value, u16)| {
+            let Some(id) = creature_id(lua, &this)? else {
+                return Ok(None);
+            };
+            Ok(Some(world(lua)?.with_player(id, &mut |p| p.add_outfit(look_type, 0))))
+        })?,
+    )?;
+    player.set(
+        "addOutfitAddon",
+        lua.create_function(|lua, (this, look_type, addon): (Value, u16, u8)| {
+            let Some(id) = creature_id(lua, &this)? else {
+                return Ok(None);
+            };
+            Ok(Some(world(lua)?.with_player(id, &mut |p| p.add_outfit(look_type, addon))))
+        })?,
+    )?;
+    player.set(
+        "removeOutfit",
+        lua.create_function(|lua, (this, look_type): (Value, u16)| {
+            let Some(id) = creature_id(lua, &this)? else {
+                return Ok(None);
+            };
+            let mut removed = false;
+            world(lua)?.with_player(id, &mut |p| removed = p.remove_outfit(look_type));
+            Ok(Some(removed))
+        })?,
+    )?;
+    player.set(
+        "removeOutfitAddon",
+        lua.create_function(|lua, (this, look_type, addon): (Value, u16, u8)| {
+            let Some(id) = creature_id(lua, &this)? else {
+                return Ok(None);
+            };
+            let mut removed = false;
+            world(lua)?.with_player(id, &mut |p| removed = p.remove_outfit_addon(look_type, addon));
+            Ok(Some(removed))
+        })?,
+    )?;
+    // hasOutfit(lookType[, addon = 0])
+    player.set(
+        "hasOutfit",
+        lua.create_function(|lua, (this, look_type, addon): (Value, u16, Option<u8>)| {
+            let Some(id) = creature_id(lua, &this)? else {
+                return Ok(None);
+            };
+            let registry = registry(lua)?;
+            let (outfits, _, config) = &*registry;
+            read_player(lua, id, |p| rules_outfit::can_wear(p, outfits, look_type, addon.unwrap_or(0), is_premium(p, config)))
+        })?,
+    )?;
+    player.set(
+        "sendOutfitWindow",
+        lua.create_function(|lua, this: Value| {
+            let Some(id) = creature_id(lua, &this)? else {
+                return Ok(None);
+            };
+            world(lua)?.send_outfit_window(id);
+            Ok(Some(true))
+        })?,
+    )?;
+
+    // hästarna tas emot med id eller namn
+    player.set(
+        "addMount",
+        lua.create_function(|lua, (this, mount): (Value, Value)| tame_mount(lua, &this, &mount))?,
+    )?;
+    // spelaren stiger av om det är hästen den sitter på
+    player.set(
+        "removeMount",
+        lua.create_function(|lua, (this, mount): (Value, Value)| {
+            let Some(id) = creature_id(lua, &this)? else {
+                return Ok(None);
+            };
+            let registry = registry(lua)?;
+            let Some(mount) = find_mount(&registry.1, &mount) else {
+                return Ok(None);
+            };
+            let world = world(lua)?;
+            let mut untamed = None;
+            world.with_player(id, &mut |p| untamed = Some(rules_outfit::untame_mount(p, &registry.1, mount.id)));
+            let Some((removed, update)) = untamed else {
+                return Ok(Some(false));
+            };
+            if let Some(outfit) = update.outfit {
+                world.set_creature_outfit(id, outfit);
+            }
+            if update.speed_changed {
+                world.send_creature_speed(id);
+            }
+            Ok(Some(removed))
+        })?,
+    )?;
+    player.set(
+        "hasMount",
+        lua.create_function(|lua, (this, mount): (Value, Value)| {
+            let Some(id) = creature_id(lua, &this)? else {
+                return Ok(None);
+            };
+            let registry = registry(lua)?;
+            let (_, mounts, config) = &*registry;
+            let Some(mount) = find_mount(mounts, &mount) else {
+                return Ok(None);
+            };
+            read_player(lua, id, |p| rules_outfit::has_mount(p, mount, is_premium(p, config)))
+        })?,
+    )
+}
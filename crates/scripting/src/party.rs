@@ -0,0 +1,166 @@
+//! Lua-klassen `Party`, motsvarar luaParty* i TFS. Ett Lua-objekt är bara
+//! partyts id; finns partyt inte längre ger metoderna nil.
+
+use common::otsys_time;
+use entities::{Parties, PartyView};
+use mlua::{Lua, MetaMethod, UserData, UserDataMethods, Value};
+
+use crate::creature::{creature_id, push_creature, world};
+use crate::experience;
+use crate::hooks::{party_event, party_share_experience};
+use crate::script_manager::global_table;
+
+#[derive(Clone, Copy)]
+pub struct LuaParty(pub u32);
+
+/// Kör `f` på partyna, som `read_player` för spelare
+fn with_parties<T>(lua: &Lua, f: impl FnOnce(&mut Parties, &dyn PartyView) -> T) -> mlua::Result<T> {
+    let mut f = Some(f);
+    let mut result = None;
+    world(lua)?.with_parties(&mut |parties, view| {
+        if let Some(f) = f.take() {
+            result = Some(f(parties, view));
+        }
+    });
+    result.ok_or_else(|| mlua::Error::RuntimeError("parties not available".into()))
+}
+
+fn players<'lua>(lua: &'lua Lua, ids: &[u32]) -> mlua::Result<Vec<Value<'lua>>> {
+    ids.iter().map(|&id| push_creature(lua, id)).collect()
+}
+
+/// Spelarens party, nil om den inte är med i något
+pub(crate) fn player_party(lua: &Lua, player_id: u32) -> mlua::Result<Option<LuaParty>> {
+    with_parties(lua, |parties, _| parties.party_of(player_id).map(|party| LuaParty(party.id())))
+}
+
+impl UserData for LuaParty {
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method("disband", |lua, this, ()| {
+            if !party_event(lua, "onDisband", this.0, None)? {
+                return Ok(false);
+            }
+            with_parties(lua, |parties, _| parties.disband(this.0))?;
+            Ok(true)
+        });
+        methods.add_method("getLeader", |lua, this, ()| {
+            match with_parties(lua, |parties, _| parties.get(this.0).map(|party| party.leader()))? {
+                Some(leader) => push_creature(lua, leader),
+                None => Ok(Value::Nil),
+            }
+        });
+        methods.add_method("setLeader", |lua, this, player: Value| {
+            let Some(player) = creature_id(lua, &player)? else {
+                return Ok(false);
+            };
+            with_parties(lua, |parties, view| {
+                let Some(leader) = parties.get(this.0).map(|party| party.leader()) else {
+                    return false;
+                };
+                parties.pass_leadership(view, leader, player, otsys_time())
+            })
+        });
+        methods.add_method("getMembers", |lua, this, ()| {
+            let members = with_parties(lua, |parties, _| parties.get(this.0).map(|party| party.members().to_vec()))?;
+            members.map(|members| players(lua, &members)).transpose()
+        });
+        methods.add_method("getMemberCount", |lua, this, ()| {
+            with_parties(lua, |parties, _| parties.get(this.0).map(|party| party.members().len()))
+        });
+        methods.add_method("getInvitees", |lua, this, ()| {
+            let invites = with_parties(lua, |parties, _| parties.get(this.0).map(|party| party.invites().to_vec()))?;
+            invites.map(|invites| players(lua, &invites)).transpose()
+        });
+        methods.add_method("getInviteeCount", |lua, this, ()| {
+            with_parties(lua, |parties, _| parties.get(this.0).map(|party| party.invites().len()))
+        });
+        methods.add_method("addInvite", |lua, this, player: Value| {
+            let Some(player) = creature_id(lua, &player)? else {
+                return Ok(false);
+            };
+            with_parties(lua, |parties, view| {
+                let Some(leader) = parties.get(this.0).map(|party| party.leader()) else {
+                    return false;
+                };
+                parties.invite(view, leader, player)
+            })
+        });
+        methods.add_method("removeInvite", |lua, this, player: Value| {
+            let Some(player) = creature_id(lua, &player)? else {
+                return Ok(false);
+            };
+            with_parties(lua, |parties, _| parties.remove_invite(this.0, player))
+        });
+        methods.add_method("addMember", |lua, this, player: Value| {
+            let Some(player) = creature_id(lua, &player)? else {
+                return Ok(false);
+            };
+            let invited = with_parties(lua, |parties, _| {
+                parties.get(this.0).is_some_and(|party| party.is_player_invited(player))
+            })?;
+            if !invited || !party_event(lua, "onJoin", this.0, Some(player))? {
+                return Ok(false);
+            }
+            with_parties(lua, |parties, view| {
+                let Some(leader) = parties.get(this.0).map(|party| party.leader()) else {
+                    return false;
+                };
+                parties.join(view, player, leader, otsys_time())
+            })
+        });
+        methods.add_method("removeMember", |lua, this, player: Value| {
+            let Some(player) = creature_id(lua, &player)? else {
+                return Ok(false);
+            };
+            let member = with_parties(lua, |parties, _| parties.get(this.0).is_some_and(|party| party.contains(player)))?;
+            if !member || !party_event(lua, "onLeave", this.0, Some(player))? {
+                return Ok(false);
+            }
+            with_parties(lua, |parties, view| parties.leave(view, player, otsys_time()))
+        });
+        methods.add_method("isSharedExperienceActive", |lua, this, ()| {
+            with_parties(lua, |parties, _| parties.get(this.0).map(|party| party.is_shared_experience_active()))
+        });
+        methods.add_method("isSharedExperienceEnabled", |lua, this, ()| {
+            with_parties(lua, |parties, _| parties.get(this.0).map(|party| party.is_shared_experience_enabled()))
+        });
+        methods.add_method("shareExperience", |lua, this, exp: u64| {
+            let receivers = with_parties(lua, |parties, _| parties.experience_receivers(this.0))?;
+            if receivers.is_empty() {
+                return Ok(false);
+            }
+            let exp = party_share_experience(lua, this.0, exp)?;
+            for player in receivers {
+                experience::add_experience(lua, player, exp, None, true)?;
+            }
+            Ok(true)
+        });
+        methods.add_method("setSharedExperience", |lua, this, active: bool| {
+            with_parties(lua, |parties, view| {
+                let Some(leader) = parties.get(this.0).map(|party| party.leader()) else {
+                    return false;
+                };
+                parties.set_shared_experience(view, leader, active, otsys_time())
+            })
+        });
+
+        methods.add_meta_method(MetaMethod::Eq, |_, this, other: LuaParty| Ok(this.0 == other.0));
+        methods.add_meta_method(MetaMethod::Index, |lua, _, key: Value| {
+            global_table(lua, "Party")?.get::<_, Value>(key)
+        });
+    }
+}
+
+impl<'lua> mlua::FromLua<'lua> for LuaParty {
+    fn from_lua(value: Value<'lua>, _: &'lua Lua) -> mlua::Result<Self> {
+        match value {
+            Value::UserData(userdata) => Ok(*userdata.borrow::<LuaParty>()?),
+            _ => Err(mlua::Error::FromLuaConversionError { from: value.type_name(), to: "Party", message: None }),
+        }
+    }
+}
+
+pub(crate) fn register(lua: &Lua) -> mlua::Result<()> {
+    global_table(lua, "Party")?;
+    Ok(())
+}
@@ -0,0 +1,82 @@
+//! Lua-klassen `Position`. Som i TFS är en position en vanlig tabell
+//! `{x, y, z, stackpos}` med `Position` som metatabell, så att
+//! data/lib/core/position.lua kan lägga till metoder på klassen.
+
+use common::Position;
+use mlua::{Lua, Table, Value};
+
+pub fn register(lua: &Lua) -> mlua::Result<()> {
+    let class = lua.create_table()?;
+    class.set("__index", class.clone())?;
+    class.set(
+        "__add",
+        lua.create_function(|lua, (a, b): (Table, Table)| {
+            let (x, y, z) = offset(&a, &b, 1)?;
+            new_position(lua, x, y, z, a.get::<_, Option<i32>>("stackpos")?.unwrap_or(0))
+        })?,
+    )?;
+    class.set(
+        "__sub",
+        lua.create_function(|lua, (a, b): (Table, Table)| {
+            let (x, y, z) = offset(&a, &b, -1)?;
+            new_position(lua, x, y, z, a.get::<_, Option<i32>>("stackpos")?.unwrap_or(0))
+        })?,
+    )?;
+    class.set(
+        "__eq",
+        lua.create_function(|_, (a, b): (Table, Table)| {
+            Ok(get_position(&a)? == get_position(&b)?)
+        })?,
+    )?;
+
+    // Position(x, y, z[, stackpos]) / Position(position)
+    let meta = lua.create_table()?;
+    meta.set(
+        "__call",
+        lua.create_function(|lua, (_, first, y, z, stackpos): (Table, Value, Option<i64>, Option<i64>, Option<i32>)| {
+            match first {
+                Value::Table(other) => {
+                    let stackpos = other.get::<_, Option<i32>>("stackpos")?.unwrap_or(0);
+                    new_position(lua, other.get("x")?, other.get("y")?, other.get("z")?, stackpos)
+                }
+                Value::Integer(x) => new_position(lua, x, y.unwrap_or(0), z.unwrap_or(0), stackpos.unwrap_or(0)),
+                Value::Number(x) => new_position(lua, x as i64, y.unwrap_or(0), z.unwrap_or(0), stackpos.unwrap_or(0)),
+                _ => new_position(lua, 0, 0, 0, 0),
+            }
+        })?,
+    )?;
+    class.set_metatable(Some(meta));
+
+    lua.globals().set("Position", class)
+}
+
+fn offset(a: &Table, b: &Table, sign: i64) -> mlua::Result<(i64, i64, i64)> {
+    let get = |t: &Table, key: &str| t.get::<_, Option<i64>>(key).map(|v| v.unwrap_or(0));
+    Ok((
+        get(a, "x")? + sign * get(b, "x")?,
+        get(a, "y")? + sign * get(b, "y")?,
+        get(a, "z")? + sign * get(b, "z")?,
+    ))
+}
+
+fn new_position(lua: &Lua, x: i64, y: i64, z: i64, stackpos: i32) -> mlua::Result<Table<'_>> {
+    let table = lua.create_table()?;
+    table.set("x", x)?;
+    table.set("y", y)?;
+    table.set("z", z)?;
+    table.set("stackpos", stackpos)?;
+    let class: Option<Table> = lua.globals().get("Position")?;
+    table.set_metatable(class);
+    Ok(table)
+}
+
+/// Motsvarar `LuaScriptInterface::pushPosition`
+pub fn push_position(lua: &Lua, pos: Position) -> mlua::Result<Table<'_>> {
+    new_position(lua, pos.x as i64, pos.y as i64, pos.z as i64, 0)
+}
+
+/// Motsvarar `LuaScriptInterface::getPosition`
+pub fn get_position(table: &Table) -> mlua::Result<Position> {
+    let get = |key: &str| table.get::<_, Option<i64>>(key).map(|v| v.unwrap_or(0));
+    Ok(Position::new(get("x")? as u16, get("y")? as u16, get("z")? as u8))
+}
@@ -0,0 +1,276 @@
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use common::tracing::warn;
+use common::{Config, Error, Result};
+use entities::{Groups, MonsterTypes, Mounts, Outfits};
+use mlua::{Lua, Table};
+use rules::death::DeathConfig;
+use rules::experience::ExperienceStages;
+use rules::outfit::OutfitConfig;
+use rules::skull::SkullConfig;
+use rules::spells::SpellConfig;
+use rules::vocation::Vocations;
+use rules::weapons::WeaponConfig;
+use world::{Towns, WorldLight};
+
+use crate::creature::{self, ScriptWorld, WorldHandle};
+use crate::creature_events::CreatureEvents;
+use crate::death::{self, Death};
+use crate::experience::{self, Experience};
+use crate::hooks::{self, Events};
+use crate::monster_type::{self, PendingMonsterTypes};
+use crate::npc::{self, NpcScripts};
+use crate::spells::{self, Spells};
+use crate::weapons::{self, Weapons};
+use crate::{
+    combat, condition, config, constants, game, group, guild, house, item, outfit, party, position, stamina, tile,
+    timer_events, town, variant, vocation,
+};
+
+/// Lua-tillståndet som alla scripts delar. Motsvarar `ScriptingManager`
+/// plus de klasser `LuaScriptInterface::registerFunctions` sätter upp.
+pub struct ScriptManager {
+    lua: Lua,
+    /// Monstertyper från `Game.createMonsterType` som inte hämtats än
+    monster_types: PendingMonsterTypes,
+}
+
+impl ScriptManager {
+    pub fn new() -> Result<Self> {
+        let lua = Lua::new();
+        let monster_types = PendingMonsterTypes::default();
+        constants::register(&lua).map_err(script_error)?;
+        position::register(&lua).map_err(script_error)?;
+        item::register(&lua).map_err(script_error)?;
+        variant::register(&lua).map_err(script_error)?;
+        combat::register(&lua).map_err(script_error)?;
+        condition::register(&lua).map_err(script_error)?;
+        timer_events::register(&lua).map_err(script_error)?;
+        spells::register(&lua).map_err(script_error)?;
+        weapons::register(&lua).map_err(script_error)?;
+        monster_type::register(&lua, monster_types.clone()).map_err(script_error)?;
+        Ok(Self { lua, monster_types })
+    }
+
+    pub fn lua(&self) -> &Lua {
+        &self.lua
+    }
+
+    pub fn load_file(&self, path: impl AsRef<Path>) -> Result<()> {
+        run_file(&self.lua, path.as_ref())
+    }
+
+    /// Ge scripten tillgång till spelet: klasserna `Creature`, `Player`,
+    /// `Monster`, `Npc`, `Tile` och `House` samt NPC-funktionerna. Måste göras innan
+    /// NPC-biblioteket laddas.
+    pub fn register_world(&self, world: Rc<dyn ScriptWorld>) -> Result<()> {
+        self.lua.set_app_data(WorldHandle(world));
+        creature::register(&self.lua).map_err(script_error)?;
+        experience::register_player(&self.lua).map_err(script_error)?;
+        death::register(&self.lua).map_err(script_error)?;
+        stamina::register(&self.lua).map_err(script_error)?;
+        party::register(&self.lua).map_err(script_error)?;
+        guild::register(&self.lua).map_err(script_error)?;
+        tile::register(&self.lua).map_err(script_error)?;
+        house::register(&self.lua).map_err(script_error)?;
+        npc::register(&self.lua).map_err(script_error)
+    }
+
+    /// Ladda data/npc/lib/npc.lua, som i sin tur laddar npcsystem. Som i
+    /// TFS är sökvägarna i biblioteket relativa till arbetskatalogen.
+    pub fn load_npc_lib(&self, path: impl AsRef<Path>) -> Result<()> {
+        run_file(&self.lua, path.as_ref())
+    }
+
+    pub fn npc_scripts(&self) -> NpcScripts<'_> {
+        NpcScripts { lua: &self.lua }
+    }
+
+    /// Kör `addEvent`-callbacks vars tid kommit
+    pub fn execute_timer_events(&self, now: u64) -> Result<usize> {
+        timer_events::execute(&self.lua, now).map_err(script_error)
+    }
+
+    /// Läs data/events/events.xml och kör scripten för klasserna som har
+    /// påslagna händelser. Returnerar antalet påslagna.
+    pub fn load_events(&self, path: impl AsRef<Path>) -> Result<usize> {
+        hooks::load(&self.lua, path.as_ref())
+    }
+
+    pub fn events(&self) -> Events<'_> {
+        Events { lua: &self.lua }
+    }
+
+    /// Läs data/creaturescripts/creaturescripts.xml in i det delade Lua-tillståndet
+    pub fn load_creature_events(&self, path: impl AsRef<Path>) -> Result<CreatureEvents> {
+        let mut events = CreatureEvents::new();
+        events.load_from_xml(&self.lua, path)?;
+        Ok(events)
+    }
+
+    /// Stegen för erfarenhet, från stages.xml eller config.lua
+    pub fn register_experience_stages(&self, stages: ExperienceStages) -> Result<()> {
+        experience::register(&self.lua, stages).map_err(script_error)
+    }
+
+    pub fn experience(&self) -> Experience<'_> {
+        Experience { lua: &self.lua }
+    }
+
+    pub fn death(&self) -> Death<'_> {
+        Death { lua: &self.lua }
+    }
+
+    /// Inställningarna för döden och skallarna från config.lua
+    pub fn register_death_config(&self, death: DeathConfig, skull: SkullConfig) -> Result<()> {
+        death::set_config(&self.lua, death, skull);
+        Ok(())
+    }
+
+    /// Läs data/spells/spells.xml. Yrkena måste vara registrerade innan.
+    /// Returnerar antalet besvärjelser.
+    pub fn load_spells(&self, path: impl AsRef<Path>) -> Result<usize> {
+        self.spells().load_from_xml(path)
+    }
+
+    pub fn spells(&self) -> Spells<'_> {
+        Spells { lua: &self.lua }
+    }
+
+    /// Inställningarna för besvärjelser från config.lua
+    pub fn register_spell_config(&self, config: SpellConfig) -> Result<()> {
+        spells::set_config(&self.lua, config).map_err(script_error)
+    }
+
+    /// Läs data/weapons/weapons.xml och lägg till vapen för övriga
+    /// närstrids- och distansitem. Yrkena och itemen måste vara inlästa
+    /// innan. Returnerar antalet vapen.
+    pub fn load_weapons(&self, path: impl AsRef<Path>) -> Result<usize> {
+        let weapons = self.weapons();
+        Ok(weapons.load_from_xml(path)? + weapons.load_defaults()?)
+    }
+
+    pub fn weapons(&self) -> Weapons<'_> {
+        Weapons { lua: &self.lua }
+    }
+
+    /// Inställningarna för vapen från config.lua
+    pub fn register_weapon_config(&self, config: WeaponConfig) -> Result<()> {
+        weapons::set_config(&self.lua, config).map_err(script_error)
+    }
+
+    /// Kör revscripten i `dir` (data/scripts), biblioteket i `lib` först.
+    /// Filer som börjar med `#` hoppas över och ett script som inte går att
+    /// köra loggas. Motsvarar `Scripts::loadScripts`. Returnerar antalet körda.
+    pub fn load_scripts(&self, dir: impl AsRef<Path>) -> Result<usize> {
+        let dir = dir.as_ref();
+        let mut files = Vec::new();
+        collect_lua_files(dir, &mut files)?;
+        files.sort_by_key(|file| (!file.starts_with(dir.join("lib")), file.clone()));
+
+        let mut count = 0;
+        for file in files {
+            match self.load_file(&file) {
+                Ok(()) => count += 1,
+                Err(e) => warn!("[ScriptManager::load_scripts] {e}"),
+            }
+        }
+        Ok(count)
+    }
+
+    /// Kör monsterscripten i `dir` (data/monster/lua) och lägg typerna de
+    /// skapar i `monsters`. Filer som börjar med `#` är exempel och hoppas
+    /// över. register_monster_type.lua måste vara inläst innan, annars finns
+    /// inte `mType:register`. Returnerar antalet typer.
+    pub fn load_monster_types(&self, dir: impl AsRef<Path>, monsters: &mut MonsterTypes) -> Result<usize> {
+        let mut files = Vec::new();
+        collect_lua_files(dir.as_ref(), &mut files)?;
+        files.sort();
+        for file in files {
+            if let Err(e) = self.load_file(&file) {
+                warn!("[ScriptManager::load_monster_types] {e}");
+            }
+        }
+
+        let created = std::mem::take(&mut *self.monster_types.borrow_mut());
+        let count = created.len();
+        for mtype in created {
+            monsters.add_monster_type(mtype.borrow().clone());
+        }
+        Ok(count)
+    }
+
+    /// Gör kartans städer tillgängliga som `Town(...)` och `Game.getTowns()`
+    pub fn register_towns(&self, towns: &Towns) -> Result<()> {
+        town::register(&self.lua, towns).map_err(script_error)
+    }
+
+    /// Gör yrkena tillgängliga som `Vocation(...)` och `Player:getVocation()`
+    pub fn register_vocations(&self, vocations: &Vocations) -> Result<()> {
+        vocation::register(&self.lua, vocations).map_err(script_error)
+    }
+
+    /// Gör grupperna tillgängliga som `Group(id)` och `Player:getGroup()`
+    pub fn register_groups(&self, groups: &Groups) -> Result<()> {
+        group::register(&self.lua, groups).map_err(script_error)
+    }
+
+    /// Gör utseendena och hästarna tillgängliga för `Player:hasOutfit`,
+    /// `Player:addMount` och liknande
+    pub fn register_outfits(&self, outfits: &Outfits, mounts: &Mounts, config: OutfitConfig) -> Result<()> {
+        outfit::register(&self.lua, outfits, mounts, config).map_err(script_error)
+    }
+
+    /// config.lua för scripten som `configManager` och `configKeys`
+    pub fn register_config(&self, config: &Config) -> Result<()> {
+        config::register(&self.lua, config).map_err(script_error)
+    }
+
+    /// Världstid och världsljus för scripten; `light` delar tillstånd med världen
+    pub fn register_world_light(&self, light: WorldLight) -> Result<()> {
+        game::register_light(&self.lua, light).map_err(script_error)
+    }
+}
+
+/// Hämta (eller skapa) en global tabell som `Game`
+pub(crate) fn global_table<'lua>(lua: &'lua Lua, name: &str) -> mlua::Result<Table<'lua>> {
+    match lua.globals().get::<_, Option<Table>>(name)? {
+        Some(table) => Ok(table),
+        None => {
+            let table = lua.create_table()?;
+            lua.globals().set(name, table.clone())?;
+            Ok(table)
+        }
+    }
+}
+
+fn collect_lua_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    let entries = std::fs::read_dir(dir).map_err(|e| Error::Script(format!("{}: {e}", dir.display())))?;
+    for entry in entries {
+        let path = entry.map_err(|e| Error::Script(format!("{}: {e}", dir.display())))?.path();
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        if name.starts_with('#') {
+            continue;
+        }
+        if path.is_dir() {
+            collect_lua_files(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext == "lua") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn run_file(lua: &Lua, path: &Path) -> Result<()> {
+    let source = std::fs::read_to_string(path)
+        .map_err(|e| Error::Script(format!("{}: {e}", path.display())))?;
+    lua.load(source)
+        .set_name(path.to_string_lossy())
+        .exec()
+        .map_err(script_error)
+}
+
+pub(crate) fn script_error(e: mlua::Error) -> Error {
+    Error::Script(e.to_string())
+}
@@ -0,0 +1,796 @@
+//! Besvärjelserna med sina script: spells.xml, revscriptens `Spell()` och
+//! kasten. Motsvarar `Spells`, luaSpell* och de delar av `InstantSpell` och
+//! `RuneSpell` som kör scripten i TFS. Uppgifterna och kontrollerna finns i
+//! `rules::spells`.
+//!
+//! Registret ligger som app data i Lua-tillståndet, så att `spell:register()`
+//! kan lägga till besvärjelser medan scripten körs.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::rc::Rc;
+
+use common::tracing::warn;
+use common::{otsys_time, Error, MagicEffect, MessageClass, Position, Result, ReturnValue, SpeakClass};
+use mlua::{Function, IntoLua, IntoLuaMulti, Lua, MetaMethod, RegistryKey, Table, UserData, UserDataMethods, Value};
+use rules::combat::on_attacked_creature;
+use rules::spells::{
+    self as rules_spells, InstantSpell, Spell, SpellCaster, SpellConfig, SpellFlags, SpellGroup, SpellKind, SpellType,
+    XmlSpell,
+};
+
+use crate::creature::{push_creature, read_player, register_class, world, ScriptWorld};
+use crate::creature_events::is_true;
+use crate::death;
+use crate::experience;
+use crate::script_manager::{global_table, run_file, script_error};
+use crate::variant::{push_variant, LuaVariant};
+use crate::vocation;
+
+/// En besvärjelse och dess `onCastSpell`
+struct ScriptSpell {
+    spell: SpellKind,
+    function: Option<RegistryKey>,
+}
+
+type SharedSpell = Rc<RefCell<ScriptSpell>>;
+
+/// De registrerade besvärjelserna, som i `Spells`
+#[derive(Default)]
+struct SpellRegistry {
+    /// Nyckeln är orden som de skrevs
+    instants: BTreeMap<String, SharedSpell>,
+    runes: BTreeMap<u16, SharedSpell>,
+    config: SpellConfig,
+}
+
+impl SpellRegistry {
+    /// Motsvarar `Spells::registerInstantLuaEvent` och `registerRuneLuaEvent`
+    fn register(&mut self, shared: SharedSpell) -> bool {
+        let spell = shared.borrow();
+        match &spell.spell {
+            SpellKind::Instant(instant) => {
+                if self.instants.contains_key(&instant.words) {
+                    warn!("[Spells::registerInstantLuaEvent] Duplicate registered instant spell with words: {}", instant.words);
+                    return false;
+                }
+                self.instants.insert(instant.words.clone(), shared.clone());
+            }
+            SpellKind::Rune(rune) => {
+                if self.runes.contains_key(&rune.rune_id) {
+                    warn!("[Spells::registerRuneLuaEvent] Duplicate registered rune with id: {}", rune.rune_id);
+                    return false;
+                }
+                self.runes.insert(rune.rune_id, shared.clone());
+            }
+        }
+        true
+    }
+
+    /// Motsvarar `Spells::getInstantSpell`
+    fn instant_by_words(&self, text: &str) -> Option<SharedSpell> {
+        let spells: Vec<(&str, bool, &SharedSpell)> = self
+            .instants
+            .iter()
+            .map(|(words, spell)| {
+                let has_param = matches!(&spell.borrow().spell, SpellKind::Instant(instant) if instant.has_param);
+                (words.as_str(), has_param, spell)
+            })
+            .collect();
+        rules_spells::get_instant_spell(spells, text).cloned()
+    }
+
+    /// Skiftlägesokänsligt, motsvarar `Spells::getInstantSpellByName`
+    fn instant_by_name(&self, name: &str) -> Option<SharedSpell> {
+        self.instants.values().find(|spell| spell.borrow().spell.spell().name.eq_ignore_ascii_case(name)).cloned()
+    }
+
+    /// Motsvarar `Spells::getRuneSpellByName`
+    fn rune_by_name(&self, name: &str) -> Option<SharedSpell> {
+        self.runes.values().find(|spell| spell.borrow().spell.spell().name.eq_ignore_ascii_case(name)).cloned()
+    }
+}
+
+/// Registret som app data
+#[derive(Clone)]
+struct SpellHandle(Rc<RefCell<SpellRegistry>>);
+
+fn registry(lua: &Lua) -> mlua::Result<Rc<RefCell<SpellRegistry>>> {
+    lua.app_data_ref::<SpellHandle>()
+        .map(|handle| handle.0.clone())
+        .ok_or_else(|| mlua::Error::RuntimeError("no spells registered".into()))
+}
+
+/// Besvärjelsen och dess funktion, utan att registret hålls lånat medan
+/// scriptet körs
+fn spell_and_function<'lua>(lua: &'lua Lua, shared: &SharedSpell) -> mlua::Result<Option<(SpellKind, Function<'lua>)>> {
+    let spell = shared.borrow();
+    let Some(key) = spell.function.as_ref() else {
+        return Ok(None);
+    };
+    Ok(Some((spell.spell.clone(), lua.registry_value(key)?)))
+}
+
+/// Hur det gick att använda en rune
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuneUse {
+    Failed,
+    /// Kastad; med `remove_charge` ska runan tappa en laddning
+    Cast { remove_charge: bool },
+}
+
+/// Ett kast av en spelare. Misslyckade kontroller skickas som
+/// avbrottsmeddelande med en puff, som i TFS.
+struct Cast<'lua> {
+    lua: &'lua Lua,
+    world: Rc<dyn ScriptWorld>,
+    player: u32,
+    config: SpellConfig,
+}
+
+impl<'lua> Cast<'lua> {
+    fn new(lua: &'lua Lua, player: u32) -> mlua::Result<Self> {
+        let config = registry(lua)?.borrow().config;
+        Ok(Self { lua, world: world(lua)?, player, config })
+    }
+
+    fn caster(&self) -> mlua::Result<Option<SpellCaster>> {
+        let Some(pos) = self.world.creature_position(self.player) else {
+            return Ok(None);
+        };
+        let mut in_protection_zone = false;
+        self.world.with_combat_view(&mut |view| in_protection_zone = view.tile(pos).protection_zone);
+        let vocations = vocation::registry(self.lua).ok();
+        let config = self.config;
+        read_player(self.lua, self.player, |player| {
+            let vocation = vocations.as_ref().and_then(|vocations| vocations.get_vocation(player.vocation));
+            SpellCaster::new(player, vocation, &config, in_protection_zone, otsys_time())
+        })
+    }
+
+    /// `sendCancelMessage` och en puff där spelaren står
+    fn fail(&self, ret: ReturnValue, poff: bool) {
+        let message = ret.message();
+        if !message.is_empty() {
+            self.world.send_text_message(self.player, MessageClass::StatusSmall, message);
+        }
+        if poff {
+            if let Some(pos) = self.world.creature_position(self.player) {
+                self.world.send_magic_effect(pos, MagicEffect::Poff);
+            }
+        }
+    }
+
+    fn check(&self, spell: &Spell, instant: bool, caster: &SpellCaster) -> mlua::Result<bool> {
+        let ret = read_player(self.lua, self.player, |player| {
+            rules_spells::player_spell_check(spell, instant, player, caster)
+        })?
+        .unwrap_or(ReturnValue::NotPossible);
+        if ret != ReturnValue::NoError {
+            // en rune som inte kylts ned ger ingen puff
+            self.fail(ret, instant || ret != ReturnValue::YouAreExhausted);
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    fn add_cooldowns(&self, spell: &Spell, caster: &SpellCaster) {
+        if caster.flags.has_no_exhaustion {
+            return;
+        }
+        for condition in spell.cooldown_conditions() {
+            self.world.add_condition(self.player, condition, false);
+        }
+    }
+
+    /// Motsvarar `Spell::postCastSpell(player)`
+    fn post_cast(&self, spell: &Spell, caster: &SpellCaster) -> mlua::Result<()> {
+        self.add_cooldowns(spell, caster);
+        let config = self.config;
+        let mut mana_spent = 0;
+        self.world.with_player(self.player, &mut |player| {
+            mana_spent = rules_spells::post_cast_spell(spell, player, caster, &config, true, true);
+        });
+        if mana_spent != 0 {
+            experience::add_mana_spent(self.lua, self.player, mana_spent as u64)?;
+        }
+        Ok(())
+    }
+
+    /// Rutan framför spelaren, motsvarar `Spells::getCasterPosition`
+    fn caster_position(&self, pos: Position) -> Position {
+        self.world
+            .creature_direction(self.player)
+            .and_then(|dir| pos.get_next_position(dir))
+            .unwrap_or(pos)
+    }
+
+    fn instant_check(&self, spell: &Spell, caster: &SpellCaster, to: Position) -> bool {
+        let mut ret = ReturnValue::NoError;
+        self.world.with_combat_view(&mut |view| ret = rules_spells::instant_spell_check(spell, caster, view, to));
+        if ret != ReturnValue::NoError {
+            self.fail(ret, true);
+            return false;
+        }
+        true
+    }
+
+    /// `onCastSpell(creature, variant[, isHotkey])`; ett fel i scriptet
+    /// loggas och räknas som ett misslyckat kast. Motsvarar `Spell::executeCastSpell`.
+    fn call(&self, name: &str, function: &Function, variant: &LuaVariant, is_hotkey: Option<bool>) -> mlua::Result<bool> {
+        let creature = push_creature(self.lua, self.player)?;
+        let variant = push_variant(self.lua, variant)?;
+        let result = match is_hotkey {
+            Some(is_hotkey) => function.call::<_, Value>((creature, variant, is_hotkey)),
+            None => function.call::<_, Value>((creature, variant)),
+        };
+        match result {
+            Ok(result) => Ok(is_true(&result)),
+            Err(e) => {
+                warn!("[Spell::executeCastSpell] {name}: {e}");
+                Ok(false)
+            }
+        }
+    }
+
+    /// Motsvarar `InstantSpell::playerCastInstant`. `param` blir målets
+    /// namn när det är ett spelarnamn.
+    fn cast_instant(&self, instant: &InstantSpell, function: &Function, param: &mut String) -> mlua::Result<bool> {
+        let spell = &instant.spell;
+        let Some(caster) = self.caster()? else {
+            return Ok(false);
+        };
+        if !self.check(spell, true, &caster)? {
+            return Ok(false);
+        }
+
+        let pos = caster.combatant.position;
+        let variant = if spell.self_target {
+            LuaVariant::Number(self.player)
+        } else if spell.need_target || instant.caster_target_or_direction {
+            let mut ret = ReturnValue::YouCanOnlyUseItOnCreatures;
+            let target = if instant.has_param {
+                match self.world.get_player_by_name_wildcard(param) {
+                    Ok(target) => {
+                        if let Some(name) = self.world.creature_name(target) {
+                            *param = name;
+                        }
+                        Some(target)
+                    }
+                    Err(e) => {
+                        ret = e;
+                        None
+                    }
+                }
+            } else {
+                read_player(self.lua, self.player, |player| player.creature.attacked_creature)?.flatten()
+            };
+
+            match target.filter(|target| self.world.creature_health(*target).is_some_and(|health| health > 0)) {
+                Some(target) => {
+                    let Some(to) = self.world.creature_position(target) else {
+                        return Ok(false);
+                    };
+                    let mut reachable = false;
+                    self.world.with_combat_view(&mut |view| {
+                        reachable = rules_spells::can_throw_spell(spell, view, pos, to);
+                    });
+                    if !reachable {
+                        self.fail(ReturnValue::CreatureIsNotReachable, true);
+                        return Ok(false);
+                    }
+                    LuaVariant::Number(target)
+                }
+                None if !instant.caster_target_or_direction => {
+                    if instant.has_param {
+                        self.add_cooldowns(spell, &caster);
+                    }
+                    self.fail(ret, true);
+                    return Ok(false);
+                }
+                None => {
+                    let to = self.caster_position(pos);
+                    if !self.instant_check(spell, &caster, to) {
+                        return Ok(false);
+                    }
+                    LuaVariant::Position(to)
+                }
+            }
+        } else if instant.has_param {
+            if instant.has_player_name_param {
+                match self.world.get_player_by_name_wildcard(param) {
+                    Ok(target) => {
+                        if let Some(name) = self.world.creature_name(target) {
+                            *param = name;
+                        }
+                    }
+                    Err(ret) => {
+                        self.add_cooldowns(spell, &caster);
+                        self.fail(ret, true);
+                        return Ok(false);
+                    }
+                }
+            }
+            LuaVariant::String(param.clone())
+        } else {
+            let to = if instant.need_direction { self.caster_position(pos) } else { pos };
+            if !self.instant_check(spell, &caster, to) {
+                return Ok(false);
+            }
+            LuaVariant::Position(to)
+        };
+
+        if !self.call(&spell.name, function, &variant, None)? {
+            return Ok(false);
+        }
+        self.post_cast(spell, &caster)?;
+        Ok(true)
+    }
+}
+
+/// Besvärjelserna för spelet
+pub struct Spells<'lua> {
+    pub(crate) lua: &'lua Lua,
+}
+
+impl Spells<'_> {
+    /// Läs spells.xml. Scripten ligger i `scripts/` bredvid filen och
+    /// `lib/spells.lua` körs först. Yrkena måste vara registrerade.
+    /// Returnerar antalet registrerade besvärjelser.
+    pub fn load_from_xml(&self, path: impl AsRef<Path>) -> Result<usize> {
+        let path = path.as_ref();
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        let vocations = vocation::registry(self.lua).map_err(script_error)?;
+        let spells = rules_spells::load_from_xml(path, &vocations)?;
+
+        let lib = dir.join("lib").join("spells.lua");
+        if lib.exists() {
+            run_file(self.lua, &lib)?;
+        }
+
+        let mut count = 0;
+        for XmlSpell { spell, script } in spells {
+            let name = spell.spell().name.clone();
+            match self.load_spell(spell, &dir.join("scripts").join(script)) {
+                Ok(true) => count += 1,
+                Ok(false) => {}
+                Err(e) => warn!("[Spells::load_from_xml] Cannot load {name}: {e}"),
+            }
+        }
+        Ok(count)
+    }
+
+    /// Kör scriptet och registrera besvärjelsen med dess `onCastSpell`.
+    /// Den globala funktionen nollställs, som för creaturescripts.
+    fn load_spell(&self, spell: SpellKind, script: &Path) -> Result<bool> {
+        let lua = self.lua;
+        run_file(lua, script)?;
+        let globals = lua.globals();
+        let function: Option<Function> = globals.get("onCastSpell").map_err(script_error)?;
+        let Some(function) = function else {
+            return Err(Error::Script(format!("onCastSpell not found in {}", script.display())));
+        };
+        globals.set("onCastSpell", Value::Nil).map_err(script_error)?;
+
+        let function = lua.create_registry_value(function).map_err(script_error)?;
+        let spell = Rc::new(RefCell::new(ScriptSpell { spell, function: Some(function) }));
+        Ok(registry(lua).map_err(script_error)?.borrow_mut().register(spell))
+    }
+
+    /// Spelaren säger `text`: är det orden till en besvärjelse kastas den
+    /// och orden sägs (som monstertal med `emoteSpells`). False om texten
+    /// inte var en besvärjelse och ska sägas som vanligt. Motsvarar
+    /// `Spells::playerSaySpell` och besvärjelsedelen av `Game::playerSaySpell`.
+    pub fn player_say_spell(&self, player: u32, text: &str) -> Result<bool> {
+        let lua = self.lua;
+        let text = text.trim();
+        let shared = registry(lua).map_err(script_error)?.borrow().instant_by_words(text);
+        let Some((SpellKind::Instant(instant), function)) =
+            shared.map(|shared| spell_and_function(lua, &shared)).transpose().map_err(script_error)?.flatten()
+        else {
+            return Ok(false);
+        };
+
+        let mut param = String::new();
+        if instant.has_param {
+            match rules_spells::parse_param(text, instant.words.len()) {
+                Some(text) => param = text,
+                None => return Ok(false),
+            }
+        }
+
+        let cast = Cast::new(lua, player).map_err(script_error)?;
+        if !cast.cast_instant(&instant, &function, &mut param).map_err(script_error)? {
+            return Ok(true);
+        }
+        let class = if cast.config.emote_spells { SpeakClass::MonsterSay } else { SpeakClass::Say };
+        cast.world.creature_say(player, &instant.spoken_words(&param), class, None, None);
+        Ok(true)
+    }
+
+    /// Är itemet en rune med en besvärjelse
+    pub fn is_rune(&self, item_id: u16) -> bool {
+        registry(self.lua).is_ok_and(|registry| registry.borrow().runes.contains_key(&item_id))
+    }
+
+    /// Spelaren använder runan på `to`, eller på `target` om den pekades ut.
+    /// Motsvarar `RuneSpell::executeUse`.
+    pub fn execute_rune(
+        &self,
+        player: u32,
+        rune_id: u16,
+        target: Option<u32>,
+        to: Position,
+        is_hotkey: bool,
+    ) -> Result<RuneUse> {
+        let lua = self.lua;
+        let shared = registry(lua).map_err(script_error)?.borrow().runes.get(&rune_id).cloned();
+        let Some((SpellKind::Rune(rune), function)) =
+            shared.map(|shared| spell_and_function(lua, &shared)).transpose().map_err(script_error)?.flatten()
+        else {
+            return Ok(RuneUse::Failed);
+        };
+        let spell = &rune.spell;
+
+        let cast = Cast::new(lua, player).map_err(script_error)?;
+        let Some(caster) = cast.caster().map_err(script_error)? else {
+            return Ok(RuneUse::Failed);
+        };
+        if !cast.check(spell, false, &caster).map_err(script_error)? {
+            return Ok(RuneUse::Failed);
+        }
+        let mut ret = ReturnValue::NoError;
+        let mut target = target;
+        cast.world.with_combat_view(&mut |view| {
+            ret = rules_spells::rune_spell_check(&rune, &caster, view, to);
+            if target.is_none() {
+                target = rules_spells::rune_target(view, to);
+            }
+        });
+        if ret != ReturnValue::NoError {
+            cast.fail(ret, true);
+            return Ok(RuneUse::Failed);
+        }
+
+        let variant = if spell.need_target {
+            LuaVariant::Number(target.unwrap_or(0))
+        } else {
+            LuaVariant::Position(to)
+        };
+        if !cast.call(&spell.name, &function, &variant, Some(is_hotkey)).map_err(script_error)? {
+            return Ok(RuneUse::Failed);
+        }
+        cast.post_cast(spell, &caster).map_err(script_error)?;
+
+        if let Some(target) = target.filter(|_| spell.pz_lock) {
+            let mut pvp = None;
+            cast.world.with_combat_view(&mut |view| {
+                pvp = view.combatant(target).and_then(|target| on_attacked_creature(view, &caster.combatant, &target));
+            });
+            if let Some(pvp) = pvp {
+                if pvp {
+                    death::on_attacked_player(self.lua, player, target).map_err(script_error)?;
+                }
+                let ticks = cast.config.pz_locked;
+                cast.world.with_player(player, &mut |player| player.add_in_fight_ticks(false, ticks));
+            }
+        }
+        Ok(RuneUse::Cast { remove_charge: rune.has_charges() && cast.config.remove_charges_from_runes })
+    }
+}
+
+#[derive(Clone)]
+pub struct LuaSpell(SharedSpell);
+
+impl<'lua> mlua::FromLua<'lua> for LuaSpell {
+    fn from_lua(value: Value<'lua>, _: &'lua Lua) -> mlua::Result<Self> {
+        match value {
+            Value::UserData(userdata) => Ok(userdata.borrow::<LuaSpell>()?.clone()),
+            _ => Err(mlua::Error::FromLuaConversionError { from: value.type_name(), to: "Spell", message: None }),
+        }
+    }
+}
+
+/// Get/set i ett för det alla besvärjelser har: `spell:level()` läser och
+/// `spell:level(20)` sätter
+macro_rules! property {
+    ($methods:ident, $name:literal, $field:ident: $ty:ty) => {
+        $methods.add_method($name, |lua, this, value: Option<$ty>| {
+            let mut spell = this.0.borrow_mut();
+            let spell = spell.spell.spell_mut();
+            match value {
+                Some(value) => {
+                    spell.$field = value;
+                    true.into_lua(lua)
+                }
+                None => spell.$field.clone().into_lua(lua),
+            }
+        });
+    };
+}
+
+/// Som `property!` för det bara instants eller runes har; false för den
+/// andra sorten
+macro_rules! kind_property {
+    ($methods:ident, $name:literal, $kind:ident, $($field:ident).+: $ty:ty) => {
+        $methods.add_method($name, |lua, this, value: Option<$ty>| {
+            let mut spell = this.0.borrow_mut();
+            let SpellKind::$kind(spell) = &mut spell.spell else {
+                return false.into_lua(lua);
+            };
+            match value {
+                Some(value) => {
+                    spell.$($field).+ = value;
+                    true.into_lua(lua)
+                }
+                None => spell.$($field).+.clone().into_lua(lua),
+            }
+        });
+    };
+}
+
+/// Gruppen som namn eller siffra, `None` för en okänd
+fn spell_group(value: &Value) -> mlua::Result<Option<SpellGroup>> {
+    let group = match value {
+        Value::Integer(value) => SpellGroup::from_u8(*value as u8),
+        Value::Number(value) => SpellGroup::from_u8(*value as u8),
+        Value::String(name) => SpellGroup::from_name(name.to_str()?),
+        _ => SpellGroup::None,
+    };
+    if group == SpellGroup::None {
+        warn!("[Spell::group] Unknown group: {value:?}");
+        return Ok(None);
+    }
+    Ok(Some(group))
+}
+
+impl UserData for LuaSpell {
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        property!(methods, "name", name: String);
+        property!(methods, "id", id: u8);
+        property!(methods, "level", level: u32);
+        property!(methods, "magicLevel", magic_level: u32);
+        property!(methods, "mana", mana: u32);
+        property!(methods, "manaPercent", mana_percent: u32);
+        property!(methods, "soul", soul: u32);
+        property!(methods, "range", range: i32);
+        property!(methods, "cooldown", cooldown: u32);
+        property!(methods, "isPremium", premium: bool);
+        property!(methods, "isEnabled", enabled: bool);
+        property!(methods, "needTarget", need_target: bool);
+        property!(methods, "needWeapon", need_weapon: bool);
+        property!(methods, "needLearn", learnable: bool);
+        property!(methods, "isSelfTarget", self_target: bool);
+        property!(methods, "isAggressive", aggressive: bool);
+        property!(methods, "isPzLock", pz_lock: bool);
+
+        kind_property!(methods, "words", Instant, words: String);
+        kind_property!(methods, "needDirection", Instant, need_direction: bool);
+        kind_property!(methods, "hasParams", Instant, has_param: bool);
+        kind_property!(methods, "hasPlayerNameParam", Instant, has_player_name_param: bool);
+        kind_property!(methods, "needCasterTargetOrDirection", Instant, caster_target_or_direction: bool);
+        kind_property!(methods, "isBlockingWalls", Instant, spell.check_line_of_sight: bool);
+
+        kind_property!(methods, "runeId", Rune, rune_id: u16);
+        kind_property!(methods, "charges", Rune, charges: u32);
+        kind_property!(methods, "allowFarUse", Rune, allow_far_use: bool);
+        kind_property!(methods, "checkFloor", Rune, check_floor: bool);
+        kind_property!(methods, "blockWalls", Rune, spell.check_line_of_sight: bool);
+        kind_property!(methods, "runeLevel", Rune, spell.level: u32);
+        kind_property!(methods, "runeMagicLevel", Rune, spell.magic_level: u32);
+
+        // group(primary[, secondary]), utan argument båda
+        methods.add_method("group", |lua, this, (primary, secondary): (Option<Value>, Option<Value>)| {
+            let mut spell = this.0.borrow_mut();
+            let spell = spell.spell.spell_mut();
+            let Some(primary) = primary else {
+                return (spell.group as u8, spell.secondary_group as u8).into_lua_multi(lua);
+            };
+            let Some(group) = spell_group(&primary)? else {
+                return false.into_lua_multi(lua);
+            };
+            spell.group = group;
+            if let Some(secondary) = secondary {
+                let Some(group) = spell_group(&secondary)? else {
+                    return false.into_lua_multi(lua);
+                };
+                spell.secondary_group = group;
+            }
+            true.into_lua_multi(lua)
+        });
+        // groupCooldown(primary[, secondary]), utan argument båda
+        methods.add_method("groupCooldown", |lua, this, (primary, secondary): (Option<u32>, Option<u32>)| {
+            let mut spell = this.0.borrow_mut();
+            let spell = spell.spell.spell_mut();
+            let Some(primary) = primary else {
+                return (spell.group_cooldown, spell.secondary_group_cooldown).into_lua_multi(lua);
+            };
+            spell.group_cooldown = primary;
+            if let Some(secondary) = secondary {
+                spell.secondary_group_cooldown = secondary;
+            }
+            true.into_lua_multi(lua)
+        });
+        // isBlocking(solid, creature), utan argument båda
+        methods.add_method("isBlocking", |lua, this, (solid, creature): (Option<bool>, Option<bool>)| {
+            let mut spell = this.0.borrow_mut();
+            let spell = spell.spell.spell_mut();
+            let Some(solid) = solid else {
+                return (spell.blocking_solid, spell.blocking_creature).into_lua_multi(lua);
+            };
+            spell.blocking_solid = solid;
+            spell.blocking_creature = creature.unwrap_or(false);
+            true.into_lua_multi(lua)
+        });
+        // vocation("sorcerer;true", "master sorcerer", ...), utan argument namnen
+        methods.add_method("vocation", |lua, this, names: mlua::Variadic<String>| {
+            let vocations = vocation::registry(lua)?;
+            let mut spell = this.0.borrow_mut();
+            let spell = spell.spell.spell_mut();
+            if names.is_empty() {
+                let names = spell.vocations.keys().filter_map(|id| vocations.get_vocation(*id)).map(|v| v.name.clone());
+                return lua.create_sequence_from(names)?.into_lua(lua);
+            }
+            for name in names.iter() {
+                let mut parts = name.split(';');
+                let vocation = parts.next().unwrap_or("");
+                let show = parts.next().is_some_and(|show| matches!(show.trim(), "true" | "yes" | "1"));
+                spell.add_vocation(&vocations, vocation, show);
+            }
+            true.into_lua(lua)
+        });
+
+        // Motsvarar `luaSpellRegister`; kräver `onCastSpell`
+        methods.add_method("register", |lua, this, ()| {
+            if this.0.borrow().function.is_none() {
+                return Ok(false);
+            }
+            Ok(registry(lua)?.borrow_mut().register(this.0.clone()))
+        });
+
+        methods.add_meta_method(MetaMethod::Index, |lua, _, key: Value| {
+            global_table(lua, "Spell")?.get::<_, Value>(key)
+        });
+
+        // `spell.onCastSpell = function(creature, variant)`, som compat.lua i TFS
+        methods.add_meta_method(MetaMethod::NewIndex, |lua, this, (key, function): (String, Function)| {
+            if key != "onCastSpell" {
+                return Err(mlua::Error::RuntimeError(format!("Spell: invalid key {key}")));
+            }
+            set_on_cast_spell(lua, this, function)
+        });
+    }
+}
+
+fn set_on_cast_spell(lua: &Lua, spell: &LuaSpell, function: Function) -> mlua::Result<()> {
+    spell.0.borrow_mut().function = Some(lua.create_registry_value(function)?);
+    Ok(())
+}
+
+/// Tabellen `Player:getInstantSpells` ger för en besvärjelse, motsvarar
+/// `pushInstantSpell`
+fn push_instant_spell<'lua>(lua: &'lua Lua, instant: &InstantSpell) -> mlua::Result<Table<'lua>> {
+    let table = lua.create_table()?;
+    table.set("name", instant.spell.name.as_str())?;
+    table.set("words", instant.words.as_str())?;
+    table.set("level", instant.spell.level)?;
+    table.set("mlevel", instant.spell.magic_level)?;
+    table.set("mana", instant.spell.mana)?;
+    table.set("manapercent", instant.spell.mana_percent)?;
+    table.set("params", instant.has_param)?;
+    Ok(table)
+}
+
+fn instants(lua: &Lua) -> mlua::Result<Vec<InstantSpell>> {
+    Ok(registry(lua)?
+        .borrow()
+        .instants
+        .values()
+        .filter_map(|spell| match &spell.borrow().spell {
+            SpellKind::Instant(instant) => Some(instant.clone()),
+            SpellKind::Rune(_) => None,
+        })
+        .collect())
+}
+
+/// `Player:getInstantSpells()`: de spelaren får kasta
+pub(crate) fn player_instant_spells(lua: &Lua, player: u32) -> mlua::Result<Option<Table<'_>>> {
+    let instants = instants(lua)?;
+    let Some(spells) = read_player(lua, player, |player| {
+        instants
+            .iter()
+            .filter(|instant| instant.can_cast(player, &SpellFlags::from_player(player)))
+            .cloned()
+            .collect::<Vec<_>>()
+    })?
+    else {
+        return Ok(None);
+    };
+    let tables = spells.iter().map(|instant| push_instant_spell(lua, instant)).collect::<mlua::Result<Vec<_>>>()?;
+    Ok(Some(lua.create_sequence_from(tables)?))
+}
+
+/// `Player:canCast(spell)`, false för runes
+pub(crate) fn player_can_cast(lua: &Lua, player: u32, spell: &LuaSpell) -> mlua::Result<Option<bool>> {
+    let SpellKind::Instant(instant) = spell.0.borrow().spell.clone() else {
+        return Ok(Some(false));
+    };
+    read_player(lua, player, |player| instant.can_cast(player, &SpellFlags::from_player(player)))
+}
+
+/// `Player:canLearnSpell(name)`, motsvarar `luaPlayerCanLearnSpell`
+pub(crate) fn player_can_learn_spell(lua: &Lua, player: u32, name: &str) -> mlua::Result<Option<bool>> {
+    let Some(shared) = registry(lua)?.borrow().instant_by_name(name) else {
+        warn!("[Player:canLearnSpell] Spell \"{name}\" not found");
+        return Ok(Some(false));
+    };
+    let SpellKind::Instant(instant) = shared.borrow().spell.clone() else {
+        return Ok(Some(false));
+    };
+    read_player(lua, player, |player| instant.can_learn(player, &SpellFlags::from_player(player)))
+}
+
+/// Inställningarna från config.lua
+pub(crate) fn set_config(lua: &Lua, config: SpellConfig) -> mlua::Result<()> {
+    registry(lua)?.borrow_mut().config = config;
+    Ok(())
+}
+
+pub(crate) fn register(lua: &Lua) -> mlua::Result<()> {
+    lua.set_app_data(SpellHandle(Rc::new(RefCell::new(SpellRegistry::default()))));
+
+    // Spell(SPELL_INSTANT | SPELL_RUNE), Spell("instant" | "rune"), eller en
+    // registrerad: Spell(runeId), Spell(name) eller Spell(words)
+    register_class(
+        lua,
+        "Spell",
+        lua.create_function(|lua, (_, value): (Table, Value)| {
+            let registry = registry(lua)?;
+            let spell_type = match &value {
+                Value::Integer(_) | Value::Number(_) => {
+                    let id = lua.coerce_integer(value.clone())?.unwrap_or(0);
+                    if let Some(rune) = u16::try_from(id).ok().and_then(|id| registry.borrow().runes.get(&id).cloned()) {
+                        return Ok(Some(LuaSpell(rune)));
+                    }
+                    match id {
+                        1 => SpellType::Instant,
+                        2 => SpellType::Rune,
+                        _ => SpellType::Undefined,
+                    }
+                }
+                Value::String(text) => {
+                    let text = text.to_str()?;
+                    let found = {
+                        let registry = registry.borrow();
+                        registry
+                            .instant_by_name(text)
+                            .or_else(|| registry.instant_by_words(text))
+                            .or_else(|| registry.rune_by_name(text))
+                    };
+                    if let Some(spell) = found {
+                        return Ok(Some(LuaSpell(spell)));
+                    }
+                    match text.to_lowercase().as_str() {
+                        "instant" => SpellType::Instant,
+                        "rune" => SpellType::Rune,
+                        _ => SpellType::Undefined,
+                    }
+                }
+                _ => SpellType::Undefined,
+            };
+            Ok(SpellKind::new(spell_type)
+                .map(|spell| LuaSpell(Rc::new(RefCell::new(ScriptSpell { spell, function: None })))))
+        })?,
+    )?;
+
+    // spell:onCastSpell(function), som TFS; finns i klasstabellen så att
+    // helper_constructors.lua hittar den
+    global_table(lua, "Spell")?.set(
+        "onCastSpell",
+        lua.create_function(|lua, (this, function): (LuaSpell, Function)| {
+            set_on_cast_spell(lua, &this, function)?;
+            Ok(true)
+        })?,
+    )
+}
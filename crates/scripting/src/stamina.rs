@@ -0,0 +1,158 @@
+//! Spelarens metoder för stamina, premium och offline-träning, som
+//! `Player:onGainExperience`, regeneratestamina.lua och offlinetraining.lua
+//! använder. Gränserna och träningen finns i `rules::stamina`.
+
+use common::MessageClass;
+use entities::Skill;
+use mlua::{Lua, Value};
+use rules::experience::{AdvanceSkill, SKILL_MAGLEVEL};
+use rules::stamina;
+
+use crate::creature::{creature_id, push_creature, read_player, world};
+use crate::experience::{call_count_event, with_vocation};
+use crate::script_manager::global_table;
+
+/// Motsvarar `Player::addOfflineTrainingTries`, true om skillsen ändrades
+pub(crate) fn add_offline_training_tries(lua: &Lua, player: u32, skill: u8, tries: u64) -> mlua::Result<bool> {
+    let skill = match skill {
+        SKILL_MAGLEVEL => AdvanceSkill::MagicLevel,
+        id => match Skill::from_u8(id) {
+            Some(skill) => AdvanceSkill::Skill(skill),
+            None => return Ok(false),
+        },
+    };
+    if tries == 0 {
+        return Ok(false);
+    }
+    let trainable = with_vocation(lua, player, |p, vocation| stamina::can_train_offline(p, vocation, skill))?;
+    if trainable != Some(true) {
+        return Ok(false);
+    }
+
+    let args = (push_creature(lua, player)?, skill.id(), tries);
+    let tries = call_count_event(lua, "onGainSkillTries", tries, args);
+    let Some(training) =
+        with_vocation(lua, player, |p, vocation| stamina::add_offline_training_tries(p, vocation, skill, tries))?
+    else {
+        return Ok(false);
+    };
+
+    let world = world(lua)?;
+    for advance in &training.advances {
+        world.player_advance(player, advance);
+    }
+    if training.updated {
+        world.send_skills(player);
+    }
+    for message in &training.messages {
+        world.send_text_message(player, MessageClass::EventAdvance, message);
+    }
+    Ok(training.updated)
+}
+
+/// Metoderna ligger i klasstabellen så att data/lib kan använda dem. Talen
+/// tas emot som flyttal och kapas som `getNumber`, eftersom scripten räknar
+/// med division.
+pub(crate) fn register(lua: &Lua) -> mlua::Result<()> {
+    let player = global_table(lua, "Player")?;
+    player.set(
+        "getStamina",
+        lua.create_function(|lua, this: Value| {
+            let Some(id) = creature_id(lua, &this)? else {
+                return Ok(None);
+            };
+            read_player(lua, id, |p| p.stamina_minutes)
+        })?,
+    )?;
+    player.set(
+        "setStamina",
+        lua.create_function(|lua, (this, minutes): (Value, f64)| {
+            let Some(id) = creature_id(lua, &this)? else {
+                return Ok(None);
+            };
+            let world = world(lua)?;
+            world.with_player(id, &mut |p| stamina::set_stamina(p, minutes as u16));
+            world.send_stats(id);
+            Ok(Some(true))
+        })?,
+    )?;
+    // `Player.isPremium` i data/lib räknar med den
+    player.set(
+        "getPremiumEndsAt",
+        lua.create_function(|lua, this: Value| {
+            let Some(id) = creature_id(lua, &this)? else {
+                return Ok(None);
+            };
+            read_player(lua, id, |p| p.premium_ends_at)
+        })?,
+    )?;
+    player.set(
+        "getLastLogout",
+        lua.create_function(|lua, this: Value| {
+            let Some(id) = creature_id(lua, &this)? else {
+                return Ok(None);
+            };
+            read_player(lua, id, |p| p.last_logout)
+        })?,
+    )?;
+    player.set(
+        "getOfflineTrainingTime",
+        lua.create_function(|lua, this: Value| {
+            let Some(id) = creature_id(lua, &this)? else {
+                return Ok(None);
+            };
+            read_player(lua, id, |p| p.offline_training_time)
+        })?,
+    )?;
+    player.set(
+        "addOfflineTrainingTime",
+        lua.create_function(|lua, (this, time): (Value, f64)| {
+            let Some(id) = creature_id(lua, &this)? else {
+                return Ok(None);
+            };
+            let world = world(lua)?;
+            world.with_player(id, &mut |p| stamina::add_offline_training_time(p, time as i32));
+            world.send_stats(id);
+            Ok(Some(true))
+        })?,
+    )?;
+    player.set(
+        "removeOfflineTrainingTime",
+        lua.create_function(|lua, (this, time): (Value, f64)| {
+            let Some(id) = creature_id(lua, &this)? else {
+                return Ok(None);
+            };
+            let world = world(lua)?;
+            world.with_player(id, &mut |p| stamina::remove_offline_training_time(p, time as i32));
+            world.send_stats(id);
+            Ok(Some(true))
+        })?,
+    )?;
+    player.set(
+        "getOfflineTrainingSkill",
+        lua.create_function(|lua, this: Value| {
+            let Some(id) = creature_id(lua, &this)? else {
+                return Ok(None);
+            };
+            read_player(lua, id, |p| p.offline_training_skill)
+        })?,
+    )?;
+    player.set(
+        "setOfflineTrainingSkill",
+        lua.create_function(|lua, (this, skill): (Value, i32)| {
+            let Some(id) = creature_id(lua, &this)? else {
+                return Ok(None);
+            };
+            Ok(Some(world(lua)?.with_player(id, &mut |p| p.offline_training_skill = skill)))
+        })?,
+    )?;
+    player.set(
+        "addOfflineTrainingTries",
+        lua.create_function(|lua, (this, skill, tries): (Value, u8, f64)| {
+            let Some(id) = creature_id(lua, &this)? else {
+                return Ok(None);
+            };
+            Ok(Some(add_offline_training_tries(lua, id, skill, tries as u64)?))
+        })?,
+    )
+}
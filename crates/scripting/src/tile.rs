@@ -0,0 +1,73 @@
+//! Lua-klassen `Tile`, motsvarar (en del av) luaTile* i TFS. Ett Lua-objekt
+//! är bara tilens position; kartan ägs av spelet och frågas genom
+//! `ScriptWorld`.
+
+use common::Position;
+use mlua::{Lua, MetaMethod, Table, UserData, UserDataMethods, Value};
+
+use crate::creature::{creature_id, register_class, world};
+use crate::house::push_house;
+use crate::position::{get_position, push_position};
+use crate::script_manager::global_table;
+
+#[derive(Clone, Copy)]
+pub struct LuaTile(pub Position);
+
+/// Tilen på positionen, nil om den inte finns
+pub(crate) fn push_tile(lua: &Lua, pos: Position) -> mlua::Result<Option<LuaTile>> {
+    Ok(world(lua)?.tile_house_id(pos).map(|_| LuaTile(pos)))
+}
+
+impl UserData for LuaTile {
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method("getPosition", |lua, this, ()| push_position(lua, this.0));
+        methods.add_method("getHouse", |lua, this, ()| match world(lua)?.tile_house_id(this.0) {
+            Some(house_id) if house_id != 0 => push_house(lua, house_id),
+            _ => Ok(None),
+        });
+
+        methods.add_meta_method(MetaMethod::Eq, |_, this, other: LuaTile| Ok(this.0 == other.0));
+        methods.add_meta_method(MetaMethod::Index, |lua, _, key: Value| {
+            global_table(lua, "Tile")?.get::<_, Value>(key)
+        });
+    }
+}
+
+impl<'lua> mlua::FromLua<'lua> for LuaTile {
+    fn from_lua(value: Value<'lua>, _: &'lua Lua) -> mlua::Result<Self> {
+        match value {
+            Value::UserData(userdata) => Ok(*userdata.borrow::<LuaTile>()?),
+            _ => Err(mlua::Error::FromLuaConversionError { from: value.type_name(), to: "Tile", message: None }),
+        }
+    }
+}
+
+pub(crate) fn register(lua: &Lua) -> mlua::Result<()> {
+    // Tile(x, y, z) / Tile(position)
+    register_class(
+        lua,
+        "Tile",
+        lua.create_function(|lua, (_, first, y, z): (Table, Value, Option<u16>, Option<u8>)| {
+            let pos = match first {
+                Value::Table(table) => get_position(&table)?,
+                Value::Integer(x) => Position::new(x as u16, y.unwrap_or(0), z.unwrap_or(0)),
+                Value::Number(x) => Position::new(x as u16, y.unwrap_or(0), z.unwrap_or(0)),
+                _ => return Ok(None),
+            };
+            push_tile(lua, pos)
+        })?,
+    )?;
+
+    global_table(lua, "Creature")?.set(
+        "getTile",
+        lua.create_function(|lua, this: Value| {
+            let Some(id) = creature_id(lua, &this)? else {
+                return Ok(None);
+            };
+            match world(lua)?.creature_position(id) {
+                Some(pos) => push_tile(lua, pos),
+                None => Ok(None),
+            }
+        })?,
+    )
+}
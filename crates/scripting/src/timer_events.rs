@@ -0,0 +1,105 @@
+//! `addEvent(callback, delay, ...)` och `stopEvent(id)`, motsvarar
+//! luaAddEvent/luaStopEvent och `LuaEnvironment::executeTimerEvent` i TFS.
+//! Spelet kör det som är dags med `ScriptManager::execute_timer_events`.
+
+use std::collections::BTreeSet;
+
+use common::otsys_time;
+use common::tracing::warn;
+use mlua::{Function, Lua, MultiValue, Table, Value, Variadic};
+
+/// Tabellen i registret med callback och argument per event-id
+const TIMER_EVENTS: &str = "TimerEvents";
+
+#[derive(Default)]
+struct TimerQueue {
+    last_id: u32,
+    /// (när, id)
+    due: BTreeSet<(u64, u32)>,
+}
+
+fn events_table(lua: &Lua) -> mlua::Result<Table<'_>> {
+    match lua.named_registry_value::<Option<Table>>(TIMER_EVENTS)? {
+        Some(events) => Ok(events),
+        None => {
+            let events = lua.create_table()?;
+            lua.set_named_registry_value(TIMER_EVENTS, events.clone())?;
+            Ok(events)
+        }
+    }
+}
+
+pub(crate) fn register(lua: &Lua) -> mlua::Result<()> {
+    lua.set_app_data(TimerQueue::default());
+
+    let globals = lua.globals();
+    globals.set(
+        "addEvent",
+        lua.create_function(|lua, (callback, delay, args): (Function, i64, Variadic<Value>)| {
+            let id = {
+                let mut queue = lua.app_data_mut::<TimerQueue>().expect("timer queue registered");
+                queue.last_id += 1;
+                let id = queue.last_id;
+                queue.due.insert((otsys_time() + delay.max(1) as u64, id));
+                id
+            };
+            let event = lua.create_table()?;
+            event.set("callback", callback)?;
+            event.set("args", lua.create_sequence_from(args.iter().cloned())?)?;
+            event.set("n", args.len())?;
+            events_table(lua)?.set(id, event)?;
+            Ok(id)
+        })?,
+    )?;
+    globals.set(
+        "stopEvent",
+        lua.create_function(|lua, id: Option<u32>| {
+            let Some(id) = id else {
+                return Ok(false);
+            };
+            let events = events_table(lua)?;
+            if events.get::<_, Option<Table>>(id)?.is_none() {
+                return Ok(false);
+            }
+            events.set(id, Value::Nil)?;
+            let mut queue = lua.app_data_mut::<TimerQueue>().expect("timer queue registered");
+            queue.due.retain(|&(_, due_id)| due_id != id);
+            Ok(true)
+        })?,
+    )?;
+    Ok(())
+}
+
+/// Kör alla events vars tid kommit. Ett fel i ett event loggas och
+/// stoppar inte de andra. Returnerar antalet körda.
+pub(crate) fn execute(lua: &Lua, now: u64) -> mlua::Result<usize> {
+    let events = events_table(lua)?;
+    let mut count = 0;
+    loop {
+        // kön får inte vara lånad medan callbacken kör, den kan lägga till nya
+        let next = {
+            let mut queue = lua.app_data_mut::<TimerQueue>().expect("timer queue registered");
+            match queue.due.first().copied() {
+                Some((time, id)) if time <= now => {
+                    queue.due.remove(&(time, id));
+                    id
+                }
+                _ => break,
+            }
+        };
+        let Some(event) = events.get::<_, Option<Table>>(next)? else {
+            continue;
+        };
+        events.set(next, Value::Nil)?;
+
+        let callback: Function = event.get("callback")?;
+        let args: Table = event.get("args")?;
+        let n: usize = event.get("n")?;
+        let args = (1..=n).map(|i| args.get::<_, Value>(i)).collect::<mlua::Result<MultiValue>>()?;
+        if let Err(e) = callback.call::<_, ()>(args) {
+            warn!("[LuaEnvironment::executeTimerEvent] {e}");
+        }
+        count += 1;
+    }
+    Ok(count)
+}
@@ -0,0 +1,48 @@
+//! Lua-klassen `Town` och `Game.getTowns()`, motsvarar luaTown* i TFS.
+//! Städerna ändras inte efter att kartan laddats, så scripten får en egen
+//! kopia av registret.
+
+use std::sync::Arc;
+
+use mlua::{Lua, UserData, UserDataMethods, Value};
+use world::{Town, Towns};
+
+use crate::position::push_position;
+use crate::script_manager::global_table;
+
+#[derive(Clone)]
+pub struct LuaTown(pub Town);
+
+impl UserData for LuaTown {
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method("getId", |_, this, ()| Ok(this.0.id));
+        methods.add_method("getName", |_, this, ()| Ok(this.0.name.clone()));
+        methods.add_method("getTemplePosition", |lua, this, ()| push_position(lua, this.0.temple_position));
+    }
+}
+
+pub fn register(lua: &Lua, towns: &Towns) -> mlua::Result<()> {
+    let towns = Arc::new(towns.clone());
+
+    // Town(id) / Town(name)
+    let lookup = towns.clone();
+    lua.globals().set(
+        "Town",
+        lua.create_function(move |_, arg: Value| {
+            let town = match arg {
+                Value::Integer(id) => lookup.get_town(id as u32),
+                Value::Number(id) => lookup.get_town(id as u32),
+                Value::String(name) => lookup.get_town_by_name(name.to_str()?),
+                _ => None,
+            };
+            Ok(town.cloned().map(LuaTown))
+        })?,
+    )?;
+
+    global_table(lua, "Game")?.set(
+        "getTowns",
+        lua.create_function(move |lua, ()| {
+            lua.create_sequence_from(towns.iter().cloned().map(LuaTown))
+        })?,
+    )
+}
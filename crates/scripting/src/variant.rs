@@ -0,0 +1,120 @@
+//! `Variant`, det spells och runes får som mål. Motsvarar `LuaVariant` och
+//! luaVariant* i TFS: en tabell med `type` och värdet, med `Variant` som
+//! metatabell.
+
+use common::Position;
+use mlua::{Lua, Table, Value};
+
+use crate::creature::{creature_id, register_class};
+use crate::position::{get_position, push_position};
+use crate::script_manager::global_table;
+
+pub const VARIANT_NONE: u8 = 0;
+pub const VARIANT_NUMBER: u8 = 1;
+pub const VARIANT_POSITION: u8 = 2;
+pub const VARIANT_TARGETPOSITION: u8 = 3;
+pub const VARIANT_STRING: u8 = 4;
+
+/// Motsvarar `LuaVariant`
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum LuaVariant {
+    #[default]
+    None,
+    /// Ett varelse-id
+    Number(u32),
+    Position(Position),
+    /// En ruta som en rune kastats på
+    TargetPosition(Position),
+    /// Ett spelarnamn
+    String(String),
+}
+
+/// Motsvarar `LuaScriptInterface::pushVariant`
+pub fn push_variant<'lua>(lua: &'lua Lua, variant: &LuaVariant) -> mlua::Result<Table<'lua>> {
+    let table = lua.create_table()?;
+    match variant {
+        LuaVariant::None => table.set("type", VARIANT_NONE)?,
+        LuaVariant::Number(number) => {
+            table.set("type", VARIANT_NUMBER)?;
+            table.set("number", *number)?;
+        }
+        LuaVariant::Position(pos) => {
+            table.set("type", VARIANT_POSITION)?;
+            table.set("pos", push_position(lua, *pos)?)?;
+        }
+        LuaVariant::TargetPosition(pos) => {
+            table.set("type", VARIANT_TARGETPOSITION)?;
+            table.set("pos", push_position(lua, *pos)?)?;
+        }
+        LuaVariant::String(string) => {
+            table.set("type", VARIANT_STRING)?;
+            table.set("string", string.as_str())?;
+        }
+    }
+    table.set_metatable(Some(global_table(lua, "Variant")?));
+    Ok(table)
+}
+
+/// Motsvarar `LuaScriptInterface::getVariant`
+pub fn get_variant(value: &Value) -> mlua::Result<LuaVariant> {
+    let Value::Table(table) = value else {
+        return Ok(LuaVariant::None);
+    };
+    let position = || -> mlua::Result<Position> {
+        match table.get::<_, Option<Table>>("pos")? {
+            Some(pos) => get_position(&pos),
+            None => Ok(Position::default()),
+        }
+    };
+    Ok(match table.get::<_, Option<u8>>("type")?.unwrap_or(VARIANT_NONE) {
+        VARIANT_NUMBER => LuaVariant::Number(table.get::<_, Option<u32>>("number")?.unwrap_or(0)),
+        VARIANT_POSITION => LuaVariant::Position(position()?),
+        VARIANT_TARGETPOSITION => LuaVariant::TargetPosition(position()?),
+        VARIANT_STRING => LuaVariant::String(table.get::<_, Option<String>>("string")?.unwrap_or_default()),
+        _ => LuaVariant::None,
+    })
+}
+
+pub(crate) fn register(lua: &Lua) -> mlua::Result<()> {
+    // Variant(creature | number | string | position)
+    register_class(
+        lua,
+        "Variant",
+        lua.create_function(|lua, (_, value): (Table, Value)| {
+            let variant = match &value {
+                Value::UserData(_) => LuaVariant::Number(creature_id(lua, &value)?.unwrap_or(0)),
+                Value::Integer(number) => LuaVariant::Number(*number as u32),
+                Value::Number(number) => LuaVariant::Number(*number as u32),
+                Value::String(string) => LuaVariant::String(string.to_str()?.to_string()),
+                Value::Table(table) => LuaVariant::Position(get_position(table)?),
+                _ => LuaVariant::None,
+            };
+            push_variant(lua, &variant)
+        })?,
+    )?;
+
+    let class = global_table(lua, "Variant")?;
+    class.set("__index", class.clone())?;
+    class.set(
+        "getNumber",
+        lua.create_function(|_, this: Value| match get_variant(&this)? {
+            LuaVariant::Number(number) => Ok(number),
+            _ => Ok(0),
+        })?,
+    )?;
+    class.set(
+        "getString",
+        lua.create_function(|_, this: Value| match get_variant(&this)? {
+            LuaVariant::String(string) => Ok(string),
+            _ => Ok(String::new()),
+        })?,
+    )?;
+    class.set(
+        "getPosition",
+        lua.create_function(|lua, this: Value| match get_variant(&this)? {
+            LuaVariant::Position(pos) | LuaVariant::TargetPosition(pos) => push_position(lua, pos),
+            _ => push_position(lua, Position::default()),
+        })?,
+    )?;
+    Ok(())
+}
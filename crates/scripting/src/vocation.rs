@@ -0,0 +1,118 @@
+//! Lua-klassen `Vocation`, motsvarar luaVocation* i TFS. Yrkena ändras inte
+//! efter inläsningen, så scripten får en egen kopia av registret.
+
+use std::sync::Arc;
+
+use entities::Skill;
+use mlua::{Lua, MetaMethod, Table, UserData, UserDataMethods, Value};
+use rules::vocation::{Vocation, Vocations};
+
+use crate::creature::register_class;
+use crate::script_manager::global_table;
+
+/// Registret som app data
+#[derive(Clone)]
+struct VocationRegistry(Arc<Vocations>);
+
+#[derive(Clone, Copy)]
+pub struct LuaVocation(pub u16);
+
+pub(crate) fn registry(lua: &Lua) -> mlua::Result<Arc<Vocations>> {
+    lua.app_data_ref::<VocationRegistry>()
+        .map(|registry| registry.0.clone())
+        .ok_or_else(|| mlua::Error::RuntimeError("no vocations registered".into()))
+}
+
+/// Läs ett värde från yrket, nil om det inte finns
+fn read_vocation<T>(lua: &Lua, id: u16, f: impl FnOnce(&Vocation) -> T) -> mlua::Result<Option<T>> {
+    Ok(registry(lua)?.get_vocation(id).map(f))
+}
+
+/// Yrket med id:t, nil om det inte finns
+pub(crate) fn push_vocation(lua: &Lua, id: u16) -> mlua::Result<Option<LuaVocation>> {
+    read_vocation(lua, id, |_| LuaVocation(id))
+}
+
+impl UserData for LuaVocation {
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method("getId", |_, this, ()| Ok(this.0));
+        methods.add_method("getClientId", |lua, this, ()| read_vocation(lua, this.0, |v| v.client_id));
+        methods.add_method("getName", |lua, this, ()| read_vocation(lua, this.0, |v| v.name.clone()));
+        methods.add_method("getDescription", |lua, this, ()| read_vocation(lua, this.0, |v| v.description.clone()));
+        methods.add_method("getRequiredSkillTries", |lua, this, (skill, level): (u8, u16)| {
+            let Some(skill) = Skill::from_u8(skill) else {
+                return Ok(Some(0));
+            };
+            read_vocation(lua, this.0, |v| v.get_req_skill_tries(skill, level))
+        });
+        methods.add_method("getRequiredManaSpent", |lua, this, mag_level: u32| {
+            read_vocation(lua, this.0, |v| v.get_req_mana(mag_level))
+        });
+        methods.add_method("getCapacityGain", |lua, this, ()| read_vocation(lua, this.0, |v| v.gain_cap));
+        methods.add_method("getHealthGain", |lua, this, ()| read_vocation(lua, this.0, |v| v.gain_hp));
+        methods.add_method("getHealthGainTicks", |lua, this, ()| read_vocation(lua, this.0, |v| v.gain_health_ticks));
+        methods.add_method("getHealthGainAmount", |lua, this, ()| read_vocation(lua, this.0, |v| v.gain_health_amount));
+        methods.add_method("getManaGain", |lua, this, ()| read_vocation(lua, this.0, |v| v.gain_mana));
+        methods.add_method("getManaGainTicks", |lua, this, ()| read_vocation(lua, this.0, |v| v.gain_mana_ticks));
+        methods.add_method("getManaGainAmount", |lua, this, ()| read_vocation(lua, this.0, |v| v.gain_mana_amount));
+        methods.add_method("getMaxSoul", |lua, this, ()| read_vocation(lua, this.0, |v| v.soul_max));
+        methods.add_method("getSoulGainTicks", |lua, this, ()| read_vocation(lua, this.0, |v| v.gain_soul_ticks));
+        methods.add_method("getAttackSpeed", |lua, this, ()| read_vocation(lua, this.0, |v| v.attack_speed));
+        methods.add_method("getBaseSpeed", |lua, this, ()| read_vocation(lua, this.0, |v| v.base_speed));
+        methods.add_method("allowsPvp", |lua, this, ()| read_vocation(lua, this.0, |v| v.allow_pvp));
+
+        // yrket det här är en befordran av, nil om det inte är någon
+        methods.add_method("getDemotion", |lua, this, ()| {
+            let Some(from) = read_vocation(lua, this.0, |v| v.from_vocation)? else {
+                return Ok(None);
+            };
+            if from == this.0 {
+                return Ok(None);
+            }
+            push_vocation(lua, from)
+        });
+        methods.add_method("getPromotion", |lua, this, ()| match registry(lua)?.get_promoted_vocation(this.0) {
+            Some(id) => push_vocation(lua, id),
+            None => Ok(None),
+        });
+
+        methods.add_meta_method(MetaMethod::Eq, |_, this, other: LuaVocation| Ok(this.0 == other.0));
+        methods.add_meta_method(MetaMethod::Index, |lua, _, key: Value| {
+            global_table(lua, "Vocation")?.get::<_, Value>(key)
+        });
+    }
+}
+
+impl<'lua> mlua::FromLua<'lua> for LuaVocation {
+    fn from_lua(value: Value<'lua>, _: &'lua Lua) -> mlua::Result<Self> {
+        match value {
+            Value::UserData(userdata) => Ok(*userdata.borrow::<LuaVocation>()?),
+            _ => Err(mlua::Error::FromLuaConversionError { from: value.type_name(), to: "Vocation", message: None }),
+        }
+    }
+}
+
+/// Id:t ur ett yrkesobjekt, ett nummer eller ett namn
+pub(crate) fn vocation_id(lua: &Lua, value: &Value) -> mlua::Result<Option<u16>> {
+    Ok(match value {
+        Value::Integer(id) => Some(*id as u16),
+        Value::Number(id) => Some(*id as u16),
+        Value::String(name) => registry(lua)?.get_vocation_id(name.to_str()?),
+        Value::UserData(userdata) => userdata.borrow::<LuaVocation>().ok().map(|v| v.0),
+        _ => None,
+    })
+}
+
+pub fn register(lua: &Lua, vocations: &Vocations) -> mlua::Result<()> {
+    lua.set_app_data(VocationRegistry(Arc::new(vocations.clone())));
+
+    // Vocation(id) / Vocation(name)
+    register_class(
+        lua,
+        "Vocation",
+        lua.create_function(|lua, (_, value): (Table, Value)| match vocation_id(lua, &value)? {
+            Some(id) => push_vocation(lua, id),
+            None => Ok(None),
+        })?,
+    )
+}
@@ -0,0 +1,430 @@
+//! Vapnen med sina script: weapons.xml, revscriptens `Weapon()` och
+//! attackerna. Motsvarar `Weapons`, luaWeapon* och `Weapon::executeUseWeapon`
+//! i TFS. Formlerna och kontrollerna finns i `rules::weapons`.
+//!
+//! Registret ligger som app data i Lua-tillståndet, som för besvärjelserna.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::rc::Rc;
+
+use common::tracing::warn;
+use common::{otsys_time, CombatType, Error, Result, ShootType};
+use entities::Player;
+use items::{Items, WeaponType};
+use mlua::{Function, Lua, MetaMethod, RegistryKey, Table, UserData, UserDataMethods, Value};
+use rules::combat::{CombatValue, Combatant};
+use rules::weapons::{
+    self as rules_weapons, on_used_weapon, use_fist, Weapon, WeaponAction, WeaponConfig, WeaponKind, WeaponTarget,
+    WeaponUse, WeaponUser, XmlWeapon,
+};
+
+use crate::creature::{push_creature, read_player, register_class, world};
+use crate::experience;
+use crate::script_manager::{global_table, run_file, script_error};
+use crate::variant::{push_variant, LuaVariant};
+use crate::vocation;
+
+/// Ett vapen och dess `onUseWeapon`
+struct ScriptWeapon {
+    weapon: Weapon,
+    function: Option<RegistryKey>,
+}
+
+type SharedWeapon = Rc<RefCell<ScriptWeapon>>;
+
+/// De registrerade vapnen, som i `Weapons`
+#[derive(Default)]
+struct WeaponRegistry {
+    weapons: BTreeMap<u16, SharedWeapon>,
+    config: WeaponConfig,
+}
+
+/// Registret som app data
+#[derive(Clone)]
+struct WeaponHandle(Rc<RefCell<WeaponRegistry>>);
+
+fn registry(lua: &Lua) -> mlua::Result<Rc<RefCell<WeaponRegistry>>> {
+    lua.app_data_ref::<WeaponHandle>()
+        .map(|handle| handle.0.clone())
+        .ok_or_else(|| mlua::Error::RuntimeError("no weapons registered".into()))
+}
+
+/// Vapnet för itemet, motsvarar `Weapons::getWeapon`
+fn get_weapon(lua: &Lua, item_id: u16) -> mlua::Result<Option<SharedWeapon>> {
+    Ok(registry(lua)?.borrow().weapons.get(&item_id).cloned())
+}
+
+/// Spelaren som den är nu och det som inte finns på `Player`
+fn weapon_user(lua: &Lua, player: u32, config: &WeaponConfig) -> mlua::Result<Option<(Player, WeaponUser)>> {
+    let vocations = vocation::registry(lua).ok();
+    read_player(lua, player, |player| {
+        let vocation = vocations.as_ref().and_then(|vocations| vocations.get_vocation(player.vocation));
+        (player.clone(), WeaponUser::new(player, vocation, config, otsys_time()))
+    })
+}
+
+/// Största skadan och elementet spelarens vapen ger `COMBAT_FORMULA_SKILL`;
+/// med `use_charges` tappar vapnet en laddning. Motsvarar vapendelen av
+/// `Combat::getCombatDamage`.
+pub(crate) fn formula_damage(lua: &Lua, player: &Combatant, use_charges: bool) -> Option<(i32, CombatValue)> {
+    let config = registry(lua).ok()?.borrow().config;
+    let (state, user) = weapon_user(lua, player.id, &config).ok()??;
+    let slot = state.get_weapon_slot(false)?;
+    let item = state.inventory_item(slot)?;
+    let shared = get_weapon(lua, item.id).ok()??;
+    let (max_damage, combat_type, value) = shared.borrow().weapon.formula_damage(&state, &user, item);
+
+    if use_charges && item.attributes().charges != 0 {
+        world(lua).ok()?.with_player(player.id, &mut |player| {
+            if let Some(item) = player.inventory_item_mut(slot) {
+                item.attributes_mut().charges -= 1;
+            }
+        });
+    }
+    Some((max_damage, CombatValue { combat_type, value }))
+}
+
+/// Vapnen för spelet
+pub struct Weapons<'lua> {
+    pub(crate) lua: &'lua Lua,
+}
+
+impl Weapons<'_> {
+    /// Läs weapons.xml. Scripten ligger i `scripts/` bredvid filen och
+    /// `lib/weapons.lua` körs först. Yrkena och itemen måste vara inlästa.
+    /// Returnerar antalet registrerade vapen.
+    pub fn load_from_xml(&self, path: impl AsRef<Path>) -> Result<usize> {
+        let path = path.as_ref();
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        let vocations = vocation::registry(self.lua).map_err(script_error)?;
+        let weapons = rules_weapons::load_from_xml(path, &vocations)?;
+
+        let lib = dir.join("lib").join("weapons.lua");
+        if lib.exists() {
+            run_file(self.lua, &lib)?;
+        }
+
+        let mut count = 0;
+        for XmlWeapon { weapon, script } in weapons {
+            let id = weapon.id;
+            let script = script.map(|script| dir.join("scripts").join(script));
+            match self.load_weapon(weapon, script.as_deref()) {
+                Ok(true) => count += 1,
+                Ok(false) => {}
+                Err(e) => warn!("[Weapons::load_from_xml] Cannot load weapon {id}: {e}"),
+            }
+        }
+        Ok(count)
+    }
+
+    /// Registrera vapnet, med `onUseWeapon` från scriptet om det har ett.
+    /// Motsvarar `Weapons::registerEvent`.
+    fn load_weapon(&self, weapon: Weapon, script: Option<&Path>) -> Result<bool> {
+        let lua = self.lua;
+        let function = match script {
+            Some(script) => {
+                run_file(lua, script)?;
+                let globals = lua.globals();
+                let function: Option<Function> = globals.get("onUseWeapon").map_err(script_error)?;
+                let Some(function) = function else {
+                    return Err(Error::Script(format!("onUseWeapon not found in {}", script.display())));
+                };
+                globals.set("onUseWeapon", Value::Nil).map_err(script_error)?;
+                Some(lua.create_registry_value(function).map_err(script_error)?)
+            }
+            None => None,
+        };
+
+        let registry = registry(lua).map_err(script_error)?;
+        let mut registry = registry.borrow_mut();
+        if registry.weapons.contains_key(&weapon.id) {
+            warn!("[Weapons::registerEvent] Duplicate registered item with id: {}", weapon.id);
+            return Ok(false);
+        }
+        registry.weapons.insert(weapon.id, Rc::new(RefCell::new(ScriptWeapon { weapon, function })));
+        Ok(true)
+    }
+
+    /// Vapen för närstrids- och distansitemen som inte är registrerade.
+    /// Motsvarar `Weapons::loadDefaults`.
+    pub fn load_defaults(&self) -> Result<usize> {
+        let Some(items) = Items::instance() else {
+            return Ok(0);
+        };
+        let registry = registry(self.lua).map_err(script_error)?;
+        let mut registry = registry.borrow_mut();
+        let mut count = 0;
+        for weapon in rules_weapons::default_weapons(items.iter()) {
+            if registry.weapons.contains_key(&weapon.id) {
+                continue;
+            }
+            registry.weapons.insert(weapon.id, Rc::new(RefCell::new(ScriptWeapon { weapon, function: None })));
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Är itemet ett registrerat vapen
+    pub fn is_weapon(&self, item_id: u16) -> bool {
+        registry(self.lua).is_ok_and(|registry| registry.borrow().weapons.contains_key(&item_id))
+    }
+
+    /// Spelaren anfaller `target` med sitt vapen, eller med knytnäven om det
+    /// inte finns något registrerat. Kostnaden dras direkt; det som träffade
+    /// och att flytta ett kastat vapen lämnas till spelet. `None` om
+    /// attacken inte blev av. Motsvarar vapendelen av `Player::doAttacking`.
+    pub fn use_weapon(&self, player: u32, target: u32) -> Result<Option<WeaponUse>> {
+        let lua = self.lua;
+        let config = registry(lua).map_err(script_error)?.borrow().config;
+        let Some((state, user)) = weapon_user(lua, player, &config).map_err(script_error)? else {
+            return Ok(None);
+        };
+        let world = world(lua).map_err(script_error)?;
+
+        let slot = state.get_weapon_slot(false);
+        let shared = match slot.and_then(|slot| state.inventory_item(slot)) {
+            Some(item) => get_weapon(lua, item.id).map_err(script_error)?,
+            None => None,
+        };
+        let main_weapon = match state.get_weapon(true) {
+            Some(bow) if bow.weapon_type() == WeaponType::Distance => {
+                get_weapon(lua, bow.id).map_err(script_error)?.map(|shared| shared.borrow().weapon.clone())
+            }
+            _ => None,
+        };
+        let (weapon, function) = match &shared {
+            Some(shared) => {
+                let shared = shared.borrow();
+                let function = match shared.function.as_ref() {
+                    Some(key) => Some(lua.registry_value::<Function>(key).map_err(script_error)?),
+                    None => None,
+                };
+                (Some(shared.weapon.clone()), function)
+            }
+            None => (None, None),
+        };
+
+        let mut weapon_use = None;
+        world.with_combat_view(&mut |view| {
+            let Some(target) = view.combatant(target) else {
+                return;
+            };
+            weapon_use = match (&weapon, slot) {
+                (Some(weapon), Some(slot)) => weapon.use_weapon(
+                    &state,
+                    &user,
+                    slot,
+                    main_weapon.as_ref(),
+                    view,
+                    &target,
+                    function.is_some(),
+                ),
+                _ => use_fist(&state, &user, view, &target),
+            };
+        });
+        let Some(weapon_use) = weapon_use else {
+            return Ok(None);
+        };
+
+        if let Some(function) = function {
+            let variant = match weapon_use.target {
+                WeaponTarget::Creature(id) => LuaVariant::Number(id),
+                WeaponTarget::Position(pos) => LuaVariant::TargetPosition(pos),
+            };
+            let args = (push_creature(lua, player).map_err(script_error)?, push_variant(lua, &variant).map_err(script_error)?);
+            if let Err(e) = function.call::<_, Value>(args) {
+                warn!("[Weapon::executeUseWeapon] {e}");
+            }
+        }
+
+        let flags = user.flags;
+        let mut skill_tries = None;
+        world.with_player(player, &mut |p| skill_tries = on_used_weapon(p, &weapon_use, &flags, &config));
+        if let Some((skill, tries)) = skill_tries {
+            experience::add_skill_tries(lua, player, skill, tries).map_err(script_error)?;
+        }
+        if weapon_use.mana != 0 {
+            experience::add_mana_spent(lua, player, weapon_use.mana as u64).map_err(script_error)?;
+        }
+        Ok(Some(weapon_use))
+    }
+}
+
+#[derive(Clone)]
+pub struct LuaWeapon(SharedWeapon);
+
+impl<'lua> mlua::FromLua<'lua> for LuaWeapon {
+    fn from_lua(value: Value<'lua>, _: &'lua Lua) -> mlua::Result<Self> {
+        match value {
+            Value::UserData(userdata) => Ok(userdata.borrow::<LuaWeapon>()?.clone()),
+            _ => Err(mlua::Error::FromLuaConversionError { from: value.type_name(), to: "Weapon", message: None }),
+        }
+    }
+}
+
+/// Sätter ett fält, `weapon:level(20)`; som i TFS går de inte att läsa
+macro_rules! setter {
+    ($methods:ident, $name:literal, $($field:ident).+: $ty:ty) => {
+        $methods.add_method($name, |_, this, value: $ty| {
+            this.0.borrow_mut().weapon.$($field).+ = value;
+            Ok(true)
+        });
+    };
+}
+
+/// Skadetypen som siffra eller namn, `None` för en okänd
+fn combat_type(value: &Value) -> mlua::Result<Option<CombatType>> {
+    match value {
+        Value::Integer(value) => Ok(Some(CombatType(*value as u16))),
+        Value::Number(value) => Ok(Some(CombatType(*value as u16))),
+        Value::String(name) => Ok(rules_weapons::wand_combat_type(name.to_str()?)),
+        _ => Ok(None),
+    }
+}
+
+impl UserData for LuaWeapon {
+    fn add_methods<'lua, M: UserDataMethods<'lua, Self>>(methods: &mut M) {
+        setter!(methods, "id", id: u16);
+        setter!(methods, "level", level: u32);
+        setter!(methods, "magicLevel", magic_level: u32);
+        setter!(methods, "mana", mana: u32);
+        setter!(methods, "manaPercent", mana_percent: u32);
+        setter!(methods, "soul", soul: u32);
+        setter!(methods, "premium", premium: bool);
+        setter!(methods, "wieldUnproperly", wield_unproperly: bool);
+
+        methods.add_method("shootType", |_, this, shoot_type: u8| {
+            this.0.borrow_mut().weapon.params.distance_effect = ShootType(shoot_type);
+            Ok(true)
+        });
+        methods.add_method("breakChance", |_, this, chance: u32| {
+            this.0.borrow_mut().weapon.break_chance = chance.min(100) as u8;
+            Ok(true)
+        });
+        // action("removecount" | "removecharge" | "move")
+        methods.add_method("action", |_, this, name: String| {
+            let Some(action) = WeaponAction::from_name(&name) else {
+                warn!("[Weapon::action] No valid action {name}");
+                return Ok(false);
+            };
+            this.0.borrow_mut().weapon.action = action;
+            Ok(true)
+        });
+        // vocation(name[, showInDescription])
+        methods.add_method("vocation", |lua, this, (name, show): (String, Option<bool>)| {
+            let vocations = vocation::registry(lua)?;
+            Ok(this.0.borrow_mut().weapon.add_vocation(&vocations, &name, show.unwrap_or(false)))
+        });
+        // damage(min[, max]), bara för wands
+        methods.add_method("damage", |_, this, (min, max): (i32, Option<i32>)| {
+            let mut weapon = this.0.borrow_mut();
+            weapon.weapon.min_change = min;
+            weapon.weapon.max_change = max.unwrap_or(min);
+            Ok(true)
+        });
+        // element(type): skadetypen för en wand
+        methods.add_method("element", |_, this, value: Value| {
+            let Some(combat_type) = combat_type(&value)? else {
+                warn!("[weapon:element] Type {value:?} does not exist.");
+                return Ok(false);
+            };
+            this.0.borrow_mut().weapon.params.combat_type = combat_type;
+            Ok(true)
+        });
+        // extraElement(attack, type): elementet närstrids- och distansvapen
+        // lägger till
+        methods.add_method("extraElement", |_, this, (attack, value): (u16, Value)| {
+            let Some(combat_type) = combat_type(&value)? else {
+                warn!("[weapon:extraElement] Type {value:?} does not exist.");
+                return Ok(false);
+            };
+            let mut weapon = this.0.borrow_mut();
+            weapon.weapon.element_type = combat_type;
+            weapon.weapon.element_damage = attack;
+            weapon.weapon.params.use_charges = true;
+            Ok(true)
+        });
+
+        // Motsvarar `luaWeaponRegister`; ersätter ett vapen med samma id
+        methods.add_method("register", |lua, this, ()| {
+            let id = {
+                let mut shared = this.0.borrow_mut();
+                let weapon = &mut shared.weapon;
+                let distance_effect = weapon.params.distance_effect;
+                let element = (weapon.element_type, weapon.element_damage);
+                weapon.configure_weapon(Items::get(weapon.id));
+                if distance_effect != ShootType::NONE {
+                    weapon.params.distance_effect = distance_effect;
+                }
+                if element.0 != CombatType::NONE {
+                    (weapon.element_type, weapon.element_damage) = element;
+                }
+                weapon.id
+            };
+            if id == 0 {
+                return Ok(false);
+            }
+            registry(lua)?.borrow_mut().weapons.insert(id, this.0.clone());
+            Ok(true)
+        });
+
+        methods.add_meta_method(MetaMethod::Index, |lua, _, key: Value| {
+            global_table(lua, "Weapon")?.get::<_, Value>(key)
+        });
+
+        // `weapon.onUseWeapon = function(player, variant)`, som compat.lua i TFS
+        methods.add_meta_method(MetaMethod::NewIndex, |lua, this, (key, function): (String, Function)| {
+            if key != "onUseWeapon" {
+                return Err(mlua::Error::RuntimeError(format!("Weapon: invalid key {key}")));
+            }
+            set_on_use_weapon(lua, this, function)
+        });
+    }
+}
+
+fn set_on_use_weapon(lua: &Lua, weapon: &LuaWeapon, function: Function) -> mlua::Result<()> {
+    weapon.0.borrow_mut().function = Some(lua.create_registry_value(function)?);
+    Ok(())
+}
+
+/// Inställningarna från config.lua
+pub(crate) fn set_config(lua: &Lua, config: WeaponConfig) -> mlua::Result<()> {
+    registry(lua)?.borrow_mut().config = config;
+    Ok(())
+}
+
+pub(crate) fn register(lua: &Lua) -> mlua::Result<()> {
+    lua.set_app_data(WeaponHandle(Rc::new(RefCell::new(WeaponRegistry::default()))));
+
+    // Weapon(WEAPON_SWORD | WEAPON_CLUB | ... | WEAPON_AMMO)
+    register_class(
+        lua,
+        "Weapon",
+        lua.create_function(|_, (_, weapon_type): (Table, u8)| {
+            let weapon_type = match weapon_type {
+                1 => WeaponType::Sword,
+                2 => WeaponType::Club,
+                3 => WeaponType::Axe,
+                5 => WeaponType::Distance,
+                6 => WeaponType::Wand,
+                7 => WeaponType::Ammo,
+                _ => WeaponType::None,
+            };
+            Ok(WeaponKind::of(weapon_type).map(|kind| {
+                LuaWeapon(Rc::new(RefCell::new(ScriptWeapon { weapon: Weapon::new(0, kind), function: None })))
+            }))
+        })?,
+    )?;
+
+    // weapon:onUseWeapon(function), som TFS; finns i klasstabellen så att
+    // helper_constructors.lua hittar den
+    global_table(lua, "Weapon")?.set(
+        "onUseWeapon",
+        lua.create_function(|lua, (this, function): (LuaWeapon, Function)| {
+            set_on_use_weapon(lua, &this, function)?;
+            Ok(true)
+        })?,
+    )
+}
@@ -1,13 +1,186 @@
 //! Timer / scheduler / event queue
+//!
+//! Hashad timer-wheel som spelloopen driver med `advance(now)`. Den är
+//! generisk över händelsetypen så att t.ex. världen kan schemalägga sina
+//! egna uppgifter (respawn, ljus, ...) utan closures som lånar världen.
 
-// public API
-pub struct TimerHandle {
-    // ...
+use std::collections::HashSet;
+
+/// Samma upplösning som SCHEDULER_MINTICKS i TFS
+pub const DEFAULT_TICK_MS: u64 = 50;
+const SLOT_COUNT: usize = 512;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TimerHandle(u64);
+
+struct Timer<E> {
+    id: u64,
+    deadline: u64,
+    event: E,
+}
+
+pub struct TimeWheel<E> {
+    tick_ms: u64,
+    slots: Vec<Vec<Timer<E>>>,
+    /// Nästa tick som inte har körts
+    current_tick: Option<u64>,
+    next_id: u64,
+    /// Id:n som väntar på att köras; avbrutna tas bort härifrån och
+    /// kastas när deras fack besöks
+    live: HashSet<u64>,
+}
+
+impl<E> Default for TimeWheel<E> {
+    fn default() -> Self {
+        Self::new(DEFAULT_TICK_MS)
+    }
 }
 
-pub fn schedule_delay<F>(_delay_ms: u64, _task: F)
-where
-    F: FnOnce() + Send + 'static,
-{
-    unimplemented!()
+impl<E> TimeWheel<E> {
+    pub fn new(tick_ms: u64) -> Self {
+        Self {
+            tick_ms: tick_ms.max(1),
+            slots: (0..SLOT_COUNT).map(|_| Vec::new()).collect(),
+            current_tick: None,
+            next_id: 1,
+            live: HashSet::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.live.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.live.is_empty()
+    }
+
+    /// Schemalägg `event` att köras `delay_ms` efter `now`
+    pub fn schedule(&mut self, now: u64, delay_ms: u64, event: E) -> TimerHandle {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        // avrunda uppåt så att en händelse aldrig körs för tidigt
+        let current = *self.current_tick.get_or_insert(now / self.tick_ms);
+        let deadline = (now + delay_ms).div_ceil(self.tick_ms).max(current);
+        self.slots[(deadline % SLOT_COUNT as u64) as usize].push(Timer { id, deadline, event });
+        self.live.insert(id);
+        TimerHandle(id)
+    }
+
+    /// Avbryt en händelse. Motsvarar `stopEvent` i TFS; false om den redan
+    /// körts eller avbrutits.
+    pub fn cancel(&mut self, handle: TimerHandle) -> bool {
+        self.live.remove(&handle.0)
+    }
+
+    /// Kör fram till `now` och returnera alla händelser som förföll, i tidsordning
+    pub fn advance(&mut self, now: u64) -> Vec<E> {
+        let target = now / self.tick_ms;
+        let start = self.current_tick.unwrap_or(target);
+        if target < start {
+            return Vec::new();
+        }
+        let mut fired = Vec::new();
+
+        // har det gått ett helt varv räcker det att besöka varje fack en gång
+        let last = target.min(start + SLOT_COUNT as u64 - 1);
+        for tick in start..=last {
+            let slot = &mut self.slots[(tick % SLOT_COUNT as u64) as usize];
+            if slot.is_empty() {
+                continue;
+            }
+            let mut keep = Vec::with_capacity(slot.len());
+            let mut due = Vec::new();
+            for timer in slot.drain(..) {
+                if timer.deadline <= target {
+                    due.push(timer);
+                } else {
+                    keep.push(timer);
+                }
+            }
+            *slot = keep;
+            due.sort_by_key(|t| t.deadline);
+            for timer in due {
+                if self.live.remove(&timer.id) {
+                    fired.push((timer.deadline, timer.id, timer.event));
+                }
+            }
+        }
+
+        self.current_tick = Some(target + 1);
+        fired.sort_by_key(|(deadline, id, _)| (*deadline, *id));
+        fired.into_iter().map(|(_, _, event)| event).collect()
+    }
+
+    pub fn clear(&mut self) {
+        for slot in &mut self.slots {
+            slot.clear();
+        }
+        self.live.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn advance_fires_in_deadline_order_and_never_early() {
+        let mut wheel = TimeWheel::new(DEFAULT_TICK_MS);
+        wheel.schedule(0, 200, "b");
+        wheel.schedule(0, 100, "a");
+        wheel.schedule(0, 120, "c");
+
+        assert!(wheel.advance(99).is_empty());
+        assert_eq!(wheel.advance(100), vec!["a"]);
+        // 120 ms avrundas upp till nästa tick, 150
+        assert!(wheel.advance(149).is_empty());
+        assert_eq!(wheel.advance(200), vec!["c", "b"]);
+        assert!(wheel.is_empty());
+    }
+
+    #[test]
+    fn cancelled_events_do_not_fire() {
+        let mut wheel = TimeWheel::new(DEFAULT_TICK_MS);
+        let a = wheel.schedule(0, 100, "a");
+        wheel.schedule(0, 100, "b");
+
+        assert!(wheel.cancel(a));
+        assert!(!wheel.cancel(a));
+        assert_eq!(wheel.len(), 1);
+        assert_eq!(wheel.advance(100), vec!["b"]);
+    }
+
+    #[test]
+    fn cancel_after_firing_returns_false() {
+        let mut wheel = TimeWheel::new(DEFAULT_TICK_MS);
+        let a = wheel.schedule(0, 50, "a");
+        assert_eq!(wheel.advance(50), vec!["a"]);
+        assert!(!wheel.cancel(a));
+    }
+
+    #[test]
+    fn events_beyond_one_revolution_wait_for_their_deadline() {
+        let mut wheel = TimeWheel::new(DEFAULT_TICK_MS);
+        let revolution = SLOT_COUNT as u64 * DEFAULT_TICK_MS;
+        wheel.schedule(0, 100, "soon");
+        wheel.schedule(0, revolution + 100, "later");
+
+        assert_eq!(wheel.advance(100), vec!["soon"]);
+        assert!(wheel.advance(revolution).is_empty());
+        assert_eq!(wheel.advance(revolution + 100), vec!["later"]);
+    }
+
+    #[test]
+    fn long_jump_fires_everything_due() {
+        let mut wheel = TimeWheel::new(DEFAULT_TICK_MS);
+        let revolution = SLOT_COUNT as u64 * DEFAULT_TICK_MS;
+        wheel.schedule(0, 100, "a");
+        wheel.schedule(0, 3 * revolution, "b");
+        wheel.schedule(0, 5 * revolution, "c");
+
+        assert_eq!(wheel.advance(4 * revolution), vec!["a", "b"]);
+        assert_eq!(wheel.len(), 1);
+    }
 }
@@ -0,0 +1,58 @@
+//! Pathfinding på stora öppna ytor och runt en lång vägg.
+//! Kör med `cargo bench -p world`.
+
+use common::Position;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use items::item::ItemGroup;
+use items::{Item, ItemType, Items};
+use world::tile::{Thing, Tile};
+use world::{FindPathParams, Map, PathFinder};
+
+const GROUND: u16 = 100;
+const WALL: u16 = 101;
+
+fn open_area(size: u16, wall: bool) -> Map {
+    let mut items = Items::new();
+    items.insert(ItemType { id: GROUND, group: ItemGroup::Ground, speed: 150, ..Default::default() });
+    items.insert(ItemType { id: WALL, block_solid: true, ..Default::default() });
+    items.install();
+
+    let mut map = Map::new();
+    for x in 100..100 + size {
+        for y in 100..100 + size {
+            let mut tile = Tile::new(Position::new(x, y, 7));
+            tile.add_thing(Thing::Item(Item::new(GROUND, 0)));
+            // vägg mitt på ytan med en öppning längst ner
+            if wall && x == 100 + size / 2 && y < 100 + size - 2 {
+                tile.add_thing(Thing::Item(Item::new(WALL, 0)));
+            }
+            map.set_tile(tile);
+        }
+    }
+    map
+}
+
+fn bench(c: &mut Criterion) {
+    let open = open_area(256, false);
+    let mut finder = PathFinder::new();
+    let start = Position::new(110, 110, 7);
+
+    c.bench_function("open area, 8 tiles", |b| {
+        let params = FindPathParams { max_search_dist: 16, ..FindPathParams::adjacent() };
+        b.iter(|| finder.find_path(&open, start, black_box(Position::new(118, 115, 7)), &(), &params))
+    });
+
+    c.bench_function("open area, 100 tiles", |b| {
+        let params = FindPathParams { max_search_dist: 128, max_nodes: 100_000, ..FindPathParams::exact() };
+        b.iter(|| finder.find_path(&open, start, black_box(Position::new(210, 180, 7)), &(), &params))
+    });
+
+    let walled = open_area(64, true);
+    c.bench_function("around a wall, 64x64", |b| {
+        let params = FindPathParams { max_search_dist: 64, max_nodes: 100_000, ..FindPathParams::exact() };
+        b.iter(|| finder.find_path(&walled, start, black_box(Position::new(150, 110, 7)), &(), &params))
+    });
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
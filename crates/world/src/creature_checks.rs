@@ -0,0 +1,70 @@
+//! Vilka varelser som ska tänka, gå och attackera och när. Motsvarar
+//! `checkCreatureLists`, `eventWalk` och attack-eventen i TFS. Världen
+//! äger inte varelserna, så förfallna kontroller köas och hämtas av
+//! spelet med `World::drain_creature_checks`.
+
+use std::collections::HashMap;
+
+use entities::creature::EVENT_CREATURECOUNT;
+use timewheel::TimerHandle;
+
+/// En varelse som ska köras nu
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CreatureCheck {
+    /// Think, attack och conditions, en gång per `EVENT_CREATURE_THINK_INTERVAL`.
+    /// Motsvarar ett varv i `Game::checkCreatures`.
+    Think(u32),
+    /// Dags för nästa steg. Motsvarar `Game::checkCreatureWalk`.
+    Walk(u32),
+    /// Dags för nästa slag. Motsvarar `Game::checkCreatureAttack`.
+    Attack(u32),
+}
+
+#[derive(Debug)]
+pub(crate) struct CreatureChecks {
+    lists: Vec<Vec<u32>>,
+    index: usize,
+    pub started: bool,
+    pub walk_events: HashMap<u32, TimerHandle>,
+    pub attack_events: HashMap<u32, TimerHandle>,
+    pub due: Vec<CreatureCheck>,
+}
+
+impl Default for CreatureChecks {
+    fn default() -> Self {
+        Self {
+            lists: vec![Vec::new(); EVENT_CREATURECOUNT],
+            index: 0,
+            started: false,
+            walk_events: HashMap::new(),
+            attack_events: HashMap::new(),
+            due: Vec::new(),
+        }
+    }
+}
+
+impl CreatureChecks {
+    /// Varelserna sprids över listorna efter id så att inte alla tänker samtidigt
+    pub fn add(&mut self, id: u32) {
+        let list = &mut self.lists[id as usize % EVENT_CREATURECOUNT];
+        if !list.contains(&id) {
+            list.push(id);
+        }
+    }
+
+    /// Returnerar walk- och attack-eventen som ska avbrytas
+    pub fn remove(&mut self, id: u32) -> Vec<TimerHandle> {
+        self.lists[id as usize % EVENT_CREATURECOUNT].retain(|&c| c != id);
+        self.due.retain(|check| {
+            !matches!(check, CreatureCheck::Think(c) | CreatureCheck::Walk(c) | CreatureCheck::Attack(c) if *c == id)
+        });
+        self.walk_events.remove(&id).into_iter().chain(self.attack_events.remove(&id)).collect()
+    }
+
+    /// Nästa lista i tur att tänka
+    pub fn next_list(&mut self) {
+        let list = &self.lists[self.index];
+        self.due.extend(list.iter().map(|&id| CreatureCheck::Think(id)));
+        self.index = (self.index + 1) % EVENT_CREATURECOUNT;
+    }
+}
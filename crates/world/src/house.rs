@@ -0,0 +1,847 @@
+//! Hus, access-listor och hyra. Motsvarar `House`/`Houses`/`AccessList` i TFS.
+//!
+//! Här finns bara tillståndet; databasdelen (ägare, hyra, tile_store)
+//! ligger i persistence så att världen inte beror på databasen.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use common::{Config, Error, MagicEffect, Position, Result};
+use common::tracing::warn;
+use items::Item;
+
+use crate::tile::{Thing, ThingRef};
+use crate::World;
+
+/// List-id för gäst- och subowner-listan, dörrar använder sitt dörr-id
+pub const GUEST_LIST: u32 = 0x100;
+pub const SUBOWNER_LIST: u32 = 0x101;
+
+/// Max antal rader och max längd per rad i en access-lista
+const MAX_LIST_LINES: usize = 100;
+const MAX_LINE_LENGTH: usize = 100;
+const MAX_PLAYER_NAME_LENGTH: usize = 30;
+
+/// Stämplat brev med hyresvarningen (ITEM_LETTER_STAMPED)
+pub const ITEM_LETTER_STAMPED: u16 = 2598;
+/// Antal varningar innan huset tas ifrån ägaren
+pub const MAX_RENT_WARNINGS: u32 = 7;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AccessHouseLevel {
+    NotInvited = 0,
+    Guest = 1,
+    SubOwner = 2,
+    Owner = 3,
+}
+
+/// Motsvarar `RentPeriod_t`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RentPeriod {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+    Never,
+}
+
+impl RentPeriod {
+    pub fn parse(value: &str) -> Self {
+        match value.to_lowercase().as_str() {
+            "daily" => RentPeriod::Daily,
+            "weekly" => RentPeriod::Weekly,
+            "monthly" => RentPeriod::Monthly,
+            "yearly" => RentPeriod::Yearly,
+            _ => RentPeriod::Never,
+        }
+    }
+
+    /// Periodens längd i sekunder (None för "never")
+    pub fn seconds(self) -> Option<u64> {
+        const DAY: u64 = 24 * 60 * 60;
+        match self {
+            RentPeriod::Daily => Some(DAY),
+            RentPeriod::Weekly => Some(DAY * 7),
+            RentPeriod::Monthly => Some(DAY * 30),
+            RentPeriod::Yearly => Some(DAY * 365),
+            RentPeriod::Never => None,
+        }
+    }
+
+    /// Ordet som används i varningsbrevet
+    pub fn describe(self) -> &'static str {
+        match self {
+            RentPeriod::Daily => "daily",
+            RentPeriod::Weekly => "weekly",
+            RentPeriod::Monthly => "monthly",
+            RentPeriod::Yearly => "annual",
+            RentPeriod::Never => "",
+        }
+    }
+}
+
+/// Konfigvärden för hus
+#[derive(Debug, Clone, Copy)]
+pub struct HouseConfig {
+    /// Pris per sqm, -1 stänger av köp
+    pub price_each_sqm: i32,
+    pub rent_period: RentPeriod,
+    pub owned_by_account: bool,
+    pub door_show_price: bool,
+    pub only_invited_can_move_items: bool,
+}
+
+impl Default for HouseConfig {
+    fn default() -> Self {
+        Self {
+            price_each_sqm: 1000,
+            rent_period: RentPeriod::Never,
+            owned_by_account: false,
+            door_show_price: true,
+            only_invited_can_move_items: true,
+        }
+    }
+}
+
+impl HouseConfig {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            price_each_sqm: config.house_price_each_sqm,
+            rent_period: RentPeriod::parse(&config.house_rent_period),
+            owned_by_account: config.house_owned_by_account,
+            door_show_price: config.house_door_show_price,
+            only_invited_can_move_items: config.only_invited_can_move_house_items,
+        }
+    }
+}
+
+/// Den som vill in i / ändra ett hus. Byggs av anroparen från spelaren.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HouseVisitor<'a> {
+    pub guid: u32,
+    pub account_id: u32,
+    pub name: &'a str,
+    /// (guildnamn, ranknamn)
+    pub guild: Option<(&'a str, &'a str)>,
+    /// PlayerFlag_CanEditHouses
+    pub can_edit_houses: bool,
+}
+
+/// Ny ägare till ett hus
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HouseOwner {
+    pub guid: u32,
+    pub account_id: u32,
+    pub name: String,
+}
+
+/// Access-lista för gäster, subowners och dörrar.
+///
+/// En rad per post: spelarnamn (med `*` och `?` som wildcards), `@guild`,
+/// `rank@guild` eller `*` för alla. Rader som börjar med `#` är kommentarer.
+#[derive(Debug, Clone, Default)]
+pub struct AccessList {
+    list: String,
+    players: Vec<String>,
+    patterns: Vec<String>,
+    guilds: Vec<String>,
+    guild_ranks: Vec<(String, String)>,
+    allow_everyone: bool,
+}
+
+impl AccessList {
+    pub fn new(list: &str) -> Self {
+        let mut access = Self::default();
+        access.parse_list(list);
+        access
+    }
+
+    /// Motsvarar `AccessList::parseList`
+    pub fn parse_list(&mut self, list: &str) {
+        *self = Self {
+            list: list.to_string(),
+            ..Default::default()
+        };
+
+        for line in list.lines().take(MAX_LIST_LINES) {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.len() > MAX_LINE_LENGTH {
+                continue;
+            }
+            let line = line.to_lowercase();
+
+            if let Some((rank, guild)) = line.split_once('@') {
+                let (rank, guild) = (rank.trim(), guild.trim());
+                if guild.is_empty() {
+                    continue;
+                }
+                if rank.is_empty() {
+                    self.guilds.push(guild.to_string());
+                } else {
+                    self.guild_ranks.push((guild.to_string(), rank.to_string()));
+                }
+            } else if line == "*" {
+                self.allow_everyone = true;
+            } else if line.contains(['*', '?']) {
+                self.patterns.push(line);
+            } else if line.len() <= MAX_PLAYER_NAME_LENGTH {
+                self.players.push(line);
+            }
+        }
+    }
+
+    pub fn get_list(&self) -> &str {
+        &self.list
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    /// Motsvarar `AccessList::isInList`
+    pub fn is_in_list(&self, visitor: &HouseVisitor) -> bool {
+        if self.allow_everyone {
+            return true;
+        }
+
+        let name = visitor.name.to_lowercase();
+        if self.players.contains(&name) || self.patterns.iter().any(|p| wildcard_match(p, &name)) {
+            return true;
+        }
+
+        let Some((guild, rank)) = visitor.guild else {
+            return false;
+        };
+        let (guild, rank) = (guild.to_lowercase(), rank.to_lowercase());
+        self.guilds.contains(&guild) || self.guild_ranks.iter().any(|(g, r)| *g == guild && *r == rank)
+    }
+}
+
+/// `*` matchar valfri följd av tecken, `?` exakt ett
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let (p, t): (Vec<char>, Vec<char>) = (pattern.chars().collect(), text.chars().collect());
+    let (mut pi, mut ti) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+
+    while ti < t.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == t[ti]) {
+            pi += 1;
+            ti += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, ti));
+            pi += 1;
+        } else if let Some((sp, st)) = star {
+            pi = sp + 1;
+            ti = st + 1;
+            star = Some((sp, st + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|&c| c == '*')
+}
+
+#[derive(Debug, Clone)]
+pub struct HouseDoor {
+    pub door_id: u8,
+    pub position: Position,
+    pub access: AccessList,
+}
+
+#[derive(Debug, Clone)]
+pub struct House {
+    pub id: u32,
+    pub name: String,
+    pub entry: Position,
+    pub rent: u32,
+    pub town_id: u32,
+
+    owner: u32,
+    owner_account_id: u32,
+    owner_name: String,
+    /// Unix-tid (sekunder) som hyran är betald till
+    pub paid_until: u64,
+    pub rent_warnings: u32,
+
+    tiles: Vec<Position>,
+    doors: Vec<HouseDoor>,
+    guest_list: AccessList,
+    subowner_list: AccessList,
+    is_loaded: bool,
+}
+
+impl House {
+    pub fn new(id: u32) -> Self {
+        Self {
+            id,
+            name: String::new(),
+            entry: Position::default(),
+            rent: 0,
+            town_id: 0,
+            owner: 0,
+            owner_account_id: 0,
+            owner_name: String::new(),
+            paid_until: 0,
+            rent_warnings: 0,
+            tiles: Vec::new(),
+            doors: Vec::new(),
+            guest_list: AccessList::default(),
+            subowner_list: AccessList::default(),
+            is_loaded: false,
+        }
+    }
+
+    pub fn owner(&self) -> u32 {
+        self.owner
+    }
+
+    pub fn owner_account_id(&self) -> u32 {
+        self.owner_account_id
+    }
+
+    pub fn owner_name(&self) -> &str {
+        &self.owner_name
+    }
+
+    pub fn tiles(&self) -> &[Position] {
+        &self.tiles
+    }
+
+    pub fn doors(&self) -> &[HouseDoor] {
+        &self.doors
+    }
+
+    pub fn get_door(&self, door_id: u8) -> Option<&HouseDoor> {
+        self.doors.iter().find(|d| d.door_id == door_id)
+    }
+
+    pub fn get_door_at(&self, pos: Position) -> Option<&HouseDoor> {
+        self.doors.iter().find(|d| d.position == pos)
+    }
+
+    pub(crate) fn add_tile(&mut self, pos: Position) {
+        if !self.tiles.contains(&pos) {
+            self.tiles.push(pos);
+        }
+    }
+
+    pub(crate) fn add_door(&mut self, door_id: u8, position: Position) {
+        if self.get_door(door_id).is_none() {
+            self.doors.push(HouseDoor {
+                door_id,
+                position,
+                access: AccessList::default(),
+            });
+        }
+    }
+
+    /// Priset för att köpa huset (antal tiles * housePriceEachSQM), None om köp är avstängt
+    pub fn price(&self, config: &HouseConfig) -> Option<u64> {
+        (config.price_each_sqm >= 0).then(|| self.tiles.len() as u64 * config.price_each_sqm as u64)
+    }
+
+    /// Motsvarar `House::getHouseAccessLevel`
+    pub fn get_house_access_level(&self, visitor: &HouseVisitor, config: &HouseConfig) -> AccessHouseLevel {
+        if config.owned_by_account && self.owner_account_id != 0 && self.owner_account_id == visitor.account_id {
+            return AccessHouseLevel::Owner;
+        }
+        if visitor.can_edit_houses || (self.owner != 0 && visitor.guid == self.owner) {
+            return AccessHouseLevel::Owner;
+        }
+        if self.subowner_list.is_in_list(visitor) {
+            return AccessHouseLevel::SubOwner;
+        }
+        if self.guest_list.is_in_list(visitor) {
+            return AccessHouseLevel::Guest;
+        }
+        AccessHouseLevel::NotInvited
+    }
+
+    pub fn is_invited(&self, visitor: &HouseVisitor, config: &HouseConfig) -> bool {
+        self.get_house_access_level(visitor, config) != AccessHouseLevel::NotInvited
+    }
+
+    /// Får besökaren flytta items i huset? (onlyInvitedCanMoveHouseItems)
+    pub fn can_move_items(&self, visitor: &HouseVisitor, config: &HouseConfig) -> bool {
+        !config.only_invited_can_move_items || self.is_invited(visitor, config)
+    }
+
+    /// Får `kicker` kasta ut `target`? Motsvarar kontrollerna i `House::kickPlayer`.
+    pub fn can_kick(&self, kicker: &HouseVisitor, target: &HouseVisitor, config: &HouseConfig) -> bool {
+        !target.can_edit_houses
+            && self.get_house_access_level(kicker, config) >= self.get_house_access_level(target, config)
+    }
+
+    /// Motsvarar `House::canEditAccessList`
+    pub fn can_edit_access_list(&self, list_id: u32, visitor: &HouseVisitor, config: &HouseConfig) -> bool {
+        match self.get_house_access_level(visitor, config) {
+            AccessHouseLevel::Owner => true,
+            AccessHouseLevel::SubOwner => list_id == GUEST_LIST,
+            _ => false,
+        }
+    }
+
+    /// Motsvarar `Door::canUse`
+    pub fn can_use_door(&self, door_id: u8, visitor: &HouseVisitor, config: &HouseConfig) -> bool {
+        if self.get_house_access_level(visitor, config) >= AccessHouseLevel::SubOwner {
+            return true;
+        }
+        self.get_door(door_id).is_some_and(|d| d.access.is_in_list(visitor))
+    }
+
+    pub fn get_access_list(&self, list_id: u32) -> Option<&str> {
+        match list_id {
+            GUEST_LIST => Some(self.guest_list.get_list()),
+            SUBOWNER_LIST => Some(self.subowner_list.get_list()),
+            _ => u8::try_from(list_id)
+                .ok()
+                .and_then(|id| self.get_door(id))
+                .map(|d| d.access.get_list()),
+        }
+    }
+
+    /// Sätt en lista. Returnerar true om gäst-/subowner-listan ändrades och
+    /// ej inbjudna spelare ska kastas ut.
+    pub fn set_access_list(&mut self, list_id: u32, text: &str) -> bool {
+        match list_id {
+            GUEST_LIST => self.guest_list.parse_list(text),
+            SUBOWNER_LIST => self.subowner_list.parse_list(text),
+            _ => {
+                let door = u8::try_from(list_id)
+                    .ok()
+                    .and_then(|id| self.doors.iter_mut().find(|d| d.door_id == id));
+                if let Some(door) = door {
+                    door.access.parse_list(text);
+                }
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Text som sätts på husets dörrar. Motsvarar `House::updateDoorDescription`.
+    pub fn door_description(&self, config: &HouseConfig) -> String {
+        if self.owner != 0 {
+            return format!("It belongs to house '{}'. {} owns this house.", self.name, self.owner_name);
+        }
+        let mut text = format!("It belongs to house '{}'. Nobody owns this house.", self.name);
+        if config.door_show_price {
+            if let Some(price) = self.price(config) {
+                text.push_str(&format!(" It costs {price} gold coins."));
+            }
+        }
+        text
+    }
+
+    /// Texten i hyresvarningen som skickas till ägarens inbox
+    pub fn rent_warning_text(&self, period: RentPeriod) -> String {
+        let days_left = MAX_RENT_WARNINGS.saturating_sub(self.rent_warnings);
+        format!(
+            "Warning! \nThe {} rent of {} gold for your house \"{}\" is payable. Have it within {} days or you will lose this house.",
+            period.describe(),
+            self.rent,
+            self.name,
+            days_left
+        )
+    }
+
+    /// Tillståndsdelen av `House::setOwner`. Returnerar true om huset hade
+    /// en ägare som nu ska vräkas (items till depot, spelare ut).
+    pub(crate) fn set_owner(&mut self, owner: Option<HouseOwner>, config: &HouseConfig, now: u64) -> bool {
+        let guid = owner.as_ref().map_or(0, |o| o.guid);
+        if self.is_loaded && self.owner == guid {
+            return false;
+        }
+        self.is_loaded = true;
+
+        let evict = self.owner != 0;
+        if evict {
+            self.owner = 0;
+            self.owner_account_id = 0;
+            self.owner_name.clear();
+            self.guest_list.parse_list("");
+            self.subowner_list.parse_list("");
+            for door in &mut self.doors {
+                door.access.parse_list("");
+            }
+            self.paid_until = 0;
+        } else {
+            self.paid_until = config.rent_period.seconds().map_or(0, |s| now + s);
+        }
+        self.rent_warnings = 0;
+
+        if let Some(owner) = owner {
+            self.owner = owner.guid;
+            self.owner_account_id = owner.account_id;
+            self.owner_name = owner.name;
+        }
+        evict
+    }
+}
+
+#[derive(Default)]
+pub struct Houses {
+    pub config: HouseConfig,
+    houses: BTreeMap<u32, House>,
+}
+
+impl Houses {
+    pub fn new(config: HouseConfig) -> Self {
+        Self {
+            config,
+            houses: BTreeMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.houses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.houses.is_empty()
+    }
+
+    pub fn add_house(&mut self, id: u32) -> &mut House {
+        self.houses.entry(id).or_insert_with(|| House::new(id))
+    }
+
+    pub fn get_house(&self, id: u32) -> Option<&House> {
+        self.houses.get(&id)
+    }
+
+    pub fn get_house_mut(&mut self, id: u32) -> Option<&mut House> {
+        self.houses.get_mut(&id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &House> {
+        self.houses.values()
+    }
+
+    pub fn get_house_by_owner(&self, guid: u32) -> Option<&House> {
+        (guid != 0).then(|| self.houses.values().find(|h| h.owner == guid)).flatten()
+    }
+
+    /// Hus vars hyra har förfallit. Motsvarar urvalet i `Houses::payHouses`.
+    pub fn houses_due_rent(&self, now: u64) -> Vec<u32> {
+        if self.config.rent_period == RentPeriod::Never {
+            return Vec::new();
+        }
+        self.houses
+            .values()
+            .filter(|h| h.owner != 0 && h.rent != 0 && h.paid_until <= now)
+            .map(|h| h.id)
+            .collect()
+    }
+
+    /// Läs husdata från XML. Motsvarar `Houses::loadHousesXML`; hus som
+    /// kartan inte redan skapat läggs till.
+    pub fn load_from_xml(&mut self, path: impl AsRef<Path>) -> Result<usize> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| Error::World(format!("Cannot open {}: {e}", path.display())))?;
+        let doc = roxmltree::Document::parse(&text)
+            .map_err(|e| Error::World(format!("Cannot parse {}: {e}", path.display())))?;
+
+        let config = self.config;
+        let mut count = 0;
+        for node in doc.root_element().children().filter(|n| n.has_tag_name("house")) {
+            let Some(id) = attr::<u32>(&node, "houseid") else {
+                warn!("[Houses::load_from_xml] House without houseid in {}", path.display());
+                continue;
+            };
+
+            let house = self.add_house(id);
+            house.name = node.attribute("name").unwrap_or_default().to_string();
+            house.entry = Position::new(
+                attr(&node, "entryx").unwrap_or(0),
+                attr(&node, "entryy").unwrap_or(0),
+                attr(&node, "entryz").unwrap_or(0),
+            );
+            if house.entry == Position::default() {
+                warn!("[Houses::load_from_xml] House entry not set - Name: {} - House id: {id}", house.name);
+            }
+            house.rent = attr(&node, "rent").unwrap_or(0);
+            house.town_id = attr(&node, "townid").unwrap_or(0);
+            house.set_owner(None, &config, common::unix_time());
+            count += 1;
+        }
+        Ok(count)
+    }
+}
+
+fn attr<T: std::str::FromStr>(node: &roxmltree::Node, name: &str) -> Option<T> {
+    node.attribute(name).and_then(|v| v.parse().ok())
+}
+
+/// Det som ska till förra ägarens depå när ett hus byter ägare
+#[derive(Debug)]
+pub struct HouseEviction {
+    pub house_id: u32,
+    pub previous_owner: u32,
+    pub town_id: u32,
+    pub items: Vec<Item>,
+}
+
+impl World {
+    pub fn get_house_at(&self, pos: &Position) -> Option<&House> {
+        let house_id = self.map.get_tile(pos)?.house_id();
+        self.houses.get_house(house_id)
+    }
+
+    /// Koppla en tile till ett hus (kartladdaren). Dörrar med dörr-id
+    /// registreras som husdörrar precis som i `HouseTile::internalAddThing`.
+    pub fn add_house_tile(&mut self, house_id: u32, pos: Position) {
+        self.map.get_or_create_tile(pos);
+        let doors: Vec<u8> = self
+            .map
+            .update_tile(&pos, |tile| {
+                tile.set_house_id(house_id);
+                tile.items()
+                    .filter(|i| i.item_type().is_door() && i.attributes().door_id != 0)
+                    .map(|i| i.attributes().door_id)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let house = self.houses.add_house(house_id);
+        house.add_tile(pos);
+        for door_id in doors {
+            house.add_door(door_id, pos);
+        }
+    }
+
+    /// Byt ägare. Motsvarar världsdelen av `House::setOwner`: hade huset en
+    /// ägare kastas alla ut, sängarna töms och lösa items plockas ihop för
+    /// att skickas till ägarens depå.
+    pub fn set_house_owner(&mut self, house_id: u32, owner: Option<HouseOwner>) -> Option<HouseEviction> {
+        let config = self.houses.config;
+        let house = self.houses.get_house_mut(house_id)?;
+        let previous_owner = house.owner();
+        let town_id = house.town_id;
+        let evict = house.set_owner(owner, &config, common::unix_time());
+
+        let eviction = if evict {
+            let items = self.take_house_items(house_id);
+            for id in self.house_players(house_id) {
+                self.kick_from_house(id);
+            }
+            self.wake_house_beds(house_id);
+            Some(HouseEviction {
+                house_id,
+                previous_owner,
+                town_id,
+                items,
+            })
+        } else {
+            None
+        };
+
+        self.update_house_doors(house_id);
+        eviction
+    }
+
+    /// Sätt en access-lista och kasta ut spelare som inte längre är
+    /// inbjudna. `is_invited` avgör det per spelar-id.
+    pub fn set_house_access_list<F: Fn(u32) -> bool>(
+        &mut self,
+        house_id: u32,
+        list_id: u32,
+        text: &str,
+        is_invited: F,
+    ) -> bool {
+        let Some(house) = self.houses.get_house_mut(house_id) else {
+            return false;
+        };
+        if house.set_access_list(list_id, text) {
+            for id in self.house_players(house_id) {
+                if !is_invited(id) {
+                    self.kick_from_house(id);
+                }
+            }
+        }
+        true
+    }
+
+    /// Teleportera en spelare till husets entré. Motsvarar `House::kickPlayer`
+    /// (behörigheten kontrolleras med `House::can_kick`).
+    pub fn kick_from_house(&mut self, creature_id: u32) -> bool {
+        let Some(pos) = self.spectators.position_of(creature_id) else {
+            return false;
+        };
+        let Some(entry) = self.get_house_at(&pos).map(|h| h.entry) else {
+            return false;
+        };
+        if self.move_creature(creature_id, entry, true).is_ok() {
+            self.add_magic_effect(pos, MagicEffect::Poff);
+            self.add_magic_effect(entry, MagicEffect::Teleport);
+        }
+        true
+    }
+
+    /// Sätt dörrarnas beskrivning efter ägare/pris
+    pub fn update_house_doors(&mut self, house_id: u32) {
+        let Some(house) = self.houses.get_house(house_id) else {
+            return;
+        };
+        let text = house.door_description(&self.houses.config);
+        let doors: Vec<(u8, Position)> = house.doors().iter().map(|d| (d.door_id, d.position)).collect();
+
+        for (door_id, pos) in doors {
+            let Some(tile) = self.map.get_tile_mut(&pos) else {
+                continue;
+            };
+            for item in tile.items_mut() {
+                if item.item_type().is_door() && item.attributes().door_id == door_id {
+                    item.attributes_mut().description = Some(text.clone());
+                }
+            }
+        }
+    }
+
+    /// Antal sängar i huset (varje säng består av två items)
+    pub fn house_bed_count(&self, house_id: u32) -> u32 {
+        let Some(house) = self.houses.get_house(house_id) else {
+            return 0;
+        };
+        let bed_items = house
+            .tiles()
+            .iter()
+            .filter_map(|pos| self.map.get_tile(pos))
+            .flat_map(|tile| tile.items())
+            .filter(|item| item.item_type().is_bed())
+            .count() as u32;
+        bed_items.div_ceil(2)
+    }
+
+    /// Spelare som står i huset
+    fn house_players(&self, house_id: u32) -> Vec<u32> {
+        let Some(house) = self.houses.get_house(house_id) else {
+            return Vec::new();
+        };
+        house
+            .tiles()
+            .iter()
+            .filter_map(|pos| self.map.get_tile(pos))
+            .flat_map(|tile| tile.creatures().iter().copied())
+            .filter(|&id| self.spectators.is_player(id))
+            .collect()
+    }
+
+    /// Plocka upp allt som ska till depån. Motsvarar urvalet i
+    /// `House::transferToDepot`: upplockningsbara items, samt innehållet i
+    /// fasta containers.
+    fn take_house_items(&mut self, house_id: u32) -> Vec<Item> {
+        let Some(house) = self.houses.get_house(house_id) else {
+            return Vec::new();
+        };
+        let mut taken = Vec::new();
+        for pos in house.tiles().to_vec() {
+            while let Some(stackpos) = self.find_stackpos(&pos, |i| i.item_type().pickupable) {
+                match self.remove_item(pos, stackpos, 0) {
+                    Some(item) => taken.push(item),
+                    None => break,
+                }
+            }
+
+            let Some(tile) = self.map.get_tile_mut(&pos) else {
+                continue;
+            };
+            for item in tile.items_mut() {
+                if let Some(container) = item.get_container_mut() {
+                    taken.append(container.items_mut());
+                }
+            }
+        }
+        taken
+    }
+
+    fn wake_house_beds(&mut self, house_id: u32) {
+        let Some(house) = self.houses.get_house(house_id) else {
+            return;
+        };
+        for pos in house.tiles().to_vec() {
+            let Some(tile) = self.map.get_tile_mut(&pos) else {
+                continue;
+            };
+            for item in tile.items_mut() {
+                if item.item_type().is_bed() && item.attributes().sleeper_guid != 0 {
+                    let attributes = item.attributes_mut();
+                    attributes.sleeper_guid = 0;
+                    attributes.sleep_start = 0;
+                }
+            }
+        }
+    }
+
+    fn find_stackpos<F: Fn(&Item) -> bool>(&self, pos: &Position, pred: F) -> Option<u8> {
+        let tile = self.map.get_tile(pos)?;
+        (0..tile.thing_count())
+            .find(|&i| matches!(tile.get_thing(i, |_| true), Some(ThingRef::Item(item)) if pred(item)))
+            .map(|i| i as u8)
+    }
+
+    /// Lägg tillbaka ett item från tile_store på en hustile
+    pub fn load_house_item(&mut self, pos: Position, item: Item) {
+        self.map.get_or_create_tile(pos);
+        self.map.update_tile(&pos, |tile| tile.add_thing(Thing::Item(item)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn visitor<'a>(name: &'a str, guild: Option<(&'a str, &'a str)>) -> HouseVisitor<'a> {
+        HouseVisitor { name, guild, ..Default::default() }
+    }
+
+    #[test]
+    fn wildcards_match_names() {
+        assert!(wildcard_match("gm *", "gm bob"));
+        assert!(wildcard_match("*bob", "bob"));
+        assert!(wildcard_match("b?b", "bob"));
+        assert!(wildcard_match("a*b*c", "axxbyyc"));
+        assert!(!wildcard_match("b?b", "bobb"));
+        assert!(!wildcard_match("a*c", "abcd"));
+    }
+
+    #[test]
+    fn list_accepts_names_patterns_and_guilds() {
+        let list = AccessList::new("# gäster\nBob\ngm *\n@Red Rose\nleader@Blue Moon\n");
+        assert!(list.is_in_list(&visitor("bob", None)));
+        assert!(list.is_in_list(&visitor("GM Alice", None)));
+        assert!(list.is_in_list(&visitor("eve", Some(("red rose", "member")))));
+        assert!(list.is_in_list(&visitor("eve", Some(("Blue Moon", "Leader")))));
+        assert!(!list.is_in_list(&visitor("eve", Some(("Blue Moon", "member")))));
+        assert!(!list.is_in_list(&visitor("alice", None)));
+        assert!(!list.is_in_list(&visitor("# gäster", None)));
+    }
+
+    #[test]
+    fn star_alone_allows_everyone() {
+        assert!(AccessList::new("*").is_in_list(&visitor("anyone", None)));
+        assert!(!AccessList::new("").is_in_list(&visitor("anyone", None)));
+        assert!(!AccessList::new("@").is_in_list(&visitor("anyone", Some(("", "")))));
+    }
+
+    #[test]
+    fn eviction_clears_owner_lists_and_rent() {
+        let config = HouseConfig {
+            price_each_sqm: 1000,
+            rent_period: RentPeriod::Daily,
+            owned_by_account: false,
+            door_show_price: true,
+            only_invited_can_move_items: true,
+        };
+        let mut house = House::new(1);
+        let owner = HouseOwner { guid: 5, account_id: 2, name: "Bob".to_string() };
+        assert!(!house.set_owner(Some(owner), &config, 1000));
+        assert_eq!(house.paid_until, 1000 + 24 * 60 * 60);
+        house.guest_list.parse_list("alice");
+
+        assert!(house.set_owner(None, &config, 2000));
+        assert_eq!(house.owner(), 0);
+        assert_eq!(house.paid_until, 0);
+        assert!(house.guest_list.is_empty());
+    }
+}
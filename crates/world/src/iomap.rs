@@ -0,0 +1,303 @@
+//! OTBM-läsaren, motsvarar `IOMap` i TFS iomap.cpp.
+
+use std::path::{Path, PathBuf};
+
+use common::tracing::{info, warn};
+use common::{Error, OtbLoader, OtbNode, Position, PropStream, Result};
+use items::{Item, Items};
+
+use crate::tile::{Thing, Tile, TileFlags};
+use crate::town::Town;
+use crate::World;
+
+/// Nodtyper, `OTBM_NodeTypes_t`
+const OTBM_MAP_DATA: u8 = 2;
+const OTBM_TILE_AREA: u8 = 4;
+const OTBM_TILE: u8 = 5;
+const OTBM_ITEM: u8 = 6;
+const OTBM_TOWNS: u8 = 12;
+const OTBM_TOWN: u8 = 13;
+const OTBM_HOUSETILE: u8 = 14;
+const OTBM_WAYPOINTS: u8 = 15;
+const OTBM_WAYPOINT: u8 = 16;
+
+/// Attribut i map data- och tile-noderna, `OTBM_AttrTypes_t`
+const OTBM_ATTR_DESCRIPTION: u8 = 1;
+const OTBM_ATTR_TILE_FLAGS: u8 = 3;
+const OTBM_ATTR_ITEM: u8 = 9;
+const OTBM_ATTR_EXT_SPAWN_FILE: u8 = 11;
+const OTBM_ATTR_EXT_HOUSE_FILE: u8 = 13;
+
+const OTBM_TILEFLAG_PROTECTIONZONE: u32 = 1 << 0;
+const OTBM_TILEFLAG_NOPVPZONE: u32 = 1 << 2;
+const OTBM_TILEFLAG_NOLOGOUT: u32 = 1 << 3;
+const OTBM_TILEFLAG_PVPZONE: u32 = 1 << 4;
+
+/// Lägsta items.otb minor-version en karta får vara sparad med (CLIENT_VERSION_810)
+const CLIENT_VERSION_810: u32 = 11;
+
+/// Det kartfilen pekar ut utöver själva tilesen
+#[derive(Debug, Clone, Default)]
+pub struct MapInfo {
+    pub description: Vec<String>,
+    /// Fulla sökvägar, relativa kartans katalog
+    pub spawn_file: Option<PathBuf>,
+    pub house_file: Option<PathBuf>,
+}
+
+pub struct IOMap;
+
+impl IOMap {
+    /// Kartan plus husen och spawnsen den pekar ut. Motsvarar `Map::loadMap`.
+    pub fn load_world(world: &mut World, path: impl AsRef<Path>) -> Result<MapInfo> {
+        let info = Self::load_map(world, path)?;
+        if let Some(house_file) = &info.house_file {
+            if let Err(e) = world.houses.load_from_xml(house_file) {
+                warn!("[IOMap::load_world] Failed to load house data: {e}");
+            }
+        }
+        if let Some(spawn_file) = &info.spawn_file {
+            if let Err(e) = world.spawns.load_from_xml(spawn_file) {
+                warn!("[IOMap::load_world] Failed to load spawn data: {e}");
+            }
+        }
+        Ok(info)
+    }
+
+    /// Läs en .otbm-fil in i världen: tiles, hus-tiles, städer och waypoints.
+    /// Motsvarar `IOMap::loadMap`.
+    pub fn load_map(world: &mut World, path: impl AsRef<Path>) -> Result<MapInfo> {
+        let path = path.as_ref();
+        let start = std::time::Instant::now();
+        let loader = OtbLoader::open(path, b"OTBM")?;
+        let root = loader.root();
+
+        let mut stream = PropStream::new(&root.props);
+        let (Some(version), Some(width), Some(height), Some(major_items), Some(minor_items)) = (
+            stream.read_u32(),
+            stream.read_u16(),
+            stream.read_u16(),
+            stream.read_u32(),
+            stream.read_u32(),
+        ) else {
+            return Err(Error::World("Could not read root header".into()));
+        };
+
+        if version == 0 {
+            return Err(Error::World(
+                "This map need to be upgraded by using the latest map editor version to be able to load correctly"
+                    .into(),
+            ));
+        }
+        if version > 2 {
+            return Err(Error::World("Unknown OTBM version detected".into()));
+        }
+        if major_items < 3 {
+            return Err(Error::World(
+                "This map need to be upgraded by using the latest map editor version to be able to load correctly"
+                    .into(),
+            ));
+        }
+        if let Some(items) = Items::instance() {
+            if major_items > items.major_version {
+                return Err(Error::World(
+                    "The map was saved with a different items.otb version, an upgraded items.otb is required".into(),
+                ));
+            }
+            if minor_items > items.minor_version {
+                warn!("This map needs an updated items.otb");
+            }
+        }
+        if minor_items < CLIENT_VERSION_810 {
+            return Err(Error::World("This map needs to be updated to 8.10".into()));
+        }
+        info!("Map size: {width}x{height}");
+
+        let map_node = root
+            .children
+            .first()
+            .filter(|node| node.node_type == OTBM_MAP_DATA)
+            .ok_or_else(|| Error::World("Could not read data node".into()))?;
+
+        let info = Self::parse_map_data_attributes(map_node, path)?;
+
+        for node in &map_node.children {
+            match node.node_type {
+                OTBM_TILE_AREA => Self::parse_tile_area(world, node)?,
+                OTBM_TOWNS => Self::parse_towns(world, node)?,
+                OTBM_WAYPOINTS if version > 1 => Self::parse_waypoints(world, node)?,
+                _ => return Err(Error::World("Unknown map node".into())),
+            }
+        }
+
+        world.map.width = width;
+        world.map.height = height;
+        info!("Map loading time: {:.3} seconds", start.elapsed().as_secs_f64());
+        Ok(info)
+    }
+
+    fn parse_map_data_attributes(node: &OtbNode, path: &Path) -> Result<MapInfo> {
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        let mut info = MapInfo::default();
+        let mut stream = PropStream::new(&node.props);
+        let invalid = || Error::World("Invalid map data attribute".into());
+
+        while let Some(attr) = stream.read_u8() {
+            let value = stream.read_string().ok_or_else(invalid)?;
+            match attr {
+                OTBM_ATTR_DESCRIPTION => info.description.push(value),
+                OTBM_ATTR_EXT_SPAWN_FILE => info.spawn_file = Some(dir.join(value)),
+                OTBM_ATTR_EXT_HOUSE_FILE => info.house_file = Some(dir.join(value)),
+                _ => return Err(Error::World("Unknown header node".into())),
+            }
+        }
+        Ok(info)
+    }
+
+    fn parse_tile_area(world: &mut World, area: &OtbNode) -> Result<()> {
+        let mut stream = PropStream::new(&area.props);
+        let (Some(base_x), Some(base_y), Some(z)) = (stream.read_u16(), stream.read_u16(), stream.read_u8()) else {
+            return Err(Error::World("Invalid map node".into()));
+        };
+
+        for node in &area.children {
+            if node.node_type != OTBM_TILE && node.node_type != OTBM_HOUSETILE {
+                return Err(Error::World("Unknown tile node".into()));
+            }
+
+            let mut stream = PropStream::new(&node.props);
+            let (Some(x), Some(y)) = (stream.read_u8(), stream.read_u8()) else {
+                return Err(Error::World("Could not read tile position".into()));
+            };
+            let pos = Position::new(base_x + x as u16, base_y + y as u16, z);
+
+            let house_id = if node.node_type == OTBM_HOUSETILE {
+                match stream.read_u32() {
+                    Some(id) => id,
+                    None => return Err(Error::World(format!("[x:{}, y:{}, z:{}] Could not read house id", pos.x, pos.y, pos.z))),
+                }
+            } else {
+                0
+            };
+
+            let mut tile = Tile::new(pos);
+            while let Some(attr) = stream.read_u8() {
+                match attr {
+                    OTBM_ATTR_TILE_FLAGS => {
+                        let Some(flags) = stream.read_u32() else {
+                            return Err(Error::World(format!("[x:{}, y:{}, z:{}] Failed to read tile flags", pos.x, pos.y, pos.z)));
+                        };
+                        Self::apply_tile_flags(&mut tile, flags);
+                    }
+                    OTBM_ATTR_ITEM => {
+                        let Some(id) = stream.read_u16() else {
+                            return Err(Error::World(format!("[x:{}, y:{}, z:{}] Failed to create item", pos.x, pos.y, pos.z)));
+                        };
+                        Self::add_tile_item(&mut tile, Item::new(id, 0), house_id);
+                    }
+                    _ => return Err(Error::World(format!("[x:{}, y:{}, z:{}] Unknown tile attribute", pos.x, pos.y, pos.z))),
+                }
+            }
+
+            for item_node in &node.children {
+                if item_node.node_type != OTBM_ITEM {
+                    return Err(Error::World(format!("[x:{}, y:{}, z:{}] Unknown node type", pos.x, pos.y, pos.z)));
+                }
+                let Some(item) = Self::parse_item(item_node) else {
+                    return Err(Error::World(format!("[x:{}, y:{}, z:{}] Failed to load item", pos.x, pos.y, pos.z)));
+                };
+                Self::add_tile_item(&mut tile, item, house_id);
+            }
+
+            world.map.set_tile(tile);
+            if house_id != 0 {
+                world.add_house_tile(house_id, pos);
+            }
+        }
+        Ok(())
+    }
+
+    /// Zonflaggorna på en tile; skyddszon går före no-pvp som går före pvp
+    fn apply_tile_flags(tile: &mut Tile, flags: u32) {
+        if flags & OTBM_TILEFLAG_PROTECTIONZONE != 0 {
+            tile.set_zone_flag(TileFlags::PROTECTIONZONE);
+        } else if flags & OTBM_TILEFLAG_NOPVPZONE != 0 {
+            tile.set_zone_flag(TileFlags::NOPVPZONE);
+        } else if flags & OTBM_TILEFLAG_PVPZONE != 0 {
+            tile.set_zone_flag(TileFlags::PVPZONE);
+        }
+        if flags & OTBM_TILEFLAG_NOLOGOUT != 0 {
+            tile.set_zone_flag(TileFlags::NOLOGOUT);
+        }
+    }
+
+    fn add_tile_item(tile: &mut Tile, item: Item, house_id: u32) {
+        if house_id != 0 && item.item_type().moveable {
+            let pos = tile.position;
+            warn!("[x:{}, y:{}, z:{}] Moveable item with ID: {}, in house: {house_id}", pos.x, pos.y, pos.z, item.id);
+            return;
+        }
+        tile.add_thing(Thing::Item(item));
+    }
+
+    /// Ett OTBM_ITEM med attribut och, för containers, barnnoder.
+    /// Motsvarar `Item::unserializeItemNode`.
+    fn parse_item(node: &OtbNode) -> Option<Item> {
+        let mut stream = PropStream::new(&node.props);
+        let id = stream.read_u16()?;
+        let mut item = Item::new(id, 0);
+        if item.unserialize_attr(&mut stream)?.is_some() {
+            return None;
+        }
+
+        for child in &node.children {
+            if child.node_type != OTBM_ITEM {
+                return None;
+            }
+            let child = Self::parse_item(child)?;
+            item.get_container_mut()?.add_item_back(child);
+        }
+        Some(item)
+    }
+
+    fn parse_towns(world: &mut World, node: &OtbNode) -> Result<()> {
+        for town_node in &node.children {
+            if town_node.node_type != OTBM_TOWN {
+                return Err(Error::World("Unknown town node".into()));
+            }
+            let mut stream = PropStream::new(&town_node.props);
+            let Some(id) = stream.read_u32() else {
+                return Err(Error::World("Could not read town id".into()));
+            };
+            let Some(name) = stream.read_string() else {
+                return Err(Error::World("Could not read town name".into()));
+            };
+            let (Some(x), Some(y), Some(z)) = (stream.read_u16(), stream.read_u16(), stream.read_u8()) else {
+                return Err(Error::World("Could not read town coordinates".into()));
+            };
+
+            let town = Town { id, name, temple_position: Position::new(x, y, z) };
+            if !world.towns.add_town(town) {
+                warn!("Duplicate town with id: {id}, discarding town");
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_waypoints(world: &mut World, node: &OtbNode) -> Result<()> {
+        for waypoint in &node.children {
+            if waypoint.node_type != OTBM_WAYPOINT {
+                return Err(Error::World("Unknown waypoint node".into()));
+            }
+            let mut stream = PropStream::new(&waypoint.props);
+            let Some(name) = stream.read_string() else {
+                return Err(Error::World("Could not read waypoint name".into()));
+            };
+            let (Some(x), Some(y), Some(z)) = (stream.read_u16(), stream.read_u16(), stream.read_u8()) else {
+                return Err(Error::World("Could not read waypoint coordinates".into()));
+            };
+            world.map.waypoints.insert(name, Position::new(x, y, z));
+        }
+        Ok(())
+    }
+}
@@ -1,14 +1,588 @@
 pub mod map;
 pub mod spectators;
 pub mod lighting;
+pub mod tile;
+pub mod spawn;
+pub mod house;
+pub mod town;
+pub mod iomap;
+pub mod mapcache;
+pub mod pathfinding;
+pub mod creature_checks;
+
+use common::{Direction, MagicEffect, Position, ReturnValue};
+use common::tracing::warn;
+use items::Item;
+use timewheel::TimeWheel;
+
+use crate::creature_checks::CreatureChecks;
+use crate::pathfinding::PathCache;
+
+pub use creature_checks::CreatureCheck;
+pub use house::{House, HouseConfig, HouseEviction, HouseOwner, HouseVisitor, Houses};
+pub use iomap::{IOMap, MapInfo};
+pub use lighting::{LightInfo, WorldLight};
+pub use map::Map;
+pub use mapcache::MapCache;
+pub use pathfinding::{FindPathParams, PathFinder, PathWalker};
+pub use spawn::{CreatureFactory, SpawnConfig, SpawnKind, Spawns};
+pub use spectators::{SpectatorIndex, SpectatorQuery};
+pub use tile::{Thing, ThingRef, Tile, TileFlags};
+pub use town::{Town, Towns};
+
+/// Ändringar på kartan som protokollet ska skicka till spelarna i
+/// `spectators` (motsvarar onAddTileItem/onUpdateTileItem/... i TFS).
+#[derive(Debug, Clone, PartialEq)]
+pub enum WorldEvent {
+    AddThing { pos: Position, stackpos: u8, spectators: Vec<u32> },
+    UpdateThing { pos: Position, stackpos: u8, spectators: Vec<u32> },
+    RemoveThing { pos: Position, stackpos: u8, spectators: Vec<u32> },
+    CreatureMove {
+        id: u32,
+        from: Position,
+        from_stackpos: u8,
+        to: Position,
+        teleport: bool,
+        spectators: Vec<u32>,
+    },
+    MagicEffect { pos: Position, effect: MagicEffect, spectators: Vec<u32> },
+    /// Nytt världsljus, skickas till alla spelare
+    WorldLight { light: LightInfo, players: Vec<u32> },
+    CreatureLight { id: u32, light: LightInfo, spectators: Vec<u32> },
+}
+
+/// Uppgifter som världen schemalägger på sin egen timer-wheel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorldTask {
+    CheckSpawn(usize),
+    CheckLight,
+    UpdateWorldTime,
+    CheckCreatures,
+    CreatureWalk(u32),
+    CreatureAttack(u32),
+}
 
 pub struct World {
-    // ...
+    pub map: Map,
+    pub spectators: SpectatorIndex,
+    pub spawns: Spawns,
+    pub houses: Houses,
+    pub towns: Towns,
+    pub light: WorldLight,
+    scheduler: TimeWheel<WorldTask>,
+    path_finder: PathFinder,
+    path_cache: PathCache,
+    creature_checks: CreatureChecks,
+    factory: Option<Box<dyn CreatureFactory + Send>>,
+    events: Vec<WorldEvent>,
+}
+
+impl Default for World {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl World {
     pub fn new() -> Self {
-        // ...
-        unimplemented!()
+        Self {
+            map: Map::new(),
+            spectators: SpectatorIndex::new(),
+            spawns: Spawns::default(),
+            houses: Houses::default(),
+            towns: Towns::new(),
+            light: WorldLight::default(),
+            scheduler: TimeWheel::default(),
+            path_finder: PathFinder::new(),
+            path_cache: PathCache::default(),
+            creature_checks: CreatureChecks::default(),
+            factory: None,
+            events: Vec::new(),
+        }
+    }
+
+    pub fn set_creature_factory(&mut self, factory: Box<dyn CreatureFactory + Send>) {
+        self.factory = Some(factory);
+    }
+
+    /// Kör schemalagda uppgifter som förfallit. Anropas från spelloopen.
+    pub fn tick(&mut self, now: u64) {
+        self.spectators.new_tick();
+        for task in self.scheduler.advance(now) {
+            match task {
+                WorldTask::CheckSpawn(index) => self.check_spawn(index, now),
+                WorldTask::CheckLight => self.check_light(now),
+                WorldTask::UpdateWorldTime => self.update_world_time(now),
+                WorldTask::CheckCreatures => self.check_creatures(now),
+                WorldTask::CreatureWalk(id) => {
+                    self.creature_checks.walk_events.remove(&id);
+                    self.creature_checks.due.push(CreatureCheck::Walk(id));
+                }
+                WorldTask::CreatureAttack(id) => {
+                    self.creature_checks.attack_events.remove(&id);
+                    self.creature_checks.due.push(CreatureCheck::Attack(id));
+                }
+            }
+        }
+    }
+
+    pub fn get_tile(&self, pos: &Position) -> Option<&Tile> {
+        self.map.get_tile(pos)
+    }
+
+    /// Töm händelsekön (protokollet skickar paket för varje händelse)
+    pub fn drain_events(&mut self) -> Vec<WorldEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    // === Varelser ===
+
+    /// Placera en varelse på kartan (login, spawn, summon)
+    pub fn place_creature(&mut self, id: u32, pos: Position, is_player: bool) -> ReturnValue {
+        let Some(tile) = self.map.get_tile_mut(&pos) else {
+            return ReturnValue::NotPossible;
+        };
+        let stackpos = tile.add_thing(Thing::Creature(id));
+        self.spectators.insert(id, pos, is_player);
+        self.creature_checks.add(id);
+        if let Some(stackpos) = stackpos {
+            let spectators = self.spectators.get_player_spectators(pos, true);
+            self.events.push(WorldEvent::AddThing { pos, stackpos, spectators });
+        }
+        ReturnValue::NoError
+    }
+
+    pub fn remove_creature(&mut self, id: u32) -> bool {
+        let Some(pos) = self.spectators.position_of(id) else {
+            return false;
+        };
+        let spectators = self.spectators.get_player_spectators(pos, true);
+        if let Some(stackpos) = self.map.get_tile_mut(&pos).and_then(|t| t.remove_creature(id)) {
+            self.events.push(WorldEvent::RemoveThing { pos, stackpos, spectators });
+        }
+        self.spectators.remove(id);
+        for handle in self.creature_checks.remove(id) {
+            self.scheduler.cancel(handle);
+        }
+        self.on_spawned_creature_removed(id);
+        true
+    }
+
+    /// Flytta en varelse. Fungerar för vanliga steg, teleport och
+    /// våningsbyten; `teleport` styr bara vilket paket klienten får.
+    pub fn move_creature(&mut self, id: u32, to: Position, teleport: bool) -> ReturnValue {
+        let Some(from) = self.spectators.position_of(id) else {
+            return ReturnValue::CreatureDoesNotExist;
+        };
+        if self.map.get_tile(&to).is_none() {
+            return ReturnValue::NotPossible;
+        }
+
+        let from_stackpos = self
+            .map
+            .get_tile_mut(&from)
+            .and_then(|t| t.remove_creature(id))
+            .unwrap_or(u8::MAX);
+
+        if let Some(tile) = self.map.get_tile_mut(&to) {
+            tile.add_thing(Thing::Creature(id));
+        }
+
+        // alla som såg start- eller målrutan behöver veta om flytten
+        let mut spectators = self.spectators.get_player_spectators(from, true);
+        self.spectators.move_creature(id, to);
+        for spectator in self.spectators.get_player_spectators(to, true) {
+            if !spectators.contains(&spectator) {
+                spectators.push(spectator);
+            }
+        }
+
+        self.events.push(WorldEvent::CreatureMove {
+            id,
+            from,
+            from_stackpos,
+            to,
+            teleport,
+            spectators,
+        });
+        ReturnValue::NoError
+    }
+
+    /// Starta think-cykeln för alla varelser på kartan. Motsvarar
+    /// schemaläggningen av `checkCreatures` i `Game::start`.
+    pub fn start_creature_checks(&mut self, now: u64) {
+        if self.creature_checks.started {
+            return;
+        }
+        self.creature_checks.started = true;
+        self.scheduler
+            .schedule(now, entities::creature::EVENT_CHECK_CREATURE_INTERVAL, WorldTask::CheckCreatures);
+    }
+
+    fn check_creatures(&mut self, now: u64) {
+        self.scheduler
+            .schedule(now, entities::creature::EVENT_CHECK_CREATURE_INTERVAL, WorldTask::CheckCreatures);
+        self.creature_checks.next_list();
+    }
+
+    /// Nästa steg om `delay` ms, t.ex. varelsens steglängd. Ett tidigare
+    /// schemalagt steg ersätts. Motsvarar `Creature::addEventWalk`.
+    pub fn schedule_creature_walk(&mut self, id: u32, now: u64, delay: u64) {
+        let handle = self.scheduler.schedule(now, delay, WorldTask::CreatureWalk(id));
+        if let Some(old) = self.creature_checks.walk_events.insert(id, handle) {
+            self.scheduler.cancel(old);
+        }
+    }
+
+    /// Motsvarar `Creature::stopEventWalk`
+    pub fn stop_creature_walk(&mut self, id: u32) {
+        if let Some(handle) = self.creature_checks.walk_events.remove(&id) {
+            self.scheduler.cancel(handle);
+        }
+    }
+
+    /// Nästa attack om `delay` ms, t.ex. spelarens attackhastighet
+    pub fn schedule_creature_attack(&mut self, id: u32, now: u64, delay: u64) {
+        let handle = self.scheduler.schedule(now, delay, WorldTask::CreatureAttack(id));
+        if let Some(old) = self.creature_checks.attack_events.insert(id, handle) {
+            self.scheduler.cancel(old);
+        }
+    }
+
+    pub fn stop_creature_attack(&mut self, id: u32) {
+        if let Some(handle) = self.creature_checks.attack_events.remove(&id) {
+            self.scheduler.cancel(handle);
+        }
+    }
+
+    /// Varelser som ska tänka, gå eller attackera nu. Spelet, som äger
+    /// varelserna, hämtar dem efter varje `tick`.
+    pub fn drain_creature_checks(&mut self) -> Vec<CreatureCheck> {
+        std::mem::take(&mut self.creature_checks.due)
+    }
+
+    // === Items ===
+
+    /// Lägg ett item på kartan. Motsvarar `internalAddItem` på en tile.
+    pub fn add_item(&mut self, pos: Position, item: Item) -> ReturnValue {
+        let ret = self.map.get_or_create_tile(pos).query_add_item(&item);
+        if !ret.is_ok() {
+            return ret;
+        }
+        let Some((removed, stackpos)) = self.map.update_tile(&pos, |tile| tile.add_item(item)) else {
+            return ReturnValue::NotPossible;
+        };
+        let spectators = self.spectators.get_player_spectators(pos, true);
+        match (removed, stackpos) {
+            // ersatt på samma plats, t.ex. ny ground (onUpdateTileItem i TFS)
+            (Some(removed), Some(stackpos)) if removed == stackpos => {
+                self.events.push(WorldEvent::UpdateThing { pos, stackpos, spectators });
+            }
+            (removed, stackpos) => {
+                if let Some(stackpos) = removed {
+                    self.events.push(WorldEvent::RemoveThing { pos, stackpos, spectators: spectators.clone() });
+                }
+                if let Some(stackpos) = stackpos {
+                    self.events.push(WorldEvent::AddThing { pos, stackpos, spectators });
+                }
+            }
+        }
+        ReturnValue::NoError
+    }
+
+    /// Ta bort `count` av itemet på stackpos (0 = hela)
+    pub fn remove_item(&mut self, pos: Position, stackpos: u8, count: u16) -> Option<Item> {
+        let (item, removed_whole) = self.map.update_tile(&pos, |tile| {
+            let before = tile.thing_count();
+            let item = tile.remove_item_at(stackpos as usize, count)?;
+            Some((item, tile.thing_count() < before))
+        })??;
+
+        // klienten ser inte items längre ner än MAX_STACKPOS
+        if stackpos as usize >= tile::MAX_STACKPOS {
+            return Some(item);
+        }
+        let spectators = self.spectators.get_player_spectators(pos, true);
+        if removed_whole {
+            self.events.push(WorldEvent::RemoveThing { pos, stackpos, spectators });
+        } else {
+            self.events.push(WorldEvent::UpdateThing { pos, stackpos, spectators });
+        }
+        Some(item)
+    }
+
+    /// Transformera/ändra count på ett item (motsvarar `transformItem`)
+    pub fn update_item(&mut self, pos: Position, stackpos: u8, new_id: u16, new_count: u16) -> ReturnValue {
+        let Some(tile) = self.map.get_tile(&pos) else {
+            return ReturnValue::NotPossible;
+        };
+        if !matches!(tile.get_thing(stackpos as usize, |_| true), Some(ThingRef::Item(_))) {
+            return ReturnValue::NotPossible;
+        }
+        // None betyder att itemet hamnade utanför det klienten ser
+        let new_stackpos = self
+            .map
+            .update_tile(&pos, |tile| tile.update_item(stackpos as usize, new_id, new_count))
+            .flatten();
+
+        let spectators = self.spectators.get_player_spectators(pos, true);
+        match new_stackpos {
+            Some(new_stackpos) if new_stackpos == stackpos => {
+                self.events.push(WorldEvent::UpdateThing { pos, stackpos, spectators });
+            }
+            new_stackpos => {
+                if (stackpos as usize) < tile::MAX_STACKPOS {
+                    self.events.push(WorldEvent::RemoveThing { pos, stackpos, spectators: spectators.clone() });
+                }
+                if let Some(stackpos) = new_stackpos {
+                    self.events.push(WorldEvent::AddThing { pos, stackpos, spectators });
+                }
+            }
+        }
+        ReturnValue::NoError
+    }
+
+    pub fn add_magic_effect(&mut self, pos: Position, effect: MagicEffect) {
+        let spectators = self.spectators.get_player_spectators(pos, true);
+        self.events.push(WorldEvent::MagicEffect { pos, effect, spectators });
+    }
+
+    // === Ljus ===
+
+    /// Starta dygnscykeln. Motsvarar anropen till `updateWorldTime` och
+    /// `checkLight` i `Game::start`.
+    pub fn start_light_cycle(&mut self, now: u64) {
+        self.update_world_time(now);
+        self.check_light(now);
+    }
+
+    fn update_world_time(&mut self, now: u64) {
+        self.scheduler.schedule(now, lighting::EVENT_WORLDTIMEINTERVAL, WorldTask::UpdateWorldTime);
+        self.light.update_world_time(now / 1000);
+    }
+
+    /// Motsvarar `Game::checkLight`, men skickar bara när ljuset ändrats
+    fn check_light(&mut self, now: u64) {
+        self.scheduler.schedule(now, lighting::EVENT_LIGHTINTERVAL, WorldTask::CheckLight);
+        self.light.update_light_level();
+        if self.light.take_changed() {
+            let players = self.spectators.players().collect();
+            self.events.push(WorldEvent::WorldLight { light: self.light.light_info(), players });
+        }
+    }
+
+    /// En varelses ljus har ändrats (utrustning, ljus-spell). Motsvarar
+    /// `Game::changeLight`.
+    pub fn change_creature_light(&mut self, id: u32, light: LightInfo) {
+        let Some(pos) = self.spectators.position_of(id) else {
+            return;
+        };
+        let spectators = self.spectators.get_player_spectators(pos, true);
+        self.events.push(WorldEvent::CreatureLight { id, light, spectators });
+    }
+
+    // === Spawns ===
+
+    /// Skapa alla varelser och starta respawn-kontrollerna.
+    /// Motsvarar `Spawns::startup` i TFS.
+    pub fn startup_spawns(&mut self, now: u64) {
+        if self.spawns.is_started() || self.spawns.is_empty() {
+            return;
+        }
+        if self.factory.is_none() {
+            warn!("[World::startup_spawns] No creature factory set, spawns are not started");
+            return;
+        }
+
+        for index in 0..self.spawns.len() {
+            let block_count = self.spawns.get(index).map_or(0, |s| s.blocks.len());
+            for block in 0..block_count {
+                self.spawn_block(index, block, now);
+            }
+            self.start_spawn_check(index, now);
+        }
+        self.spawns.set_started();
+    }
+
+    /// Motsvarar `Spawn::spawnMonster`. Placeringen är tvingad precis som i TFS.
+    fn spawn_block(&mut self, index: usize, block: usize, now: u64) -> bool {
+        let Some(factory) = self.factory.as_mut() else {
+            return false;
+        };
+        let Some(sb) = self.spawns.get(index).and_then(|s| s.blocks.get(block)) else {
+            return false;
+        };
+        let (name, pos) = (sb.name.clone(), sb.position);
+
+        let created = match sb.kind {
+            SpawnKind::Monster => factory.create_monster(&name, pos, sb.direction),
+            SpawnKind::Npc => factory.create_npc(&name, pos, sb.direction),
+        };
+        let Some(id) = created else {
+            warn!("[World::spawn_block] Can not create {name} at {pos}");
+            return false;
+        };
+
+        if !self.place_creature(id, pos, false).is_ok() {
+            warn!("[World::spawn_block] Can not place {name} at {pos}");
+            if let Some(factory) = self.factory.as_mut() {
+                factory.release_creature(id);
+            }
+            return false;
+        }
+
+        self.spawns.register(index, block, id);
+        if let Some(sb) = self.spawns.get_mut(index).map(|s| &mut s.blocks[block]) {
+            sb.last_spawn = now;
+        }
+        true
+    }
+
+    fn start_spawn_check(&mut self, index: usize, now: u64) {
+        let Some(spawn) = self.spawns.get(index) else {
+            return;
+        };
+        if spawn.check_event.is_some() || !spawn.needs_respawn() {
+            return;
+        }
+        let handle = self.scheduler.schedule(now, spawn.interval, WorldTask::CheckSpawn(index));
+        if let Some(spawn) = self.spawns.get_mut(index) {
+            spawn.check_event = Some(handle);
+        }
+    }
+
+    /// Motsvarar `Spawn::checkSpawn`: respawna block vars intervall har gått,
+    /// men inte om en spelare ser platsen.
+    fn check_spawn(&mut self, index: usize, now: u64) {
+        let Some(spawn) = self.spawns.get_mut(index) else {
+            return;
+        };
+        spawn.check_event = None;
+
+        let due: Vec<usize> = spawn
+            .blocks
+            .iter()
+            .enumerate()
+            .filter(|(i, b)| b.kind == SpawnKind::Monster && !spawn.is_spawned(*i))
+            .filter(|(_, b)| now >= b.last_spawn + b.interval_ms)
+            .map(|(i, _)| i)
+            .collect();
+
+        let mut spawn_count = 0;
+        for block in due {
+            let pos = self.spawns.get(index).map(|s| s.blocks[block].position);
+            let Some(pos) = pos else {
+                break;
+            };
+            if self.find_player(pos) {
+                if let Some(spawn) = self.spawns.get_mut(index) {
+                    spawn.blocks[block].last_spawn = now;
+                }
+                continue;
+            }
+            if self.spawn_block(index, block, now) {
+                spawn_count += 1;
+                if spawn_count >= self.spawns.config.rate_spawn {
+                    break;
+                }
+            }
+        }
+
+        self.start_spawn_check(index, now);
+    }
+
+    /// Motsvarar `Spawn::findPlayer`
+    fn find_player(&mut self, pos: Position) -> bool {
+        let query = SpectatorQuery::new(pos).only_players(true);
+        let players = self.spectators.get_spectators(&query);
+        let factory = self.factory.as_deref();
+        players
+            .into_iter()
+            .any(|id| !factory.is_some_and(|f| f.ignored_by_monsters(id)))
+    }
+
+    fn on_spawned_creature_removed(&mut self, id: u32) {
+        let now = common::otsys_time();
+        let Some(index) = self.spawns.unregister(id, now) else {
+            return;
+        };
+        if let Some(factory) = self.factory.as_mut() {
+            factory.release_creature(id);
+        }
+        self.start_spawn_check(index, now);
+    }
+
+    /// Är varelsen inom despawn-gränserna? Varelser utan spawn är alltid det.
+    pub fn is_in_spawn_range(&self, id: u32) -> bool {
+        let (Some(master), Some(pos)) = (self.spawns.master_position(id), self.spectators.position_of(id)) else {
+            return true;
+        };
+        self.spawns.config.is_in_spawn_range(master, pos)
+    }
+
+    /// Ska monstret gå tillbaka mot sin spawn? (walkToSpawnRadius)
+    pub fn should_walk_to_spawn(&self, id: u32) -> bool {
+        let (Some(master), Some(pos)) = (self.spawns.master_position(id), self.spectators.position_of(id)) else {
+            return false;
+        };
+        self.spawns.config.should_walk_back(master, pos)
+    }
+
+    /// Väg för en varelse till `target`, ur cachen om varken kartan eller
+    /// någon varelse har ändrats sedan sist. Motsvarar `Creature::getPathTo`.
+    pub fn get_path_to(
+        &mut self,
+        id: u32,
+        target: Position,
+        params: &FindPathParams,
+        walker: &dyn PathWalker,
+    ) -> Option<Vec<Direction>> {
+        let start = self.spectators.position_of(id)?;
+        let key = (id, start, target, *params);
+        let revision = (self.map.revision(), self.spectators.revision());
+        if let Some(path) = self.path_cache.get(revision, &key) {
+            return path.clone();
+        }
+        let path = self.path_finder.find_path(&self.map, start, target, walker, params);
+        self.path_cache.insert(key, path.clone());
+        path
+    }
+
+    /// Vägen tillbaka mot spawnen för ett monster som gått för långt.
+    /// Motsvarar `Monster::walkToSpawn`: det räcker att komma inom fem rutor
+    /// från var monstret står nu.
+    pub fn get_path_to_spawn(&mut self, id: u32, walker: &dyn PathWalker) -> Option<Vec<Direction>> {
+        if !self.should_walk_to_spawn(id) {
+            return None;
+        }
+        let master = self.spawns.master_position(id)?;
+        let pos = self.spectators.position_of(id)?;
+        let distance = Position::get_distance(&master, &pos);
+        let params = FindPathParams {
+            min_target_dist: 0,
+            max_target_dist: (distance - 5).max(0),
+            max_search_dist: distance,
+            ..FindPathParams::default()
+        };
+        self.get_path_to(id, master, &params, walker)
+    }
+
+    /// Despawna en varelse som lämnat sin spawn. Motsvarar despawn-delen av
+    /// `Monster::onThink`. Returnerar true om varelsen togs bort eller flyttades.
+    pub fn check_despawn(&mut self, id: u32) -> bool {
+        if self.is_in_spawn_range(id) {
+            return false;
+        }
+        let (Some(master), Some(pos)) = (self.spawns.master_position(id), self.spectators.position_of(id)) else {
+            return false;
+        };
+
+        self.add_magic_effect(pos, MagicEffect::Poff);
+        if self.spawns.config.remove_on_despawn {
+            self.remove_creature(id);
+        } else {
+            self.move_creature(id, master, true);
+        }
+        true
     }
 }
@@ -0,0 +1,133 @@
+//! Världsljus och dygnscykel, motsvarar ljusdelen av `Game` i TFS.
+//!
+//! En timme i verkligheten är ett dygn i spelet (2,5 s per spelminut).
+//! Världstiden räknas i minuter, 0..1440.
+
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU8, Ordering};
+use std::sync::Arc;
+
+use common::Config;
+pub use entities::creature::LightInfo;
+
+pub const EVENT_LIGHTINTERVAL: u64 = 10_000;
+pub const EVENT_WORLDTIMEINTERVAL: u64 = 2_500;
+
+pub const LIGHT_DAY: u8 = 250;
+pub const LIGHT_NIGHT: u8 = 40;
+/// Färgen på världsljuset (vitt i klientens 8-bitarspalett)
+pub const LIGHT_COLOR_DEFAULT: u8 = 0xD7;
+
+pub const GAME_SUNRISE: u32 = 360;
+pub const GAME_DAYTIME: u32 = 480;
+pub const GAME_SUNSET: u32 = 1080;
+pub const GAME_NIGHTTIME: u32 = 1200;
+
+/// Ljusändring per spelminut i gryning och skymning, avrundad nedåt till
+/// två decimaler precis som i TFS
+const LIGHT_CHANGE_SUNRISE: f32 = ((LIGHT_DAY - LIGHT_NIGHT) as f32 / (GAME_DAYTIME - GAME_SUNRISE) as f32 * 100.0) as i32 as f32 / 100.0;
+const LIGHT_CHANGE_SUNSET: f32 = ((LIGHT_DAY - LIGHT_NIGHT) as f32 / (GAME_NIGHTTIME - GAME_SUNSET) as f32 * 100.0) as i32 as f32 / 100.0;
+
+/// Ljusnivån vid en viss världstid. Motsvarar `Game::updateWorldLightLevel`.
+pub fn light_level_at(world_time: u32) -> u8 {
+    let level = if (GAME_SUNRISE..=GAME_DAYTIME).contains(&world_time) {
+        (world_time - GAME_SUNRISE) as f32 * LIGHT_CHANGE_SUNRISE + LIGHT_NIGHT as f32
+    } else if (GAME_SUNSET..=GAME_NIGHTTIME).contains(&world_time) {
+        LIGHT_DAY as f32 - (world_time - GAME_SUNSET) as f32 * LIGHT_CHANGE_SUNSET
+    } else if !(GAME_SUNRISE..GAME_NIGHTTIME).contains(&world_time) {
+        LIGHT_NIGHT as f32
+    } else {
+        LIGHT_DAY as f32
+    };
+    level as u8
+}
+
+/// Världstiden (minuter) för en tidpunkt i sekunder. Motsvarar
+/// `Game::updateWorldTime`: minuter och sekunder i aktuell timme / 2,5.
+pub fn world_time_at(unix_seconds: u64) -> u32 {
+    ((unix_seconds % 3600) as f32 / 2.5) as u32
+}
+
+#[derive(Debug)]
+struct LightState {
+    world_time: AtomicU32,
+    level: AtomicU8,
+    color: AtomicU8,
+    changed: AtomicBool,
+}
+
+/// Världstid och världsljus. Klonerna delar samma tillstånd, så scripten
+/// kan få en egen handle utan att låna hela världen.
+#[derive(Debug, Clone)]
+pub struct WorldLight {
+    state: Arc<LightState>,
+    /// Servern sköter dygnscykeln; annars styrs ljuset bara av `setWorldLight`
+    pub default_world_light: bool,
+}
+
+impl Default for WorldLight {
+    fn default() -> Self {
+        Self::new(true)
+    }
+}
+
+impl WorldLight {
+    pub fn new(default_world_light: bool) -> Self {
+        Self {
+            state: Arc::new(LightState {
+                world_time: AtomicU32::new(0),
+                level: AtomicU8::new(LIGHT_DAY),
+                color: AtomicU8::new(LIGHT_COLOR_DEFAULT),
+                changed: AtomicBool::new(false),
+            }),
+            default_world_light,
+        }
+    }
+
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(config.default_world_light)
+    }
+
+    pub fn world_time(&self) -> u32 {
+        self.state.world_time.load(Ordering::Relaxed)
+    }
+
+    /// Motsvarar `Game::getWorldLightInfo`
+    pub fn light_info(&self) -> LightInfo {
+        LightInfo::new(self.state.level.load(Ordering::Relaxed), self.state.color.load(Ordering::Relaxed))
+    }
+
+    pub fn update_world_time(&self, unix_seconds: u64) {
+        self.state.world_time.store(world_time_at(unix_seconds), Ordering::Relaxed);
+    }
+
+    /// Räkna om ljuset från världstiden. Gör inget om scripten styr ljuset.
+    pub fn update_light_level(&self) {
+        if !self.default_world_light {
+            return;
+        }
+        self.store(LightInfo::new(light_level_at(self.world_time()), LIGHT_COLOR_DEFAULT));
+    }
+
+    /// Sätt ljuset från ett script. Motsvarar `setWorldLight` och fungerar
+    /// bara när `defaultWorldLight` är avstängt.
+    pub fn set_world_light(&self, light: LightInfo) -> bool {
+        if self.default_world_light {
+            return false;
+        }
+        self.store(light);
+        true
+    }
+
+    /// Har ljuset ändrats sedan förra anropet?
+    pub(crate) fn take_changed(&self) -> bool {
+        self.state.changed.swap(false, Ordering::Relaxed)
+    }
+
+    fn store(&self, light: LightInfo) {
+        if self.light_info() != light {
+            self.state.level.store(light.level, Ordering::Relaxed);
+            self.state.color.store(light.color, Ordering::Relaxed);
+            self.state.changed.store(true, Ordering::Relaxed);
+        }
+    }
+}
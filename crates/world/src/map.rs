@@ -0,0 +1,195 @@
+use std::collections::{BTreeMap, HashMap};
+
+use common::Position;
+use items::item::ItemProperty;
+
+use crate::tile::Tile;
+
+/// Kartans tiles. Motsvarar `Map` i TFS, men utan QTree: varelserna
+/// indexeras separat i `SpectatorIndex`.
+#[derive(Default)]
+pub struct Map {
+    pub width: u16,
+    pub height: u16,
+    /// Namngivna positioner från kartan (OTBM_WAYPOINTS)
+    pub waypoints: BTreeMap<String, Position>,
+    tiles: HashMap<Position, Tile>,
+    /// Räknas upp när en tile ändras så att man kan gå på den på ett annat
+    /// sätt, så att cachade vägar vet när de blivit inaktuella
+    revision: u64,
+}
+
+impl Map {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn tile_count(&self) -> usize {
+        self.tiles.len()
+    }
+
+    pub fn get_tile(&self, pos: &Position) -> Option<&Tile> {
+        self.tiles.get(pos)
+    }
+
+    /// För varelser och items som inte blockerar. Ändringar av mark,
+    /// blockerande items eller zoner ska göras med `update_tile`, annars
+    /// märker vägcachen dem inte.
+    pub fn get_tile_mut(&mut self, pos: &Position) -> Option<&mut Tile> {
+        self.tiles.get_mut(pos)
+    }
+
+    /// Ändra en tile. Revisionen räknas bara upp när marken, flaggorna eller
+    /// huset ändrats, så att varelser som går inte tömmer vägcachen.
+    pub fn update_tile<R>(&mut self, pos: &Position, f: impl FnOnce(&mut Tile) -> R) -> Option<R> {
+        let tile = self.tiles.get_mut(pos)?;
+        let before = tile.path_state();
+        let result = f(tile);
+        if tile.path_state() != before {
+            self.revision += 1;
+        }
+        Some(result)
+    }
+
+    /// Hämta eller skapa en tom tile (används av loadern och när items
+    /// läggs på en position som saknar tile)
+    pub fn get_or_create_tile(&mut self, pos: Position) -> &mut Tile {
+        let revision = &mut self.revision;
+        self.tiles.entry(pos).or_insert_with(|| {
+            *revision += 1;
+            Tile::new(pos)
+        })
+    }
+
+    /// Gör plats för fler tiles i förväg (används av laddningen)
+    pub fn reserve(&mut self, additional: usize) {
+        self.tiles.reserve(additional);
+    }
+
+    pub fn set_tile(&mut self, tile: Tile) {
+        self.revision += 1;
+        self.width = self.width.max(tile.position.x);
+        self.height = self.height.max(tile.position.y);
+        self.tiles.insert(tile.position, tile);
+    }
+
+    pub fn remove_tile(&mut self, pos: &Position) -> Option<Tile> {
+        self.revision += 1;
+        self.tiles.remove(pos)
+    }
+
+    pub fn tiles(&self) -> impl Iterator<Item = &Tile> {
+        self.tiles.values()
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Kan något kastas/skjutas från `from` till `to`? Motsvarar `Map::isSightClear`.
+    pub fn is_sight_clear(&self, from: Position, to: Position, same_floor: bool) -> bool {
+        if from.z == to.z {
+            if Position::get_distance_x(&from, &to) < 2 && Position::get_distance_y(&from, &to) < 2 {
+                return true;
+            }
+            let clear = self.check_sight_line(from, to);
+            if clear || same_floor {
+                return clear;
+            }
+            // inga hinder ovanför våning 0, annars kastar vi över hindret
+            if from.z == 0 {
+                return true;
+            }
+            let above_from = Position::new(from.x, from.y, from.z - 1);
+            let above_to = Position::new(to.x, to.y, to.z - 1);
+            if !self.is_tile_clear(above_from, true) || !self.is_tile_clear(above_to, true) {
+                return false;
+            }
+            return self.check_sight_line(above_from, above_to);
+        }
+
+        if same_floor {
+            return false;
+        }
+        // genom marknivån ser man aldrig
+        if (from.z < 8 && to.z > 7) || (from.z > 7 && to.z < 8) {
+            return false;
+        }
+
+        if from.z > to.z {
+            if Position::get_distance_z(&from, &to) > 1 {
+                return false;
+            }
+            let above = Position::new(from.x, from.y, from.z - 1);
+            if !self.is_tile_clear(above, true) {
+                return false;
+            }
+            return self.check_sight_line(above, to);
+        }
+
+        for z in from.z..to.z {
+            if !self.is_tile_clear(Position::new(to.x, to.y, z), true) {
+                return false;
+            }
+        }
+        self.check_sight_line(from, Position::new(to.x, to.y, from.z))
+    }
+
+    fn is_tile_clear(&self, pos: Position, block_floor: bool) -> bool {
+        let Some(tile) = self.get_tile(&pos) else {
+            return true;
+        };
+        if block_floor && tile.ground().is_some() {
+            return false;
+        }
+        !tile.has_property(ItemProperty::BlockProjectile)
+    }
+
+    /// Motsvarar `Map::checkSightLine`
+    fn check_sight_line(&self, from: Position, to: Position) -> bool {
+        if from == to {
+            return true;
+        }
+        let (mut start, destination) = if from.z > to.z { (to, from) } else { (from, to) };
+        let step = |a: u16, b: u16| -> i32 { (b as i32 - a as i32).signum() };
+        let (mx, my) = (step(start.x, destination.x), step(start.y, destination.y));
+
+        let a = Position::get_offset_y(&destination, &start);
+        let b = Position::get_offset_x(&start, &destination);
+        let c = -(a * destination.x as i32 + b * destination.y as i32);
+
+        while start.x != destination.x || start.y != destination.y {
+            let (x, y) = (start.x as i32, start.y as i32);
+            let move_hor = (a * (x + mx) + b * y + c).abs();
+            let move_ver = (a * x + b * (y + my) + c).abs();
+            let move_cross = (a * (x + mx) + b * (y + my) + c).abs();
+
+            if start.y != destination.y
+                && (start.x == destination.x || move_hor > move_ver || move_hor > move_cross)
+            {
+                start.y = (start.y as i32 + my) as u16;
+            }
+            if start.x != destination.x
+                && (start.y == destination.y || move_ver > move_hor || move_ver > move_cross)
+            {
+                start.x = (start.x as i32 + mx) as u16;
+            }
+
+            if self
+                .get_tile(&start)
+                .is_some_and(|tile| tile.has_property(ItemProperty::BlockProjectile))
+            {
+                return false;
+            }
+        }
+
+        // mellan våningarna måste allt vara tomt
+        while start.z != destination.z {
+            if self.get_tile(&start).is_some_and(|tile| tile.thing_count() > 0) {
+                return false;
+            }
+            start.z += 1;
+        }
+        true
+    }
+}
@@ -0,0 +1,454 @@
+//! Binär cache av den laddade världen, så att en omstart slipper tolka
+//! .otbm-filen och hus-/spawn-XML:en på nytt.
+//!
+//! Cachen är giltig så länge källfilernas hashar, item-registrets version
+//! och formatversionen stämmer. Annars (eller om filen är trasig) laddas
+//! allt som vanligt och cachen skrivs om.
+//!
+//! Format (little endian, samma strömmar som item-attributen):
+//! `"RSMC" version items(major minor build count) källor info towns waypoints tiles houses spawns`
+
+use std::path::{Path, PathBuf};
+
+use common::tracing::{info, warn};
+use common::{Direction, Error, Position, PropStream, PropWriteStream, Result};
+use items::serialize::attr;
+use items::{Item, Items};
+
+use crate::iomap::{IOMap, MapInfo};
+use crate::spawn::{Spawn, SpawnBlock, SpawnKind};
+use crate::tile::{Thing, Tile, TileFlags};
+use crate::town::Town;
+use crate::{Houses, Spawns, World};
+
+const MAGIC: &[u8; 4] = b"RSMC";
+/// Räkna upp när formatet ändras; gamla cachefiler byggs då om
+const FORMAT_VERSION: u32 = 1;
+
+pub struct MapCache;
+
+impl MapCache {
+    /// Standardplatsen för cachen: bredvid kartan, t.ex. `forgotten.otbm.cache`
+    pub fn default_path(map_path: impl AsRef<Path>) -> PathBuf {
+        let mut path = map_path.as_ref().as_os_str().to_owned();
+        path.push(".cache");
+        PathBuf::from(path)
+    }
+
+    /// Ladda världen från cachen om den är aktuell, annars från källfilerna
+    /// (och skriv en ny cache). Används i stället för `IOMap::load_world`.
+    pub fn load_world(world: &mut World, map_path: impl AsRef<Path>, cache_path: impl AsRef<Path>) -> Result<MapInfo> {
+        let (map_path, cache_path) = (map_path.as_ref(), cache_path.as_ref());
+
+        match Self::load(world, map_path, cache_path) {
+            Ok(Some(info)) => return Ok(info),
+            Ok(None) => info!("Map cache {} is missing or stale, loading {}", cache_path.display(), map_path.display()),
+            Err(e) => warn!("[MapCache::load_world] Ignoring map cache {}: {e}", cache_path.display()),
+        }
+
+        let info = IOMap::load_world(world, map_path)?;
+        if let Err(e) = Self::save(world, &info, map_path, cache_path) {
+            warn!("[MapCache::load_world] Could not write map cache {}: {e}", cache_path.display());
+        }
+        Ok(info)
+    }
+
+    /// Läs cachen. `Ok(None)` om den saknas eller är inaktuell.
+    pub fn load(world: &mut World, map_path: &Path, cache_path: &Path) -> Result<Option<MapInfo>> {
+        let start = std::time::Instant::now();
+        // filen läses i ett svep och tolkas direkt ur bufferten
+        let data = match std::fs::read(cache_path) {
+            Ok(data) => data,
+            Err(_) => return Ok(None),
+        };
+        let mut stream = PropStream::new(&data);
+        let invalid = || Error::World("Corrupt map cache".into());
+
+        if stream.read_bytes(4) != Some(MAGIC.as_slice()) || stream.read_u32() != Some(FORMAT_VERSION) {
+            return Ok(None);
+        }
+        if !Self::items_match(&mut stream).ok_or_else(invalid)? {
+            return Ok(None);
+        }
+        if !Self::sources_match(&mut stream, map_path).ok_or_else(invalid)? {
+            return Ok(None);
+        }
+
+        // läs till en egen värld så att en trasig cache inte lämnar något halvladdat
+        let mut loaded = World::new();
+        loaded.houses = Houses::new(world.houses.config);
+        loaded.spawns = Spawns::new(world.spawns.config);
+        let info = Self::read_world(&mut loaded, &mut stream).ok_or_else(invalid)?;
+        world.map = loaded.map;
+        world.towns = loaded.towns;
+        world.houses = loaded.houses;
+        world.spawns = loaded.spawns;
+        info!("Map loaded from cache in {:.3} seconds", start.elapsed().as_secs_f64());
+        Ok(Some(info))
+    }
+
+    /// Skriv cachen för en nyladdad värld. Skrivs till en temporär fil som
+    /// sedan byter namn, så att en avbruten skrivning aldrig lämnar en halv cache.
+    pub fn save(world: &World, info: &MapInfo, map_path: &Path, cache_path: &Path) -> Result<()> {
+        let mut stream = PropWriteStream::new();
+        stream.write_bytes(MAGIC);
+        stream.write_u32(FORMAT_VERSION);
+        Self::write_items_version(&mut stream);
+        Self::write_sources(&mut stream, map_path, info)?;
+        Self::write_world(&mut stream, world, info);
+
+        let tmp = cache_path.with_extension("tmp");
+        std::fs::write(&tmp, stream.get_stream())
+            .and_then(|_| std::fs::rename(&tmp, cache_path))
+            .map_err(|e| Error::World(format!("{}: {e}", cache_path.display())))
+    }
+
+    // === Nycklar ===
+
+    fn items_version() -> [u32; 4] {
+        match Items::instance() {
+            Some(items) => [items.major_version, items.minor_version, items.build_number, items.len() as u32],
+            None => [0; 4],
+        }
+    }
+
+    fn write_items_version(stream: &mut PropWriteStream) {
+        for value in Self::items_version() {
+            stream.write_u32(value);
+        }
+    }
+
+    fn items_match(stream: &mut PropStream) -> Option<bool> {
+        let mut stored = [0u32; 4];
+        for value in &mut stored {
+            *value = stream.read_u32()?;
+        }
+        Some(stored == Self::items_version())
+    }
+
+    /// Kartan först, sedan hus- och spawnfilerna den pekar ut
+    fn write_sources(stream: &mut PropWriteStream, map_path: &Path, info: &MapInfo) -> Result<()> {
+        let sources: Vec<&Path> = std::iter::once(map_path)
+            .chain(info.house_file.as_deref())
+            .chain(info.spawn_file.as_deref())
+            .collect();
+        stream.write_u8(sources.len() as u8);
+        for path in sources {
+            let hash = hash_file(path).ok_or_else(|| Error::World(format!("Cannot read {}", path.display())))?;
+            stream.write_string(&path.to_string_lossy());
+            stream.write_u64(hash);
+        }
+        Ok(())
+    }
+
+    fn sources_match(stream: &mut PropStream, map_path: &Path) -> Option<bool> {
+        let count = stream.read_u8()?;
+        let mut matches = true;
+        for index in 0..count {
+            let path = PathBuf::from(stream.read_string()?);
+            let hash = stream.read_u64()?;
+            if index == 0 && path != map_path {
+                matches = false;
+            }
+            if matches && hash_file(&path) != Some(hash) {
+                matches = false;
+            }
+        }
+        Some(matches)
+    }
+
+    // === Innehåll ===
+
+    fn write_world(stream: &mut PropWriteStream, world: &World, info: &MapInfo) {
+        stream.write_u16(world.map.width);
+        stream.write_u16(world.map.height);
+        write_opt_path(stream, info.spawn_file.as_deref());
+        write_opt_path(stream, info.house_file.as_deref());
+        stream.write_u16(info.description.len() as u16);
+        for line in &info.description {
+            stream.write_string(line);
+        }
+
+        stream.write_u32(world.towns.len() as u32);
+        for town in world.towns.iter() {
+            stream.write_u32(town.id);
+            stream.write_string(&town.name);
+            write_position(stream, town.temple_position);
+        }
+
+        stream.write_u32(world.map.waypoints.len() as u32);
+        for (name, pos) in &world.map.waypoints {
+            stream.write_string(name);
+            write_position(stream, *pos);
+        }
+
+        stream.write_u32(world.map.tile_count() as u32);
+        for tile in world.map.tiles() {
+            write_position(stream, tile.position);
+            // de flesta tiles har varken zon eller hus, så de skrivs bara vid behov
+            let zone = tile.flags().0 & TileFlags::ZONE_MASK.0;
+            let house_id = tile.house_id();
+            stream.write_u8(u8::from(zone != 0) | (u8::from(house_id != 0) << 1));
+            if zone != 0 {
+                stream.write_u32(zone);
+            }
+            if house_id != 0 {
+                stream.write_u32(house_id);
+            }
+            // i den ordning de ska läggas på tilen igen: down items läggs
+            // alltid överst, så de skrivs baklänges
+            let items: Vec<&Item> = tile
+                .ground()
+                .into_iter()
+                .chain(tile.top_items())
+                .chain(tile.down_items().iter().rev())
+                .collect();
+            stream.write_u16(items.len() as u16);
+            for item in items {
+                write_item(stream, item);
+            }
+        }
+
+        stream.write_u32(world.houses.len() as u32);
+        for house in world.houses.iter() {
+            stream.write_u32(house.id);
+            stream.write_string(&house.name);
+            write_position(stream, house.entry);
+            stream.write_u32(house.rent);
+            stream.write_u32(house.town_id);
+        }
+
+        stream.write_u32(world.spawns.len() as u32);
+        for spawn in world.spawns.iter() {
+            write_position(stream, spawn.center);
+            stream.write_i32(spawn.radius);
+            stream.write_u32(spawn.blocks.len() as u32);
+            for block in &spawn.blocks {
+                stream.write_string(&block.name);
+                stream.write_u8(match block.kind {
+                    SpawnKind::Monster => 0,
+                    SpawnKind::Npc => 1,
+                });
+                write_position(stream, block.position);
+                stream.write_u8(block.direction as u8);
+                stream.write_u64(block.interval_ms);
+            }
+        }
+    }
+
+    fn read_world(world: &mut World, stream: &mut PropStream) -> Option<MapInfo> {
+        let (width, height) = (stream.read_u16()?, stream.read_u16()?);
+        let mut info = MapInfo {
+            spawn_file: read_opt_path(stream)?,
+            house_file: read_opt_path(stream)?,
+            ..Default::default()
+        };
+        for _ in 0..stream.read_u16()? {
+            info.description.push(stream.read_string()?);
+        }
+
+        for _ in 0..stream.read_u32()? {
+            let id = stream.read_u32()?;
+            let name = stream.read_string()?;
+            let temple_position = read_position(stream)?;
+            world.towns.add_town(Town { id, name, temple_position });
+        }
+
+        for _ in 0..stream.read_u32()? {
+            let name = stream.read_string()?;
+            world.map.waypoints.insert(name, read_position(stream)?);
+        }
+
+        let tile_count = stream.read_u32()?;
+        world.map.reserve(tile_count as usize);
+        for _ in 0..tile_count {
+            let mut tile = Tile::new(read_position(stream)?);
+            let bits = stream.read_u8()?;
+            if bits & 1 != 0 {
+                tile.set_zone_flag(TileFlags(stream.read_u32()? & TileFlags::ZONE_MASK.0));
+            }
+            let house_id = if bits & 2 != 0 { stream.read_u32()? } else { 0 };
+            for _ in 0..stream.read_u16()? {
+                tile.add_thing(Thing::Item(Item::unserialize(stream)?));
+            }
+            let pos = tile.position;
+            world.map.set_tile(tile);
+            if house_id != 0 {
+                world.add_house_tile(house_id, pos);
+            }
+        }
+
+        let config = world.houses.config;
+        for _ in 0..stream.read_u32()? {
+            let house = world.houses.add_house(stream.read_u32()?);
+            house.name = stream.read_string()?;
+            house.entry = read_position(stream)?;
+            house.rent = stream.read_u32()?;
+            house.town_id = stream.read_u32()?;
+            house.set_owner(None, &config, common::unix_time());
+        }
+
+        for _ in 0..stream.read_u32()? {
+            let mut spawn = Spawn::new(read_position(stream)?, stream.read_i32()?);
+            for _ in 0..stream.read_u32()? {
+                let name = stream.read_string()?;
+                let kind = match stream.read_u8()? {
+                    0 => SpawnKind::Monster,
+                    _ => SpawnKind::Npc,
+                };
+                let position = read_position(stream)?;
+                let direction = Direction::from_u8(stream.read_u8()?)?;
+                let interval_ms = stream.read_u64()?;
+                spawn.add_block(SpawnBlock { name, kind, position, direction, interval_ms, last_spawn: 0 });
+            }
+            world.spawns.add(spawn);
+        }
+
+        world.map.width = width;
+        world.map.height = height;
+        Some(info)
+    }
+}
+
+/// Som `Item::serialize`, plus de attribut som bara kartan har (unique id,
+/// action id på fasta items, dörr- och depå-id) och som inte sparas i databasen
+fn write_item(stream: &mut PropWriteStream, item: &Item) {
+    stream.write_u16(item.id);
+    item.serialize_attr(stream);
+
+    let a = item.attributes();
+    if !item.item_type().moveable && a.action_id != 0 {
+        stream.write_u8(attr::ACTION_ID);
+        stream.write_u16(a.action_id);
+    }
+    if a.unique_id != 0 {
+        stream.write_u8(attr::UNIQUE_ID);
+        stream.write_u16(a.unique_id);
+    }
+    if a.door_id != 0 {
+        stream.write_u8(attr::HOUSEDOORID);
+        stream.write_u8(a.door_id);
+    }
+    if a.depot_id != 0 {
+        stream.write_u8(attr::DEPOT_ID);
+        stream.write_u16(a.depot_id);
+    }
+
+    if let Some(container) = item.get_container() {
+        stream.write_u8(attr::CONTAINER_ITEMS);
+        stream.write_u32(container.size() as u32);
+        for child in container.items().iter().rev() {
+            write_item(stream, child);
+        }
+    }
+    stream.write_u8(attr::END);
+}
+
+fn write_position(stream: &mut PropWriteStream, pos: Position) {
+    stream.write_u16(pos.x);
+    stream.write_u16(pos.y);
+    stream.write_u8(pos.z);
+}
+
+fn read_position(stream: &mut PropStream) -> Option<Position> {
+    Some(Position::new(stream.read_u16()?, stream.read_u16()?, stream.read_u8()?))
+}
+
+fn write_opt_path(stream: &mut PropWriteStream, path: Option<&Path>) {
+    stream.write_string(&path.map(|p| p.to_string_lossy()).unwrap_or_default());
+}
+
+fn read_opt_path(stream: &mut PropStream) -> Option<Option<PathBuf>> {
+    let path = stream.read_string()?;
+    Some((!path.is_empty()).then(|| PathBuf::from(path)))
+}
+
+/// FNV-1a över hela filen. Räcker för att se om en källfil ändrats.
+fn hash_file(path: &Path) -> Option<u64> {
+    let data = std::fs::read(path).ok()?;
+    Some(data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tile::tests::{install_items, BAG, BORDER, GROUND, SPLASH};
+
+    fn sample_world() -> World {
+        let mut world = World::new();
+        world.towns.add_town(Town { id: 1, name: "Thais".into(), temple_position: Position::new(100, 100, 7) });
+        world.map.waypoints.insert("temple".into(), Position::new(100, 101, 7));
+
+        // count sparas bara för items där den betyder något, som splashens vätska
+        let mut tile = Tile::new(Position::new(100, 100, 7));
+        let mut ground = Item::new(GROUND, 0);
+        ground.attributes_mut().unique_id = 1000;
+        tile.add_thing(Thing::Item(ground));
+        tile.add_thing(Thing::Item(Item::new(BORDER, 0)));
+        tile.add_thing(Thing::Item(Item::new(SPLASH, 1)));
+        let mut bag = Item::new(BAG, 0);
+        bag.attributes_mut().text = Some("hej".into());
+        tile.add_thing(Thing::Item(bag));
+        tile.set_zone_flag(TileFlags::PROTECTIONZONE);
+        world.map.set_tile(tile);
+
+        let house = world.houses.add_house(7);
+        house.name = "Villa".into();
+        house.entry = Position::new(100, 100, 7);
+        house.rent = 500;
+        house.town_id = 1;
+        world.add_house_tile(7, Position::new(100, 100, 7));
+
+        let mut spawn = Spawn::new(Position::new(110, 110, 7), 3);
+        spawn.add_block(SpawnBlock {
+            name: "Rat".into(),
+            kind: SpawnKind::Monster,
+            position: Position::new(111, 110, 7),
+            direction: Direction::South,
+            interval_ms: 60_000,
+            last_spawn: 0,
+        });
+        world.spawns.add(spawn);
+        world
+    }
+
+    #[test]
+    fn round_trip_restores_world() {
+        install_items();
+        let dir = std::env::temp_dir().join(format!("mapcache-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (map_path, cache_path) = (dir.join("test.otbm"), dir.join("test.otbm.cache"));
+        std::fs::write(&map_path, b"OTBM").unwrap();
+
+        let world = sample_world();
+        let info = MapInfo { description: vec!["Testkarta".into()], ..Default::default() };
+        MapCache::save(&world, &info, &map_path, &cache_path).unwrap();
+
+        let mut loaded = World::new();
+        let loaded_info = MapCache::load(&mut loaded, &map_path, &cache_path).unwrap().expect("fresh cache");
+        assert_eq!(loaded_info.description, info.description);
+        assert_eq!(loaded.towns.get_town(1), world.towns.get_town(1));
+        assert_eq!(loaded.map.waypoints.get("temple"), Some(&Position::new(100, 101, 7)));
+
+        let pos = Position::new(100, 100, 7);
+        let (tile, original) = (loaded.map.get_tile(&pos).unwrap(), world.map.get_tile(&pos).unwrap());
+        assert_eq!(tile.items().collect::<Vec<_>>(), original.items().collect::<Vec<_>>());
+        assert!(tile.is_protection_zone());
+        assert_eq!(tile.house_id(), 7);
+
+        let house = loaded.houses.get_house(7).unwrap();
+        assert_eq!((house.name.as_str(), house.rent, house.town_id), ("Villa", 500, 1));
+        assert_eq!(house.tiles(), &[pos]);
+
+        let spawn = loaded.spawns.iter().next().unwrap();
+        assert_eq!(spawn.blocks[0].name, "Rat");
+        assert_eq!(spawn.blocks[0].interval_ms, 60_000);
+
+        // en ändrad karta gör cachen inaktuell
+        std::fs::write(&map_path, b"OTBM2").unwrap();
+        assert!(MapCache::load(&mut World::new(), &map_path, &cache_path).unwrap().is_none());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,360 @@
+//! A* över tile-modellen. Motsvarar `Map::getPathMatching`, `AStarNodes` och
+//! `FrozenPathingConditionCall` i TFS, men med en heuristik mot målet i
+//! stället för ren Dijkstra.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use common::{Direction, Position};
+
+use crate::map::Map;
+use crate::tile::{Tile, TileFlags};
+
+pub const MAP_NORMALWALKCOST: i32 = 10;
+pub const MAP_DIAGONALWALKCOST: i32 = 25;
+
+/// Max antal noder i en sökning, som `MAX_NODES` i TFS
+pub const MAX_NODES: usize = 512;
+
+/// Utan `max_search_dist` avbryts sökningen efter så många stängda noder
+const MAX_CLOSED_NODES_UNBOUNDED: usize = 100;
+
+/// Motsvarar `FindPathParams` i TFS
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FindPathParams {
+    /// Sök åt alla håll runt målet, inte bara på startens sida
+    pub full_path_search: bool,
+    /// Slutrutan måste ha fri sikt till målet
+    pub clear_sight: bool,
+    pub allow_diagonal: bool,
+    /// Lämna aldrig det tillåtna avståndet till målet under vägen
+    pub keep_distance: bool,
+    /// 0 = obegränsat (men högst 100 stängda noder)
+    pub max_search_dist: i32,
+    pub min_target_dist: i32,
+    pub max_target_dist: i32,
+    pub max_nodes: usize,
+}
+
+impl Default for FindPathParams {
+    fn default() -> Self {
+        Self {
+            full_path_search: true,
+            clear_sight: true,
+            allow_diagonal: true,
+            keep_distance: false,
+            max_search_dist: 0,
+            min_target_dist: -1,
+            max_target_dist: -1,
+            max_nodes: MAX_NODES,
+        }
+    }
+}
+
+impl FindPathParams {
+    /// Ända fram till positionen
+    pub fn exact() -> Self {
+        Self {
+            min_target_dist: 0,
+            max_target_dist: 0,
+            ..Self::default()
+        }
+    }
+
+    /// Till en ruta bredvid målet, t.ex. för att använda ett item eller
+    /// följa en varelse
+    pub fn adjacent() -> Self {
+        Self {
+            min_target_dist: 1,
+            max_target_dist: 1,
+            ..Self::default()
+        }
+    }
+}
+
+/// Det pathfindern behöver veta om varelsen som går
+pub trait PathWalker {
+    /// Kan varelsen gå igenom varelsen `id`? Annars blockerar den rutan.
+    fn can_walk_through(&self, _id: u32) -> bool {
+        false
+    }
+
+    /// Varelsespecifika regler, t.ex. skyddszoner för monster eller hus
+    /// spelaren inte är inbjuden till
+    fn can_walk_on(&self, _tile: &Tile) -> bool {
+        true
+    }
+
+    /// Extra kostnad för rutan, t.ex. magiska fält varelsen inte är immun mot
+    fn tile_cost(&self, _tile: &Tile) -> i32 {
+        0
+    }
+}
+
+/// En varelse utan särskilda regler
+impl PathWalker for () {}
+
+/// Kan `walker` gå till rutan när vi söker väg? Motsvarar `Map::canWalkTo`
+/// med FLAG_PATHFINDING.
+pub fn can_walk_to(tile: &Tile, walker: &dyn PathWalker) -> bool {
+    if tile.blocks_path() || tile.has_flag(TileFlags::FLOORCHANGE) || tile.is_teleport() {
+        return false;
+    }
+    if tile.creatures().iter().any(|&id| !walker.can_walk_through(id)) {
+        return false;
+    }
+    walker.can_walk_on(tile)
+}
+
+/// Motsvarar `AStarNodes::getTileWalkCost`
+fn tile_walk_cost(tile: &Tile, walker: &dyn PathWalker) -> i32 {
+    let mut cost = walker.tile_cost(tile);
+    if !tile.creatures().is_empty() {
+        // varelsen i vägen måste knuffas eller dödas
+        cost += MAP_NORMALWALKCOST * 3;
+    }
+    cost
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Node {
+    x: u16,
+    y: u16,
+    parent: Option<usize>,
+    g: i32,
+    closed: bool,
+}
+
+/// Återanvändbara buffertar för sökningar; en instans per tråd räcker
+#[derive(Debug, Default)]
+pub struct PathFinder {
+    nodes: Vec<Node>,
+    lookup: HashMap<(u16, u16), usize>,
+    open: BinaryHeap<Reverse<(i32, i32, usize)>>,
+}
+
+impl PathFinder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Väg från `start` till `target` enligt `params`. Returnerar stegen i
+    /// ordning, tom om vi redan står rätt. Motsvarar `Creature::getPathTo`.
+    pub fn find_path(
+        &mut self,
+        map: &Map,
+        start: Position,
+        target: Position,
+        walker: &dyn PathWalker,
+        params: &FindPathParams,
+    ) -> Option<Vec<Direction>> {
+        if start.z != target.z {
+            return None;
+        }
+
+        self.nodes.clear();
+        self.lookup.clear();
+        self.open.clear();
+        self.push_node(start.x, start.y, None, 0, 0);
+
+        let mut best_match = 0;
+        let mut found = None;
+        let mut closed = 0;
+
+        while params.max_search_dist != 0 || closed < MAX_CLOSED_NODES_UNBOUNDED {
+            let Some(index) = self.pop_best() else {
+                break;
+            };
+            let node = self.nodes[index];
+            let pos = Position::new(node.x, node.y, start.z);
+
+            if matches_target(start, pos, target, map, params, &mut best_match) {
+                found = Some(index);
+                if best_match == 0 {
+                    break;
+                }
+            }
+
+            let directions: &[Direction] = if params.allow_diagonal { &Direction::ALL } else { &Direction::ALL[..4] };
+            for &dir in directions {
+                let Some(next) = pos.get_next_position(dir) else {
+                    continue;
+                };
+                if params.max_search_dist != 0
+                    && (Position::get_distance_x(&start, &next) > params.max_search_dist
+                        || Position::get_distance_y(&start, &next) > params.max_search_dist)
+                {
+                    continue;
+                }
+                if params.keep_distance && !is_in_range(start, next, target, params) {
+                    continue;
+                }
+
+                let existing = self.lookup.get(&(next.x, next.y)).copied();
+                if existing.is_some_and(|i| self.nodes[i].closed) {
+                    continue;
+                }
+                let Some(tile) = map.get_tile(&next) else {
+                    continue;
+                };
+                if existing.is_none() && !can_walk_to(tile, walker) {
+                    continue;
+                }
+
+                let step = if dir.is_diagonal() { MAP_DIAGONALWALKCOST } else { MAP_NORMALWALKCOST };
+                let g = node.g + step + tile_walk_cost(tile, walker);
+                let h = heuristic(next, target, params);
+
+                match existing {
+                    Some(i) => {
+                        if self.nodes[i].g <= g {
+                            continue;
+                        }
+                        self.nodes[i].g = g;
+                        self.nodes[i].parent = Some(index);
+                        self.open.push(Reverse((g + h, h, i)));
+                    }
+                    None => {
+                        if self.nodes.len() >= params.max_nodes {
+                            continue;
+                        }
+                        self.push_node(next.x, next.y, Some(index), g, h);
+                    }
+                }
+            }
+
+            self.nodes[index].closed = true;
+            closed += 1;
+        }
+
+        let mut index = found?;
+        let mut path = Vec::new();
+        while let Some(parent) = self.nodes[index].parent {
+            let (from, to) = (self.nodes[parent], self.nodes[index]);
+            let from = Position::new(from.x, from.y, start.z);
+            path.push(from.get_direction_to(&Position::new(to.x, to.y, start.z)));
+            index = parent;
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    fn push_node(&mut self, x: u16, y: u16, parent: Option<usize>, g: i32, h: i32) {
+        let index = self.nodes.len();
+        self.nodes.push(Node { x, y, parent, g, closed: false });
+        self.lookup.insert((x, y), index);
+        // lika f: ta noden närmast målet först
+        self.open.push(Reverse((g + h, h, index)));
+    }
+
+    /// Billigaste öppna noden; gamla poster för noder som fått lägre
+    /// kostnad sedan de lades in hoppas över
+    fn pop_best(&mut self) -> Option<usize> {
+        while let Some(Reverse((_, _, index))) = self.open.pop() {
+            if !self.nodes[index].closed {
+                return Some(index);
+            }
+        }
+        None
+    }
+}
+
+/// Lägsta möjliga kostnad kvar. Ett diagonalsteg kostar mer än två raka,
+/// så manhattan-avståndet till målområdet gånger en rak gång underskattar aldrig.
+fn heuristic(pos: Position, target: Position, params: &FindPathParams) -> i32 {
+    let reach = params.max_target_dist.max(0);
+    let dx = (Position::get_distance_x(&pos, &target) - reach).max(0);
+    let dy = (Position::get_distance_y(&pos, &target) - reach).max(0);
+    (dx + dy) * MAP_NORMALWALKCOST
+}
+
+/// Motsvarar `FrozenPathingConditionCall::isInRange`
+fn is_in_range(start: Position, test: Position, target: Position, params: &FindPathParams) -> bool {
+    let max = params.max_target_dist;
+    let (tx, ty) = (target.x as i32, target.y as i32);
+    let (x, y) = (test.x as i32, test.y as i32);
+
+    if params.full_path_search {
+        return x <= tx + max && x >= tx - max && y <= ty + max && y >= ty - max;
+    }
+
+    // bara på den sida av målet där vi startade
+    let dx = Position::get_offset_x(&start, &target);
+    let dy = Position::get_offset_y(&start, &target);
+    let x_ok = if dx <= 0 { x <= tx + max && x >= tx } else { x <= tx && x >= tx - max };
+    let y_ok = if dy <= 0 { y <= ty + max && y >= ty } else { y <= ty && y >= ty - max };
+    x_ok && y_ok
+}
+
+/// Motsvarar `FrozenPathingConditionCall::operator()`. `best_match` sätts
+/// till 0 när en perfekt ruta hittats.
+fn matches_target(
+    start: Position,
+    test: Position,
+    target: Position,
+    map: &Map,
+    params: &FindPathParams,
+    best_match: &mut i32,
+) -> bool {
+    if !is_in_range(start, test, target, params) {
+        return false;
+    }
+    if params.clear_sight && !map.is_sight_clear(test, target, true) {
+        return false;
+    }
+
+    let dist = Position::get_distance(&test, &target);
+    if params.max_target_dist == 1 {
+        return dist >= params.min_target_dist && dist <= params.max_target_dist;
+    }
+    if dist > params.max_target_dist || dist < params.min_target_dist {
+        return false;
+    }
+    if dist == params.max_target_dist {
+        *best_match = 0;
+        return true;
+    }
+    if dist > *best_match {
+        *best_match = dist;
+        return true;
+    }
+    false
+}
+
+/// Cache att fråga om samma väg flera gånger, t.ex. ett monster som tänker
+/// varje sekund medan inget runt det rör sig. Töms när kartan ändrats eller
+/// en varelse flyttat sig, eftersom varelser i vägen ändrar vägen.
+#[derive(Debug, Default)]
+pub struct PathCache {
+    /// `Map::revision` och `SpectatorIndex::revision`
+    revision: (u64, u64),
+    entries: HashMap<(u32, Position, Position, FindPathParams), Option<Vec<Direction>>>,
+}
+
+/// Fler sparade vägar än så och cachen töms, så att den inte växer i det oändliga
+const MAX_CACHED_PATHS: usize = 4096;
+
+impl PathCache {
+    pub fn get(
+        &mut self,
+        revision: (u64, u64),
+        key: &(u32, Position, Position, FindPathParams),
+    ) -> Option<&Option<Vec<Direction>>> {
+        if self.revision != revision {
+            self.entries.clear();
+            self.revision = revision;
+        }
+        self.entries.get(key)
+    }
+
+    pub fn insert(&mut self, key: (u32, Position, Position, FindPathParams), path: Option<Vec<Direction>>) {
+        if self.entries.len() >= MAX_CACHED_PATHS {
+            self.entries.clear();
+        }
+        self.entries.insert(key, path);
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
@@ -0,0 +1,320 @@
+//! Spawns från `forgotten-spawn.xml`. Motsvarar `Spawn`/`Spawns` i TFS.
+//!
+//! Själva varelserna skapas via `CreatureFactory` eftersom monster och
+//! NPC:er bor i andra moduler; spawnen håller bara reda på id:n.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use common::{Config, Direction, Error, Position, Result};
+use common::tracing::warn;
+use timewheel::TimerHandle;
+
+/// Kortaste/längsta tillåtna spawntime, samma gränser som TFS
+pub const MINSPAWN_INTERVAL: u64 = 10 * 1000;
+pub const MAXSPAWN_INTERVAL: u64 = 24 * 60 * 60 * 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpawnKind {
+    Monster,
+    Npc,
+}
+
+/// Skapar och släpper varelser åt spawnsystemet
+pub trait CreatureFactory {
+    fn create_monster(&mut self, name: &str, pos: Position, dir: Direction) -> Option<u32>;
+    fn create_npc(&mut self, name: &str, pos: Position, dir: Direction) -> Option<u32>;
+    /// Varelsen har tagits bort från kartan och kan släppas
+    fn release_creature(&mut self, id: u32);
+
+    /// Blockerar varelsen rutan den står på?
+    fn creature_blocks(&self, _id: u32) -> bool {
+        true
+    }
+
+    /// Spelare med flaggan IgnoredByMonsters hindrar inte respawn
+    fn ignored_by_monsters(&self, _player_id: u32) -> bool {
+        false
+    }
+}
+
+/// Konfigvärden som styr respawn och despawn
+#[derive(Debug, Clone, Copy)]
+pub struct SpawnConfig {
+    /// Max antal varelser som skapas per check (rateSpawn)
+    pub rate_spawn: u32,
+    pub despawn_range: i32,
+    pub despawn_radius: i32,
+    pub remove_on_despawn: bool,
+    pub walk_to_spawn_radius: i32,
+}
+
+impl Default for SpawnConfig {
+    fn default() -> Self {
+        Self {
+            rate_spawn: 1,
+            despawn_range: 2,
+            despawn_radius: 50,
+            remove_on_despawn: true,
+            walk_to_spawn_radius: 15,
+        }
+    }
+}
+
+impl SpawnConfig {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            rate_spawn: config.rate_spawn.max(1) as u32,
+            despawn_range: config.despawn_range,
+            despawn_radius: config.despawn_radius,
+            remove_on_despawn: config.remove_on_despawn,
+            walk_to_spawn_radius: config.walk_to_spawn_radius,
+        }
+    }
+
+    /// Motsvarar `Spawns::isInZone` + våningskollen i `Monster::despawn`.
+    /// 0 betyder att gränsen är avstängd.
+    pub fn is_in_spawn_range(&self, master: Position, pos: Position) -> bool {
+        if self.despawn_radius == 0 {
+            return true;
+        }
+        let radius = self.despawn_radius.max(0);
+        if !Position::are_in_range(&master, &pos, radius, radius, i32::MAX) {
+            return false;
+        }
+        if self.despawn_range == 0 {
+            return true;
+        }
+        Position::get_distance_z(&master, &pos) <= self.despawn_range
+    }
+
+    /// Har monstret gått för långt från sin spawn? (walkToSpawnRadius)
+    pub fn should_walk_back(&self, master: Position, pos: Position) -> bool {
+        if self.walk_to_spawn_radius <= 0 {
+            return false;
+        }
+        master.z == pos.z && Position::get_distance(&master, &pos) > self.walk_to_spawn_radius
+    }
+}
+
+/// En `<monster>`/`<npc>` i en spawn. Motsvarar `spawnBlock_t`.
+#[derive(Debug, Clone)]
+pub struct SpawnBlock {
+    pub name: String,
+    pub kind: SpawnKind,
+    pub position: Position,
+    pub direction: Direction,
+    pub interval_ms: u64,
+    pub last_spawn: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Spawn {
+    pub center: Position,
+    /// -1 = ingen gräns
+    pub radius: i32,
+    pub blocks: Vec<SpawnBlock>,
+    /// block-index -> varelse-id
+    pub(crate) spawned: HashMap<usize, u32>,
+    /// Kortaste intervallet bland blocken, styr hur ofta `check_spawn` körs
+    pub interval: u64,
+    pub(crate) check_event: Option<TimerHandle>,
+}
+
+impl Spawn {
+    pub fn new(center: Position, radius: i32) -> Self {
+        Self {
+            center,
+            radius,
+            blocks: Vec::new(),
+            spawned: HashMap::new(),
+            interval: MAXSPAWN_INTERVAL,
+            check_event: None,
+        }
+    }
+
+    pub fn add_block(&mut self, block: SpawnBlock) {
+        if block.kind == SpawnKind::Monster {
+            self.interval = self.interval.min(block.interval_ms);
+        }
+        self.blocks.push(block);
+    }
+
+    pub fn spawned_count(&self) -> usize {
+        self.spawned.len()
+    }
+
+    pub fn is_spawned(&self, block: usize) -> bool {
+        self.spawned.contains_key(&block)
+    }
+
+    /// Motsvarar `Spawn::isInSpawnZone`
+    pub fn is_in_spawn_zone(&self, pos: Position) -> bool {
+        if self.radius < 0 {
+            return true;
+        }
+        Position::are_in_range(&self.center, &pos, self.radius, self.radius, i32::MAX)
+    }
+
+    /// Finns det monsterblock som saknar varelse? NPC:er respawnar aldrig.
+    pub fn needs_respawn(&self) -> bool {
+        self.blocks
+            .iter()
+            .enumerate()
+            .any(|(i, b)| b.kind == SpawnKind::Monster && !self.is_spawned(i))
+    }
+}
+
+#[derive(Default)]
+pub struct Spawns {
+    pub config: SpawnConfig,
+    spawns: Vec<Spawn>,
+    /// varelse-id -> (spawn, block)
+    owners: HashMap<u32, (usize, usize)>,
+    started: bool,
+}
+
+impl Spawns {
+    pub fn new(config: SpawnConfig) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.spawns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.spawns.is_empty()
+    }
+
+    pub fn is_started(&self) -> bool {
+        self.started
+    }
+
+    pub(crate) fn set_started(&mut self) {
+        self.started = true;
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Spawn> {
+        self.spawns.get(index)
+    }
+
+    pub(crate) fn get_mut(&mut self, index: usize) -> Option<&mut Spawn> {
+        self.spawns.get_mut(index)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Spawn> {
+        self.spawns.iter()
+    }
+
+    pub fn add(&mut self, spawn: Spawn) -> usize {
+        self.spawns.push(spawn);
+        self.spawns.len() - 1
+    }
+
+    /// Vilken spawn och vilket block varelsen kom från
+    pub fn owner_of(&self, creature_id: u32) -> Option<(usize, usize)> {
+        self.owners.get(&creature_id).copied()
+    }
+
+    /// Spawnens mittpunkt för varelsen (masterPos i TFS)
+    pub fn master_position(&self, creature_id: u32) -> Option<Position> {
+        let (spawn, block) = self.owner_of(creature_id)?;
+        Some(self.spawns[spawn].blocks[block].position)
+    }
+
+    pub(crate) fn register(&mut self, spawn: usize, block: usize, creature_id: u32) {
+        self.spawns[spawn].spawned.insert(block, creature_id);
+        self.owners.insert(creature_id, (spawn, block));
+    }
+
+    /// Varelsen är borta (död, despawn); blocket får respawna efter sitt intervall.
+    /// Motsvarar `Spawn::removeMonster`.
+    pub(crate) fn unregister(&mut self, creature_id: u32, now: u64) -> Option<usize> {
+        let (spawn, block) = self.owners.remove(&creature_id)?;
+        let s = &mut self.spawns[spawn];
+        s.spawned.remove(&block);
+        s.blocks[block].last_spawn = now;
+        Some(spawn)
+    }
+
+    /// Läs in spawns från XML. Motsvarar `Spawns::loadFromXml`.
+    pub fn load_from_xml(&mut self, path: impl AsRef<Path>) -> Result<usize> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| Error::World(format!("Cannot open {}: {e}", path.display())))?;
+        let doc = roxmltree::Document::parse(&text)
+            .map_err(|e| Error::World(format!("Cannot parse {}: {e}", path.display())))?;
+
+        let before = self.spawns.len();
+        for node in doc.root_element().children().filter(|n| n.has_tag_name("spawn")) {
+            let Some(center) = parse_center(&node) else {
+                warn!("[Spawns::load_from_xml] Missing center position in {}", path.display());
+                continue;
+            };
+            let radius = node.attribute("radius").and_then(|v| v.parse().ok()).unwrap_or(-1);
+            let mut spawn = Spawn::new(center, radius);
+
+            for child in node.children().filter(|n| n.is_element()) {
+                let kind = match child.tag_name().name() {
+                    "monster" => SpawnKind::Monster,
+                    "npc" => SpawnKind::Npc,
+                    _ => continue,
+                };
+                let Some(name) = child.attribute("name") else {
+                    continue;
+                };
+                let x: i32 = attr(&child, "x").unwrap_or(0);
+                let y: i32 = attr(&child, "y").unwrap_or(0);
+                let Some(position) = center.translated(x, y, 0) else {
+                    warn!("[Spawns::load_from_xml] Invalid position for {name} near {center}");
+                    continue;
+                };
+                let direction = attr::<u8>(&child, "direction")
+                    .and_then(Direction::from_u8)
+                    .unwrap_or(Direction::North);
+                let interval_ms = attr::<u64>(&child, "spawntime").unwrap_or(0) * 1000;
+
+                if kind == SpawnKind::Monster
+                    && !(MINSPAWN_INTERVAL..=MAXSPAWN_INTERVAL).contains(&interval_ms)
+                {
+                    warn!(
+                        "[Spawns::load_from_xml] {name} {position} spawntime can not be less than {} seconds or more than {} seconds",
+                        MINSPAWN_INTERVAL / 1000,
+                        MAXSPAWN_INTERVAL / 1000
+                    );
+                    continue;
+                }
+
+                spawn.add_block(SpawnBlock {
+                    name: name.to_string(),
+                    kind,
+                    position,
+                    direction,
+                    interval_ms,
+                    last_spawn: 0,
+                });
+            }
+
+            if !spawn.blocks.is_empty() {
+                self.spawns.push(spawn);
+            }
+        }
+        Ok(self.spawns.len() - before)
+    }
+}
+
+fn attr<T: std::str::FromStr>(node: &roxmltree::Node, name: &str) -> Option<T> {
+    node.attribute(name).and_then(|v| v.parse().ok())
+}
+
+fn parse_center(node: &roxmltree::Node) -> Option<Position> {
+    Some(Position::new(
+        attr(node, "centerx")?,
+        attr(node, "centery")?,
+        attr(node, "centerz")?,
+    ))
+}
@@ -0,0 +1,419 @@
+//! Spatialt index över varelser, motsvarar QTree-löven + `getSpectators` i TFS.
+//!
+//! Kartan delas in i sektorer om `SECTOR_SIZE`x`SECTOR_SIZE` rutor per våning.
+//! En fråga "vem ser position P" besöker bara sektorerna som täcks av vyn
+//! istället för att loopa över alla varelser.
+
+use std::collections::HashMap;
+
+use common::position::MAP_MAX_LAYERS;
+use common::Position;
+
+pub const SECTOR_SIZE: u16 = 16;
+
+pub const MAX_VIEWPORT_X: i32 = 11;
+pub const MAX_VIEWPORT_Y: i32 = 11;
+pub const MAX_CLIENT_VIEWPORT_X: i32 = 8;
+pub const MAX_CLIENT_VIEWPORT_Y: i32 = 6;
+
+type SectorKey = (u16, u16, u8);
+
+fn sector_key(pos: &Position) -> SectorKey {
+    (pos.x / SECTOR_SIZE, pos.y / SECTOR_SIZE, pos.z)
+}
+
+#[derive(Default)]
+struct Sector {
+    creatures: Vec<u32>,
+    players: Vec<u32>,
+}
+
+impl Sector {
+    fn remove(&mut self, id: u32, is_player: bool) {
+        self.creatures.retain(|&c| c != id);
+        if is_player {
+            self.players.retain(|&c| c != id);
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.creatures.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    position: Position,
+    is_player: bool,
+}
+
+/// Parametrar till en spectator-fråga, samma semantik som TFS `getSpectators`.
+/// Ranger som är 0 betyder standard-viewport (och bara då används cachen).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SpectatorQuery {
+    pub center: Position,
+    pub multifloor: bool,
+    pub only_players: bool,
+    pub min_range_x: i32,
+    pub max_range_x: i32,
+    pub min_range_y: i32,
+    pub max_range_y: i32,
+}
+
+impl SpectatorQuery {
+    pub fn new(center: Position) -> Self {
+        Self { center, ..Default::default() }
+    }
+
+    pub fn multifloor(mut self, value: bool) -> Self {
+        self.multifloor = value;
+        self
+    }
+
+    pub fn only_players(mut self, value: bool) -> Self {
+        self.only_players = value;
+        self
+    }
+
+    pub fn range(mut self, range_x: i32, range_y: i32) -> Self {
+        self.min_range_x = range_x;
+        self.max_range_x = range_x;
+        self.min_range_y = range_y;
+        self.max_range_y = range_y;
+        self
+    }
+
+    fn is_default_range(&self) -> bool {
+        self.min_range_x == 0 && self.max_range_x == 0 && self.min_range_y == 0 && self.max_range_y == 0
+    }
+}
+
+/// Vilka våningar som syns från `z` (klientens regler för över/under jord)
+pub fn floor_range(z: u8, multifloor: bool) -> (u8, u8) {
+    if !multifloor {
+        return (z, z);
+    }
+    match z {
+        // under jord: två våningar upp och ner
+        8.. => (z.saturating_sub(2), (z + 2).min(MAP_MAX_LAYERS - 1)),
+        6 => (0, 8),
+        7 => (0, 9),
+        _ => (0, 7),
+    }
+}
+
+/// Motsvarar `Creature::canSee` i TFS
+pub fn can_see(viewer: &Position, pos: &Position, view_range_x: i32, view_range_y: i32) -> bool {
+    if viewer.z <= 7 {
+        // ovan jord ser vi aldrig ner under jord
+        if pos.z > 7 {
+            return false;
+        }
+    } else if Position::get_distance_z(viewer, pos) > 2 {
+        return false;
+    }
+
+    let offset_z = Position::get_offset_z(viewer, pos);
+    let (x, y) = (pos.x as i32, pos.y as i32);
+    let (vx, vy) = (viewer.x as i32, viewer.y as i32);
+    x >= vx - view_range_x + offset_z
+        && x <= vx + view_range_x + offset_z
+        && y >= vy - view_range_y + offset_z
+        && y <= vy + view_range_y + offset_z
+}
+
+/// Kan en spelare på `viewer` se `pos` i klienten?
+pub fn client_can_see(viewer: &Position, pos: &Position) -> bool {
+    can_see(viewer, pos, MAX_CLIENT_VIEWPORT_X + 1, MAX_CLIENT_VIEWPORT_Y + 1)
+}
+
+type CacheKey = (Position, bool, bool);
+
+/// Sektorbaserat index + cache för standardfrågor.
+///
+/// Cachen lever inom en tick: den rensas av `new_tick` och selektivt när en
+/// varelse rör sig inom räckhåll för en cachad position.
+#[derive(Default)]
+pub struct SpectatorIndex {
+    sectors: HashMap<SectorKey, Sector>,
+    entries: HashMap<u32, Entry>,
+    cache: HashMap<CacheKey, Vec<u32>>,
+    /// Räknas upp varje gång en varelse läggs till, tas bort eller flyttas
+    revision: u64,
+}
+
+impl SpectatorIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn contains(&self, id: u32) -> bool {
+        self.entries.contains_key(&id)
+    }
+
+    pub fn position_of(&self, id: u32) -> Option<Position> {
+        self.entries.get(&id).map(|e| e.position)
+    }
+
+    pub fn is_player(&self, id: u32) -> bool {
+        self.entries.get(&id).is_some_and(|e| e.is_player)
+    }
+
+    /// Alla inloggade spelare
+    pub fn players(&self) -> impl Iterator<Item = u32> + '_ {
+        self.entries.iter().filter(|(_, e)| e.is_player).map(|(&id, _)| id)
+    }
+
+    /// Lägg till en varelse (login, spawn, summon)
+    pub fn insert(&mut self, id: u32, position: Position, is_player: bool) {
+        if self.entries.contains_key(&id) {
+            self.move_creature(id, position);
+            return;
+        }
+
+        self.entries.insert(id, Entry { position, is_player });
+        self.revision += 1;
+        let sector = self.sectors.entry(sector_key(&position)).or_default();
+        sector.creatures.push(id);
+        if is_player {
+            sector.players.push(id);
+        }
+        self.invalidate_around(&position);
+    }
+
+    pub fn remove(&mut self, id: u32) -> bool {
+        let Some(entry) = self.entries.remove(&id) else {
+            return false;
+        };
+        self.remove_from_sector(id, &entry);
+        self.invalidate_around(&entry.position);
+        self.revision += 1;
+        true
+    }
+
+    /// Flytta en varelse. Gäller både vanliga steg, teleport och våningsbyte.
+    pub fn move_creature(&mut self, id: u32, new_pos: Position) -> bool {
+        let Some(entry) = self.entries.get(&id).copied() else {
+            return false;
+        };
+        let old_pos = entry.position;
+        if old_pos == new_pos {
+            return true;
+        }
+
+        if sector_key(&old_pos) != sector_key(&new_pos) {
+            self.remove_from_sector(id, &entry);
+            let sector = self.sectors.entry(sector_key(&new_pos)).or_default();
+            sector.creatures.push(id);
+            if entry.is_player {
+                sector.players.push(id);
+            }
+        }
+
+        if let Some(e) = self.entries.get_mut(&id) {
+            e.position = new_pos;
+        }
+
+        self.invalidate_around(&old_pos);
+        self.invalidate_around(&new_pos);
+        self.revision += 1;
+        true
+    }
+
+    /// Ändras när någon varelse flyttat sig, se `PathCache`
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Anropas av spelloopen i början av varje tick
+    pub fn new_tick(&mut self) {
+        self.cache.clear();
+    }
+
+    pub fn get_spectators(&mut self, query: &SpectatorQuery) -> Vec<u32> {
+        if !query.is_default_range() {
+            return self.collect(query);
+        }
+
+        let key = (query.center, query.multifloor, query.only_players);
+        if let Some(cached) = self.cache.get(&key) {
+            return cached.clone();
+        }
+
+        let result = self.collect(query);
+        self.cache.insert(key, result.clone());
+        result
+    }
+
+    /// Spelare som kan se `pos` i klienten (för broadcast av rörelse, tal, effekter)
+    pub fn get_player_spectators(&mut self, pos: Position, multifloor: bool) -> Vec<u32> {
+        self.get_spectators(&SpectatorQuery::new(pos).multifloor(multifloor).only_players(true))
+    }
+
+    pub fn is_player_in_range(&self, center: &Position, range_x: i32, range_y: i32, multifloor: bool) -> bool {
+        let query = SpectatorQuery::new(*center)
+            .multifloor(multifloor)
+            .only_players(true)
+            .range(range_x, range_y);
+        let mut found = false;
+        self.visit(&query, |_| {
+            found = true;
+            false
+        });
+        found
+    }
+
+    fn collect(&self, query: &SpectatorQuery) -> Vec<u32> {
+        let mut out = Vec::new();
+        self.visit(query, |id| {
+            out.push(id);
+            true
+        });
+        out
+    }
+
+    /// Går igenom alla träffar; `f` returnerar false för att avbryta
+    fn visit<F: FnMut(u32) -> bool>(&self, query: &SpectatorQuery, mut f: F) {
+        let (min_x, max_x, min_y, max_y) = resolve_ranges(query);
+        let center = query.center;
+        let (min_z, max_z) = floor_range(center.z, query.multifloor);
+
+        for z in min_z..=max_z {
+            // varje våning förskjuts diagonalt, som i klienten
+            let offset_z = center.z as i32 - z as i32;
+            let x1 = (center.x as i32 + min_x + offset_z).clamp(0, u16::MAX as i32);
+            let x2 = (center.x as i32 + max_x + offset_z).clamp(0, u16::MAX as i32);
+            let y1 = (center.y as i32 + min_y + offset_z).clamp(0, u16::MAX as i32);
+            let y2 = (center.y as i32 + max_y + offset_z).clamp(0, u16::MAX as i32);
+
+            let sx1 = x1 as u16 / SECTOR_SIZE;
+            let sx2 = x2 as u16 / SECTOR_SIZE;
+            let sy1 = y1 as u16 / SECTOR_SIZE;
+            let sy2 = y2 as u16 / SECTOR_SIZE;
+
+            for sy in sy1..=sy2 {
+                for sx in sx1..=sx2 {
+                    let Some(sector) = self.sectors.get(&(sx, sy, z)) else {
+                        continue;
+                    };
+                    let list = if query.only_players { &sector.players } else { &sector.creatures };
+                    for &id in list {
+                        let Some(entry) = self.entries.get(&id) else {
+                            continue;
+                        };
+                        let (cx, cy) = (entry.position.x as i32, entry.position.y as i32);
+                        if cx < x1 || cx > x2 || cy < y1 || cy > y2 {
+                            continue;
+                        }
+                        if !f(id) {
+                            return;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn remove_from_sector(&mut self, id: u32, entry: &Entry) {
+        let key = sector_key(&entry.position);
+        if let Some(sector) = self.sectors.get_mut(&key) {
+            sector.remove(id, entry.is_player);
+            if sector.is_empty() {
+                self.sectors.remove(&key);
+            }
+        }
+    }
+
+    /// Släng cachade frågor vars vy kan innehålla `pos`
+    fn invalidate_around(&mut self, pos: &Position) {
+        if self.cache.is_empty() {
+            return;
+        }
+        // Alla cachade frågor har standard-range, så det räcker att kolla
+        // om positionen ligger inom max viewport + våningsförskjutning.
+        let reach_x = MAX_VIEWPORT_X + MAP_MAX_LAYERS as i32;
+        let reach_y = MAX_VIEWPORT_Y + MAP_MAX_LAYERS as i32;
+        self.cache.retain(|(center, multifloor, _), _| {
+            let (min_z, max_z) = floor_range(center.z, *multifloor);
+            pos.z < min_z
+                || pos.z > max_z
+                || Position::get_distance_x(center, pos) > reach_x
+                || Position::get_distance_y(center, pos) > reach_y
+        });
+    }
+}
+
+/// Översätt frågans ranger till offset från centrum (min är negativ)
+fn resolve_ranges(query: &SpectatorQuery) -> (i32, i32, i32, i32) {
+    let min_x = if query.min_range_x == 0 { -MAX_VIEWPORT_X } else { -query.min_range_x };
+    let max_x = if query.max_range_x == 0 { MAX_VIEWPORT_X } else { query.max_range_x };
+    let min_y = if query.min_range_y == 0 { -MAX_VIEWPORT_Y } else { -query.min_range_y };
+    let max_y = if query.max_range_y == 0 { MAX_VIEWPORT_Y } else { query.max_range_y };
+    (min_x, max_x, min_y, max_y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn floor_range_follows_client_rules() {
+        assert_eq!(floor_range(7, false), (7, 7));
+        assert_eq!(floor_range(5, true), (0, 7));
+        assert_eq!(floor_range(6, true), (0, 8));
+        assert_eq!(floor_range(7, true), (0, 9));
+        assert_eq!(floor_range(8, true), (6, 10));
+        assert_eq!(floor_range(15, true), (13, 15));
+    }
+
+    #[test]
+    fn can_see_stays_above_or_below_ground() {
+        let viewer = Position::new(100, 100, 7);
+        assert!(can_see(&viewer, &Position::new(107, 107, 0), 8, 6));
+        assert!(!can_see(&viewer, &Position::new(101, 101, 8), 8, 6));
+
+        let viewer = Position::new(100, 100, 10);
+        assert!(can_see(&viewer, &Position::new(100, 100, 8), 8, 6));
+        assert!(!can_see(&viewer, &Position::new(100, 100, 13), 8, 6));
+    }
+
+    #[test]
+    fn can_see_shifts_other_floors_diagonally() {
+        let viewer = Position::new(100, 100, 7);
+        assert!(can_see(&viewer, &Position::new(108, 106, 7), 8, 6));
+        assert!(!can_see(&viewer, &Position::new(109, 100, 7), 8, 6));
+
+        // en våning upp förskjuts vyn ett steg
+        assert!(can_see(&viewer, &Position::new(109, 107, 6), 8, 6));
+        assert!(!can_see(&viewer, &Position::new(92, 100, 6), 8, 6));
+    }
+
+    #[test]
+    fn player_spectators_follow_moves() {
+        let mut index = SpectatorIndex::new();
+        index.insert(1, Position::new(100, 100, 7), true);
+        index.insert(2, Position::new(101, 100, 7), false);
+
+        let pos = Position::new(105, 100, 7);
+        assert_eq!(index.get_player_spectators(pos, true), vec![1]);
+
+        // cachen ska släppa positionen när spelaren går därifrån
+        let revision = index.revision();
+        index.move_creature(1, Position::new(150, 100, 7));
+        assert!(index.revision() > revision);
+        assert!(index.get_player_spectators(pos, true).is_empty());
+        assert!(index.get_player_spectators(pos, false).is_empty());
+
+        index.move_creature(1, Position::new(104, 100, 6));
+        assert_eq!(index.get_player_spectators(pos, true), vec![1]);
+        assert!(index.get_player_spectators(pos, false).is_empty());
+    }
+}
@@ -0,0 +1,677 @@
+//! Tile och stack-ordning, motsvarar tile.h/tile.cpp i TFS.
+//!
+//! En tile håller (i klientens ordning): ground, top items (sorterade på
+//! `top_order`), varelser (nyast först) och down items (nyast först).
+//! Klienten ser bara de första `MAX_STACKPOS` sakerna.
+
+use common::{Position, ReturnValue};
+use items::item::{floor_change, ItemProperty};
+use items::Item;
+
+pub const MAX_STACKPOS: usize = 10;
+
+/// Tile-flaggor, samma bitar som `tileflags_t` i TFS
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct TileFlags(pub u32);
+
+impl TileFlags {
+    pub const NONE: TileFlags = TileFlags(0);
+    pub const FLOORCHANGE_DOWN: TileFlags = TileFlags(floor_change::DOWN as u32);
+    pub const FLOORCHANGE_NORTH: TileFlags = TileFlags(floor_change::NORTH as u32);
+    pub const FLOORCHANGE_SOUTH: TileFlags = TileFlags(floor_change::SOUTH as u32);
+    pub const FLOORCHANGE_EAST: TileFlags = TileFlags(floor_change::EAST as u32);
+    pub const FLOORCHANGE_WEST: TileFlags = TileFlags(floor_change::WEST as u32);
+    pub const FLOORCHANGE_SOUTH_ALT: TileFlags = TileFlags(floor_change::SOUTH_ALT as u32);
+    pub const FLOORCHANGE_EAST_ALT: TileFlags = TileFlags(floor_change::EAST_ALT as u32);
+    pub const PROTECTIONZONE: TileFlags = TileFlags(1 << 7);
+    pub const NOPVPZONE: TileFlags = TileFlags(1 << 8);
+    pub const NOLOGOUT: TileFlags = TileFlags(1 << 9);
+    pub const PVPZONE: TileFlags = TileFlags(1 << 10);
+    pub const TELEPORT: TileFlags = TileFlags(1 << 11);
+    pub const MAGICFIELD: TileFlags = TileFlags(1 << 12);
+    pub const MAILBOX: TileFlags = TileFlags(1 << 13);
+    pub const TRASHHOLDER: TileFlags = TileFlags(1 << 14);
+    pub const BED: TileFlags = TileFlags(1 << 15);
+    pub const DEPOT: TileFlags = TileFlags(1 << 16);
+    pub const BLOCKSOLID: TileFlags = TileFlags(1 << 17);
+    pub const BLOCKPATH: TileFlags = TileFlags(1 << 18);
+    pub const IMMOVABLEBLOCKSOLID: TileFlags = TileFlags(1 << 19);
+    pub const IMMOVABLEBLOCKPATH: TileFlags = TileFlags(1 << 20);
+    pub const IMMOVABLENOFIELDBLOCKPATH: TileFlags = TileFlags(1 << 21);
+    pub const NOFIELDBLOCKPATH: TileFlags = TileFlags(1 << 22);
+    pub const SUPPORTS_HANGABLE: TileFlags = TileFlags(1 << 23);
+
+    pub const FLOORCHANGE: TileFlags = TileFlags(0x7F);
+    /// Flaggor som kommer från kartan (OTBM) och inte från items på tilen
+    pub const ZONE_MASK: TileFlags = TileFlags((1 << 7) | (1 << 8) | (1 << 9) | (1 << 10));
+
+    pub fn contains(self, other: TileFlags) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    pub fn insert(&mut self, other: TileFlags) {
+        self.0 |= other.0;
+    }
+
+    pub fn remove(&mut self, other: TileFlags) {
+        self.0 &= !other.0;
+    }
+}
+
+impl std::ops::BitOr for TileFlags {
+    type Output = TileFlags;
+    fn bitor(self, rhs: TileFlags) -> TileFlags {
+        TileFlags(self.0 | rhs.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZoneType {
+    Normal,
+    Protection,
+    NoPvp,
+    Pvp,
+}
+
+/// En sak på en tile, sett utifrån stackpos
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ThingRef<'a> {
+    Item(&'a Item),
+    Creature(u32),
+}
+
+/// Det som läggs på/tas bort från en tile
+#[derive(Debug, Clone, PartialEq)]
+pub enum Thing {
+    Item(Item),
+    Creature(u32),
+}
+
+#[derive(Debug, Clone)]
+pub struct Tile {
+    pub position: Position,
+    ground: Option<Item>,
+    top_items: Vec<Item>,
+    creatures: Vec<u32>,
+    down_items: Vec<Item>,
+    flags: TileFlags,
+    house_id: u32,
+}
+
+impl Tile {
+    pub fn new(position: Position) -> Self {
+        Self {
+            position,
+            ground: None,
+            top_items: Vec::new(),
+            creatures: Vec::new(),
+            down_items: Vec::new(),
+            flags: TileFlags::NONE,
+            house_id: 0,
+        }
+    }
+
+    // === Flaggor ===
+
+    pub fn flags(&self) -> TileFlags {
+        self.flags
+    }
+
+    pub fn has_flag(&self, flag: TileFlags) -> bool {
+        self.flags.contains(flag)
+    }
+
+    /// Sätt zon-flaggor från kartan (PZ, no-PvP, no-logout, PvP-zon)
+    pub fn set_zone_flag(&mut self, flag: TileFlags) {
+        self.flags.insert(TileFlags(flag.0 & TileFlags::ZONE_MASK.0));
+    }
+
+    pub fn reset_zone_flag(&mut self, flag: TileFlags) {
+        self.flags.remove(TileFlags(flag.0 & TileFlags::ZONE_MASK.0));
+    }
+
+    pub fn get_zone(&self) -> ZoneType {
+        if self.has_flag(TileFlags::PROTECTIONZONE) {
+            ZoneType::Protection
+        } else if self.has_flag(TileFlags::NOPVPZONE) {
+            ZoneType::NoPvp
+        } else if self.has_flag(TileFlags::PVPZONE) {
+            ZoneType::Pvp
+        } else {
+            ZoneType::Normal
+        }
+    }
+
+    pub fn is_protection_zone(&self) -> bool {
+        self.has_flag(TileFlags::PROTECTIONZONE)
+    }
+
+    pub fn is_no_pvp_zone(&self) -> bool {
+        self.has_flag(TileFlags::NOPVPZONE)
+    }
+
+    pub fn is_no_logout(&self) -> bool {
+        self.has_flag(TileFlags::NOLOGOUT)
+    }
+
+    pub fn is_house_tile(&self) -> bool {
+        self.house_id != 0
+    }
+
+    pub fn house_id(&self) -> u32 {
+        self.house_id
+    }
+
+    pub fn set_house_id(&mut self, house_id: u32) {
+        self.house_id = house_id;
+    }
+
+    /// Det som avgör om och hur man kan gå här: marken, flaggorna från
+    /// items och zoner samt huset. Se `Map::update_tile`.
+    pub(crate) fn path_state(&self) -> (Option<u16>, TileFlags, u32) {
+        (self.ground.as_ref().map(|ground| ground.id), self.flags, self.house_id)
+    }
+
+    pub fn is_blocking(&self) -> bool {
+        self.ground.is_none() || self.has_flag(TileFlags::BLOCKSOLID)
+    }
+
+    pub fn blocks_path(&self) -> bool {
+        self.is_blocking() || self.has_flag(TileFlags::BLOCKPATH)
+    }
+
+    pub fn has_floor_change(&self) -> bool {
+        self.has_flag(TileFlags::FLOORCHANGE)
+    }
+
+    pub fn is_teleport(&self) -> bool {
+        self.has_flag(TileFlags::TELEPORT)
+    }
+
+    pub fn has_height(&self, min: usize) -> bool {
+        let mut height = 0;
+        for item in self.items() {
+            if item.has_property(ItemProperty::HasHeight) {
+                height += 1;
+                if height >= min {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    /// Motsvarar `Tile::hasProperty` (kollar alla items)
+    pub fn has_property(&self, prop: ItemProperty) -> bool {
+        self.items().any(|i| i.has_property(prop))
+    }
+
+    /// Markhastighet för gång-beräkningar (0 om ingen ground)
+    pub fn ground_speed(&self) -> u16 {
+        self.ground.as_ref().map(|g| g.item_type().speed).unwrap_or(0)
+    }
+
+    // === Innehåll ===
+
+    pub fn ground(&self) -> Option<&Item> {
+        self.ground.as_ref()
+    }
+
+    pub fn top_items(&self) -> &[Item] {
+        &self.top_items
+    }
+
+    pub fn down_items(&self) -> &[Item] {
+        &self.down_items
+    }
+
+    pub fn creatures(&self) -> &[u32] {
+        &self.creatures
+    }
+
+    pub fn has_creature(&self, id: u32) -> bool {
+        self.creatures.contains(&id)
+    }
+
+    /// Alla items i stack-ordning (utan varelser)
+    pub fn items(&self) -> impl Iterator<Item = &Item> {
+        self.ground
+            .iter()
+            .chain(self.top_items.iter())
+            .chain(self.down_items.iter())
+    }
+
+    pub fn items_mut(&mut self) -> impl Iterator<Item = &mut Item> {
+        self.ground
+            .iter_mut()
+            .chain(self.top_items.iter_mut())
+            .chain(self.down_items.iter_mut())
+    }
+
+    pub fn thing_count(&self) -> usize {
+        self.ground.is_some() as usize + self.top_items.len() + self.creatures.len() + self.down_items.len()
+    }
+
+    /// Item överst i down-stacken eller top-stacken (det man "använder")
+    pub fn get_top_down_item(&self) -> Option<&Item> {
+        self.down_items.first()
+    }
+
+    pub fn get_top_top_item(&self) -> Option<&Item> {
+        self.top_items.last()
+    }
+
+    pub fn get_top_creature(&self) -> Option<u32> {
+        self.creatures.first().copied()
+    }
+
+    pub fn get_item_by_type(&self, id: u16) -> Option<&Item> {
+        self.items().find(|i| i.id == id)
+    }
+
+    pub fn get_teleport_item(&self) -> Option<&Item> {
+        if !self.is_teleport() {
+            return None;
+        }
+        self.items().find(|i| i.item_type().is_teleport())
+    }
+
+    // === Stackpos ===
+
+    /// Sak på given stackpos. `visible` avgör om en varelse syns för
+    /// betraktaren (ghost/invisible), precis som `canSeeCreature` i TFS.
+    pub fn get_thing<F: Fn(u32) -> bool>(&self, mut index: usize, visible: F) -> Option<ThingRef<'_>> {
+        if let Some(ground) = &self.ground {
+            if index == 0 {
+                return Some(ThingRef::Item(ground));
+            }
+            index -= 1;
+        }
+
+        if index < self.top_items.len() {
+            return Some(ThingRef::Item(&self.top_items[index]));
+        }
+        index -= self.top_items.len();
+
+        for &c in &self.creatures {
+            if !visible(c) {
+                continue;
+            }
+            if index == 0 {
+                return Some(ThingRef::Creature(c));
+            }
+            index -= 1;
+        }
+
+        self.down_items.get(index).map(ThingRef::Item)
+    }
+
+    /// Motsvarar `getClientIndexOfCreature`
+    pub fn get_stackpos_of_creature<F: Fn(u32) -> bool>(&self, id: u32, visible: F) -> Option<u8> {
+        let mut n = self.ground.is_some() as usize + self.top_items.len();
+        for &c in &self.creatures {
+            if c == id {
+                return (n < MAX_STACKPOS).then_some(n as u8);
+            }
+            if visible(c) {
+                n += 1;
+            }
+        }
+        None
+    }
+
+    /// Stackpos för item på index i top- eller down-listan, se `getStackposOfItem`
+    fn stackpos_of_item<F: Fn(u32) -> bool>(&self, slot: ItemSlot, visible: F) -> Option<u8> {
+        let mut n = self.ground.is_some() as usize;
+        let pos = match slot {
+            ItemSlot::Ground => 0,
+            ItemSlot::Top(i) => n + i,
+            ItemSlot::Down(i) => {
+                n += self.top_items.len();
+                n += self.creatures.iter().filter(|&&c| visible(c)).count();
+                n + i
+            }
+        };
+        (pos < MAX_STACKPOS).then_some(pos as u8)
+    }
+
+    fn slot_of_stackpos<F: Fn(u32) -> bool>(&self, mut index: usize, visible: F) -> Option<ItemSlot> {
+        if self.ground.is_some() {
+            if index == 0 {
+                return Some(ItemSlot::Ground);
+            }
+            index -= 1;
+        }
+        if index < self.top_items.len() {
+            return Some(ItemSlot::Top(index));
+        }
+        index -= self.top_items.len();
+
+        let visible_creatures = self.creatures.iter().filter(|&&c| visible(c)).count();
+        if index < visible_creatures {
+            return None;
+        }
+        index -= visible_creatures;
+        (index < self.down_items.len()).then_some(ItemSlot::Down(index))
+    }
+
+    // === Queries ===
+
+    /// Kan en varelse gå hit? `creature_blocks` avgör om en varelse som
+    /// redan står på tilen blockerar (walkthrough, PZ osv).
+    pub fn query_add_creature<F: Fn(u32) -> bool>(&self, creature_blocks: F) -> ReturnValue {
+        if self.ground.is_none() {
+            return ReturnValue::NotPossible;
+        }
+        if self.has_flag(TileFlags::BLOCKSOLID) {
+            return ReturnValue::NotPossible;
+        }
+        if self.creatures.iter().any(|&c| creature_blocks(c)) {
+            return ReturnValue::NotPossible;
+        }
+        ReturnValue::NoError
+    }
+
+    /// Kan ett item läggas här? (motsvarar item-delen av `queryAdd`)
+    pub fn query_add_item(&self, item: &Item) -> ReturnValue {
+        let it = item.item_type();
+        if it.is_ground() || it.always_on_top {
+            return ReturnValue::NoError;
+        }
+        if self.ground.is_none() {
+            return ReturnValue::NotPossible;
+        }
+        if it.hangable && self.has_flag(TileFlags::SUPPORTS_HANGABLE) {
+            return ReturnValue::NoError;
+        }
+        if self.has_flag(TileFlags::BLOCKSOLID) {
+            return ReturnValue::NotEnoughRoom;
+        }
+        if it.block_solid && !self.creatures.is_empty() {
+            return ReturnValue::NotEnoughRoom;
+        }
+        ReturnValue::NoError
+    }
+
+    // === Add / remove / update ===
+
+    /// Lägg till en sak och returnera dess stackpos (None om den hamnar
+    /// utanför det klienten ser). Motsvarar `Tile::addThing`.
+    pub fn add_thing(&mut self, thing: Thing) -> Option<u8> {
+        let slot = match thing {
+            Thing::Creature(id) => {
+                self.creatures.insert(0, id);
+                return self.get_stackpos_of_creature(id, |_| true);
+            }
+            Thing::Item(item) => self.insert_item(item).0,
+        };
+        self.update_tile_flags();
+        self.stackpos_of_item(slot, |_| true)
+    }
+
+    /// Som `add_thing` för ett item, men ger också stackpos för splashen
+    /// eller grounden som ersattes, så att klienterna kan ta bort den först.
+    /// Returnerar (ersatt item, nytt item).
+    pub fn add_item(&mut self, item: Item) -> (Option<u8>, Option<u8>) {
+        let (slot, removed) = self.insert_item(item);
+        self.update_tile_flags();
+        (removed, self.stackpos_of_item(slot, |_| true))
+    }
+
+    /// Ta bort en varelse, returnerar stackpos den hade
+    pub fn remove_creature(&mut self, id: u32) -> Option<u8> {
+        let stackpos = self.get_stackpos_of_creature(id, |_| true);
+        let idx = self.creatures.iter().position(|&c| c == id)?;
+        self.creatures.remove(idx);
+        stackpos
+    }
+
+    /// Ta bort (del av) item på stackpos. För stackbara items tas bara
+    /// `count` bort; resten ligger kvar. Returnerar det borttagna itemet.
+    pub fn remove_item_at(&mut self, stackpos: usize, count: u16) -> Option<Item> {
+        let slot = self.slot_of_stackpos(stackpos, |_| true)?;
+        let item = match slot {
+            ItemSlot::Ground => self.ground.as_mut()?,
+            ItemSlot::Top(i) => &mut self.top_items[i],
+            ItemSlot::Down(i) => &mut self.down_items[i],
+        };
+
+        if item.is_stackable() && count > 0 && count < item.count {
+            item.count -= count;
+            let mut removed = item.clone();
+            removed.count = count;
+            return Some(removed);
+        }
+
+        let removed = match slot {
+            ItemSlot::Ground => self.ground.take(),
+            ItemSlot::Top(i) => Some(self.top_items.remove(i)),
+            ItemSlot::Down(i) => Some(self.down_items.remove(i)),
+        };
+        self.update_tile_flags();
+        removed
+    }
+
+    /// Ta bort första itemet med givet id (t.ex. från scripts)
+    pub fn remove_item_by_id(&mut self, id: u16, count: u16) -> Option<(u8, Item)> {
+        let stackpos = (0..self.thing_count()).find(|&pos| {
+            matches!(self.get_thing(pos, |_| true), Some(ThingRef::Item(i)) if i.id == id)
+        })?;
+        let item = self.remove_item_at(stackpos, count)?;
+        Some((stackpos.min(u8::MAX as usize) as u8, item))
+    }
+
+    /// Uppdatera item på plats: transform (nytt id) och/eller ny count.
+    /// Om ordningen ändras (t.ex. ground ↔ top) flyttas itemet. Motsvarar
+    /// `Tile::updateThing` + `replaceThing`. Returnerar nya stackpos, None om
+    /// itemet saknas eller hamnar utanför det klienten ser.
+    pub fn update_item(&mut self, stackpos: usize, new_id: u16, new_count: u16) -> Option<u8> {
+        let slot = self.slot_of_stackpos(stackpos, |_| true)?;
+        let old = match slot {
+            ItemSlot::Ground => self.ground.as_ref()?,
+            ItemSlot::Top(i) => &self.top_items[i],
+            ItemSlot::Down(i) => &self.down_items[i],
+        };
+
+        let old_it = old.item_type();
+        let new_it = items::Items::get(new_id);
+        let same_layer = old_it.is_ground() == new_it.is_ground()
+            && old_it.always_on_top == new_it.always_on_top
+            && old_it.top_order == new_it.top_order;
+
+        if same_layer {
+            let item = match slot {
+                ItemSlot::Ground => self.ground.as_mut()?,
+                ItemSlot::Top(i) => &mut self.top_items[i],
+                ItemSlot::Down(i) => &mut self.down_items[i],
+            };
+            item.id = new_id;
+            item.count = new_count;
+            self.update_tile_flags();
+            return (stackpos < MAX_STACKPOS).then_some(stackpos as u8);
+        }
+
+        let mut item = self.remove_item_at(stackpos, 0)?;
+        item.id = new_id;
+        item.count = new_count;
+        self.add_thing(Thing::Item(item))
+    }
+
+    /// Lägg in itemet på sin plats. Returnerar platsen och stackpos för en
+    /// splash eller ground som togs bort.
+    fn insert_item(&mut self, item: Item) -> (ItemSlot, Option<u8>) {
+        let it = item.item_type();
+        if it.is_ground() {
+            // bara en ground per tile, den gamla ligger alltid på stackpos 0
+            let removed = self.ground.replace(item).map(|_| 0);
+            return (ItemSlot::Ground, removed);
+        }
+
+        if it.always_on_top {
+            // sortera på top_order, lika ordning hamnar efter befintliga
+            let order = it.top_order;
+            let idx = self
+                .top_items
+                .iter()
+                .position(|i| i.top_order() > order)
+                .unwrap_or(self.top_items.len());
+            self.top_items.insert(idx, item);
+            return (ItemSlot::Top(idx), None);
+        }
+
+        let mut removed = None;
+        if it.is_splash() {
+            // bara en splash per tile
+            if let Some(idx) = self.down_items.iter().position(|i| i.item_type().is_splash()) {
+                removed = self.stackpos_of_item(ItemSlot::Down(idx), |_| true);
+                self.down_items.remove(idx);
+            }
+        }
+
+        self.down_items.insert(0, item);
+        (ItemSlot::Down(0), removed)
+    }
+
+    /// Räkna om item-beroende flaggor (motsvarar setTileFlags/resetTileFlags)
+    fn update_tile_flags(&mut self) {
+        let mut flags = TileFlags(self.flags.0 & TileFlags::ZONE_MASK.0);
+
+        for item in self.items() {
+            let it = item.item_type();
+            if !flags.contains(TileFlags::FLOORCHANGE) && it.floor_change != 0 {
+                flags.insert(TileFlags(it.floor_change as u32));
+            }
+            if item.has_property(ItemProperty::ImmovableBlockSolid) {
+                flags.insert(TileFlags::IMMOVABLEBLOCKSOLID);
+            }
+            if item.has_property(ItemProperty::BlockPath) {
+                flags.insert(TileFlags::BLOCKPATH);
+            }
+            if item.has_property(ItemProperty::NoFieldBlockPath) {
+                flags.insert(TileFlags::NOFIELDBLOCKPATH);
+            }
+            if item.has_property(ItemProperty::ImmovableNoFieldBlockPath) {
+                flags.insert(TileFlags::IMMOVABLENOFIELDBLOCKPATH);
+            }
+            if item.has_property(ItemProperty::ImmovableBlockPath) {
+                flags.insert(TileFlags::IMMOVABLEBLOCKPATH);
+            }
+            if it.is_teleport() {
+                flags.insert(TileFlags::TELEPORT);
+            }
+            if it.is_magic_field() {
+                flags.insert(TileFlags::MAGICFIELD);
+            }
+            if it.is_mailbox() {
+                flags.insert(TileFlags::MAILBOX);
+            }
+            if it.is_trash_holder() {
+                flags.insert(TileFlags::TRASHHOLDER);
+            }
+            if it.block_solid {
+                flags.insert(TileFlags::BLOCKSOLID);
+            }
+            if it.is_bed() {
+                flags.insert(TileFlags::BED);
+            }
+            if it.is_depot() {
+                flags.insert(TileFlags::DEPOT);
+            }
+            if item.has_property(ItemProperty::SupportHangable) {
+                flags.insert(TileFlags::SUPPORTS_HANGABLE);
+            }
+        }
+
+        self.flags = flags;
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ItemSlot {
+    Ground,
+    Top(usize),
+    Down(usize),
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use items::item::ItemGroup;
+    use items::{ItemType, Items};
+
+    pub(crate) const GROUND: u16 = 100;
+    pub(crate) const OTHER_GROUND: u16 = 101;
+    pub(crate) const BORDER: u16 = 200;
+    pub(crate) const WALL: u16 = 201;
+    pub(crate) const SPLASH: u16 = 300;
+    pub(crate) const OTHER_SPLASH: u16 = 301;
+    pub(crate) const BAG: u16 = 400;
+
+    /// Typerna testerna i world använder; registret är globalt för hela testbinären
+    pub(crate) fn install_items() {
+        let mut items = Items::new();
+        let types = [
+            (GROUND, ItemGroup::Ground, false, 0),
+            (OTHER_GROUND, ItemGroup::Ground, false, 0),
+            (BORDER, ItemGroup::None, true, 1),
+            (WALL, ItemGroup::None, true, 2),
+            (SPLASH, ItemGroup::Splash, false, 0),
+            (OTHER_SPLASH, ItemGroup::Splash, false, 0),
+            (BAG, ItemGroup::None, false, 0),
+        ];
+        for (id, group, always_on_top, top_order) in types {
+            items.insert(ItemType { id, group, always_on_top, top_order, ..Default::default() });
+        }
+        // alla tester installerar samma typer, så det gör inget om en annan hann först
+        items.install();
+    }
+
+    fn ids(tile: &Tile) -> Vec<Option<u16>> {
+        (0..tile.thing_count())
+            .map(|i| match tile.get_thing(i, |_| true) {
+                Some(ThingRef::Item(item)) => Some(item.id),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn stack_is_ground_top_creatures_down() {
+        install_items();
+        let mut tile = Tile::new(Position::new(100, 100, 7));
+        tile.add_thing(Thing::Item(Item::new(BAG, 1)));
+        tile.add_thing(Thing::Item(Item::new(WALL, 1)));
+        tile.add_thing(Thing::Creature(1));
+        tile.add_thing(Thing::Item(Item::new(BORDER, 1)));
+        tile.add_thing(Thing::Item(Item::new(SPLASH, 1)));
+        tile.add_thing(Thing::Item(Item::new(GROUND, 1)));
+
+        // down items läggs överst, top items sorteras på top_order
+        let expected = vec![Some(GROUND), Some(BORDER), Some(WALL), None, Some(SPLASH), Some(BAG)];
+        assert_eq!(ids(&tile), expected);
+        assert_eq!(tile.get_stackpos_of_creature(1, |_| true), Some(3));
+    }
+
+    #[test]
+    fn new_splash_replaces_old() {
+        install_items();
+        let mut tile = Tile::new(Position::new(100, 100, 7));
+        tile.add_item(Item::new(GROUND, 1));
+        tile.add_item(Item::new(SPLASH, 1));
+        tile.add_item(Item::new(BAG, 1));
+
+        assert_eq!(tile.add_item(Item::new(OTHER_SPLASH, 1)), (Some(2), Some(1)));
+        assert_eq!(ids(&tile), vec![Some(GROUND), Some(OTHER_SPLASH), Some(BAG)]);
+    }
+
+    #[test]
+    fn new_ground_replaces_old() {
+        install_items();
+        let mut tile = Tile::new(Position::new(100, 100, 7));
+        assert_eq!(tile.add_item(Item::new(GROUND, 1)), (None, Some(0)));
+        tile.add_item(Item::new(BAG, 1));
+
+        assert_eq!(tile.add_item(Item::new(OTHER_GROUND, 1)), (Some(0), Some(0)));
+        assert_eq!(ids(&tile), vec![Some(OTHER_GROUND), Some(BAG)]);
+    }
+}
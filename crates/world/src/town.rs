@@ -0,0 +1,71 @@
+//! Städer och tempel, motsvarar town.h i TFS. Listan kommer från kartans
+//! towns-nod och speglas till `towns`-tabellen vid uppstart.
+
+use std::collections::{BTreeMap, HashMap};
+
+use common::Position;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Town {
+    pub id: u32,
+    pub name: String,
+    pub temple_position: Position,
+}
+
+/// Alla städer, nåbara på id och (skiftlägesokänsligt) namn.
+/// Motsvarar `Towns` i TFS.
+#[derive(Debug, Clone, Default)]
+pub struct Towns {
+    towns: BTreeMap<u32, Town>,
+    names: HashMap<String, u32>,
+}
+
+impl Towns {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Lägg till en stad. Returnerar false om id:t redan finns.
+    pub fn add_town(&mut self, town: Town) -> bool {
+        if self.towns.contains_key(&town.id) {
+            return false;
+        }
+        self.names.entry(town.name.to_lowercase()).or_insert(town.id);
+        self.towns.insert(town.id, town);
+        true
+    }
+
+    pub fn get_town(&self, id: u32) -> Option<&Town> {
+        self.towns.get(&id)
+    }
+
+    pub fn get_town_by_name(&self, name: &str) -> Option<&Town> {
+        let id = self.names.get(&name.to_lowercase())?;
+        self.towns.get(id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Town> {
+        self.towns.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.towns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.towns.is_empty()
+    }
+
+    /// Var en spelare ska hamna vid inloggning. En sparad position på 0,0,0
+    /// betyder första inloggningen (eller att positionen nollställts) och då
+    /// används stadens tempel, som i `IOLoginData::loadPlayer`.
+    /// None om staden inte finns.
+    pub fn login_position(&self, town_id: u32, saved: Position) -> Option<Position> {
+        let town = self.get_town(town_id)?;
+        if saved.x == 0 && saved.y == 0 && saved.z == 0 {
+            Some(town.temple_position)
+        } else {
+            Some(saved)
+        }
+    }
+}
@@ -221,7 +221,7 @@ impl Config {
             server_save_clean_map: get_or_default(&globals, "serverSaveCleanMap", false),
             server_save_close: get_or_default(&globals, "serverSaveClose", false),
             server_save_shutdown: get_or_default(&globals, "serverSaveShutdown", true),
-            experience_stages: Vec::new(),
+            experience_stages,
             rate_exp: get_or_default(&globals, "rateExp", 5),
             rate_skill: get_or_default(&globals, "rateSkill", 3),
             rate_loot: get_or_default(&globals, "rateLoot", 2),
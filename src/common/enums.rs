@@ -0,0 +1,568 @@
+/// Motsvarar `ReturnValue` i TFS (enums.h)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ReturnValue {
+    #[default]
+    NoError,
+    NotPossible,
+    NotEnoughRoom,
+    TooFarAway,
+    DestinationOutOfReach,
+    FirstGoDownstairs,
+    FirstGoUpstairs,
+    ContainerNotEnoughRoom,
+    NotMoveable,
+    DropTwoHandedItem,
+    BothHandsNeedToBeFree,
+    CanOnlyUseOneWeapon,
+    NeedExchange,
+    CannotBeDressed,
+    PutThisObjectInYourHand,
+    PutThisObjectInBothHands,
+    CannotPickup,
+    ThisIsImpossible,
+    DepotIsFull,
+    CreatureDoesNotExist,
+    CannotUseThisObject,
+    PlayerWithThisNameIsNotOnline,
+    NotRequiredLevelToUseRune,
+    YouAreAlreadyTrading,
+    ThisPlayerIsAlreadyTrading,
+    YouMayNotLogoutDuringAFight,
+    DirectPlayerShoot,
+    NotEnoughLevel,
+    NotEnoughMagicLevel,
+    NotEnoughMana,
+    NotEnoughSoul,
+    YouAreExhausted,
+    YouCannotUseObjectsThatFast,
+    PlayerIsNotReachable,
+    CanOnlyUseThisRuneOnCreatures,
+    ActionNotPermittedInProtectionZone,
+    YouMayNotAttackThisPlayer,
+    YouMayNotAttackAPersonInProtectionZone,
+    YouMayNotAttackAPersonWhileInProtectionZone,
+    YouMayNotAttackThisCreature,
+    YouCanOnlyUseItOnCreatures,
+    CreatureIsNotReachable,
+    TurnSecureModeToAttackUnmarkedPlayers,
+    YouNeedPremiumAccount,
+    YouNeedToLearnThisSpell,
+    YourVocationCannotUseThisSpell,
+    YouNeedAWeaponToUseThisSpell,
+    PlayerIsPzLockedLeavePvpZone,
+    PlayerIsPzLockedEnterPvpZone,
+    ActionNotPermittedInANoPvpZone,
+    YouCannotLogoutHere,
+    YouNeedAMagicItemToCastSpell,
+    NameIsTooAmbiguous,
+    CanOnlyUseOneShield,
+    NoPartyMembersInRange,
+    YouAreNotTheOwner,
+    TradePlayerFarAway,
+    YouDontOwnThisHouse,
+    TradePlayerAlreadyOwnsAHouse,
+    TradePlayerHighestBidder,
+    YouCannotTradeThisHouse,
+    NotEnoughCapacity,
+    CannotMoveItemIsNotStoreItem,
+    ItemCannotBeMovedThere,
+    PlayerIsNotInvited,
+}
+
+impl ReturnValue {
+    pub fn is_ok(self) -> bool {
+        self == ReturnValue::NoError
+    }
+
+    /// Motsvarar `getReturnMessage` i TFS
+    pub fn message(self) -> &'static str {
+        use ReturnValue::*;
+        match self {
+            NoError => "",
+            NotPossible | ThisIsImpossible => "Sorry, not possible.",
+            NotEnoughRoom => "There is not enough room.",
+            TooFarAway => "Too far away.",
+            DestinationOutOfReach => "Destination is out of reach.",
+            FirstGoDownstairs => "First go downstairs.",
+            FirstGoUpstairs => "First go upstairs.",
+            ContainerNotEnoughRoom => "You cannot put more objects in this container.",
+            NotMoveable => "You cannot move this object.",
+            DropTwoHandedItem => "Drop the double-handed object first.",
+            BothHandsNeedToBeFree => "Both hands need to be free.",
+            CanOnlyUseOneWeapon => "You may only use one weapon.",
+            NeedExchange | CannotBeDressed => "You cannot dress this object there.",
+            PutThisObjectInYourHand => "Put this object in your hand.",
+            PutThisObjectInBothHands => "Put this object in both hands.",
+            CannotPickup => "You cannot take this object.",
+            DepotIsFull => "You cannot put more items in this depot.",
+            CreatureDoesNotExist => "Creature does not exist.",
+            CannotUseThisObject => "You cannot use this object.",
+            PlayerWithThisNameIsNotOnline => "A player with this name is not online.",
+            NotRequiredLevelToUseRune => "You do not have the required magic level to use this rune.",
+            YouAreAlreadyTrading => "You are already trading. Finish this trade first.",
+            ThisPlayerIsAlreadyTrading => "This player is already trading.",
+            YouMayNotLogoutDuringAFight => "You may not logout during or immediately after a fight!",
+            DirectPlayerShoot => "You are not allowed to shoot directly on players.",
+            NotEnoughLevel => "Your level is too low.",
+            NotEnoughMagicLevel => "You do not have enough magic level.",
+            NotEnoughMana => "You do not have enough mana.",
+            NotEnoughSoul => "You do not have enough soul.",
+            YouAreExhausted => "You are exhausted.",
+            YouCannotUseObjectsThatFast => "You cannot use objects that fast.",
+            PlayerIsNotReachable => "Player is not reachable.",
+            CanOnlyUseThisRuneOnCreatures => "You can only use this rune on creatures.",
+            ActionNotPermittedInProtectionZone => "This action is not permitted in a protection zone.",
+            YouMayNotAttackThisPlayer => "You may not attack this person.",
+            YouMayNotAttackAPersonInProtectionZone => "You may not attack a person in a protection zone.",
+            YouMayNotAttackAPersonWhileInProtectionZone => {
+                "You may not attack a person while you are in a protection zone."
+            }
+            YouMayNotAttackThisCreature => "You may not attack this creature.",
+            YouCanOnlyUseItOnCreatures => "You can only use it on creatures.",
+            CreatureIsNotReachable => "Creature is not reachable.",
+            TurnSecureModeToAttackUnmarkedPlayers => {
+                "Turn secure mode off if you really want to attack unmarked players."
+            }
+            YouNeedPremiumAccount => "You need a premium account.",
+            YouNeedToLearnThisSpell => "You must learn this spell first.",
+            YourVocationCannotUseThisSpell => "You have the wrong vocation to cast this spell.",
+            YouNeedAWeaponToUseThisSpell => "You need to equip a weapon to use this spell.",
+            PlayerIsPzLockedLeavePvpZone => "You can not leave a pvp zone after attacking another player.",
+            PlayerIsPzLockedEnterPvpZone => "You can not enter a pvp zone after attacking another player.",
+            ActionNotPermittedInANoPvpZone => "This action is not permitted in a non pvp zone.",
+            YouCannotLogoutHere => "You can not logout here.",
+            YouNeedAMagicItemToCastSpell => "You need a magic item to cast this spell.",
+            NameIsTooAmbiguous => "Player name is ambiguous.",
+            CanOnlyUseOneShield => "You may use only one shield.",
+            NoPartyMembersInRange => "No party members in range.",
+            YouAreNotTheOwner => "You are not the owner.",
+            TradePlayerFarAway => "Trade player is too far away.",
+            YouDontOwnThisHouse => "You don't own this house.",
+            TradePlayerAlreadyOwnsAHouse => "Trade player already owns a house.",
+            TradePlayerHighestBidder => "Trade player is currently the highest bidder of an auctioned house.",
+            YouCannotTradeThisHouse => "You can not trade this house.",
+            NotEnoughCapacity => "This object is too heavy for you to carry.",
+            CannotMoveItemIsNotStoreItem => "You cannot move this item into your Store inbox as it was not bought in the Store.",
+            ItemCannotBeMovedThere => "This item cannot be moved there.",
+            PlayerIsNotInvited => "You are not invited.",
+        }
+    }
+}
+
+/// Motsvarar `MagicEffectClasses` i TFS (const.h)
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum MagicEffect {
+    #[default]
+    None = 0,
+    DrawBlood = 1,
+    LoseEnergy = 2,
+    Poff = 3,
+    BlockHit = 4,
+    ExplosionArea = 5,
+    ExplosionHit = 6,
+    FireArea = 7,
+    YellowRings = 8,
+    GreenRings = 9,
+    HitArea = 10,
+    Teleport = 11,
+    EnergyHit = 12,
+    MagicBlue = 13,
+    MagicRed = 14,
+    MagicGreen = 15,
+    HitByFire = 16,
+    HitByPoison = 17,
+    MortArea = 18,
+    SoundGreen = 19,
+    SoundRed = 20,
+    PoisonArea = 21,
+    SoundYellow = 22,
+    SoundPurple = 23,
+    SoundBlue = 24,
+    SoundWhite = 25,
+    Bubbles = 26,
+    Craps = 27,
+    GiftWraps = 28,
+    FireworkYellow = 29,
+    FireworkRed = 30,
+    FireworkBlue = 31,
+    Stun = 32,
+    Sleep = 33,
+    WaterCreature = 34,
+    GroundShaker = 35,
+    Hearts = 36,
+    FireAttack = 37,
+    EnergyArea = 38,
+    SmallClouds = 39,
+    HolyDamage = 40,
+    BigClouds = 41,
+    IceArea = 42,
+    IceTornado = 43,
+    IceAttack = 44,
+    Stones = 45,
+    SmallPlants = 46,
+    Carniphila = 47,
+    PurpleEnergy = 48,
+    YellowEnergy = 49,
+    HolyArea = 50,
+    BigPlants = 51,
+    Cake = 52,
+    GiantIce = 53,
+    WaterSplash = 54,
+    PlantAttack = 55,
+    TutorialArrow = 56,
+    TutorialSquare = 57,
+    MirrorHorizontal = 58,
+    MirrorVertical = 59,
+    SkullHorizontal = 60,
+    SkullVertical = 61,
+    Assassin = 62,
+    StepsHorizontal = 63,
+    BloodySteps = 64,
+    StepsVertical = 65,
+    YalahariGhost = 66,
+    Bats = 67,
+    Smoke = 68,
+    Insects = 69,
+    DragonHead = 70,
+    OrcShaman = 71,
+    OrcShamanFire = 72,
+    Thunder = 73,
+    Ferumbras = 74,
+    ConfettiHorizontal = 75,
+    ConfettiVertical = 76,
+}
+
+/// Namnen i monster- och spellfilerna, som `magicEffectNames` i tools.cpp
+const MAGIC_EFFECT_NAMES: [(&str, MagicEffect); 76] = [
+    ("redspark", MagicEffect::DrawBlood),
+    ("bluebubble", MagicEffect::LoseEnergy),
+    ("poff", MagicEffect::Poff),
+    ("yellowspark", MagicEffect::BlockHit),
+    ("explosionarea", MagicEffect::ExplosionArea),
+    ("explosion", MagicEffect::ExplosionHit),
+    ("firearea", MagicEffect::FireArea),
+    ("yellowbubble", MagicEffect::YellowRings),
+    ("greenbubble", MagicEffect::GreenRings),
+    ("blackspark", MagicEffect::HitArea),
+    ("teleport", MagicEffect::Teleport),
+    ("energy", MagicEffect::EnergyHit),
+    ("blueshimmer", MagicEffect::MagicBlue),
+    ("redshimmer", MagicEffect::MagicRed),
+    ("greenshimmer", MagicEffect::MagicGreen),
+    ("fire", MagicEffect::HitByFire),
+    ("greenspark", MagicEffect::HitByPoison),
+    ("mortarea", MagicEffect::MortArea),
+    ("greennote", MagicEffect::SoundGreen),
+    ("rednote", MagicEffect::SoundRed),
+    ("poison", MagicEffect::PoisonArea),
+    ("yellownote", MagicEffect::SoundYellow),
+    ("purplenote", MagicEffect::SoundPurple),
+    ("bluenote", MagicEffect::SoundBlue),
+    ("whitenote", MagicEffect::SoundWhite),
+    ("bubbles", MagicEffect::Bubbles),
+    ("dice", MagicEffect::Craps),
+    ("giftwraps", MagicEffect::GiftWraps),
+    ("yellowfirework", MagicEffect::FireworkYellow),
+    ("redfirework", MagicEffect::FireworkRed),
+    ("bluefirework", MagicEffect::FireworkBlue),
+    ("stun", MagicEffect::Stun),
+    ("sleep", MagicEffect::Sleep),
+    ("watercreature", MagicEffect::WaterCreature),
+    ("groundshaker", MagicEffect::GroundShaker),
+    ("hearts", MagicEffect::Hearts),
+    ("fireattack", MagicEffect::FireAttack),
+    ("energyarea", MagicEffect::EnergyArea),
+    ("smallclouds", MagicEffect::SmallClouds),
+    ("holydamage", MagicEffect::HolyDamage),
+    ("bigclouds", MagicEffect::BigClouds),
+    ("icearea", MagicEffect::IceArea),
+    ("icetornado", MagicEffect::IceTornado),
+    ("iceattack", MagicEffect::IceAttack),
+    ("stones", MagicEffect::Stones),
+    ("smallplants", MagicEffect::SmallPlants),
+    ("carniphila", MagicEffect::Carniphila),
+    ("purpleenergy", MagicEffect::PurpleEnergy),
+    ("yellowenergy", MagicEffect::YellowEnergy),
+    ("holyarea", MagicEffect::HolyArea),
+    ("bigplants", MagicEffect::BigPlants),
+    ("cake", MagicEffect::Cake),
+    ("giantice", MagicEffect::GiantIce),
+    ("watersplash", MagicEffect::WaterSplash),
+    ("plantattack", MagicEffect::PlantAttack),
+    ("tutorialarrow", MagicEffect::TutorialArrow),
+    ("tutorialsquare", MagicEffect::TutorialSquare),
+    ("mirrorhorizontal", MagicEffect::MirrorHorizontal),
+    ("mirrorvertical", MagicEffect::MirrorVertical),
+    ("skullhorizontal", MagicEffect::SkullHorizontal),
+    ("skullvertical", MagicEffect::SkullVertical),
+    ("assassin", MagicEffect::Assassin),
+    ("stepshorizontal", MagicEffect::StepsHorizontal),
+    ("bloodysteps", MagicEffect::BloodySteps),
+    ("stepsvertical", MagicEffect::StepsVertical),
+    ("yalaharighost", MagicEffect::YalahariGhost),
+    ("bats", MagicEffect::Bats),
+    ("smoke", MagicEffect::Smoke),
+    ("insects", MagicEffect::Insects),
+    ("dragonhead", MagicEffect::DragonHead),
+    ("orcshaman", MagicEffect::OrcShaman),
+    ("orcshamanfire", MagicEffect::OrcShamanFire),
+    ("thunder", MagicEffect::Thunder),
+    ("ferumbras", MagicEffect::Ferumbras),
+    ("confettihorizontal", MagicEffect::ConfettiHorizontal),
+    ("confettivertical", MagicEffect::ConfettiVertical),
+];
+
+impl MagicEffect {
+    pub fn from_u8(value: u8) -> Option<Self> {
+        if value == 0 {
+            return Some(MagicEffect::None);
+        }
+        MAGIC_EFFECT_NAMES.get(value as usize - 1).map(|&(_, effect)| effect)
+    }
+
+    /// Motsvarar `getMagicEffect` i tools.cpp
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.to_ascii_lowercase();
+        MAGIC_EFFECT_NAMES.iter().find(|(n, _)| *n == name).map(|&(_, effect)| effect)
+    }
+}
+
+/// Projektiler, motsvarar `ShootType_t`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct ShootType(pub u8);
+
+/// Namn och värde, som `shootTypeNames` i tools.cpp
+const SHOOT_TYPE_NAMES: [(&str, u8); 54] = [
+    ("spear", 1), ("bolt", 2), ("arrow", 3), ("fire", 4), ("energy", 5), ("poisonarrow", 6),
+    ("burstarrow", 7), ("throwingstar", 8), ("throwingknife", 9), ("smallstone", 10), ("death", 11),
+    ("largerock", 12), ("snowball", 13), ("powerbolt", 14), ("poison", 15), ("infernalbolt", 16),
+    ("huntingspear", 17), ("enchantedspear", 18), ("redstar", 19), ("greenstar", 20),
+    ("royalspear", 21), ("sniperarrow", 22), ("onyxarrow", 23), ("piercingbolt", 24),
+    ("whirlwindsword", 25), ("whirlwindaxe", 26), ("whirlwindclub", 27), ("etherealspear", 28),
+    ("ice", 29), ("earth", 30), ("holy", 31), ("suddendeath", 32), ("flasharrow", 33),
+    ("flammingarrow", 34), ("shiverarrow", 35), ("energyball", 36), ("smallice", 37),
+    ("smallholy", 38), ("smallearth", 39), ("eartharrow", 40), ("explosion", 41), ("cake", 42),
+    ("tarsalarrow", 44), ("vortexbolt", 45), ("prismaticbolt", 48), ("crystallinearrow", 49),
+    ("drillbolt", 50), ("envenomedarrow", 51), ("gloothspear", 53), ("simplearrow", 54),
+    ("leafstar", 56), ("diamondarrow", 57), ("spectralbolt", 58), ("royalstar", 59),
+];
+
+impl ShootType {
+    pub const NONE: Self = Self(0);
+    pub const WHIRLWIND_SWORD: Self = Self(25);
+    pub const WHIRLWIND_AXE: Self = Self(26);
+    pub const WHIRLWIND_CLUB: Self = Self(27);
+    /// Byts mot virvlen för spelarens vapen, `CONST_ANI_WEAPONTYPE`
+    pub const WEAPON_TYPE: Self = Self(0xFE);
+
+    /// Motsvarar `getShootType` i tools.cpp
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.to_ascii_lowercase();
+        SHOOT_TYPE_NAMES.iter().find(|(n, _)| *n == name).map(|&(_, value)| Self(value))
+    }
+
+    pub fn name(self) -> Option<&'static str> {
+        SHOOT_TYPE_NAMES.iter().find(|(_, value)| *value == self.0).map(|&(n, _)| n)
+    }
+}
+
+/// Skadetyper, motsvarar `CombatType_t`. En bit per typ så att immuniteter
+/// kan samlas i en mask.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct CombatType(pub u16);
+
+impl CombatType {
+    pub const NONE: Self = Self(0);
+    pub const PHYSICAL: Self = Self(1 << 0);
+    pub const ENERGY: Self = Self(1 << 1);
+    pub const EARTH: Self = Self(1 << 2);
+    pub const FIRE: Self = Self(1 << 3);
+    pub const UNDEFINED: Self = Self(1 << 4);
+    pub const LIFEDRAIN: Self = Self(1 << 5);
+    pub const MANADRAIN: Self = Self(1 << 6);
+    pub const HEALING: Self = Self(1 << 7);
+    pub const DROWN: Self = Self(1 << 8);
+    pub const ICE: Self = Self(1 << 9);
+    pub const HOLY: Self = Self(1 << 10);
+    pub const DEATH: Self = Self(1 << 11);
+
+    /// Alla typer utom NONE, i bitordning
+    pub const ALL: [Self; 12] = [
+        Self::PHYSICAL,
+        Self::ENERGY,
+        Self::EARTH,
+        Self::FIRE,
+        Self::UNDEFINED,
+        Self::LIFEDRAIN,
+        Self::MANADRAIN,
+        Self::HEALING,
+        Self::DROWN,
+        Self::ICE,
+        Self::HOLY,
+        Self::DEATH,
+    ];
+
+    pub fn intersects(self, other: Self) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    /// Index 0..12 som i `combatTypeToIndex`, None för NONE och blandningar
+    pub fn index(self) -> Option<usize> {
+        self.0.is_power_of_two().then(|| self.0.trailing_zeros() as usize)
+    }
+
+    /// Namnen i t.ex. `<element firePercent>` och `<immunity fire>`. Motsvarar
+    /// `getCombatName` baklänges; "poison" är det gamla namnet för earth.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name.to_ascii_lowercase().as_str() {
+            "physical" => Self::PHYSICAL,
+            "energy" => Self::ENERGY,
+            "earth" | "poison" => Self::EARTH,
+            "fire" => Self::FIRE,
+            "undefined" => Self::UNDEFINED,
+            "lifedrain" => Self::LIFEDRAIN,
+            "manadrain" => Self::MANADRAIN,
+            "healing" => Self::HEALING,
+            "drown" => Self::DROWN,
+            "ice" => Self::ICE,
+            "holy" => Self::HOLY,
+            "death" => Self::DEATH,
+            _ => return None,
+        })
+    }
+
+    /// Motsvarar `getCombatName`
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::PHYSICAL => "physical",
+            Self::ENERGY => "energy",
+            Self::EARTH => "earth",
+            Self::FIRE => "fire",
+            Self::LIFEDRAIN => "life drain",
+            Self::MANADRAIN => "mana drain",
+            Self::HEALING => "healing",
+            Self::DROWN => "drowning",
+            Self::ICE => "ice",
+            Self::HOLY => "holy",
+            Self::DEATH => "death",
+            _ => "unknown",
+        }
+    }
+}
+
+impl std::ops::BitOr for CombatType {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+/// Motsvarar `SpeakClasses` (TALKTYPE_*) för 10.98
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SpeakClass {
+    #[default]
+    Say = 1,
+    Whisper = 2,
+    Yell = 3,
+    PrivateFrom = 4,
+    PrivateTo = 5,
+    ChannelY = 7,
+    ChannelO = 8,
+    /// NPC till spelare i NPC-kanalen
+    PrivateNp = 10,
+    /// Spelare till NPC i NPC-kanalen
+    PrivatePn = 12,
+    Broadcast = 13,
+    ChannelR1 = 14,
+    PrivateRedFrom = 15,
+    PrivateRedTo = 16,
+    MonsterSay = 36,
+    MonsterYell = 37,
+    Potion = 52,
+    /// `#c` i en kanal, rött utan namn
+    ChannelR2 = 0xFF,
+}
+
+impl SpeakClass {
+    pub fn from_u8(value: u8) -> Option<Self> {
+        Some(match value {
+            1 => SpeakClass::Say,
+            2 => SpeakClass::Whisper,
+            3 => SpeakClass::Yell,
+            4 => SpeakClass::PrivateFrom,
+            5 => SpeakClass::PrivateTo,
+            7 => SpeakClass::ChannelY,
+            8 => SpeakClass::ChannelO,
+            10 => SpeakClass::PrivateNp,
+            12 => SpeakClass::PrivatePn,
+            13 => SpeakClass::Broadcast,
+            14 => SpeakClass::ChannelR1,
+            15 => SpeakClass::PrivateRedFrom,
+            16 => SpeakClass::PrivateRedTo,
+            36 => SpeakClass::MonsterSay,
+            37 => SpeakClass::MonsterYell,
+            52 => SpeakClass::Potion,
+            0xFF => SpeakClass::ChannelR2,
+            _ => return None,
+        })
+    }
+}
+
+/// Motsvarar `MessageClasses` (MESSAGE_*) för 10.98
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MessageClass {
+    StatusConsoleBlue = 4,
+    StatusConsoleRed = 13,
+    StatusDefault = 17,
+    StatusWarning = 18,
+    EventAdvance = 19,
+    StatusSmall = 21,
+    InfoDescr = 22,
+    DamageDealt = 23,
+    DamageReceived = 24,
+    Healed = 25,
+    Experience = 26,
+    DamageOthers = 27,
+    HealedOthers = 28,
+    ExperienceOthers = 29,
+    EventDefault = 30,
+    Loot = 31,
+    Guild = 33,
+    PartyManagement = 34,
+    Party = 35,
+    EventOrange = 36,
+    StatusConsoleOrange = 37,
+}
+
+impl MessageClass {
+    pub fn from_u8(value: u8) -> Option<Self> {
+        Some(match value {
+            4 => MessageClass::StatusConsoleBlue,
+            13 => MessageClass::StatusConsoleRed,
+            17 => MessageClass::StatusDefault,
+            18 => MessageClass::StatusWarning,
+            19 => MessageClass::EventAdvance,
+            21 => MessageClass::StatusSmall,
+            22 => MessageClass::InfoDescr,
+            23 => MessageClass::DamageDealt,
+            24 => MessageClass::DamageReceived,
+            25 => MessageClass::Healed,
+            26 => MessageClass::Experience,
+            27 => MessageClass::DamageOthers,
+            28 => MessageClass::HealedOthers,
+            29 => MessageClass::ExperienceOthers,
+            30 => MessageClass::EventDefault,
+            31 => MessageClass::Loot,
+            33 => MessageClass::Guild,
+            34 => MessageClass::PartyManagement,
+            35 => MessageClass::Party,
+            36 => MessageClass::EventOrange,
+            37 => MessageClass::StatusConsoleOrange,
+            _ => return None,
+        })
+    }
+}
@@ -0,0 +1,100 @@
+//! Läsare för OTB-filernas nodträd (items.otb och .otbm).
+//! Motsvarar `OTB::Loader` i TFS fileloader.cpp.
+//!
+//! Filen börjar med en fyra bytes lång identifierare följt av rotnoden.
+//! Varje nod är `NODE_START typ <props> <barn...> NODE_END`, där bytes som
+//! krockar med markörerna föregås av `ESCAPE`.
+
+use std::path::Path;
+
+use super::{Error, Result};
+
+pub const NODE_START: u8 = 0xFE;
+pub const NODE_END: u8 = 0xFF;
+pub const ESCAPE: u8 = 0xFD;
+
+/// En nod i trädet, med props redan av-escapade
+#[derive(Debug, Clone, Default)]
+pub struct OtbNode {
+    pub node_type: u8,
+    pub props: Vec<u8>,
+    pub children: Vec<OtbNode>,
+}
+
+/// Hela filen inläst som ett nodträd
+#[derive(Debug)]
+pub struct OtbLoader {
+    root: OtbNode,
+}
+
+impl OtbLoader {
+    /// Läs och tolka en fil. `identifier` är t.ex. `b"OTBM"`; en
+    /// identifierare med bara nollor godtas alltid, precis som i TFS.
+    pub fn open(path: impl AsRef<Path>, identifier: &[u8; 4]) -> Result<Self> {
+        let path = path.as_ref();
+        let data = std::fs::read(path)
+            .map_err(|e| Error::World(format!("Failed to open {}: {e}", path.display())))?;
+        Self::from_bytes(&data, identifier)
+            .map_err(|e| Error::World(format!("{}: {e}", path.display())))
+    }
+
+    pub fn from_bytes(data: &[u8], identifier: &[u8; 4]) -> Result<Self> {
+        if data.len() < 6 {
+            return Err(Error::World("File too small".into()));
+        }
+        let (id, data) = data.split_at(4);
+        if id != identifier && id != [0u8; 4] {
+            return Err(Error::World("Invalid file identifier".into()));
+        }
+        if data[0] != NODE_START {
+            return Err(Error::World("Invalid file format".into()));
+        }
+
+        let mut parser = Parser { data, pos: 1 };
+        let root = parser.parse_node()?;
+        Ok(Self { root })
+    }
+
+    pub fn root(&self) -> &OtbNode {
+        &self.root
+    }
+
+    pub fn into_root(self) -> OtbNode {
+        self.root
+    }
+}
+
+struct Parser<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn next(&mut self) -> Result<u8> {
+        let byte = *self
+            .data
+            .get(self.pos)
+            .ok_or_else(|| Error::World("Unexpected end of file".into()))?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    /// Läser en nod; NODE_START har redan konsumerats
+    fn parse_node(&mut self) -> Result<OtbNode> {
+        let mut node = OtbNode {
+            node_type: self.next()?,
+            ..Default::default()
+        };
+        loop {
+            match self.next()? {
+                NODE_START => node.children.push(self.parse_node()?),
+                NODE_END => return Ok(node),
+                ESCAPE => {
+                    let byte = self.next()?;
+                    node.props.push(byte);
+                }
+                byte => node.props.push(byte),
+            }
+        }
+    }
+}
@@ -1,8 +1,18 @@
 pub mod error;
 pub mod logger;
 pub mod configmanager;
+pub mod position;
+pub mod enums;
+pub mod tools;
+pub mod propstream;
+pub mod fileloader;
 
 pub use error::{Error, Result};
 pub use logger::init as init_logger;
 pub use tracing;
-pub use configmanager::Config;
\ No newline at end of file
+pub use configmanager::Config;
+pub use position::{Direction, Position};
+pub use enums::{CombatType, MagicEffect, MessageClass, ReturnValue, ShootType, SpeakClass};
+pub use tools::{boolean_random, normal_random, otsys_time, uniform_random, unix_time};
+pub use propstream::{PropStream, PropWriteStream};
+pub use fileloader::{OtbLoader, OtbNode};
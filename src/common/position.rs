@@ -0,0 +1,150 @@
+/// Motsvarar `Position` i TFS (position.h)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Position {
+    pub x: u16,
+    pub y: u16,
+    pub z: u8,
+}
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Direction {
+    North = 0,
+    East = 1,
+    #[default]
+    South = 2,
+    West = 3,
+    SouthWest = 4,
+    SouthEast = 5,
+    NorthWest = 6,
+    NorthEast = 7,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 8] = [
+        Direction::North,
+        Direction::East,
+        Direction::South,
+        Direction::West,
+        Direction::SouthWest,
+        Direction::SouthEast,
+        Direction::NorthWest,
+        Direction::NorthEast,
+    ];
+
+    pub fn from_u8(value: u8) -> Option<Self> {
+        Self::ALL.get(value as usize).copied()
+    }
+
+    pub fn is_diagonal(self) -> bool {
+        (self as u8) >= Direction::SouthWest as u8
+    }
+
+    /// (dx, dy) för ett steg i riktningen
+    pub fn offset(self) -> (i32, i32) {
+        match self {
+            Direction::North => (0, -1),
+            Direction::East => (1, 0),
+            Direction::South => (0, 1),
+            Direction::West => (-1, 0),
+            Direction::SouthWest => (-1, 1),
+            Direction::SouthEast => (1, 1),
+            Direction::NorthWest => (-1, -1),
+            Direction::NorthEast => (1, -1),
+        }
+    }
+}
+
+impl Position {
+    pub const fn new(x: u16, y: u16, z: u8) -> Self {
+        Self { x, y, z }
+    }
+
+    pub fn get_offset_x(a: &Position, b: &Position) -> i32 {
+        a.x as i32 - b.x as i32
+    }
+
+    pub fn get_offset_y(a: &Position, b: &Position) -> i32 {
+        a.y as i32 - b.y as i32
+    }
+
+    pub fn get_offset_z(a: &Position, b: &Position) -> i32 {
+        a.z as i32 - b.z as i32
+    }
+
+    pub fn get_distance_x(a: &Position, b: &Position) -> i32 {
+        Self::get_offset_x(a, b).abs()
+    }
+
+    pub fn get_distance_y(a: &Position, b: &Position) -> i32 {
+        Self::get_offset_y(a, b).abs()
+    }
+
+    pub fn get_distance_z(a: &Position, b: &Position) -> i32 {
+        Self::get_offset_z(a, b).abs()
+    }
+
+    /// Chebyshev-avstånd på samma våning (det TFS kallar "max distance")
+    pub fn get_distance(a: &Position, b: &Position) -> i32 {
+        Self::get_distance_x(a, b).max(Self::get_distance_y(a, b))
+    }
+
+    pub fn are_in_range(a: &Position, b: &Position, dx: i32, dy: i32, dz: i32) -> bool {
+        Self::get_distance_x(a, b) <= dx
+            && Self::get_distance_y(a, b) <= dy
+            && Self::get_distance_z(a, b) <= dz
+    }
+
+    /// Flytta positionen med ett offset, None om vi hamnar utanför kartan
+    pub fn translated(&self, dx: i32, dy: i32, dz: i32) -> Option<Position> {
+        let x = self.x as i32 + dx;
+        let y = self.y as i32 + dy;
+        let z = self.z as i32 + dz;
+        if !(0..=u16::MAX as i32).contains(&x)
+            || !(0..=u16::MAX as i32).contains(&y)
+            || !(0..MAP_MAX_LAYERS as i32).contains(&z)
+        {
+            return None;
+        }
+        Some(Position::new(x as u16, y as u16, z as u8))
+    }
+
+    /// Motsvarar `getNextPosition` i TFS
+    pub fn get_next_position(&self, dir: Direction) -> Option<Position> {
+        let (dx, dy) = dir.offset();
+        self.translated(dx, dy, 0)
+    }
+
+    /// Riktning från `self` mot `to` (samma regler som `getDirectionTo` i TFS)
+    pub fn get_direction_to(&self, to: &Position) -> Direction {
+        let dx = Self::get_offset_x(self, to);
+        let east = dx < 0;
+        let dx = dx.abs();
+        let mut dir = if east { Direction::East } else { Direction::West };
+
+        let dy = Self::get_offset_y(self, to);
+        if dy >= 0 {
+            if dy > dx {
+                dir = Direction::North;
+            } else if dy == dx {
+                dir = if east { Direction::NorthEast } else { Direction::NorthWest };
+            }
+        } else {
+            let dy = dy.abs();
+            if dy > dx {
+                dir = Direction::South;
+            } else if dy == dx {
+                dir = if east { Direction::SouthEast } else { Direction::SouthWest };
+            }
+        }
+        dir
+    }
+}
+
+impl std::fmt::Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "( {} / {} / {} )", self.x, self.y, self.z)
+    }
+}
+
+pub const MAP_MAX_LAYERS: u8 = 16;
@@ -0,0 +1,115 @@
+//! Binära strömmar för item-attribut, tile_store och OTBM-noder.
+//! Motsvarar `PropStream`/`PropWriteStream` i TFS (fileloader.h), little endian.
+
+macro_rules! read_num {
+    ($($name:ident => $ty:ty),* $(,)?) => {
+        $(
+            pub fn $name(&mut self) -> Option<$ty> {
+                const N: usize = std::mem::size_of::<$ty>();
+                let bytes = self.data.get(self.pos..self.pos + N)?;
+                self.pos += N;
+                Some(<$ty>::from_le_bytes(bytes.try_into().ok()?))
+            }
+        )*
+    };
+}
+
+macro_rules! write_num {
+    ($($name:ident => $ty:ty),* $(,)?) => {
+        $(
+            pub fn $name(&mut self, value: $ty) {
+                self.buffer.extend_from_slice(&value.to_le_bytes());
+            }
+        )*
+    };
+}
+
+pub struct PropStream<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> PropStream<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    /// Antal bytes kvar att läsa
+    pub fn size(&self) -> usize {
+        self.data.len() - self.pos
+    }
+
+    read_num! {
+        read_u8 => u8,
+        read_u16 => u16,
+        read_u32 => u32,
+        read_u64 => u64,
+        read_i8 => i8,
+        read_i32 => i32,
+        read_i64 => i64,
+        read_f32 => f32,
+        read_f64 => f64,
+    }
+
+    /// Sträng med u16-längd först
+    pub fn read_string(&mut self) -> Option<String> {
+        let len = self.read_u16()? as usize;
+        let bytes = self.read_bytes(len)?;
+        Some(String::from_utf8_lossy(bytes).into_owned())
+    }
+
+    pub fn read_bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self.data.get(self.pos..self.pos + len)?;
+        self.pos += len;
+        Some(bytes)
+    }
+
+    pub fn skip(&mut self, len: usize) -> bool {
+        self.read_bytes(len).is_some()
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct PropWriteStream {
+    buffer: Vec<u8>,
+}
+
+impl PropWriteStream {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get_stream(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+    }
+
+    pub fn into_inner(self) -> Vec<u8> {
+        self.buffer
+    }
+
+    write_num! {
+        write_u8 => u8,
+        write_u16 => u16,
+        write_u32 => u32,
+        write_u64 => u64,
+        write_i8 => i8,
+        write_i32 => i32,
+        write_i64 => i64,
+        write_f32 => f32,
+        write_f64 => f64,
+    }
+
+    pub fn write_string(&mut self, value: &str) {
+        let bytes = &value.as_bytes()[..value.len().min(u16::MAX as usize)];
+        self.write_u16(bytes.len() as u16);
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+}
@@ -0,0 +1,47 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rand::Rng;
+
+/// Millisekunder sedan epoch, motsvarar `OTSYS_TIME()` i TFS
+pub fn otsys_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Sekunder sedan epoch, motsvarar `time(nullptr)`
+pub fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Slumptal i `[min, max]`, båda inklusive. Motsvarar `uniform_random`.
+pub fn uniform_random(min: i64, max: i64) -> i64 {
+    if min >= max {
+        return min;
+    }
+    rand::thread_rng().gen_range(min..=max)
+}
+
+/// Slumptal i `[min, max]` som oftast hamnar nära mitten. Motsvarar
+/// `normal_random`, som drar ur N(0.5, 0.25) och klipper till [0, 1].
+pub fn normal_random(min: i64, max: i64) -> i64 {
+    if min >= max {
+        return min;
+    }
+    let mut rng = rand::thread_rng();
+    // Box-Muller
+    let u1: f64 = rng.gen_range(f64::EPSILON..1.0);
+    let u2: f64 = rng.gen();
+    let z = (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos();
+    let v = (0.5 + z * 0.25).clamp(0.0, 1.0);
+    min + ((max - min) as f64 * v).round() as i64
+}
+
+/// Sant med sannolikheten `ratio`. Motsvarar `boolean_random`.
+pub fn boolean_random(ratio: f64) -> bool {
+    rand::thread_rng().gen_bool(ratio.clamp(0.0, 1.0))
+}
@@ -1,5 +1,5 @@
 use anyhow::{anyhow, Result};
-use mysql_async::{prelude::Queryable, Pool, Row};
+use mysql_async::{prelude::Queryable, Pool, Row, Transaction, TxOpts, Value};
 use once_cell::sync::OnceCell;
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -89,12 +89,6 @@ impl Database {
         }
     }
 
-    /// Sista auto_increment ID (best effort).
-    pub async fn last_insert_id(&self) -> Result<u64> {
-        let mut conn = self.pool.get_conn().await?;
-        Ok(conn.last_insert_id().unwrap_or(0))
-    }
-
     /// Server-version (TFS skriver ut MySQL-version efter connect).
     pub async fn server_version(&self) -> String {
         let mut conn = match self.pool.get_conn().await {
@@ -132,10 +126,16 @@ impl Database {
         out
     }
 
-    /// Motsvarighet till escapeBlob.
+    /// Motsvarighet till escapeBlob. Binärdata skrivs som hex-literal så
+    /// att bytes som inte är giltig UTF-8 överlever.
     pub fn escape_blob(&self, bytes: &[u8]) -> String {
-        let s = String::from_utf8_lossy(bytes);
-        self.escape_string(&s)
+        let mut out = String::with_capacity(bytes.len() * 2 + 3);
+        out.push_str("X'");
+        for b in bytes {
+            out.push_str(&format!("{b:02X}"));
+        }
+        out.push('\'');
+        out
     }
 }
 
@@ -167,6 +167,8 @@ impl DbResult {
         self.cursor < self.rows.len()
     }
 
+    /// Samma namn som `DBResult::next` i TFS
+    #[allow(clippy::should_implement_trait)]
     pub fn next(&mut self) -> bool {
         if self.cursor + 1 < self.rows.len() {
             self.cursor += 1;
@@ -184,19 +186,21 @@ impl DbResult {
                 return String::new();
             }
         };
-        match self.rows.get(self.cursor).and_then(|row| row.get::<String, _>(idx)) {
-            Some(s) => s,
-            None => String::new(),
-        }
+        // NULL och värden som inte går att tolka som text ger tom sträng
+        self.rows
+            .get(self.cursor)
+            .and_then(|row| row.get_opt::<Option<String>, _>(idx))
+            .and_then(|value| value.ok())
+            .flatten()
+            .unwrap_or_default()
     }
 
     pub fn get_stream(&self, col: &str) -> Option<Vec<u8>> {
         let idx = *self.columns.get(col)?;
-        self.rows
-            .get(self.cursor)
-            .and_then(|row| row.as_ref(idx))
-            .map(|val| val.as_bytes().map(|b| b.to_vec()))
-            .flatten()
+        match self.rows.get(self.cursor)?.as_ref(idx)? {
+            Value::Bytes(bytes) => Some(bytes.clone()),
+            _ => None,
+        }
     }
 
     pub fn get_number<T: std::str::FromStr + Default>(&self, col: &str) -> T {
@@ -241,48 +245,71 @@ impl DbInsert {
     }
 
     pub async fn execute(&mut self) -> Result<()> {
+        match self.take_query() {
+            Some(sql) => Database::instance().execute(&sql).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Som `execute`, men i transaktionen
+    pub async fn execute_in(&mut self, transaction: &mut DbTransaction) -> Result<()> {
+        match self.take_query() {
+            Some(sql) => transaction.execute(&sql).await,
+            None => Ok(()),
+        }
+    }
+
+    fn take_query(&mut self) -> Option<String> {
         if self.values.is_empty() {
-            return Ok(());
+            return None;
         }
         let sql = format!("{} {}", self.query, self.values.join(","));
-        Database::instance().execute(&sql).await?;
         self.values.clear();
         self.total_len = self.query.len();
-        Ok(())
+        Some(sql)
     }
 }
 
 /// =======================
-/// DbTransaction (RAII)
+/// DbTransaction
 /// =======================
+/// Alla frågor i transaktionen går genom den, på samma anslutning ur
+/// poolen. Släpps den utan `commit` rullas den tillbaka innan anslutningen
+/// används igen.
 pub struct DbTransaction {
-    active: bool,
+    transaction: Transaction<'static>,
 }
 
 impl DbTransaction {
     pub async fn begin() -> Result<Self> {
-        Database::instance().execute("BEGIN").await?;
-        Ok(Self { active: true })
+        let transaction = Database::instance().pool.start_transaction(TxOpts::default()).await?;
+        Ok(Self { transaction })
     }
 
-    pub async fn commit(mut self) -> Result<()> {
-        Database::instance().execute("COMMIT").await?;
-        self.active = false;
+    /// Kör kommando utan result-set i transaktionen
+    pub async fn execute(&mut self, query: &str) -> Result<()> {
+        self.transaction.query_drop(query).await?;
         Ok(())
     }
 
-    pub async fn rollback(mut self) -> Result<()> {
-        Database::instance().execute("ROLLBACK").await?;
-        self.active = false;
+    /// Kör SELECT i transaktionen, None om tomt
+    pub async fn store_query(&mut self, query: &str) -> Result<Option<DbResult>> {
+        let rows: Vec<Row> = self.transaction.query(query).await?;
+        Ok((!rows.is_empty()).then(|| DbResult::new(rows)))
+    }
+
+    /// Sista auto_increment ID från transaktionens anslutning
+    pub fn last_insert_id(&self) -> u64 {
+        self.transaction.last_insert_id().unwrap_or(0)
+    }
+
+    pub async fn commit(self) -> Result<()> {
+        self.transaction.commit().await?;
         Ok(())
     }
-}
 
-impl Drop for DbTransaction {
-    fn drop(&mut self) {
-        if self.active {
-            // best effort rollback
-            let _ = futures::executor::block_on(Database::instance().execute("ROLLBACK"));
-        }
+    pub async fn rollback(self) -> Result<()> {
+        self.transaction.rollback().await?;
+        Ok(())
     }
 }
@@ -147,7 +147,7 @@ async fn main_loader(
 
 
     // 5. Load game assets
-    //rules::vocation::load("data/vocations.xml")?;
+    //let vocations = rules::vocation::Vocations::load_from_xml("data/XML/vocations.xml", config.classic_attack_speed)?;
     //items::loader::load_all("data/items")?;
     //scripting::script_manager::load_scripts("data/scripts")?;
     //entities::monster::load("data/monsters")?;
@@ -0,0 +1,430 @@
+// src/protocols/game.rs
+// Delar av TFS ProtocolGame: NPC-kanalen och butiksfönstret, party, gillen, VIP-listan, chattkanaler och
+// utseendefönstret
+
+use crate::common::{Position, SpeakClass};
+use crate::net::networkmessage::NetworkMessage;
+
+/// En rad i butiksfönstret, det klienten behöver av `ShopInfo` och itemtypen
+pub struct ShopItem<'a> {
+    pub client_id: u16,
+    /// Fluidtyp eller antal, skickas som en byte
+    pub sub_type: u8,
+    pub name: &'a str,
+    pub weight: u32,
+    pub buy_price: u32,
+    pub sell_price: u32,
+}
+
+/// Klientens paket som rör NPC:er, motsvarar fallen i `ProtocolGame::parsePacket`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NpcPacket {
+    LookInShop { id: u16, count: u8 },
+    PlayerPurchase { id: u16, count: u8, amount: u16, ignore_cap: bool, in_backpacks: bool },
+    PlayerSale { id: u16, count: u8, amount: u16, ignore_equipped: bool },
+    CloseShop,
+    CloseNpcChannel,
+}
+
+impl NpcPacket {
+    /// Läser paketet efter opkoden. `None` om opkoden inte är ett NPC-paket.
+    pub fn parse(opcode: u8, msg: &mut NetworkMessage) -> Option<Self> {
+        Some(match opcode {
+            0x79 => NpcPacket::LookInShop { id: msg.get_u16(), count: msg.get_byte() },
+            0x7A => NpcPacket::PlayerPurchase {
+                id: msg.get_u16(),
+                count: msg.get_byte(),
+                amount: msg.get_u16(),
+                ignore_cap: msg.get_byte() != 0,
+                in_backpacks: msg.get_byte() != 0,
+            },
+            0x7B => NpcPacket::PlayerSale {
+                id: msg.get_u16(),
+                count: msg.get_byte(),
+                amount: msg.get_u16(),
+                ignore_equipped: msg.get_byte() != 0,
+            },
+            0x7C => NpcPacket::CloseShop,
+            0x9E => NpcPacket::CloseNpcChannel,
+            _ => return None,
+        })
+    }
+}
+
+/// Klientens party-paket, motsvarar parseInviteToParty och grannarna
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PartyPacket {
+    Invite(u32),
+    Join(u32),
+    RevokeInvitation(u32),
+    PassLeadership(u32),
+    Leave,
+    EnableSharedExperience(bool),
+}
+
+impl PartyPacket {
+    pub fn parse(opcode: u8, msg: &mut NetworkMessage) -> Option<Self> {
+        Some(match opcode {
+            0xA3 => PartyPacket::Invite(msg.get_u32()),
+            0xA4 => PartyPacket::Join(msg.get_u32()),
+            0xA5 => PartyPacket::RevokeInvitation(msg.get_u32()),
+            0xA6 => PartyPacket::PassLeadership(msg.get_u32()),
+            0xA7 => PartyPacket::Leave,
+            0xA8 => PartyPacket::EnableSharedExperience(msg.get_byte() == 1),
+            _ => return None,
+        })
+    }
+}
+
+/// Klientens stridspaket, motsvarar parseFightModes, parseAttack,
+/// parseFollow och parseCancelMove
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CombatPacket {
+    /// `FightMode` 1-3, chase och secure mode
+    FightModes { fight_mode: u8, chase_mode: bool, secure_mode: bool },
+    Attack(u32),
+    Follow(u32),
+    Cancel,
+}
+
+impl CombatPacket {
+    pub fn parse(opcode: u8, msg: &mut NetworkMessage) -> Option<Self> {
+        Some(match opcode {
+            0xA0 => CombatPacket::FightModes {
+                fight_mode: msg.get_byte(),
+                chase_mode: msg.get_byte() != 0,
+                secure_mode: msg.get_byte() != 0,
+            },
+            0xA1 | 0xA2 => {
+                let id = msg.get_u32();
+                // sekvensnumret, som TFS inte använder
+                msg.get_u32();
+                if opcode == 0xA1 { CombatPacket::Attack(id) } else { CombatPacket::Follow(id) }
+            }
+            0xBE => CombatPacket::Cancel,
+            _ => return None,
+        })
+    }
+}
+
+/// Öppna och stäng en chattkanal, t.ex. `CHANNEL_PARTY`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelPacket {
+    Open(u16),
+    Close(u16),
+}
+
+impl ChannelPacket {
+    pub fn parse(opcode: u8, msg: &mut NetworkMessage) -> Option<Self> {
+        Some(match opcode {
+            0x98 => ChannelPacket::Open(msg.get_u16()),
+            0x99 => ChannelPacket::Close(msg.get_u16()),
+            _ => return None,
+        })
+    }
+}
+
+/// Klientens VIP-paket, motsvarar parseAddVip, parseRemoveVip och parseEditVip
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VipPacket {
+    Add(String),
+    Remove(u32),
+    Edit { guid: u32, description: String, icon: u32, notify: bool },
+}
+
+impl VipPacket {
+    pub fn parse(opcode: u8, msg: &mut NetworkMessage) -> Option<Self> {
+        Some(match opcode {
+            0xDC => VipPacket::Add(msg.get_string(None)),
+            0xDD => VipPacket::Remove(msg.get_u32()),
+            0xDE => VipPacket::Edit {
+                guid: msg.get_u32(),
+                description: msg.get_string(None),
+                // VIPICON_LAST
+                icon: msg.get_u32().min(10),
+                notify: msg.get_byte() != 0,
+            },
+            _ => return None,
+        })
+    }
+}
+
+/// Utseendet som klienten skickar och visar, motsvarar `Outfit_t`.
+/// `look_type_ex` är itemets client id när `look_type` är 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct OutfitLook {
+    pub look_type: u16,
+    pub look_type_ex: u16,
+    pub look_head: u8,
+    pub look_body: u8,
+    pub look_legs: u8,
+    pub look_feet: u8,
+    pub look_addons: u8,
+    pub look_mount: u16,
+}
+
+/// Utseendefönstret och hästen, motsvarar parseRequestOutfit, parseSetOutfit
+/// och parseToggleMount
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutfitPacket {
+    Request,
+    Set(OutfitLook),
+    ToggleMount(bool),
+}
+
+impl OutfitPacket {
+    pub fn parse(opcode: u8, msg: &mut NetworkMessage) -> Option<Self> {
+        Some(match opcode {
+            0xD2 => OutfitPacket::Request,
+            0xD3 => OutfitPacket::Set(OutfitLook {
+                look_type: msg.get_u16(),
+                look_type_ex: 0,
+                look_head: msg.get_byte(),
+                look_body: msg.get_byte(),
+                look_legs: msg.get_byte(),
+                look_feet: msg.get_byte(),
+                look_addons: msg.get_byte(),
+                look_mount: msg.get_u16(),
+            }),
+            0xD4 => OutfitPacket::ToggleMount(msg.get_byte() != 0),
+            _ => return None,
+        })
+    }
+}
+
+pub fn add_position(msg: &mut NetworkMessage, pos: &Position) {
+    msg.add::<u16>(pos.x);
+    msg.add::<u16>(pos.y);
+    msg.add_byte(pos.z);
+}
+
+/// Motsvarar `ProtocolGame::sendCreatureSay`. `level` skickas bara för
+/// spelare, NPC:er och monster skickar 0.
+pub fn send_creature_say(
+    msg: &mut NetworkMessage,
+    statement_id: u32,
+    name: &str,
+    level: u16,
+    class: SpeakClass,
+    pos: Option<&Position>,
+    text: &str,
+) {
+    msg.add_byte(0xAA);
+    msg.add::<u32>(statement_id);
+    msg.add_string(name);
+    msg.add::<u16>(level);
+    msg.add_byte(class as u8);
+    if let Some(pos) = pos {
+        add_position(msg, pos);
+    }
+    msg.add_string(text);
+}
+
+/// Motsvarar `ProtocolGame::sendShop`, klienten tar högst 0xFFFF rader
+pub fn send_shop(msg: &mut NetworkMessage, npc_name: &str, items: &[ShopItem<'_>]) {
+    msg.add_byte(0x7A);
+    msg.add_string(npc_name);
+
+    let count = items.len().min(u16::MAX as usize);
+    msg.add::<u16>(count as u16);
+    for item in &items[..count] {
+        msg.add::<u16>(item.client_id);
+        msg.add_byte(item.sub_type);
+        msg.add_string(item.name);
+        msg.add::<u32>(item.weight);
+        msg.add::<u32>(item.buy_price);
+        msg.add::<u32>(item.sell_price);
+    }
+}
+
+/// Motsvarar `ProtocolGame::sendSaleItemList`: spelarens pengar och hur
+/// många av varje säljbart item (client id) spelaren bär på
+pub fn send_sale_item_list(msg: &mut NetworkMessage, money: u64, items: &[(u16, u8)]) {
+    msg.add_byte(0x7B);
+    msg.add::<u64>(money);
+
+    let count = items.len().min(u8::MAX as usize);
+    msg.add_byte(count as u8);
+    for &(client_id, amount) in &items[..count] {
+        msg.add::<u16>(client_id);
+        msg.add_byte(amount);
+    }
+}
+
+pub fn send_close_shop(msg: &mut NetworkMessage) {
+    msg.add_byte(0x7C);
+}
+
+/// Motsvarar `ProtocolGame::sendCreatureSkull`
+pub fn send_creature_skull(msg: &mut NetworkMessage, creature_id: u32, skull: u8) {
+    msg.add_byte(0x90);
+    msg.add::<u32>(creature_id);
+    msg.add_byte(skull);
+}
+
+/// Motsvarar `ProtocolGame::sendCreatureShield`, `shield` är en `PartyShields_t`
+pub fn send_creature_shield(msg: &mut NetworkMessage, creature_id: u32, shield: u8) {
+    msg.add_byte(0x91);
+    msg.add::<u32>(creature_id);
+    msg.add_byte(shield);
+}
+
+/// Motsvarar `ProtocolGame::sendMagicEffect`
+pub fn send_magic_effect(msg: &mut NetworkMessage, pos: &Position, effect: u8) {
+    msg.add_byte(0x83);
+    add_position(msg, pos);
+    msg.add_byte(effect);
+}
+
+/// Motsvarar `ProtocolGame::sendDistanceShoot`
+pub fn send_distance_shoot(msg: &mut NetworkMessage, from: &Position, to: &Position, shoot: u8) {
+    msg.add_byte(0x85);
+    add_position(msg, from);
+    add_position(msg, to);
+    msg.add_byte(shoot);
+}
+
+/// Motsvarar `ProtocolGame::sendIcons`, se `Player::client_icons`
+pub fn send_icons(msg: &mut NetworkMessage, icons: u16) {
+    msg.add_byte(0xA2);
+    msg.add::<u16>(icons);
+}
+
+/// Motsvarar `ProtocolGame::sendCancelTarget`
+pub fn send_cancel_target(msg: &mut NetworkMessage) {
+    msg.add_byte(0xA3);
+    msg.add::<u32>(0x00);
+}
+
+/// Motsvarar `ProtocolGame::sendChannel`, med användarna som har kanalen öppen
+pub fn send_channel(msg: &mut NetworkMessage, channel_id: u16, name: &str, users: &[&str]) {
+    msg.add_byte(0xAC);
+    msg.add::<u16>(channel_id);
+    msg.add_string(name);
+    msg.add::<u16>(users.len() as u16);
+    for user in users {
+        msg.add_string(user);
+    }
+    // inbjudna, bara för privata kanaler
+    msg.add::<u16>(0x00);
+}
+
+/// Motsvarar `ProtocolGame::sendClosePrivate`
+pub fn send_close_private(msg: &mut NetworkMessage, channel_id: u16) {
+    msg.add_byte(0xB3);
+    msg.add::<u16>(channel_id);
+}
+
+/// Motsvarar `ProtocolGame::sendToChannel`. Utan avsändare (`None`) är
+/// det ett meddelande från servern.
+pub fn send_to_channel(
+    msg: &mut NetworkMessage,
+    statement_id: u32,
+    sender: Option<(&str, u16)>,
+    class: SpeakClass,
+    channel_id: u16,
+    text: &str,
+) {
+    msg.add_byte(0xAA);
+    msg.add::<u32>(statement_id);
+    match sender {
+        Some((name, level)) => {
+            msg.add_string(name);
+            msg.add::<u16>(level);
+        }
+        // tomt namn och nivå 0
+        None => msg.add::<u32>(0x00),
+    }
+    msg.add_byte(class as u8);
+    msg.add::<u16>(channel_id);
+    msg.add_string(text);
+}
+
+/// Motsvarar `ProtocolGame::sendChannelMessage`, ett meddelande utan
+/// statement-id och nivå. Gillets dagens meddelande skickas så, med
+/// "Message of the Day" som avsändare och `SpeakClass::ChannelR1` till
+/// `CHANNEL_GUILD` (`Game::sendGuildMotd`).
+pub fn send_channel_message(msg: &mut NetworkMessage, author: &str, text: &str, class: SpeakClass, channel_id: u16) {
+    msg.add_byte(0xAA);
+    msg.add::<u32>(0x00);
+    msg.add_string(author);
+    msg.add::<u16>(0x00);
+    msg.add_byte(class as u8);
+    msg.add::<u16>(channel_id);
+    msg.add_string(text);
+}
+
+/// Motsvarar `ProtocolGame::sendVIP`, en rad i VIP-listan. `status` är en `VipStatus_t`.
+pub fn send_vip(msg: &mut NetworkMessage, guid: u32, name: &str, description: &str, icon: u32, notify: bool, status: u8) {
+    msg.add_byte(0xD2);
+    msg.add::<u32>(guid);
+    msg.add_string(name);
+    msg.add_string(description);
+    msg.add::<u32>(icon.min(10));
+    msg.add_byte(notify as u8);
+    msg.add_byte(status);
+}
+
+/// Motsvarar `ProtocolGame::sendUpdatedVIPStatus`
+pub fn send_updated_vip_status(msg: &mut NetworkMessage, guid: u32, status: u8) {
+    msg.add_byte(0xD3);
+    msg.add::<u32>(guid);
+    msg.add_byte(status);
+}
+
+/// Motsvarar `ProtocolGame::AddOutfit`
+pub fn add_outfit(msg: &mut NetworkMessage, outfit: &OutfitLook) {
+    msg.add::<u16>(outfit.look_type);
+    if outfit.look_type != 0 {
+        msg.add_byte(outfit.look_head);
+        msg.add_byte(outfit.look_body);
+        msg.add_byte(outfit.look_legs);
+        msg.add_byte(outfit.look_feet);
+        msg.add_byte(outfit.look_addons);
+    } else {
+        msg.add::<u16>(outfit.look_type_ex);
+    }
+    msg.add::<u16>(outfit.look_mount);
+}
+
+/// Motsvarar `ProtocolGame::sendCreatureOutfit`
+pub fn send_creature_outfit(msg: &mut NetworkMessage, creature_id: u32, outfit: &OutfitLook) {
+    msg.add_byte(0x8E);
+    msg.add::<u32>(creature_id);
+    add_outfit(msg, outfit);
+}
+
+/// Motsvarar `ProtocolGame::sendChangeSpeed`, klienten räknar med halva farten
+pub fn send_change_speed(msg: &mut NetworkMessage, creature_id: u32, base_speed: u32, speed: u32) {
+    msg.add_byte(0x8F);
+    msg.add::<u32>(creature_id);
+    msg.add::<u16>((base_speed / 2) as u16);
+    msg.add::<u16>((speed / 2) as u16);
+}
+
+/// Motsvarar `ProtocolGame::sendOutfitWindow`. `outfits` är (looktype, namn,
+/// addons) och `mounts` (client id, namn); klienten tar högst 255 av varje.
+pub fn send_outfit_window(
+    msg: &mut NetworkMessage,
+    current: &OutfitLook,
+    outfits: &[(u16, &str, u8)],
+    mounts: &[(u16, &str)],
+) {
+    msg.add_byte(0xC8);
+    add_outfit(msg, current);
+
+    let count = outfits.len().min(u8::MAX as usize);
+    msg.add_byte(count as u8);
+    for &(look_type, name, addons) in &outfits[..count] {
+        msg.add::<u16>(look_type);
+        msg.add_string(name);
+        msg.add_byte(addons);
+    }
+
+    let count = mounts.len().min(u8::MAX as usize);
+    msg.add_byte(count as u8);
+    for &(client_id, name) in &mounts[..count] {
+        msg.add::<u16>(client_id);
+        msg.add_string(name);
+    }
+}